@@ -0,0 +1,83 @@
+//! Contains function for adopting profiles on a (new) machine.
+
+use config::{
+    general_config::GeneralConfig,
+    profile_config::{AdoptionReport, AuditActor, ProfileConfig},
+};
+use log::{error, warn};
+
+use crate::scheduler;
+
+/// Adopts the given [ProfileConfig], e.g., after its config file was copied to a new machine.
+///
+/// Recomputes `next_backup` if it is stale, registers the schedule of the profile and stores it.
+///
+/// # Returns
+/// [AdoptionReport] describing what happened.
+pub fn adopt(profile_config: &mut ProfileConfig, general_config: &GeneralConfig) -> AdoptionReport {
+    let now = chrono::Local::now().naive_local();
+    let mut report = profile_config.adopt(now);
+
+    if !report.target_available {
+        warn!("Target dir {:?} of {:?} isn't available", report.target_dir, report.name);
+    }
+
+    match scheduler::schedule_backup(profile_config.get_uuid().clone(), profile_config.next_backup) {
+        Ok(_) => report.schedule_registered = true,
+        Err(e) => error!("Couldn't schedule next backup: {:?}", e),
+    }
+
+    if let Err(e) = profile_config.store_audited(&general_config.profile_configs, AuditActor::Cli) {
+        error!("Couldn't store adopted ProfileConfig: {:?}", e);
+    }
+
+    report
+}
+
+/// Formats the given [AdoptionReport] as a single line.
+pub fn format_report(report: &AdoptionReport) -> String {
+    format!(
+        "{} ({}): next backup {}{}, target dir {:?} {}, schedule {}",
+        report.name,
+        report.uuid,
+        report.next_backup,
+        if report.rescheduled { " (rescheduled)" } else { "" },
+        report.target_dir,
+        if report.target_available { "available" } else { "NOT available" },
+        if report.schedule_registered { "registered" } else { "NOT registered" }
+    )
+}
+
+#[cfg(test)]
+mod adopt_tests {
+    use std::path::PathBuf;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn format_report_tests() {
+        let mut report = AdoptionReport {
+            uuid: Uuid::nil(),
+            name: String::from("Hutzi"),
+            rescheduled: true,
+            next_backup: chrono::NaiveDate::from_ymd_opt(2023, 5, 2)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+            target_dir: PathBuf::from("E:/backup"),
+            target_available: false,
+            schedule_registered: true,
+        };
+
+        assert_eq!(
+            format_report(&report),
+            "Hutzi (00000000-0000-0000-0000-000000000000): next backup 2023-05-02 12:00:00 (rescheduled), target dir \"E:/backup\" NOT available, schedule registered"
+        );
+
+        report.rescheduled = false;
+        report.target_available = true;
+        assert!(format_report(&report).contains("12:00:00, target dir \"E:/backup\" available"));
+    }
+}
@@ -0,0 +1,294 @@
+//! Contains functions for detecting the layout of backup archives and upgrading old archives to the current layout.
+
+use std::{
+    fs::{self, File},
+    io::{Read, Seek},
+    path::{Path, PathBuf},
+};
+
+use config::{
+    archive_manifest::{ArchiveManifest, ARCHIVE_MANIFEST_NAME},
+    profile_config::ProfileConfig,
+};
+use log::{info, warn};
+use uuid::Uuid;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::{
+    backup::{write_list, write_manifest, write_snapshot},
+    common::{list_archives, read_list, SNAPSHOT_FILE_NAME, UNSTABLE_FILE_NAME},
+    tempfiles::{temp_path_for, TempKind},
+};
+
+/// Layouts of backup archives that were written by the different versions of the backupper.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ArchiveFormat {
+    /// Only contains the backed up files
+    Legacy,
+    /// Also contains the [SNAPSHOT_FILE_NAME] entry listing all backed up files
+    Snapshot,
+    /// Names the entries relative to the roots listed in the [ARCHIVE_MANIFEST_NAME] entry instead of using the absolute paths
+    Manifest,
+}
+
+/// Layout of the archives written by this version.
+pub const CURRENT_ARCHIVE_FORMAT: ArchiveFormat = ArchiveFormat::Manifest;
+
+impl ArchiveFormat {
+    /// Detects the layout of the given archive from the entries it contains.
+    pub fn detect<R: Read + Seek>(zip: &ZipArchive<R>) -> ArchiveFormat {
+        if zip.file_names().any(|name| name == ARCHIVE_MANIFEST_NAME) {
+            ArchiveFormat::Manifest
+        } else if zip.file_names().any(|name| name == SNAPSHOT_FILE_NAME) {
+            ArchiveFormat::Snapshot
+        } else {
+            ArchiveFormat::Legacy
+        }
+    }
+}
+
+/// Opens the archive at the given path.
+fn open_archive(path: &PathBuf) -> Result<ZipArchive<File>, String> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("Error opening file {:?}: {:?}", path, err)),
+    };
+    match ZipArchive::new(file) {
+        Ok(zip) => Ok(zip),
+        Err(err) => Err(format!("Couldn't read archive {:?} because {:?}", path, err)),
+    }
+}
+
+/// Rewrites the archive at the given `path`, belonging to the profile with the given [Uuid], into the [CURRENT_ARCHIVE_FORMAT].
+/// The entries are copied without recompressing them into a staging file next to the archive, which then replaces the old one.
+///
+/// # Returns
+/// The [ArchiveFormat] the archive had before.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if something went wrong. The original archive is left untouched in that case.
+pub fn upgrade_archive(path: &PathBuf, uuid: &Uuid) -> Result<ArchiveFormat, String> {
+    let mut zip = open_archive(path)?;
+    let format = ArchiveFormat::detect(&zip);
+    if format == CURRENT_ARCHIVE_FORMAT {
+        return Ok(format);
+    }
+
+    let upgraded_path = temp_path_for(path.parent().unwrap_or(Path::new(".")), TempKind::Staging, uuid);
+    let upgraded = match File::create(&upgraded_path) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("Error creating file {:?}: {:?}", upgraded_path, err)),
+    };
+    let mut upgraded = ZipWriter::new(upgraded);
+
+    let res = copy_with_manifest(&mut zip, &mut upgraded);
+    // both files have to be closed before the archive can be replaced
+    drop(upgraded);
+    drop(zip);
+
+    if let Err(msg) = res {
+        if let Err(err) = fs::remove_file(&upgraded_path) {
+            warn!("Couldn't remove {:?} because {:?}", upgraded_path, err);
+        }
+        return Err(msg);
+    }
+
+    if let Err(err) = fs::rename(&upgraded_path, path) {
+        return Err(format!("Couldn't replace {:?} because {:?}", path, err));
+    }
+    Ok(format)
+}
+
+/// Copies all entries of `zip` to `upgraded` without recompressing them, names them relative to their roots and adds the [ARCHIVE_MANIFEST_NAME] entry.
+/// The [SNAPSHOT_FILE_NAME] and [UNSTABLE_FILE_NAME] entries are rewritten with the new names. Archives without a snapshot get one listing all files.
+fn copy_with_manifest(zip: &mut ZipArchive<File>, upgraded: &mut ZipWriter<File>) -> Result<(), String> {
+    let snapshot = read_list(zip, SNAPSHOT_FILE_NAME);
+    let unstable = read_list(zip, UNSTABLE_FILE_NAME);
+    let mut manifest = ArchiveManifest::default();
+    let mut files = vec![];
+    for i in 0..zip.len() {
+        let entry = match zip.by_index_raw(i) {
+            Ok(entry) => entry,
+            Err(err) => return Err(format!("Error reading entry: {:?}", err)),
+        };
+        if entry.name() == SNAPSHOT_FILE_NAME || entry.name() == UNSTABLE_FILE_NAME {
+            continue;
+        }
+        let mut name = manifest.entry_name(Path::new(entry.name()));
+        if entry.is_dir() {
+            name.push('/');
+        } else {
+            files.push(name.clone());
+        }
+        if let Err(err) = upgraded.raw_copy_file_rename(entry, name) {
+            return Err(format!("Couldn't copy entry because {:?}", err));
+        }
+    }
+
+    let mut rename = |names: std::collections::HashSet<String>| -> Vec<String> {
+        names.iter().map(|name| manifest.entry_name(Path::new(name))).collect()
+    };
+    let snapshot = snapshot.map_or(files, &mut rename);
+    if let Some(unstable) = unstable {
+        write_list(upgraded, UNSTABLE_FILE_NAME, rename(unstable))?;
+    }
+    write_manifest(upgraded, &manifest)?;
+    write_snapshot(upgraded, snapshot)?;
+    match upgraded.finish() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Couldn't finish archive because of {:?}", err)),
+    }
+}
+
+/// Upgrades all archives of the given [ProfileConfig] in its target dir to the [CURRENT_ARCHIVE_FORMAT].
+/// Archives in the `previous_target_dirs` are left untouched.
+///
+/// # Parameters
+/// - `dry_run`: Only detects the format of each archive without rewriting it.
+///
+/// # Returns
+/// The path and the detected [ArchiveFormat] of each archive, or an [Err] describing why it couldn't be inspected or upgraded.
+pub fn upgrade_archives(
+    profile_config: &ProfileConfig,
+    dry_run: bool,
+) -> Result<Vec<(PathBuf, Result<ArchiveFormat, String>)>, String> {
+    let archives = list_archives(profile_config)?;
+
+    Ok(archives
+        .into_iter()
+        .map(|(_, path)| path)
+        .filter(|path| path.parent() == Some(profile_config.target_dir.as_path()))
+        .map(|path| {
+            let res = if dry_run {
+                open_archive(&path).map(|zip| ArchiveFormat::detect(&zip))
+            } else {
+                upgrade_archive(&path, profile_config.get_uuid())
+            };
+            info!("Archive {:?}: {:?}", path, res);
+            (path, res)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod archive_format_tests {
+    use std::io::Write;
+
+    use zip::write::FileOptions;
+
+    use super::*;
+
+    /// Writes an archive like the backupper did before snapshots were added.
+    fn write_legacy_archive(path: &PathBuf) {
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        for (name, content) in [("C:/docs/b.txt", "bbb"), ("C:/docs/a.txt", "a")] {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn read_entry(zip: &mut ZipArchive<File>, name: &str) -> String {
+        let mut content = String::new();
+        zip.by_name(name).unwrap().read_to_string(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    fn detect_formats() {
+        let path = PathBuf::from("./test_dir/detect_formats_tmp.zip");
+        write_legacy_archive(&path);
+        let legacy = ArchiveFormat::detect(&open_archive(&path).unwrap());
+
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        write_snapshot(&mut zip, vec![]).unwrap();
+        zip.finish().unwrap();
+        drop(zip);
+        let snapshot = ArchiveFormat::detect(&open_archive(&path).unwrap());
+
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        write_manifest(&mut zip, &ArchiveManifest::default()).unwrap();
+        write_snapshot(&mut zip, vec![]).unwrap();
+        zip.finish().unwrap();
+        drop(zip);
+        let manifest = ArchiveFormat::detect(&open_archive(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(legacy, ArchiveFormat::Legacy);
+        assert_eq!(snapshot, ArchiveFormat::Snapshot);
+        assert_eq!(manifest, ArchiveFormat::Manifest);
+    }
+
+    #[test]
+    fn upgrade_legacy_archive() {
+        let path = PathBuf::from("./test_dir/upgrade_legacy_tmp.zip");
+        write_legacy_archive(&path);
+
+        let first = upgrade_archive(&path, &Uuid::nil());
+        let second = upgrade_archive(&path, &Uuid::nil());
+        let mut zip = open_archive(&path).unwrap();
+        let format = ArchiveFormat::detect(&zip);
+        let a = read_entry(&mut zip, "C/docs/a.txt");
+        let snapshot = read_entry(&mut zip, SNAPSHOT_FILE_NAME);
+        let manifest: ArchiveManifest = serde_json::from_str(&read_entry(&mut zip, ARCHIVE_MANIFEST_NAME)).unwrap();
+        let len = zip.len();
+        drop(zip);
+        let leftover: Vec<PathBuf> = fs::read_dir("./test_dir")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.to_str().unwrap().contains(".staging."))
+            .collect();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(first, Ok(ArchiveFormat::Legacy));
+        assert_eq!(second, Ok(ArchiveFormat::Manifest));
+        assert_eq!(format, ArchiveFormat::Manifest);
+        assert_eq!(len, 4);
+        assert_eq!(a, "a");
+        assert_eq!(snapshot, "C/docs/a.txt\nC/docs/b.txt\n");
+        assert_eq!(manifest.original_path("C/docs/a.txt"), Some(String::from("C:/docs/a.txt")));
+        assert_eq!(leftover, Vec::<PathBuf>::new());
+    }
+
+    #[test]
+    fn upgrade_snapshot_archive() {
+        let path = PathBuf::from("./test_dir/upgrade_snapshot_tmp.zip");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        for (name, content) in [
+            ("C:/docs/a.txt", "a"),
+            ("C:/docs/dropped.log", "dropped"),
+            (SNAPSHOT_FILE_NAME, "C:/docs/a.txt\n"),
+            (UNSTABLE_FILE_NAME, "C:/docs/dropped.log\n"),
+        ] {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        drop(zip);
+
+        let res = upgrade_archive(&path, &Uuid::nil());
+        let mut zip = open_archive(&path).unwrap();
+        let snapshot = read_entry(&mut zip, SNAPSHOT_FILE_NAME);
+        let unstable = read_entry(&mut zip, UNSTABLE_FILE_NAME);
+        let len = zip.len();
+        drop(zip);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(res, Ok(ArchiveFormat::Snapshot));
+        assert_eq!(len, 5);
+        assert_eq!(snapshot, "C/docs/a.txt\n");
+        assert_eq!(unstable, "C/docs/dropped.log\n");
+    }
+
+    #[test]
+    fn upgrade_broken_archive() {
+        let path = PathBuf::from("./test_dir/upgrade_broken_tmp.zip");
+        fs::write(&path, "no zip").unwrap();
+        let res = upgrade_archive(&path, &Uuid::nil());
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(res.is_err());
+        assert_eq!(content, "no zip");
+    }
+}
@@ -0,0 +1,69 @@
+//! Contains functions for displaying the audit log of a [ProfileConfig].
+
+use config::{
+    general_config::GeneralConfig,
+    profile_config::{read_audit_log, AuditActor, AuditEntry, ProfileConfig},
+};
+
+/// Formats the given [AuditEntry] with one line for the entry itself and one indented line per changed field.
+pub fn format_entry(entry: &AuditEntry) -> String {
+    let actor = match &entry.actor {
+        AuditActor::Cli => String::from("cli"),
+        AuditActor::Server { client_ip: Some(ip) } => format!("server ({})", ip),
+        AuditActor::Server { client_ip: None } => String::from("server"),
+        AuditActor::Backupper => String::from("backupper"),
+    };
+    let mut lines = vec![format!("{} {:?} by {}", entry.timestamp, entry.action, actor)];
+    lines.extend(
+        entry
+            .changes
+            .iter()
+            .map(|change| format!("    {}: {} -> {}", change.field, change.old, change.new)),
+    );
+    lines.join("\n")
+}
+
+/// Reads the audit log of the given [ProfileConfig] and formats it from oldest to newest entry.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the log couldn't be read.
+pub fn format_audit_log(
+    profile_config: &ProfileConfig,
+    general_config: &GeneralConfig,
+) -> Result<String, String> {
+    match read_audit_log(&general_config.profile_configs, profile_config.get_uuid()) {
+        Ok(entries) if entries.is_empty() => Ok(String::from("No audit log entries")),
+        Ok(entries) => Ok(entries.iter().map(format_entry).collect::<Vec<String>>().join("\n")),
+        Err(err) => Err(format!("Couldn't read audit log because {:?}", err)),
+    }
+}
+
+#[cfg(test)]
+mod audit_tests {
+    use chrono::NaiveDate;
+    use config::profile_config::{AuditAction, FieldChange};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn format_changes() {
+        let entry = AuditEntry {
+            timestamp: NaiveDate::from_ymd_opt(2023, 4, 1).unwrap().and_hms_opt(10, 30, 0).unwrap(),
+            actor: AuditActor::Server {
+                client_ip: Some(String::from("127.0.0.1")),
+            },
+            action: AuditAction::Update,
+            changes: vec![FieldChange {
+                field: String::from("dirs_to_include"),
+                old: json!(["C:/docs", "C:/pics"]),
+                new: json!(["C:/docs"]),
+            }],
+        };
+
+        assert_eq!(
+            format_entry(&entry),
+            "2023-04-01 10:30:00 Update by server (127.0.0.1)\n    dirs_to_include: [\"C:/docs\",\"C:/pics\"] -> [\"C:/docs\"]"
+        );
+    }
+}
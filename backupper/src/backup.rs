@@ -1,55 +1,165 @@
 //! Contains functions for actually creating a backup file.
 
 use std::{
-    fs::{self, File, OpenOptions},
-    io::{Read, Write},
-    path::PathBuf,
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
 };
 
-use chrono::offset;
+use chrono::{offset, DateTime, Datelike, Local, NaiveDateTime, Timelike};
 use config::{
-    general_config::GeneralConfig, interval::DateTimeMatch, profile_config::ProfileConfig,
+    archive_manifest::{ArchiveManifest, ARCHIVE_MANIFEST_NAME},
+    backup_catalog::{append_entries, catalog_file, CatalogEntry},
+    backup_inventory::{clock_skew, last_activity},
+    backup_report::{is_cancelled_archive, BackupReport, SkippedEntry},
+    general_config::GeneralConfig,
+    interval::DateTimeMatch,
+    profile_config::{
+        AuditActor, BackupMode, CompressionMethod, CompressionSettings, LockedFilePolicy, ProfileConfig, SymlinkPolicy,
+        UnstableFilePolicy, CRATE_VERSION,
+    },
 };
 use log::{error, warn, info, debug};
-use zip::{write::FileOptions, ZipWriter};
+use sha2::{Digest, Sha256};
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
 
 use crate::{
+    breakdown::{format_breakdown, size_breakdown},
+    cancel::{Cancellation, CANCELLED},
     cli_args::Args,
     dialog::{retry_dialog, DialogResult, RETRY},
-    scheduler::schedule_backup, common::is_target_dir_available,
+    encryption::{encrypt_file, readable_archive, verify_encrypted},
+    hooks::run_hooks,
+    metrics::{Metrics, PAUSED_PHASE},
+    progress::ProgressSink,
+    prune::prune,
+    quota::check_quota,
+    report::{archive_file, archive_totals, write_report},
+    summary::{format_skipped, BackupStats, Outcome, ProfileOutcome, SkippedFile},
+    scheduler::schedule_backup, common::{
+        archive_creation_date, archive_parts, collision_key, is_target_dir_available, list_archives, long_path, original_path, part_file_name, read_list,
+        read_manifest, ARCHIVE_DATE_FORMAT, INCREMENTAL_SUFFIX, SNAPSHOT_FILE_NAME, UNSTABLE_FILE_NAME,
+    },
+    volumes::Volumes,
 };
 
 /// Handles the provided [ProfileConfig].
 /// Checks when the next update is due and either schedules another call to this executable or performs the update.
 /// Might also open an alert window, if necessary.
+/// Disabled profiles (see `enabled`) are never backed up, but their `next_backup` is still updated.
 ///
 /// Also stores the updated version of profile config.
+///
+/// `own_paths` are the paths of the program's own files, which are skipped unless the profile sets `include_self` (see [crate::config::own_paths]).
+/// The progress of the backup is reported to `progress`. If the backup is cancelled through `cancellation`, its unfinished archive is removed
+/// and the `next_backup` isn't updated, so the next run tries again. The same goes for stopped backups, but their partial archive is kept.
+///
+/// # Returns
+/// The [ProfileOutcome] of handling the profile. If no backup was performed, the outcome is [Outcome::Skipped].
+/// If the profile config was written by a newer version, it isn't stored and the outcome is a warning. The backup is performed anyway.
 pub fn handle_profile(
     profile_config: &mut ProfileConfig,
     general_config: &GeneralConfig,
     args: &Args,
-) {
-    let (update_next_backup, do_perform_backup) = is_scheduled(profile_config, args.force);
+    own_paths: &[PathBuf],
+    progress: &mut dyn ProgressSink,
+    cancellation: &Cancellation,
+) -> ProfileOutcome {
+    let mut outcome = Outcome::Skipped;
+    let mut stats = BackupStats::default();
+    // the stored profile keeps its portable paths, only the backup works with the resolved ones
+    let mut resolved = profile_config.resolve_paths();
+
+    if let Some(skew) = clock_skew(resolved.as_ref().unwrap_or(profile_config), offset::Local::now().naive_local()) {
+        warn!(
+            "The system clock appears to be wrong! It is {} hours behind the last backup of {:?}.",
+            skew.num_hours(),
+            profile_config.name
+        );
+    }
+
+    if profile_config.refresh_upcoming() {
+        info!("Repaired outdated upcoming runs of {:?}", profile_config.name);
+    }
+
+    let (update_next_backup, mut do_perform_backup) = is_scheduled(profile_config, args.force);
+    if do_perform_backup && !profile_config.enabled {
+        info!("Skipping backup of {:?} because it is disabled", profile_config.name);
+        do_perform_backup = false;
+    }
 
     // actually perform backup
     if do_perform_backup {
-        if let Err(msg) = perform_backup(profile_config, args) {
-            error!("{}", msg);
+        if let Err(msgs) = profile_config.validate() {
+            for msg in msgs {
+                warn!("{:?}: {}", profile_config.name, msg);
+            }
+        }
+        match &mut resolved {
+            Ok(resolved) => match perform_backup(resolved, args, own_paths, general_config.show_dialogs, progress, cancellation) {
+                Ok(archived) => {
+                    if let Some(archived) = archived {
+                        outcome = Outcome::Success;
+                        if cancellation.is_stopped() {
+                            outcome.escalate(Outcome::Warning(String::from(
+                                "Stopped early, the archive only contains the files archived until then",
+                            )));
+                        }
+                        if !archived.skipped.is_empty() {
+                            outcome.escalate(Outcome::Warning(format_skipped(&archived.skipped)));
+                        }
+                        update_catalog(general_config, resolved, &archived.catalog);
+                        stats = archived;
+                    }
+                    profile_config.last_backup = resolved.last_backup;
+                    profile_config.last_backup_archive = resolved.last_backup_archive.clone();
+                    if let Err(msg) = prune(resolved) {
+                        // old archives stay until the next backup, which doesn't make this one any worse
+                        warn!("Couldn't prune old archives because {}", msg);
+                    }
+                }
+                Err(msg) => {
+                    error!("{}", msg);
+                    outcome.escalate(Outcome::Failure(msg));
+                }
+            },
+            Err(msg) => {
+                error!("{}", msg);
+                outcome.escalate(Outcome::Failure(msg.clone()));
+            }
         }
     }
 
     // update next_backup if needed
-    if update_next_backup {
-        let next_scheduled =
-            profile_config.get_next_scheduled(Some(offset::Local::now().naive_local()));
-        profile_config.next_backup = next_scheduled;
+    if update_next_backup && cancellation.is_stopped() {
+        info!("Keeping next_backup of {:?}, because the backup was cancelled or stopped", profile_config.name);
+    } else if update_next_backup {
+        let now = offset::Local::now().naive_local();
+        let next_scheduled = profile_config.get_next_scheduled(Some(now));
+        if !profile_config.set_next_backup_guarded(
+            next_scheduled,
+            now,
+            last_activity(resolved.as_ref().unwrap_or(profile_config)),
+        ) {
+            warn!(
+                "Didn't move next_backup back from {} to {} because the system clock appears to be wrong.",
+                profile_config.next_backup, next_scheduled
+            );
+        }
     }
 
-    if let Err(err) = profile_config.store(&general_config.profile_configs) {
+    if let Err(msg) = check_writable(profile_config) {
+        warn!("{}", msg);
+        outcome.escalate(Outcome::Warning(msg));
+    } else if let Err(err) = profile_config.store_audited(&general_config.profile_configs, AuditActor::Backupper) {
         error!(
             "Couldn't store profile_config {:?}.\nGot error: {:?}",
             profile_config, err
         );
+        outcome.escalate(Outcome::Warning(format!("Couldn't store profile config because {:?}", err)));
     }
 
     if let Err(msg) = schedule_backup(
@@ -57,7 +167,62 @@ pub fn handle_profile(
         profile_config.next_backup,
     ) {
         error!("Couldn't set up next backup.\nGot error: {:?}", msg);
+        outcome.escalate(Outcome::Warning(format!("Couldn't set up next backup because {:?}", msg)));
+    }
+
+    ProfileOutcome {
+        name: profile_config.name.clone(),
+        uuid: *profile_config.get_uuid(),
+        outcome,
+        stats,
+    }
+}
+
+/// Checks if the backup may archive another file, i.e., it wasn't stopped or cancelled.
+/// If it was paused through its control channel (see [crate::control]), it idles until it is resumed first.
+/// The entries archived until then are flushed before idling, and the pause is timed as the [PAUSED_PHASE], so it doesn't count as archiving.
+fn may_continue(volumes: &mut Volumes, state: &mut BackupState) -> bool {
+    if state.cancellation.is_paused() && !state.cancellation.is_stopped() {
+        if let Err(err) = volumes.zip.flush() {
+            warn!("Couldn't flush the archive before pausing because of {:?}", err);
+        }
+        info!("Paused the backup, waiting to be resumed");
+        state.metrics.start_phase(PAUSED_PHASE);
+        let paused = state.cancellation.wait_while_paused();
+        state.metrics.start_phase("archive");
+        info!("Continuing the backup after a pause of {:.0}s", paused.as_secs_f64());
+    }
+    !state.cancellation.is_stopped()
+}
+
+/// Appends the files archived by the latest backup of the given [ProfileConfig] to its catalog (see [config::backup_catalog]).
+/// The catalog is only an index, so failing to update it doesn't fail the backup.
+fn update_catalog(general_config: &GeneralConfig, profile_config: &ProfileConfig, entries: &[CatalogEntry]) {
+    let dir = &general_config.profile_configs;
+    let file = catalog_file(dir, profile_config.get_uuid());
+    if !file.exists() && list_archives(profile_config).map_or(false, |archives| archives.len() > 1) {
+        warn!(
+            "Started a new catalog for {:?}, which doesn't list its older archives. Run `backupper catalog rebuild` to add them",
+            profile_config.name
+        );
+    }
+    if let Err(err) = append_entries(dir, profile_config.get_uuid(), entries) {
+        warn!("Couldn't update the catalog {:?} because of {:?}. Run `backupper catalog rebuild` to repair it", file, err);
+    }
+}
+
+/// Checks if this version is allowed to store the given [ProfileConfig] (see [ProfileConfig::is_read_only]).
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the profile was written by a newer version.
+fn check_writable(profile_config: &ProfileConfig) -> Result<(), String> {
+    if profile_config.is_read_only() {
+        return Err(format!(
+            "Version {} is too old to modify {:?}, which was written by version {}. Changes to it aren't stored.",
+            CRATE_VERSION, profile_config.name, profile_config.written_by_version
+        ));
     }
+    Ok(())
 }
 
 /// Checks if a backup actually has to be performed or if only the `next_backup` field of the profived [ProfileConfig] has to be updated, or none of both.
@@ -94,14 +259,49 @@ fn is_scheduled(profile_config: &ProfileConfig, forced: bool) -> (bool, bool) {
 /// Performs actual backup.
 ///
 /// 1. If the target directory for the zip archive is accesible and opens retry dialog boxes until it is accesibly, or the backup is cancelled.
-/// 2. Creates a file for the zip archive.
-/// 3. Recursively goes through directories to include and adds each file, not matched by the excluded files to the archive
-/// 4. Goes through the files to include and adds each file, not matched by the included dirs to the archive
-/// 5. Stores zip an exits
-fn perform_backup(profile_config: &ProfileConfig, args: &Args) -> std::result::Result<(), String> {
+///    Without `show_dialogs`, the backup fails right away if the target directory isn't accesible.
+/// 2. Checks that the backup fits into the `max_target_usage_bytes` of the profile and runs the `pre_backup_commands`. If one of them fails, the backup is aborted.
+///    If the profile sets `skip_if_unchanged` and nothing changed since the newest archive (see [nothing_changed]), no archive is created. Forced backups are never skipped.
+/// 3. Creates a file for the zip archive. Incremental archives get the [INCREMENTAL_SUFFIX] (see [incremental_since]).
+///    If the profile sets a `max_archive_size`, the archive is split into several volumes with the [PART_SUFFIX](config::backup_inventory::PART_SUFFIX) (see [Volumes]).
+/// 4. Scans the files to archive: Recursively goes through directories to include and collects each file, not matched by the excluded files or the `own_paths`.
+///    The target dir and archives of the profile are always skipped, even if they lie in an included dir.
+///    Then goes through the files to include and collects each file, not matched by the `own_paths`, even if it lies in an excluded dir.
+///    Each file is collected once, however its path is spelled (see [plan_backup]).
+///    Links are skipped, followed or collected as links according to the profile's [SymlinkPolicy]. Each dir is only scanned once, so link cycles end.
+///    For incremental archives, files that weren't modified since the newest archive are only listed in the snapshot.
+/// 5. Adds the collected files to the archive, ordered by [ProfileConfig::archive_order] and compressed according to the profile's [CompressionSettings].
+///    Files in use by another process are handled according to the profile's [LockedFilePolicy].
+///    The collected links are added as link entries storing their targets.
+/// 6. Handles the files that changed while they were archived according to the profile's [UnstableFilePolicy] and lists them in the [UNSTABLE_FILE_NAME] entry
+/// 7. Adds the [ARCHIVE_MANIFEST_NAME] entry, which maps the relative entry names back to the original paths, and the [SNAPSHOT_FILE_NAME] entry listing all archived files
+/// 8. Stores zip an exits and, if the profile sets `verify_after_backup`, reads every entry of every volume back (see [verify_archive]).
+///    Corrupted archives are removed and fail the backup.
+/// 9. Records the archive as the last backup of the profile
+/// 10. Runs the `post_backup_commands`. Their failures are only logged.
+///
+/// # Returns
+/// The [BackupStats] of the archive, or [None] if it was skipped because nothing changed.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if no archive could be created.
+/// If the backup is cancelled through `cancellation`, the unfinished archive is removed and the [Err] is [CANCELLED].
+/// If it is stopped, no more files are started and the archive is finished with the files archived until then.
+/// It is marked as `cancelled` in its manifest and report (see [is_cancelled_archive]) and isn't recorded as the last backup.
+fn perform_backup(
+    profile_config: &mut ProfileConfig,
+    args: &Args,
+    own_paths: &[PathBuf],
+    show_dialogs: bool,
+    progress: &mut dyn ProgressSink,
+    cancellation: &Cancellation,
+) -> std::result::Result<Option<BackupStats>, String> {
     // if target dir isn't available, open dialog
     let mut choice = DialogResult(RETRY);
     while !is_target_dir_available(&profile_config.target_dir, true) && choice == DialogResult(RETRY) {
+        if !show_dialogs {
+            return Err(format!("Directory {:?} isn't available", profile_config.target_dir));
+        }
         let msg = format!("Das Verzeichnis {:?} scheint nicht verfügpar zu sein.\nBitte schließe die externe Festplatte an und versuche es erneut.", profile_config.target_dir);
         let title = "Zielfverzeichnis nicht verfügbar.";
         choice = retry_dialog(title, &msg);
@@ -115,144 +315,1041 @@ fn perform_backup(profile_config: &ProfileConfig, args: &Args) -> std::result::R
 
     // now the target dir should be available
 
+    check_quota(profile_config)?;
+
+    // without the passphrase, there's no point in archiving anything
+    let passphrase = match &profile_config.encryption {
+        Some(encryption) => Some(encryption.passphrase()?),
+        None => None,
+    };
+
+    run_hooks(&profile_config.pre_backup_commands, profile_config.hook_timeout())
+        .map_err(|msg| format!("Aborted backup because a pre-backup hook failed: {}", msg))?;
+
+    if profile_config.skip_if_unchanged && !args.force && nothing_changed(profile_config, own_paths) {
+        info!("Nothing to do for {:?}, because nothing changed since its newest archive", profile_config.name);
+        return Ok(None);
+    }
+
     // set up zip archive
-    let filename = profile_config.get_uuid().as_hyphenated().to_string()
+    let started = chrono::offset::Local::now().naive_local();
+    let changed_since = incremental_since(profile_config, args.full);
+    let stem = profile_config.get_uuid().as_hyphenated().to_string()
         + "_"
-        + &chrono::offset::Local::now()
-            .naive_local()
-            .format("%Y-%m-%d_%H-%M")
-            .to_string()
-        + ".zip";
-    let path = profile_config.target_dir.as_path().join(filename);
-    let file = match OpenOptions::new()
-        .write(true)
-        .create_new(true)
-        .open(path.clone())
-    {
-        Ok(file) => file,
-        Err(err) => return Err(format!("Error creating file {:?}: {:?}", path, err)),
+        + &started.format(ARCHIVE_DATE_FORMAT).to_string()
+        + if changed_since.is_some() { INCREMENTAL_SUFFIX } else { "" };
+    let path = profile_config.target_dir.join(format!("{}.zip", stem));
+    let first_part = profile_config.target_dir.join(part_file_name(&stem, 1));
+    if path.exists() || first_part.exists() {
+        return Err(format!("Archive {:?} already exists", path));
+    }
+    // the archive is written to temporary files first, so there are never unfinished archives with a valid name
+    let mut volumes = Volumes::create(
+        &profile_config.target_dir,
+        profile_config.get_uuid(),
+        profile_config.max_archive_size,
+    )?;
+    let mut state = BackupState {
+        own_paths: own_paths.to_vec(),
+        target_dir: excluded_target_dir(profile_config),
+        changed_since,
+        compression: profile_config.compression.clone(),
+        locked_retries: profile_config.locked_file_policy.retries(),
+        metrics: Metrics::new(),
+        cancellation: cancellation.clone(),
+        ..Default::default()
     };
-    let mut zip = ZipWriter::new(file);
 
-    // add all directories
-    for dir in &profile_config.dirs_to_include {
-        if let Err(msg) = add_directory(&mut zip, dir, profile_config, args) {
-            warn!("Couldn't add dir {:?} because {:?}", dir, msg);
+    state.metrics.start_phase("scan");
+    warn_target_dir_included(profile_config, state.target_dir.as_ref());
+    let plan = plan_backup(profile_config, &mut state);
+    for msg in &plan.errors {
+        warn!("{}", msg);
+    }
+    info!(
+        "Planned {} files, {} of them found in the included dirs and {} included explicitly",
+        plan.files.len(),
+        plan.count(Source::Dir),
+        plan.count(Source::File)
+    );
+    let files: Vec<(PathBuf, u64)> = plan.files.into_iter().map(|file| (file.path, file.size)).collect();
+
+    state.metrics.start_phase("archive");
+    let total_bytes = files.iter().map(|(_, size)| size).sum();
+    if profile_config.small_files_first {
+        info!("Archiving {} files with {} bytes, small files first", files.len(), total_bytes);
+    } else {
+        info!("Archiving {} files with {} bytes in the order they were found", files.len(), total_bytes);
+    }
+    progress.on_start(files.len(), total_bytes);
+    for (file, _) in profile_config.archive_order(files) {
+        if !may_continue(&mut volumes, &mut state) {
+            break;
+        }
+        if let Err(msg) = write_to_volumes(&file, &mut volumes, args, &mut state, progress) {
+            warn!("{}", msg);
+            state.skipped.push(SkippedFile { path: file, reason: msg });
         }
     }
 
-    // add all files
-    for file in &profile_config.files_to_include {
-        if let Err(msg) = add_file(&mut zip, file, profile_config, args) {
-            warn!("Couldn't add file {:?} because {:?}", file, msg);
+    if !state.symlinks.is_empty() {
+        info!("Archiving {} links", state.symlinks.len());
+    }
+    for link in std::mem::take(&mut state.symlinks) {
+        if !may_continue(&mut volumes, &mut state) {
+            break;
+        }
+        let written = volumes
+            .make_room(&link, 0, &state.manifest)
+            .and_then(|_| write_symlink(&link, &mut volumes.zip, &mut state));
+        if let Err(msg) = written {
+            warn!("{}", msg);
+            state.skipped.push(SkippedFile { path: link, reason: msg });
+        }
+    }
+    if state.cancellation.is_cancelled() {
+        volumes.remove();
+        return Err(String::from(CANCELLED));
+    }
+
+    // files that were in use often become available once their application is done with them
+    if profile_config.retry_in_use_files && !state.in_use.is_empty() {
+        info!("Retrying {} files that were in use", state.in_use.len());
+        for file in std::mem::take(&mut state.in_use) {
+            if !may_continue(&mut volumes, &mut state) {
+                break;
+            }
+            if let Err(msg) = write_to_volumes(&file, &mut volumes, args, &mut state, progress) {
+                warn!("{}", msg);
+                state.skipped.push(SkippedFile { path: file, reason: msg });
+            }
+        }
+    }
+    if !state.in_use.is_empty() {
+        if profile_config.locked_file_policy == LockedFilePolicy::Fail {
+            let msg = format!("{} files were in use by another process: {:?}", state.in_use.len(), state.in_use);
+            volumes.remove();
+            return Err(msg);
+        }
+        warn!(
+            "Skipped {} files that were in use by another process: {:?}",
+            state.in_use.len(),
+            state.in_use
+        );
+        for file in std::mem::take(&mut state.in_use) {
+            state.skipped.push(SkippedFile {
+                path: file,
+                reason: String::from("in use by another process"),
+            });
         }
     }
 
-    if let Err(err) = zip.finish() {
-        remove_archive(zip, path);
-        return Err(format!("Couldn't finish archive because of {:?}", err));
+    if state.self_excluded > 0 {
+        info!("Skipped {} self-excluded paths", state.self_excluded);
+    }
+    if state.filtered > 0 {
+        info!("Skipped {} files because of their extension or size", state.filtered);
+    }
+    if state.unchanged > 0 {
+        info!("Skipped {} files that weren't modified since the previous backup", state.unchanged);
     }
 
-    info!("Finished archive in {:?}", path);
-    Ok(())
+    // a second attempt usually succeeds once the file isn't written to anymore
+    if profile_config.retry_unstable_files && !state.unstable.is_empty() {
+        info!("Retrying {} files that changed while they were archived", state.unstable.len());
+        for file in std::mem::take(&mut state.unstable) {
+            if !may_continue(&mut volumes, &mut state) {
+                break;
+            }
+            if let Err(msg) = write_to_volumes(&file, &mut volumes, args, &mut state, progress) {
+                warn!("{}", msg);
+            }
+        }
+    }
+    progress.on_done();
+    if state.cancellation.is_cancelled() {
+        volumes.remove();
+        return Err(String::from(CANCELLED));
+    }
+    let stopped = state.cancellation.is_stopped();
+    if stopped {
+        warn!("Stopped {:?} early, finishing the archive with the files archived until then", profile_config.name);
+        state.manifest.cancelled = true;
+    }
+    state.metrics.start_phase("finalize");
+    // the lists are only added to the last volume, so they have to fit into it together
+    let lists_size = state
+        .snapshot
+        .iter()
+        .map(|name| name.len() as u64 + 1)
+        .chain(state.unstable.iter().map(|path| path.as_os_str().len() as u64 + 17))
+        .chain(state.manifest.roots.iter().map(|(label, root)| (label.len() + root.len()) as u64 + 8))
+        .sum();
+    if let Err(msg) = volumes.make_room(Path::new(SNAPSHOT_FILE_NAME), lists_size, &state.manifest) {
+        volumes.remove();
+        return Err(msg);
+    }
+    if let Err(msg) = handle_unstable(&mut volumes.zip, &mut state, profile_config.unstable_files) {
+        volumes.remove();
+        return Err(msg);
+    }
+
+    if let Err(msg) = write_manifest(&mut volumes.zip, &state.manifest) {
+        volumes.remove();
+        return Err(msg);
+    }
+
+    if let Err(msg) = write_snapshot(&mut volumes.zip, state.snapshot) {
+        volumes.remove();
+        return Err(msg);
+    }
+
+    if let Err(msg) = volumes.finish() {
+        volumes.remove();
+        return Err(msg);
+    }
+
+    if profile_config.verify_after_backup {
+        state.metrics.start_phase("verify");
+        if let Some(msg) = volumes.paths().iter().find_map(|part| verify_archive(part).err()) {
+            let msg = format!("Archive {:?} is corrupted: {}", path, msg);
+            volumes.remove();
+            return Err(msg);
+        }
+    }
+
+    let parts = volumes.paths().to_vec();
+    drop(volumes);
+    // encrypted archives can't be read cheaply anymore, so the totals are taken beforehand
+    let totals = archive_totals(&parts);
+    let (files, bytes) = totals.as_ref().map_or((0, 0), |(files, bytes, _)| (*files, *bytes));
+    if let Some(passphrase) = &passphrase {
+        state.metrics.start_phase("encrypt");
+        let encrypted = parts.iter().try_for_each(|part| {
+            encrypt_file(part, profile_config.get_uuid(), passphrase)?;
+            if profile_config.verify_after_backup {
+                verify_encrypted(part, passphrase).map_err(|msg| format!("Encrypted archive {:?} is corrupted: {}", path, msg))?;
+            }
+            Ok::<(), String>(())
+        });
+        if let Err(msg) = encrypted {
+            for part in &parts {
+                let _ = fs::remove_file(part);
+            }
+            return Err(msg);
+        }
+    }
+    let path = if parts.len() == 1 { path } else { first_part };
+    let mut finished: Vec<PathBuf> = vec![];
+    for (i, partial_path) in parts.iter().enumerate() {
+        let part_path = if parts.len() == 1 {
+            path.clone()
+        } else {
+            profile_config.target_dir.join(part_file_name(&stem, i as u32 + 1))
+        };
+        if let Err(err) = fs::rename(partial_path, &part_path) {
+            // an incomplete set of volumes can't be restored
+            for part in finished.iter().chain(parts[i..].iter()) {
+                let _ = fs::remove_file(part);
+            }
+            return Err(format!("Couldn't rename {:?} to {:?} because of {:?}", partial_path, part_path, err));
+        }
+        finished.push(part_path);
+    }
+    // a partial archive isn't a backup of the profile, so the next run tries again
+    if !stopped {
+        profile_config.last_backup = Some(started);
+        profile_config.last_backup_archive = Some(path.clone());
+    }
+
+    // the report is only for auditing, so the backup doesn't fail without it
+    let report = totals.and_then(|(file_count, uncompressed_bytes, compressed_bytes)| {
+        Ok(BackupReport {
+            profile_name: profile_config.name.clone(),
+            profile_uuid: *profile_config.get_uuid(),
+            started,
+            finished: offset::Local::now().naive_local(),
+            file_count,
+            uncompressed_bytes,
+            compressed_bytes,
+            skipped: state
+                .skipped
+                .iter()
+                .map(|file| SkippedEntry {
+                    path: file.path.clone(),
+                    reason: file.reason.clone(),
+                })
+                .collect(),
+            archive_files: finished.iter().map(|part| archive_file(part)).collect::<Result<_, _>>()?,
+            cancelled: stopped,
+        })
+    });
+    if let Err(msg) = report.and_then(|report| write_report(&path, &report)) {
+        warn!("Couldn't write the report of {:?}: {}", path, msg);
+    }
+
+    // the archive is complete, so failing post-backup hooks don't fail the backup
+    if let Err(msg) = run_hooks(&profile_config.post_backup_commands, profile_config.hook_timeout()) {
+        warn!("Post-backup hook failed: {}", msg);
+    }
+
+    state.metrics.finish_phase();
+    state.metrics.bytes_written = finished
+        .iter()
+        .map(|part| fs::metadata(part).map_or(0, |metadata| metadata.len()))
+        .sum();
+
+    if finished.len() > 1 {
+        info!("Finished archive in {} volumes {:?}", finished.len(), finished);
+    } else {
+        info!("Finished archive in {:?}", path);
+    }
+    let metrics = state.metrics.summary();
+    info!("Metrics: {}", metrics);
+    match size_breakdown(profile_config, &path) {
+        Ok(breakdown) => info!("Size breakdown:\n{}", format_breakdown(&breakdown)),
+        Err(msg) => warn!("Couldn't compute size breakdown because {}", msg),
+    }
+    if !state.skipped.is_empty() {
+        let lines: Vec<String> = state
+            .skipped
+            .iter()
+            .map(|file| format!("{:?}: {}", file.path, file.reason))
+            .collect();
+        warn!("Couldn't archive {} files:\n{}", state.skipped.len(), lines.join("\n"));
+    }
+    let catalog = catalog_entries(std::mem::take(&mut state.archived), &path, started, profile_config);
+    Ok(Some(BackupStats {
+        files,
+        bytes,
+        skipped: state.skipped,
+        catalog,
+        metrics,
+    }))
+}
+
+/// Builds the [CatalogEntry]s of the `archived` files of the backup started at `started` into the archive at `path`.
+/// Files that were archived more than once, e.g., because they were retried, are only listed with their last version.
+fn catalog_entries(archived: Vec<ArchivedFile>, path: &Path, started: NaiveDateTime, profile_config: &ProfileConfig) -> Vec<CatalogEntry> {
+    let mut last_index: HashMap<String, usize> = HashMap::new();
+    for (i, file) in archived.iter().enumerate() {
+        last_index.insert(file.path.clone(), i);
+    }
+    archived
+        .into_iter()
+        .enumerate()
+        .filter(|(i, file)| last_index.get(&file.path) == Some(i))
+        .map(|(_, file)| CatalogEntry {
+            path: file.path,
+            size: file.size,
+            modified: file.modified,
+            sha256: file.sha256,
+            archive: path.to_path_buf(),
+            archived: started,
+            profile_uuid: *profile_config.get_uuid(),
+        })
+        .collect()
+}
+
+/// Checks if a new archive of the given [ProfileConfig] would contain the same files as its newest archive.
+/// That's the case if the same files would be archived, none of them was modified after the newest archive was created and their sizes didn't change.
+/// Any doubt, e.g., an unreadable archive or file, counts as a change.
+fn nothing_changed(profile_config: &ProfileConfig, own_paths: &[PathBuf]) -> bool {
+    let (since, newest) = match list_archives(profile_config).ok().and_then(|mut archives| archives.pop()) {
+        Some(newest) => newest,
+        None => return false,
+    };
+    // the lists of split archives are in their last volume
+    let last_part = archive_parts(&newest).pop().unwrap_or(newest);
+    let last_part = match readable_archive(profile_config, &last_part) {
+        Ok(last_part) => last_part,
+        Err(msg) => {
+            debug!("Couldn't read newest archive: {}", msg);
+            return false;
+        }
+    };
+    let mut zip = match File::open(last_part.path()).ok().and_then(|file| ZipArchive::new(file).ok()) {
+        Some(zip) => zip,
+        None => return false,
+    };
+    let manifest = read_manifest(&mut zip);
+    let snapshot = match read_list(&mut zip, SNAPSHOT_FILE_NAME) {
+        Some(snapshot) => snapshot,
+        None => return false,
+    };
+    // entry names of the archived files by their original paths
+    let archived: HashMap<String, String> = snapshot
+        .into_iter()
+        .filter_map(|name| Some((original_path(manifest.as_ref(), &name)?, name)))
+        .collect();
+
+    let mut state = BackupState {
+        own_paths: own_paths.to_vec(),
+        target_dir: excluded_target_dir(profile_config),
+        ..Default::default()
+    };
+    let plan = plan_backup(profile_config, &mut state);
+    if !plan.errors.is_empty() || plan.files.len() + state.symlinks.len() != archived.len() {
+        return false;
+    }
+
+    for link in &state.symlinks {
+        let name = state.manifest.entry_name(link);
+        if state.manifest.original_path(&name).map_or(true, |path| !archived.contains_key(&path)) {
+            return false;
+        }
+    }
+    for PlannedFile { path, size, .. } in &plan.files {
+        let name = state.manifest.entry_name(path);
+        let entry_name = match state.manifest.original_path(&name).and_then(|path| archived.get(&path)) {
+            Some(entry_name) => entry_name,
+            None => return false,
+        };
+        let modified = match fs::metadata(long_path(path)).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => DateTime::<Local>::from(modified).naive_local(),
+            Err(_) => return false,
+        };
+        // archive names only have minute precision, so files modified in the minute of the newest backup count as changed
+        if modified >= since {
+            return false;
+        }
+        // incremental archives only contain the files that changed before them, and other volumes the rest of the files
+        if let Ok(entry) = zip.by_name(entry_name) {
+            if entry.size() != *size {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Determines whether the next archive of the given [ProfileConfig] is an incremental one.
+/// That's the case if the profile uses [BackupMode::Incremental], the backup isn't forced to be `full` and there is a previous archive.
+/// Partial archives of stopped backups don't count (see [is_cancelled_archive]).
+///
+/// # Returns
+/// The creation date of the newest complete archive, if only files modified since then have to be archived. [None] for a full backup.
+fn incremental_since(profile_config: &ProfileConfig, full: bool) -> Option<NaiveDateTime> {
+    if profile_config.backup_mode != BackupMode::Incremental {
+        return None;
+    }
+    if full {
+        info!("Performing a full backup of {:?} because it was requested", profile_config.name);
+        return None;
+    }
+    // the partial archives of stopped backups miss files, so they mustn't be built on
+    let since = list_archives(profile_config).ok().and_then(|archives| {
+        archives
+            .into_iter()
+            .rev()
+            .find(|(_, path)| !is_cancelled_archive(path))
+            .map(|(creation_date, _)| creation_date)
+    });
+    if since.is_none() {
+        info!("Performing a full backup of {:?} because there is no previous archive", profile_config.name);
+    }
+    since
+}
+
+/// Size in bytes above which files are archived as zip64 entries, which aren't limited to 4 GiB.
+/// It's a bit below that limit, so files that grow while they are archived or don't compress well still fit.
+const LARGE_FILE_THRESHOLD: u64 = 0xF000_0000;
+
+/// Time to wait before trying to open a file in use for the first time again
+const LOCKED_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Longest time to wait before trying to open a file in use again
+const MAX_LOCKED_RETRY_DELAY: Duration = Duration::from_secs(8);
+
+/// Collects information about the files while they are added to the archive.
+#[derive(Debug, Default)]
+struct BackupState {
+    /// Names of the archived entries
+    snapshot: Vec<String>,
+    /// Roots of the archived files, which the entry names are relative to
+    manifest: ArchiveManifest,
+    /// Canonical paths of the scanned dirs, so dirs reachable through link cycles or several links are only scanned once
+    scanned_dirs: HashSet<PathBuf>,
+    /// Links that are archived as links (see [SymlinkPolicy::Preserve])
+    symlinks: Vec<PathBuf>,
+    /// Files that couldn't be opened because another process is using them
+    in_use: Vec<PathBuf>,
+    /// Number of times opening a file in use is tried again right away (see [LockedFilePolicy::retries])
+    locked_retries: u32,
+    /// Files that couldn't be archived
+    skipped: Vec<SkippedFile>,
+    /// Paths of the archived files by the [collision_key] of their entry names, so no two paths end up with the same entry name
+    entry_paths: HashMap<String, PathBuf>,
+    /// Paths of the program's own files, which are skipped
+    own_paths: Vec<PathBuf>,
+    /// Number of paths that were skipped because they are in `own_paths`
+    self_excluded: usize,
+    /// Canonical path of the target dir, which is never archived, so archives don't contain themselves
+    target_dir: Option<PathBuf>,
+    /// Number of files that were skipped because of their extension or size
+    filtered: usize,
+    /// Creation date of the previous archive, if only files modified since then are archived
+    changed_since: Option<NaiveDateTime>,
+    /// Number of files that were skipped because they weren't modified since `changed_since`
+    unchanged: usize,
+    /// How the archived files are compressed. [None] means the defaults of the zip crate.
+    compression: Option<CompressionSettings>,
+    /// Files that changed while they were archived
+    unstable: Vec<PathBuf>,
+    /// Original paths of the archived files with what the catalog records about them, in the order they were archived
+    archived: Vec<ArchivedFile>,
+    /// Costs of the backup
+    metrics: Metrics,
+    /// Stops the backup between files and while a file is read
+    cancellation: Cancellation,
+    /// Called after the first chunk of a file was archived, so tests can modify the file meanwhile
+    #[cfg(test)]
+    on_read: Option<fn(&PathBuf)>,
+}
+
+/// Archived file, as recorded in the catalog of the profile (see [CatalogEntry]).
+#[derive(Debug)]
+struct ArchivedFile {
+    path: String,
+    size: u64,
+    modified: Option<NaiveDateTime>,
+    sha256: String,
 }
 
-/// Attempts to remove started zip-archive from filesystem.
-/// You call this after an unrecoverable error occured, to clean up
-#[allow(unused_must_use)]
-fn remove_archive(mut zip: ZipWriter<File>, path: PathBuf) {
-    zip.finish();
-    fs::remove_file(path);
+impl BackupState {
+    /// Checks if the given `path` is one of the program's own files and counts it as self-excluded if so.
+    fn skip_self(&mut self, path: &PathBuf, profile_config: &ProfileConfig) -> bool {
+        let skip = profile_config.is_self_excluded(path, &self.own_paths);
+        if skip {
+            debug!("Skipping own file {:?}", path);
+            self.self_excluded += 1;
+        }
+        skip
+    }
+
+    /// Checks if the file at `path` with the given `size` has to be skipped because of its extension or size and counts it as filtered if so.
+    fn skip_filtered(&mut self, path: &PathBuf, size: u64, profile_config: &ProfileConfig) -> bool {
+        let reason = if profile_config.is_extension_excluded(path) {
+            "of its extension"
+        } else if profile_config.exceeds_max_file_size(size) {
+            "it is larger than the maximum file size"
+        } else {
+            return false;
+        };
+        debug!("Skipping {:?} because {}", path, reason);
+        self.filtered += 1;
+        true
+    }
+
+    /// Checks if the given `path` is a link that isn't followed because of the profile's [SymlinkPolicy].
+    /// Links that are preserved are collected in `symlinks`. Junctions on Windows count as links, too.
+    fn skip_symlink(&mut self, path: &PathBuf, profile_config: &ProfileConfig) -> bool {
+        let is_symlink = fs::symlink_metadata(long_path(path)).map_or(false, |metadata| metadata.file_type().is_symlink());
+        if !is_symlink {
+            return false;
+        }
+        match profile_config.symlink_policy {
+            SymlinkPolicy::Follow => false,
+            SymlinkPolicy::Skip => {
+                debug!("Skipping link {:?}", path);
+                true
+            }
+            SymlinkPolicy::Preserve => {
+                debug!("Preserving link {:?}", path);
+                self.symlinks.push(path.clone());
+                true
+            }
+        }
+    }
+
+    /// Computes the entry name of the file at `path` and makes sure that no other path is archived under the same name.
+    /// The same path may be archived more than once though, e.g., when it is retried.
+    ///
+    /// # Errors
+    /// Returns an [Err] naming the other path if the entry name is already taken.
+    fn claim_entry_name(&mut self, path: &PathBuf) -> Result<String, String> {
+        let name = self.manifest.entry_name(path);
+        match self.entry_paths.get(&collision_key(&name)) {
+            Some(other) if other != path => Err(format!("Entry name {:?} of {:?} is already used by {:?}", name, path, other)),
+            Some(_) => Ok(name),
+            None => {
+                self.entry_paths.insert(collision_key(&name), path.clone());
+                Ok(name)
+            }
+        }
+    }
+
+    /// Checks if the file at `path` was already found before, e.g., because it is reachable through several include roots.
+    /// Files whose entry names collide with the one of another file (see [BackupState::claim_entry_name]) count as found as well
+    /// and are recorded as skipped, so they show up in the summary.
+    fn is_duplicate(&mut self, path: &PathBuf) -> bool {
+        let name = self.manifest.entry_name(path);
+        match self.entry_paths.get(&collision_key(&name)) {
+            None => {
+                self.entry_paths.insert(collision_key(&name), path.clone());
+                false
+            }
+            Some(other) if other == path => {
+                debug!("Skipping {:?} because it was already found", path);
+                true
+            }
+            Some(other) => {
+                let reason = format!("Entry name {:?} is already used by {:?}", name, other);
+                warn!("Skipping {:?}: {}", path, reason);
+                self.skipped.push(SkippedFile {
+                    path: path.clone(),
+                    reason,
+                });
+                true
+            }
+        }
+    }
+
+    /// Removes the files and links that were already found before from `files` and `symlinks` (see [BackupState::is_duplicate]).
+    fn drop_duplicates(&mut self, files: &mut Vec<PlannedFile>) {
+        files.retain(|file| !self.is_duplicate(&file.path));
+        let mut symlinks = std::mem::take(&mut self.symlinks);
+        symlinks.retain(|link| !self.is_duplicate(link));
+        self.symlinks = symlinks;
+    }
+
+    /// Builds the [FileOptions] for archiving the file at `path` with the given `size` in bytes according to the `compression` settings.
+    /// Files larger than [LARGE_FILE_THRESHOLD] get zip64 entries.
+    fn file_options(&self, path: &PathBuf, size: u64) -> FileOptions {
+        let options = FileOptions::default().large_file(size > LARGE_FILE_THRESHOLD);
+        let (method, level) = match &self.compression {
+            Some(compression) => compression.for_path(path),
+            None => return options,
+        };
+        let method = match method {
+            CompressionMethod::Stored => zip::CompressionMethod::Stored,
+            CompressionMethod::Deflated => zip::CompressionMethod::Deflated,
+            CompressionMethod::Bzip2 => zip::CompressionMethod::Bzip2,
+            CompressionMethod::Zstd => zip::CompressionMethod::Zstd,
+        };
+        options.compression_method(method).compression_level(level)
+    }
+
+    /// Checks if the file at `path` wasn't modified since `changed_since` and counts it as unchanged if so.
+    /// Unchanged files are still added to the snapshot, so it lists all files that existed at backup time.
+    fn skip_unchanged(&mut self, path: &PathBuf) -> bool {
+        let since = match self.changed_since {
+            Some(since) => since,
+            None => return false,
+        };
+        let modified = match fs::metadata(long_path(path)).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => DateTime::<Local>::from(modified).naive_local(),
+            // better archive a file too much than to miss one
+            Err(_) => return false,
+        };
+        // archive names only have minute precision, so files modified in the minute of the previous backup are archived again
+        if modified >= since {
+            return false;
+        }
+        debug!("Skipping {:?} because it wasn't modified since {}", path, since);
+        self.unchanged += 1;
+        let name = self.manifest.entry_name(path);
+        self.snapshot.push(name);
+        true
+    }
 }
 
-/// Walks through the given `dir` and adds all files not excluded to the zip-archive.
-fn add_directory(
+/// Lists the unstable files in `state` in the [UNSTABLE_FILE_NAME] entry of the `zip` and applies the [UnstableFilePolicy] to them.
+/// With [UnstableFilePolicy::Drop], they are removed from the snapshot, so they aren't restored. Their entries stay in the archive though.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the entry couldn't be written.
+fn handle_unstable(
     zip: &mut ZipWriter<File>,
+    state: &mut BackupState,
+    policy: UnstableFilePolicy,
+) -> Result<(), String> {
+    if state.unstable.is_empty() {
+        return Ok(());
+    }
+    warn!(
+        "{} files changed while they were archived and may be inconsistent ({:?}): {:?}",
+        state.unstable.len(),
+        policy,
+        state.unstable
+    );
+
+    let names: Vec<String> = state
+        .unstable
+        .iter()
+        .map(|path| state.manifest.entry_name(path))
+        .collect();
+    if policy == UnstableFilePolicy::Drop {
+        state.snapshot.retain(|name| !names.contains(name));
+    }
+    write_list(zip, UNSTABLE_FILE_NAME, names)
+}
+
+/// Reads every entry of the zip archive at the given `path` completely, which makes the zip crate check its CRC.
+///
+/// # Errors
+/// Returns an [Err] describing the first entry that couldn't be read, or why the archive couldn't be opened.
+fn verify_archive(path: &PathBuf) -> Result<(), String> {
+    let file = File::open(path).map_err(|err| format!("Couldn't open archive because of {:?}", err))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|err| format!("Couldn't read archive because of {:?}", err))?;
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|err| format!("Couldn't read entry {} because of {:?}", i, err))?;
+        if let Err(err) = io::copy(&mut entry, &mut io::sink()) {
+            return Err(format!("Couldn't read entry {:?} because of {:?}", entry.name(), err));
+        }
+    }
+    Ok(())
+}
+
+/// Where a planned file was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    /// While going through the `dirs_to_include`
+    Dir,
+    /// In the `files_to_include`
+    File,
+}
+
+/// File that is archived by a backup.
+#[derive(Debug, Clone, PartialEq)]
+struct PlannedFile {
+    path: PathBuf,
+    /// Size of the file in bytes when it was found
+    size: u64,
+    source: Source,
+}
+
+/// Files that are archived by a backup, collected before anything is written (see [plan_backup]).
+#[derive(Debug, Default)]
+struct Plan {
+    /// Files to archive in the order they were found
+    files: Vec<PlannedFile>,
+    /// Locations of the files found so far (see [location]), whether they are archived or skipped, so each file is handled once however its path is spelled
+    found: HashSet<PathBuf>,
+    /// Why some of the included dirs or files couldn't be scanned
+    errors: Vec<String>,
+}
+
+impl Plan {
+    /// Records the file at `location` as found.
+    ///
+    /// # Returns
+    /// `false` if it was already found before.
+    fn find(&mut self, location: PathBuf) -> bool {
+        self.found.insert(location)
+    }
+
+    /// Counts the planned files from the given [Source].
+    fn count(&self, source: Source) -> usize {
+        self.files.iter().filter(|file| file.source == source).count()
+    }
+}
+
+/// Returns the location of the file at `path`, i.e., its canonicalized dir joined with its name. Links themselves aren't resolved.
+/// Falls back to `path` if the dir can't be canonicalized.
+fn location(path: &Path) -> PathBuf {
+    let (dir, name) = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => (dir, name),
+        _ => return path.to_path_buf(),
+    };
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    fs::canonicalize(long_path(dir)).map_or_else(|_| path.to_path_buf(), |dir| dir.join(name))
+}
+
+/// Collects the files to archive: Goes through the `dirs_to_include` first and then through the `files_to_include`.
+/// Each file is planned once, even if it is reachable through several included dirs or is an included file in an included dir.
+/// Included files are archived even if they lie in an excluded dir or the included dir they lie in couldn't be scanned.
+/// Files and links whose entry names collide are skipped (see [BackupState::drop_duplicates]).
+fn plan_backup(profile_config: &ProfileConfig, state: &mut BackupState) -> Plan {
+    let mut plan = Plan::default();
+    for dir in &profile_config.dirs_to_include {
+        if let Err(msg) = scan_directory(dir, profile_config, state, &mut plan) {
+            plan.errors.push(format!("Couldn't add dir {:?} because {:?}", dir, msg));
+        }
+    }
+    for file in &profile_config.files_to_include {
+        if let Err(msg) = scan_file(file, profile_config, state, &mut plan) {
+            plan.errors.push(format!("Couldn't add file {:?} because {:?}", file, msg));
+        }
+    }
+    state.drop_duplicates(&mut plan.files);
+    plan
+}
+
+/// Walks through the given `dir` and adds all files not excluded to the `plan`.
+fn scan_directory(
     dir: &PathBuf,
     profile_config: &ProfileConfig,
-    args: &Args,
+    state: &mut BackupState,
+    plan: &mut Plan,
 ) -> Result<(), String> {
-    if !dir.is_dir() {
+    let long_dir = long_path(dir);
+    if !long_dir.is_dir() {
         return Err(format!("{:?} is not a directory!", dir));
     }
+    let canonical = fs::canonicalize(&long_dir).unwrap_or_else(|_| dir.clone());
+    if state.target_dir.as_ref() == Some(&canonical) {
+        debug!("Skipping {:?} because it is the target dir", dir);
+        return Ok(());
+    }
+    if !state.scanned_dirs.insert(canonical.clone()) {
+        debug!("Skipping {:?} because it was already scanned", dir);
+        return Ok(());
+    }
 
-    let entries = match fs::read_dir(dir) {
+    let entries = match fs::read_dir(&long_dir) {
         Ok(entries) => entries,
         Err(err) => return Err(format!("Error reading dir: {:?}", err)),
     };
     for entry in entries {
+        if state.cancellation.is_stopped() {
+            return Ok(());
+        }
         if entry.is_err() {
             continue;
         }
         let entry = entry.unwrap();
-        let path = entry.path();
+        // the entries are in the extended-length form of the dir, which is only used to access them
+        let path = dir.join(entry.file_name());
         // skip excluded paths
-        if profile_config.is_excluded(&path) {
+        if profile_config.is_excluded(&path)
+            || state.skip_self(&path, profile_config)
+            || state.skip_symlink(&path, profile_config)
+        {
             continue;
         }
 
+        let long = long_dir.join(entry.file_name());
         // go recursively into directories
-        if path.is_dir() {
-            if let Err(msg) = add_directory(zip, &path, profile_config, args) {
+        if long.is_dir() {
+            if let Err(msg) = scan_directory(&path, profile_config, state, plan) {
                 warn!("{}", msg);
             }
         }
 
-        // actually store file
-        if path.is_file() {
-            match write_to_zip(&path, zip, args) {
-                Ok(_) => (),
-                Err(msg) => {
-                    warn!("{}", msg);
-                }
+        if long.is_file() {
+            if skip_archive(&path, profile_config) || !plan.find(canonical.join(entry.file_name())) {
+                continue;
+            }
+            let size = entry.metadata().map_or(0, |metadata| metadata.len());
+            if !state.skip_filtered(&path, size, profile_config) && !state.skip_unchanged(&path) {
+                plan.files.push(PlannedFile {
+                    path,
+                    size,
+                    source: Source::Dir,
+                });
             }
         }
     }
     Ok(())
 }
 
-/// Attempts to add file at the given path to the archive.
-fn add_file(
-    zip: &mut ZipWriter<File>,
+/// Canonicalizes the target dir of the profile, so it can be skipped while scanning (see [BackupState::target_dir]).
+///
+/// # Returns
+/// [None] if the target dir can't be canonicalized, e.g., because it doesn't exist.
+fn excluded_target_dir(profile_config: &ProfileConfig) -> Option<PathBuf> {
+    fs::canonicalize(&profile_config.target_dir).ok()
+}
+
+/// Warns about each included dir the canonical `target_dir` lies in. Its contents are skipped, as if they were excluded.
+fn warn_target_dir_included(profile_config: &ProfileConfig, target_dir: Option<&PathBuf>) {
+    let target_dir = match target_dir {
+        Some(target_dir) => target_dir,
+        None => return,
+    };
+    for dir in &profile_config.dirs_to_include {
+        if fs::canonicalize(dir).map_or(false, |dir| target_dir.starts_with(dir)) {
+            warn!(
+                "Target dir {:?} lies in the included dir {:?}, so it is excluded from the backup",
+                profile_config.target_dir, dir
+            );
+        }
+    }
+}
+
+/// Checks if the file at `path` is an archive of the profile (see [archive_creation_date]), e.g., one left in a former target dir, which is never archived.
+fn skip_archive(path: &PathBuf, profile_config: &ProfileConfig) -> bool {
+    let skip = archive_creation_date(profile_config, path).is_some();
+    if skip {
+        debug!("Skipping archive {:?}", path);
+    }
+    skip
+}
+
+/// Adds the file at the given path to the `plan`, unless it was already found while going through the included dirs.
+/// Included files overwrite the excluded dirs, so it is added even if it lies in one of them.
+fn scan_file(
     file: &PathBuf,
     profile_config: &ProfileConfig,
-    args: &Args,
+    state: &mut BackupState,
+    plan: &mut Plan,
 ) -> Result<(), String> {
-    if !file.is_file() {
+    if !long_path(file).is_file() {
         return Err(format!("{:?} is not a file!", file));
     }
-
-    // included files shall overwrite the excluded files,
-    // but it should not be added again, if it was already coverd by an included dir
-    // --> if it is in an included dir, it might have not been added since is is also in an excluded dir
-    //      --> add that file
-    if profile_config.in_included_dirs(file) && !profile_config.is_excluded(file) {
+    if !plan.find(location(file)) {
         debug!("File {:?} is already covered by included dirs.", file);
         return Ok(());
     }
+    if state.skip_self(file, profile_config)
+        || skip_archive(file, profile_config)
+        || state.skip_symlink(file, profile_config)
+    {
+        return Ok(());
+    }
 
-    write_to_zip(file, zip, args)
+    let size = fs::metadata(long_path(file)).map_or(0, |metadata| metadata.len());
+    if !state.skip_filtered(file, size, profile_config) && !state.skip_unchanged(file) {
+        plan.files.push(PlannedFile {
+            path: file.clone(),
+            size,
+            source: Source::File,
+        });
+    }
+    Ok(())
 }
 
-/// Attempts to write the file at the specified `path` to the `zip`.
-///
-/// # Errors
-/// Returns an [Err] describing the issue if something goes wrong
-fn write_to_zip(path: &PathBuf, zip: &mut ZipWriter<File>, _args: &Args) -> Result<(), String> {
-    let mut file = match File::open(path) {
-        Ok(file) => file,
-        Err(err) => {
-            return Err(format!(
-                "Couldn't open file {:?} because of {:?}",
-                path, err
-            ))
-        }
+/// Opens the file at `path`. If another process is using it, opening it is tried again up to `retries` times (see [retry_delay]).
+fn open_with_retries(path: &PathBuf, retries: u32) -> io::Result<File> {
+    let mut attempt = 0;
+    loop {
+        match File::open(long_path(path)) {
+            Err(err) if is_in_use(&err) && attempt < retries => {
+                let delay = retry_delay(attempt);
+                debug!("{:?} is in use, trying again in {:?}", path, delay);
+                thread::sleep(delay);
+                attempt += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+/// Time to wait before the given `attempt` to open a file in use. Doubles with each attempt, up to [MAX_LOCKED_RETRY_DELAY].
+fn retry_delay(attempt: u32) -> Duration {
+    LOCKED_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_LOCKED_RETRY_DELAY)
+}
+
+/// Checks if opening a file failed because another process is using it (sharing or lock violation on Windows).
+fn is_in_use(err: &io::Error) -> bool {
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+
+    cfg!(target_family = "windows")
+        && matches!(
+            err.raw_os_error(),
+            Some(ERROR_SHARING_VIOLATION) | Some(ERROR_LOCK_VIOLATION)
+        )
+}
+
+/// Returns the size and modification time of the file at `path`, which change when the file is written to.
+fn fingerprint(path: &PathBuf) -> Option<(u64, SystemTime)> {
+    let metadata = fs::metadata(long_path(path)).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+/// Converts the modification time of a file into the local time stored in its archive entry, which has a resolution of 2 seconds.
+///
+/// # Returns
+/// [None] if the time lies outside of the years 1980 to 2107, which zip archives can't represent.
+fn zip_date_time(modified: SystemTime) -> Option<zip::DateTime> {
+    let modified = DateTime::<Local>::from(modified).naive_local();
+    zip::DateTime::from_date_and_time(
+        u16::try_from(modified.year()).ok()?,
+        modified.month() as u8,
+        modified.day() as u8,
+        modified.hour() as u8,
+        modified.minute() as u8,
+        modified.second() as u8,
+    )
+    .ok()
+}
+
+/// Returns the Unix permission bits of a file to store in its archive entry.
+/// On other platforms, only the read-only attribute is represented, as `0o444` or `0o644`.
+fn permission_bits(metadata: &fs::Metadata) -> u32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o777
+    }
+    #[cfg(not(unix))]
+    {
+        if metadata.permissions().readonly() {
+            0o444
+        } else {
+            0o644
+        }
+    }
+}
+
+/// Writes the file at the specified `path` to the current volume of the archive, after starting a new volume if it doesn't fit anymore (see [Volumes::make_room]).
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the file is too large for any volume or couldn't be written (see [write_to_zip]).
+fn write_to_volumes(
+    path: &PathBuf,
+    volumes: &mut Volumes,
+    args: &Args,
+    state: &mut BackupState,
+    progress: &mut dyn ProgressSink,
+) -> Result<(), String> {
+    let size = fs::metadata(long_path(path)).map_or(0, |metadata| metadata.len());
+    volumes.make_room(path, size, &state.manifest)?;
+    write_to_zip(path, &mut volumes.zip, args, state, progress)
+}
+
+/// Attempts to write the file at the specified `path` to the `zip`.
+/// The entry is named relative to the root of the path (see [ArchiveManifest::entry_name]) and its name is added to the snapshot in `state`.
+/// If the file is in use by another process, it is added to the in-use files in `state` instead.
+/// If the file changed while it was read, it is added to the unstable files in `state` as well.
+/// The entry records the modification time and permissions of the file, so they can be restored.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if something goes wrong
+fn write_to_zip(
+    path: &PathBuf,
+    zip: &mut ZipWriter<File>,
+    _args: &Args,
+    state: &mut BackupState,
+    progress: &mut dyn ProgressSink,
+) -> Result<(), String> {
+    let mut file = match open_with_retries(path, state.locked_retries) {
+        Ok(file) => file,
+        Err(err) if is_in_use(&err) => {
+            debug!("{:?} is in use", path);
+            state.in_use.push(path.clone());
+            return Ok(());
+        }
+        Err(err) => {
+            return Err(format!(
+                "Couldn't open file {:?} because of {:?}",
+                path, err
+            ))
+        }
     };
 
     debug!("Store {:?}", path);
-
-    let name = String::from(path.to_str().unwrap_or(""));
-    if let Err(err) = zip.start_file(name, FileOptions::default()) {
+    let before = fingerprint(path);
+
+    let name = state.claim_entry_name(path)?;
+    let size = before.map_or(0, |(size, _)| size);
+    let mut options = state.file_options(path, size);
+    progress.on_file_start(path, size);
+    let mut modified_at = None;
+    if let Ok(metadata) = file.metadata() {
+        modified_at = metadata.modified().ok().map(|modified| DateTime::<Local>::from(modified).naive_local());
+        if let Some(modified) = metadata.modified().ok().and_then(zip_date_time) {
+            options = options.last_modified_time(modified);
+        }
+        options = options.unix_permissions(permission_bits(&metadata));
+    }
+    if let Err(err) = zip.start_file(name.clone(), options) {
         return Err(format!(
             "Couldn't start file {:?} because of {:?}",
             path, err
@@ -261,7 +1358,12 @@ fn write_to_zip(path: &PathBuf, zip: &mut ZipWriter<File>, _args: &Args) -> Resu
 
     const N: usize = 0x2000;
     let mut buf = [0u8; N];
+    let mut total_bytes = 0;
+    let mut hasher = Sha256::new();
     loop {
+        if state.cancellation.is_cancelled() {
+            return Err(format!("{} while archiving {:?}", CANCELLED, path));
+        }
         let read_bytes = match Read::by_ref(&mut file).take(N as u64).read(&mut buf) {
             Ok(n) => n,
             Err(err) => {
@@ -282,9 +1384,95 @@ fn write_to_zip(path: &PathBuf, zip: &mut ZipWriter<File>, _args: &Args) -> Resu
                 path, err
             ));
         }
+        hasher.update(&buf[..read_bytes]);
+
+        #[cfg(test)]
+        if total_bytes == 0 {
+            if let Some(on_read) = state.on_read {
+                on_read(path);
+            }
+        }
+        total_bytes += read_bytes as u64;
+        progress.on_bytes_written(read_bytes as u64);
+    }
+    state.metrics.bytes_read += total_bytes;
+
+    let after = fingerprint(path);
+    if before.is_none() || before != after || before.map(|(size, _)| size) != Some(total_bytes) {
+        debug!("{:?} changed while it was archived", path);
+        state.unstable.push(path.clone());
     }
 
     debug!("Successfully added {:?} to archive.", path);
+    state.archived.push(ArchivedFile {
+        path: state.manifest.original_path(&name).unwrap_or_else(|| path.to_string_lossy().to_string()),
+        size: total_bytes,
+        modified: modified_at,
+        sha256: format!("{:x}", hasher.finalize()),
+    });
+    state.snapshot.push(name);
+    Ok(())
+}
+
+/// Writes the link at `path` to the `zip` as a link entry, which stores the target of the link instead of the content it points to.
+/// The name of the created entry is added to the snapshot in `state`.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if something goes wrong
+fn write_symlink(path: &PathBuf, zip: &mut ZipWriter<File>, state: &mut BackupState) -> Result<(), String> {
+    let target = match fs::read_link(long_path(path)) {
+        Ok(target) => target,
+        Err(err) => return Err(format!("Couldn't read link {:?} because of {:?}", path, err)),
+    };
+    let name = state.claim_entry_name(path)?;
+    if let Err(err) = zip.add_symlink(name.clone(), target.to_string_lossy(), FileOptions::default()) {
+        return Err(format!("Couldn't add link {:?} because of {:?}", path, err));
+    }
+    state.snapshot.push(name);
+    Ok(())
+}
+
+/// Writes the [SNAPSHOT_FILE_NAME] entry to the `zip`.
+/// It lists the names of all entries in `snapshot` sorted and one per line, so the set of files that existed at backup time can be reconstructed from the archive alone.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if something goes wrong
+pub fn write_snapshot(zip: &mut ZipWriter<File>, snapshot: Vec<String>) -> Result<(), String> {
+    write_list(zip, SNAPSHOT_FILE_NAME, snapshot)
+}
+
+/// Writes the [ARCHIVE_MANIFEST_NAME] entry to the `zip`, so the original paths of the entries can be reconstructed.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if something goes wrong
+pub fn write_manifest(zip: &mut ZipWriter<File>, manifest: &ArchiveManifest) -> Result<(), String> {
+    let content = match serde_json::to_string_pretty(manifest) {
+        Ok(content) => content,
+        Err(err) => return Err(format!("Couldn't serialize {} because of {:?}", ARCHIVE_MANIFEST_NAME, err)),
+    };
+    if let Err(err) = zip.start_file(ARCHIVE_MANIFEST_NAME, FileOptions::default()) {
+        return Err(format!("Couldn't start {} because of {:?}", ARCHIVE_MANIFEST_NAME, err));
+    }
+    if let Err(err) = zip.write_all(content.as_bytes()) {
+        return Err(format!("Couldn't write {} because of {:?}", ARCHIVE_MANIFEST_NAME, err));
+    }
+    Ok(())
+}
+
+/// Writes an entry with the given `entry_name` to the `zip` that lists the given `names` sorted and one per line.
+pub fn write_list(zip: &mut ZipWriter<File>, entry_name: &str, mut names: Vec<String>) -> Result<(), String> {
+    names.sort();
+    names.dedup();
+
+    if let Err(err) = zip.start_file(entry_name, FileOptions::default()) {
+        return Err(format!("Couldn't start {} because of {:?}", entry_name, err));
+    }
+    for name in names {
+        if let Err(err) = writeln!(zip, "{}", name) {
+            return Err(format!("Couldn't write {} because of {:?}", entry_name, err));
+        }
+    }
+
     Ok(())
 }
 
@@ -293,6 +1481,7 @@ mod backup_tests {
     use config::interval::Interval;
 
     use super::*;
+    use crate::progress::SilentProgress;
 
     fn dummy_profile_config(interval: Interval) -> ProfileConfig {
         ProfileConfig::new(
@@ -306,6 +1495,1258 @@ mod backup_tests {
         )
     }
 
+    #[test]
+    fn write_snapshot_sorted() {
+        let path = PathBuf::from("./test_dir/write_snapshot_tmp.zip");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        let snapshot = vec![
+            String::from("C:/b.txt"),
+            String::from("C:/a/c.txt"),
+            String::from("C:/b.txt"),
+        ];
+        write_snapshot(&mut zip, snapshot).unwrap();
+        zip.finish().unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let mut content = String::new();
+        archive
+            .by_name(SNAPSHOT_FILE_NAME)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        drop(archive);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(content, "C:/a/c.txt\nC:/b.txt\n");
+    }
+
+    #[test]
+    fn small_files_first_order() {
+        use clap::Parser;
+
+        let dir = PathBuf::from("./test_dir/small_files_first_tmp");
+        let src = dir.join("src");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::write(src.join("big.bin"), [0u8; 5000]).unwrap();
+        fs::write(src.join("sub").join("mid.txt"), [0u8; 500]).unwrap();
+        fs::write(src.join("small.txt"), [0u8; 5]).unwrap();
+        fs::write(dir.join("single.txt"), [0u8; 2000]).unwrap();
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("small_files_first"),
+            dir.join("out"),
+            vec![dir.join("single.txt")],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.small_files_first = true;
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+
+        let res = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default());
+        let archive = profile_config.last_backup_archive.clone().unwrap();
+        let mut zip = zip::ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+        let order: Vec<String> = (0..zip.len())
+            .map(|i| {
+                let name = zip.by_index(i).unwrap().name().to_string();
+                name.rsplit(['/', '\\']).next().unwrap().to_string()
+            })
+            .collect();
+        drop(zip);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(res.is_ok());
+        assert_eq!(profile_config.last_backup_archive, Some(archive));
+        assert!(profile_config.last_backup.is_some());
+        assert_eq!(
+            order,
+            vec!["single.txt", "small.txt", "mid.txt", "big.bin", "manifest.json", SNAPSHOT_FILE_NAME]
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn symlink_loop() {
+        let dir = PathBuf::from("./test_dir/symlink_loop_tmp");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub").join("a.txt"), "a").unwrap();
+        // links back to its own parent, so following it blindly never ends
+        std::os::unix::fs::symlink("..", dir.join("sub").join("loop")).unwrap();
+
+        let mut profile_config = dummy_profile_config(config::interval::IntervalBuilder::default().build().unwrap());
+        let mut scanned = vec![];
+        for policy in [SymlinkPolicy::Follow, SymlinkPolicy::Skip, SymlinkPolicy::Preserve] {
+            profile_config.symlink_policy = policy;
+            let mut state = BackupState::default();
+            let mut plan = Plan::default();
+            let res = scan_directory(&dir, &profile_config, &mut state, &mut plan);
+            let files: Vec<(PathBuf, u64)> = plan.files.into_iter().map(|file| (file.path, file.size)).collect();
+            scanned.push((res, files, state.symlinks));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        let a = vec![(dir.join("sub").join("a.txt"), 1)];
+        assert_eq!(scanned[0], (Ok(()), a.clone(), vec![]));
+        assert_eq!(scanned[1], (Ok(()), a.clone(), vec![]));
+        assert_eq!(scanned[2], (Ok(()), a, vec![dir.join("sub").join("loop")]));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn preserved_symlinks_are_restored() {
+        use clap::Parser;
+        use crate::restore::{restore, Selection};
+
+        let dir = PathBuf::from("./test_dir/preserve_symlinks_tmp");
+        let src = dir.join("src");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::write(src.join("sub").join("a.txt"), "a").unwrap();
+        std::os::unix::fs::symlink("sub/a.txt", src.join("link.txt")).unwrap();
+        std::os::unix::fs::symlink(".", src.join("loop")).unwrap();
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("preserve_symlinks"),
+            dir.join("out"),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.symlink_policy = SymlinkPolicy::Preserve;
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        let res = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default());
+        let selection = Selection {
+            output_dir: Some(dir.join("restored")),
+            ..Default::default()
+        };
+        restore(&profile_config, offset::Local::now().naive_local(), true, &selection, true, &Cancellation::default()).unwrap();
+
+        let restored = dir.join("restored").join(ArchiveManifest::default().entry_name(&src).split_once('/').unwrap().1);
+        let link = fs::read_link(restored.join("link.txt"));
+        let loop_link = fs::read_link(restored.join("loop"));
+        let content = fs::read_to_string(restored.join("link.txt"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(res.is_ok());
+        assert_eq!(link.unwrap(), PathBuf::from("sub/a.txt"));
+        assert_eq!(loop_link.unwrap(), PathBuf::from("."));
+        assert_eq!(content.unwrap(), "a");
+    }
+
+    #[test]
+    fn split_archive_roundtrip() {
+        use clap::Parser;
+        use crate::restore::{restore, Selection};
+
+        let dir = PathBuf::from("./test_dir/split_archive_tmp");
+        let src = dir.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(dir.join("out")).unwrap();
+        for i in 0..4u8 {
+            fs::write(src.join(format!("{}.bin", i)), vec![i; 400_000]).unwrap();
+        }
+        fs::write(src.join("large.bin"), vec![9u8; 2 << 20]).unwrap();
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("split_archive"),
+            dir.join("out"),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.max_archive_size = Some(1 << 20);
+        profile_config.compression = Some(CompressionSettings {
+            method: CompressionMethod::Stored,
+            ..Default::default()
+        });
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        let skipped = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default()).unwrap().unwrap().skipped;
+        let parts = archive_parts(profile_config.last_backup_archive.as_ref().unwrap());
+        let sizes: Vec<u64> = parts.iter().map(|part| fs::metadata(part).unwrap().len()).collect();
+        let report = crate::report::read_report(&parts[0]).unwrap();
+        let selection = Selection {
+            output_dir: Some(dir.join("restored")),
+            ..Default::default()
+        };
+        restore(&profile_config, offset::Local::now().naive_local(), true, &selection, true, &Cancellation::default()).unwrap();
+        let restored = dir.join("restored").join(ArchiveManifest::default().entry_name(&src).split_once('/').unwrap().1);
+        let contents: Vec<Option<Vec<u8>>> = (0..4).map(|i| fs::read(restored.join(format!("{}.bin", i))).ok()).collect();
+        let large_restored = restored.join("large.bin").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].path, src.join("large.bin"));
+        assert!(skipped[0].reason.contains("max_archive_size"));
+        assert!(parts.len() > 1);
+        assert!(parts[0].to_str().unwrap().ends_with(".part01.zip"));
+        assert!(sizes.iter().all(|size| *size <= 1 << 20));
+        assert_eq!(report.file_count, 4);
+        assert_eq!(report.uncompressed_bytes, 1_600_000);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.archive_files.len(), parts.len());
+        assert_eq!(report.archive_files[1].bytes, sizes[1]);
+        for (i, content) in contents.into_iter().enumerate() {
+            assert_eq!(content, Some(vec![i as u8; 400_000]));
+        }
+        assert!(!large_restored);
+    }
+
+    #[test]
+    fn catalog_matches_rebuilt_catalog() {
+        use clap::Parser;
+        use config::backup_catalog::read_catalog;
+
+        let dir = PathBuf::from("./test_dir/backup_catalog_tmp");
+        let src = dir.join("src");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::write(src.join("thesis.docx"), "final version").unwrap();
+        fs::write(src.join("sub").join("notes.txt"), "").unwrap();
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("backup_catalog"),
+            dir.join("out"),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        let mut catalog = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default())
+            .unwrap()
+            .unwrap()
+            .catalog;
+        let rebuilt = crate::catalog::rebuild(&profile_config, &dir).and_then(|added| {
+            let entries = read_catalog(&dir, profile_config.get_uuid()).map_err(|e| format!("{:?}", e))?;
+            Ok((added, entries))
+        });
+        let archive = profile_config.last_backup_archive.clone().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let (added, mut rebuilt) = rebuilt.unwrap();
+        assert_eq!(added, 1);
+        catalog.sort_by(|a, b| a.path.cmp(&b.path));
+        rebuilt.sort_by(|a, b| a.path.cmp(&b.path));
+        let key = |entry: &CatalogEntry| (entry.path.clone(), entry.size, entry.sha256.clone(), entry.archive.clone());
+        assert_eq!(catalog.iter().map(key).collect::<Vec<_>>(), rebuilt.iter().map(key).collect::<Vec<_>>());
+        assert_eq!(catalog.len(), 2);
+        assert!(catalog[1].path.ends_with("thesis.docx"));
+        assert_eq!(catalog[1].size, 13);
+        assert_eq!(catalog[1].sha256, format!("{:x}", Sha256::digest(b"final version")));
+        assert_eq!(catalog[1].archive, archive);
+        assert!(catalog[1].modified.is_some());
+    }
+
+    #[test]
+    fn encrypted_roundtrip() {
+        use clap::Parser;
+        use config::profile_config::{EncryptionSettings, PassphraseSource};
+        use crate::{encryption::is_encrypted, restore::{restore, Selection}};
+
+        let dir = PathBuf::from("./test_dir/encrypted_roundtrip_tmp");
+        let src = dir.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::write(src.join("secret.txt"), "top secret").unwrap();
+        let key_file = dir.join("backup.key");
+        fs::write(&key_file, "test key\n").unwrap();
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("encrypted_roundtrip"),
+            dir.join("out"),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.encryption = Some(EncryptionSettings {
+            passphrase: PassphraseSource::KeyFile(key_file.clone()),
+        });
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default()).unwrap();
+        let archive = profile_config.last_backup_archive.clone().unwrap();
+        let encrypted = is_encrypted(&archive);
+        let raw = fs::read(&archive).unwrap();
+        let restored = dir
+            .join("restored")
+            .join(ArchiveManifest::default().entry_name(&src).split_once('/').unwrap().1)
+            .join("secret.txt");
+        let restore_with = |passphrase: &str| {
+            fs::write(&key_file, passphrase).unwrap();
+            let selection = Selection {
+                output_dir: Some(dir.join("restored")),
+                ..Default::default()
+            };
+            let outcome = restore(&profile_config, offset::Local::now().naive_local(), true, &selection, true, &Cancellation::default());
+            let content = fs::read_to_string(&restored).ok();
+            let _ = fs::remove_dir_all(dir.join("restored"));
+            (outcome.is_ok(), content)
+        };
+        let wrong_key = restore_with("wrong key");
+        let right_key = restore_with("test key");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(encrypted);
+        assert!(!raw.windows(b"top secret".len()).any(|window| window == b"top secret"));
+        assert_eq!(wrong_key, (false, None));
+        assert_eq!(right_key, (true, Some(String::from("top secret"))));
+    }
+
+    /// Two names that aren't unicode and have the same lossy name
+    #[cfg(unix)]
+    fn non_unicode_names() -> [std::ffi::OsString; 2] {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+        [OsStr::from_bytes(b"a\xff.txt").into(), OsStr::from_bytes(b"a\xfe.txt").into()]
+    }
+
+    /// Two names that aren't unicode and have the same lossy name
+    #[cfg(windows)]
+    fn non_unicode_names() -> [std::ffi::OsString; 2] {
+        use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+        let name = |surrogate: u16| OsString::from_wide(&[0x61, surrogate, 0x2E, 0x74, 0x78, 0x74]);
+        [name(0xD800), name(0xDC00)]
+    }
+
+    #[test]
+    fn non_unicode_names_roundtrip() {
+        use clap::Parser;
+        use crate::restore::{restore, Selection};
+
+        let dir = PathBuf::from("./test_dir/non_unicode_names_tmp");
+        let src = dir.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(dir.join("out")).unwrap();
+        let names = non_unicode_names();
+        for (i, name) in names.iter().enumerate() {
+            fs::write(src.join(name), format!("content {}", i)).unwrap();
+        }
+        fs::write(src.join("plain.txt"), "plain").unwrap();
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("non_unicode_names"),
+            dir.join("out"),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        let skipped = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default()).unwrap().unwrap().skipped;
+        let archive = profile_config.last_backup_archive.clone().unwrap();
+        let mut zip = zip::ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+        let entries = zip.len();
+        let manifest = read_manifest(&mut zip).unwrap();
+        drop(zip);
+        let selection = Selection {
+            output_dir: Some(dir.join("restored")),
+            ..Default::default()
+        };
+        restore(&profile_config, offset::Local::now().naive_local(), true, &selection, true, &Cancellation::default()).unwrap();
+        let restored = dir.join("restored").join(ArchiveManifest::default().entry_name(&src).split_once('/').unwrap().1);
+        let contents: Vec<Option<String>> = names.iter().map(|name| fs::read_to_string(restored.join(name)).ok()).collect();
+        let plain = fs::read_to_string(restored.join("plain.txt")).ok();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(skipped, vec![]);
+        // 3 files, the manifest and the snapshot
+        assert_eq!(entries, 5);
+        assert_eq!(manifest.raw_names.len(), 2);
+        assert_eq!(contents, vec![Some(String::from("content 0")), Some(String::from("content 1"))]);
+        assert_eq!(plain, Some(String::from("plain")));
+    }
+
+    #[test]
+    fn duplicate_entry_names() {
+        let mut state = BackupState::default();
+        let first = PathBuf::from("C:/a.txt");
+        let name = state.claim_entry_name(&first).unwrap();
+        // different paths only get the same entry name through a hash collision, so one is forged
+        state.entry_paths.insert(String::from("C/b.txt"), PathBuf::from("C:/other/b.txt"));
+
+        assert_eq!(state.claim_entry_name(&first), Ok(name));
+        assert!(state
+            .claim_entry_name(&PathBuf::from("C:/b.txt"))
+            .unwrap_err()
+            .contains("already used by \"C:/other/b.txt\""));
+    }
+
+    #[test]
+    fn target_dir_in_included_dir() {
+        use clap::Parser;
+        use crate::restore::{restore, Selection};
+
+        let dir = PathBuf::from("./test_dir/target_in_source_tmp");
+        let src = dir.join("src");
+        let out = src.join("out");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::create_dir_all(src.join("old")).unwrap();
+        fs::create_dir_all(&out).unwrap();
+        fs::write(src.join("a.txt"), "a").unwrap();
+        fs::write(src.join("sub").join("b.txt"), "b").unwrap();
+        fs::write(out.join("unrelated.zip"), "not an archive of the profile").unwrap();
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("target_in_source"),
+            out.clone(),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        // an archive of the profile left in a former target dir
+        let uuid = profile_config.get_uuid().as_hyphenated().to_string();
+        fs::write(src.join("old").join(format!("{}_2020-01-01_00-00.zip", uuid)), "old archive").unwrap();
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        let stats = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default()).unwrap().unwrap();
+        let archive = profile_config.last_backup_archive.clone().unwrap();
+        let mut zip = zip::ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+        let names: Vec<String> = zip.file_names().map(String::from).collect();
+        drop(zip);
+        let selection = Selection {
+            output_dir: Some(dir.join("restored")),
+            ..Default::default()
+        };
+        restore(&profile_config, offset::Local::now().naive_local(), true, &selection, true, &Cancellation::default()).unwrap();
+        let restored = dir.join("restored").join(ArchiveManifest::default().entry_name(&src).split_once('/').unwrap().1);
+        let a = fs::read_to_string(restored.join("a.txt")).ok();
+        let b = fs::read_to_string(restored.join("sub").join("b.txt")).ok();
+        let restored_out = restored.join("out").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(stats.skipped, vec![]);
+        assert_eq!(stats.files, 2);
+        assert_eq!(stats.bytes, 2);
+        // 2 files, the manifest and the snapshot
+        assert_eq!(names.len(), 4);
+        assert!(names.iter().all(|name| !name.ends_with(".zip")));
+        assert_eq!(a, Some(String::from("a")));
+        assert_eq!(b, Some(String::from("b")));
+        assert!(!restored_out);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn long_paths() {
+        use clap::Parser;
+        use crate::restore::{restore, Selection};
+
+        let dir = std::env::current_dir().unwrap().join("test_dir").join("long_paths_tmp");
+        let src = dir.join("src");
+        let mut deep = src.clone();
+        while deep.as_os_str().len() <= 300 {
+            deep.push("a_rather_long_directory_name");
+        }
+        fs::create_dir_all(long_path(&deep)).unwrap();
+        fs::create_dir_all(dir.join("out")).unwrap();
+        let file = deep.join("deep.txt");
+        fs::write(long_path(&file), "deep").unwrap();
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("long_paths"),
+            dir.join("out"),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        let stats = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default()).unwrap().unwrap();
+        fs::remove_file(long_path(&file)).unwrap();
+        restore(&profile_config, offset::Local::now().naive_local(), true, &Selection::default(), true, &Cancellation::default()).unwrap();
+        let restored = fs::read_to_string(long_path(&file)).ok();
+        fs::remove_dir_all(long_path(&dir)).unwrap();
+
+        assert_eq!(stats.files, 1);
+        assert_eq!(restored, Some(String::from("deep")));
+    }
+
+    #[test]
+    fn overlapping_includes() {
+        let dir = PathBuf::from("./test_dir/overlapping_includes_tmp");
+        let src = dir.join("src");
+        let excluded = src.join("excluded");
+        fs::create_dir_all(&excluded).unwrap();
+        fs::create_dir_all(dir.join("other")).unwrap();
+        fs::write(src.join("a.txt"), "a").unwrap();
+        fs::write(excluded.join("b.txt"), "b").unwrap();
+        fs::write(excluded.join("c.txt"), "c").unwrap();
+        fs::write(dir.join("other").join("d.txt"), "d").unwrap();
+
+        let profile_config = ProfileConfig::new(
+            String::from("overlapping_includes"),
+            dir.join("out"),
+            vec![
+                src.join("a.txt"),
+                src.join("..").join("src").join("a.txt"),
+                excluded.join("b.txt"),
+                excluded.join("b.txt"),
+                dir.join("other").join("d.txt"),
+            ],
+            vec![src.clone()],
+            vec![],
+            vec![excluded.clone()],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        let mut state = BackupState::default();
+        let plan = plan_backup(&profile_config, &mut state);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let sources: Vec<(String, Source)> = plan
+            .files
+            .iter()
+            .map(|file| (file.path.file_name().unwrap().to_string_lossy().into_owned(), file.source))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                (String::from("a.txt"), Source::Dir),
+                (String::from("b.txt"), Source::File),
+                (String::from("d.txt"), Source::File),
+            ]
+        );
+        assert!(plan.errors.is_empty());
+        assert!(state.skipped.is_empty());
+    }
+
+    #[test]
+    fn duplicate_files() {
+        let mut state = BackupState::default();
+        let planned = |path: &str, size| PlannedFile {
+            path: PathBuf::from(path),
+            size,
+            source: Source::File,
+        };
+        let mut files = vec![
+            planned("C:/Dir/a.txt", 1),
+            planned("C:/Dir/b.txt", 2),
+            planned("C:/Dir/a.txt", 1),
+        ];
+        // files that are found twice are archived once and aren't reported
+        state.drop_duplicates(&mut files);
+        assert_eq!(files.len(), 2);
+        assert!(state.skipped.is_empty());
+
+        // entry names only differing in case collide on Windows
+        let mut other_case = vec![planned("C:/dir/A.txt", 3)];
+        state.drop_duplicates(&mut other_case);
+        if cfg!(windows) {
+            assert!(other_case.is_empty());
+            assert_eq!(state.skipped.len(), 1);
+            assert!(state.skipped[0].reason.contains("already used by \"C:/Dir/a.txt\""));
+        } else {
+            assert_eq!(other_case.len(), 1);
+            assert!(state.skipped.is_empty());
+        }
+    }
+
+    #[test]
+    fn restores_modification_time_and_permissions() {
+        use clap::Parser;
+        use crate::restore::{restore, Selection};
+
+        let dir = PathBuf::from("./test_dir/preserve_metadata_tmp");
+        let src = dir.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(dir.join("out")).unwrap();
+        let old = src.join("old.txt");
+        fs::write(&old, "old").unwrap();
+        let modified = NaiveDateTime::parse_from_str("2001-02-03 04:05", "%Y-%m-%d %H:%M").unwrap();
+        let modified_time = SystemTime::from(modified.and_local_timezone(Local).unwrap());
+        File::options().write(true).open(&old).unwrap().set_modified(modified_time).unwrap();
+        let mut permissions = fs::metadata(&old).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&old, permissions).unwrap();
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("preserve_metadata"),
+            dir.join("out"),
+            vec![old.clone()],
+            vec![],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        let res = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default());
+        let selection = Selection {
+            output_dir: Some(dir.join("restored")),
+            ..Default::default()
+        };
+        restore(&profile_config, offset::Local::now().naive_local(), true, &selection, true, &Cancellation::default()).unwrap();
+
+        let restored = dir.join("restored").join(ArchiveManifest::default().entry_name(&old).split_once('/').unwrap().1);
+        let metadata = fs::metadata(&restored).unwrap();
+        let restored_modified = DateTime::<Local>::from(metadata.modified().unwrap()).naive_local();
+        let readonly = metadata.permissions().readonly();
+        for path in [&old, &restored] {
+            let mut permissions = fs::metadata(path).unwrap().permissions();
+            #[allow(clippy::permissions_set_readonly_false)]
+            permissions.set_readonly(false);
+            fs::set_permissions(path, permissions).unwrap();
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(res.is_ok());
+        assert_eq!(restored_modified.format("%Y-%m-%d %H:%M").to_string(), "2001-02-03 04:05");
+        assert!(readonly);
+    }
+
+    #[test]
+    fn reports_progress() {
+        use clap::Parser;
+        use std::path::Path;
+
+        #[derive(Default)]
+        struct Recorder {
+            started: Option<(usize, u64)>,
+            files: Vec<(PathBuf, u64)>,
+            written: u64,
+            done: bool,
+        }
+        impl ProgressSink for Recorder {
+            fn on_start(&mut self, files: usize, total_bytes: u64) {
+                self.started = Some((files, total_bytes));
+            }
+            fn on_file_start(&mut self, path: &Path, size: u64) {
+                self.files.push((path.to_path_buf(), size));
+            }
+            fn on_bytes_written(&mut self, bytes: u64) {
+                self.written += bytes;
+            }
+            fn on_done(&mut self) {
+                self.done = true;
+            }
+        }
+
+        let dir = PathBuf::from("./test_dir/reports_progress_tmp");
+        let src = dir.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::write(src.join("a.txt"), [0u8; 3]).unwrap();
+        fs::write(src.join("b.txt"), [0u8; 0x3000]).unwrap();
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("progress"),
+            dir.join("out"),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.small_files_first = true;
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        let mut recorder = Recorder::default();
+        let res = perform_backup(&mut profile_config, &args, &[], true, &mut recorder, &Cancellation::default());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(res.is_ok());
+        assert_eq!(recorder.started, Some((2, 0x3003)));
+        assert_eq!(recorder.files, vec![(src.join("a.txt"), 3), (src.join("b.txt"), 0x3000)]);
+        assert_eq!(recorder.written, 0x3003);
+        assert!(recorder.done);
+    }
+
+    /// Cancels or stops the backup from another thread once the first chunk of the second file was written
+    struct CancelMidway {
+        cancellation: Cancellation,
+        stop: bool,
+        files: usize,
+    }
+
+    impl ProgressSink for CancelMidway {
+        fn on_start(&mut self, _files: usize, _total_bytes: u64) {}
+        fn on_file_start(&mut self, _path: &std::path::Path, _size: u64) {
+            self.files += 1;
+        }
+        fn on_bytes_written(&mut self, _bytes: u64) {
+            if self.files == 2 {
+                let cancellation = self.cancellation.clone();
+                let stop = self.stop;
+                std::thread::spawn(move || if stop { cancellation.stop() } else { cancellation.cancel() })
+                    .join()
+                    .unwrap();
+            }
+        }
+        fn on_done(&mut self) {}
+    }
+
+    #[test]
+    fn cancelled_backup() {
+        use clap::Parser;
+
+        let dir = PathBuf::from("./test_dir/cancelled_backup_tmp");
+        let src = dir.join("src");
+        let out = dir.join("out");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&out).unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(src.join(name), [0u8; 0x6000]).unwrap();
+        }
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("cancelled"),
+            out.clone(),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        let cancellation = Cancellation::default();
+        let mut progress = CancelMidway {
+            cancellation: cancellation.clone(),
+            stop: false,
+            files: 0,
+        };
+        let res = perform_backup(&mut profile_config, &args, &[], true, &mut progress, &cancellation);
+        let left = fs::read_dir(&out).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(res, Err(String::from(CANCELLED)));
+        // the third file was never started
+        assert_eq!(progress.files, 2);
+        assert_eq!(left, 0);
+        assert_eq!(profile_config.last_backup_archive, None);
+    }
+
+    #[test]
+    fn stopped_backup() {
+        use clap::Parser;
+
+        let dir = PathBuf::from("./test_dir/stopped_backup_tmp");
+        let src = dir.join("src");
+        let out = dir.join("out");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&out).unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(src.join(name), [0u8; 0x6000]).unwrap();
+        }
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("stopped"),
+            out.clone(),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.backup_mode = BackupMode::Incremental;
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        let cancellation = Cancellation::default();
+        let mut progress = CancelMidway {
+            cancellation: cancellation.clone(),
+            stop: true,
+            files: 0,
+        };
+        let res = perform_backup(&mut profile_config, &args, &[], true, &mut progress, &cancellation);
+        let archive = list_archives(&profile_config).unwrap().pop().map(|(_, path)| path);
+        let manifest = archive
+            .as_ref()
+            .and_then(|archive| read_manifest(&mut ZipArchive::new(File::open(archive).unwrap()).unwrap()));
+        let cancelled = archive.as_ref().map(|archive| is_cancelled_archive(archive));
+        let since = incremental_since(&profile_config, false);
+        fs::remove_dir_all(&dir).unwrap();
+
+        // the second file is finished, but the third one is never started
+        assert_eq!(res.unwrap().unwrap().files, 2);
+        assert_eq!(progress.files, 2);
+        assert!(manifest.unwrap().cancelled);
+        assert_eq!(cancelled, Some(true));
+        // the partial archive is neither the last backup nor built on
+        assert_eq!(profile_config.last_backup_archive, None);
+        assert_eq!(since, None);
+    }
+
+    /// Pauses the backup once the first chunk of the second file was written and resumes it from another thread after [PauseMidway::PAUSE]
+    #[derive(Default)]
+    struct PauseMidway {
+        cancellation: Cancellation,
+        files: usize,
+        paused_at: Option<std::time::Instant>,
+        resumer: Option<std::thread::JoinHandle<()>>,
+        third_started: Option<std::time::Instant>,
+    }
+
+    impl PauseMidway {
+        const PAUSE: Duration = Duration::from_millis(300);
+    }
+
+    impl ProgressSink for PauseMidway {
+        fn on_start(&mut self, _files: usize, _total_bytes: u64) {}
+        fn on_file_start(&mut self, _path: &std::path::Path, _size: u64) {
+            self.files += 1;
+            if self.files == 3 {
+                self.third_started = Some(std::time::Instant::now());
+            }
+        }
+        fn on_bytes_written(&mut self, _bytes: u64) {
+            if self.files == 2 && self.paused_at.is_none() {
+                self.cancellation.pause();
+                self.paused_at = Some(std::time::Instant::now());
+                let cancellation = self.cancellation.clone();
+                self.resumer = Some(std::thread::spawn(move || {
+                    std::thread::sleep(PauseMidway::PAUSE);
+                    cancellation.resume();
+                }));
+            }
+        }
+        fn on_done(&mut self) {}
+    }
+
+    #[test]
+    fn paused_backup() {
+        use clap::Parser;
+
+        let dir = PathBuf::from("./test_dir/paused_backup_tmp");
+        let src = dir.join("src");
+        let out = dir.join("out");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&out).unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(src.join(name), [0u8; 0x6000]).unwrap();
+        }
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("paused"),
+            out.clone(),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        let cancellation = Cancellation::default();
+        let mut progress = PauseMidway {
+            cancellation: cancellation.clone(),
+            ..Default::default()
+        };
+        let res = perform_backup(&mut profile_config, &args, &[], true, &mut progress, &cancellation);
+        progress.resumer.take().unwrap().join().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        // the second file is finished before pausing and the third one is archived once the backup is resumed
+        assert_eq!(res.unwrap().unwrap().files, 3);
+        assert!(progress.third_started.unwrap() - progress.paused_at.unwrap() >= PauseMidway::PAUSE);
+        assert!(!cancellation.is_paused());
+        assert!(profile_config.last_backup_archive.is_some());
+    }
+
+    #[test]
+    fn incremental_backup() {
+        use clap::Parser;
+
+        let dir = PathBuf::from("./test_dir/incremental_tmp");
+        let src = dir.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::write(src.join("old.txt"), [0u8; 5]).unwrap();
+        fs::write(src.join("new.txt"), [0u8; 5]).unwrap();
+        File::options()
+            .write(true)
+            .open(src.join("old.txt"))
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("incremental"),
+            dir.join("out"),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.backup_mode = BackupMode::Incremental;
+        let first_run = incremental_since(&profile_config, false);
+        let previous = dir.join("out").join(format!("{}_2000-01-01_00-00.zip", profile_config.get_uuid()));
+        File::create(&previous).unwrap();
+        let forced = incremental_since(&profile_config, true);
+
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        let res = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default());
+        let archive = profile_config.last_backup_archive.clone().unwrap();
+        let mut zip = zip::ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+        let names: Vec<String> = zip.file_names().map(String::from).collect();
+        let mut snapshot = String::new();
+        zip.by_name(SNAPSHOT_FILE_NAME).unwrap().read_to_string(&mut snapshot).unwrap();
+        drop(zip);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(first_run, None);
+        assert_eq!(forced, None);
+        assert!(res.is_ok());
+        assert!(archive.to_str().unwrap().ends_with("_incr.zip"));
+        assert_eq!(names.len(), 3);
+        assert!(names.iter().any(|name| name.ends_with("new.txt")));
+        assert_eq!(snapshot.lines().count(), 2);
+    }
+
+    #[test]
+    fn skip_if_unchanged() {
+        use clap::Parser;
+
+        let dir = PathBuf::from("./test_dir/skip_if_unchanged_tmp");
+        let src = dir.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(dir.join("out")).unwrap();
+        for name in ["a.txt", "b.txt"] {
+            fs::write(src.join(name), name).unwrap();
+            File::options()
+                .write(true)
+                .open(src.join(name))
+                .unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH)
+                .unwrap();
+        }
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("skip_if_unchanged"),
+            dir.join("out"),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.skip_if_unchanged = true;
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+        let first = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default());
+        // pretend the first archive is older, so the following ones get other names
+        let previous = dir.join("out").join(format!("{}_2000-01-01_00-00.zip", profile_config.get_uuid()));
+        fs::rename(profile_config.last_backup_archive.clone().unwrap(), &previous).unwrap();
+        let archives = |profile_config: &ProfileConfig| list_archives(profile_config).unwrap().len();
+
+        let unchanged = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default());
+        let after_unchanged = archives(&profile_config);
+        let unchanged_skippable = nothing_changed(&profile_config, &[]);
+        fs::write(src.join("b.txt"), "touched").unwrap();
+        let touched_skippable = nothing_changed(&profile_config, &[]);
+        let touched = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default());
+        let after_touched = archives(&profile_config);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(first.is_ok());
+        assert_eq!(unchanged, Ok(None));
+        assert_eq!(after_unchanged, 1);
+        assert!(unchanged_skippable);
+        assert!(!touched_skippable);
+        assert!(touched.unwrap().is_some());
+        assert_eq!(after_touched, 2);
+    }
+
+    #[test]
+    fn forced_backup_isnt_skipped() {
+        use clap::Parser;
+
+        let dir = PathBuf::from("./test_dir/forced_unchanged_tmp");
+        let src = dir.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::write(src.join("a.txt"), "a").unwrap();
+        File::options()
+            .write(true)
+            .open(src.join("a.txt"))
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("forced_unchanged"),
+            dir.join("out"),
+            vec![],
+            vec![src.clone()],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.skip_if_unchanged = true;
+        let args = Args::parse_from(["backupper", "--all", "-f", "backup"]);
+        perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default()).unwrap();
+        let previous = dir.join("out").join(format!("{}_2000-01-01_00-00.zip", profile_config.get_uuid()));
+        fs::rename(profile_config.last_backup_archive.clone().unwrap(), &previous).unwrap();
+
+        let skippable = nothing_changed(&profile_config, &[]);
+        let res = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default());
+        let archives = list_archives(&profile_config).unwrap().len();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(skippable);
+        assert!(res.is_ok());
+        assert_eq!(archives, 2);
+    }
+
+    #[test]
+    fn stored_faster_than_deflated() {
+        use clap::Parser;
+        use std::time::Instant;
+
+        let dir = PathBuf::from("./test_dir/compression_speed_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("noise.bin");
+        // xorshift output is incompressible, so deflating it is wasted effort
+        let mut x: u64 = 0x2545F4914F6CDD1D;
+        let noise: Vec<u8> = (0..4_000_000)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect();
+        fs::write(&path, noise).unwrap();
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+
+        let mut durations = vec![];
+        let mut sizes = vec![];
+        for method in [CompressionMethod::Stored, CompressionMethod::Deflated] {
+            let archive = dir.join(format!("{:?}.zip", method));
+            let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+            let mut state = BackupState {
+                compression: Some(CompressionSettings {
+                    method,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let start = Instant::now();
+            write_to_zip(&path, &mut zip, &args, &mut state, &mut SilentProgress).unwrap();
+            zip.finish().unwrap();
+            durations.push(start.elapsed());
+            drop(zip);
+
+            let mut zip = zip::ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+            let entry = zip.by_index(0).unwrap();
+            sizes.push((entry.compression(), entry.size()));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(sizes[0], (zip::CompressionMethod::Stored, 4_000_000));
+        assert_eq!(sizes[1].0, zip::CompressionMethod::Deflated);
+        assert!(durations[0] * 2 < durations[1], "Stored took {:?}, Deflated {:?}", durations[0], durations[1]);
+    }
+
+    #[test]
+    fn large_file_options() {
+        let state = BackupState::default();
+        let path = PathBuf::from("C:/vm.img");
+        // the options can't be inspected, so the length of the extra field in the local header is checked, which holds the zip64 sizes
+        let extra_field_len = |size: u64| {
+            let mut zip = ZipWriter::new(io::Cursor::new(vec![]));
+            zip.start_file("entry", state.file_options(&path, size)).unwrap();
+            let bytes = zip.finish().unwrap().into_inner();
+            u16::from_le_bytes([bytes[28], bytes[29]])
+        };
+        assert_eq!(extra_field_len(1000), 0);
+        assert_eq!(extra_field_len(LARGE_FILE_THRESHOLD), 0);
+        assert_eq!(extra_field_len(6 << 30), 20);
+    }
+
+    #[test]
+    fn verify_corrupted_archive() {
+        let path = PathBuf::from("./test_dir/verify_archive_tmp.zip");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("a.txt", options).unwrap();
+        zip.write_all(b"intact content").unwrap();
+        zip.finish().unwrap();
+        let intact = verify_archive(&path);
+
+        // flip a byte of the stored content, which only the CRC reveals
+        let mut bytes = fs::read(&path).unwrap();
+        let pos = bytes.windows(6).position(|window| window == b"intact").unwrap();
+        bytes[pos] = b'I';
+        fs::write(&path, bytes).unwrap();
+        let corrupted = verify_archive(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(intact, Ok(()));
+        assert!(corrupted.unwrap_err().contains("a.txt"));
+    }
+
+    /// Archives a sparse file of 5 GiB. Takes a while, so run it with `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn zip64_archive() {
+        use clap::Parser;
+
+        let dir = PathBuf::from("./test_dir/zip64_tmp");
+        fs::create_dir_all(dir.join("out")).unwrap();
+        let image = dir.join("vm.img");
+        File::create(&image).unwrap().set_len(5 << 30).unwrap();
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("zip64"),
+            dir.join("out"),
+            vec![image.clone()],
+            vec![],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.compression = Some(CompressionSettings {
+            method: CompressionMethod::Zstd,
+            ..Default::default()
+        });
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+
+        let res = perform_backup(&mut profile_config, &args, &[], true, &mut SilentProgress, &Cancellation::default());
+        let archive = profile_config.last_backup_archive.clone();
+        let size = archive.as_ref().map(|archive| {
+            let mut zip = zip::ZipArchive::new(File::open(archive).unwrap()).unwrap();
+            let size = zip.by_name(&ArchiveManifest::default().entry_name(&image)).unwrap().size();
+            size
+        });
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(res.map(|stats| stats.unwrap().skipped), Ok(vec![]));
+        assert_eq!(size, Some(5 << 30));
+    }
+
+    #[test]
+    fn unavailable_target_without_dialogs() {
+        use clap::Parser;
+
+        let mut profile_config = ProfileConfig::new(
+            String::from("headless"),
+            PathBuf::from("./test_dir/doesnt_exist"),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+
+        let msg = perform_backup(&mut profile_config, &args, &[], false, &mut SilentProgress, &Cancellation::default()).unwrap_err();
+        assert!(msg.contains("isn't available"));
+    }
+
+    #[test]
+    fn scan_skips_filtered_files() {
+        let dir = PathBuf::from("./test_dir/filtered_scan_tmp");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("debian.ISO"), [0u8; 5]).unwrap();
+        fs::write(dir.join("sub").join("huge.bin"), [0u8; 2000]).unwrap();
+        fs::write(dir.join("notes.txt"), [0u8; 5]).unwrap();
+        fs::write(dir.join("single.iso"), [0u8; 5]).unwrap();
+
+        let mut profile_config = dummy_profile_config(config::interval::IntervalBuilder::default().build().unwrap());
+        profile_config.exclude_extensions = vec![String::from("iso")];
+        profile_config.max_file_size = Some(1000);
+        let mut state = BackupState::default();
+        let mut plan = Plan::default();
+        let res = scan_directory(&dir, &profile_config, &mut state, &mut plan)
+            // the walk already found it, so check it on its own
+            .and_then(|_| scan_file(&dir.join("single.iso"), &profile_config, &mut state, &mut Plan::default()));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(res.is_ok());
+        assert_eq!(plan.files.len(), 1);
+        assert_eq!((&plan.files[0].path, plan.files[0].size), (&dir.join("notes.txt"), 5));
+        assert_eq!(state.filtered, 4);
+    }
+
+    #[test]
+    fn writable_versions() {
+        let mut profile_config = dummy_profile_config(config::interval::IntervalBuilder::default().build().unwrap());
+        assert!(check_writable(&profile_config).is_ok());
+
+        profile_config.written_by_version = String::from("0.0.1");
+        assert!(check_writable(&profile_config).is_ok());
+
+        profile_config.written_by_version = String::from("999.0.0");
+        let msg = check_writable(&profile_config).unwrap_err();
+        assert!(msg.contains("999.0.0") && msg.contains(CRATE_VERSION));
+    }
+
+    #[test]
+    fn retry_delays() {
+        let delays: Vec<u64> = (0..7).map(|attempt| retry_delay(attempt).as_millis() as u64).collect();
+        assert_eq!(delays, vec![250, 500, 1000, 2000, 4000, 8000, 8000]);
+        assert_eq!(retry_delay(u32::MAX), MAX_LOCKED_RETRY_DELAY);
+    }
+
+    #[test]
+    fn open_without_retries() {
+        // only files in use are tried again, so missing files fail right away
+        let started = std::time::Instant::now();
+        let res = open_with_retries(&PathBuf::from("./test_dir/open_missing_tmp.txt"), 5);
+        assert!(res.is_err());
+        assert!(started.elapsed() < LOCKED_RETRY_DELAY);
+    }
+
+    #[test]
+    fn in_use_errors() {
+        assert_eq!(is_in_use(&io::Error::from_raw_os_error(32)), cfg!(target_family = "windows"));
+        assert_eq!(is_in_use(&io::Error::from_raw_os_error(33)), cfg!(target_family = "windows"));
+        assert!(!is_in_use(&io::Error::from_raw_os_error(2)));
+        assert!(!is_in_use(&io::Error::new(io::ErrorKind::Other, "in use")));
+    }
+
+    #[test]
+    fn unstable_files() {
+        use clap::Parser;
+
+        fn append(path: &PathBuf) {
+            let mut file = fs::OpenOptions::new().append(true).open(path).unwrap();
+            file.write_all(b" appended").unwrap();
+        }
+
+        let dir = PathBuf::from("./test_dir/unstable_files_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let stable = dir.join("stable.txt");
+        let growing = dir.join("growing.log");
+        fs::write(&stable, "stable").unwrap();
+        fs::write(&growing, "growing").unwrap();
+        let args = Args::parse_from(["backupper", "--all", "backup"]);
+
+        let archive = dir.join("unstable.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        let mut state = BackupState::default();
+        write_to_zip(&stable, &mut zip, &args, &mut state, &mut SilentProgress).unwrap();
+        state.on_read = Some(append);
+        write_to_zip(&growing, &mut zip, &args, &mut state, &mut SilentProgress).unwrap();
+        let unstable = state.unstable.clone();
+
+        let mut keep_state = BackupState {
+            snapshot: state.snapshot.clone(),
+            unstable: state.unstable.clone(),
+            ..Default::default()
+        };
+        handle_unstable(&mut zip, &mut keep_state, UnstableFilePolicy::Keep).unwrap();
+        handle_unstable(&mut zip, &mut state, UnstableFilePolicy::Drop).unwrap();
+        zip.finish().unwrap();
+        drop(zip);
+        let mut zip = zip::ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+        let mut listed = String::new();
+        zip.by_name(UNSTABLE_FILE_NAME).unwrap().read_to_string(&mut listed).unwrap();
+        drop(zip);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let growing_name = ArchiveManifest::default().entry_name(&growing);
+        let stable_name = ArchiveManifest::default().entry_name(&stable);
+        assert_eq!(unstable, vec![growing]);
+        assert_eq!(listed, format!("{}\n", growing_name));
+        assert_eq!(keep_state.snapshot, vec![stable_name.clone(), growing_name]);
+        assert_eq!(state.snapshot, vec![stable_name]);
+    }
+
     mod is_scheduled_tests {
         use chrono::{Duration, NaiveDateTime, Timelike};
         use config::interval::{IntervalBuilder, Month};
@@ -0,0 +1,259 @@
+//! Contains functions for breaking down the size of an archive by the include roots of a [ProfileConfig].
+
+use std::{fs::File, path::{Path, PathBuf}};
+
+use config::profile_config::ProfileConfig;
+use zip::ZipArchive;
+
+use crate::{
+    common::{archive_parts, original_path, read_manifest, SNAPSHOT_FILE_NAME},
+    encryption::readable_archive,
+};
+
+/// Sizes of the files of one include root within an archive.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RootBreakdown {
+    /// Entry of `dirs_to_include` or `files_to_include`
+    pub root: PathBuf,
+    /// Number of archived files
+    pub file_count: usize,
+    /// Uncompressed size in bytes
+    pub raw_size: u64,
+    /// Compressed size in bytes
+    pub compressed_size: u64,
+}
+
+impl RootBreakdown {
+    fn new(root: PathBuf) -> RootBreakdown {
+        RootBreakdown {
+            root,
+            file_count: 0,
+            raw_size: 0,
+            compressed_size: 0,
+        }
+    }
+
+    /// Compressed size divided by raw size. `1.0` for empty roots.
+    pub fn ratio(&self) -> f64 {
+        if self.raw_size == 0 {
+            1.0
+        } else {
+            self.compressed_size as f64 / self.raw_size as f64
+        }
+    }
+}
+
+/// Reads the finished `archive` and sums up the sizes of its entries per include root of the given [ProfileConfig].
+/// Entries are assigned by their original paths (see [original_path]). Entries that don't belong to any root (like the snapshot) are not counted.
+/// The entries of all volumes of split archives are counted (see [archive_parts]).
+///
+/// # Returns
+/// One [RootBreakdown] per include root, in the order of `dirs_to_include` followed by `files_to_include`.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the archive couldn't be read.
+pub fn size_breakdown(
+    profile_config: &ProfileConfig,
+    archive: &PathBuf,
+) -> Result<Vec<RootBreakdown>, String> {
+    let mut breakdown: Vec<RootBreakdown> = profile_config
+        .dirs_to_include
+        .iter()
+        .chain(profile_config.files_to_include.iter())
+        .map(|root| RootBreakdown::new(root.clone()))
+        .collect();
+
+    for part in archive_parts(archive) {
+        let part = readable_archive(profile_config, &part)?;
+        add_entries(&mut breakdown, part.path())?;
+    }
+
+    Ok(breakdown)
+}
+
+/// Adds the sizes of the entries of the archive at `path` to the root of the `breakdown` they belong to.
+fn add_entries(breakdown: &mut [RootBreakdown], path: &Path) -> Result<(), String> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("Error opening file {:?}: {:?}", path, err)),
+    };
+    let mut zip = match ZipArchive::new(file) {
+        Ok(zip) => zip,
+        Err(err) => return Err(format!("Couldn't read archive because {:?}", err)),
+    };
+
+    let manifest = read_manifest(&mut zip);
+    for i in 0..zip.len() {
+        let entry = match zip.by_index_raw(i) {
+            Ok(entry) => entry,
+            Err(err) => return Err(format!("Error reading entry: {:?}", err)),
+        };
+        if entry.name() == SNAPSHOT_FILE_NAME {
+            continue;
+        }
+
+        let path = match original_path(manifest.as_ref(), entry.name()) {
+            Some(path) => PathBuf::from(path),
+            None => continue,
+        };
+        if let Some(root) = breakdown
+            .iter_mut()
+            .find(|root| path.starts_with(&root.root))
+        {
+            root.file_count += 1;
+            root.raw_size += entry.size();
+            root.compressed_size += entry.compressed_size();
+        }
+    }
+    Ok(())
+}
+
+/// Formats the `breakdown` as a table with one row per root and a row containing the totals.
+pub fn format_breakdown(breakdown: &[RootBreakdown]) -> String {
+    let mut total = RootBreakdown::new(PathBuf::from("Total"));
+    let mut lines = vec![format!(
+        "{:<40} {:>8} {:>14} {:>14} {:>7}",
+        "Root", "Files", "Raw", "Compressed", "Ratio"
+    )];
+
+    for root in breakdown {
+        total.file_count += root.file_count;
+        total.raw_size += root.raw_size;
+        total.compressed_size += root.compressed_size;
+        lines.push(format_row(root));
+    }
+    lines.push(format_row(&total));
+
+    lines.join("\n")
+}
+
+fn format_row(root: &RootBreakdown) -> String {
+    format!(
+        "{:<40} {:>8} {:>14} {:>14} {:>6.1}%",
+        root.root.display(),
+        root.file_count,
+        root.raw_size,
+        root.compressed_size,
+        root.ratio() * 100.0
+    )
+}
+
+#[cfg(test)]
+mod breakdown_tests {
+    use std::{fs, io::Write};
+
+    use config::interval::IntervalBuilder;
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    #[test]
+    fn totals_reconcile_with_archive_size() {
+        let archive = PathBuf::from("./test_dir/breakdown_tmp.zip");
+        let profile_config = ProfileConfig::new(
+            String::from("breakdown"),
+            PathBuf::from("./test_dir"),
+            vec![PathBuf::from("C:/single.txt")],
+            vec![PathBuf::from("C:/docs"), PathBuf::from("C:/pics")],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        );
+
+        let entries = [
+            ("C:/docs/a.txt", "a".repeat(10_000)),
+            ("C:/docs/sub/b.txt", "ab".repeat(3_000)),
+            ("C:/pics/c.txt", String::from("no compression gain")),
+            ("C:/single.txt", "single".repeat(100)),
+            ("C:/other/d.txt", String::from("not in any root")),
+        ];
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        for (name, content) in &entries {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.start_file(SNAPSHOT_FILE_NAME, FileOptions::default()).unwrap();
+        zip.finish().unwrap();
+
+        let breakdown = size_breakdown(&profile_config, &archive).unwrap();
+        let archive_size = fs::metadata(&archive).unwrap().len();
+        fs::remove_file(&archive).unwrap();
+
+        let roots: Vec<(PathBuf, usize, u64)> = breakdown
+            .iter()
+            .map(|root| (root.root.clone(), root.file_count, root.raw_size))
+            .collect();
+        assert_eq!(
+            roots,
+            vec![
+                (PathBuf::from("C:/docs"), 2, 16_000),
+                (PathBuf::from("C:/pics"), 1, 19),
+                (PathBuf::from("C:/single.txt"), 1, 600),
+            ]
+        );
+        assert!(breakdown[0].ratio() < 0.1);
+
+        // local header, central directory header and data descriptor per entry plus the end of central directory
+        let slack: u64 = entries
+            .iter()
+            .map(|(name, _)| name)
+            .chain(std::iter::once(&SNAPSHOT_FILE_NAME))
+            .map(|name| 30 + 46 + 16 + 2 * name.len() as u64)
+            .sum::<u64>()
+            + 22;
+        // deflating a tiny file may add a few bytes
+        let unassigned = entries[4].1.len() as u64 + 16;
+        let total_compressed: u64 = breakdown.iter().map(|root| root.compressed_size).sum();
+        assert!(total_compressed <= archive_size);
+        assert!(archive_size <= total_compressed + unassigned + slack);
+    }
+
+    #[test]
+    fn relative_entry_names() {
+        let archive = PathBuf::from("./test_dir/breakdown_relative_tmp.zip");
+        let profile_config = ProfileConfig::new(
+            String::from("breakdown"),
+            PathBuf::from("./test_dir"),
+            vec![],
+            vec![PathBuf::from("C:/docs")],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        );
+
+        let mut manifest = config::archive_manifest::ArchiveManifest::default();
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        for name in ["C:/docs/a.txt", "C:/other/b.txt"] {
+            zip.start_file(manifest.entry_name(std::path::Path::new(name)), FileOptions::default()).unwrap();
+            zip.write_all(b"content").unwrap();
+        }
+        crate::backup::write_manifest(&mut zip, &manifest).unwrap();
+        zip.finish().unwrap();
+        drop(zip);
+
+        let breakdown = size_breakdown(&profile_config, &archive);
+        fs::remove_file(&archive).unwrap();
+
+        let breakdown = breakdown.unwrap();
+        assert_eq!(breakdown[0].file_count, 1);
+        assert_eq!(breakdown[0].raw_size, 7);
+    }
+
+    #[test]
+    fn format_totals() {
+        let mut docs = RootBreakdown::new(PathBuf::from("C:/docs"));
+        docs.file_count = 2;
+        docs.raw_size = 200;
+        docs.compressed_size = 50;
+        let mut pics = RootBreakdown::new(PathBuf::from("C:/pics"));
+        pics.file_count = 1;
+        pics.raw_size = 100;
+        pics.compressed_size = 100;
+
+        let table = format_breakdown(&[docs, pics]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("C:/docs") && lines[1].ends_with("25.0%"));
+        assert!(lines[3].starts_with("Total") && lines[3].contains(" 300 ") && lines[3].ends_with("50.0%"));
+    }
+}
@@ -0,0 +1,180 @@
+//! Contains the flags that cancel or stop a running backup or restore, e.g., when the user presses Ctrl-C.
+//!
+//! Backups and restores check the flags between files and while copying them. A cancelled backup removes its unfinished archive.
+//! A stopped backup finishes the current file and keeps the files archived until then as a partial archive marked as `cancelled`.
+//! Backups are stopped by the stop signal (see [install_stop_handler]) or through their control channel (see [crate::control]),
+//! which the server uses to cancel the jobs it started. Paused backups idle between files until they are resumed, stopped or cancelled.
+
+use std::{
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+
+#[cfg(target_family = "windows")]
+mod windows;
+
+/// Exit code if the run was cancelled, like shells report processes ended by Ctrl-C
+pub const EXIT_CODE: exitcode::ExitCode = 130;
+
+/// Exit code if the run was stopped and kept a partial archive
+pub const STOPPED_EXIT_CODE: exitcode::ExitCode = config::backup_report::STOPPED_EXIT_CODE;
+
+/// Error message of a cancelled backup
+pub const CANCELLED: &str = "Cancelled by the user";
+
+/// How often a paused run checks if it was resumed
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Flags that are shared between the code that cancels and the backups and restores that are cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation {
+    cancelled: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+}
+
+impl Cancellation {
+    /// Cancels all backups and restores checking this flag.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Checks if the flag was set.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Stops all backups checking this flag after their current file. Their archives are kept with the files archived until then.
+    /// The flag is also set by the stop signal (see [install_stop_handler]).
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
+    /// Checks if no more files should be started, because the run was stopped or cancelled.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst) || self.is_cancelled()
+    }
+
+    /// Pauses all backups checking this flag after their current file (see [Cancellation::wait_while_paused]).
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Lets paused backups continue.
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Checks if the run was paused and not resumed yet.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Blocks while the run is paused, until it is resumed, stopped or cancelled.
+    ///
+    /// # Returns
+    /// How long the run was paused.
+    pub fn wait_while_paused(&self) -> Duration {
+        let start = Instant::now();
+        while self.is_paused() && !self.is_stopped() {
+            thread::sleep(PAUSE_POLL_INTERVAL);
+        }
+        start.elapsed()
+    }
+}
+
+/// Sets the given `cancellation` when the user presses Ctrl-C. Pressing it again exits right away.
+pub fn install_handler(cancellation: Cancellation) {
+    let handled = ctrlc::set_handler(move || {
+        if cancellation.is_cancelled() {
+            process::exit(EXIT_CODE);
+        }
+        warn!("Cancelling, press Ctrl-C again to exit right away");
+        cancellation.cancel();
+    });
+    if let Err(err) = handled {
+        warn!("Couldn't install Ctrl-C handler because of {:?}", err);
+    }
+}
+
+/// Stops the given `cancellation` (see [Cancellation::stop]) when the process receives the stop signal:
+/// `SIGUSR1` on Unix and `CTRL_BREAK_EVENT` on Windows. Has to be called after [install_handler], so Ctrl-Break isn't handled like Ctrl-C.
+pub fn install_stop_handler(cancellation: Cancellation) {
+    #[cfg(target_family = "windows")]
+    let handled = windows::register_stop_flag(cancellation.stopped);
+    #[cfg(unix)]
+    let handled = signal_hook::flag::register(signal_hook::consts::SIGUSR1, cancellation.stopped)
+        .map(|_| ())
+        .map_err(|err| format!("{:?}", err));
+    if let Err(msg) = handled {
+        warn!("Couldn't install the stop signal handler because of {}", msg);
+    }
+}
+
+#[cfg(test)]
+mod cancel_tests {
+    use super::*;
+
+    #[test]
+    fn shared_between_clones() {
+        let cancellation = Cancellation::default();
+        let other = cancellation.clone();
+        assert!(!other.is_cancelled());
+        cancellation.cancel();
+        assert!(other.is_cancelled());
+        assert!(!Cancellation::default().is_cancelled());
+    }
+
+    #[test]
+    fn stopped() {
+        let cancellation = Cancellation::default();
+        let other = cancellation.clone();
+        cancellation.stop();
+        assert!(other.is_stopped());
+        assert!(!other.is_cancelled());
+
+        // cancelled runs don't start more files either
+        let cancellation = Cancellation::default();
+        cancellation.cancel();
+        assert!(cancellation.is_stopped());
+    }
+
+    #[test]
+    fn paused() {
+        let cancellation = Cancellation::default();
+        assert!(cancellation.wait_while_paused() < PAUSE_POLL_INTERVAL);
+        cancellation.pause();
+        let other = cancellation.clone();
+        let resumer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            other.resume();
+        });
+        let waited = cancellation.wait_while_paused();
+        resumer.join().unwrap();
+        assert!(waited >= Duration::from_millis(300));
+        assert!(!cancellation.is_paused());
+
+        // stopping a paused run ends the pause, so it can finish its archive
+        cancellation.pause();
+        cancellation.stop();
+        assert!(cancellation.wait_while_paused() < PAUSE_POLL_INTERVAL);
+        assert!(cancellation.is_paused());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stop_signal() {
+        let cancellation = Cancellation::default();
+        install_stop_handler(cancellation.clone());
+        signal_hook::low_level::raise(signal_hook::consts::SIGUSR1).unwrap();
+        assert!(cancellation.is_stopped());
+        assert!(!cancellation.is_cancelled());
+    }
+}
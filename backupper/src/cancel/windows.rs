@@ -0,0 +1,40 @@
+//! Contains the windows implementation of the stop signal, which is `CTRL_BREAK_EVENT`
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, OnceLock,
+};
+
+use windows::Win32::{
+    Foundation::{BOOL, FALSE, TRUE},
+    System::Console::{SetConsoleCtrlHandler, CTRL_BREAK_EVENT},
+};
+
+/// Flag that is set on `CTRL_BREAK_EVENT`
+static STOP_FLAG: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// Sets the [STOP_FLAG] on `CTRL_BREAK_EVENT` and passes all other events on to the next handler, e.g., the Ctrl-C handler.
+unsafe extern "system" fn handle_ctrl_event(ctrl_type: u32) -> BOOL {
+    match STOP_FLAG.get() {
+        Some(flag) if ctrl_type == CTRL_BREAK_EVENT => {
+            flag.store(true, Ordering::SeqCst);
+            TRUE
+        }
+        _ => FALSE,
+    }
+}
+
+/// Sets `flag` when the process receives `CTRL_BREAK_EVENT`.
+/// Handlers are called from the latest one registered, so this one has to be registered after the Ctrl-C handler.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the handler couldn't be registered.
+pub fn register_stop_flag(flag: Arc<AtomicBool>) -> Result<(), String> {
+    if STOP_FLAG.set(flag).is_err() {
+        return Err(String::from("the stop signal is already handled"));
+    }
+    match unsafe { SetConsoleCtrlHandler(Some(handle_ctrl_event), true) }.as_bool() {
+        true => Ok(()),
+        false => Err(String::from("SetConsoleCtrlHandler failed")),
+    }
+}
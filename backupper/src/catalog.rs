@@ -0,0 +1,98 @@
+//! Contains rebuilding the catalog of a profile (see [config::backup_catalog]) from its archives, e.g., when it was lost or is stale.
+
+use std::{collections::HashMap, fs::File, io, path::Path};
+
+use config::{
+    archive_manifest::ARCHIVE_MANIFEST_NAME,
+    backup_catalog::{write_catalog, CatalogEntry},
+    profile_config::ProfileConfig,
+};
+use log::{debug, warn};
+use sha2::{Digest, Sha256};
+use zip::ZipArchive;
+
+use crate::{
+    common::{archive_parts, list_archives, original_path, read_manifest, SNAPSHOT_FILE_NAME, UNSTABLE_FILE_NAME},
+    contents::modified_time,
+    encryption::readable_archive,
+    restore::{S_IFLNK, S_IFMT},
+};
+
+/// Lists the files stored in the archive of the given [ProfileConfig] at `path`, which was created at `archived`, with their hashes.
+/// Files that were archived more than once, e.g., because they were retried, are only listed with their last version. Links aren't listed.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the archive couldn't be decrypted or read.
+pub fn archive_entries(profile_config: &ProfileConfig, path: &Path, archived: chrono::NaiveDateTime) -> Result<Vec<CatalogEntry>, String> {
+    let mut entries: Vec<CatalogEntry> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for part in archive_parts(&path.to_path_buf()) {
+        let readable = readable_archive(profile_config, &part)?;
+        let file = File::open(readable.path()).map_err(|e| format!("Couldn't open {:?} because of {:?}", part, e))?;
+        let mut zip = ZipArchive::new(file).map_err(|e| format!("Couldn't read {:?} because of {:?}", part, e))?;
+        let manifest = read_manifest(&mut zip);
+        for i in 0..zip.len() {
+            let mut entry = zip
+                .by_index(i)
+                .map_err(|e| format!("Couldn't read entry {} of {:?} because of {:?}", i, part, e))?;
+            if entry.is_dir()
+                || entry.unix_mode().map_or(false, |mode| mode & S_IFMT == S_IFLNK)
+                || [SNAPSHOT_FILE_NAME, UNSTABLE_FILE_NAME, ARCHIVE_MANIFEST_NAME].contains(&entry.name())
+            {
+                continue;
+            }
+            let original = match original_path(manifest.as_ref(), entry.name()) {
+                Some(original) => original,
+                None => {
+                    debug!("Skip {:?} because its root isn't listed in the manifest", entry.name());
+                    continue;
+                }
+            };
+            let mut hasher = Sha256::new();
+            io::copy(&mut entry, &mut hasher)
+                .map_err(|e| format!("Couldn't read entry {:?} of {:?} because of {:?}", entry.name(), part, e))?;
+            let catalog_entry = CatalogEntry {
+                path: original.clone(),
+                size: entry.size(),
+                modified: modified_time(entry.last_modified()),
+                sha256: format!("{:x}", hasher.finalize()),
+                archive: path.to_path_buf(),
+                archived,
+                profile_uuid: *profile_config.get_uuid(),
+            };
+            match index.get(&original) {
+                Some(&i) => entries[i] = catalog_entry,
+                None => {
+                    index.insert(original, entries.len());
+                    entries.push(catalog_entry);
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Replaces the catalog of the given [ProfileConfig] in the profile config dir at `dir` with the files of all its archives.
+/// Archives that can't be read are skipped with a warning, so one broken archive doesn't keep the others out of the catalog.
+///
+/// # Returns
+/// The number of archives that were added to the catalog.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the archives couldn't be listed or the catalog couldn't be written.
+pub fn rebuild(profile_config: &ProfileConfig, dir: &Path) -> Result<usize, String> {
+    let mut entries = vec![];
+    let mut added = 0;
+    for (created, archive) in list_archives(profile_config)? {
+        match archive_entries(profile_config, &archive, created) {
+            Ok(archived) => {
+                entries.extend(archived);
+                added += 1;
+            }
+            Err(msg) => warn!("Skipping {:?} in the catalog: {}", archive, msg),
+        }
+    }
+    write_catalog(dir, profile_config.get_uuid(), &entries)
+        .map_err(|e| format!("Couldn't write the catalog of {:?} because of {:?}", profile_config.name, e))?;
+    Ok(added)
+}
@@ -1,17 +1,25 @@
+use std::{path::PathBuf, str::FromStr};
+
 use chrono::NaiveDateTime;
-use clap::{ ArgGroup, Parser, Subcommand };
+use clap::{ error::ErrorKind, ArgGroup, CommandFactory, Parser, Subcommand };
+
+use ::config::profile_config::ProfilePreset;
 
-use crate::config::ProfileSpecifier;
+use crate::{
+    config::{ProfileSpecifier, GENERAL_CONFIG_PATH, LOGGER_CONFIG_PATH},
+    restore::ConflictPolicy,
+};
 
 /// Crate for creating and restoring backups.
 /// 
-/// Allows to only check for specific Backup Profiles if either the name or the uuid are provided, or checks all of them, if nothing is provided.
+/// Allows to only check for specific Backup Profiles if either the name or the uuid are provided, or checks all of them, if `--all` is provided.
+/// One of them is required, unless an archive is restored without its profile (see [Args::check_selection]).
 #[derive(Parser)]
 #[command(author, version, about, long_about)]
 #[command(group(
     ArgGroup::new("id")
-        .required(true)
-        .args(["name", "uuid"]),
+        .multiple(true)
+        .args(["name", "uuid", "all", "tag"]),
 ))]
 #[command(propagate_version = true)]
 pub struct Args {
@@ -19,25 +27,45 @@ pub struct Args {
     pub command: Commands,
 
     /// Path to general config file.
-    #[arg(short, long, default_value_t = String::from("./general_config.json"))]
+    #[arg(short, long, default_value_t = String::from(GENERAL_CONFIG_PATH))]
     pub general_config: String,
+
+    /// Fail if the general config file doesn't exist instead of creating a default one.
+    #[arg(long)]
+    pub no_create: bool,
         
     /// Path to the logger config YAML-file.
-    #[arg(long, default_value_t = String::from("./main_logging_conf.yaml"))]
+    #[arg(long, default_value_t = String::from(LOGGER_CONFIG_PATH))]
     pub logger_config: String,
 
     /// Name of Profile to check.
-    #[arg(short, long)]
+    #[arg(short, long, conflicts_with_all = ["uuid", "all"])]
     pub name: Option<String>,
 
     /// Uuid of Profile to check.
-    #[arg(short, long)]
+    #[arg(short, long, conflicts_with = "all")]
     pub uuid: Option<String>,
 
+    /// Check all Profiles.
+    #[arg(long)]
+    pub all: bool,
+
+    /// Only check the Profiles with this tag. Can be combined with `--all`.
+    #[arg(long, conflicts_with_all = ["name", "uuid"])]
+    pub tag: Option<String>,
+
+    /// Operate on all Profiles matching the given name. Without this, a name matching more than one Profile is an error.
+    #[arg(long, requires = "name")]
+    pub all_matching: bool,
+
     /// Force backup even if it isn't due yet.
     #[arg(short)]
     pub force: bool,
 
+    /// Force a full backup, even if the Profile uses incremental backups.
+    #[arg(long)]
+    pub full: bool,
+
     /// Set to get verbose output
     #[arg(short, long)]
     pub verbose: bool,
@@ -56,6 +84,216 @@ pub enum Commands {
 
     /// Delete the given profile
     Delete(Delete),
+
+    /// Adopt the given profiles on this machine: recompute stale schedules and register them
+    Adopt,
+
+    /// Manage the archives of the given profile
+    Archives(Archives),
+
+    /// Explain why the interval of the given profile does or doesn't match a point in time
+    Explain(Explain),
+
+    /// Show who changed the given profile and when
+    Audit,
+
+    /// Delete the archives of the given profile that exceed its retention rules (`keep_last` and `keep_days`)
+    Prune,
+
+    /// Check the given profile for mistakes, e.g., a target dir inside an included dir
+    Validate,
+
+    /// Show when the last successful backup of the given profile was made and when the next one is due
+    Status,
+
+    /// Create a new profile without dirs whose interval never matches, optionally with a preset for its filters
+    Create(Create),
+
+    /// Create a copy of the given profile with a new uuid. The copy isn't scheduled until its first backup or reschedule
+    Duplicate(Duplicate),
+
+    /// Write the configs of the given profiles, the logs and the version info into one zip archive for bug reports
+    SupportBundle(SupportBundle),
+
+    /// List the backup archives of the given profiles with their creation dates and sizes, and whether they are incremental and have a report
+    ListBackups(ListBackups),
+
+    /// List the files stored in a backup archive of the given profile with their sizes and modification times, without extracting them
+    #[command(visible_alias = "list-contents")]
+    Show(Show),
+
+    /// Pause, resume or stop the running backup of the given profile, or show its state
+    Control(Control),
+
+    /// Find the archived versions of files in the catalogs of the given profiles, newest first
+    Find(Find),
+
+    /// Maintain the catalogs of the given profiles, which list the files in their archives
+    Catalog(Catalog),
+
+    /// Mount a backup archive of the given profile as read-only directory until Ctrl-C is pressed, so files can be opened without restoring them
+    Mount(Mount),
+}
+
+impl Commands {
+    /// Whether the command changes profiles, archives or running backups, so it mustn't guess which of several profiles with the same name was meant.
+    /// The other commands only read and operate on all matching profiles.
+    pub fn is_modifying(&self) -> bool {
+        match self {
+            Commands::Backup
+            | Commands::Restore(_)
+            | Commands::Reschedule
+            | Commands::Delete(_)
+            | Commands::Adopt
+            | Commands::Prune
+            | Commands::Create(_)
+            | Commands::Duplicate(_)
+            | Commands::Catalog(_) => true,
+            Commands::Archives(archives) => !matches!(archives.command, ArchivesCommands::Upgrade { dry_run: true }),
+            Commands::Control(control) => control.command != ControlCommands::Status,
+            Commands::Explain(_)
+            | Commands::Audit
+            | Commands::Validate
+            | Commands::Status
+            | Commands::SupportBundle(_)
+            | Commands::ListBackups(_)
+            | Commands::Show(_)
+            | Commands::Find(_)
+            | Commands::Mount(_) => false,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Control {
+    #[command(subcommand)]
+    pub command: ControlCommands,
+}
+
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlCommands {
+    /// Pause the backup after the current file until it is resumed
+    Pause,
+    /// Continue a paused backup
+    Resume,
+    /// Show whether the backup is running, paused or stopping
+    Status,
+    /// Finish the current file and keep the files archived until then as a partial archive
+    Stop,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Find {
+    /// Part of the path to look for, e.g. "thesis.docx", or a glob like "*/Documents/*.docx". Case is ignored
+    pub pattern: String,
+
+    /// Only list this many versions
+    #[arg(long)]
+    pub limit: Option<usize>,
+
+    /// Print the versions as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Catalog {
+    #[command(subcommand)]
+    pub command: CatalogCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CatalogCommands {
+    /// Rebuild the catalog from the archives of the profile, e.g. when it was lost or lists pruned archives
+    Rebuild,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Mount {
+    /// Path of the archive to mount, e.g. one from `list-backups`
+    #[arg(long)]
+    pub archive: PathBuf,
+
+    /// Empty or missing directory to mount the archive at
+    #[arg(long)]
+    pub at: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Create {
+    /// Name of the new profile. Mustn't be taken by another profile
+    pub new_name: String,
+
+    /// Quick setup of the filters: "documents", "full-folder" or "photos"
+    #[arg(long, value_parser = ProfilePreset::from_str)]
+    pub preset: Option<ProfilePreset>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Duplicate {
+    /// Name of the copy. Mustn't be taken by another profile
+    #[arg(long)]
+    pub new_name: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ListBackups {
+    /// Print the list as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Show {
+    /// Format: "YYYY-MM-DD HH:MM". Show the latest archive made before this time. If not set, the latest archive is shown.
+    #[arg(short, long, value_parser = valid_time_format, conflicts_with = "archive")]
+    pub timestamp: Option<NaiveDateTime>,
+
+    /// Path of the archive to show, e.g. one from `list-backups`
+    #[arg(long)]
+    pub archive: Option<PathBuf>,
+
+    /// Only list the files whose original path matches this glob, e.g. "*.jpg" or "C:/Users/me/Pictures/**"
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// Print the listing as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct SupportBundle {
+    /// Path of the zip archive to write
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// Replace all path components beyond the root by hashes. The logs are left out in that case.
+    #[arg(long)]
+    pub redact: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Explain {
+    /// Format: "YYYY-MM-DD HH:MM". Point in time to check. If not set, the current time is chosen.
+    #[arg(long, value_parser = valid_time_format)]
+    pub at: Option<NaiveDateTime>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Archives {
+    #[command(subcommand)]
+    pub command: ArchivesCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ArchivesCommands {
+    /// Rewrite old archives of the profile into the current layout
+    Upgrade {
+        /// Only report the layout of each archive without rewriting it
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[derive(clap::Args, Debug)]
@@ -63,6 +301,51 @@ pub struct Restore {
     /// Format: "YYYY-MM-DD HH:MM". Timestamp that has to be preceeded by the backup. If not set, the latest backup is chosen.
     #[arg(short, long, value_parser = valid_time_format)]
     pub timestamp: Option<NaiveDateTime>,
+
+    /// Also overwrite files that were modified after their archived version, even in interactive restores.
+    #[arg(long)]
+    pub force_older: bool,
+
+    /// Never overwrite files that were modified after their archived version. This is the default for interactive restores, e.g., from a terminal.
+    #[arg(long, conflicts_with = "force_older")]
+    pub protect_newer: bool,
+
+    /// Only list which files would be restored and whether they are missing, identical or different, without writing anything.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Print the list of the dry run as JSON.
+    #[arg(long, requires = "dry_run")]
+    pub json: bool,
+
+    /// What happens to files that already exist. Defaults to "overwrite", which will change to "newer" in a future version.
+    #[arg(long, value_enum)]
+    pub on_conflict: Option<ConflictPolicy>,
+
+    /// Only restore the files below this path, e.g. "C:/Users/me/Pictures/2022". Can be given several times.
+    #[arg(long = "subtree", visible_alias = "path")]
+    pub subtrees: Vec<PathBuf>,
+
+    /// Remove the subtree from the restored paths, so its content lands directly in the output dir.
+    #[arg(long, requires_all = ["subtrees", "output_dir"])]
+    pub strip_prefix: bool,
+
+    /// Restore into this directory instead of the original locations, e.g. to inspect a backup without touching the live files.
+    /// The entries keep their paths below it and never leave it, even if they contain "..".
+    #[arg(long, visible_alias = "destination")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Restore this archive without looking up its profile, e.g. because the profile config is lost. No profile has to be selected in that case.
+    #[arg(long, conflicts_with_all = ["timestamp", "dry_run"])]
+    pub archive: Option<PathBuf>,
+}
+
+impl Restore {
+    /// Whether files that were modified after their archived version are kept: always with `--protect-newer`,
+    /// never with `--force-older` and otherwise only for `interactive` restores.
+    pub fn protects_newer(&self, interactive: bool) -> bool {
+        !self.force_older && (self.protect_newer || interactive)
+    }
 }
 
 #[derive(clap::Args, Debug)]
@@ -70,6 +353,10 @@ pub struct Delete {
     /// Remove the already created backup files as well
     #[arg(short, long)]
     pub remove_backups: bool,
+
+    /// Also remove the backup files that were created before the profile got a new uuid
+    #[arg(long, requires = "remove_backups")]
+    pub include_former: bool,
 }
 
 /// Checks that the provided string is in format `YYYY-MM-DD HH:MM`.
@@ -90,9 +377,193 @@ impl ProfileSpecifier for Args {
     fn uuid(&self) -> Option<&str> {
         self.uuid.as_ref().map(|uuid| uuid.as_str())
     }
+
+    fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+}
+
+impl Args {
+    /// Checks that the profiles to operate on are selected, which isn't needed to restore an explicit archive or create a profile.
+    ///
+    /// # Errors
+    /// Returns a [clap::Error] explaining the missing arguments otherwise.
+    pub fn check_selection(&self) -> Result<(), clap::Error> {
+        let selected = self.name.is_some() || self.uuid.is_some() || self.all || self.tag.is_some();
+        let archive = matches!(&self.command, Commands::Restore(restore) if restore.archive.is_some());
+        let create = matches!(self.command, Commands::Create(_));
+        if selected || archive || create {
+            return Ok(());
+        }
+        Err(Args::command().error(
+            ErrorKind::MissingRequiredArgument,
+            "one of --name, --uuid, --all or --tag is required, unless `restore --archive` or `create` is used",
+        ))
+    }
 }
 
 /// Parses cli-args and returns them.
 pub fn get_args() -> Args {
-    Args::parse()
+    let args = Args::parse();
+    if let Err(err) = args.check_selection() {
+        err.exit();
+    }
+    args
+}
+
+#[cfg(test)]
+mod cli_args_tests {
+    use super::*;
+
+    #[test]
+    fn tag_selection() {
+        let args = Args::parse_from(["backupper", "--tag", "work", "backup"]);
+        assert_eq!(args.tag(), Some("work"));
+        assert!(Args::try_parse_from(["backupper", "--all", "--tag", "work", "backup"]).is_ok());
+
+        assert!(Args::try_parse_from(["backupper", "--name", "Hutzi", "--tag", "work", "backup"]).is_err());
+        assert!(Args::try_parse_from(["backupper", "--uuid", "abc", "--tag", "work", "backup"]).is_err());
+    }
+
+    #[test]
+    fn duplicate_requires_new_name() {
+        assert!(Args::try_parse_from(["backupper", "--name", "Hutzi", "duplicate"]).is_err());
+        let args = Args::parse_from(["backupper", "--name", "Hutzi", "duplicate", "--new-name", "Hutzi 2"]);
+        match args.command {
+            Commands::Duplicate(duplicate) => assert_eq!(duplicate.new_name, "Hutzi 2"),
+            command => panic!("Expected duplicate, got {:?}", command),
+        }
+    }
+
+    #[test]
+    fn control_args() {
+        assert!(Args::try_parse_from(["backupper", "-u", "0f5e7b0c-9a5e-4f0a-8d63-4d3a5b8c2e11", "control"]).is_err());
+        assert!(Args::try_parse_from(["backupper", "-u", "0f5e7b0c-9a5e-4f0a-8d63-4d3a5b8c2e11", "control", "restart"]).is_err());
+        for (name, command) in [
+            ("pause", ControlCommands::Pause),
+            ("resume", ControlCommands::Resume),
+            ("status", ControlCommands::Status),
+            ("stop", ControlCommands::Stop),
+        ] {
+            let args = Args::parse_from(["backupper", "-u", "0f5e7b0c-9a5e-4f0a-8d63-4d3a5b8c2e11", "control", name]);
+            match args.command {
+                Commands::Control(control) => assert_eq!(control.command, command),
+                command => panic!("Expected control, got {:?}", command),
+            }
+        }
+    }
+
+    #[test]
+    fn find_args() {
+        assert!(Args::try_parse_from(["backupper", "--all", "find"]).is_err());
+        let args = Args::parse_from(["backupper", "--all", "find", "thesis.docx", "--limit", "3"]);
+        match args.command {
+            Commands::Find(find) => {
+                assert_eq!(find.pattern, "thesis.docx");
+                assert_eq!(find.limit, Some(3));
+                assert!(!find.json);
+            }
+            command => panic!("Expected find, got {:?}", command),
+        }
+        let args = Args::parse_from(["backupper", "--name", "Hutzi", "catalog", "rebuild"]);
+        assert!(matches!(args.command, Commands::Catalog(Catalog { command: CatalogCommands::Rebuild })));
+    }
+
+    #[test]
+    fn mount_args() {
+        assert!(Args::try_parse_from(["backupper", "--name", "Hutzi", "mount", "--at", "M:/"]).is_err());
+        let args = Args::parse_from(["backupper", "--name", "Hutzi", "mount", "--archive", "D:/backup.zip", "--at", "M:/"]);
+        match args.command {
+            Commands::Mount(mount) => {
+                assert_eq!(mount.archive, PathBuf::from("D:/backup.zip"));
+                assert_eq!(mount.at, PathBuf::from("M:/"));
+            }
+            command => panic!("Expected mount, got {:?}", command),
+        }
+    }
+
+    #[test]
+    fn restore_destination() {
+        let args = Args::parse_from(["backupper", "--all", "restore", "--destination", "C:/restore-test", "--path", "C:/a", "--subtree", "C:/b"]);
+        match args.command {
+            Commands::Restore(restore) => {
+                assert_eq!(restore.output_dir, Some(PathBuf::from("C:/restore-test")));
+                assert_eq!(restore.subtrees, vec![PathBuf::from("C:/a"), PathBuf::from("C:/b")]);
+            }
+            command => panic!("Expected restore, got {:?}", command),
+        }
+    }
+
+    #[test]
+    fn protect_newer() {
+        let restore = |args: &[&str]| match Args::parse_from([&["backupper", "--all", "restore"], args].concat()).command {
+            Commands::Restore(restore) => restore,
+            command => panic!("Expected restore, got {:?}", command),
+        };
+        assert!(restore(&[]).protects_newer(true));
+        assert!(!restore(&[]).protects_newer(false));
+        assert!(restore(&["--protect-newer"]).protects_newer(false));
+        assert!(!restore(&["--force-older"]).protects_newer(true));
+        assert!(Args::try_parse_from(["backupper", "--all", "restore", "--protect-newer", "--force-older"]).is_err());
+    }
+
+    #[test]
+    fn full_flag() {
+        assert!(!Args::parse_from(["backupper", "--all", "backup"]).full);
+        assert!(Args::parse_from(["backupper", "--all", "--full", "backup"]).full);
+    }
+
+    #[test]
+    fn restore_archive_without_profile() {
+        let args = Args::parse_from(["backupper", "restore", "--archive", "D:/old/backup.zip", "--destination", "C:/restore-test"]);
+        assert!(args.check_selection().is_ok());
+        match args.command {
+            Commands::Restore(restore) => assert_eq!(restore.archive, Some(PathBuf::from("D:/old/backup.zip"))),
+            command => panic!("Expected restore, got {:?}", command),
+        }
+        assert!(Args::parse_from(["backupper", "restore"]).check_selection().is_err());
+        assert!(Args::try_parse_from(["backupper", "restore", "--archive", "a.zip", "--timestamp", "2023-01-01 10:00"]).is_err());
+    }
+
+    #[test]
+    fn create_with_preset() {
+        let args = Args::parse_from(["backupper", "create", "Docs", "--preset", "documents"]);
+        assert!(args.check_selection().is_ok());
+        assert!(args.command.is_modifying());
+        match args.command {
+            Commands::Create(create) => {
+                assert_eq!(create.new_name, "Docs");
+                assert_eq!(create.preset, Some(ProfilePreset::Documents));
+            }
+            command => panic!("Expected create, got {:?}", command),
+        }
+        match Args::parse_from(["backupper", "create", "Pics", "--preset", "Full-Folder"]).command {
+            Commands::Create(create) => assert_eq!(create.preset, Some(ProfilePreset::FullFolder)),
+            command => panic!("Expected create, got {:?}", command),
+        }
+        assert!(Args::try_parse_from(["backupper", "create", "Docs", "--preset", "music"]).is_err());
+    }
+
+    #[test]
+    fn modifying_commands() {
+        for command in ["backup", "reschedule", "delete", "prune"] {
+            assert!(Args::parse_from(["backupper", "-n", "Hutzi", command]).command.is_modifying(), "{}", command);
+        }
+        for command in ["status", "audit", "validate", "list-backups", "show", "explain"] {
+            assert!(!Args::parse_from(["backupper", "-n", "Hutzi", command]).command.is_modifying(), "{}", command);
+        }
+        assert!(Args::parse_from(["backupper", "-n", "Hutzi", "restore", "--dry-run"]).command.is_modifying());
+        assert!(Args::parse_from(["backupper", "-n", "Hutzi", "archives", "upgrade"]).command.is_modifying());
+        assert!(!Args::parse_from(["backupper", "-n", "Hutzi", "archives", "upgrade", "--dry-run"]).command.is_modifying());
+        assert!(Args::parse_from(["backupper", "-n", "Hutzi", "control", "stop"]).command.is_modifying());
+        assert!(!Args::parse_from(["backupper", "-n", "Hutzi", "control", "status"]).command.is_modifying());
+    }
+
+    #[test]
+    fn exclusive_ids() {
+        assert!(Args::parse_from(["backupper", "backup"]).check_selection().is_err());
+        assert!(Args::try_parse_from(["backupper", "--name", "Hutzi", "--uuid", "abc", "backup"]).is_err());
+        assert!(Args::try_parse_from(["backupper", "--name", "Hutzi", "--all", "backup"]).is_err());
+        assert!(Args::try_parse_from(["backupper", "--uuid", "abc", "--all", "backup"]).is_err());
+    }
 }
\ No newline at end of file
@@ -1,6 +1,22 @@
 //! Contains utility functions that are used in different modules.
 
-use std::{path::PathBuf, fs};
+use std::{collections::HashSet, path::{Path, PathBuf}, fs, io::{Read, Seek}};
+
+use config::archive_manifest::{ArchiveManifest, ARCHIVE_MANIFEST_NAME};
+use log::warn;
+use zip::ZipArchive;
+
+pub use config::backup_inventory::{
+    archive_creation_date, archive_parts, archives_in_dir, is_incremental_archive, list_archives, part_file_name,
+    split_part_suffix, ARCHIVE_DATE_FORMAT, INCREMENTAL_SUFFIX,
+};
+
+/// Name of the archive entry listing the paths of all files that were backed up.
+pub const SNAPSHOT_FILE_NAME: &str = "SNAPSHOT.lst";
+
+/// Name of the archive entry listing the paths of all files that changed while they were backed up.
+/// Only present if there were such files.
+pub const UNSTABLE_FILE_NAME: &str = "UNSTABLE.lst";
 
 /// Checks if the target directory specified in [ProfileConfig] is writable or not.
 /// 
@@ -12,4 +28,134 @@ pub fn is_target_dir_available(dir_path: &PathBuf, is_writeable: bool) -> bool {
         Err(_) => false,
         Ok(metadata) => metadata.is_dir() && (!is_writeable || !metadata.permissions().readonly()),
     }
-}
\ No newline at end of file
+}
+
+/// Reads the entry with the given name that lists one entry name per line, like the [SNAPSHOT_FILE_NAME].
+///
+/// # Returns
+/// [None] if there is no such entry or it couldn't be read.
+pub fn read_list<R: Read + Seek>(zip: &mut ZipArchive<R>, entry_name: &str) -> Option<HashSet<String>> {
+    let mut content = String::new();
+    zip.by_name(entry_name).ok()?.read_to_string(&mut content).ok()?;
+    Some(content.lines().map(String::from).collect())
+}
+
+/// Reads the [ARCHIVE_MANIFEST_NAME] entry of the given archive.
+///
+/// # Returns
+/// [None] if there is no such entry (i.e., the archive was written by an older version) or it couldn't be read.
+pub fn read_manifest<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Option<ArchiveManifest> {
+    let mut content = String::new();
+    zip.by_name(ARCHIVE_MANIFEST_NAME).ok()?.read_to_string(&mut content).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(manifest) => Some(manifest),
+        Err(err) => {
+            warn!("Couldn't parse the archive manifest because {:?}", err);
+            None
+        }
+    }
+}
+
+/// Determines the original path of the archive entry with the given name (see [ArchiveManifest::original_path]).
+/// Archives without a `manifest` were written by older versions, which used the original paths as entry names.
+pub fn original_path(manifest: Option<&ArchiveManifest>, entry_name: &str) -> Option<String> {
+    match manifest {
+        Some(manifest) => manifest.original_path(entry_name),
+        None => Some(String::from(entry_name)),
+    }
+}
+
+/// Returns the key two entry names or paths are compared by to find out if they collide.
+/// Windows doesn't distinguish the case of file names, so they are compared case-insensitively there.
+pub fn collision_key(name: &str) -> String {
+    if cfg!(windows) {
+        name.to_lowercase()
+    } else {
+        String::from(name)
+    }
+}
+
+/// Returns the extended-length form of `path` (e.g., `\\?\C:\Users\...`) on Windows, so files deeper than 260 characters can be accessed.
+/// Relative paths are made absolute and `.` and `..` are resolved. Paths that already are in that form or can't be converted are returned as they are.
+/// Only use the returned path to access the file system. Entry names and log messages keep the path as it was given.
+///
+/// Other platforms don't limit the length of paths, so `path` is returned as it is there.
+pub fn long_path(path: &Path) -> PathBuf {
+    #[cfg(target_family = "windows")]
+    {
+        use std::path::{Component, Prefix};
+
+        let absolute = match std::path::absolute(path) {
+            Ok(absolute) => absolute,
+            Err(_) => return path.to_path_buf(),
+        };
+        let mut components = absolute.components();
+        let mut long = match components.next() {
+            Some(Component::Prefix(prefix)) => match prefix.kind() {
+                Prefix::Disk(letter) => PathBuf::from(format!(r"\\?\{}:\", letter as char)),
+                // `\\server\share` becomes `\\?\UNC\server\share`
+                Prefix::UNC(server, share) => PathBuf::from(r"\\?\UNC\").join(server).join(share),
+                _ => return absolute,
+            },
+            _ => return absolute,
+        };
+        long.extend(components.filter(|component| matches!(component, Component::Normal(_))));
+        long
+    }
+    #[cfg(not(target_family = "windows"))]
+    path.to_path_buf()
+}
+
+#[cfg(test)]
+mod common_tests {
+    use std::fs::File;
+
+    use config::{interval::IntervalBuilder, profile_config::ProfileConfig};
+
+    use super::*;
+
+    fn archive_test_config(target_dir: &str) -> ProfileConfig {
+        ProfileConfig::new(
+            String::from("archives"),
+            PathBuf::from(target_dir),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        )
+    }
+
+    #[test]
+    fn collision_keys() {
+        assert_eq!(collision_key("C/a.txt"), collision_key("C/a.txt"));
+        assert_ne!(collision_key("C/a.txt"), collision_key("C/b.txt"));
+        assert_eq!(collision_key("C/Foo.txt") == collision_key("c/foo.txt"), cfg!(windows));
+    }
+
+    #[test]
+    fn list_archives_sorted() {
+        let dir = "./test_dir/list_archives_tmp";
+        fs::create_dir_all(dir).unwrap();
+        let profile_config = archive_test_config(dir);
+        let uuid = profile_config.get_uuid().as_hyphenated().to_string();
+        for name in [
+            format!("{}_2023-05-01_10-00.zip", uuid),
+            format!("{}_2022-05-01_10-00.zip", uuid),
+            format!("{}_2023-01-01_10-00.zip", uuid),
+            String::from("unrelated.zip"),
+        ] {
+            File::create(PathBuf::from(dir).join(name)).unwrap();
+        }
+
+        let archives = list_archives(&profile_config);
+        fs::remove_dir_all(dir).unwrap();
+
+        let dates: Vec<String> = archives
+            .unwrap()
+            .iter()
+            .map(|(date_time, _)| date_time.format(ARCHIVE_DATE_FORMAT).to_string())
+            .collect();
+        assert_eq!(dates, vec!["2022-05-01_10-00", "2023-01-01_10-00", "2023-05-01_10-00"]);
+    }
+}
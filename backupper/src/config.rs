@@ -1,38 +1,60 @@
 //! Contains functions and methods for loading and managing configs.
 
-use std::{ffi::OsStr, fs, io::Error, path::PathBuf};
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
 
-use config::{general_config::GeneralConfig, profile_config::ProfileConfig};
+use config::{general_config::GeneralConfig, profile_config::{ConfigFormat, ProfileConfig}, Error};
+use log::{info, warn};
 
-const GENERAL_CONFIG_PATH: &'static str = "./general_config.json";
+/// Default path of the general config file.
+pub const GENERAL_CONFIG_PATH: &'static str = "./general_config.json";
+
+/// Default path of the logger config file.
+pub const LOGGER_CONFIG_PATH: &'static str = "./main_logging_conf.yaml";
 
 /// Loads the general config file.
 /// Either from the provided `path` or from [GENERAL_CONFIG_PATH] if `path` is [None]
 ///
+/// If the file doesn't exist and `create` is set, the default config and its profile dir are created (see [GeneralConfig::create_default]).
+///
 /// # Returns
 /// [Result::Ok] containing the config if it could be loaded.
-/// [Result::Err] containing a [String] describing the issue if some occured.
-pub fn load_general_config(path: Option<&str>) -> Result<GeneralConfig, String> {
-    let path = PathBuf::from(path.unwrap_or(GENERAL_CONFIG_PATH));
-
-    match path.try_exists() {
-        Ok(val) => {
-            if !val {
-                return Err(String::from("General config file doesn't exist!"));
-            }
+/// [Result::Err] containing an [Error] describing the issue if some occured. A missing file is reported as [Error::Io] of kind [ErrorKind::NotFound].
+pub fn load_general_config(path: Option<&str>, create: bool) -> Result<GeneralConfig, Error> {
+    let path = Path::new(path.unwrap_or(GENERAL_CONFIG_PATH));
+
+    if !path.try_exists()? {
+        if create {
+            let (config, created) = GeneralConfig::create_default(path)?;
+            info!("General config {:?} didn't exist. Created {:?}", path, created);
+            return Ok(config);
         }
-        Err(e) => return Err(e.to_string()),
+        return Err(Error::Io(io::Error::new(
+            ErrorKind::NotFound,
+            "General config file doesn't exist!",
+        )));
     }
 
     if !path.is_file() {
-        return Err(String::from(
+        return Err(Error::Io(io::Error::new(
+            ErrorKind::InvalidInput,
             "Path to general config file doesn't point to a file!",
-        ));
+        )));
     }
 
-    match GeneralConfig::read(&path) {
-        Err(e) => Err(e.to_string()),
-        Ok(config) => Ok(config),
+    GeneralConfig::read(path)
+}
+
+/// Returns the exit code matching the kind of the given [Error], e.g., [exitcode::NOINPUT] for a missing file or [exitcode::CONFIG] for an invalid one.
+pub fn exit_code(err: &Error) -> exitcode::ExitCode {
+    match err {
+        Error::Io(err) if err.kind() == ErrorKind::NotFound => exitcode::NOINPUT,
+        Error::Io(_) => exitcode::IOERR,
+        Error::Parse(_) | Error::InvalidInterval(_) | Error::InvalidSpecifierRange(_) => exitcode::CONFIG,
+        Error::ReadOnly(_) => exitcode::NOPERM,
     }
 }
 
@@ -63,14 +85,19 @@ fn valid_dir_path(path: &PathBuf) -> Result<(), String> {
 
 /// Trait for matching [ProfileConfig]s.
 /// For a [ProfileConfig] to match, either its `name` must match what is returned by [ProfileSpecifier::name],
-/// or its `uuid` has to match what is returned by [ProfileSpecifier::uuid].
+/// or its `uuid` has to match what is returned by [ProfileSpecifier::uuid],
+/// or it has to have the tag returned by [ProfileSpecifier::tag].
 pub trait ProfileSpecifier {
     fn name(&self) -> Option<&str>;
 
     fn uuid(&self) -> Option<&str>;
+
+    fn tag(&self) -> Option<&str> {
+        None
+    }
 }
 
-/// Checks if the given [ProfileConfig] either has the `name` or the `uuid` specified in `args`.
+/// Checks if the given [ProfileConfig] has the `name`, the `uuid` and the tag specified in `args`, as far as they are specified.
 ///
 /// # Returns
 /// Given [ProfileConfig] if it does.
@@ -97,13 +124,46 @@ fn matches_specifier<T: ProfileSpecifier>(
         None => uuid_match = true,
     };
 
-    if name_match && uuid_match {
+    let tag_match = specifier
+        .tag()
+        .map_or(true, |tag| profile_conf.has_tag(tag));
+
+    if name_match && uuid_match && tag_match {
         Some(profile_conf)
     } else {
         None
     }
 }
 
+/// Checks that the `specifier` doesn't select several [ProfileConfig]s by their name.
+/// Selecting by uuid or selecting all profiles is never ambiguous.
+///
+/// # Errors
+/// Returns an [Err] listing the uuids of the matching `profile_configs` if the name matched more than one of them.
+pub fn check_unambiguous<T: ProfileSpecifier>(
+    profile_configs: &[ProfileConfig],
+    specifier: &T,
+) -> Result<(), String> {
+    let name = match (specifier.name(), specifier.uuid()) {
+        (Some(name), None) => name,
+        _ => return Ok(()),
+    };
+    if profile_configs.len() <= 1 {
+        return Ok(());
+    }
+
+    let uuids: Vec<String> = profile_configs
+        .iter()
+        .map(|profile_config| profile_config.get_uuid().as_hyphenated().to_string())
+        .collect();
+    Err(format!(
+        "The name {:?} matches {} profiles: {}. Select one with -u <UUID> or pass --all-matching.",
+        name,
+        profile_configs.len(),
+        uuids.join(", ")
+    ))
+}
+
 /// Loads profile configs from the specification in the provided [GeneralConfig].
 ///
 /// Only returns those [ProfileConfig]s that match the `name` or the `uuid` given in `cli_args`. If both are [None], all found [ProfileConfig]s are returned.
@@ -130,14 +190,12 @@ pub fn soft_load_profile_configs<T: ProfileSpecifier>(
         .ok()
         .expect("This must be an ok!")
         .filter_map(|entry| {
-            // filter out bad entries or the ones that are no JSONs
+            // filter out bad entries or the ones that aren't in a config format
             if entry.is_err() {
                 return None;
             }
             let dir_entry = entry.as_ref().ok()?;
-            if dir_entry.path().extension().unwrap_or_default() != OsStr::new("json") {
-                return None;
-            }
+            ConfigFormat::from_path(&dir_entry.path())?;
 
             // load the profile
             matches_specifier(ProfileConfig::load(&entry.ok()?.path()).ok(), specifier)
@@ -172,14 +230,12 @@ pub fn hard_load_profile_configs<T: ProfileSpecifier>(
         .ok()
         .expect("This must be an ok!")
         .filter_map(|entry| {
-            // filter out bad entries or the ones that are no JSONs
+            // filter out bad entries or the ones that aren't in a config format
             if entry.is_err() {
                 return None;
             }
             let dir_entry = entry.as_ref().ok()?;
-            if dir_entry.path().extension().unwrap_or_default() != OsStr::new("json") {
-                return None;
-            }
+            ConfigFormat::from_path(&dir_entry.path())?;
 
             entry.ok()
         })
@@ -195,10 +251,127 @@ pub fn hard_load_profile_configs<T: ProfileSpecifier>(
     }
 }
 
+/// Resolves the paths of the given [ProfileConfig]s (see [ProfileConfig::resolve_paths]) for subcommands that don't store them.
+/// Profiles whose paths can't be resolved are skipped with a warning.
+pub fn resolve_paths(profile_configs: &[ProfileConfig]) -> Vec<ProfileConfig> {
+    profile_configs
+        .iter()
+        .filter_map(|profile_config| match profile_config.resolve_paths() {
+            Ok(resolved) => Some(resolved),
+            Err(msg) => {
+                warn!("Skipping profile: {}", msg);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Makes the given `path` absolute by joining it onto the current working dir, since the walked paths are absolute as well.
+fn absolute(path: PathBuf) -> PathBuf {
+    if path.is_absolute() {
+        return path;
+    }
+    match std::env::current_dir() {
+        Ok(dir) => dir.join(path),
+        Err(_) => path,
+    }
+}
+
+/// Reads the paths of the files written by the appenders configured in the logger config file at `logger_config`.
+/// Relative paths are resolved against the current working dir, like log4rs does.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the file couldn't be read or isn't valid YAML.
+pub fn log_file_paths(logger_config: &PathBuf) -> Result<Vec<PathBuf>, String> {
+    let content = match fs::read_to_string(logger_config) {
+        Ok(content) => content,
+        Err(err) => return Err(format!("Error reading {:?}: {:?}", logger_config, err)),
+    };
+    let yaml: serde_yaml::Value = match serde_yaml::from_str(&content) {
+        Ok(yaml) => yaml,
+        Err(err) => return Err(format!("Couldn't parse {:?} because {:?}", logger_config, err)),
+    };
+
+    let appenders = match yaml.get("appenders").and_then(|appenders| appenders.as_mapping()) {
+        Some(appenders) => appenders,
+        None => return Ok(vec![]),
+    };
+    Ok(appenders
+        .values()
+        .filter_map(|appender| appender.get("path"))
+        .filter_map(|path| path.as_str())
+        .map(|path| absolute(PathBuf::from(path)))
+        .collect())
+}
+
+/// Resolves the paths of the program's own files, which are skipped by backups of profiles that don't set `include_self`.
+/// These are the general config file, the profile configs dir and the files of the log appenders.
+pub fn own_paths(general_config: &GeneralConfig, general_config_file: &str, logger_config: &str) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = general_config
+        .own_paths(&PathBuf::from(general_config_file))
+        .into_iter()
+        .map(absolute)
+        .collect();
+
+    match log_file_paths(&PathBuf::from(logger_config)) {
+        Ok(log_files) => paths.extend(log_files),
+        Err(msg) => warn!("Couldn't resolve the log files: {}", msg),
+    }
+    paths
+}
+
 #[cfg(test)]
 mod config_tests {
+    use config::general_config::GENERAL_CONFIG_VERSION;
+
     use super::*;
 
+    mod load_general_config_tests {
+        use super::*;
+
+        #[test]
+        fn missing_file() {
+            let err = load_general_config(Some("./test_dir/doesnt_exist.json"), false).unwrap_err();
+            assert_eq!(err.to_string(), "General config file doesn't exist!");
+            assert_eq!(exit_code(&err), exitcode::NOINPUT);
+        }
+
+        #[test]
+        fn invalid_file() {
+            let path = "./test_dir/invalid_general_config_tmp.json";
+            fs::write(path, "Hutzi").unwrap();
+            let err = load_general_config(Some(path), true).unwrap_err();
+            fs::remove_file(path).unwrap();
+            assert_eq!(exit_code(&err), exitcode::CONFIG);
+        }
+
+        #[test]
+        fn creates_missing_file() {
+            let dir = PathBuf::from("./test_dir/create_general_config_tmp");
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            let path = dir.join("general_config.json");
+
+            let config = load_general_config(path.to_str(), true).unwrap();
+            let stored = fs::read_to_string(&path);
+            let profile_dir_exists = dir.join("profile_configs").is_dir();
+            let reloaded = load_general_config(path.to_str(), false).unwrap();
+            fs::remove_dir_all(&dir).unwrap();
+
+            assert!(profile_dir_exists);
+            assert_eq!(config.profile_configs, dir.join("profile_configs"));
+            let stored: serde_json::Value = serde_json::from_str(&stored.unwrap()).unwrap();
+            assert_eq!(stored["profile_configs"], serde_json::json!(dir.join("profile_configs")));
+            assert_eq!(reloaded.profile_configs, config.profile_configs);
+        }
+
+        #[test]
+        fn read_only() {
+            let err = Error::ReadOnly(String::from("Hutzi"));
+            assert_eq!(exit_code(&err), exitcode::NOPERM);
+        }
+    }
+
     mod valid_dir_path_tests {
         use super::*;
 
@@ -236,11 +409,19 @@ mod config_tests {
     struct MockProfileSpecifier {
         name: Option<String>,
         uuid: Option<String>,
+        tag: Option<String>,
     }
 
     impl MockProfileSpecifier {
         pub fn new(name: Option<String>, uuid: Option<String>) -> MockProfileSpecifier {
-            MockProfileSpecifier { name, uuid }
+            MockProfileSpecifier { name, uuid, tag: None }
+        }
+
+        pub fn with_tag(tag: &str) -> MockProfileSpecifier {
+            MockProfileSpecifier {
+                tag: Some(String::from(tag)),
+                ..Self::with_none()
+            }
         }
 
         pub fn with_name(name: Option<String>) -> MockProfileSpecifier {
@@ -264,6 +445,10 @@ mod config_tests {
         fn uuid(&self) -> Option<&str> {
             self.uuid.as_ref().map(|uuid| uuid.as_str())
         }
+
+        fn tag(&self) -> Option<&str> {
+            self.tag.as_deref()
+        }
     }
 
     mod matches_specifier_tests {
@@ -293,6 +478,25 @@ mod config_tests {
             assert!(matches_specifier(Some(profile_config), &specifier).is_some());
         }
 
+        #[test]
+        fn tag_matching() {
+            let mut profile_config = ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from(""),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            );
+            profile_config.tags = vec![String::from("photos"), String::from("work")];
+
+            let specifier = MockProfileSpecifier::with_tag("work");
+            assert!(matches_specifier(Some(profile_config.clone()), &specifier).is_some());
+            let specifier = MockProfileSpecifier::with_tag("music");
+            assert!(matches_specifier(Some(profile_config), &specifier).is_none());
+        }
+
         #[test]
         fn name_not_matching() {
             let specifier = MockProfileSpecifier::with_name(Some(String::from("Flubo")));
@@ -345,6 +549,98 @@ mod config_tests {
         }
     }
 
+    mod check_unambiguous_tests {
+        use super::*;
+
+        fn hutzis() -> Vec<ProfileConfig> {
+            let config = GeneralConfig {
+                profile_configs: PathBuf::from("./test_dir/valid_profile_configs"),
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
+            };
+            let specifier = MockProfileSpecifier::with_name(Some(String::from("Hutzi")));
+            soft_load_profile_configs(&config, &specifier).unwrap()
+        }
+
+        #[test]
+        fn ambiguous_name() {
+            let configs = hutzis();
+            let specifier = MockProfileSpecifier::with_name(Some(String::from("Hutzi")));
+            let msg = check_unambiguous(&configs, &specifier).unwrap_err();
+
+            assert!(msg.contains("matches 3 profiles"));
+            for config in &configs {
+                assert!(msg.contains(&config.get_uuid().as_hyphenated().to_string()));
+            }
+        }
+
+        #[test]
+        fn unique_name() {
+            let configs = hutzis();
+            let specifier = MockProfileSpecifier::with_name(Some(String::from("Hutzi")));
+            assert!(check_unambiguous(&configs[..1], &specifier).is_ok());
+            assert!(check_unambiguous(&[], &specifier).is_ok());
+        }
+
+        #[test]
+        fn not_by_name() {
+            let configs = hutzis();
+            assert!(check_unambiguous(&configs, &MockProfileSpecifier::with_none()).is_ok());
+            let specifier = MockProfileSpecifier::new(
+                Some(String::from("Hutzi")),
+                Some(configs[0].get_uuid().as_hyphenated().to_string()),
+            );
+            assert!(check_unambiguous(&configs, &specifier).is_ok());
+        }
+    }
+
+    mod own_paths_tests {
+        use super::*;
+
+        #[test]
+        fn from_configured_paths() {
+            let logger_config = "./test_dir/own_paths_tmp.yaml";
+            fs::write(
+                logger_config,
+                "appenders:\n  stdout:\n    kind: console\n  file:\n    kind: file\n    path: \"somewhere/custom.log\"\n  rolling:\n    kind: rolling_file\n    path: \"log/rolling.log\"\nroot:\n  level: info\n",
+            )
+            .unwrap();
+            let general_config = GeneralConfig {
+                profile_configs: PathBuf::from("./some_profiles"),
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
+            };
+
+            let paths = own_paths(&general_config, "./my_general_config.json", logger_config);
+            fs::remove_file(logger_config).unwrap();
+
+            let cwd = std::env::current_dir().unwrap();
+            assert_eq!(paths.len(), 4);
+            assert!(paths.iter().all(|path| path.is_absolute()));
+            assert!(paths.contains(&cwd.join("./my_general_config.json")));
+            assert!(paths.contains(&cwd.join("./some_profiles")));
+            assert!(paths.contains(&cwd.join("somewhere/custom.log")));
+            assert!(paths.contains(&cwd.join("log/rolling.log")));
+        }
+
+        #[test]
+        fn missing_logger_config() {
+            assert!(log_file_paths(&PathBuf::from("./test_dir/doesnt_exist.yaml")).is_err());
+        }
+    }
+
     mod soft_load_profile_configs_tests {
         use super::*;
 
@@ -353,6 +649,14 @@ mod config_tests {
             let path = PathBuf::from("./hfgjdish/fhdjis");
             let config = GeneralConfig {
                 profile_configs: path,
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
             };
             let specifier = MockProfileSpecifier::with_none();
             assert!(soft_load_profile_configs(&config, &specifier).is_err());
@@ -360,6 +664,14 @@ mod config_tests {
             let path = PathBuf::from("./Cargo.toml");
             let config = GeneralConfig {
                 profile_configs: path,
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
             };
             assert!(soft_load_profile_configs(&config, &specifier).is_err());
         }
@@ -369,6 +681,14 @@ mod config_tests {
             let path = PathBuf::from("./test_dir/valid_profile_configs");
             let config = GeneralConfig {
                 profile_configs: path,
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
             };
             let specifier = MockProfileSpecifier::with_none();
             let configs = soft_load_profile_configs(&config, &specifier).unwrap();
@@ -381,6 +701,14 @@ mod config_tests {
             let path = PathBuf::from("./test_dir/valid_profile_configs");
             let config = GeneralConfig {
                 profile_configs: path,
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
             };
             let specifier = MockProfileSpecifier::with_name(Some(String::from("Hutzi")));
             let configs = soft_load_profile_configs(&config, &specifier).unwrap();
@@ -393,6 +721,14 @@ mod config_tests {
             let path = PathBuf::from("./test_dir/valid_profile_configs");
             let config = GeneralConfig {
                 profile_configs: path,
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
             };
             let specifier = MockProfileSpecifier::with_uuid(Some(String::from(
                 "6f41ec8a-da22-4e77-9a9c-50d18556375f",
@@ -407,6 +743,14 @@ mod config_tests {
             let path = PathBuf::from("./test_dir/invalid_profile_configs");
             let config = GeneralConfig {
                 profile_configs: path,
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
             };
             let specifier = MockProfileSpecifier::with_none();
             let configs = soft_load_profile_configs(&config, &specifier).unwrap();
@@ -423,6 +767,14 @@ mod config_tests {
             let path = PathBuf::from("./hfgjdish/fhdjis");
             let config = GeneralConfig {
                 profile_configs: path,
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
             };
             let specifier = MockProfileSpecifier::with_none();
             assert!(hard_load_profile_configs(&config, &specifier).is_err());
@@ -430,6 +782,14 @@ mod config_tests {
             let path = PathBuf::from("./Cargo.toml");
             let config = GeneralConfig {
                 profile_configs: path,
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
             };
             assert!(hard_load_profile_configs(&config, &specifier).is_err());
         }
@@ -439,6 +799,14 @@ mod config_tests {
             let path = PathBuf::from("./test_dir/valid_profile_configs");
             let config = GeneralConfig {
                 profile_configs: path,
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
             };
             let specifer = MockProfileSpecifier::with_none();
             let configs = hard_load_profile_configs(&config, &specifer).unwrap();
@@ -451,6 +819,14 @@ mod config_tests {
             let path = PathBuf::from("./test_dir/valid_profile_configs");
             let config = GeneralConfig {
                 profile_configs: path,
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
             };
             let specifier = MockProfileSpecifier::with_name(Some(String::from("Hutzi")));
             let configs = hard_load_profile_configs(&config, &specifier).unwrap();
@@ -463,6 +839,14 @@ mod config_tests {
             let path = PathBuf::from("./test_dir/valid_profile_configs");
             let config = GeneralConfig {
                 profile_configs: path,
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
             };
             let specifier = MockProfileSpecifier::with_uuid(Some(String::from(
                 "6f41ec8a-da22-4e77-9a9c-50d18556375f",
@@ -477,6 +861,14 @@ mod config_tests {
             let path = PathBuf::from("./test_dir/invalid_profile_configs");
             let config = GeneralConfig {
                 profile_configs: path,
+                max_history_entries: None,
+                max_sidecar_logs: None,
+                max_sidecar_age_days: None,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
             };
             let specifier = MockProfileSpecifier::with_none();
             let configs = hard_load_profile_configs(&config, &specifier);
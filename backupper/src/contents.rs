@@ -0,0 +1,187 @@
+//! Contains listing the entries of a backup archive without extracting them.
+
+use std::{collections::HashMap, fs::File, path::{Path, PathBuf}};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use config::{archive_manifest::ARCHIVE_MANIFEST_NAME, profile_config::ProfileConfig};
+use globset::{GlobBuilder, GlobMatcher};
+use log::debug;
+use zip::ZipArchive;
+
+use crate::{
+    common::{archive_parts, list_archives, original_path, read_manifest, SNAPSHOT_FILE_NAME, UNSTABLE_FILE_NAME},
+    encryption::readable_archive,
+};
+
+/// A file stored in a backup archive.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path the file was archived from
+    pub path: String,
+    /// Uncompressed size in bytes
+    pub size: u64,
+    /// Modification time of the file when it was archived, in local time. [None] if the archive holds an invalid date.
+    pub modified: Option<NaiveDateTime>,
+}
+
+impl ArchiveEntry {
+    /// Formats the entry as one line of the listing.
+    pub fn line(&self) -> String {
+        let modified = self
+            .modified
+            .map_or_else(|| String::from("unknown"), |modified| modified.format("%Y-%m-%d %H:%M:%S").to_string());
+        format!("{:<19}  {:>12}  {}", modified, self.size, self.path)
+    }
+
+    /// Converts the entry into one element of the `--json` listing.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "path": self.path,
+            "size": self.size,
+            "modified": self.modified.map(|modified| modified.format("%Y-%m-%dT%H:%M:%S").to_string()),
+        })
+    }
+}
+
+/// Converts the modification time of an archive entry, which is stored in local time. [None] if it is an invalid date.
+pub fn modified_time(date_time: zip::DateTime) -> Option<NaiveDateTime> {
+    NaiveDate::from_ymd_opt(date_time.year().into(), date_time.month().into(), date_time.day().into())
+        .and_then(|date| date.and_hms_opt(date_time.hour().into(), date_time.minute().into(), date_time.second().into()))
+}
+
+/// Compiles the `--filter` of the listing. `*` also matches `/`, so `*.jpg` matches the images in all directories.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if `pattern` isn't a valid glob.
+pub fn entry_filter(pattern: &str) -> Result<GlobMatcher, String> {
+    GlobBuilder::new(pattern)
+        .build()
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| format!("{:?} isn't a valid glob: {}", pattern, e))
+}
+
+/// Finds the archive of the given [ProfileConfig] that was the latest at the provided timestamp.
+/// Incremental archives only contain the files that changed since the previous archive.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the archives couldn't be listed or none of them is older than the timestamp.
+pub fn archive_at(profile_config: &ProfileConfig, timestamp: NaiveDateTime) -> Result<PathBuf, String> {
+    list_archives(profile_config)?
+        .into_iter()
+        .rev()
+        .find(|(creation_date, _)| creation_date <= &timestamp)
+        .map(|(_, path)| path)
+        .ok_or_else(|| format!("There is no archive of {:?} older than {}", profile_config.name, timestamp))
+}
+
+/// Calls `visit` for each file stored in the archive at `path` (and its other volumes, if it is split) whose original path is matched by `filter`.
+/// The entries are read one by one without being decompressed, so listing large archives doesn't need much memory.
+/// Metadata entries like the snapshot aren't listed, and files that were archived again after changing are only listed once.
+///
+/// # Returns
+/// The number of listed entries.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the archive couldn't be decrypted or read.
+pub fn list_contents(
+    profile_config: &ProfileConfig,
+    path: &Path,
+    filter: Option<&GlobMatcher>,
+    mut visit: impl FnMut(ArchiveEntry),
+) -> Result<usize, String> {
+    let mut listed = 0;
+    for part in archive_parts(&path.to_path_buf()) {
+        let readable = readable_archive(profile_config, &part)?;
+        let file = File::open(readable.path()).map_err(|e| format!("Couldn't open {:?} because of {:?}", part, e))?;
+        let mut zip = ZipArchive::new(file).map_err(|e| format!("Couldn't read {:?} because of {:?}", part, e))?;
+        let manifest = read_manifest(&mut zip);
+        let last_index: HashMap<String, usize> = (0..zip.len())
+            .filter_map(|i| Some((zip.by_index_raw(i).ok()?.name().to_string(), i)))
+            .collect();
+
+        for i in 0..zip.len() {
+            let entry = zip
+                .by_index_raw(i)
+                .map_err(|e| format!("Couldn't read entry {} of {:?} because of {:?}", i, part, e))?;
+            if entry.is_dir()
+                || [SNAPSHOT_FILE_NAME, UNSTABLE_FILE_NAME, ARCHIVE_MANIFEST_NAME].contains(&entry.name())
+                || last_index.get(entry.name()) != Some(&i)
+            {
+                continue;
+            }
+            let original = match original_path(manifest.as_ref(), entry.name()) {
+                Some(original) => original,
+                None => {
+                    debug!("Skip {:?} because its root isn't listed in the manifest", entry.name());
+                    continue;
+                }
+            };
+            if filter.map_or(false, |filter| !filter.is_match(&original)) {
+                continue;
+            }
+            visit(ArchiveEntry {
+                path: original,
+                size: entry.size(),
+                modified: modified_time(entry.last_modified()),
+            });
+            listed += 1;
+        }
+    }
+    Ok(listed)
+}
+
+#[cfg(test)]
+mod contents_tests {
+    use std::{fs, io::Write};
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    #[test]
+    fn lists_entries() {
+        let dir = PathBuf::from("./test_dir/list_contents_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        let archived = zip::DateTime::from_date_and_time(2020, 1, 2, 3, 4, 6).unwrap();
+        let options = FileOptions::default().last_modified_time(archived);
+        zip.add_directory("docs", options).unwrap();
+        for (name, content) in [("docs/a.txt", "first"), ("docs/b.jpg", "image"), ("docs/a.txt", "retried"), (SNAPSHOT_FILE_NAME, "docs/a.txt")] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        drop(zip);
+        let profile_config = ProfileConfig::new(
+            String::from("list_contents"),
+            dir.clone(),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+
+        let mut entries = vec![];
+        let listed = list_contents(&profile_config, &archive, None, |entry| entries.push(entry));
+        let filter = entry_filter("*.jpg").unwrap();
+        let mut filtered = vec![];
+        let listed_filtered = list_contents(&profile_config, &archive, Some(&filter), |entry| filtered.push(entry.path));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let modified = NaiveDate::from_ymd_opt(2020, 1, 2).unwrap().and_hms_opt(3, 4, 6);
+        assert_eq!(listed, Ok(2));
+        assert_eq!(
+            entries,
+            vec![
+                ArchiveEntry { path: String::from("docs/b.jpg"), size: 5, modified },
+                ArchiveEntry { path: String::from("docs/a.txt"), size: 7, modified },
+            ]
+        );
+        assert_eq!(entries[1].line(), format!("2020-01-02 03:04:06  {:>12}  docs/a.txt", 7));
+        assert_eq!(listed_filtered, Ok(1));
+        assert_eq!(filtered, vec![String::from("docs/b.jpg")]);
+        assert!(entry_filter("[").is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! Contains the listening end of the control channel of a running backup (see [config::control]).
+//!
+//! While a profile is backed up, a thread answers the commands sent to the channel of the profile and applies them to the [Cancellation] of the run.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+use config::control::{control_endpoint, BackupState, ControlCommand, ControlStatus, ERROR_PREFIX};
+use log::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::cancel::Cancellation;
+
+#[cfg(target_family = "windows")]
+mod windows;
+
+/// Control channel of the backup of one profile. It is closed once this is dropped.
+#[derive(Debug)]
+pub struct ControlServer {
+    endpoint: PathBuf,
+    closed: Arc<AtomicBool>,
+    cancellation: Cancellation,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ControlServer {
+    /// Opens the control channel of the backup of the profile with the given [Uuid], which applies the commands it receives to `cancellation`.
+    ///
+    /// # Errors
+    /// Returns an [Err] describing the issue if the channel couldn't be opened, e.g., because another backup of the profile is using it.
+    pub fn start(uuid: &Uuid, cancellation: Cancellation) -> Result<ControlServer, String> {
+        let endpoint = control_endpoint(uuid);
+        let closed = Arc::new(AtomicBool::new(false));
+        let handler = {
+            let uuid = *uuid;
+            let cancellation = cancellation.clone();
+            move |line: &str| handle_command(line, &uuid, &cancellation)
+        };
+
+        #[cfg(unix)]
+        let thread = {
+            let listener = bind(&endpoint)?;
+            let closed = closed.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if closed.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    match stream {
+                        Ok(mut stream) => answer(&mut stream, &handler),
+                        Err(err) => warn!("Couldn't accept a control connection because of {:?}", err),
+                    }
+                }
+            })
+        };
+        #[cfg(target_family = "windows")]
+        let thread = windows::serve(&endpoint, closed.clone(), handler)?;
+
+        debug!("Listening for control commands on {:?}", endpoint);
+        Ok(ControlServer {
+            endpoint,
+            closed,
+            cancellation,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::SeqCst);
+        // the listening thread only notices that it is closed when it is woken up by another connection
+        let woken = connect(&self.endpoint).is_ok();
+        if let Some(thread) = self.thread.take() {
+            if woken {
+                let _ = thread.join();
+            }
+        }
+        #[cfg(unix)]
+        if let Err(err) = std::fs::remove_file(&self.endpoint) {
+            warn!("Couldn't remove the control socket {:?} because of {:?}", self.endpoint, err);
+        }
+        // a pause belongs to the backup of this profile and mustn't hold up the next one
+        self.cancellation.resume();
+    }
+}
+
+/// Binds the unix socket at `endpoint`. A socket left behind by a backupper that crashed is replaced.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if another backup is listening on the socket or it couldn't be bound.
+#[cfg(unix)]
+fn bind(endpoint: &PathBuf) -> Result<std::os::unix::net::UnixListener, String> {
+    if endpoint.exists() {
+        if connect(endpoint).is_ok() {
+            return Err(format!("Another backup is listening on {:?}", endpoint));
+        }
+        std::fs::remove_file(endpoint).map_err(|e| format!("Couldn't remove the stale socket {:?} because of {:?}", endpoint, e))?;
+    }
+    std::os::unix::net::UnixListener::bind(endpoint).map_err(|e| format!("Couldn't listen on {:?} because of {:?}", endpoint, e))
+}
+
+/// Connects to the control channel at `endpoint` without sending anything.
+fn connect(endpoint: &PathBuf) -> std::io::Result<()> {
+    #[cfg(unix)]
+    std::os::unix::net::UnixStream::connect(endpoint)?;
+    #[cfg(target_family = "windows")]
+    std::fs::OpenOptions::new().read(true).write(true).open(endpoint)?;
+    Ok(())
+}
+
+/// Reads one command from the client connected through `stream` and writes the answer of `handler` back.
+/// Clients that only connect, e.g., to wake the listening thread, don't get an answer.
+fn answer<S: Read + Write>(stream: &mut S, handler: &impl Fn(&str) -> String) {
+    let mut line = String::new();
+    match BufReader::new(&mut *stream).read_line(&mut line) {
+        Ok(0) => return,
+        Ok(_) => {}
+        Err(err) => {
+            warn!("Couldn't read a control command because of {:?}", err);
+            return;
+        }
+    }
+    let mut reply = handler(&line);
+    reply.push('\n');
+    if let Err(err) = stream.write_all(reply.as_bytes()).and_then(|_| stream.flush()) {
+        warn!("Couldn't answer the control command {:?} because of {:?}", line.trim(), err);
+    }
+}
+
+/// Applies the command in `line` to `cancellation` and returns the answer: the [ControlStatus] as JSON or an error message (see [ERROR_PREFIX]).
+fn handle_command(line: &str, uuid: &Uuid, cancellation: &Cancellation) -> String {
+    let command = match line.parse::<ControlCommand>() {
+        Ok(command) => command,
+        Err(msg) => return format!("{}{}", ERROR_PREFIX, msg),
+    };
+    match command {
+        ControlCommand::Pause if !cancellation.is_paused() => {
+            info!("Pausing the backup after the current file, as requested through the control channel");
+            cancellation.pause();
+        }
+        ControlCommand::Resume if cancellation.is_paused() => {
+            info!("Resuming the backup, as requested through the control channel");
+            cancellation.resume();
+        }
+        ControlCommand::Stop if !cancellation.is_stopped() => {
+            warn!("Stopping after the current file, as requested through the control channel");
+            cancellation.stop();
+        }
+        _ => {}
+    }
+    let state = if cancellation.is_stopped() {
+        BackupState::Stopping
+    } else if cancellation.is_paused() {
+        BackupState::Paused
+    } else {
+        BackupState::Running
+    };
+    let status = ControlStatus {
+        profile_uuid: *uuid,
+        pid: process::id(),
+        state,
+    };
+    serde_json::to_string(&status).unwrap_or_else(|err| format!("{}{:?}", ERROR_PREFIX, err))
+}
+
+#[cfg(test)]
+mod control_tests {
+    use config::control::send_command;
+
+    use super::*;
+
+    #[test]
+    fn commands() {
+        let uuid = Uuid::new_v4();
+        let cancellation = Cancellation::default();
+        let status = |line: &str| -> Result<ControlStatus, String> {
+            let answer = handle_command(line, &uuid, &cancellation);
+            match answer.strip_prefix(ERROR_PREFIX) {
+                Some(msg) => Err(msg.to_string()),
+                None => Ok(serde_json::from_str(&answer).unwrap()),
+            }
+        };
+
+        assert_eq!(status("status\n").map(|status| status.state), Ok(BackupState::Running));
+        assert_eq!(status("pause\n").map(|status| status.state), Ok(BackupState::Paused));
+        assert!(cancellation.is_paused());
+        assert_eq!(status("pause\n").map(|status| status.state), Ok(BackupState::Paused));
+        assert_eq!(status("resume\n").map(|status| status.state), Ok(BackupState::Running));
+        assert!(!cancellation.is_paused());
+        assert!(status("restart\n").unwrap_err().contains("Unknown command"));
+        let stopped = status("stop\n").unwrap();
+        assert_eq!(stopped.state, BackupState::Stopping);
+        assert_eq!(stopped.profile_uuid, uuid);
+        assert_eq!(stopped.pid, process::id());
+        assert!(cancellation.is_stopped());
+        assert!(!cancellation.is_cancelled());
+    }
+
+    #[test]
+    fn channel() {
+        let uuid = Uuid::new_v4();
+        let cancellation = Cancellation::default();
+        let server = ControlServer::start(&uuid, cancellation.clone()).unwrap();
+        assert!(ControlServer::start(&uuid, Cancellation::default()).is_err());
+
+        let paused = send_command(&uuid, ControlCommand::Pause);
+        let is_paused = cancellation.is_paused();
+        let status = send_command(&uuid, ControlCommand::Status);
+        drop(server);
+        let after = send_command(&uuid, ControlCommand::Status);
+
+        assert_eq!(paused.map(|status| status.state), Ok(BackupState::Paused));
+        assert!(is_paused);
+        assert_eq!(status.map(|status| status.state), Ok(BackupState::Paused));
+        assert!(after.is_err());
+        // closing the channel ends the pause
+        assert!(!cancellation.is_paused());
+        assert!(!control_endpoint(&uuid).exists());
+
+        // another backup of the profile can listen again
+        let server = ControlServer::start(&uuid, Cancellation::default());
+        assert!(server.is_ok());
+    }
+}
@@ -0,0 +1,105 @@
+//! Contains the windows implementation of the control channel, which is a named pipe
+
+use std::{
+    fs::File,
+    io::Write,
+    os::windows::{
+        ffi::OsStrExt,
+        io::{AsRawHandle, FromRawHandle, RawHandle},
+    },
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use log::warn;
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::{GetLastError, ERROR_PIPE_CONNECTED, HANDLE, INVALID_HANDLE_VALUE},
+        Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX},
+        System::Pipes::{
+            ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE,
+            PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+        },
+    },
+};
+
+use super::answer;
+
+/// Size of the buffers of a pipe instance in bytes, which easily fits a command or an answer
+const BUFFER_SIZE: u32 = 4096;
+
+/// Creates an instance of the named pipe `name`, which is a null terminated UTF-16 string.
+/// Only the `first` instance may create the pipe, so two backups can't listen on the same one.
+fn create_instance(name: &[u16], first: bool) -> Result<File, String> {
+    let mut open_mode = PIPE_ACCESS_DUPLEX;
+    if first {
+        open_mode |= FILE_FLAG_FIRST_PIPE_INSTANCE;
+    }
+    let handle = unsafe {
+        CreateNamedPipeW(
+            PCWSTR::from_raw(name.as_ptr()),
+            open_mode,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_UNLIMITED_INSTANCES,
+            BUFFER_SIZE,
+            BUFFER_SIZE,
+            0,
+            None,
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(format!("CreateNamedPipeW failed with {:?}", unsafe { GetLastError() }));
+    }
+    // the file closes the handle once it is dropped
+    Ok(unsafe { File::from_raw_handle(handle.0 as RawHandle) })
+}
+
+/// Waits until a client connects to the pipe instance `pipe`.
+fn wait_for_client(pipe: &File) -> Result<(), String> {
+    let connected = unsafe { ConnectNamedPipe(HANDLE(pipe.as_raw_handle() as isize), None) }.as_bool();
+    // clients may connect between creating the instance and waiting for them
+    let error = unsafe { GetLastError() };
+    if connected || error == ERROR_PIPE_CONNECTED {
+        Ok(())
+    } else {
+        Err(format!("ConnectNamedPipe failed with {:?}", error))
+    }
+}
+
+/// Answers the commands sent to the named pipe at `endpoint` with `handler` on a new thread, until `closed` is set.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the pipe couldn't be created, e.g., because another backup is listening on it.
+pub fn serve(
+    endpoint: &PathBuf,
+    closed: Arc<AtomicBool>,
+    handler: impl Fn(&str) -> String + Send + 'static,
+) -> Result<JoinHandle<()>, String> {
+    let name: Vec<u16> = endpoint.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut pipe = create_instance(&name, true).map_err(|msg| format!("Couldn't listen on {:?}: {}", endpoint, msg))?;
+    Ok(thread::spawn(move || loop {
+        if let Err(msg) = wait_for_client(&pipe) {
+            warn!("Couldn't accept a control connection: {}", msg);
+        }
+        if closed.load(Ordering::SeqCst) {
+            break;
+        }
+        // the next instance is created first, so other clients don't find the pipe busy for long
+        let next = create_instance(&name, false);
+        answer(&mut pipe, &handler);
+        // the answer has to be read before the instance is closed
+        let _ = pipe.flush().and_then(|_| pipe.sync_all());
+        match next {
+            Ok(next) => pipe = next,
+            Err(msg) => {
+                warn!("Couldn't keep listening for control commands: {}", msg);
+                break;
+            }
+        }
+    }))
+}
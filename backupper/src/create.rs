@@ -0,0 +1,126 @@
+//! Contains functions for creating a new profile.
+
+use config::{
+    general_config::GeneralConfig,
+    interval::{IntervalBuilder, SpecifierKind},
+    profile_config::{AuditActor, ProfileConfig, ProfilePreset},
+};
+use log::{debug, info};
+
+/// Stores a new [ProfileConfig] named `name` in the profile config directory, just like the server's create endpoint.
+/// It has no dirs yet, its target dir is the `default_target_dir` of the [GeneralConfig] and its interval never matches.
+/// If a `preset` is given, it sets up the filters (see [ProfilePreset::apply]).
+///
+/// # Returns
+/// The stored profile
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the name is already taken (case-insensitive) or the profile couldn't be stored.
+pub fn create(
+    general_config: &GeneralConfig,
+    name: &str,
+    preset: Option<ProfilePreset>,
+) -> Result<ProfileConfig, String> {
+    let dir = &general_config.profile_configs;
+    let profile_configs = ProfileConfig::load_dir(dir)
+        .map_err(|err| format!("Couldn't read profile configs in {:?} because of {:?}", dir, err))?;
+    if profile_configs
+        .iter()
+        .any(|config| config.name.to_lowercase() == name.to_lowercase())
+    {
+        return Err(format!("Name {:?} is already taken", name));
+    }
+
+    let interval = IntervalBuilder::default()
+        .minutes(SpecifierKind::None)
+        .build()
+        .map_err(|err| format!("Couldn't build interval because of {:?}", err))?;
+    let mut profile_config = ProfileConfig::new(
+        String::from(name),
+        general_config.default_target_dir.clone().unwrap_or_default(),
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+        interval,
+    );
+    if let Some(preset) = preset {
+        debug!("Apply preset {:?} to {:?}", preset, name);
+        preset.apply(&mut profile_config);
+    }
+
+    profile_config
+        .store_audited(dir, AuditActor::Cli)
+        .map_err(|err| format!("Couldn't store {:?} because of {:?}", name, err))?;
+    info!("Created {:?} ({})", profile_config.name, profile_config.get_uuid());
+    Ok(profile_config)
+}
+
+#[cfg(test)]
+mod create_tests {
+    use std::{fs, path::{Path, PathBuf}};
+
+    use config::general_config::GENERAL_CONFIG_VERSION;
+
+    use super::*;
+
+    fn general_config(dir: &Path) -> GeneralConfig {
+        GeneralConfig {
+            profile_configs: dir.to_path_buf(),
+            max_history_entries: None,
+            max_sidecar_logs: None,
+            max_sidecar_age_days: None,
+            free_space_warning_days: None,
+            default_target_dir: Some(PathBuf::from("backups")),
+            show_dialogs: true,
+            log_level: None,
+            config_version: GENERAL_CONFIG_VERSION,
+        }
+    }
+
+    #[test]
+    fn applies_preset() {
+        let dir = PathBuf::from("./test_dir/create_preset_tmp");
+        fs::create_dir_all(&dir).unwrap();
+
+        let created = create(&general_config(&dir), "Docs", Some(ProfilePreset::Documents));
+        let stored = ProfileConfig::load_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let created = created.unwrap();
+        let mut expected = ProfileConfig::new(
+            String::from("Docs"),
+            PathBuf::from("backups"),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        );
+        ProfilePreset::Documents.apply(&mut expected);
+        assert_eq!(created.exclude_extensions, expected.exclude_extensions);
+        assert_eq!(created.include_extensions, expected.include_extensions);
+        assert_eq!(created.max_file_size, expected.max_file_size);
+        assert_eq!(created.target_dir, expected.target_dir);
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].get_uuid(), created.get_uuid());
+    }
+
+    #[test]
+    fn rejects_taken_names() {
+        let dir = PathBuf::from("./test_dir/create_taken_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let general_config = general_config(&dir);
+
+        let first = create(&general_config, "Hutzi", None);
+        let taken = create(&general_config, "HUTZI", Some(ProfilePreset::Photos));
+        let stored = ProfileConfig::load_dir(&dir).unwrap().len();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let first = first.unwrap();
+        assert!(first.include_extensions.is_empty());
+        assert_eq!(first.max_file_size, None);
+        assert!(taken.is_err());
+        assert_eq!(stored, 1);
+    }
+}
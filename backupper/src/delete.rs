@@ -1,54 +1,48 @@
 use std::{fs, path::PathBuf};
 
-use config::{general_config::GeneralConfig, profile_config::ProfileConfig};
+use config::{
+    backup_inventory::{files_below, ARCHIVE_SEARCH_DEPTH},
+    backup_report::REPORT_SUFFIX,
+    general_config::GeneralConfig,
+    profile_config::{AuditActor, ProfileConfig},
+};
 use log::error;
-use uuid::Uuid;
 
 use crate::scheduler::{schedule_backup, unschedule_backup};
 
-/// Deletes all the backup files belonging to the given [Uuid] within the given directory.
-fn delete_backup_files(uuid: &Uuid, dir: &PathBuf) -> Result<(), String> {
-    let entries = match fs::read_dir(dir) {
-        Ok(entries) => entries,
-        Err(err) => {
-            return Err(format!("Error reading dir: {:?}", err));
-        }
-    };
-
-    for entry in entries {
-        if entry.is_err() {
-            continue;
-        }
-
-        let entry = entry.unwrap();
-        let path = entry.path();
-
-        if path.is_dir() {
-            continue;
-        }
-
+/// Deletes all the backup files belonging to the given [ProfileConfig] within the given directory, including the reports of the archives.
+/// The subdirectories are searched like for listing the archives (see [ARCHIVE_SEARCH_DEPTH]), but aren't removed.
+/// Archives named after one of the `former_uuids` are only deleted if `include_former` is set.
+fn delete_backup_files(profile_config: &ProfileConfig, dir: &PathBuf, include_former: bool) -> Result<(), String> {
+    for path in files_below(dir, ARCHIVE_SEARCH_DEPTH)? {
         let filename = match path.file_name().and_then(|name| name.to_str()) {
             Some(name) => name,
             None => continue,
         };
 
-        if filename.starts_with(&uuid.as_hyphenated().to_string()) && filename.ends_with(".zip") {
+        let is_own = if include_former {
+            profile_config.is_own_archive(filename)
+        } else {
+            filename.starts_with(&profile_config.get_uuid().as_hyphenated().to_string())
+        };
+        if is_own && (filename.ends_with(".zip") || filename.ends_with(REPORT_SUFFIX)) {
             if let Err(e) = fs::remove_file(&path) {
                 error!("Couldn't delete {:?}. Got: {:#?}", path, e);
             }
         }
-
     }
 
     Ok(())
 }
 
 /// Deletes the config file for the given [ProfileConfig] and unschedules its backups.
-/// If `delete_backups` is true, the profiles backups are alos deleted
+/// If `delete_backups` is true, the profiles backups are alos deleted.
+/// If `include_former` is true as well, this includes the backups made under its `former_uuids`.
 pub fn delete(
     profile_config: &ProfileConfig,
     general_config: &GeneralConfig,
     delete_backups: bool,
+    include_former: bool,
 ) {
     if let Err(e) = unschedule_backup(profile_config.get_uuid().clone()) {
         error!("Couldn't unschedule profile. Got {}", e);
@@ -56,7 +50,7 @@ pub fn delete(
     }
 
     if delete_backups {
-        if let Err(e) = delete_backup_files(profile_config.get_uuid(), &profile_config.target_dir) {
+        if let Err(e) = delete_backup_files(profile_config, &profile_config.target_dir, include_former) {
             error!("Couldn't delete previous backups. Got {}", e);
 
             if let Err(e) = schedule_backup(
@@ -69,8 +63,7 @@ pub fn delete(
         }
     }
 
-    let filename = profile_config.get_uuid().as_hyphenated().to_string() + ".json";
-    let path = general_config.profile_configs.join(filename);
+    let path = profile_config.file_path(&general_config.profile_configs);
 
     if let Err(e) = fs::remove_file(&path) {
         error!("Coudln't delete config file. Got {:#?}", e);
@@ -80,5 +73,51 @@ pub fn delete(
         ) {
             error!("Couldn't reschedule old backup. Got: {}", e);
         }
+        return;
+    }
+
+    if let Err(e) = profile_config.audit_deletion(&general_config.profile_configs, AuditActor::Cli) {
+        error!("Couldn't write deletion to the audit log. Got {:?}", e);
+    }
+}
+
+#[cfg(test)]
+mod delete_tests {
+    use std::fs::File;
+
+    use config::interval::IntervalBuilder;
+
+    use super::*;
+
+    #[test]
+    fn deletes_reports() {
+        let dir = PathBuf::from("./test_dir/delete_reports_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let profile_config = ProfileConfig::new(
+            String::from("delete_reports"),
+            dir.clone(),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        );
+        let uuid = profile_config.get_uuid().as_hyphenated().to_string();
+        fs::create_dir_all(dir.join("2023")).unwrap();
+        for name in [
+            format!("{}_2023-05-01_10-00.zip", uuid),
+            format!("{}_2023-05-01_10-00.report.json", uuid),
+            format!("2023/{}_2023-04-01_10-00.zip", uuid),
+            String::from("unrelated.report.json"),
+        ] {
+            File::create(dir.join(name)).unwrap();
+        }
+
+        let res = delete_backup_files(&profile_config, &dir, false);
+        let remaining: Vec<PathBuf> = files_below(&dir, ARCHIVE_SEARCH_DEPTH).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(res, Ok(()));
+        assert_eq!(remaining, vec![dir.join("unrelated.report.json")]);
     }
 }
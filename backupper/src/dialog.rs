@@ -10,6 +10,8 @@ pub struct DialogResult(pub i32);
 pub const OK: i32 = 1;
 pub const CANCEL: i32 = 2;
 pub const RETRY: i32 = 4;
+pub const YES: i32 = 6;
+pub const NO: i32 = 7;
 
 /// Displays a retry dialog with the given `title` and `msg`.
 /// 
@@ -37,4 +39,18 @@ pub fn retry_dialog(title: &str, msg: &str) -> DialogResult {
 pub fn info_dialog(title: &str, msg: &str) -> DialogResult {
     #[cfg(target_family = "windows")]
     windows::info_dialog(title, msg)
+}
+
+/// Displays a dialog with the given `title` and `msg` that asks a yes-no question.
+/// 
+/// # Parameters
+/// - `title`: Title of the dialog window
+/// - `msg`: Message to be displayed
+/// The parameters don't have to end with a null-character `'\0'`. If needed, they will be added by the function.
+/// 
+/// # Returns
+/// [DialogResult] depending on what the user clicked on.
+pub fn question_dialog(title: &str, msg: &str) -> DialogResult {
+    #[cfg(target_family = "windows")]
+    windows::question_dialog(title, msg)
 }
\ No newline at end of file
@@ -28,5 +28,12 @@ pub fn retry_dialog(title: &str, msg: &str) -> DialogResult {
 pub fn info_dialog(title: &str, msg: &str) -> DialogResult {
     let res = generic_message_box(title, msg, MB_OK | MB_ICONINFORMATION);
 
+    DialogResult(res.0)
+}
+
+/// Opens a Yes-No-Message box with the given parameters and returns the users answer.
+pub fn question_dialog(title: &str, msg: &str) -> DialogResult {
+    let res = generic_message_box(title, msg, MB_YESNO | MB_ICONWARNING);
+
     DialogResult(res.0)
 }
\ No newline at end of file
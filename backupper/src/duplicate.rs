@@ -0,0 +1,84 @@
+//! Contains functions for duplicating a profile.
+
+use config::{
+    general_config::GeneralConfig,
+    profile_config::{AuditActor, ProfileConfig},
+};
+use log::info;
+
+/// Stores a copy of the given [ProfileConfig] named `new_name` in the same directory and format (see [ProfileConfig::duplicate]).
+/// The copy isn't scheduled until its first backup or reschedule.
+///
+/// # Returns
+/// The stored copy
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the name is already taken (case-insensitive) or the copy couldn't be stored.
+pub fn duplicate(
+    profile_config: &ProfileConfig,
+    general_config: &GeneralConfig,
+    new_name: &str,
+) -> Result<ProfileConfig, String> {
+    let dir = &general_config.profile_configs;
+    let profile_configs = ProfileConfig::load_dir(dir)
+        .map_err(|err| format!("Couldn't read profile configs in {:?} because of {:?}", dir, err))?;
+    if profile_configs
+        .iter()
+        .any(|config| config.name.to_lowercase() == new_name.to_lowercase())
+    {
+        return Err(format!("Name {:?} is already taken", new_name));
+    }
+
+    let copy = profile_config.duplicate(String::from(new_name));
+    copy.store_audited(dir, AuditActor::Cli)
+        .map_err(|err| format!("Couldn't store duplicate of {:?} because of {:?}", profile_config.name, err))?;
+    info!("Duplicated {:?} as {:?} ({})", profile_config.name, copy.name, copy.get_uuid());
+    Ok(copy)
+}
+
+#[cfg(test)]
+mod duplicate_tests {
+    use std::{fs, path::PathBuf};
+
+    use config::{general_config::GENERAL_CONFIG_VERSION, interval::IntervalBuilder};
+
+    use super::*;
+
+    #[test]
+    fn rejects_taken_names() {
+        let dir = PathBuf::from("./test_dir/duplicate_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let general_config = GeneralConfig {
+            profile_configs: dir.clone(),
+            max_history_entries: None,
+            max_sidecar_logs: None,
+            max_sidecar_age_days: None,
+            free_space_warning_days: None,
+            default_target_dir: None,
+            show_dialogs: true,
+            log_level: None,
+            config_version: GENERAL_CONFIG_VERSION,
+        };
+        let profile_config = ProfileConfig::new(
+            String::from("Hutzi"),
+            PathBuf::from("ho"),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.store(&dir).unwrap();
+
+        let taken = duplicate(&profile_config, &general_config, "HUTZI");
+        let copy = duplicate(&profile_config, &general_config, "Hutzi 2");
+        let stored = ProfileConfig::load_dir(&dir).unwrap().len();
+        let taken_again = duplicate(&profile_config, &general_config, "hutzi 2");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(taken.is_err());
+        assert_eq!(copy.unwrap().name, "Hutzi 2");
+        assert_eq!(stored, 2);
+        assert!(taken_again.is_err());
+    }
+}
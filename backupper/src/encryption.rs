@@ -0,0 +1,373 @@
+//! Contains the encryption of finished archives with a passphrase (see [EncryptionSettings](config::profile_config::EncryptionSettings)).
+//!
+//! Encrypted archives keep their names, but start with [MAGIC] instead of being zip archives. After it follow the number of PBKDF2 rounds (`u32`, little endian),
+//! the salt and the nonce prefix. The rest consists of chunks of at most [CHUNK_SIZE] bytes encrypted with AES-256-GCM (STREAM construction),
+//! so every chunk is authenticated and truncated or reordered archives are noticed as well.
+
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use aes_gcm::{
+    aead::{
+        rand_core::RngCore,
+        stream::{DecryptorBE32, EncryptorBE32},
+        KeyInit, OsRng,
+    },
+    Aes256Gcm, Key,
+};
+use config::profile_config::ProfileConfig;
+use log::warn;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::tempfiles::{temp_path_for, TempKind};
+
+/// Bytes every encrypted archive starts with
+const MAGIC: &[u8; 8] = b"BKRSAES1";
+
+/// Number of PBKDF2 rounds used for new archives. Archives record their number of rounds, so it can be raised later.
+#[cfg(not(test))]
+const KDF_ROUNDS: u32 = 600_000;
+
+/// Unoptimized test builds would take far too long with the real number of rounds.
+#[cfg(test)]
+const KDF_ROUNDS: u32 = 1_000;
+
+const SALT_SIZE: usize = 16;
+
+/// Size of the nonce prefix of the STREAM construction, which uses the remaining 5 bytes of the nonce for the chunk counter and the last chunk flag
+const NONCE_PREFIX_SIZE: usize = 7;
+
+/// Bytes of the archive per encrypted chunk
+const CHUNK_SIZE: usize = 0x1_0000;
+
+/// Bytes of the authentication tag added to each chunk
+const TAG_SIZE: usize = 16;
+
+/// Checks if the file at `path` is an encrypted archive.
+pub fn is_encrypted(path: &Path) -> bool {
+    let mut magic = [0u8; MAGIC.len()];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .map_or(false, |_| &magic == MAGIC)
+}
+
+/// Encrypts everything `reader` yields with the given `passphrase` and writes it to `writer`.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if reading or writing failed.
+pub fn encrypt<R: Read, W: Write>(reader: &mut R, writer: &mut W, passphrase: &str) -> Result<(), String> {
+    let mut salt = [0u8; SALT_SIZE];
+    let mut nonce_prefix = [0u8; NONCE_PREFIX_SIZE];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce_prefix);
+
+    let mut header = MAGIC.to_vec();
+    header.extend_from_slice(&KDF_ROUNDS.to_le_bytes());
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&nonce_prefix);
+    writer.write_all(&header).map_err(|err| format!("Couldn't write header because of {:?}", err))?;
+
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, &salt, KDF_ROUNDS));
+    let mut encryptor = EncryptorBE32::from_aead(cipher, nonce_prefix.as_slice().into());
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    loop {
+        let len = read_full(reader, &mut chunk).map_err(|err| format!("Couldn't read archive because of {:?}", err))?;
+        // a chunk that isn't full is the last one, which may also be empty
+        if len < CHUNK_SIZE {
+            let encrypted = encryptor
+                .encrypt_last(&chunk[..len])
+                .map_err(|_| String::from("Couldn't encrypt last chunk"))?;
+            return writer
+                .write_all(&encrypted)
+                .map_err(|err| format!("Couldn't write encrypted archive because of {:?}", err));
+        }
+        let encrypted = encryptor
+            .encrypt_next(chunk.as_slice())
+            .map_err(|_| String::from("Couldn't encrypt chunk"))?;
+        writer
+            .write_all(&encrypted)
+            .map_err(|err| format!("Couldn't write encrypted archive because of {:?}", err))?;
+    }
+}
+
+/// Decrypts the encrypted archive `reader` yields with the given `passphrase` and writes the archive to `writer`.
+/// Each chunk is only written after it was authenticated, but if decrypting fails, `writer` may already contain the preceding chunks.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the passphrase is wrong, the archive is corrupted or reading or writing failed.
+pub fn decrypt<R: Read, W: Write>(reader: &mut R, writer: &mut W, passphrase: &str) -> Result<(), String> {
+    let mut header = [0u8; MAGIC.len() + 4 + SALT_SIZE + NONCE_PREFIX_SIZE];
+    if read_full(reader, &mut header).map_err(|err| format!("Couldn't read header because of {:?}", err))? < header.len()
+        || !header.starts_with(MAGIC)
+    {
+        return Err(String::from("Not an encrypted archive"));
+    }
+    let (rounds, rest) = header[MAGIC.len()..].split_at(4);
+    let (salt, nonce_prefix) = rest.split_at(SALT_SIZE);
+    let rounds = u32::from_le_bytes([rounds[0], rounds[1], rounds[2], rounds[3]]);
+
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, salt, rounds));
+    let mut decryptor = DecryptorBE32::from_aead(cipher, nonce_prefix.into());
+    let mut chunk = vec![0u8; CHUNK_SIZE + TAG_SIZE];
+    loop {
+        let len = read_full(reader, &mut chunk).map_err(|err| format!("Couldn't read archive because of {:?}", err))?;
+        if len < chunk.len() {
+            let decrypted = decryptor
+                .decrypt_last(&chunk[..len])
+                .map_err(|_| String::from("Wrong passphrase or corrupted archive"))?;
+            return writer
+                .write_all(&decrypted)
+                .map_err(|err| format!("Couldn't write decrypted archive because of {:?}", err));
+        }
+        let decrypted = decryptor
+            .decrypt_next(chunk.as_slice())
+            .map_err(|_| String::from("Wrong passphrase or corrupted archive"))?;
+        writer
+            .write_all(&decrypted)
+            .map_err(|err| format!("Couldn't write decrypted archive because of {:?}", err))?;
+    }
+}
+
+/// Encrypts the archive at `path` in place with the given `passphrase`.
+/// The encrypted archive is written to a temporary file in the same dir first, which then replaces the archive.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the archive couldn't be encrypted. The archive is left unchanged in that case.
+pub fn encrypt_file(path: &Path, uuid: &Uuid, passphrase: &str) -> Result<(), String> {
+    let encrypted_path = temp_path_for(path.parent().unwrap_or(Path::new(".")), TempKind::Staging, uuid);
+    let res = File::open(path)
+        .map_err(|err| format!("Couldn't open {:?} because of {:?}", path, err))
+        .and_then(|file| {
+            let encrypted = File::create(&encrypted_path)
+                .map_err(|err| format!("Couldn't create {:?} because of {:?}", encrypted_path, err))?;
+            let mut writer = BufWriter::new(encrypted);
+            encrypt(&mut BufReader::new(file), &mut writer, passphrase)?;
+            writer
+                .into_inner()
+                .map_err(|err| format!("Couldn't write {:?} because of {:?}", encrypted_path, err.error()))?
+                .sync_all()
+                .map_err(|err| format!("Couldn't write {:?} because of {:?}", encrypted_path, err))
+        })
+        .and_then(|_| {
+            fs::rename(&encrypted_path, path)
+                .map_err(|err| format!("Couldn't replace {:?} because of {:?}", path, err))
+        });
+    if res.is_err() {
+        let _ = fs::remove_file(&encrypted_path);
+    }
+    res
+}
+
+/// Checks that the encrypted archive at `path` can be decrypted with the given `passphrase`, without writing the archive anywhere.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the archive couldn't be decrypted.
+pub fn verify_encrypted(path: &Path, passphrase: &str) -> Result<(), String> {
+    let file = File::open(path).map_err(|err| format!("Couldn't open {:?} because of {:?}", path, err))?;
+    decrypt(&mut BufReader::new(file), &mut io::sink(), passphrase)
+}
+
+/// Archive that can be read as zip archive. For an encrypted archive, this is a temporary decrypted copy, which is removed once this is dropped.
+#[derive(Debug)]
+pub struct ReadableArchive {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl ReadableArchive {
+    /// Path of the zip archive
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ReadableArchive {
+    fn drop(&mut self) {
+        if self.temporary {
+            if let Err(err) = fs::remove_file(&self.path) {
+                warn!("Couldn't remove decrypted archive {:?} because of {:?}", self.path, err);
+            }
+        }
+    }
+}
+
+/// Makes the archive of the given [ProfileConfig] at `path` readable. Unencrypted archives are used as they are.
+/// Encrypted ones are decrypted with the passphrase of the profile into the temp dir of the system, so no decrypted copy ends up next to the archives.
+/// Nothing is decrypted partially, so a wrong passphrase can't result in a corrupted restore.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the archive is encrypted, but the profile has no passphrase or decrypting failed.
+pub fn readable_archive(profile_config: &ProfileConfig, path: &Path) -> Result<ReadableArchive, String> {
+    if !is_encrypted(path) {
+        return Ok(ReadableArchive {
+            path: path.to_path_buf(),
+            temporary: false,
+        });
+    }
+    let passphrase = match &profile_config.encryption {
+        Some(encryption) => encryption.passphrase()?,
+        None => return Err(format!("{:?} is encrypted, but the profile has no encryption settings", path)),
+    };
+    decrypted_copy(path, profile_config.get_uuid(), &passphrase)
+}
+
+/// Decrypts the encrypted archive at `path` with the given `passphrase` into a temporary file.
+fn decrypted_copy(path: &Path, uuid: &Uuid, passphrase: &str) -> Result<ReadableArchive, String> {
+    let file = File::open(path).map_err(|err| format!("Couldn't open {:?} because of {:?}", path, err))?;
+    let decrypted_path = temp_path_for(&env::temp_dir(), TempKind::Extract, uuid);
+    let output = File::create(&decrypted_path)
+        .map_err(|err| format!("Couldn't create {:?} because of {:?}", decrypted_path, err))?;
+    // removes the decrypted copy again if anything goes wrong
+    let decrypted = ReadableArchive {
+        path: decrypted_path,
+        temporary: true,
+    };
+    let mut writer = BufWriter::new(output);
+    decrypt(&mut BufReader::new(file), &mut writer, passphrase).map_err(|msg| format!("Couldn't decrypt {:?}: {}", path, msg))?;
+    writer
+        .flush()
+        .map_err(|err| format!("Couldn't write {:?} because of {:?}", decrypted.path(), err))?;
+    Ok(decrypted)
+}
+
+/// Derives the key from the `passphrase` with PBKDF2-HMAC-SHA256.
+fn derive_key(passphrase: &str, salt: &[u8], rounds: u32) -> Key<Aes256Gcm> {
+    let mut key = Key::<Aes256Gcm>::default();
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, rounds, &mut key);
+    key
+}
+
+/// Reads from `reader` until `buf` is full or the end is reached.
+///
+/// # Returns
+/// The number of bytes read.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod encryption_tests {
+    use config::profile_config::{EncryptionSettings, PassphraseSource};
+
+    use super::*;
+
+    const TEST_KEY: &str = "correct horse battery staple";
+
+    fn roundtrip(len: usize) -> Vec<u8> {
+        let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+        let mut encrypted = vec![];
+        encrypt(&mut data.as_slice(), &mut encrypted, TEST_KEY).unwrap();
+        assert!(encrypted.starts_with(MAGIC));
+        let mut decrypted = vec![];
+        decrypt(&mut encrypted.as_slice(), &mut decrypted, TEST_KEY).unwrap();
+        assert_eq!(decrypted, data);
+        encrypted
+    }
+
+    #[test]
+    fn roundtrips() {
+        for len in [0, 1, CHUNK_SIZE, 2 * CHUNK_SIZE + 17] {
+            roundtrip(len);
+        }
+    }
+
+    #[test]
+    fn wrong_passphrase() {
+        let encrypted = roundtrip(100);
+        let mut decrypted = vec![];
+        let res = decrypt(&mut encrypted.as_slice(), &mut decrypted, "wrong");
+        assert_eq!(res, Err(String::from("Wrong passphrase or corrupted archive")));
+        assert!(decrypted.is_empty());
+    }
+
+    #[test]
+    fn tampered() {
+        let encrypted = roundtrip(CHUNK_SIZE + 100);
+        let mut flipped = encrypted.clone();
+        let last = flipped.len() - 1;
+        flipped[last] ^= 1;
+        assert!(decrypt(&mut flipped.as_slice(), &mut vec![], TEST_KEY).is_err());
+        // dropping the last chunk must not go unnoticed either
+        let truncated = &encrypted[..encrypted.len() - 100 - TAG_SIZE];
+        assert!(decrypt(&mut &truncated[..], &mut vec![], TEST_KEY).is_err());
+        assert_eq!(
+            decrypt(&mut &b"PK\x03\x04"[..], &mut vec![], TEST_KEY),
+            Err(String::from("Not an encrypted archive"))
+        );
+    }
+
+    #[test]
+    fn encrypt_in_place() {
+        let dir = PathBuf::from("./test_dir/encrypt_in_place_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("archive.zip");
+        fs::write(&path, b"zip content").unwrap();
+        let uuid = Uuid::new_v4();
+
+        encrypt_file(&path, &uuid, TEST_KEY).unwrap();
+        let encrypted = is_encrypted(&path);
+        let verified = verify_encrypted(&path, TEST_KEY);
+        let wrong_key = decrypted_copy(&path, &uuid, "wrong");
+        let readable = decrypted_copy(&path, &uuid, TEST_KEY).unwrap();
+        let content = fs::read(readable.path()).unwrap();
+        let decrypted_path = readable.path().to_path_buf();
+        drop(readable);
+        let remaining = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(encrypted);
+        assert!(verified.is_ok());
+        assert!(wrong_key.unwrap_err().contains("Wrong passphrase"));
+        assert_eq!(content, b"zip content");
+        assert!(!decrypted_path.exists());
+        assert_eq!(remaining, 1);
+    }
+
+    #[test]
+    fn readable_archives() {
+        let dir = PathBuf::from("./test_dir/readable_archives_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let plain = dir.join("plain.zip");
+        let encrypted = dir.join("encrypted.zip");
+        fs::write(&plain, b"plain").unwrap();
+        fs::write(&encrypted, b"secret").unwrap();
+        let key_file = dir.join("backup.key");
+        fs::write(&key_file, format!("{}\n", TEST_KEY)).unwrap();
+        let mut profile_config = ProfileConfig::new(
+            String::from("readable_archives"),
+            dir.clone(),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        encrypt_file(&encrypted, profile_config.get_uuid(), TEST_KEY).unwrap();
+
+        let without_settings = readable_archive(&profile_config, &encrypted);
+        let plain_path = readable_archive(&profile_config, &plain).map(|archive| archive.path().to_path_buf());
+        profile_config.encryption = Some(EncryptionSettings {
+            passphrase: PassphraseSource::KeyFile(key_file),
+        });
+        let content = readable_archive(&profile_config, &encrypted).map(|archive| fs::read(archive.path()).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(without_settings.unwrap_err().contains("encryption settings"));
+        assert_eq!(plain_path, Ok(plain));
+        assert_eq!(content, Ok(b"secret".to_vec()));
+    }
+}
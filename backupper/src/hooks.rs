@@ -0,0 +1,150 @@
+//! Contains functions for running the `pre_backup_commands` and `post_backup_commands` of a [ProfileConfig](config::profile_config::ProfileConfig).
+
+use std::{
+    io::Read,
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+
+/// Interval in which a running hook is checked for completion
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Creates a [Command] running `command` in the platform's shell, i.e., `cmd /C` on Windows and `sh -c` elsewhere.
+fn shell_command(command: &str) -> Command {
+    #[cfg(windows)]
+    let mut shell = {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    };
+    #[cfg(not(windows))]
+    let mut shell = {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
+
+/// Reads the remaining output of a pipe in a separate thread, so the hook can't block on a full pipe.
+fn collect<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut output = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut output);
+        }
+        String::from_utf8_lossy(&output).trim_end().to_string()
+    })
+}
+
+/// Waits for the `child` to exit. Kills it if it runs longer than `timeout`.
+///
+/// # Returns
+/// Whether the `child` exited successfully
+///
+/// # Errors
+/// Returns an [Err] describing the issue if it timed out or couldn't be waited for.
+fn wait(child: &mut Child, timeout: Duration) -> Result<bool, String> {
+    let start = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status.success()),
+            Ok(None) if start.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("timed out after {:?}", timeout));
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(err) => return Err(format!("couldn't be waited for because of {:?}", err)),
+        }
+    }
+}
+
+/// Runs a single hook `command` and logs its stdout and stderr.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the command couldn't be started, timed out or exited unsuccessfully.
+pub fn run_hook(command: &str, timeout: Duration) -> Result<(), String> {
+    info!("Running hook {:?}", command);
+    let mut child = shell_command(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("Hook {:?} couldn't be started because of {:?}", command, err))?;
+    let stdout = collect(child.stdout.take());
+    let stderr = collect(child.stderr.take());
+
+    // processes started by a killed hook may still hold the pipes open, so the output is only read if the hook exited by itself
+    let success = wait(&mut child, timeout).map_err(|msg| format!("Hook {:?} {}", command, msg))?;
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !stdout.is_empty() {
+        info!("Output of hook {:?}:\n{}", command, stdout);
+    }
+    if !stderr.is_empty() {
+        warn!("Error output of hook {:?}:\n{}", command, stderr);
+    }
+
+    if success {
+        Ok(())
+    } else {
+        Err(format!("Hook {:?} failed", command))
+    }
+}
+
+/// Runs the given hook `commands` one after another (see [run_hook]).
+///
+/// # Errors
+/// Returns the [Err] of the first failing command. The remaining commands aren't run.
+pub fn run_hooks(commands: &[String], timeout: Duration) -> Result<(), String> {
+    commands
+        .iter()
+        .try_for_each(|command| run_hook(command, timeout))
+}
+
+#[cfg(test)]
+mod hooks_tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn successful_hooks() {
+        assert!(run_hooks(&[], TIMEOUT).is_ok());
+        assert!(run_hooks(&[String::from("exit 0"), String::from("echo hutzi")], TIMEOUT).is_ok());
+    }
+
+    #[test]
+    fn failing_hook() {
+        let res = run_hooks(&[String::from("exit 1"), String::from("exit 0")], TIMEOUT);
+        assert!(res.unwrap_err().contains("exit 1"));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn stops_after_failing_hook() {
+        let marker = std::path::PathBuf::from("./test_dir/hooks_stop_marker_tmp");
+        let _ = std::fs::remove_file(&marker);
+
+        let res = run_hooks(
+            &[String::from("exit 3"), format!("touch {}", marker.display())],
+            TIMEOUT,
+        );
+        assert!(res.is_err());
+        assert!(!marker.exists());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn timed_out_hook() {
+        let start = Instant::now();
+        let res = run_hook("sleep 5", Duration::from_millis(200));
+        assert!(res.unwrap_err().contains("timed out"));
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+}
@@ -0,0 +1,87 @@
+//! Contains the texts shown to the user that depend on the situation, like advice for common errors.
+//! Like the other dialog texts, they are in German.
+
+/// Extracts the OS error code from an error message that contains a formatted [std::io::Error], e.g. `Os { code: 112, kind: ... }`.
+pub fn os_error_code(msg: &str) -> Option<i32> {
+    let start = msg.find("Os { code: ")? + "Os { code: ".len();
+    let digits: String = msg[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '-')
+        .collect();
+    digits.parse().ok()
+}
+
+/// Extracts the first quoted path from an error message, like they are formatted by `{:?}`.
+fn quoted_path(msg: &str) -> Option<String> {
+    let start = msg.find('"')? + 1;
+    let end = start + msg[start..].find('"')?;
+    Some(msg[start..end].replace("\\\\", "\\"))
+}
+
+/// Translates a Windows OS error code into advice for the user.
+/// `path` is the affected path if it is known.
+///
+/// # Returns
+/// [None] if there is no advice for the given code.
+pub fn os_error_advice(code: i32, path: Option<&str>) -> Option<String> {
+    let path = path.unwrap_or("das Zielverzeichnis");
+    let advice = match code {
+        // ERROR_ACCESS_DENIED
+        5 => format!("Zugriff verweigert auf {}.", path),
+        // ERROR_PATH_NOT_FOUND
+        3 => format!("Der Pfad {} wurde nicht gefunden.", path),
+        // ERROR_NOT_READY
+        21 => String::from("Das Laufwerk ist nicht bereit. Ist die externe Festplatte angeschlossen?"),
+        // ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION
+        32 | 33 => format!("{} wird von einem anderen Programm verwendet.", path),
+        // ERROR_HANDLE_DISK_FULL, ERROR_DISK_FULL
+        39 | 112 => String::from("Das Zielaufwerk ist voll."),
+        _ => return None,
+    };
+    Some(advice)
+}
+
+/// Translates the OS error contained in the given error message into advice for the user.
+///
+/// # Returns
+/// [None] if the message doesn't contain an OS error or there is no advice for it.
+pub fn error_advice(msg: &str) -> Option<String> {
+    os_error_advice(os_error_code(msg)?, quoted_path(msg).as_deref())
+}
+
+#[cfg(test)]
+mod localization_tests {
+    use super::*;
+
+    #[test]
+    fn extract_code() {
+        let msg = r#"Couldn't finish archive because of Io(Os { code: 112, kind: StorageFull, message: "Nicht genügend Speicherplatz" })"#;
+        assert_eq!(os_error_code(msg), Some(112));
+        assert_eq!(os_error_code("Archive \"x.zip\" already exists"), None);
+    }
+
+    #[test]
+    fn mapped_codes() {
+        assert_eq!(os_error_advice(112, None), Some(String::from("Das Zielaufwerk ist voll.")));
+        assert_eq!(os_error_advice(39, None), os_error_advice(112, None));
+        assert_eq!(
+            os_error_advice(5, Some("E:\\backups")),
+            Some(String::from("Zugriff verweigert auf E:\\backups."))
+        );
+        assert!(os_error_advice(5, None).unwrap().contains("das Zielverzeichnis"));
+        assert!(os_error_advice(3, Some("E:\\backups")).unwrap().contains("E:\\backups"));
+        assert!(os_error_advice(21, None).is_some());
+        assert!(os_error_advice(32, Some("C:\\a.txt")).unwrap().starts_with("C:\\a.txt"));
+        assert_eq!(os_error_advice(1234, None), None);
+    }
+
+    #[test]
+    fn advice_from_message() {
+        let msg = r#"Error creating file "E:\\backups\\x.tmp": Os { code: 5, kind: PermissionDenied, message: "Zugriff verweigert" }"#;
+        assert_eq!(
+            error_advice(msg),
+            Some(String::from("Zugriff verweigert auf E:\\backups\\x.tmp."))
+        );
+        assert_eq!(error_advice("Quota exceeded"), None);
+    }
+}
@@ -0,0 +1,208 @@
+//! Contains the lock that keeps two runs (e.g., a scheduled one and a manual one) from handling the same profile at the same time.
+//!
+//! The lock of a profile is the file `<uuid>.lock` in the profile configs dir, containing the PID of the process holding it.
+//! Locks of processes that aren't running anymore are stale and taken over.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, SystemTime},
+};
+
+use config::profile_config::ProfileConfig;
+use log::{debug, warn};
+use uuid::Uuid;
+
+#[cfg(target_family = "windows")]
+mod windows;
+
+/// Exit code if a profile was skipped because another process is handling it
+pub const ALREADY_RUNNING: exitcode::ExitCode = exitcode::TEMPFAIL;
+
+/// Locks without a PID that are older than this were left by a process that crashed while creating them
+const UNFINISHED_LOCK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Why the lock of a profile couldn't be acquired.
+#[derive(Debug, PartialEq)]
+pub enum LockError {
+    /// The running process with the given PID holds the lock. [None] if the PID isn't known yet.
+    Held(Option<u32>),
+    /// The lock file couldn't be created or read
+    Io(String),
+}
+
+/// Lock of a profile, which is released when it is dropped.
+#[derive(Debug)]
+pub struct ProfileLock {
+    path: PathBuf,
+}
+
+impl ProfileLock {
+    /// Acquires the lock of the profile with the given [Uuid] in `dir`. A stale lock is taken over.
+    ///
+    /// # Errors
+    /// Returns [LockError::Held] if another running process holds the lock and [LockError::Io] if the lock file couldn't be created.
+    pub fn acquire(dir: &Path, uuid: &Uuid) -> Result<ProfileLock, LockError> {
+        let path = lock_path(dir, uuid);
+        // only one attempt may take over a stale lock, others find the new lock
+        for attempt in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    let written = write!(file, "{}", process::id());
+                    let lock = ProfileLock { path };
+                    return match written {
+                        Ok(_) => Ok(lock),
+                        Err(err) => Err(LockError::Io(format!("Couldn't write {:?} because of {:?}", lock.path, err))),
+                    };
+                }
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                    let holder = fs::read_to_string(&path).ok().and_then(|pid| pid.trim().parse::<u32>().ok());
+                    let stale = match holder {
+                        Some(pid) => !is_running(pid),
+                        // a lock that is just being created has no PID yet
+                        None => is_older_than(&path, UNFINISHED_LOCK_TIMEOUT),
+                    };
+                    if attempt > 0 || !stale {
+                        return Err(LockError::Held(holder));
+                    }
+                    warn!("Taking over stale lock {:?} of process {:?}", path, holder);
+                    if let Err(err) = fs::remove_file(&path) {
+                        return Err(LockError::Io(format!("Couldn't remove stale lock {:?} because of {:?}", path, err)));
+                    }
+                }
+                Err(err) => return Err(LockError::Io(format!("Couldn't create {:?} because of {:?}", path, err))),
+            }
+        }
+        Err(LockError::Io(format!("Couldn't acquire {:?}", path)))
+    }
+}
+
+impl Drop for ProfileLock {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            warn!("Couldn't release lock {:?} because of {:?}", self.path, err);
+        }
+    }
+}
+
+/// Returns the path of the lock of the profile with the given [Uuid] in `dir`.
+fn lock_path(dir: &Path, uuid: &Uuid) -> PathBuf {
+    dir.join(format!("{}.lock", uuid.as_hyphenated()))
+}
+
+/// Locks the given profile for the rest of the current command.
+/// If the lock couldn't be created, e.g., because the profile configs dir is read-only, the profile is handled without it.
+///
+/// # Returns
+/// The lock, or [None] if the profile is handled without it.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if another process is already handling the profile.
+pub fn lock_profile(dir: &Path, profile_config: &ProfileConfig) -> Result<Option<ProfileLock>, String> {
+    match ProfileLock::acquire(dir, profile_config.get_uuid()) {
+        Ok(lock) => {
+            debug!("Locked {:?}", profile_config.name);
+            Ok(Some(lock))
+        }
+        Err(LockError::Held(pid)) => Err(format!(
+            "{:?} is already running in {}, so it is skipped",
+            profile_config.name,
+            pid.map_or_else(|| String::from("another process"), |pid| format!("process {}", pid))
+        )),
+        Err(LockError::Io(msg)) => {
+            warn!("Handling {:?} without lock: {}", profile_config.name, msg);
+            Ok(None)
+        }
+    }
+}
+
+/// Checks if the lock at `path` was last modified longer than `timeout` ago.
+fn is_older_than(path: &Path, timeout: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map_or(false, |age| age > timeout)
+}
+
+/// Checks if a process with the given PID is running. If that can't be determined, it is assumed to be running.
+fn is_running(pid: u32) -> bool {
+    #[cfg(target_family = "windows")]
+    return windows::is_running(pid);
+    #[cfg(not(target_family = "windows"))]
+    {
+        let proc = Path::new("/proc");
+        !proc.is_dir() || proc.join(pid.to_string()).exists()
+    }
+}
+
+#[cfg(test)]
+mod lock_tests {
+    use super::*;
+
+    #[test]
+    fn second_lock_is_refused() {
+        let dir = PathBuf::from("./test_dir/lock_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let uuid = Uuid::new_v4();
+
+        let first = ProfileLock::acquire(&dir, &uuid);
+        let content = fs::read_to_string(lock_path(&dir, &uuid)).ok();
+        let second = ProfileLock::acquire(&dir, &uuid);
+        let other_profile = ProfileLock::acquire(&dir, &Uuid::new_v4());
+        drop(first);
+        let released = !lock_path(&dir, &uuid).exists();
+        let again = ProfileLock::acquire(&dir, &uuid);
+        let other_profile_locked = other_profile.is_ok();
+        let locked_again = again.is_ok();
+        drop(other_profile);
+        drop(again);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(content, Some(process::id().to_string()));
+        assert_eq!(second.unwrap_err(), LockError::Held(Some(process::id())));
+        assert!(other_profile_locked);
+        assert!(released);
+        assert!(locked_again);
+    }
+
+    #[test]
+    fn stale_lock_is_taken_over() {
+        let dir = PathBuf::from("./test_dir/stale_lock_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let uuid = Uuid::new_v4();
+        // PIDs are far below this on all supported systems
+        fs::write(lock_path(&dir, &uuid), (u32::MAX - 1).to_string()).unwrap();
+
+        let lock = ProfileLock::acquire(&dir, &uuid);
+        let content = fs::read_to_string(lock_path(&dir, &uuid)).ok();
+        drop(lock);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(content, Some(process::id().to_string()));
+    }
+
+    #[test]
+    fn lock_without_pid() {
+        let dir = PathBuf::from("./test_dir/lock_without_pid_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let uuid = Uuid::new_v4();
+        fs::write(lock_path(&dir, &uuid), "").unwrap();
+
+        let fresh = ProfileLock::acquire(&dir, &uuid);
+        let old = is_older_than(&lock_path(&dir, &uuid), Duration::ZERO);
+        fs::remove_dir_all(&dir).unwrap();
+
+        // another process may still be writing its PID
+        assert_eq!(fresh.unwrap_err(), LockError::Held(None));
+        assert!(old);
+    }
+
+    #[test]
+    fn missing_dir() {
+        let lock = ProfileLock::acquire(Path::new("./test_dir/doesnt_exist"), &Uuid::new_v4());
+        assert!(matches!(lock, Err(LockError::Io(_))));
+    }
+}
@@ -0,0 +1,20 @@
+//! Contains the windows implementation of checking for running processes
+
+use windows::Win32::{
+    Foundation::{CloseHandle, ERROR_INVALID_PARAMETER, STILL_ACTIVE},
+    System::Threading::{GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION},
+};
+
+/// Checks if the process with the given PID is running.
+/// Processes that can't be queried, e.g., because they belong to another user, count as running.
+pub fn is_running(pid: u32) -> bool {
+    let process = match unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) } {
+        Ok(process) => process,
+        // there is no process with that PID
+        Err(err) => return err.code() != ERROR_INVALID_PARAMETER.to_hresult(),
+    };
+    let mut exit_code = 0;
+    let queried = unsafe { GetExitCodeProcess(process, &mut exit_code) };
+    unsafe { CloseHandle(process) };
+    !queried.as_bool() || exit_code == STILL_ACTIVE.0 as u32
+}
@@ -1,18 +1,46 @@
 mod common;
+mod adopt;
+mod archive_format;
+mod audit;
 mod backup;
+mod breakdown;
+mod cancel;
+mod catalog;
+mod contents;
+mod control;
+mod create;
 mod restore;
 mod reschedule;
 mod delete;
 mod cli_args;
 mod config;
 mod dialog;
+mod duplicate;
+mod encryption;
+mod hooks;
+mod localization;
+mod lock;
+mod metrics;
+mod mount;
+mod quota;
 mod scheduler;
+mod status;
+mod summary;
+mod support;
+mod tempfiles;
+mod onboarding;
+mod progress;
+mod prune;
+mod report;
+mod vfs;
+mod volumes;
 
-use std::{process::exit, path::PathBuf};
+use std::{io::{self, IsTerminal}, process::exit, path::{Path, PathBuf}};
 
+use ::config::{control::ControlCommand, profile_config::ProfileConfig};
 use backup::handle_profile;
-use dialog::info_dialog;
-use log::{info, error};
+use dialog::{info_dialog, question_dialog};
+use log::{debug, info, error, warn};
 use reschedule::reschedule;
 use restore::restore;
 use exitcode;
@@ -30,6 +58,65 @@ fn init_logger(path: &PathBuf) {
     }
 }
 
+/// Limits the logged levels to the `log_level` of the [GeneralConfig](::config::GeneralConfig), if it is set.
+fn apply_log_level(log_level: &Option<String>) {
+    if let Some(log_level) = log_level {
+        match log_level.parse::<log::LevelFilter>() {
+            Ok(level) => {
+                info!("Setting log level to {}", level);
+                log::set_max_level(level);
+            }
+            Err(_) => warn!("Ignoring invalid log level {:?}", log_level),
+        }
+    }
+}
+
+/// Opens the file of the first log appender configured in the logger config file at `logger_config`.
+fn open_log(logger_config: &str) {
+    let log_file = match config::log_file_paths(&PathBuf::from(logger_config)) {
+        Ok(paths) => paths.into_iter().next(),
+        Err(msg) => {
+            error!("Couldn't find log file: {}", msg);
+            return;
+        }
+    };
+    if let Some(log_file) = log_file {
+        if let Err(err) = std::process::Command::new("explorer").arg(&log_file).spawn() {
+            error!("Couldn't open {:?} because {:?}", log_file, err);
+        }
+    }
+}
+
+/// Whether someone started the backupper from a terminal, unlike scheduled runs, which have no console.
+fn interactive() -> bool {
+    io::stderr().is_terminal()
+}
+
+/// Locks the given profile (see [lock::lock_profile]) and removes the stale temporary files that interrupted runs left in its target dir.
+/// Without the lock, another run could still be writing them, so nothing is removed in that case.
+fn lock_profile(dir: &Path, profile_config: &ProfileConfig) -> Result<Option<lock::ProfileLock>, String> {
+    let lock = lock::lock_profile(dir, profile_config)?;
+    if let (Some(_), Ok(resolved)) = (&lock, profile_config.resolve_paths()) {
+        if resolved.target_dir.is_dir() {
+            tempfiles::cleanup_target_dir(&resolved.target_dir);
+        }
+    }
+    Ok(lock)
+}
+
+/// Determines which files a restore with the given arguments chooses and where they are written.
+fn restore_selection(restore_params: &cli_args::Restore) -> restore::Selection {
+    restore::Selection {
+        subtrees: restore_params.subtrees.clone(),
+        strip_prefix: restore_params.strip_prefix,
+        output_dir: restore_params.output_dir.clone(),
+        on_conflict: restore_params.on_conflict.unwrap_or_else(|| {
+            warn!("Existing files are overwritten. A future version will only overwrite older files, pass --on-conflict overwrite to keep overwriting them");
+            restore::ConflictPolicy::Overwrite
+        }),
+    }
+}
+
 fn main() {
     let orig_path = match std::env::current_dir() {
         Ok(path) => Some(path),
@@ -50,15 +137,50 @@ fn main() {
         exit(exitcode::OSERR);
     }
 
+    // started without arguments on a fresh machine (e.g., by double-click)
+    if onboarding::is_interactive_start()
+        && onboarding::is_unconfigured(
+            &PathBuf::from(config::GENERAL_CONFIG_PATH),
+            &PathBuf::from(config::LOGGER_CONFIG_PATH),
+        )
+    {
+        if let Err(msg) = onboarding::onboarding() {
+            eprintln!("Onboarding failed: {}", msg);
+            exit(exitcode::CANTCREAT);
+        }
+        return;
+    }
+
     let args = cli_args::get_args();
     init_logger(&PathBuf::from(&args.logger_config));
-    let general_config = match config::load_general_config(Some(&args.general_config)) {
+
+    // the profile of an explicit archive may be lost, so neither it nor the general config are loaded
+    if let cli_args::Commands::Restore(restore_params @ cli_args::Restore { archive: Some(archive), .. }) = &args.command {
+        let cancellation = cancel::Cancellation::default();
+        cancel::install_handler(cancellation.clone());
+        let outcome = restore::restore_archive(archive, restore_params.protects_newer(interactive()), &restore_selection(restore_params), &cancellation);
+        let exit_code = match outcome {
+            Ok(outcome) if outcome.matched == 0 && !restore_params.subtrees.is_empty() => {
+                error!("No file of {:?} is below {:?}", archive, restore_params.subtrees);
+                restore::NO_MATCH
+            }
+            Ok(_) if cancellation.is_cancelled() => cancel::EXIT_CODE,
+            Ok(outcome) => outcome.exit_code(),
+            Err(msg) => {
+                error!("{}", msg);
+                restore::NOT_RESTORED
+            }
+        };
+        exit(exit_code);
+    }
+    let general_config = match config::load_general_config(Some(&args.general_config), !args.no_create) {
         Ok(config) => config,
-        Err(msg) => {
-            error!("Error loading general config: {}", msg);
-            exit(exitcode::UNAVAILABLE);
+        Err(err) => {
+            error!("Error loading general config: {}", err);
+            exit(config::exit_code(&err));
         }
     };
+    apply_log_level(&general_config.log_level);
 
     info!("Loaded general config: {:?}", general_config);
 
@@ -71,31 +193,480 @@ fn main() {
     };
 
     info!("Loaded {} profile configs.", profile_configs.len());
+    for profile_config in &profile_configs {
+        if let Err(msg) = profile_config.interval.satisfiability_check() {
+            warn!(
+                "The interval of {:?} never matches, so it won't be backed up: {}",
+                profile_config.name, msg
+            );
+        }
+        if let Err(msg) = profile_config.validate_patterns() {
+            warn!("{} of {:?} is ignored", msg, profile_config.name);
+        }
+    }
+
+    // commands that change something mustn't guess which profile was meant, the others operate on all matching ones
+    if !args.all_matching {
+        if let Err(msg) = config::check_unambiguous(&profile_configs, &args) {
+            if args.command.is_modifying() {
+                error!("{}", msg);
+                exit(exitcode::USAGE);
+            }
+            debug!("{}", msg);
+            info!(
+                "The name {:?} matches {} profiles, operating on all of them",
+                args.name.as_deref().unwrap_or_default(),
+                profile_configs.len()
+            );
+        }
+    }
 
     info!("Running subcommand {:?}", args.command);
+    let mut exit_code = exitcode::OK;
 
     match &args.command {
         cli_args::Commands::Backup => {
+            let own_paths = config::own_paths(&general_config, &args.general_config, &args.logger_config);
+            debug!("Skipping own paths {:?}", own_paths);
+            // scheduled runs have no console to show the progress in
+            let mut progress: Box<dyn progress::ProgressSink> = if io::stderr().is_terminal() {
+                Box::new(progress::ConsoleProgress::default())
+            } else {
+                Box::new(progress::SilentProgress)
+            };
+            let cancellation = cancel::Cancellation::default();
+            cancel::install_handler(cancellation.clone());
+            cancel::install_stop_handler(cancellation.clone());
+            let mut outcomes = vec![];
             for mut profile_config in profile_configs {
-                handle_profile(&mut profile_config, &general_config, &args)
+                if cancellation.is_stopped() {
+                    break;
+                }
+                let _lock = match lock_profile(&general_config.profile_configs, &profile_config) {
+                    Ok(lock) => lock,
+                    Err(msg) => {
+                        error!("{}", msg);
+                        exit_code = lock::ALREADY_RUNNING;
+                        continue;
+                    }
+                };
+                // the tray and the server pause, resume and stop the backup through its control channel
+                let _control = match control::ControlServer::start(profile_config.get_uuid(), cancellation.clone()) {
+                    Ok(control) => Some(control),
+                    Err(msg) => {
+                        warn!("Couldn't open the control channel of {:?}: {}", profile_config.name, msg);
+                        None
+                    }
+                };
+                let outcome = handle_profile(
+                    &mut profile_config,
+                    &general_config,
+                    &args,
+                    &own_paths,
+                    progress.as_mut(),
+                    &cancellation,
+                );
+                if profile_config.is_read_only() {
+                    exit_code = exitcode::NOPERM;
+                }
+                outcomes.push(outcome);
+            }
+            if args.verbose {
+                for outcome in outcomes.iter().filter(|outcome| !outcome.stats.metrics.is_empty()) {
+                    println!("{:?}: {}", outcome.name, outcome.stats.metrics);
+                }
+            }
+            // failed backups matter more than profiles that couldn't be stored
+            if summary::any_failed(&outcomes) {
+                exit_code = summary::exit_code(&outcomes);
+            }
+            if cancellation.is_cancelled() {
+                exit_code = cancel::EXIT_CODE;
+            } else if cancellation.is_stopped() {
+                exit_code = cancel::STOPPED_EXIT_CODE;
+            }
+
+            let (title, heading) = summary::completion_heading(&outcomes);
+            let msg = format!(
+                "{}\n\n{}\n\n{}",
+                heading,
+                summary::format_summary(&outcomes),
+                summary::format_totals(&outcomes)
+            );
+            // whoever cancelled or stopped the backup already knows and doesn't need a dialog
+            if !general_config.show_dialogs || cancellation.is_stopped() {
+                info!("{}", msg);
+            } else if summary::any_warned(&outcomes) {
+                let choice = question_dialog(title, &format!("{}\n\nLog öffnen?", msg));
+                if choice == dialog::DialogResult(dialog::YES) {
+                    open_log(&args.logger_config);
+                }
+            } else {
+                info_dialog(title, &msg);
             }
-        
-            info_dialog("Backup Abgeschlossen", "Das Backup ist abgeschlossen. Die externe Festplatte kann jetzt entfernt werden.");
         },
         cli_args::Commands::Restore(restore_params) => {
             let timestamp = restore_params.timestamp.unwrap_or_else(|| chrono::Local::now().naive_local());
-            for profile_config in profile_configs {
-                restore(&profile_config, timestamp, &args);
+            let selection = restore_selection(restore_params);
+            let cancellation = cancel::Cancellation::default();
+            cancel::install_handler(cancellation.clone());
+            let mut previews = vec![];
+            let mut outcomes = vec![];
+            for profile_config in config::resolve_paths(&profile_configs) {
+                if cancellation.is_cancelled() {
+                    break;
+                }
+                let _lock = match lock_profile(&general_config.profile_configs, &profile_config) {
+                    Ok(lock) => lock,
+                    Err(msg) => {
+                        error!("{}", msg);
+                        exit_code = lock::ALREADY_RUNNING;
+                        continue;
+                    }
+                };
+                let matched = if restore_params.dry_run {
+                    let preview = restore::preview(
+                        &profile_config,
+                        timestamp,
+                        restore_params.protects_newer(interactive()),
+                        &selection,
+                        general_config.show_dialogs,
+                    );
+                    let matched = match &preview {
+                        Ok(preview) => Some(preview.matched),
+                        Err(msg) => {
+                            error!("Couldn't preview the restore of {:?}: {}", profile_config.name, msg);
+                            exit_code = restore::NOT_RESTORED;
+                            None
+                        }
+                    };
+                    let preview = preview.unwrap_or_default();
+                    if restore_params.json {
+                        let mut json = preview.to_json();
+                        json["name"] = serde_json::json!(profile_config.name);
+                        json["uuid"] = serde_json::json!(profile_config.get_uuid());
+                        previews.push(json);
+                    } else {
+                        println!("{:?}:", profile_config.name);
+                        for line in preview.lines() {
+                            println!("  {}", line);
+                        }
+                    }
+                    matched
+                } else {
+                    let outcome = restore(
+                        &profile_config,
+                        timestamp,
+                        restore_params.protects_newer(interactive()),
+                        &selection,
+                        general_config.show_dialogs,
+                        &cancellation,
+                    );
+                    match outcome {
+                        Ok(outcome) => {
+                            let matched = outcome.matched;
+                            if outcome.exit_code() != exitcode::OK {
+                                exit_code = outcome.exit_code();
+                            }
+                            outcomes.push((profile_config.name.clone(), Ok(outcome)));
+                            Some(matched)
+                        }
+                        Err(msg) => {
+                            error!("Couldn't restore {:?}: {}", profile_config.name, msg);
+                            exit_code = restore::NOT_RESTORED;
+                            outcomes.push((profile_config.name.clone(), Err(msg)));
+                            None
+                        }
+                    }
+                };
+                if !selection.subtrees.is_empty() && matched == Some(0) {
+                    error!("No file of the backup of {:?} is below {:?}", profile_config.name, selection.subtrees);
+                    exit_code = restore::NO_MATCH;
+                }
+            }
+            if restore_params.json {
+                println!("{}", serde_json::Value::Array(previews));
+            }
+            if cancellation.is_cancelled() {
+                exit_code = cancel::EXIT_CODE;
+            }
+            // whoever cancelled is at the console and doesn't need a dialog
+            if general_config.show_dialogs && !cancellation.is_cancelled() && !outcomes.is_empty() {
+                let title = if exit_code == exitcode::OK {
+                    "Wiederherstellung abgeschlossen"
+                } else {
+                    "Wiederherstellung fehlgeschlagen"
+                };
+                let msg: Vec<String> = outcomes
+                    .iter()
+                    .map(|(name, outcome)| match outcome {
+                        Ok(outcome) => format!("{:?}: {}", name, outcome.summary()),
+                        Err(msg) => format!("{:?}: {}", name, msg),
+                    })
+                    .collect();
+                info_dialog(title, &msg.join("\n\n"));
             }
         },
         cli_args::Commands::Reschedule => for mut profile_config in profile_configs {
             reschedule(&mut profile_config, &general_config);
         },
         cli_args::Commands::Delete(delete_params) => {
-            for profile_config in profile_configs {
-                delete::delete(&profile_config, &general_config, delete_params.remove_backups);
+            for profile_config in config::resolve_paths(&profile_configs) {
+                let _lock = match lock_profile(&general_config.profile_configs, &profile_config) {
+                    Ok(lock) => lock,
+                    Err(msg) => {
+                        error!("{}", msg);
+                        exit_code = lock::ALREADY_RUNNING;
+                        continue;
+                    }
+                };
+                if delete_params.remove_backups && !delete_params.include_former && !profile_config.former_uuids.is_empty() {
+                    info!(
+                        "{:?} has backups from {} former uuids. Use --include-former to remove them as well.",
+                        profile_config.name,
+                        profile_config.former_uuids.len()
+                    );
+                }
+                delete::delete(&profile_config, &general_config, delete_params.remove_backups, delete_params.include_former);
             }
         }
+        cli_args::Commands::Adopt => for mut profile_config in profile_configs {
+            let report = adopt::adopt(&mut profile_config, &general_config);
+            println!("{}", adopt::format_report(&report));
+        },
+        cli_args::Commands::Archives(archives_params) => match archives_params.command {
+            cli_args::ArchivesCommands::Upgrade { dry_run } => for profile_config in config::resolve_paths(&profile_configs) {
+                match archive_format::upgrade_archives(&profile_config, dry_run) {
+                    Ok(results) => for (path, res) in results {
+                        match res {
+                            Ok(format) if dry_run => println!("{}: {:?}", path.display(), format),
+                            Ok(format) => println!("{}: upgraded from {:?}", path.display(), format),
+                            Err(msg) => println!("{}: {}", path.display(), msg),
+                        }
+                    },
+                    Err(msg) => error!("Couldn't upgrade archives of {:?}: {}", profile_config.name, msg),
+                }
+            },
+        },
+        cli_args::Commands::SupportBundle(bundle_params) => {
+            let log_files = config::log_file_paths(&PathBuf::from(&args.logger_config)).unwrap_or_else(|msg| {
+                error!("Couldn't find log files: {}", msg);
+                vec![]
+            });
+            match support::write_support_bundle(
+                &general_config,
+                &profile_configs,
+                &log_files,
+                bundle_params.redact,
+                &bundle_params.output,
+            ) {
+                Ok(_) => println!("Wrote support bundle to {:?}", bundle_params.output),
+                Err(msg) => {
+                    error!("{}", msg);
+                    exit_code = exitcode::CANTCREAT;
+                }
+            }
+        },
+        cli_args::Commands::ListBackups(list_params) => {
+            let mut inventories = vec![];
+            for profile_config in config::resolve_paths(&profile_configs) {
+                match ::config::backup_inventory::backup_inventory(&profile_config) {
+                    Ok(backups) if list_params.json => inventories.push(serde_json::json!({
+                        "name": profile_config.name,
+                        "uuid": profile_config.get_uuid(),
+                        "backups": backups,
+                    })),
+                    Ok(backups) => {
+                        println!("{:?}:", profile_config.name);
+                        for backup in &backups {
+                            println!("  {}", report::format_backup(backup));
+                        }
+                    }
+                    Err(msg) => {
+                        error!("Couldn't list the backups of {:?}: {}", profile_config.name, msg);
+                        exit_code = exitcode::IOERR;
+                    }
+                }
+            }
+            if list_params.json {
+                println!("{}", serde_json::Value::Array(inventories));
+            }
+        },
+        cli_args::Commands::Show(show_params) => {
+            let filter = show_params.filter.as_deref().map(|pattern| {
+                contents::entry_filter(pattern).unwrap_or_else(|msg| {
+                    error!("{}", msg);
+                    exit(exitcode::USAGE);
+                })
+            });
+            // the entries are printed while reading, so the JSON array is written piece by piece
+            let mut first = true;
+            if show_params.json {
+                print!("[");
+            }
+            for profile_config in config::resolve_paths(&profile_configs) {
+                let archive = match &show_params.archive {
+                    Some(archive) => Ok(archive.clone()),
+                    None => contents::archive_at(
+                        &profile_config,
+                        show_params.timestamp.unwrap_or_else(|| chrono::Local::now().naive_local()),
+                    ),
+                };
+                let listed = archive.and_then(|archive| {
+                    if !show_params.json {
+                        println!("{:?} ({}):", profile_config.name, archive.display());
+                    }
+                    contents::list_contents(&profile_config, &archive, filter.as_ref(), |entry| {
+                        if show_params.json {
+                            let mut json = entry.to_json();
+                            json["name"] = serde_json::json!(profile_config.name);
+                            json["uuid"] = serde_json::json!(profile_config.get_uuid());
+                            json["archive"] = serde_json::json!(archive);
+                            print!("{}{}", if first { "" } else { "," }, json);
+                            first = false;
+                        } else {
+                            println!("  {}", entry.line());
+                        }
+                    })
+                });
+                if let Err(msg) = listed {
+                    error!("Couldn't show the archive of {:?}: {}", profile_config.name, msg);
+                    exit_code = exitcode::NOINPUT;
+                }
+            }
+            if show_params.json {
+                println!("]");
+            }
+        },
+        cli_args::Commands::Audit => for profile_config in profile_configs {
+            match audit::format_audit_log(&profile_config, &general_config) {
+                Ok(log) => println!("{:?}:\n{}", profile_config.name, log),
+                Err(msg) => error!("{}", msg),
+            }
+        },
+        cli_args::Commands::Prune => for profile_config in config::resolve_paths(&profile_configs) {
+            match prune::prune(&profile_config) {
+                Ok(deleted) => println!("{:?}: deleted {} archives", profile_config.name, deleted),
+                Err(msg) => error!("Couldn't prune archives of {:?}: {}", profile_config.name, msg),
+            }
+        },
+        cli_args::Commands::Validate => for profile_config in profile_configs {
+            match profile_config.validate() {
+                Ok(_) => println!("{:?}: ok", profile_config.name),
+                Err(msgs) => {
+                    println!("{:?}:", profile_config.name);
+                    for msg in msgs {
+                        println!("    {}", msg);
+                    }
+                    exit_code = exitcode::DATAERR;
+                }
+            }
+        },
+        cli_args::Commands::Create(create_params) => {
+            match create::create(&general_config, &create_params.new_name, create_params.preset) {
+                Ok(profile_config) => println!("Created {:?} ({})", profile_config.name, profile_config.get_uuid()),
+                Err(msg) => {
+                    error!("{}", msg);
+                    exit_code = exitcode::CANTCREAT;
+                }
+            }
+        },
+        cli_args::Commands::Duplicate(duplicate_params) => {
+            if profile_configs.len() != 1 {
+                error!("Select exactly one profile to duplicate, but {} were selected", profile_configs.len());
+                exit(exitcode::USAGE);
+            }
+            match duplicate::duplicate(&profile_configs[0], &general_config, &duplicate_params.new_name) {
+                Ok(copy) => println!("Created {:?} ({})", copy.name, copy.get_uuid()),
+                Err(msg) => {
+                    error!("{}", msg);
+                    exit_code = exitcode::CANTCREAT;
+                }
+            }
+        },
+        cli_args::Commands::Control(control_params) => {
+            let command = match control_params.command {
+                cli_args::ControlCommands::Pause => ControlCommand::Pause,
+                cli_args::ControlCommands::Resume => ControlCommand::Resume,
+                cli_args::ControlCommands::Status => ControlCommand::Status,
+                cli_args::ControlCommands::Stop => ControlCommand::Stop,
+            };
+            for profile_config in profile_configs {
+                match ::config::control::send_command(profile_config.get_uuid(), command) {
+                    Ok(status) => println!("{:?}: {:?} (pid {})", profile_config.name, status.state, status.pid),
+                    Err(msg) => {
+                        error!("{:?}: {}", profile_config.name, msg);
+                        exit_code = exitcode::UNAVAILABLE;
+                    }
+                }
+            }
+        },
+        cli_args::Commands::Find(find_params) => {
+            if let Err(msg) = ::config::backup_catalog::path_matcher(&find_params.pattern) {
+                error!("{}", msg);
+                exit(exitcode::USAGE);
+            }
+            let uuids: Vec<uuid::Uuid> = profile_configs.iter().map(|profile_config| *profile_config.get_uuid()).collect();
+            match ::config::backup_catalog::search(&general_config.profile_configs, Some(&uuids), &find_params.pattern, find_params.limit) {
+                Ok(found) if find_params.json => println!("{}", serde_json::json!(found)),
+                Ok(found) => {
+                    for entry in &found {
+                        let name = profile_configs
+                            .iter()
+                            .find(|profile_config| profile_config.get_uuid() == &entry.profile_uuid)
+                            .map_or("", |profile_config| profile_config.name.as_str());
+                        println!("{}  {:>12}  {}  ({:?}: {})", entry.archived.format("%Y-%m-%d %H:%M"), entry.size, entry.path, name, entry.archive.display());
+                    }
+                    if found.is_empty() {
+                        println!("No archived file matches {:?}. If the catalog is missing, run `backupper catalog rebuild`", find_params.pattern);
+                    }
+                }
+                Err(msg) => {
+                    error!("Couldn't search the catalogs: {}", msg);
+                    exit_code = exitcode::IOERR;
+                }
+            }
+        },
+        cli_args::Commands::Catalog(catalog_params) => match catalog_params.command {
+            cli_args::CatalogCommands::Rebuild => for profile_config in config::resolve_paths(&profile_configs) {
+                match catalog::rebuild(&profile_config, &general_config.profile_configs) {
+                    Ok(added) => println!("{:?}: cataloged {} archives", profile_config.name, added),
+                    Err(msg) => {
+                        error!("Couldn't rebuild the catalog of {:?}: {}", profile_config.name, msg);
+                        exit_code = exitcode::IOERR;
+                    }
+                }
+            },
+        },
+        cli_args::Commands::Mount(mount_params) => {
+            if profile_configs.len() != 1 {
+                error!("Select exactly one profile to mount an archive of, but {} were selected", profile_configs.len());
+                exit(exitcode::USAGE);
+            }
+            let cancellation = cancel::Cancellation::default();
+            cancel::install_handler(cancellation.clone());
+            let mounted = match config::resolve_paths(&profile_configs).first() {
+                Some(profile_config) => mount::mount(profile_config, &mount_params.archive, &mount_params.at, &cancellation),
+                None => Err(String::from("The paths of the profile couldn't be resolved")),
+            };
+            if let Err(msg) = mounted {
+                error!("Couldn't mount {:?}: {}", mount_params.archive, msg);
+                exit_code = exitcode::UNAVAILABLE;
+            }
+        },
+        cli_args::Commands::Status => for profile_config in profile_configs {
+            let report = profile_config.last_backup_archive.as_ref().and_then(|archive| report::read_report(archive));
+            let resolved = profile_config.resolve_paths();
+            let clock_skew = ::config::backup_inventory::clock_skew(resolved.as_ref().unwrap_or(&profile_config), chrono::Local::now().naive_local());
+            println!("{}", status::format_status(&profile_config, report.as_ref(), clock_skew));
+        },
+        cli_args::Commands::Explain(explain_params) => {
+            let at = explain_params.at.unwrap_or_else(|| chrono::Local::now().naive_local());
+            for profile_config in profile_configs {
+                println!("{:?} at {}:", profile_config.name, at);
+                println!("{}", profile_config.interval.explain_match(at));
+            }
+        },
     }
 
     if let Some(orig_path) = orig_path {
@@ -104,4 +675,5 @@ fn main() {
             exit(exitcode::OSERR);
         }
     }
+    exit(exit_code);
 }
@@ -0,0 +1,191 @@
+//! Contains a collector for the costs of a backup: wall time per phase, bytes read and written and, where available, CPU time and memory usage.
+
+use std::time::{Duration, Instant};
+
+#[cfg(target_family = "windows")]
+mod windows;
+
+/// Phase of a backup that idles while it is paused. It isn't part of the [Metrics::total], so pauses don't distort the costs.
+pub const PAUSED_PHASE: &str = "paused";
+
+/// Collects the costs of a backup.
+/// Phases are timed one after another. Timing a phase again adds to its previous duration.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Finished phases with their accumulated duration, in the order they were first started
+    phases: Vec<(&'static str, Duration)>,
+    /// Phase that is currently timed
+    running: Option<(&'static str, Instant)>,
+    /// CPU time of the process when the collector was created
+    cpu_start: Option<Duration>,
+    /// Number of bytes read from the backed up files
+    pub bytes_read: u64,
+    /// Size of the written archive in bytes
+    pub bytes_written: u64,
+}
+
+impl Metrics {
+    /// Creates a collector and remembers the current CPU time of the process.
+    pub fn new() -> Metrics {
+        Metrics {
+            cpu_start: process_cpu_time(),
+            ..Default::default()
+        }
+    }
+
+    /// Finishes the running phase and starts timing the phase with the given `name`.
+    pub fn start_phase(&mut self, name: &'static str) {
+        self.start_phase_at(name, Instant::now());
+    }
+
+    /// Finishes the running phase, if there is one.
+    pub fn finish_phase(&mut self) {
+        self.finish_phase_at(Instant::now());
+    }
+
+    fn start_phase_at(&mut self, name: &'static str, now: Instant) {
+        self.finish_phase_at(now);
+        self.running = Some((name, now));
+    }
+
+    fn finish_phase_at(&mut self, now: Instant) {
+        let (name, start) = match self.running.take() {
+            Some(running) => running,
+            None => return,
+        };
+        let duration = now.saturating_duration_since(start);
+        match self.phases.iter_mut().find(|(phase, _)| *phase == name) {
+            Some((_, total)) => *total += duration,
+            None => self.phases.push((name, duration)),
+        }
+    }
+
+    /// Returns the accumulated duration of the finished phase with the given `name`.
+    #[cfg(test)]
+    pub fn phase(&self, name: &str) -> Option<Duration> {
+        self.phases
+            .iter()
+            .find(|(phase, _)| *phase == name)
+            .map(|(_, duration)| *duration)
+    }
+
+    /// Returns the sum of all finished phases, except for the [PAUSED_PHASE].
+    pub fn total(&self) -> Duration {
+        self.phases
+            .iter()
+            .filter(|(phase, _)| *phase != PAUSED_PHASE)
+            .map(|(_, duration)| *duration)
+            .sum()
+    }
+
+    /// Returns the CPU time the process spent since the collector was created.
+    /// [None] if it isn't available on this platform.
+    pub fn cpu_time(&self) -> Option<Duration> {
+        Some(process_cpu_time()?.saturating_sub(self.cpu_start?))
+    }
+
+    /// Formats all metrics as a single line. Metrics that aren't available are shown as `n/a`.
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = self
+            .phases
+            .iter()
+            .map(|(name, duration)| format!("{} {:.2}s", name, duration.as_secs_f64()))
+            .collect();
+        parts.push(format!("total {:.2}s", self.total().as_secs_f64()));
+        parts.push(format!("read {} bytes", self.bytes_read));
+        parts.push(format!("written {} bytes", self.bytes_written));
+        parts.push(match self.cpu_time() {
+            Some(cpu_time) => format!("cpu {:.2}s", cpu_time.as_secs_f64()),
+            None => String::from("cpu n/a"),
+        });
+        parts.push(match peak_working_set() {
+            Some(bytes) => format!("peak working set {} bytes", bytes),
+            None => String::from("peak working set n/a"),
+        });
+        parts.join(", ")
+    }
+}
+
+/// Returns the CPU time (kernel and user) the process has spent so far, or [None] if it isn't available.
+fn process_cpu_time() -> Option<Duration> {
+    #[cfg(target_family = "windows")]
+    return windows::process_cpu_time();
+    #[cfg(not(target_family = "windows"))]
+    None
+}
+
+/// Returns the peak working set of the process in bytes, or [None] if it isn't available.
+fn peak_working_set() -> Option<u64> {
+    #[cfg(target_family = "windows")]
+    return windows::peak_working_set();
+    #[cfg(not(target_family = "windows"))]
+    None
+}
+
+#[cfg(test)]
+mod metrics_tests {
+    use super::*;
+
+    #[test]
+    fn phase_arithmetic() {
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let mut metrics = Metrics::default();
+
+        metrics.start_phase_at("scan", at(0));
+        metrics.start_phase_at("archive", at(100));
+        metrics.start_phase_at("scan", at(350));
+        metrics.finish_phase_at(at(400));
+        // finishing twice doesn't count anything
+        metrics.finish_phase_at(at(1000));
+
+        assert_eq!(metrics.phase("scan"), Some(Duration::from_millis(150)));
+        assert_eq!(metrics.phase("archive"), Some(Duration::from_millis(250)));
+        assert_eq!(metrics.phase("verify"), None);
+        assert_eq!(metrics.total(), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn pauses_arent_counted() {
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let mut metrics = Metrics::default();
+
+        metrics.start_phase_at("archive", at(0));
+        metrics.start_phase_at(PAUSED_PHASE, at(100));
+        metrics.start_phase_at("archive", at(60_100));
+        metrics.finish_phase_at(at(60_300));
+
+        assert_eq!(metrics.phase("archive"), Some(Duration::from_millis(300)));
+        assert_eq!(metrics.phase(PAUSED_PHASE), Some(Duration::from_secs(60)));
+        assert_eq!(metrics.total(), Duration::from_millis(300));
+        assert!(metrics.summary().starts_with("archive 0.30s, paused 60.00s, total 0.30s"));
+    }
+
+    #[test]
+    fn clock_going_backwards() {
+        let start = Instant::now() + Duration::from_secs(1);
+        let mut metrics = Metrics::default();
+        metrics.start_phase_at("scan", start);
+        metrics.finish_phase_at(start - Duration::from_millis(10));
+        assert_eq!(metrics.phase("scan"), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn summary_line() {
+        let start = Instant::now();
+        let mut metrics = Metrics::default();
+        metrics.start_phase_at("scan", start);
+        metrics.start_phase_at("archive", start + Duration::from_millis(500));
+        metrics.finish_phase_at(start + Duration::from_millis(2000));
+        metrics.bytes_read = 1234;
+        metrics.bytes_written = 567;
+
+        let summary = metrics.summary();
+        assert!(!summary.contains('\n'));
+        assert!(summary.starts_with("scan 0.50s, archive 1.50s, total 2.00s, read 1234 bytes, written 567 bytes, cpu "));
+        if cfg!(not(target_family = "windows")) {
+            assert!(summary.ends_with("cpu n/a, peak working set n/a"));
+        }
+    }
+}
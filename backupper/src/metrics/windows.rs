@@ -0,0 +1,54 @@
+//! Contains the windows implementation of the OS counters
+
+use std::{mem, time::Duration};
+
+use windows::Win32::{
+    Foundation::FILETIME,
+    System::{
+        ProcessStatus::{K32GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS},
+        Threading::{GetCurrentProcess, GetProcessTimes},
+    },
+};
+
+/// Converts a [FILETIME], which counts 100-nanosecond intervals, into a [Duration].
+fn to_duration(time: FILETIME) -> Duration {
+    let intervals = ((time.dwHighDateTime as u64) << 32) | time.dwLowDateTime as u64;
+    Duration::from_nanos(intervals * 100)
+}
+
+/// Returns the kernel and user time of the current process.
+pub fn process_cpu_time() -> Option<Duration> {
+    let mut creation = FILETIME::default();
+    let mut exit = FILETIME::default();
+    let mut kernel = FILETIME::default();
+    let mut user = FILETIME::default();
+    let ok = unsafe {
+        GetProcessTimes(
+            GetCurrentProcess(),
+            &mut creation,
+            &mut exit,
+            &mut kernel,
+            &mut user,
+        )
+    };
+    if !ok.as_bool() {
+        return None;
+    }
+    Some(to_duration(kernel) + to_duration(user))
+}
+
+/// Returns the peak working set of the current process in bytes.
+pub fn peak_working_set() -> Option<u64> {
+    let mut counters = PROCESS_MEMORY_COUNTERS::default();
+    let ok = unsafe {
+        K32GetProcessMemoryInfo(
+            GetCurrentProcess(),
+            &mut counters,
+            mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+        )
+    };
+    if !ok.as_bool() {
+        return None;
+    }
+    Some(counters.PeakWorkingSetSize as u64)
+}
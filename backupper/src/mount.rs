@@ -0,0 +1,85 @@
+//! Contains the mounting of backup archives as read-only drives or directories, so single files can be opened without restoring them.
+//!
+//! The files are served by the [ArchiveFs] of the archive through a native filesystem driver (WinFsp or Dokan on Windows, FUSE elsewhere),
+//! which is reached through the [Driver] trait. The mount lasts until the user presses Ctrl-C.
+
+use std::{fs, path::Path};
+
+use config::profile_config::ProfileConfig;
+
+use crate::{
+    cancel::Cancellation,
+    vfs::{ArchiveFs, DEFAULT_CACHE_BLOCKS},
+};
+
+/// Filesystem driver that serves an [ArchiveFs] at a mount point.
+pub trait Driver {
+    /// Serves `fs` at `at` until `cancellation` is cancelled, then unmounts it.
+    ///
+    /// # Errors
+    /// Returns an [Err] describing the issue if the filesystem couldn't be mounted.
+    fn mount(&self, fs: ArchiveFs, at: &Path, cancellation: &Cancellation) -> Result<(), String>;
+}
+
+/// Returns the filesystem driver of the platform.
+///
+/// # Errors
+/// Returns an [Err] describing what to use instead, because this build doesn't contain bindings for WinFsp, Dokan or FUSE yet.
+pub fn native_driver() -> Result<Box<dyn Driver>, String> {
+    Err(String::from(
+        "This build of backupper can't mount archives, because it has no WinFsp, Dokan or FUSE support. \
+        Use `backupper show --archive <archive>` to list the files and `backupper restore --archive <archive> --destination <dir>` to extract them",
+    ))
+}
+
+/// Checks if `at` can be used as mount point: it either doesn't exist yet or is an empty directory.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if `at` is a file or a directory with content.
+pub fn check_mount_point(at: &Path) -> Result<(), String> {
+    if !at.exists() {
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(at).map_err(|e| format!("{:?} can't be used as mount point because of {:?}", at, e))?;
+    if entries.next().is_some() {
+        return Err(format!("{:?} can't be used as mount point, because it isn't empty", at));
+    }
+    Ok(())
+}
+
+/// Mounts the `archive` of the given [ProfileConfig] at `at` until `cancellation` is cancelled.
+/// The archive is opened before a driver is looked for, so a broken archive or a wrong passphrase is reported first.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the mount point can't be used, the archive couldn't be read or there is no driver.
+pub fn mount(profile_config: &ProfileConfig, archive: &Path, at: &Path, cancellation: &Cancellation) -> Result<(), String> {
+    check_mount_point(at)?;
+    let fs = ArchiveFs::open(profile_config, archive, DEFAULT_CACHE_BLOCKS)?;
+    native_driver()?.mount(fs, at, cancellation)
+}
+
+#[cfg(test)]
+mod mount_tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn mount_point() {
+        let dir = PathBuf::from("./test_dir/mount_point_tmp");
+        fs::create_dir_all(dir.join("full")).unwrap();
+        fs::create_dir_all(dir.join("empty")).unwrap();
+        fs::write(dir.join("file.txt"), "").unwrap();
+
+        let missing = check_mount_point(&dir.join("missing"));
+        let empty = check_mount_point(&dir.join("empty"));
+        let full = check_mount_point(&dir);
+        let file = check_mount_point(&dir.join("file.txt"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(missing, Ok(()));
+        assert_eq!(empty, Ok(()));
+        assert!(full.is_err());
+        assert!(file.is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Contains the first-run onboarding, that sets up the configuration files on a fresh machine.
+
+use std::{fs, path::PathBuf};
+
+use config::general_config::GeneralConfig;
+use log::info;
+
+use crate::{
+    config::{GENERAL_CONFIG_PATH, LOGGER_CONFIG_PATH},
+    dialog::info_dialog,
+};
+
+/// Default directory for the profile configs created by the onboarding.
+const PROFILE_CONFIGS_PATH: &str = "./profile_configs";
+
+/// Logger config that is written by the onboarding.
+const LOGGER_CONFIG_TEMPLATE: &str = include_str!("../main_logging_conf.yaml");
+
+/// Checks if the binary was started without any arguments, e.g., by double-clicking it.
+pub fn is_interactive_start() -> bool {
+    std::env::args_os().len() <= 1
+}
+
+/// Checks if the program isn't configured yet, i.e., the general config or the logger config doesn't exist.
+pub fn is_unconfigured(general_config: &PathBuf, logger_config: &PathBuf) -> bool {
+    !general_config.exists() || !logger_config.exists()
+}
+
+/// Runs the onboarding in the current working directory.
+/// Writes the template configs and tells the user where to find them.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if a template couldn't be written.
+pub fn onboarding() -> Result<(), String> {
+    let created = write_templates(
+        &PathBuf::from(GENERAL_CONFIG_PATH),
+        &PathBuf::from(LOGGER_CONFIG_PATH),
+        &PathBuf::from(PROFILE_CONFIGS_PATH),
+    )?;
+    info!("Onboarding created {:?}", created);
+
+    let msg = format!(
+        "Backup-rs wurde eingerichtet. Folgende Dateien und Verzeichnisse wurden angelegt:\n{}\n\nLege im Verzeichnis {:?} Profile an, um Backups zu erstellen.",
+        created
+            .iter()
+            .map(|path| format!("- {}", path.display()))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        PROFILE_CONFIGS_PATH
+    );
+    info_dialog("Backup-rs eingerichtet", &msg);
+    Ok(())
+}
+
+/// Writes a template general config to `general_config`, a template logger config to `logger_config` and creates the `profile_configs` dir.
+/// The general config points to the `profile_configs` dir. Files that already exist aren't overwritten.
+///
+/// # Returns
+/// The paths of the files and directories that were created.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if something couldn't be written.
+fn write_templates(
+    general_config: &PathBuf,
+    logger_config: &PathBuf,
+    profile_configs: &PathBuf,
+) -> Result<Vec<PathBuf>, String> {
+    let mut created = vec![];
+
+    if !profile_configs.exists() {
+        if let Err(err) = fs::create_dir_all(profile_configs) {
+            return Err(format!("Couldn't create dir {:?} because {:?}", profile_configs, err));
+        }
+        created.push(profile_configs.clone());
+    }
+
+    if !general_config.exists() {
+        let template = GeneralConfig {
+            profile_configs: profile_configs.clone(),
+            ..GeneralConfig::default()
+        };
+        if let Err(err) = template.store(general_config) {
+            return Err(format!("Couldn't write {:?} because {:?}", general_config, err));
+        }
+        created.push(general_config.clone());
+    }
+
+    if !logger_config.exists() {
+        if let Err(err) = fs::write(logger_config, LOGGER_CONFIG_TEMPLATE) {
+            return Err(format!("Couldn't write {:?} because {:?}", logger_config, err));
+        }
+        created.push(logger_config.clone());
+    }
+
+    Ok(created)
+}
+
+#[cfg(test)]
+mod onboarding_tests {
+    use super::*;
+
+    #[test]
+    fn write_templates_once() {
+        let dir = PathBuf::from("./test_dir/onboarding_tmp");
+        let general_config = dir.join("general_config.json");
+        let logger_config = dir.join("main_logging_conf.yaml");
+        let profile_configs = dir.join("profile_configs");
+        fs::create_dir_all(&dir).unwrap();
+        assert!(is_unconfigured(&general_config, &logger_config));
+
+        let first = write_templates(&general_config, &logger_config, &profile_configs);
+        let second = write_templates(&general_config, &logger_config, &profile_configs);
+        let configured = !is_unconfigured(&general_config, &logger_config);
+        let loaded = GeneralConfig::read(&general_config);
+        let logger_template = fs::read_to_string(&logger_config);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            first.unwrap(),
+            vec![profile_configs.clone(), general_config, logger_config]
+        );
+        assert_eq!(second.unwrap(), Vec::<PathBuf>::new());
+        assert!(configured);
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.profile_configs, profile_configs);
+        assert!(loaded.max_history_entries.is_some());
+        assert_eq!(logger_template.unwrap(), LOGGER_CONFIG_TEMPLATE);
+    }
+}
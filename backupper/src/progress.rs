@@ -0,0 +1,132 @@
+//! Contains the reporting of the progress of a backup.
+
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+/// Receives the progress of a backup while the files are archived.
+pub trait ProgressSink {
+    /// Called once the files to archive are known, with their number and total size in bytes.
+    fn on_start(&mut self, files: usize, total_bytes: u64);
+    /// Called before the file at the given `path` with the given `size` is archived.
+    fn on_file_start(&mut self, path: &Path, size: u64);
+    /// Called after the given number of `bytes` of the current file were written to the archive.
+    fn on_bytes_written(&mut self, bytes: u64);
+    /// Called once all files are archived.
+    fn on_done(&mut self);
+}
+
+/// Ignores the progress, e.g., for scheduled runs, where nobody watches the console.
+#[derive(Debug, Default)]
+pub struct SilentProgress;
+
+impl ProgressSink for SilentProgress {
+    fn on_start(&mut self, _files: usize, _total_bytes: u64) {}
+    fn on_file_start(&mut self, _path: &Path, _size: u64) {}
+    fn on_bytes_written(&mut self, _bytes: u64) {}
+    fn on_done(&mut self) {}
+}
+
+/// Shows the percentage of the bytes written so far and the current file in a single line of the console.
+#[derive(Debug, Default)]
+pub struct ConsoleProgress {
+    total_bytes: u64,
+    written_bytes: u64,
+    /// Displayed name of the file that is currently archived
+    current: String,
+    /// Percentage that is currently shown, so the line is only redrawn if it changes
+    shown_percent: Option<u64>,
+    /// Length of the shown line, so a shorter line can overwrite it completely
+    shown_len: usize,
+}
+
+impl ConsoleProgress {
+    /// Percentage of the total bytes written so far. Files that are archived again (e.g., because they were in use) may exceed the total, so it's capped at 100.
+    fn percent(&self) -> u64 {
+        if self.total_bytes == 0 {
+            return 100;
+        }
+        (self.written_bytes.saturating_mul(100) / self.total_bytes).min(100)
+    }
+
+    fn redraw(&mut self) {
+        let percent = self.percent();
+        let line = format_progress(percent, &self.current);
+        let padding = self.shown_len.saturating_sub(line.chars().count());
+        eprint!("\r{}{}", line, " ".repeat(padding));
+        let _ = io::stderr().flush();
+        self.shown_percent = Some(percent);
+        self.shown_len = line.chars().count();
+    }
+}
+
+impl ProgressSink for ConsoleProgress {
+    fn on_start(&mut self, _files: usize, total_bytes: u64) {
+        *self = ConsoleProgress {
+            total_bytes,
+            ..Default::default()
+        };
+    }
+
+    fn on_file_start(&mut self, path: &Path, _size: u64) {
+        self.current = path.to_string_lossy().to_string();
+        self.redraw();
+    }
+
+    fn on_bytes_written(&mut self, bytes: u64) {
+        self.written_bytes += bytes;
+        if self.shown_percent != Some(self.percent()) {
+            self.redraw();
+        }
+    }
+
+    fn on_done(&mut self) {
+        self.current.clear();
+        self.redraw();
+        eprintln!();
+    }
+}
+
+/// Formats the line shown by [ConsoleProgress]. Long paths are shortened to their end, which holds the file name.
+fn format_progress(percent: u64, current: &str) -> String {
+    const MAX_PATH_LEN: usize = 60;
+    let len = current.chars().count();
+    let current = if len > MAX_PATH_LEN {
+        let tail: String = current.chars().skip(len - (MAX_PATH_LEN - 3)).collect();
+        format!("...{}", tail)
+    } else {
+        String::from(current)
+    };
+    format!("{:>3}% {}", percent, current).trim_end().to_string()
+}
+
+#[cfg(test)]
+mod progress_tests {
+    use super::*;
+
+    #[test]
+    fn percent_is_capped() {
+        let mut progress = ConsoleProgress::default();
+        progress.on_start(2, 200);
+        assert_eq!(progress.percent(), 0);
+        progress.on_bytes_written(150);
+        assert_eq!(progress.percent(), 75);
+        progress.on_bytes_written(150);
+        assert_eq!(progress.percent(), 100);
+
+        progress.on_start(0, 0);
+        assert_eq!(progress.percent(), 100);
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(format_progress(7, "C:/a.txt"), "  7% C:/a.txt");
+        assert_eq!(format_progress(100, ""), "100%");
+        let long = format!("C:/{}/b.txt", "x".repeat(100));
+        let line = format_progress(42, &long);
+        assert_eq!(line.chars().count(), 5 + 60);
+        assert!(line.starts_with(" 42% ...x"));
+        assert!(line.ends_with("/b.txt"));
+    }
+}
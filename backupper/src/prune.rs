@@ -0,0 +1,110 @@
+//! Contains functions for deleting the archives that exceed the retention rules of a [ProfileConfig].
+
+use std::fs;
+
+use chrono::offset;
+use config::{backup_report::report_path, profile_config::ProfileConfig};
+use log::{error, info};
+
+use crate::common::{archive_parts, archives_in_dir};
+
+/// Deletes the archives in the `target_dir` of the given [ProfileConfig] that aren't covered by its retention rules anymore (see [ProfileConfig::archives_to_prune]).
+/// The `previous_target_dirs` are never touched. Archives that couldn't be deleted are only logged.
+/// All volumes of split archives are deleted (see [archive_parts]), as well as the reports of the archives (see [report_path]).
+///
+/// # Returns
+/// The number of deleted archives.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the target dir couldn't be read.
+pub fn prune(profile_config: &ProfileConfig) -> Result<usize, String> {
+    if profile_config.keep_last.is_none() && profile_config.keep_days.is_none() {
+        return Ok(0);
+    }
+
+    let archives = archives_in_dir(profile_config, &profile_config.target_dir)?;
+    let mut deleted = 0;
+    for archive in profile_config.archives_to_prune(&archives, offset::Local::now().naive_local()) {
+        let mut complete = true;
+        for path in archive_parts(&archive) {
+            if let Err(err) = fs::remove_file(&path) {
+                error!("Couldn't delete {:?}. Got: {:?}", path, err);
+                complete = false;
+            }
+        }
+        if complete {
+            deleted += 1;
+            let report = report_path(&archive);
+            if report.exists() {
+                if let Err(err) = fs::remove_file(&report) {
+                    error!("Couldn't delete {:?}. Got: {:?}", report, err);
+                }
+            }
+        }
+    }
+    info!("Pruned {} archives of {:?}", deleted, profile_config.name);
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod prune_tests {
+    use std::{fs::File, path::PathBuf};
+
+    use config::interval::IntervalBuilder;
+
+    use super::*;
+
+    #[test]
+    fn keeps_newest_archives() {
+        let dir = "./test_dir/prune_tmp";
+        let old_dir = "./test_dir/prune_tmp/old";
+        fs::create_dir_all(old_dir).unwrap();
+        let mut profile_config = ProfileConfig::new(
+            String::from("prune"),
+            PathBuf::from(dir),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.previous_target_dirs = vec![PathBuf::from(old_dir)];
+        let uuid = profile_config.get_uuid().as_hyphenated().to_string();
+        for day in 2..=4 {
+            File::create(PathBuf::from(dir).join(format!("{}_2023-05-0{}_10-00.zip", uuid, day))).unwrap();
+        }
+        File::create(PathBuf::from(dir).join(format!("{}_2023-05-02_10-00.report.json", uuid))).unwrap();
+        File::create(PathBuf::from(dir).join(format!("{}_2023-05-04_10-00.report.json", uuid))).unwrap();
+        // split archives are pruned with all their volumes
+        for part in 1..=2 {
+            File::create(PathBuf::from(dir).join(format!("{}_2023-05-01_10-00.part0{}.zip", uuid, part))).unwrap();
+        }
+        File::create(PathBuf::from(dir).join("unrelated.zip")).unwrap();
+        File::create(PathBuf::from(old_dir).join(format!("{}_2022-05-01_10-00.zip", uuid))).unwrap();
+
+        let unchanged = prune(&profile_config);
+        profile_config.keep_last = Some(2);
+        let deleted = prune(&profile_config);
+        let mut remaining: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_str().unwrap().to_string())
+            .collect();
+        remaining.sort();
+        let old_remaining = fs::read_dir(old_dir).unwrap().count();
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(unchanged, Ok(0));
+        assert_eq!(deleted, Ok(2));
+        assert_eq!(
+            remaining,
+            vec![
+                format!("{}_2023-05-03_10-00.zip", uuid),
+                format!("{}_2023-05-04_10-00.report.json", uuid),
+                format!("{}_2023-05-04_10-00.zip", uuid),
+                String::from("old"),
+                String::from("unrelated.zip"),
+            ]
+        );
+        assert_eq!(old_remaining, 1);
+    }
+}
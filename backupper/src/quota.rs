@@ -0,0 +1,117 @@
+//! Contains functions for enforcing the `max_target_usage_bytes` of a [ProfileConfig].
+
+use std::fs;
+
+use config::profile_config::ProfileConfig;
+use log::info;
+
+use crate::common::{archive_parts, list_archives};
+
+/// Computes how many bytes the archives of the given [ProfileConfig] use in its target dir.
+/// Archives in the `previous_target_dirs` don't count. All volumes of split archives count.
+///
+/// # Returns
+/// The total size of the archives and the size of the newest one, which serves as estimate for the next backup.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the target dir couldn't be read.
+pub fn target_usage(profile_config: &ProfileConfig) -> Result<(u64, u64), String> {
+    let sizes: Vec<u64> = list_archives(profile_config)?
+        .into_iter()
+        .filter(|(_, path)| path.parent() == Some(profile_config.target_dir.as_path()))
+        .map(|(_, path)| {
+            archive_parts(&path)
+                .iter()
+                .map(|part| fs::metadata(part).map_or(0, |metadata| metadata.len()))
+                .sum()
+        })
+        .collect();
+
+    Ok((sizes.iter().sum(), sizes.last().copied().unwrap_or(0)))
+}
+
+/// Checks if another backup of the given [ProfileConfig] fits into its `max_target_usage_bytes`.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the quota would be exceeded or the usage couldn't be determined.
+pub fn check_quota(profile_config: &ProfileConfig) -> Result<(), String> {
+    if profile_config.max_target_usage_bytes.is_none() {
+        return Ok(());
+    }
+
+    let (usage, estimate) = target_usage(profile_config)?;
+    if let Some(percentage) = profile_config.target_usage_percentage(usage) {
+        info!(
+            "{:?} uses {} bytes ({:.1}% of its quota) in {:?}",
+            profile_config.name, usage, percentage, profile_config.target_dir
+        );
+    }
+
+    if profile_config.exceeds_target_usage(usage, estimate) {
+        return Err(format!(
+            "Quota exceeded: {:?} already uses {} bytes and the next backup is estimated at {} bytes, but only {} bytes are allowed",
+            profile_config.name,
+            usage,
+            estimate,
+            profile_config.max_target_usage_bytes.unwrap_or_default()
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod quota_tests {
+    use std::path::PathBuf;
+
+    use config::interval::IntervalBuilder;
+
+    use super::*;
+
+    fn quota_config(target_dir: &str, max_target_usage_bytes: Option<u64>) -> ProfileConfig {
+        let mut profile_config = ProfileConfig::new(
+            String::from("quota"),
+            PathBuf::from(target_dir),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.max_target_usage_bytes = max_target_usage_bytes;
+        profile_config
+    }
+
+    #[test]
+    fn over_and_under_quota() {
+        let dir = "./test_dir/quota_tmp";
+        fs::create_dir_all(dir).unwrap();
+        let mut profile_config = quota_config(dir, Some(100));
+        let uuid = profile_config.get_uuid().as_hyphenated().to_string();
+        fs::write(PathBuf::from(dir).join(format!("{}_2023-01-01_10-00.zip", uuid)), [0u8; 30]).unwrap();
+        fs::write(PathBuf::from(dir).join(format!("{}_2023-01-02_10-00.part01.zip", uuid)), [0u8; 12]).unwrap();
+        fs::write(PathBuf::from(dir).join(format!("{}_2023-01-02_10-00.part02.zip", uuid)), [0u8; 8]).unwrap();
+        fs::write(PathBuf::from(dir).join("other.zip"), [0u8; 50]).unwrap();
+
+        let usage = target_usage(&profile_config);
+        let fits = check_quota(&profile_config);
+        profile_config.max_target_usage_bytes = Some(69);
+        let exceeded = check_quota(&profile_config);
+        profile_config.max_target_usage_bytes = None;
+        let unlimited = check_quota(&profile_config);
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(usage, Ok((50, 20)));
+        assert!(fits.is_ok());
+        assert!(exceeded.unwrap_err().starts_with("Quota exceeded"));
+        assert!(unlimited.is_ok());
+    }
+
+    #[test]
+    fn missing_target_dir() {
+        let profile_config = quota_config("./test_dir/doesnt_exist", Some(100));
+        assert!(check_quota(&profile_config).is_err());
+        let profile_config = quota_config("./test_dir/doesnt_exist", None);
+        assert!(check_quota(&profile_config).is_ok());
+    }
+}
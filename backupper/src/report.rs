@@ -0,0 +1,232 @@
+//! Contains functions for writing and reading the [BackupReport] next to each backup archive.
+
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use config::{
+    archive_manifest::ARCHIVE_MANIFEST_NAME,
+    backup_inventory::BackupInfo,
+    backup_report::{report_path, ArchiveFile, BackupReport},
+};
+use log::warn;
+use sha2::{Digest, Sha256};
+use zip::ZipArchive;
+
+use crate::common::{SNAPSHOT_FILE_NAME, UNSTABLE_FILE_NAME};
+
+/// Reads the report of the archive at `path`.
+///
+/// # Returns
+/// [None] if there is no report (e.g., because the archive was written by an older version) or it couldn't be read.
+pub fn read_report(path: &Path) -> Option<BackupReport> {
+    let content = fs::read_to_string(report_path(path)).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(report) => Some(report),
+        Err(err) => {
+            warn!("Couldn't parse the report of {:?} because {:?}", path, err);
+            None
+        }
+    }
+}
+
+/// Writes the `report` of the archive at `path` next to it.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the report couldn't be written.
+pub fn write_report(path: &Path, report: &BackupReport) -> Result<(), String> {
+    let report_path = report_path(path);
+    let content = match serde_json::to_string_pretty(report) {
+        Ok(content) => content,
+        Err(err) => return Err(format!("Couldn't serialize report because of {:?}", err)),
+    };
+    match fs::write(&report_path, content) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Couldn't write {:?} because of {:?}", report_path, err)),
+    }
+}
+
+/// Counts the archived files in the zip archives at the given `paths` and sums up their uncompressed and compressed sizes.
+/// The manifest and the lists of the archive aren't counted.
+///
+/// # Returns
+/// The number of distinct files, their uncompressed size and their compressed size in bytes.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if an archive couldn't be read.
+pub fn archive_totals(paths: &[PathBuf]) -> Result<(usize, u64, u64), String> {
+    let mut names = HashSet::new();
+    let mut uncompressed = 0;
+    let mut compressed = 0;
+    for path in paths {
+        let file = File::open(path).map_err(|err| format!("Error opening file {:?}: {:?}", path, err))?;
+        let mut zip = ZipArchive::new(file).map_err(|err| format!("Couldn't read archive because {:?}", err))?;
+        for i in 0..zip.len() {
+            let entry = zip
+                .by_index_raw(i)
+                .map_err(|err| format!("Error reading entry: {:?}", err))?;
+            let name = entry.name();
+            if name == ARCHIVE_MANIFEST_NAME || name == SNAPSHOT_FILE_NAME || name == UNSTABLE_FILE_NAME {
+                continue;
+            }
+            uncompressed += entry.size();
+            compressed += entry.compressed_size();
+            names.insert(String::from(name));
+        }
+    }
+    Ok((names.len(), uncompressed, compressed))
+}
+
+/// Describes the file at `path` for a report, including its SHA-256.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the file couldn't be read.
+pub fn archive_file(path: &Path) -> Result<ArchiveFile, String> {
+    let mut file = File::open(path).map_err(|err| format!("Error opening file {:?}: {:?}", path, err))?;
+    let mut hasher = Sha256::new();
+    let bytes = io::copy(&mut file, &mut hasher).map_err(|err| format!("Couldn't read {:?} because of {:?}", path, err))?;
+    Ok(ArchiveFile {
+        file_name: path
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().to_string()),
+        bytes,
+        sha256: format!("{:x}", hasher.finalize()),
+    })
+}
+
+/// Formats the `report` of an archive as a single line, e.g., `12 files, 3000 bytes (1200 compressed), 1 skipped`.
+pub fn format_report(report: &BackupReport) -> String {
+    format!(
+        "{} files, {} bytes ({} compressed), {} skipped",
+        report.file_count,
+        report.uncompressed_bytes,
+        report.compressed_bytes,
+        report.skipped.len()
+    )
+}
+
+/// Formats an archive listed by [backup_inventory](config::backup_inventory::backup_inventory) as a single line,
+/// e.g., `2023-05-01 10:00  full         3000 bytes in 2 volumes, report     "D:/backups/<uuid>_2023-05-01_10-00.part01.zip"`.
+/// Partial archives of stopped backups end with `(cancelled)`.
+pub fn format_backup(backup: &BackupInfo) -> String {
+    format!(
+        "{}  {:<11}  {} bytes in {} volume{}, {:<9}  {:?}{}",
+        backup.created.format("%Y-%m-%d %H:%M"),
+        if backup.incremental { "incremental" } else { "full" },
+        backup.bytes,
+        backup.volumes,
+        if backup.volumes == 1 { "" } else { "s" },
+        if backup.has_report { "report" } else { "no report" },
+        backup.path,
+        if backup.cancelled { " (cancelled)" } else { "" }
+    )
+}
+
+#[cfg(test)]
+mod report_tests {
+    use std::io::Write;
+
+    use chrono::NaiveDate;
+    use config::backup_report::SkippedEntry;
+    use uuid::Uuid;
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    fn test_report() -> BackupReport {
+        let started = NaiveDate::from_ymd_opt(2023, 5, 1).unwrap().and_hms_opt(12, 0, 0).unwrap();
+        BackupReport {
+            profile_name: String::from("Hutzi"),
+            profile_uuid: Uuid::new_v4(),
+            started,
+            finished: started,
+            file_count: 12,
+            uncompressed_bytes: 3000,
+            compressed_bytes: 1200,
+            skipped: vec![SkippedEntry {
+                path: PathBuf::from("C:/a.pst"),
+                reason: String::from("in use"),
+            }],
+            archive_files: vec![],
+            cancelled: false,
+        }
+    }
+
+    #[test]
+    fn write_and_read() {
+        let dir = PathBuf::from("./test_dir/report_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.part01.zip");
+        let report = test_report();
+        let missing = read_report(&archive);
+        write_report(&archive, &report).unwrap();
+        let read = read_report(&dir.join("archive.part02.zip"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(missing, None);
+        assert_eq!(read, Some(report));
+    }
+
+    #[test]
+    fn totals_and_hash() {
+        let dir = PathBuf::from("./test_dir/report_totals_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        let stored = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (name, size) in [("C/a.txt", 10), ("C/b.txt", 20), ("C/a.txt", 10), (SNAPSHOT_FILE_NAME, 5)] {
+            zip.start_file(name, stored).unwrap();
+            zip.write_all(&vec![b'x'; size]).unwrap();
+        }
+        zip.finish().unwrap();
+        let empty = dir.join("empty");
+        File::create(&empty).unwrap();
+
+        let totals = archive_totals(&[archive]);
+        let empty_file = archive_file(&empty).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        // a.txt was archived twice, but is only one file
+        assert_eq!(totals, Ok((2, 40, 40)));
+        assert_eq!(empty_file.file_name, "empty");
+        assert_eq!(empty_file.bytes, 0);
+        assert_eq!(
+            empty_file.sha256,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(format_report(&test_report()), "12 files, 3000 bytes (1200 compressed), 1 skipped");
+    }
+
+    #[test]
+    fn format_backups() {
+        let mut backup = BackupInfo {
+            path: PathBuf::from("D:/backups/abc_2023-05-01_10-00.part01.zip"),
+            created: NaiveDate::from_ymd_opt(2023, 5, 1).unwrap().and_hms_opt(10, 0, 0).unwrap(),
+            incremental: false,
+            volumes: 2,
+            bytes: 3000,
+            has_report: true,
+            cancelled: false,
+        };
+        assert_eq!(
+            format_backup(&backup),
+            r#"2023-05-01 10:00  full         3000 bytes in 2 volumes, report     "D:/backups/abc_2023-05-01_10-00.part01.zip""#
+        );
+        backup.incremental = true;
+        backup.volumes = 1;
+        backup.has_report = false;
+        assert_eq!(
+            format_backup(&backup),
+            r#"2023-05-01 10:00  incremental  3000 bytes in 1 volume, no report  "D:/backups/abc_2023-05-01_10-00.part01.zip""#
+        );
+        backup.cancelled = true;
+        assert!(format_backup(&backup).ends_with(r#"part01.zip" (cancelled)"#));
+    }
+}
@@ -1,4 +1,4 @@
-use config::{profile_config::ProfileConfig, general_config::GeneralConfig};
+use config::{profile_config::{AuditActor, ProfileConfig}, general_config::GeneralConfig};
 use log::{info, error};
 
 use crate::scheduler;
@@ -14,13 +14,14 @@ pub fn reschedule(profile_config: &mut ProfileConfig, general_config: &GeneralCo
 
     let next_backup = profile_config.get_next_scheduled(Some(now));
     profile_config.next_backup = next_backup;
+    profile_config.refresh_upcoming();
 
     if let Err(e) = scheduler::schedule_backup(profile_config.get_uuid().clone(), profile_config.next_backup) {
         error!("Couldn't schedule next backup: {:?}", e);
         return;
     }
 
-    if let Err(e) = profile_config.store(&general_config.profile_configs) {
+    if let Err(e) = profile_config.store_audited(&general_config.profile_configs, AuditActor::Cli) {
         error!("Couldn't store updated ProfileConfig: {:?}", e);
         info!("The backup is still rescheduled though.");
     }
@@ -1,48 +1,420 @@
 //! Contains function for restoring a backup.
 
-use std::{fs::{self, File}, path::PathBuf, io};
+use std::{collections::{HashMap, HashSet}, fmt::Display, fs::{self, File}, path::{Component, Path, PathBuf}, io::{self, Read}, time::{Duration, SystemTime}};
 
-use chrono::NaiveDateTime;
-use config::profile_config::ProfileConfig;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use config::{archive_manifest::ARCHIVE_MANIFEST_NAME, backup_report::is_cancelled_archive, profile_config::ProfileConfig};
 use log::{error, info, warn, debug};
-use zip::ZipArchive;
+use uuid::Uuid;
+use sha2::{Digest, Sha256};
+use zip::{read::ZipFile, ZipArchive};
 
 use crate::{
-    cli_args::Args,
-    common::is_target_dir_available,
-    dialog::{retry_dialog, DialogResult, RETRY}, 
+    archive_format::ArchiveFormat,
+    cancel::Cancellation,
+    common::{
+        archive_creation_date, archive_parts, collision_key, is_incremental_archive, is_target_dir_available, list_archives, long_path, original_path, read_list, read_manifest,
+        split_part_suffix, ARCHIVE_DATE_FORMAT, INCREMENTAL_SUFFIX, SNAPSHOT_FILE_NAME, UNSTABLE_FILE_NAME,
+    },
+    dialog::{retry_dialog, DialogResult, RETRY},
+    encryption::{is_encrypted, readable_archive},
+    tempfiles::{temp_path_for, TempKind},
 };
 
+/// Exit code if none of the files of the backup is below the selected subtrees, e.g., because of a typo
+pub const NO_MATCH: exitcode::ExitCode = exitcode::NOINPUT;
+
+/// Exit code if nothing was restored, e.g., because there is no backup or it can't be read
+pub const NOT_RESTORED: exitcode::ExitCode = exitcode::NOINPUT;
+
+/// Exit code if some of the chosen files couldn't be restored (see [RestoreOutcome::errors])
+pub const PARTIALLY_RESTORED: exitcode::ExitCode = exitcode::IOERR;
+
+/// What happens if a restored file already exists.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConflictPolicy {
+    /// Replace the existing file
+    #[default]
+    Overwrite,
+    /// Keep the existing file and don't restore the archived one
+    Skip,
+    /// Keep the existing file and restore the archived one next to it, e.g., `a.txt` as `a.restored.txt` (see [restored_name])
+    Rename,
+    /// Only replace the existing file if the archived one was modified after it
+    Newer,
+}
+
+/// Selects which files of a backup are restored and where they are written to.
+#[derive(Debug, Default, Clone)]
+pub struct Selection {
+    /// Only restore the entries below one of these paths. If empty, all entries are restored
+    pub subtrees: Vec<PathBuf>,
+    /// Remove the matching subtree from the restored paths
+    pub strip_prefix: bool,
+    /// Restore into this dir instead of the original locations
+    pub output_dir: Option<PathBuf>,
+    /// What happens if a restored file already exists
+    pub on_conflict: ConflictPolicy,
+}
+
+impl Selection {
+    /// Normalizes `path` to the form of the entry names in the archives, which always use `/` as separator.
+    fn normalize(path: &Path) -> PathBuf {
+        PathBuf::from(path.to_string_lossy().replace('\\', "/"))
+    }
+
+    /// Computes where the archive entry with the given original path has to be restored to.
+    /// The `subtrees` are matched component-wise, so `C:/Pic` doesn't match `C:/Pictures/a.txt`.
+    /// If several subtrees contain the entry, the most specific one is stripped.
+    ///
+    /// # Returns
+    /// [None] if the entry isn't selected.
+    pub fn destination(&self, original: impl AsRef<Path>) -> Option<PathBuf> {
+        let path = original.as_ref().to_path_buf();
+        let subtree = self
+            .subtrees
+            .iter()
+            .map(|subtree| Self::normalize(subtree))
+            .filter(|subtree| path.starts_with(subtree))
+            .max_by_key(|subtree| subtree.components().count());
+        if !self.subtrees.is_empty() && subtree.is_none() {
+            return None;
+        }
+
+        let output_dir = match &self.output_dir {
+            Some(output_dir) => output_dir,
+            None => return Some(path),
+        };
+        let relative = match (&subtree, self.strip_prefix) {
+            (Some(subtree), true) => path.strip_prefix(subtree).ok()?,
+            _ => path.as_path(),
+        };
+        // drive letters and roots can't be part of a path below the output dir, and `..` would leave it
+        let relative: PathBuf = relative
+            .components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect();
+        Some(output_dir.join(relative))
+    }
+}
+
+impl Display for Selection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.subtrees.is_empty() {
+            write!(f, "all files")?;
+        } else {
+            let subtrees: Vec<PathBuf> = self.subtrees.iter().map(|subtree| Self::normalize(subtree)).collect();
+            write!(f, "files below {:?}", subtrees)?;
+        }
+        if self.strip_prefix {
+            write!(f, " without the subtree prefix")?;
+        }
+        match &self.output_dir {
+            Some(output_dir) => write!(f, " into {:?}", output_dir)?,
+            None => write!(f, " to their original locations")?,
+        }
+        match self.on_conflict {
+            ConflictPolicy::Overwrite => write!(f, ", overwriting existing files"),
+            ConflictPolicy::Skip => write!(f, ", keeping existing files"),
+            ConflictPolicy::Rename => write!(f, ", renaming files that already exist"),
+            ConflictPolicy::Newer => write!(f, ", only overwriting older files"),
+        }
+    }
+}
+
 /// Restores the files from the latest backup of the provided [ProfileConfig] that is older than the given `timestamp`.
+/// If that's an incremental archive, the chain of archives back to the preceding full one is restored (see [find_backup_chain]).
+/// Archives that are split into volumes are restored volume by volume (see [archive_parts]). Encrypted archives are decrypted first, and a wrong passphrase aborts the restore.
+/// Only the files chosen by the [Selection] are restored.
 ///
-/// If there is no such backup, nothing happens.
-pub fn restore(profile_config: &ProfileConfig, timestamp: NaiveDateTime, _args: &Args) {
-    if !available_target_dir_dialog(profile_config) {
-        info!("Target dir {:?} wasn't available and canceled.", profile_config.target_dir);
-        return;
+/// If `protect_newer` is set, files that were modified after their archived version are not overwritten (see [restore_from_backup]).
+/// Without `show_dialogs`, nothing is restored if the target dir isn't available.
+/// If the restore is cancelled through `cancellation`, it stops after the file that is currently restored. The files restored until then are kept.
+///
+/// Files that can't be restored don't stop the restore, but are listed in the [RestoreOutcome].
+///
+/// # Returns
+/// The [RestoreOutcome], which also tells how many files of the backup were chosen by the [Selection], so a selection matching nothing can be reported.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if nothing was restored, e.g., because there is no such backup or it couldn't be decrypted.
+pub fn restore(
+    profile_config: &ProfileConfig,
+    timestamp: NaiveDateTime,
+    protect_newer: bool,
+    selection: &Selection,
+    show_dialogs: bool,
+    cancellation: &Cancellation,
+) -> Result<RestoreOutcome, String> {
+    let chain_state = ChainState {
+        cancellation: cancellation.clone(),
+        ..Default::default()
+    };
+    let outcome = RestoreOutcome::from(restore_chain(profile_config, timestamp, protect_newer, selection, show_dialogs, chain_state)?);
+    println!("{} files of the backup matched {}", outcome.matched, selection);
+    println!("{}", outcome.summary());
+    Ok(outcome)
+}
+
+/// What a [restore] did.
+#[derive(Debug, Default, PartialEq)]
+pub struct RestoreOutcome {
+    /// Newest restored archive. Older archives of its chain were restored as well.
+    pub archive: Option<PathBuf>,
+    /// Number of files of the backup that were chosen by the [Selection], whether they were restored or not
+    pub matched: usize,
+    /// Number of restored files, including the renamed ones
+    pub restored: usize,
+    /// Number of files restored under another name (see [ConflictPolicy::Rename])
+    pub renamed: usize,
+    /// Number of chosen files that were left alone on purpose, e.g., because they are newer than the backup
+    pub skipped: usize,
+    /// Number of skipped files that were kept because they already exist (see [ConflictPolicy])
+    pub kept: usize,
+    /// Files or archives that couldn't be restored with the reason
+    pub errors: Vec<(String, String)>,
+}
+
+impl RestoreOutcome {
+    /// Describes the outcome in a few lines for the console and the dialog at the end of a restore.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Restored {} files ({} of them renamed), skipped {} files ({} of them existing ones that were kept)",
+            self.restored, self.renamed, self.skipped, self.kept
+        );
+        if !self.errors.is_empty() {
+            summary += &format!(", {} errors:", self.errors.len());
+            for (path, msg) in &self.errors {
+                summary += &format!("\n    {}: {}", path, msg);
+            }
+        }
+        summary
     }
 
-    let best_backup = find_backup_archive(profile_config, timestamp);
-    println!("Found best: {:?}", best_backup);
-    if best_backup.is_none() {
-        return;
+    /// Determines the exit code of the restore: [PARTIALLY_RESTORED] if any file couldn't be restored.
+    pub fn exit_code(&self) -> exitcode::ExitCode {
+        if self.errors.is_empty() {
+            exitcode::OK
+        } else {
+            PARTIALLY_RESTORED
+        }
+    }
+}
+
+impl From<ChainState> for RestoreOutcome {
+    fn from(chain_state: ChainState) -> Self {
+        RestoreOutcome {
+            archive: chain_state.archive,
+            matched: chain_state.matched,
+            restored: chain_state.restored,
+            renamed: chain_state.renamed,
+            skipped: chain_state.skipped,
+            kept: chain_state.kept,
+            errors: chain_state.errors,
+        }
+    }
+}
+
+/// Restores the files of the archive at `path` without a [ProfileConfig], e.g., because the profile config got lost with the rest of the files.
+/// Only this archive is restored, so an incremental one only brings back the files that changed before it was made. Split archives are restored volume by volume.
+/// Encrypted archives can't be restored this way, because their passphrase is part of the profile.
+///
+/// The files are chosen and written like in [restore]. If `protect_newer` is set, files that were modified after their archived version aren't overwritten.
+/// The creation date is taken from the filename, or from the modification time of the file if it isn't named like the archives of this program.
+///
+/// # Returns
+/// The [RestoreOutcome] like [restore].
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the file doesn't exist, can't be read, is encrypted or isn't a zip archive.
+pub fn restore_archive(path: &Path, protect_newer: bool, selection: &Selection, cancellation: &Cancellation) -> Result<RestoreOutcome, String> {
+    if !path.is_file() {
+        return Err(format!("{:?} isn't a file", path));
     }
+    let file = File::open(path).map_err(|e| format!("Couldn't open {:?} because of {:?}", path, e))?;
+    if is_encrypted(path) {
+        return Err(format!("{:?} is encrypted and can only be restored through its profile, which knows the passphrase", path));
+    }
+    ZipArchive::new(file).map_err(|e| format!("{:?} isn't a zip archive: {}", path, e))?;
 
-    let best_backup = best_backup.unwrap();
-    restore_from_backup(best_backup);
+    let (uuid, creation_date) = match parse_archive_name(path) {
+        Some((uuid, creation_date)) => (uuid, Some(creation_date)),
+        None => {
+            warn!("{:?} isn't named like a backup archive (\"<uuid>_<YYYY-MM-DD_HH-MM>.zip\"), restoring it anyway", path);
+            let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+            (Uuid::nil(), modified.map(|modified| DateTime::<Local>::from(modified).naive_local()))
+        }
+    };
+    if is_incremental_archive(&path.to_path_buf()) {
+        warn!("{:?} is incremental and only contains the files that changed since the previous archive", path);
+    }
+    let protect_newer = if protect_newer { creation_date } else { None };
+
+    let mut chain_state = ChainState {
+        cancellation: cancellation.clone(),
+        archive: Some(path.to_path_buf()),
+        ..Default::default()
+    };
+    // the lists of split archives are in their last volume, and later volumes contain the retried files
+    for part in archive_parts(&path.to_path_buf()).into_iter().rev() {
+        restore_from_backup(part.clone(), &uuid, protect_newer, selection, &mut chain_state);
+        if chain_state.cancellation.is_cancelled() {
+            warn!("Cancelled restore, {:?} wasn't restored completely", part);
+            break;
+        }
+    }
+    let outcome = RestoreOutcome::from(chain_state);
+    println!("{} files of the archive matched {}", outcome.matched, selection);
+    println!("{}", outcome.summary());
+    Ok(outcome)
+}
+
+/// Reads the uuid and the creation date from the filename of a backup archive like [archive_creation_date] does, but for any profile.
+///
+/// # Returns
+/// [None] if the file isn't named like a backup archive.
+fn parse_archive_name(path: &Path) -> Option<(Uuid, NaiveDateTime)> {
+    let (uuid, creation_date) = path.file_name()?.to_str()?.strip_suffix(".zip")?.split_once('_')?;
+    let (creation_date, _) = split_part_suffix(creation_date);
+    let creation_date = creation_date.strip_suffix(INCREMENTAL_SUFFIX).unwrap_or(creation_date);
+    Some((
+        Uuid::parse_str(uuid).ok()?,
+        NaiveDateTime::parse_from_str(creation_date, ARCHIVE_DATE_FORMAT).ok()?,
+    ))
+}
+
+/// How restoring an archive entry would change the file at its destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// There is no file yet
+    Missing,
+    /// The file has the same content as the entry
+    Identical,
+    /// The file would be overwritten with another version
+    Different,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Missing => write!(f, "missing"),
+            Change::Identical => write!(f, "identical"),
+            Change::Different => write!(f, "different"),
+        }
+    }
+}
+
+/// Result of a restore that was only previewed (see [preview]).
+#[derive(Debug, Default)]
+pub struct Preview {
+    /// Number of files of the backup that were chosen by the [Selection]
+    pub matched: usize,
+    /// Destinations of the files that would be restored and how they would change, in the order they would be restored
+    pub changes: Vec<(PathBuf, Change)>,
+}
+
+impl Preview {
+    /// Formats the changes as one line each, e.g., `missing   "C:/Users/me/a.txt"`.
+    pub fn lines(&self) -> Vec<String> {
+        self.changes
+            .iter()
+            .map(|(path, change)| format!("{:<9} {:?}", change.to_string(), path))
+            .collect()
+    }
+
+    /// Describes the changes for the frontend, e.g., `{"matched": 1, "changes": [{"path": "C:/Users/me/a.txt", "change": "missing"}]}`.
+    pub fn to_json(&self) -> serde_json::Value {
+        let changes: Vec<serde_json::Value> = self
+            .changes
+            .iter()
+            .map(|(path, change)| serde_json::json!({ "path": path, "change": change.to_string() }))
+            .collect();
+        serde_json::json!({ "matched": self.matched, "changes": changes })
+    }
+}
+
+/// Determines what [restore] would do with the same arguments without writing any file: Each file that would be restored is compared to the one at its destination (see [classify]).
+///
+/// # Errors
+/// Returns an [Err] describing the issue if there is no backup to restore or it can't be read.
+pub fn preview(
+    profile_config: &ProfileConfig,
+    timestamp: NaiveDateTime,
+    protect_newer: bool,
+    selection: &Selection,
+    show_dialogs: bool,
+) -> Result<Preview, String> {
+    let chain_state = ChainState {
+        dry_run: true,
+        ..Default::default()
+    };
+    let chain_state = restore_chain(profile_config, timestamp, protect_newer, selection, show_dialogs, chain_state)?;
+    Ok(Preview {
+        matched: chain_state.matched,
+        changes: chain_state.changes,
+    })
+}
+
+/// Restores the chain of archives for [restore] and [preview].
+///
+/// # Returns
+/// The state after restoring the chain. If the restore was cancelled, the state until then.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if nothing was restored, because the target dir isn't available, there is no backup or it couldn't be decrypted.
+fn restore_chain(
+    profile_config: &ProfileConfig,
+    timestamp: NaiveDateTime,
+    protect_newer: bool,
+    selection: &Selection,
+    show_dialogs: bool,
+    mut chain_state: ChainState,
+) -> Result<ChainState, String> {
+    if !available_target_dir_dialog(profile_config, show_dialogs) {
+        return Err(format!("Target dir {:?} isn't available", profile_config.target_dir));
+    }
+
+    let chain = find_backup_chain(profile_config, timestamp)?;
+    info!("Found best: {:?}", chain);
+    let newest = chain
+        .last()
+        .ok_or_else(|| format!("There is no backup of {:?} older than {}", profile_config.name, timestamp))?;
+    let creation_date = archive_creation_date(profile_config, newest)
+        .ok_or_else(|| format!("Couldn't determine the creation date of {:?}", newest))?;
+    chain_state.archive = Some(newest.clone());
+
+    let protect_newer = if protect_newer { Some(creation_date) } else { None };
+    // newest first, so each file is taken from the newest archive containing it
+    for backup_file in chain.into_iter().rev() {
+        // the lists of split archives are in their last volume, and later volumes contain the retried files
+        for part in archive_parts(&backup_file).into_iter().rev() {
+            // a wrong passphrase would fail for the other archives as well
+            let readable = readable_archive(profile_config, &part).map_err(|msg| format!("Aborted restore: {}", msg))?;
+            restore_from_backup(readable.path().to_path_buf(), profile_config.get_uuid(), protect_newer, selection, &mut chain_state);
+            if chain_state.cancellation.is_cancelled() {
+                warn!("Cancelled restore, {:?} and older archives weren't restored completely", part);
+                return Ok(chain_state);
+            }
+        }
+    }
+    Ok(chain_state)
 }
 
 /// Opens retry dialog to attach external drive if the `profile_config`s target directory is not available.
+/// Without `show_dialogs`, an unavailable directory is treated like a cancel.
 /// 
 /// # Returns
 /// `true` if the restoring shall proceed.
 /// `false` if cancel was selected.
-fn available_target_dir_dialog(profile_config: &ProfileConfig) -> bool {
+fn available_target_dir_dialog(profile_config: &ProfileConfig, show_dialogs: bool) -> bool {
     // make sure, directory is available
     let mut choice = DialogResult(RETRY);
     while !is_target_dir_available(&profile_config.target_dir, false)
         && choice == DialogResult(RETRY)
     {
+        if !show_dialogs {
+            return false;
+        }
         let msg = format!("Das Verzeichnis mit den Backups {:?} scheint nicht verfügpar zu sein.\nBitte schließe die externe Festplatte an und versuche es erneut.", profile_config.target_dir);
         let title = "Backupverzeichnis nicht verfügbar.";
         choice = retry_dialog(title, &msg);
@@ -51,70 +423,309 @@ fn available_target_dir_dialog(profile_config: &ProfileConfig) -> bool {
     choice == DialogResult(RETRY)
 }
 
-/// Finds the latest backup file in the target dir that is older than the provided timestamp.
-/// 
-/// Returns [None] if no such backup file was found. This function doesn't go through the target dir recursively.
-fn find_backup_archive(profile_config: &ProfileConfig, timestamp: NaiveDateTime) -> Option<PathBuf> {
-    let entries = match fs::read_dir(&profile_config.target_dir) {
-        Ok(entries) => entries,
-        Err(err) => {
-            error!("Error reading dir: {:?}", err);
-            return None;
+/// Finds the archives needed to restore the state at the provided timestamp: The latest full archive that is older than the timestamp,
+/// followed by all incremental archives between it and the timestamp in chronological order.
+/// Besides the target dir, the `previous_target_dirs` of the [ProfileConfig] are searched as well.
+///
+/// Returns an empty [Vec] if there is no archive older than the timestamp. The archives are searched like in [list_archives], including the subdirectories.
+/// Partial archives of stopped backups are skipped (see [is_cancelled_archive]).
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the target dir couldn't be read or the incremental archives aren't preceded by a full one.
+fn find_backup_chain(profile_config: &ProfileConfig, timestamp: NaiveDateTime) -> Result<Vec<PathBuf>, String> {
+    let archives = list_archives(profile_config)?;
+
+    let mut chain = vec![];
+    // the partial archives of stopped backups miss files, so they are only restored explicitly (see [restore_archive])
+    for (_, path) in archives
+        .into_iter()
+        .rev()
+        .filter(|(creation_date, path)| creation_date <= &timestamp && !is_cancelled_archive(path))
+    {
+        let is_full = !is_incremental_archive(&path);
+        chain.push(path);
+        if is_full {
+            chain.reverse();
+            debug!("Best backup chain is {:?}", chain);
+            return Ok(chain);
         }
+    }
+
+    match chain.last() {
+        None => Ok(chain),
+        Some(oldest) => Err(format!("There is no full archive preceding the incremental archive {:?}", oldest)),
+    }
+}
+
+/// Keeps track of restoring a chain of archives, which are restored newest first.
+#[derive(Debug, Default)]
+struct ChainState {
+    /// Whether the newest archive of the chain was already restored
+    started: bool,
+    /// Original paths listed in the snapshot of the newest archive, i.e., the files that existed at backup time. [None] if it doesn't have one.
+    snapshot: Option<HashSet<String>>,
+    /// Original paths of the entries that were found in a newer archive, so their older versions aren't restored
+    superseded: HashSet<String>,
+    /// Original paths of the files that changed while they were archived. Listed in the last volume of split archives, but apply to all of them.
+    unstable: HashSet<String>,
+    /// Number of files chosen by the [Selection], whether they were restored or skipped
+    matched: usize,
+    /// Number of restored files, including the renamed ones
+    restored: usize,
+    /// Number of chosen files that weren't restored on purpose (see [RestoreOutcome::skipped])
+    skipped: usize,
+    /// Files or archives that couldn't be restored with the reason
+    errors: Vec<(String, String)>,
+    /// Newest archive of the chain
+    archive: Option<PathBuf>,
+    /// Number of files restored under another name, because the file already existed (see [ConflictPolicy::Rename])
+    renamed: usize,
+    /// Number of existing files that were kept because of the [ConflictPolicy]
+    kept: usize,
+    /// Original paths of the restored files by the [collision_key] of their destination, so entries that would overwrite each other are detected
+    written: HashMap<String, String>,
+    /// Stops the restore between files
+    cancellation: Cancellation,
+    /// Only determine how the files would change instead of restoring them (see [preview])
+    dry_run: bool,
+    /// Files that would be restored by a `dry_run`
+    changes: Vec<(PathBuf, Change)>,
+}
+
+impl ChainState {
+    /// Logs that `subject` couldn't be restored and records it for the [RestoreOutcome].
+    fn fail(&mut self, subject: &Path, msg: String) {
+        error!("{}", msg);
+        self.errors.push((subject.display().to_string(), msg));
+    }
+}
+
+/// Computes the key under which the file restored to `destination` is recorded in [ChainState::written].
+/// Destinations that aren't unicode are recorded under their escaped form, because the lossy conversion would mix them up.
+fn destination_key(destination: &Path) -> String {
+    match destination.to_str() {
+        Some(destination) => collision_key(destination),
+        None => format!("{:?}", destination),
+    }
+}
+
+/// Compares the file at `destination` to the archive `entry` that would be restored to it.
+/// Files with the same size and modification time are identical, as are files with the same SHA-256 as the entry. Links are compared by their targets.
+fn classify(destination: &Path, entry: &mut ZipFile) -> Change {
+    let long_destination = long_path(destination);
+    let metadata = match fs::symlink_metadata(&long_destination) {
+        Ok(metadata) => metadata,
+        Err(_) => return Change::Missing,
     };
+    if entry.unix_mode().map_or(false, |mode| mode & S_IFMT == S_IFLNK) {
+        let mut target = String::new();
+        let same_target = entry.read_to_string(&mut target).is_ok()
+            && fs::read_link(&long_destination).map_or(false, |existing| existing == Path::new(&target));
+        return if same_target { Change::Identical } else { Change::Different };
+    }
+    if !metadata.is_file() || metadata.len() != entry.size() {
+        return Change::Different;
+    }
+    // the archived time only has a precision of two seconds
+    let same_time = metadata
+        .modified()
+        .ok()
+        .zip(entry_modified(entry.last_modified()))
+        .and_then(|(existing, archived)| existing.duration_since(archived).ok())
+        .map_or(false, |difference| difference < Duration::from_secs(2));
+    if same_time {
+        return Change::Identical;
+    }
+    let existing = File::open(&long_destination).and_then(|mut file| sha256(&mut file));
+    match (existing, sha256(entry)) {
+        (Ok(existing), Ok(archived)) if existing == archived => Change::Identical,
+        _ => Change::Different,
+    }
+}
 
-    let mut best_backup = None;
+/// Computes the SHA-256 of the content of `reader`.
+fn sha256(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut hasher = Sha256::new();
+    io::copy(reader, &mut hasher)?;
+    Ok(hasher.finalize().to_vec())
+}
 
-    for entry in entries {
-        if entry.is_err() {
-            continue;
-        }
+/// Inserts `.restored` before the extension of `path`, e.g., `a.txt` becomes `a.restored.txt` and `README` becomes `README.restored`.
+fn restored_name(path: &Path) -> PathBuf {
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(".restored");
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
 
-        let entry = entry.unwrap();
-        let path = entry.path();
+/// Checks if the archive entry, which was modified at `archived`, is newer than the existing file at `destination`.
+///
+/// # Returns
+/// `false` if a modification time is unknown, so the existing file is kept.
+fn is_archived_newer(destination: &Path, archived: zip::DateTime) -> bool {
+    let existing = match fs::metadata(long_path(destination)).and_then(|metadata| metadata.modified()) {
+        Ok(existing) => existing,
+        Err(_) => return false,
+    };
+    entry_modified(archived).map_or(false, |archived| archived > existing)
+}
 
-        if path.is_dir() {
-            continue;
+/// Zip archives store modification times in steps of 2 seconds, so an unchanged file can seem up to this much newer than its archived version
+const MTIME_RESOLUTION: Duration = Duration::from_secs(2);
+
+/// Checks if the file at `destination` was modified after `archived`, the modification time of its archived version.
+///
+/// # Returns
+/// `false` if the file doesn't exist or its modification time can't be read.
+fn is_destination_newer(destination: &Path, archived: SystemTime) -> bool {
+    let modified = match fs::metadata(long_path(destination)).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => modified,
+        Err(_) => return false,
+    };
+
+    modified > archived + MTIME_RESOLUTION
+}
+
+/// Bit mask of the file type in the mode of an archive entry
+pub const S_IFMT: u32 = 0o170000;
+/// File type of links in the mode of an archive entry
+pub const S_IFLNK: u32 = 0o120000;
+
+/// Creates a link at `path` pointing to `target`, replacing an existing file or link.
+/// On Windows, a dir link is created if the target is a dir.
+fn restore_symlink(path: &Path, target: &str) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_file() || metadata.file_type().is_symlink() => {
+            fs::remove_file(path).or_else(|_| fs::remove_dir(path))?
+        }
+        _ => {}
+    }
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, path)
+    }
+    #[cfg(windows)]
+    {
+        if path.parent().unwrap_or(Path::new(".")).join(target).is_dir() {
+            std::os::windows::fs::symlink_dir(target, path)
+        } else {
+            std::os::windows::fs::symlink_file(target, path)
         }
+    }
+}
 
-        // Extract the creation date from the filename; one could use the creation date of the file, but this way we can be really sure
-        let creation_date = path
-            .file_name()
-            .map(|name| name.to_str().unwrap_or("")) // convert OsStr into normal str
-            .map(|name| name.strip_suffix(".zip").unwrap_or(""))
-            .map(|name| {
-                name.strip_prefix(&(profile_config.get_uuid().as_hyphenated().to_string() + "_"))
-                    .unwrap_or("")
-            })
-            .unwrap_or("");
-        // actually parse str into NaiveDateTime
-        let creation_date = match NaiveDateTime::parse_from_str(creation_date, "%Y-%m-%d_%H-%M") {
-            Ok(date_time) => date_time,
-            Err(e) => {
-                warn!("Couldn't parse date {:?} because {:?}", creation_date, e);
-                continue;
+/// Resolves the `.` and `..` components of the original path of an archive entry without accessing the file system.
+///
+/// # Returns
+/// [None] if a `..` leaves the path, e.g., in `C:/../Windows/a.dll` or `../a.txt`, so the entry would be written outside of the dir it was archived from.
+fn resolve_components(path: &Path) -> Option<PathBuf> {
+    let mut resolved = PathBuf::new();
+    let mut depth = 0;
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if depth == 0 => return None,
+            Component::ParentDir => {
+                resolved.pop();
+                depth -= 1;
             }
-        };
-
-        // update current best
-        if creation_date <= timestamp
-            && best_backup.as_ref().map_or(true, |&(curr_best_date, _)| curr_best_date < creation_date)
-        {   
-            debug!("Update best_backup to {:?}", creation_date);
-            best_backup = Some((creation_date, path));
+            Component::Normal(_) => {
+                resolved.push(component);
+                depth += 1;
+            }
+            Component::Prefix(_) | Component::RootDir => resolved.push(component),
         }
     }
+    Some(resolved)
+}
+
+/// Resolves the links on the way to `path`, which doesn't need to exist yet: Its nearest existing ancestor is canonicalized and the rest is appended.
+/// So a link restored from an archive can't lead the files restored after it outside of the output dir.
+///
+/// # Returns
+/// [None] if the path couldn't be resolved.
+fn resolve_links(path: &Path) -> Option<PathBuf> {
+    let absolute = std::path::absolute(path).ok()?;
+    let existing = absolute.ancestors().find(|ancestor| long_path(ancestor).exists())?;
+    let canonical = fs::canonicalize(long_path(existing)).ok()?;
+    resolve_components(&canonical.join(absolute.strip_prefix(existing).ok()?))
+}
+
+/// Returns the dir that the links restored before `filepath` mustn't lead it out of: the output dir, or the `root` the entry was archived from.
+/// Archives without a manifest have no root, so the entries are only kept below the root of their own path, or the working dir if it is relative.
+fn link_boundary(selection: &Selection, root: &Path, filepath: &Path) -> PathBuf {
+    if let Some(output_dir) = &selection.output_dir {
+        return output_dir.clone();
+    }
+    if !root.as_os_str().is_empty() {
+        return root.to_path_buf();
+    }
+    let fs_root: PathBuf = filepath
+        .components()
+        .take_while(|component| matches!(component, Component::Prefix(_) | Component::RootDir))
+        .collect();
+    if fs_root.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        fs_root
+    }
+}
+
+/// Converts the modification time stored in an archive entry, which is in local time, into a [SystemTime].
+fn entry_modified(date_time: zip::DateTime) -> Option<SystemTime> {
+    let modified = NaiveDate::from_ymd_opt(date_time.year().into(), date_time.month().into(), date_time.day().into())?
+        .and_hms_opt(date_time.hour().into(), date_time.minute().into(), date_time.second().into())?;
+    local_system_time(modified)
+}
+
+/// Converts a local [NaiveDateTime] into a [SystemTime].
+fn local_system_time(date_time: NaiveDateTime) -> Option<SystemTime> {
+    Local.from_local_datetime(&date_time).earliest().map(SystemTime::from)
+}
 
-    best_backup.and_then(|(_, path)| Some(path))
+/// Applies the permission bits stored in an archive entry to the restored file at `path`.
+/// On other platforms than Unix, only the read-only attribute is set, if none of the write bits is set.
+fn apply_permissions(path: &Path, mode: u32) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))
+    }
+    #[cfg(not(unix))]
+    {
+        let mut permissions = fs::metadata(path)?.permissions();
+        permissions.set_readonly(mode & 0o222 == 0);
+        fs::set_permissions(path, permissions)
+    }
 }
 
-/// Restores each file in the given backup.
-/// If a file already exists, it is everwritten. If it doesn't exist, it is created.
-fn restore_from_backup(backup_file: PathBuf) {
+/// Restores each file chosen by the [Selection] in the given backup of the profile with the given [Uuid].
+/// The original paths of the entries are taken from the [ARCHIVE_MANIFEST_NAME] entry. Archives without one contain the original paths as entry names.
+/// If a file already exists, it is handled according to the [ConflictPolicy] of the selection. If it doesn't exist, it is created.
+/// Each file is extracted to a temporary file first, that replaces the existing one once it is complete.
+///
+/// If `protect_newer` contains the time of the backup, existing files that were modified after their archived version are skipped.
+/// Archives older than [ArchiveFormat::Manifest] don't record the modification times of their files, so the time of the backup is used for all of them and a warning is logged.
+/// The restored files get the modification times and permissions recorded in the archive. Link entries are restored as links (see [restore_symlink]).
+/// Entries that would be written outside of the root they were archived from or outside of the output dir (see [resolve_components] and [resolve_links]) are skipped.
+///
+/// Entries that aren't listed in the snapshot (e.g., dropped unstable files) are skipped. If an entry was archived more than once, only the last one is restored.
+/// Entries that can't be read or written are recorded in the `chain` and the other entries are still restored. Only a cancellation stops the archive early.
+/// The snapshot of the newest archive in the `chain` is used for all of its archives, and entries of newer archives aren't restored from older ones.
+fn restore_from_backup(
+    backup_file: PathBuf,
+    uuid: &Uuid,
+    protect_newer: Option<NaiveDateTime>,
+    selection: &Selection,
+    chain: &mut ChainState,
+) {
     let file = match File::open(&backup_file) {
         Ok(file) => file,
         Err(e) => {
-            error!("Error opening file {:?}: {:?}", backup_file, e);
+            chain.fail(&backup_file, format!("Error opening file {:?}: {:?}", backup_file, e));
             return;
         }
     };
@@ -122,39 +733,1089 @@ fn restore_from_backup(backup_file: PathBuf) {
     let mut zip = match ZipArchive::new(file) {
         Ok(file) => file,
         Err(e) => {
-            error!("Couldn't create archive because {:?}", e);
+            chain.fail(&backup_file, format!("Couldn't create archive because {:?}", e));
             return;
         }
     };
 
+    let format = ArchiveFormat::detect(&zip);
+    debug!("Archive {:?} has format {:?}", backup_file, format);
+    // the modification times of the entries were recorded since the manifest was introduced
+    let records_mtimes = format == ArchiveFormat::Manifest;
+    match protect_newer {
+        Some(_) if records_mtimes => info!("Skipping files modified after their archived version."),
+        Some(backup_time) => warn!(
+            "{:?} doesn't record the modification times of its files, so files modified after the backup at {} are skipped",
+            backup_file, backup_time
+        ),
+        None => {}
+    }
+    info!("Restoring {}", selection);
+
+    let manifest = read_manifest(&mut zip);
+    let original = |name: &String| original_path(manifest.as_ref(), name);
+    let snapshot: Option<HashSet<String>> = read_list(&mut zip, SNAPSHOT_FILE_NAME)
+        .map(|names| names.iter().filter_map(original).collect());
+    if !chain.started {
+        chain.started = true;
+        chain.snapshot = snapshot;
+    }
+    chain.unstable.extend(
+        read_list(&mut zip, UNSTABLE_FILE_NAME)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(original),
+    );
+    // retried files are archived again, and the later entry supersedes the earlier one
+    let last_index: HashMap<String, usize> = (0..zip.len())
+        .filter_map(|i| Some((zip.by_index_raw(i).ok()?.name().to_string(), i)))
+        .collect();
+
+    let mut restored = 0;
+    let mut skipped_newer = 0;
+    let mut dropped = 0;
+    let mut collisions = 0;
+    let mut escaping = 0;
+    let mut renamed = 0;
+    let mut kept = 0;
+    let mut restored_unstable = vec![];
+
     for i in 0..zip.len() {
+        if chain.cancellation.is_cancelled() {
+            break;
+        }
         let mut file = match zip.by_index(i) {
             Ok(file) => file,
             Err(e) => {
-                error!("Error extracting file: {:?}", e);
-                return;
+                chain.fail(&backup_file, format!("Error extracting file: {:?}", e));
+                continue;
+            }
+        };
+        if [SNAPSHOT_FILE_NAME, UNSTABLE_FILE_NAME, ARCHIVE_MANIFEST_NAME].contains(&file.name()) {
+            continue;
+        }
+        if last_index.get(file.name()) != Some(&i) {
+            continue;
+        }
+        let original = match original_path(manifest.as_ref(), file.name()) {
+            Some(original) => original,
+            None => {
+                warn!("Skip {:?} because its root isn't listed in the manifest", file.name());
+                continue;
+            }
+        };
+        if chain.superseded.contains(&original) {
+            continue;
+        }
+        // paths that aren't unicode are restored under their true names
+        let original_os = manifest
+            .as_ref()
+            .and_then(|manifest| manifest.original_os_path(file.name()))
+            .unwrap_or_else(|| PathBuf::from(&original));
+        let root = manifest
+            .as_ref()
+            .and_then(|manifest| manifest.roots.get(file.name().split_once('/')?.0))
+            .map_or_else(PathBuf::new, PathBuf::from);
+        let original_os = match resolve_components(&original_os).filter(|resolved| resolved.starts_with(&root)) {
+            Some(resolved) => resolved,
+            None => {
+                warn!("Skip {:?} because it would be restored outside of {:?}", file.name(), root);
+                escaping += 1;
+                continue;
+            }
+        };
+        let filepath = match selection.destination(&original_os) {
+            Some(filepath) => filepath,
+            None => continue,
+        };
+        if chain.snapshot.as_ref().map_or(false, |snapshot| !snapshot.contains(&original)) {
+            debug!("Skip {:?} because it was dropped from the backup", filepath);
+            dropped += 1;
+            continue;
+        }
+        chain.matched += 1;
+
+        let archived = protect_newer.and_then(|backup_time| {
+            Some(file.last_modified())
+                .filter(|_| records_mtimes)
+                .and_then(entry_modified)
+                .or_else(|| local_system_time(backup_time))
+        });
+        if archived.map_or(false, |archived| is_destination_newer(&filepath, archived)) {
+            debug!("Skip {:?} because it is newer than its archived version", filepath);
+            skipped_newer += 1;
+            chain.skipped += 1;
+            continue;
+        }
+        let destination_key = destination_key(&filepath);
+        if let Some(written) = chain.written.get(&destination_key).filter(|written| **written != original) {
+            warn!("Skip {:?} because {:?} was already restored to the same destination", original, written);
+            collisions += 1;
+            chain.skipped += 1;
+            continue;
+        }
+
+        let exists = fs::symlink_metadata(long_path(&filepath)).is_ok();
+        let filepath = match selection.on_conflict {
+            _ if !exists => filepath,
+            ConflictPolicy::Overwrite => filepath,
+            ConflictPolicy::Skip => {
+                debug!("Skip {:?} because it already exists", filepath);
+                kept += 1;
+                continue;
+            }
+            ConflictPolicy::Newer if !is_archived_newer(&filepath, file.last_modified()) => {
+                debug!("Skip {:?} because it isn't older than the archived file", filepath);
+                kept += 1;
+                continue;
+            }
+            ConflictPolicy::Newer => filepath,
+            ConflictPolicy::Rename => {
+                let renamed = restored_name(&filepath);
+                debug!("Restore {:?} as {:?} because it already exists", filepath, renamed);
+                renamed
             }
         };
-        let filepath = PathBuf::from(file.name());
+        let is_renamed = exists && selection.on_conflict == ConflictPolicy::Rename;
+
+        let boundary = link_boundary(selection, &root, &filepath);
+        let dir = filepath.parent().unwrap_or(Path::new("."));
+        if !resolve_links(dir).zip(resolve_links(&boundary)).map_or(false, |(path, dir)| path.starts_with(dir)) {
+            warn!("Skip {:?} because a link would lead it outside of {:?}", filepath, boundary);
+            escaping += 1;
+            chain.skipped += 1;
+            continue;
+        }
+
+        if chain.dry_run {
+            let change = classify(&filepath, &mut file);
+            debug!("{:?} would be restored, it is {}", filepath, change);
+            chain.changes.push((filepath, change));
+            chain.written.insert(destination_key, original);
+            continue;
+        }
 
-        if let Some(p) = filepath.parent() {
+        // the extended-length form is only used to write the file, the logs keep the original path
+        let long_filepath = long_path(&filepath);
+        if let Some(p) = long_filepath.parent() {
             if !p.exists() {
                 if let Err(e) = fs::create_dir_all(p) {
-                    error!("Couldn't create dir {:?} because {:?}", filepath.parent(), e);
-                    return;
+                    chain.fail(&filepath, format!("Couldn't create dir {:?} because {:?}", filepath.parent(), e));
+                    continue;
                 }
             }
         }
-        let mut outfile = match fs::File::create(&filepath) {
+        if file.unix_mode().map_or(false, |mode| mode & S_IFMT == S_IFLNK) {
+            let mut target = String::new();
+            if let Err(e) = file.read_to_string(&mut target).and_then(|_| restore_symlink(&long_filepath, &target)) {
+                chain.fail(&filepath, format!("Couldn't restore link {:?} to {:?} because {:?}", filepath, target, e));
+                continue;
+            }
+            chain.written.insert(destination_key, original);
+            restored += 1;
+            renamed += usize::from(is_renamed);
+            continue;
+        }
+        let temp_path = temp_path_for(long_filepath.parent().unwrap_or(Path::new(".")), TempKind::Extract, uuid);
+        let mut outfile = match fs::File::create(&temp_path) {
             Ok(outfile) => outfile,
             Err(e) => {
-                error!("Couldn't create outfile {:?} because {:?}", filepath, e);
-                return;
+                chain.fail(&filepath, format!("Couldn't create outfile {:?} because {:?}", temp_path, e));
+                continue;
             }
         };
         if let Err(e) = io::copy(&mut file, &mut outfile) {
-            error!("Couldn't copy to outfile because {:?}", e);
-            return;
+            chain.fail(&filepath, format!("Couldn't copy to outfile because {:?}", e));
+            drop(outfile);
+            let _ = fs::remove_file(&temp_path);
+            continue;
+        }
+        if let Some(modified) = entry_modified(file.last_modified()) {
+            if let Err(e) = outfile.set_modified(modified) {
+                warn!("Couldn't set modification time of {:?} because {:?}", filepath, e);
+            }
+        }
+        drop(outfile);
+        if let Err(e) = fs::rename(&temp_path, &long_filepath) {
+            chain.fail(&filepath, format!("Couldn't move {:?} to {:?} because {:?}", temp_path, filepath, e));
+            let _ = fs::remove_file(&temp_path);
+            continue;
+        }
+        if let Some(mode) = file.unix_mode() {
+            if let Err(e) = apply_permissions(&long_filepath, mode) {
+                warn!("Couldn't set permissions of {:?} because {:?}", filepath, e);
+            }
+        }
+        if chain.unstable.contains(&original) {
+            restored_unstable.push(filepath);
+        }
+        chain.written.insert(destination_key, original);
+        restored += 1;
+        renamed += usize::from(is_renamed);
+    }
+    chain.superseded.extend(last_index.keys().filter_map(original));
+    chain.restored += restored;
+    chain.renamed += renamed;
+    chain.kept += kept;
+    chain.skipped += kept;
+
+    if chain.dry_run {
+        return;
+    }
+    info!(
+        "Restored {} files ({}, {} of them renamed), kept {} existing files, skipped {} files that are newer than the backup, {} files that were dropped, {} files that collided with another one and {} files that would have been restored outside of their dir.",
+        restored, selection, renamed, kept, skipped_newer, dropped, collisions, escaping
+    );
+    if !restored_unstable.is_empty() {
+        warn!(
+            "Restored {} files that changed while they were backed up and may be inconsistent: {:?}",
+            restored_unstable.len(),
+            restored_unstable
+        );
+    }
+}
+
+#[cfg(test)]
+mod restore_tests {
+    use super::*;
+
+    #[test]
+    fn destination_newer() {
+        let path = PathBuf::from("./test_dir/destination_newer_tmp.txt");
+        fs::write(&path, "new").unwrap();
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+
+        let newer = is_destination_newer(&path, now - day);
+        let older = is_destination_newer(&path, now + day);
+        let same = is_destination_newer(&path, fs::metadata(&path).unwrap().modified().unwrap() - Duration::from_secs(1));
+        fs::remove_file(&path).unwrap();
+
+        assert!(newer);
+        assert!(!older);
+        assert!(!same);
+        assert!(!is_destination_newer(&PathBuf::from("./test_dir/doesnt_exist.txt"), now));
+    }
+
+    #[test]
+    fn restore_after_new_uuid() {
+        let dir = PathBuf::from("./test_dir/restore_new_uuid_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let mut profile_config = ProfileConfig::new(
+            String::from("renamed"),
+            dir.clone(),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        );
+        let archive = dir.join(format!("{}_2023-05-01_10-00.zip", profile_config.get_uuid().as_hyphenated()));
+        File::create(&archive).unwrap();
+
+        profile_config.regenerate_uuid();
+        let now = chrono::Local::now().naive_local();
+        let found = find_backup_chain(&profile_config, now);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found, Ok(vec![archive]));
+    }
+
+    /// Creates empty archives with the given names in the given `dir` for the given profile.
+    fn create_archives(dir: &PathBuf, profile_config: &ProfileConfig, names: &[&str]) -> Vec<PathBuf> {
+        fs::create_dir_all(dir).unwrap();
+        names
+            .iter()
+            .map(|name| {
+                let path = dir.join(format!("{}_{}.zip", profile_config.get_uuid().as_hyphenated(), name));
+                File::create(&path).unwrap();
+                path
+            })
+            .collect()
+    }
+
+    fn chain_test_config(dir: &PathBuf) -> ProfileConfig {
+        ProfileConfig::new(
+            String::from("chain"),
+            dir.clone(),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            config::interval::IntervalBuilder::default().build().unwrap(),
+        )
+    }
+
+    fn timestamp(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn chain_full_only() {
+        let dir = PathBuf::from("./test_dir/chain_full_only_tmp");
+        let profile_config = chain_test_config(&dir);
+        let archives = create_archives(&dir, &profile_config, &["2023-05-01_10-00", "2023-06-01_10-00"]);
+
+        let between = find_backup_chain(&profile_config, timestamp("2023-05-15 10:00"));
+        let before = find_backup_chain(&profile_config, timestamp("2023-04-01 10:00"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(between, Ok(vec![archives[0].clone()]));
+        assert_eq!(before, Ok(vec![]));
+    }
+
+    #[test]
+    fn chain_full_and_increments() {
+        let dir = PathBuf::from("./test_dir/chain_increments_tmp");
+        let profile_config = chain_test_config(&dir);
+        let archives = create_archives(
+            &dir,
+            &profile_config,
+            &[
+                "2023-05-01_10-00",
+                "2023-05-02_10-00_incr",
+                "2023-06-01_10-00",
+                "2023-06-02_10-00_incr",
+                "2023-06-03_10-00_incr",
+                "2023-06-04_10-00_incr",
+            ],
+        );
+
+        let chain = find_backup_chain(&profile_config, timestamp("2023-06-03 12:00"));
+        let first_chain = find_backup_chain(&profile_config, timestamp("2023-05-20 12:00"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(chain, Ok(archives[2..5].to_vec()));
+        assert_eq!(first_chain, Ok(archives[0..2].to_vec()));
+    }
+
+    #[test]
+    fn chain_missing_full() {
+        let dir = PathBuf::from("./test_dir/chain_missing_full_tmp");
+        let profile_config = chain_test_config(&dir);
+        create_archives(&dir, &profile_config, &["2023-05-02_10-00_incr", "2023-05-03_10-00_incr"]);
+
+        let chain = find_backup_chain(&profile_config, timestamp("2023-06-01 10:00"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(chain.unwrap_err().contains("no full archive"));
+    }
+
+    #[test]
+    fn restore_chain_newest_first() {
+        use std::io::Write;
+        use zip::{write::FileOptions, ZipWriter};
+
+        let dir = PathBuf::from("./test_dir/restore_chain_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let write_archive = |name: &str, entries: &[(&str, &str)]| {
+            let archive = dir.join(name);
+            let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+            for (name, content) in entries {
+                zip.start_file(*name, FileOptions::default()).unwrap();
+                zip.write_all(content.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+            archive
+        };
+        let full = write_archive(
+            "full.zip",
+            &[("a.txt", "full"), ("b.txt", "full"), ("deleted.txt", "full"), (SNAPSHOT_FILE_NAME, "a.txt\nb.txt\ndeleted.txt\n")],
+        );
+        let incr = write_archive("incr.zip", &[("a.txt", "incr"), (SNAPSHOT_FILE_NAME, "a.txt\nb.txt\n")]);
+
+        let out = dir.join("out");
+        let selection = Selection {
+            output_dir: Some(out.clone()),
+            ..Default::default()
+        };
+        let mut chain_state = ChainState::default();
+        for archive in [incr, full] {
+            restore_from_backup(archive, &Uuid::nil(), None, &selection, &mut chain_state);
         }
+        let a = fs::read_to_string(out.join("a.txt")).unwrap();
+        let b = fs::read_to_string(out.join("b.txt")).unwrap();
+        let deleted_exists = out.join("deleted.txt").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(a, "incr");
+        assert_eq!(b, "full");
+        assert!(!deleted_exists);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn cancelled_restore() {
+        use std::io::Write;
+        use zip::{write::FileOptions, ZipWriter};
+
+        let dir = PathBuf::from("./test_dir/restore_cancelled_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        for (name, content) in [("docs/a.txt", "a"), ("docs/b.txt", "b")] {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        drop(zip);
+
+        let out = dir.join("out");
+        let selection = Selection {
+            output_dir: Some(out.clone()),
+            ..Default::default()
+        };
+        let mut chain_state = ChainState::default();
+        chain_state.cancellation.cancel();
+        restore_from_backup(archive, &Uuid::nil(), None, &selection, &mut chain_state);
+        let restored = out.exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!restored);
+    }
+
+    #[test]
+    fn skip_dropped_and_superseded() {
+        use std::io::Write;
+        use zip::{write::FileOptions, ZipWriter};
+
+        let dir = PathBuf::from("./test_dir/restore_dropped_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        for (name, content) in [
+            ("docs/a.txt", "first"),
+            ("docs/dropped.log", "dropped"),
+            ("docs/a.txt", "retried"),
+            (SNAPSHOT_FILE_NAME, "docs/a.txt\n"),
+            (UNSTABLE_FILE_NAME, "docs/a.txt\ndocs/dropped.log\n"),
+        ] {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        drop(zip);
+
+        let out = dir.join("out");
+        let selection = Selection {
+            output_dir: Some(out.clone()),
+            ..Default::default()
+        };
+        restore_from_backup(archive, &Uuid::nil(), None, &selection, &mut ChainState::default());
+        let a = fs::read_to_string(out.join("docs/a.txt")).unwrap();
+        let dropped_exists = out.join("docs/dropped.log").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(a, "retried");
+        assert!(!dropped_exists);
+    }
+
+    #[test]
+    fn restore_with_manifest() {
+        use std::io::Write;
+        use zip::{write::FileOptions, ZipWriter};
+        use config::archive_manifest::ArchiveManifest;
+
+        let dir = PathBuf::from("./test_dir/restore_manifest_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.zip");
+        let mut manifest = ArchiveManifest::default();
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        for (path, content) in [("C:/Users/me/docs/a.txt", "a"), ("C:/Users/me/pics/b.jpg", "b")] {
+            zip.start_file(manifest.entry_name(Path::new(path)), FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        // entries below unknown roots can't be restored
+        zip.start_file("D/c.txt", FileOptions::default()).unwrap();
+        crate::backup::write_manifest(&mut zip, &manifest).unwrap();
+        zip.finish().unwrap();
+        drop(zip);
+
+        let out = dir.join("out");
+        let selection = Selection {
+            subtrees: vec![PathBuf::from("C:/Users/me/docs")],
+            strip_prefix: true,
+            output_dir: Some(out.clone()),
+            on_conflict: ConflictPolicy::Overwrite,
+        };
+        restore_from_backup(archive, &Uuid::nil(), None, &selection, &mut ChainState::default());
+        let a = fs::read_to_string(out.join("a.txt")).unwrap();
+        let restored = fs::read_dir(&out).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(a, "a");
+        assert_eq!(restored, 1);
+    }
+
+    #[test]
+    fn restore_several_subtrees() {
+        use std::io::Write;
+        use zip::{write::FileOptions, ZipWriter};
+
+        let dir = PathBuf::from("./test_dir/restore_subtrees_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        for name in [
+            "docs/tax/2022/a.pdf",
+            "docs/tax/b.pdf",
+            "docs/taxidermy/c.txt",
+            "pics/2022/summer/d.jpg",
+            "pics/2021/e.jpg",
+        ] {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(name.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        drop(zip);
+
+        let out = dir.join("out");
+        let selection = Selection {
+            subtrees: vec![PathBuf::from("docs/tax"), PathBuf::from("pics\\2022"), PathBuf::from("pics/2022/summer")],
+            output_dir: Some(out.clone()),
+            ..Default::default()
+        };
+        let mut chain_state = ChainState::default();
+        restore_from_backup(archive.clone(), &Uuid::nil(), None, &selection, &mut chain_state);
+        let mut restored = vec![];
+        for name in ["docs/tax/2022/a.pdf", "docs/tax/b.pdf", "docs/taxidermy/c.txt", "pics/2022/summer/d.jpg", "pics/2021/e.jpg"] {
+            if out.join(name).exists() {
+                restored.push(name);
+            }
+        }
+        let typo = Selection {
+            subtrees: vec![PathBuf::from("docs/ta")],
+            output_dir: Some(dir.join("typo")),
+            ..Default::default()
+        };
+        let mut typo_state = ChainState::default();
+        restore_from_backup(archive, &Uuid::nil(), None, &typo, &mut typo_state);
+        let typo_restored = dir.join("typo").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(chain_state.matched, 3);
+        assert_eq!(restored, vec!["docs/tax/2022/a.pdf", "docs/tax/b.pdf", "pics/2022/summer/d.jpg"]);
+        assert_eq!(typo_state.matched, 0);
+        assert!(!typo_restored);
+    }
+
+    #[test]
+    fn resolve_parent_components() {
+        assert_eq!(resolve_components(Path::new("/a/./b/../c.txt")), Some(PathBuf::from("/a/c.txt")));
+        assert_eq!(resolve_components(Path::new("docs/sub/../a.txt")), Some(PathBuf::from("docs/a.txt")));
+        assert_eq!(resolve_components(Path::new("../a.txt")), None);
+        assert_eq!(resolve_components(Path::new("docs/../../a.txt")), None);
+        assert_eq!(resolve_components(Path::new("/../etc/passwd")), None);
+    }
+
+    #[test]
+    fn malicious_entries() {
+        use std::io::Write;
+        use zip::{write::FileOptions, ZipWriter};
+        use config::archive_manifest::ArchiveManifest;
+
+        let dir = PathBuf::from("./test_dir/restore_malicious_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let write_archive = |name: &str, entries: &[&str], manifest: Option<&ArchiveManifest>| {
+            let archive = dir.join(name);
+            let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+            for name in entries {
+                zip.start_file(*name, FileOptions::default()).unwrap();
+                zip.write_all(b"x").unwrap();
+            }
+            if let Some(manifest) = manifest {
+                crate::backup::write_manifest(&mut zip, manifest).unwrap();
+            }
+            zip.finish().unwrap();
+            archive
+        };
+
+        // archives without a manifest use the original paths as entry names
+        let out = dir.join("out");
+        let legacy = write_archive(
+            "legacy.zip",
+            &["../evil.txt", "docs/../../evil.txt", "/docs/../../evil.txt", "docs/./a.txt", "docs/sub/../b.txt"],
+            None,
+        );
+        let selection = Selection {
+            output_dir: Some(out.clone()),
+            ..Default::default()
+        };
+        restore_from_backup(legacy, &Uuid::nil(), None, &selection, &mut ChainState::default());
+        let mut legacy_restored: Vec<PathBuf> = fs::read_dir(out.join("docs")).unwrap().map(|entry| entry.unwrap().path()).collect();
+        legacy_restored.sort();
+
+        // entries mustn't leave the root they were archived from
+        let root = std::path::absolute(dir.join("root")).unwrap();
+        let mut manifest = ArchiveManifest::default();
+        manifest.roots.insert(String::from("R"), format!("{}/", root.to_string_lossy()));
+        let rooted = write_archive("rooted.zip", &["R/a.txt", "R/../evil.txt", "R/sub/../../evil.txt"], Some(&manifest));
+        restore_from_backup(rooted, &Uuid::nil(), None, &Selection::default(), &mut ChainState::default());
+        let rooted_a = root.join("a.txt").exists();
+
+        let escaped = dir.join("evil.txt").exists() || out.join("evil.txt").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(legacy_restored, vec![out.join("docs").join("a.txt"), out.join("docs").join("b.txt")]);
+        assert!(rooted_a);
+        assert!(!escaped);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_dont_lead_outside() {
+        use std::io::Write;
+        use zip::{write::FileOptions, ZipWriter};
+
+        let dir = PathBuf::from("./test_dir/restore_link_escape_tmp");
+        fs::create_dir_all(dir.join("outside")).unwrap();
+        let archive = dir.join("archive.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        zip.add_symlink("docs/link", "../../outside", FileOptions::default()).unwrap();
+        zip.start_file("docs/link/evil.txt", FileOptions::default()).unwrap();
+        zip.write_all(b"evil").unwrap();
+        zip.start_file("docs/a.txt", FileOptions::default()).unwrap();
+        zip.write_all(b"a").unwrap();
+        zip.finish().unwrap();
+        drop(zip);
+
+        let out = dir.join("out");
+        let selection = Selection {
+            output_dir: Some(out.clone()),
+            ..Default::default()
+        };
+        restore_from_backup(archive, &Uuid::nil(), None, &selection, &mut ChainState::default());
+        let link = fs::symlink_metadata(out.join("docs").join("link")).map_or(false, |metadata| metadata.file_type().is_symlink());
+        let a = out.join("docs").join("a.txt").exists();
+        let escaped = dir.join("outside").join("evil.txt").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(link);
+        assert!(a);
+        assert!(!escaped);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_dont_lead_outside_of_original_locations() {
+        use std::io::Write;
+        use zip::{write::FileOptions, ZipWriter};
+        use config::archive_manifest::ArchiveManifest;
+
+        let dir = PathBuf::from("./test_dir/restore_link_escape_original_tmp");
+        // the working dir contains the test dir, so the link has to lead out of both
+        let outside = std::env::temp_dir().join("restore_link_escape_original_outside_tmp");
+        let root = std::path::absolute(dir.join("root")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::create_dir_all(&root).unwrap();
+        let write_archive = |name: &str, prefix: &str, manifest: Option<&ArchiveManifest>| {
+            let archive = dir.join(name);
+            let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+            zip.add_symlink(format!("{}/link", prefix), outside.to_string_lossy(), FileOptions::default()).unwrap();
+            zip.start_file(format!("{}/link/evil.txt", prefix), FileOptions::default()).unwrap();
+            zip.write_all(b"evil").unwrap();
+            zip.start_file(format!("{}/a.txt", prefix), FileOptions::default()).unwrap();
+            zip.write_all(b"a").unwrap();
+            if let Some(manifest) = manifest {
+                crate::backup::write_manifest(&mut zip, manifest).unwrap();
+            }
+            zip.finish().unwrap();
+            archive
+        };
+
+        // the entries must stay below the root they were archived from
+        let mut manifest = ArchiveManifest::default();
+        manifest.roots.insert(String::from("R"), format!("{}/", root.to_string_lossy()));
+        let rooted = write_archive("rooted.zip", "R", Some(&manifest));
+        restore_from_backup(rooted, &Uuid::nil(), None, &Selection::default(), &mut ChainState::default());
+        let rooted_a = root.join("a.txt").exists();
+        let rooted_escaped = outside.join("evil.txt").exists();
+
+        // archives without a manifest have no root, so relative entries stay below the working dir
+        let legacy_dir = dir.join("legacy");
+        let legacy = write_archive("legacy.zip", &legacy_dir.to_string_lossy(), None);
+        let mut chain_state = ChainState::default();
+        restore_from_backup(legacy, &Uuid::nil(), None, &Selection::default(), &mut chain_state);
+        let legacy_a = legacy_dir.join("a.txt").exists();
+        let legacy_escaped = outside.join("evil.txt").exists();
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_dir_all(&outside).unwrap();
+
+        assert!(rooted_a);
+        assert!(!rooted_escaped);
+        assert!(legacy_a);
+        assert!(!legacy_escaped);
+        assert_eq!(chain_state.skipped, 1);
+    }
+
+    #[test]
+    fn protect_newer_than_archived_version() {
+        use std::io::Write;
+        use zip::{write::FileOptions, ZipWriter};
+        use config::archive_manifest::ArchiveManifest;
+
+        let dir = PathBuf::from("./test_dir/restore_protect_newer_tmp");
+        let root = std::path::absolute(dir.join("root")).unwrap();
+        let legacy_dir = dir.join("legacy");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&legacy_dir).unwrap();
+        let local = |year: i32| SystemTime::from(NaiveDate::from_ymd_opt(year, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Local).unwrap());
+        let write_archive = |name: &str, prefix: &str, manifest: Option<&ArchiveManifest>| {
+            let archive = dir.join(name);
+            let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+            let archived = zip::DateTime::from_date_and_time(2020, 1, 1, 0, 0, 0).unwrap();
+            for name in ["newer.txt", "older.txt"] {
+                zip.start_file(format!("{}/{}", prefix, name), FileOptions::default().last_modified_time(archived)).unwrap();
+                zip.write_all(b"archived").unwrap();
+            }
+            if let Some(manifest) = manifest {
+                crate::backup::write_manifest(&mut zip, manifest).unwrap();
+            }
+            zip.finish().unwrap();
+            archive
+        };
+        let existing = |dir: &Path| {
+            for (name, year) in [("newer.txt", 2021), ("older.txt", 2019)] {
+                fs::write(dir.join(name), "existing").unwrap();
+                File::options().write(true).open(dir.join(name)).unwrap().set_modified(local(year)).unwrap();
+            }
+        };
+        let backup_time = NaiveDate::from_ymd_opt(2022, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+
+        // the recorded modification times of the entries are compared, not the time of the backup
+        let mut manifest = ArchiveManifest::default();
+        manifest.roots.insert(String::from("R"), format!("{}/", root.to_string_lossy()));
+        let rooted = write_archive("rooted.zip", "R", Some(&manifest));
+        existing(&root);
+        let mut chain_state = ChainState::default();
+        restore_from_backup(rooted, &Uuid::nil(), Some(backup_time), &Selection::default(), &mut chain_state);
+        let rooted = (fs::read_to_string(root.join("newer.txt")).unwrap(), fs::read_to_string(root.join("older.txt")).unwrap(), chain_state.skipped);
+
+        // legacy archives don't record them, so the time of the backup is used
+        let legacy = write_archive("legacy.zip", &legacy_dir.to_string_lossy(), None);
+        existing(&legacy_dir);
+        restore_from_backup(legacy, &Uuid::nil(), Some(backup_time), &Selection::default(), &mut ChainState::default());
+        let legacy = (fs::read_to_string(legacy_dir.join("newer.txt")).unwrap(), fs::read_to_string(legacy_dir.join("older.txt")).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(rooted, (String::from("existing"), String::from("archived"), 1));
+        assert_eq!(legacy, (String::from("archived"), String::from("archived")));
+    }
+
+    #[test]
+    fn conflict_policies() {
+        use std::io::Write;
+        use zip::{write::FileOptions, ZipWriter};
+
+        let dir = PathBuf::from("./test_dir/restore_conflicts_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        let archived = zip::DateTime::from_date_and_time(2020, 1, 1, 0, 0, 0).unwrap();
+        for name in ["docs/newer.txt", "docs/older.txt", "docs/missing.txt", "docs/README"] {
+            zip.start_file(name, FileOptions::default().last_modified_time(archived)).unwrap();
+            zip.write_all(b"archived").unwrap();
+        }
+        zip.finish().unwrap();
+        drop(zip);
+
+        let mut results = vec![];
+        for policy in [ConflictPolicy::Overwrite, ConflictPolicy::Skip, ConflictPolicy::Rename, ConflictPolicy::Newer] {
+            let out = dir.join(format!("{:?}", policy));
+            fs::create_dir_all(out.join("docs")).unwrap();
+            for name in ["newer.txt", "older.txt", "README"] {
+                fs::write(out.join("docs").join(name), "existing").unwrap();
+            }
+            let older = SystemTime::from(NaiveDate::from_ymd_opt(2000, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Local).unwrap());
+            File::options().write(true).open(out.join("docs").join("older.txt")).unwrap().set_modified(older).unwrap();
+
+            let selection = Selection {
+                output_dir: Some(out.clone()),
+                on_conflict: policy,
+                ..Default::default()
+            };
+            let mut chain_state = ChainState::default();
+            restore_from_backup(archive.clone(), &Uuid::nil(), None, &selection, &mut chain_state);
+            let mut files: Vec<(String, String)> = fs::read_dir(out.join("docs"))
+                .unwrap()
+                .map(|entry| {
+                    let path = entry.unwrap().path();
+                    (path.file_name().unwrap().to_string_lossy().into_owned(), fs::read_to_string(&path).unwrap())
+                })
+                .collect();
+            files.sort();
+            results.push((files, chain_state.restored, chain_state.renamed, chain_state.kept));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        let file = |name: &str, content: &str| (String::from(name), String::from(content));
+        assert_eq!(
+            results[0],
+            (vec![file("README", "archived"), file("missing.txt", "archived"), file("newer.txt", "archived"), file("older.txt", "archived")], 4, 0, 0)
+        );
+        assert_eq!(
+            results[1],
+            (vec![file("README", "existing"), file("missing.txt", "archived"), file("newer.txt", "existing"), file("older.txt", "existing")], 1, 0, 3)
+        );
+        assert_eq!(
+            results[2],
+            (
+                vec![
+                    file("README", "existing"),
+                    file("README.restored", "archived"),
+                    file("missing.txt", "archived"),
+                    file("newer.restored.txt", "archived"),
+                    file("newer.txt", "existing"),
+                    file("older.restored.txt", "archived"),
+                    file("older.txt", "existing"),
+                ],
+                4,
+                3,
+                0
+            )
+        );
+        assert_eq!(
+            results[3],
+            (vec![file("README", "existing"), file("missing.txt", "archived"), file("newer.txt", "existing"), file("older.txt", "archived")], 2, 0, 2)
+        );
+    }
+
+    #[test]
+    fn restore_explicit_archive() {
+        use std::io::Write;
+        use zip::{write::FileOptions, ZipWriter};
+
+        let dir = PathBuf::from("./test_dir/restore_explicit_archive_tmp");
+        let out = dir.join("out");
+        fs::create_dir_all(&out).unwrap();
+        let archive = dir.join("renamed by hand.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        zip.start_file("docs/a.txt", FileOptions::default()).unwrap();
+        zip.write_all(b"archived").unwrap();
+        zip.finish().unwrap();
+        drop(zip);
+        let not_a_zip = dir.join("notes.txt");
+        fs::write(&not_a_zip, "no archive").unwrap();
+
+        let selection = Selection {
+            output_dir: Some(out.clone()),
+            ..Default::default()
+        };
+        let cancellation = Cancellation::default();
+        let outcome = restore_archive(&archive, true, &selection, &cancellation);
+        let restored = fs::read_to_string(out.join("docs").join("a.txt"));
+        let not_a_zip = restore_archive(&not_a_zip, true, &selection, &cancellation);
+        let missing = restore_archive(&dir.join("missing.zip"), true, &selection, &cancellation);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let outcome = outcome.unwrap();
+        assert_eq!((outcome.matched, outcome.restored, outcome.errors.len()), (1, 1, 0));
+        assert_eq!(outcome.archive, Some(archive));
+        assert_eq!(restored.unwrap(), "archived");
+        assert!(not_a_zip.unwrap_err().contains("isn't a zip archive"));
+        assert!(missing.unwrap_err().contains("isn't a file"));
+    }
+
+    #[test]
+    fn unwritable_entry_doesnt_stop_archive() {
+        use std::io::Write;
+        use zip::{write::FileOptions, ZipWriter};
+
+        let dir = PathBuf::from("./test_dir/restore_unwritable_entry_tmp");
+        let out = dir.join("out");
+        fs::create_dir_all(&out).unwrap();
+        // a file where the dir of the first entry has to be created
+        fs::write(out.join("blocked"), "in the way").unwrap();
+        let archive = dir.join("archive.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        for name in ["blocked/a.txt", "docs/b.txt"] {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(b"archived").unwrap();
+        }
+        zip.finish().unwrap();
+        drop(zip);
+
+        let selection = Selection {
+            output_dir: Some(out.clone()),
+            ..Default::default()
+        };
+        let mut chain_state = ChainState::default();
+        restore_from_backup(archive, &Uuid::nil(), None, &selection, &mut chain_state);
+        let restored = fs::read_to_string(out.join("docs").join("b.txt"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(restored.unwrap(), "archived");
+        assert_eq!(chain_state.restored, 1);
+        assert_eq!(chain_state.errors.len(), 1);
+        assert_eq!(chain_state.errors[0].0, out.join("blocked").join("a.txt").display().to_string());
+        // older archives of the chain mustn't restore either of them
+        assert!(chain_state.superseded.contains("blocked/a.txt"));
+        assert!(chain_state.superseded.contains("docs/b.txt"));
+    }
+
+    #[test]
+    fn failures_are_counted() {
+        let missing = PathBuf::from("./test_dir/doesnt_exist.zip");
+        let mut chain_state = ChainState::default();
+        restore_from_backup(missing.clone(), &Uuid::nil(), None, &Selection::default(), &mut chain_state);
+        let outcome = RestoreOutcome::from(chain_state);
+
+        assert_eq!(outcome.errors.len(), 1);
+        assert_eq!(outcome.errors[0].0, missing.display().to_string());
+        assert_eq!(outcome.exit_code(), PARTIALLY_RESTORED);
+        assert!(outcome.summary().starts_with("Restored 0 files (0 of them renamed), skipped 0 files (0 of them existing ones that were kept), 1 errors:\n"));
+        assert_eq!(RestoreOutcome::default().exit_code(), exitcode::OK);
+    }
+
+    #[test]
+    fn archive_names() {
+        let uuid = Uuid::new_v4();
+        let created = NaiveDate::from_ymd_opt(2023, 4, 1).unwrap().and_hms_opt(13, 37, 0).unwrap();
+        let name = |name: &str| PathBuf::from("D:/old").join(format!("{}_{}", uuid.as_hyphenated(), name));
+
+        assert_eq!(parse_archive_name(&name("2023-04-01_13-37.zip")), Some((uuid, created)));
+        assert_eq!(parse_archive_name(&name("2023-04-01_13-37_incr.part02.zip")), Some((uuid, created)));
+        assert_eq!(parse_archive_name(Path::new("D:/old/backup.zip")), None);
+        assert_eq!(parse_archive_name(&name("yesterday.zip")), None);
+    }
+
+    #[test]
+    fn dry_run_classifies() {
+        use std::io::Write;
+        use zip::{write::FileOptions, ZipWriter};
+
+        let dir = PathBuf::from("./test_dir/restore_dry_run_tmp");
+        let out = dir.join("out");
+        fs::create_dir_all(out.join("docs")).unwrap();
+        let archive = dir.join("archive.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        let archived = zip::DateTime::from_date_and_time(2020, 1, 1, 0, 0, 0).unwrap();
+        for name in ["docs/missing.txt", "docs/same_time.txt", "docs/same_content.txt", "docs/same_size.txt", "docs/other_size.txt"] {
+            zip.start_file(name, FileOptions::default().last_modified_time(archived)).unwrap();
+            zip.write_all(b"archived").unwrap();
+        }
+        zip.finish().unwrap();
+        drop(zip);
+
+        for (name, content) in [("same_time.txt", "archived"), ("same_content.txt", "archived"), ("same_size.txt", "existing"), ("other_size.txt", "other")] {
+            fs::write(out.join("docs").join(name), content).unwrap();
+        }
+        File::options()
+            .write(true)
+            .open(out.join("docs").join("same_time.txt"))
+            .unwrap()
+            .set_modified(entry_modified(archived).unwrap())
+            .unwrap();
+        let before: HashSet<PathBuf> = fs::read_dir(out.join("docs")).unwrap().map(|entry| entry.unwrap().path()).collect();
+
+        let selection = Selection {
+            output_dir: Some(out.clone()),
+            ..Default::default()
+        };
+        let mut chain_state = ChainState {
+            dry_run: true,
+            ..Default::default()
+        };
+        restore_from_backup(archive, &Uuid::nil(), None, &selection, &mut chain_state);
+        let after: HashSet<PathBuf> = fs::read_dir(out.join("docs")).unwrap().map(|entry| entry.unwrap().path()).collect();
+        let same_size = fs::read_to_string(out.join("docs").join("same_size.txt")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let docs = out.join("docs");
+        assert_eq!(
+            chain_state.changes,
+            vec![
+                (docs.join("missing.txt"), Change::Missing),
+                (docs.join("same_time.txt"), Change::Identical),
+                (docs.join("same_content.txt"), Change::Identical),
+                (docs.join("same_size.txt"), Change::Different),
+                (docs.join("other_size.txt"), Change::Different),
+            ]
+        );
+        assert_eq!(chain_state.restored, 0);
+        assert_eq!(before, after);
+        assert_eq!(same_size, "existing");
+
+        let preview = Preview {
+            matched: chain_state.matched,
+            changes: chain_state.changes,
+        };
+        assert_eq!(preview.lines()[0], format!("missing   {:?}", docs.join("missing.txt")));
+        assert_eq!(preview.to_json()["matched"], 5);
+        assert_eq!(preview.to_json()["changes"][1]["change"], "identical");
+    }
+
+    #[test]
+    fn select_subtree() {
+        let selection = Selection {
+            subtrees: vec![PathBuf::from("C:/Users/me/Pictures/2022")],
+            ..Default::default()
+        };
+        assert_eq!(
+            selection.destination("C:/Users/me/Pictures/2022/summer/a.jpg"),
+            Some(PathBuf::from("C:/Users/me/Pictures/2022/summer/a.jpg"))
+        );
+        assert_eq!(selection.destination("C:/Users/me/Pictures/2021/b.jpg"), None);
+        assert_eq!(selection.destination("C:/Users/me/Documents/c.txt"), None);
+
+        let partial_name = Selection {
+            subtrees: vec![PathBuf::from("C:/Users/me/Pic")],
+            ..Default::default()
+        };
+        assert_eq!(partial_name.destination("C:/Users/me/Pictures/a.jpg"), None);
+
+        let nested = Selection {
+            subtrees: vec![PathBuf::from("C:/Users/me"), PathBuf::from("C:/Users/me/Pictures")],
+            strip_prefix: true,
+            output_dir: Some(PathBuf::from("out")),
+            on_conflict: ConflictPolicy::Overwrite,
+        };
+        // the most specific subtree is stripped
+        assert_eq!(nested.destination("C:/Users/me/Pictures/a.jpg"), Some(PathBuf::from("out/a.jpg")));
+        assert_eq!(nested.destination("C:/Users/me/Documents/c.txt"), Some(PathBuf::from("out/Documents/c.txt")));
+
+        assert_eq!(
+            Selection::default().destination("C:/Users/me/Documents/c.txt"),
+            Some(PathBuf::from("C:/Users/me/Documents/c.txt"))
+        );
+    }
+
+    #[test]
+    fn into_output_dir() {
+        let kept = Selection {
+            subtrees: vec![PathBuf::from("C:/Users/me/Pictures")],
+            strip_prefix: false,
+            output_dir: Some(PathBuf::from("out")),
+            on_conflict: ConflictPolicy::Overwrite,
+        };
+        let stripped = Selection {
+            strip_prefix: true,
+            ..kept.clone()
+        };
+
+        let rooted = Selection {
+            subtrees: vec![],
+            ..kept.clone()
+        };
+
+        assert_eq!(
+            rooted.destination("/Users/me/Pictures/2022/a.jpg"),
+            Some(PathBuf::from("out/Users/me/Pictures/2022/a.jpg"))
+        );
+        assert_eq!(kept.destination("C:/Users/me/Documents/c.txt"), None);
+        assert_eq!(
+            stripped.destination("C:/Users/me/Pictures/2022/a.jpg"),
+            Some(PathBuf::from("out/2022/a.jpg"))
+        );
+        assert_eq!(stripped.destination("C:/Users/me/Documents/c.txt"), None);
+        // entries never leave the output dir
+        assert_eq!(
+            rooted.destination("/Users/../../Windows/a.dll"),
+            Some(PathBuf::from("out/Users/Windows/a.dll"))
+        );
+        assert_eq!(
+            stripped.destination("C:/Users/me/Pictures/../../../b.dll"),
+            Some(PathBuf::from("out/b.dll"))
+        );
+        assert!(stripped.to_string().contains("without the subtree prefix"));
+    }
+}
@@ -0,0 +1,116 @@
+//! Contains functions for displaying the backup status of a [ProfileConfig].
+
+use chrono::Duration;
+use config::{backup_report::BackupReport, profile_config::ProfileConfig};
+
+use crate::report::format_report;
+
+/// Formats when the last successful backup of the given [ProfileConfig] was made, where it was stored and when the next one is due.
+/// If the `report` of the last archive is given, its contents are summarized as well.
+/// If the system clock appears to be wrong (see [config::backup_inventory::clock_skew]), the `clock_skew` is shown as a warning.
+pub fn format_status(profile_config: &ProfileConfig, report: Option<&BackupReport>, clock_skew: Option<Duration>) -> String {
+    let last_backup = match (&profile_config.last_backup, &profile_config.last_backup_archive) {
+        (Some(datetime), Some(archive)) => format!("{} ({})", datetime, archive.display()),
+        (Some(datetime), None) => datetime.to_string(),
+        (None, _) => String::from("never"),
+    };
+    let contents = match report {
+        Some(report) => format!("\n    last archive: {}", format_report(report)),
+        None => String::new(),
+    };
+    let warning = match clock_skew {
+        Some(skew) => format!(
+            "\n    warning: the system clock appears to be wrong, it is {} hours behind the last backup",
+            skew.num_hours()
+        ),
+        None => String::new(),
+    };
+    format!(
+        "{:?}:\n    last backup: {}{}\n    next backup: {}{}",
+        profile_config.name, last_backup, contents, profile_config.next_backup, warning
+    )
+}
+
+#[cfg(test)]
+mod status_tests {
+    use std::path::PathBuf;
+
+    use chrono::NaiveDate;
+    use config::interval::IntervalBuilder;
+
+    use super::*;
+
+    fn status_config() -> ProfileConfig {
+        let mut profile_config = ProfileConfig::new(
+            String::from("Hutzi"),
+            PathBuf::from("D:/backups"),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        );
+        profile_config.next_backup = NaiveDate::from_ymd_opt(2023, 5, 2)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        profile_config
+    }
+
+    #[test]
+    fn never_backed_up() {
+        assert_eq!(
+            format_status(&status_config(), None, None),
+            "\"Hutzi\":\n    last backup: never\n    next backup: 2023-05-02 12:00:00"
+        );
+    }
+
+    #[test]
+    fn backed_up() {
+        let mut profile_config = status_config();
+        profile_config.last_backup = NaiveDate::from_ymd_opt(2023, 5, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 5);
+        profile_config.last_backup_archive = Some(PathBuf::from("D:/backups/archive.zip"));
+        assert_eq!(
+            format_status(&profile_config, None, None),
+            "\"Hutzi\":\n    last backup: 2023-05-01 12:00:05 (D:/backups/archive.zip)\n    next backup: 2023-05-02 12:00:00"
+        );
+    }
+
+    #[test]
+    fn clock_skewed() {
+        let mut profile_config = status_config();
+        profile_config.last_backup = NaiveDate::from_ymd_opt(2023, 5, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 5);
+        assert_eq!(
+            format_status(&profile_config, None, Some(Duration::hours(26))),
+            "\"Hutzi\":\n    last backup: 2023-05-01 12:00:05\n    next backup: 2023-05-02 12:00:00\n    warning: the system clock appears to be wrong, it is 26 hours behind the last backup"
+        );
+    }
+
+    #[test]
+    fn with_report() {
+        let mut profile_config = status_config();
+        profile_config.last_backup = NaiveDate::from_ymd_opt(2023, 5, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 5);
+        let report = BackupReport {
+            profile_name: profile_config.name.clone(),
+            profile_uuid: *profile_config.get_uuid(),
+            started: profile_config.last_backup.unwrap(),
+            finished: profile_config.last_backup.unwrap(),
+            file_count: 3,
+            uncompressed_bytes: 300,
+            compressed_bytes: 100,
+            skipped: vec![],
+            archive_files: vec![],
+            cancelled: false,
+        };
+        assert_eq!(
+            format_status(&profile_config, Some(&report), None),
+            "\"Hutzi\":\n    last backup: 2023-05-01 12:00:05\n    last archive: 3 files, 300 bytes (100 compressed), 0 skipped\n    next backup: 2023-05-02 12:00:00"
+        );
+    }
+}
@@ -0,0 +1,282 @@
+//! Contains the outcome of handling a profile and the summary that is shown at the end of a run.
+
+use std::path::PathBuf;
+
+use config::backup_catalog::CatalogEntry;
+use uuid::Uuid;
+
+use crate::localization::error_advice;
+
+/// Number of skipped files listed in the [Outcome] of a profile. The others are only logged.
+const LISTED_SKIPPED_FILES: usize = 5;
+
+/// Outcome of handling a single profile.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Outcome {
+    Success,
+    /// No backup was performed, because none was due, the profile is disabled or nothing changed since the newest archive
+    Skipped,
+    /// Handled, but something needs attention
+    Warning(String),
+    /// The backup couldn't be created
+    Failure(String),
+}
+
+impl Outcome {
+    fn severity(&self) -> u8 {
+        match self {
+            Outcome::Success | Outcome::Skipped => 0,
+            Outcome::Warning(_) => 1,
+            Outcome::Failure(_) => 2,
+        }
+    }
+
+    /// Replaces this outcome with `other` if `other` is more severe.
+    pub fn escalate(&mut self, other: Outcome) {
+        if other.severity() > self.severity() {
+            *self = other;
+        }
+    }
+
+    fn icon(&self) -> &'static str {
+        match self {
+            Outcome::Success => "✔",
+            Outcome::Skipped => "–",
+            Outcome::Warning(_) => "⚠",
+            Outcome::Failure(_) => "✖",
+        }
+    }
+}
+
+/// What a backup archived.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct BackupStats {
+    /// Number of archived files
+    pub files: usize,
+    /// Size of the archived files in bytes
+    pub bytes: u64,
+    /// Files that couldn't be archived
+    pub skipped: Vec<SkippedFile>,
+    /// Archived files, which are added to the catalog of the profile
+    pub catalog: Vec<CatalogEntry>,
+    /// Costs of the backup as a single line (see [Metrics::summary](crate::metrics::Metrics::summary))
+    pub metrics: String,
+}
+
+/// [Outcome] of the profile with the given name.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProfileOutcome {
+    pub name: String,
+    pub uuid: Uuid,
+    pub outcome: Outcome,
+    /// What the backup of the profile archived. Empty if no backup was performed or it failed.
+    pub stats: BackupStats,
+}
+
+/// Formats one line per profile for the end-of-run dialog.
+/// Warnings and failures are followed by advice for the user if the error is known, or by the error itself otherwise.
+pub fn format_summary(outcomes: &[ProfileOutcome]) -> String {
+    outcomes
+        .iter()
+        .map(|profile| {
+            let detail = match &profile.outcome {
+                Outcome::Success | Outcome::Skipped => String::new(),
+                Outcome::Warning(msg) | Outcome::Failure(msg) => {
+                    format!(": {}", error_advice(msg).unwrap_or_else(|| msg.clone()))
+                }
+            };
+            format!("{} {}{}", profile.outcome.icon(), profile.name, detail)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Formats the totals of all profiles for the end-of-run dialog, e.g., `12 Dateien (3000 Bytes) gesichert, 1 Dateien übersprungen`.
+pub fn format_totals(outcomes: &[ProfileOutcome]) -> String {
+    let files: usize = outcomes.iter().map(|profile| profile.stats.files).sum();
+    let bytes: u64 = outcomes.iter().map(|profile| profile.stats.bytes).sum();
+    let skipped: usize = outcomes.iter().map(|profile| profile.stats.skipped.len()).sum();
+    format!("{} Dateien ({} Bytes) gesichert, {} Dateien übersprungen", files, bytes, skipped)
+}
+
+/// File that couldn't be archived.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    /// Why the file couldn't be archived
+    pub reason: String,
+}
+
+/// Formats the given skipped files for the [Outcome] of a profile, e.g., `3 files skipped: C:/a.pst (in use by another process), ...`.
+/// Only the first few files are listed.
+pub fn format_skipped(skipped: &[SkippedFile]) -> String {
+    let mut listed: Vec<String> = skipped
+        .iter()
+        .take(LISTED_SKIPPED_FILES)
+        .map(|file| format!("{} ({})", file.path.to_string_lossy(), file.reason))
+        .collect();
+    if skipped.len() > LISTED_SKIPPED_FILES {
+        listed.push(format!("and {} more", skipped.len() - LISTED_SKIPPED_FILES));
+    }
+    format!("{} files skipped: {}", skipped.len(), listed.join(", "))
+}
+
+/// Checks if handling any of the profiles failed.
+pub fn any_failed(outcomes: &[ProfileOutcome]) -> bool {
+    outcomes
+        .iter()
+        .any(|profile| matches!(profile.outcome, Outcome::Failure(_)))
+}
+
+/// Checks if handling any of the profiles failed or needs attention, e.g., because files were skipped.
+pub fn any_warned(outcomes: &[ProfileOutcome]) -> bool {
+    outcomes
+        .iter()
+        .any(|profile| matches!(profile.outcome, Outcome::Warning(_) | Outcome::Failure(_)))
+}
+
+/// Returns the exit code of a run with the given outcomes: [exitcode::IOERR] if any backup failed, which is mostly because the target dir
+/// couldn't be read or written, and [exitcode::OK] otherwise.
+pub fn exit_code(outcomes: &[ProfileOutcome]) -> exitcode::ExitCode {
+    if any_failed(outcomes) {
+        exitcode::IOERR
+    } else {
+        exitcode::OK
+    }
+}
+
+/// Title and first sentence of the end-of-run dialog, which make clear whether any backup failed or needs attention.
+pub fn completion_heading(outcomes: &[ProfileOutcome]) -> (&'static str, &'static str) {
+    if any_failed(outcomes) {
+        (
+            "Backup Fehlgeschlagen",
+            "Mindestens ein Backup ist fehlgeschlagen. Details stehen im Log.",
+        )
+    } else if any_warned(outcomes) {
+        (
+            "Backup Abgeschlossen",
+            "Das Backup ist abgeschlossen, aber nicht alles wurde gesichert. Die externe Festplatte kann jetzt entfernt werden.",
+        )
+    } else {
+        (
+            "Backup Abgeschlossen",
+            "Das Backup ist abgeschlossen. Die externe Festplatte kann jetzt entfernt werden.",
+        )
+    }
+}
+
+#[cfg(test)]
+mod summary_tests {
+    use super::*;
+
+    fn profile(name: &str, outcome: Outcome) -> ProfileOutcome {
+        ProfileOutcome {
+            name: String::from(name),
+            uuid: Uuid::nil(),
+            outcome,
+            stats: BackupStats::default(),
+        }
+    }
+
+    #[test]
+    fn escalate_keeps_worst() {
+        let mut outcome = Outcome::Success;
+        outcome.escalate(Outcome::Warning(String::from("first")));
+        outcome.escalate(Outcome::Warning(String::from("second")));
+        assert_eq!(outcome, Outcome::Warning(String::from("first")));
+        outcome.escalate(Outcome::Failure(String::from("failed")));
+        outcome.escalate(Outcome::Success);
+        assert_eq!(outcome, Outcome::Failure(String::from("failed")));
+    }
+
+    #[test]
+    fn one_line_per_profile() {
+        let outcomes = vec![
+            profile("Docs", Outcome::Success),
+            profile(
+                "Pics",
+                Outcome::Failure(String::from(
+                    "Couldn't finish archive because of Io(Os { code: 112, kind: StorageFull, message: \"full\" })",
+                )),
+            ),
+            profile("Music", Outcome::Warning(String::from("Quota almost exceeded"))),
+            profile("Videos", Outcome::Skipped),
+        ];
+
+        assert_eq!(
+            format_summary(&outcomes),
+            "✔ Docs\n✖ Pics: Das Zielaufwerk ist voll.\n⚠ Music: Quota almost exceeded\n– Videos"
+        );
+        assert!(any_failed(&outcomes));
+        assert!(!any_failed(&outcomes[..1]));
+    }
+
+    #[test]
+    fn heading_points_out_failures() {
+        let mut outcomes = vec![profile("Docs", Outcome::Warning(String::from("Quota almost exceeded")))];
+        assert_eq!(completion_heading(&outcomes).0, "Backup Abgeschlossen");
+        assert!(completion_heading(&outcomes).1.contains("nicht alles"));
+
+        outcomes.push(profile(
+            "Pics",
+            Outcome::Failure(String::from("Archive \"a.zip\" is corrupted: Couldn't read entry \"b.txt\"")),
+        ));
+        assert_eq!(completion_heading(&outcomes).0, "Backup Fehlgeschlagen");
+    }
+
+    #[test]
+    fn skipped_profiles_need_no_attention() {
+        let outcomes = vec![profile("Docs", Outcome::Skipped), profile("Pics", Outcome::Success)];
+        assert!(!any_warned(&outcomes));
+        assert_eq!(completion_heading(&outcomes).0, "Backup Abgeschlossen");
+    }
+
+    #[test]
+    fn exit_codes() {
+        let mut outcomes = vec![profile("Docs", Outcome::Success), profile("Pics", Outcome::Skipped)];
+        assert_eq!(exit_code(&[]), exitcode::OK);
+        assert_eq!(exit_code(&outcomes), exitcode::OK);
+        outcomes.push(profile("Music", Outcome::Warning(String::from("1 files skipped"))));
+        assert_eq!(exit_code(&outcomes), exitcode::OK);
+        outcomes.push(profile("Videos", Outcome::Failure(String::from("Directory \"E:/\" isn't available"))));
+        assert_eq!(exit_code(&outcomes), exitcode::IOERR);
+    }
+
+    #[test]
+    fn totals() {
+        let mut docs = profile("Docs", Outcome::Success);
+        docs.stats = BackupStats {
+            files: 10,
+            bytes: 2000,
+            skipped: vec![SkippedFile {
+                path: PathBuf::from("C:/a.pst"),
+                reason: String::from("in use by another process"),
+            }],
+            ..Default::default()
+        };
+        let mut pics = profile("Pics", Outcome::Success);
+        pics.stats.files = 2;
+        pics.stats.bytes = 1000;
+        let outcomes = vec![docs, pics, profile("Music", Outcome::Skipped)];
+
+        assert_eq!(format_totals(&outcomes), "12 Dateien (3000 Bytes) gesichert, 1 Dateien übersprungen");
+    }
+
+    #[test]
+    fn skipped_files() {
+        let skipped: Vec<SkippedFile> = (0..7)
+            .map(|i| SkippedFile {
+                path: PathBuf::from(format!("C:/{}.pst", i)),
+                reason: String::from("in use by another process"),
+            })
+            .collect();
+
+        assert_eq!(
+            format_skipped(&skipped[..1]),
+            "1 files skipped: C:/0.pst (in use by another process)"
+        );
+        let all = format_skipped(&skipped);
+        assert!(all.starts_with("7 files skipped: C:/0.pst (in use by another process), C:/1.pst"));
+        assert!(all.ends_with("C:/4.pst (in use by another process), and 2 more"));
+    }
+}
@@ -0,0 +1,140 @@
+//! Contains functions for writing a support bundle for bug reports.
+
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::PathBuf,
+};
+
+use config::{
+    general_config::GeneralConfig,
+    profile_config::ProfileConfig,
+    support_bundle::{PathRedactor, SupportBundle},
+};
+use log::warn;
+use zip::{write::FileOptions, ZipWriter};
+
+/// Assembles a [SupportBundle] from the given configs and log files and writes it as zip archive to `output`.
+/// If `redact` is set, all paths in the configs are redacted. The log files aren't added in that case, since they can't be redacted.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the bundle couldn't be written.
+pub fn write_support_bundle(
+    general_config: &GeneralConfig,
+    profile_configs: &[ProfileConfig],
+    log_files: &[PathBuf],
+    redact: bool,
+    output: &PathBuf,
+) -> Result<(), String> {
+    let redactor = if redact { Some(PathRedactor::new()) } else { None };
+    let mut bundle = SupportBundle::new(redactor);
+    if let Err(err) = bundle.add_general_config(general_config) {
+        return Err(format!("Couldn't add general config because {:?}", err));
+    }
+    for profile_config in profile_configs {
+        if let Err(err) = bundle.add_profile_config(profile_config) {
+            return Err(format!("Couldn't add {:?} because {:?}", profile_config.name, err));
+        }
+    }
+    if !redact {
+        for log_file in log_files {
+            let name = log_file.file_name().unwrap_or_default().to_string_lossy();
+            match fs::read(log_file) {
+                Ok(content) => bundle.add_entry(format!("logs/{}", name), content),
+                Err(err) => warn!("Couldn't add log file {:?} because {:?}", log_file, err),
+            }
+        }
+    }
+
+    let entries = match bundle.into_entries() {
+        Ok(entries) => entries,
+        Err(err) => return Err(format!("Couldn't serialize bundle because {:?}", err)),
+    };
+    let file = match File::create(output) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("Error creating file {:?}: {:?}", output, err)),
+    };
+    let mut zip = ZipWriter::new(file);
+    for (name, content) in entries {
+        if let Err(err) = zip.start_file(name, FileOptions::default()) {
+            return Err(format!("Couldn't start entry because of {:?}", err));
+        }
+        if let Err(err) = zip.write_all(&content) {
+            return Err(format!("Couldn't write entry because of {:?}", err));
+        }
+    }
+    match zip.finish() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Couldn't finish bundle because of {:?}", err)),
+    }
+}
+
+#[cfg(test)]
+mod support_tests {
+    use std::io::Read;
+
+    use config::{general_config::GENERAL_CONFIG_VERSION, interval::IntervalBuilder, support_bundle::{SupportBundleManifest, MANIFEST_NAME}};
+    use zip::ZipArchive;
+
+    use super::*;
+
+    fn bundle_contents(redact: bool) -> (SupportBundleManifest, String) {
+        let dir = PathBuf::from(format!("./test_dir/support_bundle_{}_tmp", redact));
+        fs::create_dir_all(&dir).unwrap();
+        let log_file = dir.join("backupper.log");
+        fs::write(&log_file, "log line").unwrap();
+        let general_config = GeneralConfig {
+            profile_configs: PathBuf::from("C:/configs"),
+            max_history_entries: None,
+            max_sidecar_logs: None,
+            max_sidecar_age_days: None,
+            free_space_warning_days: None,
+            default_target_dir: None,
+            show_dialogs: true,
+            log_level: None,
+            config_version: GENERAL_CONFIG_VERSION,
+        };
+        let profile_config = ProfileConfig::new(
+            String::from("Hutzi"),
+            PathBuf::from("E:/backups"),
+            vec![],
+            vec![PathBuf::from("C:/Users/hutzi/Documents")],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        );
+        let output = dir.join("bundle.zip");
+
+        write_support_bundle(&general_config, &[profile_config.clone()], &[log_file], redact, &output).unwrap();
+        let mut zip = ZipArchive::new(File::open(&output).unwrap()).unwrap();
+        let mut manifest = String::new();
+        zip.by_name(MANIFEST_NAME).unwrap().read_to_string(&mut manifest).unwrap();
+        let mut profile = String::new();
+        zip.by_name(&format!("profiles/{}.json", profile_config.get_uuid().as_hyphenated()))
+            .unwrap()
+            .read_to_string(&mut profile)
+            .unwrap();
+        drop(zip);
+        fs::remove_dir_all(&dir).unwrap();
+
+        (serde_json::from_str(&manifest).unwrap(), profile)
+    }
+
+    #[test]
+    fn plain_bundle() {
+        let (manifest, profile) = bundle_contents(false);
+        assert!(!manifest.redacted);
+        assert_eq!(manifest.entries.len(), 3);
+        assert_eq!(manifest.entries[0], "general_config.json");
+        assert_eq!(manifest.entries[2], "logs/backupper.log");
+        assert!(profile.contains("Documents"));
+    }
+
+    #[test]
+    fn redacted_bundle() {
+        let (manifest, profile) = bundle_contents(true);
+        assert!(manifest.redacted);
+        assert_eq!(manifest.entries.len(), 2);
+        assert!(!profile.contains("Documents"));
+    }
+}
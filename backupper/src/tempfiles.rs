@@ -0,0 +1,164 @@
+//! Contains functions for naming temporary files and cleaning up the ones that were left behind.
+//!
+//! Temporary files are named `<uuid>.<kind>.<random>.tmp`, where `uuid` is the [Uuid] of the profile they belong to.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use log::{info, warn};
+use uuid::Uuid;
+
+/// Temporary files older than this are considered stale at startup (24 hours).
+pub const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Kinds of temporary files.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TempKind {
+    /// Archive that is still being written
+    Partial,
+    /// Rewritten archive that replaces an existing one
+    Staging,
+    /// File that is being extracted by a restore
+    Extract,
+}
+
+impl TempKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TempKind::Partial => "partial",
+            TempKind::Staging => "staging",
+            TempKind::Extract => "extract",
+        }
+    }
+}
+
+/// Returns a path for a new temporary file of the given `kind` in `dir`, belonging to the profile with the given [Uuid].
+pub fn temp_path_for(dir: &Path, kind: TempKind, uuid: &Uuid) -> PathBuf {
+    dir.join(format!(
+        "{}.{}.{}.tmp",
+        uuid.as_hyphenated(),
+        kind.as_str(),
+        Uuid::new_v4().simple()
+    ))
+}
+
+/// Checks if the given file name is the name of a temporary file of the given `kind`.
+fn is_temp_file_name(file_name: &str, kind: TempKind) -> bool {
+    let parts: Vec<&str> = file_name.split('.').collect();
+    match parts[..] {
+        [uuid, file_kind, _, "tmp"] => {
+            file_kind == kind.as_str() && Uuid::parse_str(uuid).is_ok()
+        }
+        _ => false,
+    }
+}
+
+/// Removes the temporary files of the given `kind` in `dir` that were last modified more than `older_than` ago.
+/// Other files are never touched. Doesn't go through `dir` recursively.
+///
+/// # Returns
+/// The paths of the removed files.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if `dir` couldn't be read.
+pub fn cleanup_stale(dir: &Path, kind: TempKind, older_than: Duration) -> Result<Vec<PathBuf>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => return Err(format!("Error reading dir: {:?}", err)),
+    };
+
+    let now = SystemTime::now();
+    let mut removed = vec![];
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let is_temp_file = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map_or(false, |name| is_temp_file_name(name, kind));
+        if !is_temp_file || !path.is_file() {
+            continue;
+        }
+
+        let age = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok());
+        if age.map_or(true, |age| age <= older_than) {
+            continue;
+        }
+
+        match fs::remove_file(&path) {
+            Ok(_) => removed.push(path),
+            Err(err) => warn!("Couldn't remove stale temporary file {:?} because {:?}", path, err),
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Removes the stale temporary archives in the given target dir that were left behind by earlier runs, and logs the result.
+pub fn cleanup_target_dir(target_dir: &Path) {
+    for kind in [TempKind::Partial, TempKind::Staging] {
+        match cleanup_stale(target_dir, kind, STALE_AFTER) {
+            Ok(removed) if removed.is_empty() => (),
+            Ok(removed) => info!("Removed stale temporary files {:?}", removed),
+            Err(msg) => info!("Couldn't clean up {:?}: {}", target_dir, msg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tempfiles_tests {
+    use super::*;
+
+    #[test]
+    fn temp_file_names() {
+        let uuid = Uuid::new_v4();
+        let path = temp_path_for(Path::new("dir"), TempKind::Partial, &uuid);
+        let name = path.file_name().unwrap().to_str().unwrap();
+
+        assert_eq!(path.parent(), Some(Path::new("dir")));
+        assert!(name.starts_with(&uuid.as_hyphenated().to_string()));
+        assert!(is_temp_file_name(name, TempKind::Partial));
+        assert!(!is_temp_file_name(name, TempKind::Staging));
+        assert_ne!(path, temp_path_for(Path::new("dir"), TempKind::Partial, &uuid));
+
+        assert!(!is_temp_file_name("notes.partial.1.tmp", TempKind::Partial));
+        assert!(!is_temp_file_name(
+            &format!("{}_2023-01-01_10-00.zip", uuid.as_hyphenated()),
+            TempKind::Partial
+        ));
+        assert!(!is_temp_file_name(
+            &format!("{}.partial.tmp", uuid.as_hyphenated()),
+            TempKind::Partial
+        ));
+    }
+
+    #[test]
+    fn cleanup_by_kind_and_age() {
+        let dir = PathBuf::from("./test_dir/cleanup_stale_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let uuid = Uuid::new_v4();
+        let partial = temp_path_for(&dir, TempKind::Partial, &uuid);
+        let staging = temp_path_for(&dir, TempKind::Staging, &uuid);
+        let user_file = dir.join("partial.tmp");
+        for path in [&partial, &staging, &user_file] {
+            fs::write(path, "").unwrap();
+        }
+
+        let too_young = cleanup_stale(&dir, TempKind::Partial, Duration::from_secs(60 * 60));
+        std::thread::sleep(Duration::from_millis(20));
+        let old = cleanup_stale(&dir, TempKind::Partial, Duration::from_millis(10));
+        let remaining = (partial.exists(), staging.exists(), user_file.exists());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(too_young.unwrap(), Vec::<PathBuf>::new());
+        assert_eq!(old.unwrap(), vec![partial]);
+        assert_eq!(remaining, (false, true, true));
+        assert!(cleanup_stale(&dir, TempKind::Partial, STALE_AFTER).is_err());
+    }
+}
@@ -0,0 +1,476 @@
+//! Contains the read-only virtual filesystem of a backup archive, which is mounted by a native driver (see [crate::mount]).
+//!
+//! The directory tree mirrors the entry names of the archive, e.g., `C/Users/hutzi/notes.txt`, and files that were archived again
+//! (e.g., because they were in use) only show up once with their latest content. Files are decompressed on demand in blocks of [BLOCK_SIZE] bytes,
+//! of which the most recently used ones are kept in a small [BlockCache], so browsing a large archive doesn't extract it.
+
+// the filesystem is only read through the native drivers, which aren't part of this build yet
+#![cfg_attr(not(test), allow(dead_code))]
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDateTime;
+use config::{archive_manifest::ARCHIVE_MANIFEST_NAME, profile_config::ProfileConfig};
+use zip::{CompressionMethod, ZipArchive};
+
+use crate::{
+    common::{archive_parts, SNAPSHOT_FILE_NAME, UNSTABLE_FILE_NAME},
+    contents::modified_time,
+    encryption::{readable_archive, ReadableArchive},
+};
+
+/// Size of the blocks files are decompressed and cached in
+pub const BLOCK_SIZE: usize = 0x10000;
+
+/// Number of blocks that are cached by default, i.e., 4 MiB
+pub const DEFAULT_CACHE_BLOCKS: usize = 64;
+
+/// Number of blocks that are decompressed after the requested one, so reading a compressed file sequentially doesn't decompress it from the start for each block
+const READ_AHEAD: u64 = 3;
+
+/// Attributes of a file or directory of an [ArchiveFs].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attributes {
+    pub is_dir: bool,
+    /// Uncompressed size in bytes, 0 for directories
+    pub size: u64,
+    /// Modification time of the file when it was archived. [None] for directories and invalid dates.
+    pub modified: Option<NaiveDateTime>,
+}
+
+/// Archive entry a file of an [ArchiveFs] is read from.
+#[derive(Debug, Clone, Copy)]
+struct FileNode {
+    /// Index of the volume containing the entry
+    part: usize,
+    /// Index of the entry within its volume
+    index: usize,
+    size: u64,
+    modified: Option<NaiveDateTime>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Dir(BTreeMap<String, Node>),
+    File(FileNode),
+}
+
+impl Node {
+    fn attributes(&self) -> Attributes {
+        match self {
+            Node::Dir(_) => Attributes {
+                is_dir: true,
+                size: 0,
+                modified: None,
+            },
+            Node::File(file) => Attributes {
+                is_dir: false,
+                size: file.size,
+                modified: file.modified,
+            },
+        }
+    }
+}
+
+/// Key of a cached block: the volume and index of its entry and its number within the entry
+type BlockKey = (usize, usize, u64);
+
+/// Keeps the decompressed blocks that were used most recently.
+#[derive(Debug)]
+pub struct BlockCache {
+    capacity: usize,
+    blocks: HashMap<BlockKey, Vec<u8>>,
+    /// Keys of the cached blocks from the least to the most recently used one
+    order: VecDeque<BlockKey>,
+}
+
+impl BlockCache {
+    /// Creates a cache holding up to `capacity` blocks, but at least one.
+    pub fn new(capacity: usize) -> BlockCache {
+        BlockCache {
+            capacity: capacity.max(1),
+            blocks: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Returns the cached block with the given key and marks it as the most recently used one.
+    fn get(&mut self, key: &BlockKey) -> Option<&[u8]> {
+        let position = self.order.iter().position(|cached| cached == key)?;
+        if let Some(key) = self.order.remove(position) {
+            self.order.push_back(key);
+        }
+        self.blocks.get(key).map(|block| block.as_slice())
+    }
+
+    /// Caches the block with the given key. If the cache is full, the least recently used block is dropped.
+    fn insert(&mut self, key: BlockKey, block: Vec<u8>) {
+        if self.blocks.insert(key, block).is_some() {
+            self.order.retain(|cached| cached != &key);
+        }
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.blocks.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Read-only directory tree of the files stored in a backup archive and its other volumes, if it is split.
+#[derive(Debug)]
+pub struct ArchiveFs {
+    volumes: Vec<ZipArchive<File>>,
+    /// Paths of the readable volumes, for reading uncompressed entries directly
+    paths: Vec<PathBuf>,
+    /// Decrypted copies of encrypted volumes, which are removed once the filesystem is dropped
+    _readable: Vec<ReadableArchive>,
+    root: Node,
+    cache: BlockCache,
+}
+
+impl ArchiveFs {
+    /// Opens the archive of the given [ProfileConfig] at `path`, caching up to `cache_blocks` decompressed blocks.
+    /// Encrypted archives are decrypted with the passphrase of the profile first.
+    ///
+    /// # Errors
+    /// Returns an [Err] describing the issue if the archive couldn't be decrypted or read.
+    pub fn open(profile_config: &ProfileConfig, path: &Path, cache_blocks: usize) -> Result<ArchiveFs, String> {
+        let readable = archive_parts(&path.to_path_buf())
+            .iter()
+            .map(|part| readable_archive(profile_config, part))
+            .collect::<Result<Vec<_>, _>>()?;
+        if readable.is_empty() {
+            return Err(format!("There is no archive at {:?}", path));
+        }
+        let paths = readable.iter().map(|part| part.path().to_path_buf()).collect();
+        let mut fs = ArchiveFs::from_volumes(paths, cache_blocks)?;
+        fs._readable = readable;
+        Ok(fs)
+    }
+
+    /// Builds the directory tree of the zip archives at `paths`, which are the volumes of one archive in order.
+    /// Entries of later volumes replace those of earlier ones with the same name.
+    ///
+    /// # Errors
+    /// Returns an [Err] describing the issue if one of the volumes couldn't be read.
+    fn from_volumes(paths: Vec<PathBuf>, cache_blocks: usize) -> Result<ArchiveFs, String> {
+        let mut root = Node::Dir(BTreeMap::new());
+        let mut volumes = vec![];
+        for (part, path) in paths.iter().enumerate() {
+            let file = File::open(path).map_err(|e| format!("Couldn't open {:?} because of {:?}", path, e))?;
+            let mut zip = ZipArchive::new(file).map_err(|e| format!("Couldn't read {:?} because of {:?}", path, e))?;
+            for index in 0..zip.len() {
+                let entry = zip
+                    .by_index_raw(index)
+                    .map_err(|e| format!("Couldn't read entry {} of {:?} because of {:?}", index, path, e))?;
+                if [SNAPSHOT_FILE_NAME, UNSTABLE_FILE_NAME, ARCHIVE_MANIFEST_NAME].contains(&entry.name()) {
+                    continue;
+                }
+                let node = if entry.is_dir() {
+                    None
+                } else {
+                    Some(FileNode {
+                        part,
+                        index,
+                        size: entry.size(),
+                        modified: modified_time(entry.last_modified()),
+                    })
+                };
+                insert(&mut root, entry.name(), node);
+            }
+            volumes.push(zip);
+        }
+        Ok(ArchiveFs {
+            volumes,
+            paths,
+            _readable: vec![],
+            root,
+            cache: BlockCache::new(cache_blocks),
+        })
+    }
+
+    /// Finds the node at the given `path`, e.g., `C/Users/hutzi`. Both `/` and `\` separate the components and the root is `""` or `/`.
+    fn node(&self, path: &str) -> Option<&Node> {
+        components(path).try_fold(&self.root, |node, name| match node {
+            Node::Dir(children) => children.get(name),
+            Node::File(_) => None,
+        })
+    }
+
+    /// Returns the [Attributes] of the file or directory at `path`, or [None] if there is none.
+    pub fn attributes(&self, path: &str) -> Option<Attributes> {
+        self.node(path).map(Node::attributes)
+    }
+
+    /// Lists the names and [Attributes] of the children of the directory at `path` in alphabetical order.
+    /// Returns [None] if there is no directory at `path`.
+    pub fn read_dir(&self, path: &str) -> Option<Vec<(String, Attributes)>> {
+        match self.node(path)? {
+            Node::Dir(children) => Some(
+                children
+                    .iter()
+                    .map(|(name, child)| (name.clone(), child.attributes()))
+                    .collect(),
+            ),
+            Node::File(_) => None,
+        }
+    }
+
+    /// Counts the files in the tree.
+    pub fn file_count(&self) -> usize {
+        fn count(node: &Node) -> usize {
+            match node {
+                Node::Dir(children) => children.values().map(count).sum(),
+                Node::File(_) => 1,
+            }
+        }
+        count(&self.root)
+    }
+
+    /// Reads up to `len` bytes of the file at `path`, starting at `offset`. Reading beyond the end of the file returns fewer bytes.
+    ///
+    /// # Errors
+    /// Returns an [Err] describing the issue if there is no file at `path` or it couldn't be decompressed.
+    pub fn read(&mut self, path: &str, offset: u64, len: usize) -> Result<Vec<u8>, String> {
+        let file = match self.node(path) {
+            Some(Node::File(file)) => *file,
+            Some(Node::Dir(_)) => return Err(format!("{:?} is a directory", path)),
+            None => return Err(format!("There is no file {:?}", path)),
+        };
+        let end = offset.saturating_add(len as u64).min(file.size);
+        let mut data = Vec::with_capacity(end.saturating_sub(offset) as usize);
+        let mut position = offset;
+        while position < end {
+            let block = position / BLOCK_SIZE as u64;
+            let key = (file.part, file.index, block);
+            if self.cache.get(&key).is_none() {
+                self.load_blocks(&file, block)?;
+            }
+            let cached = self
+                .cache
+                .get(&key)
+                .ok_or_else(|| format!("Couldn't read block {} of {:?}", block, path))?;
+            let start = (position - block * BLOCK_SIZE as u64) as usize;
+            let stop = ((end - block * BLOCK_SIZE as u64) as usize).min(cached.len());
+            if start >= stop {
+                return Err(format!("{:?} is shorter than its archived size", path));
+            }
+            data.extend_from_slice(&cached[start..stop]);
+            position += (stop - start) as u64;
+        }
+        Ok(data)
+    }
+
+    /// Decompresses the given `block` of `file` into the cache, followed by up to [READ_AHEAD] more blocks.
+    /// Uncompressed entries are read directly at the block, compressed ones are decompressed from their start.
+    fn load_blocks(&mut self, file: &FileNode, block: u64) -> Result<(), String> {
+        let volume = &self.paths[file.part];
+        let mut entry = self.volumes[file.part]
+            .by_index(file.index)
+            .map_err(|e| format!("Couldn't read entry {} of {:?} because of {:?}", file.index, volume, e))?;
+        let skip = block * BLOCK_SIZE as u64;
+        let read_error = |e: io::Error| format!("Couldn't decompress entry {} of {:?} because of {:?}", file.index, volume, e);
+        let mut direct;
+        let reader: &mut dyn Read = if entry.compression() == CompressionMethod::Stored {
+            direct = File::open(volume).map_err(read_error)?;
+            direct.seek(SeekFrom::Start(entry.data_start() + skip)).map_err(read_error)?;
+            &mut direct
+        } else {
+            io::copy(&mut Read::by_ref(&mut entry).take(skip), &mut io::sink()).map_err(read_error)?;
+            &mut entry
+        };
+        let mut blocks = vec![];
+        for number in block..=block + READ_AHEAD {
+            let remaining = file.size.saturating_sub(number * BLOCK_SIZE as u64);
+            if remaining == 0 {
+                break;
+            }
+            let mut data = vec![0u8; remaining.min(BLOCK_SIZE as u64) as usize];
+            reader.read_exact(&mut data).map_err(read_error)?;
+            blocks.push((number, data));
+        }
+        // the requested block is cached last, so a small cache doesn't drop it for the blocks read ahead
+        for (number, data) in blocks.into_iter().rev() {
+            self.cache.insert((file.part, file.index, number), data);
+        }
+        Ok(())
+    }
+}
+
+/// Splits `path` into its components, ignoring empty ones.
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['/', '\\']).filter(|component| !component.is_empty())
+}
+
+/// Inserts the entry with the given `name` into the tree below `root`, creating the missing directories.
+/// `node` is [None] for directory entries. Names conflicting with an existing file or directory replace it.
+fn insert(root: &mut Node, name: &str, node: Option<FileNode>) {
+    let mut names: Vec<&str> = components(name).collect();
+    let last = match names.pop() {
+        Some(last) => last,
+        None => return,
+    };
+    let mut current = root;
+    for name in names {
+        let children = match current {
+            Node::Dir(children) => children,
+            Node::File(_) => unreachable!("files are replaced by directories below"),
+        };
+        let child = children.entry(String::from(name)).or_insert_with(|| Node::Dir(BTreeMap::new()));
+        if let Node::File(_) = child {
+            *child = Node::Dir(BTreeMap::new());
+        }
+        current = child;
+    }
+    if let Node::Dir(children) = current {
+        match node {
+            Some(file) => {
+                children.insert(String::from(last), Node::File(file));
+            }
+            None => {
+                let child = children.entry(String::from(last)).or_insert_with(|| Node::Dir(BTreeMap::new()));
+                if let Node::File(_) = child {
+                    *child = Node::Dir(BTreeMap::new());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod vfs_tests {
+    use std::{fs, io::Write};
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    /// Writes a zip archive with the given entries at `path`. Entries ending with `/` are directories.
+    fn write_archive(path: &Path, entries: &[(&str, &[u8], CompressionMethod)]) {
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        for (name, content, compression) in entries {
+            let options = FileOptions::default().compression_method(*compression);
+            if name.ends_with('/') {
+                zip.add_directory(*name, options).unwrap();
+            } else {
+                zip.start_file(*name, options).unwrap();
+                zip.write_all(content).unwrap();
+            }
+        }
+        zip.finish().unwrap();
+    }
+
+    /// Content that isn't the same in each block, so reading the wrong block is noticed
+    fn content(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i / 7 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn lru_cache() {
+        let mut cache = BlockCache::new(2);
+        cache.insert((0, 0, 0), vec![0]);
+        cache.insert((0, 0, 1), vec![1]);
+        assert_eq!(cache.get(&(0, 0, 0)), Some([0u8].as_slice()));
+        // the second block was used least recently
+        cache.insert((0, 0, 2), vec![2]);
+        assert_eq!(cache.get(&(0, 0, 1)), None);
+        assert_eq!(cache.get(&(0, 0, 0)), Some([0u8].as_slice()));
+        assert_eq!(cache.get(&(0, 0, 2)), Some([2u8].as_slice()));
+        assert_eq!(BlockCache::new(0).capacity, 1);
+    }
+
+    #[test]
+    fn tree() {
+        let dir = PathBuf::from("./test_dir/vfs_tree_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.zip");
+        write_archive(
+            &archive,
+            &[
+                ("C/Users/hutzi/a.txt", b"first", CompressionMethod::Deflated),
+                ("C/Users/hutzi/empty/", b"", CompressionMethod::Stored),
+                ("C/Users/hutzi/a.txt", b"retried", CompressionMethod::Deflated),
+                ("root/etc/hosts", b"127.0.0.1", CompressionMethod::Stored),
+                (SNAPSHOT_FILE_NAME, b"C/Users/hutzi/a.txt", CompressionMethod::Deflated),
+            ],
+        );
+        let fs = ArchiveFs::from_volumes(vec![archive], 4);
+        fs::remove_dir_all(&dir).unwrap();
+        let fs = fs.unwrap();
+
+        let names = |path: &str| -> Option<Vec<String>> {
+            fs.read_dir(path).map(|children| children.into_iter().map(|(name, _)| name).collect())
+        };
+        assert_eq!(names(""), Some(vec![String::from("C"), String::from("root")]));
+        assert_eq!(names("/C/Users/hutzi"), Some(vec![String::from("a.txt"), String::from("empty")]));
+        assert_eq!(names(r"\C\Users\hutzi\empty"), Some(vec![]));
+        assert_eq!(names("C/Users/hutzi/a.txt"), None);
+        assert_eq!(names("C/missing"), None);
+        assert_eq!(fs.file_count(), 2);
+
+        let attributes = fs.attributes("C/Users/hutzi/a.txt").unwrap();
+        assert!(!attributes.is_dir);
+        assert_eq!(attributes.size, 7);
+        assert!(fs.attributes("root/etc").unwrap().is_dir);
+        assert_eq!(fs.attributes("root/etc/hosts/x"), None);
+    }
+
+    #[test]
+    fn read_blocks() {
+        let dir = PathBuf::from("./test_dir/vfs_read_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.zip");
+        let large = content(BLOCK_SIZE * 5 + 123);
+        write_archive(
+            &archive,
+            &[
+                ("deflated.bin", &large, CompressionMethod::Deflated),
+                ("stored.bin", &large, CompressionMethod::Stored),
+                ("small.txt", b"hello", CompressionMethod::Deflated),
+                ("docs/", b"", CompressionMethod::Stored),
+            ],
+        );
+        // smaller than the read-ahead, so blocks are evicted while reading
+        let mut fs = ArchiveFs::from_volumes(vec![archive], 2).unwrap();
+
+        for name in ["deflated.bin", "stored.bin"] {
+            let whole = fs.read(name, 0, large.len() + 10).unwrap();
+            assert_eq!(whole, large, "{}", name);
+            // across a block boundary and backwards
+            let offset = BLOCK_SIZE * 3 - 5;
+            assert_eq!(fs.read(name, offset as u64, 10).unwrap(), large[offset..offset + 10].to_vec());
+            assert_eq!(fs.read(name, 3, 4).unwrap(), large[3..7].to_vec());
+            assert_eq!(fs.read(name, large.len() as u64, 10).unwrap(), Vec::<u8>::new());
+        }
+        assert_eq!(fs.read("small.txt", 1, 3).unwrap(), b"ell".to_vec());
+        assert!(fs.read("docs", 0, 1).is_err());
+        assert!(fs.read("missing.txt", 0, 1).is_err());
+        drop(fs);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn later_volumes_win() {
+        let dir = PathBuf::from("./test_dir/vfs_volumes_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let first = dir.join("archive.part01.zip");
+        let second = dir.join("archive.part02.zip");
+        write_archive(&first, &[("a.txt", b"first", CompressionMethod::Deflated), ("b.txt", b"b", CompressionMethod::Deflated)]);
+        write_archive(&second, &[("a.txt", b"retried", CompressionMethod::Deflated)]);
+
+        let mut fs = ArchiveFs::from_volumes(vec![first, second], 4).unwrap();
+        let a = fs.read("a.txt", 0, 100);
+        let b = fs.read("b.txt", 0, 100);
+        drop(fs);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(a, Ok(b"retried".to_vec()));
+        assert_eq!(b, Ok(b"b".to_vec()));
+    }
+}
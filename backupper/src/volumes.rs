@@ -0,0 +1,223 @@
+//! Contains the writer of backup archives, which splits them into several volumes if they would exceed the `max_archive_size` of a [ProfileConfig](config::profile_config::ProfileConfig).
+//!
+//! Each volume is a complete zip archive on its own, containing the [ARCHIVE_MANIFEST_NAME](config::archive_manifest::ARCHIVE_MANIFEST_NAME) entry for its entries.
+//! The lists of the archive, like the snapshot, are only added to the last volume.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    path::{Path, PathBuf},
+};
+
+use config::archive_manifest::ArchiveManifest;
+use log::{debug, warn};
+use uuid::Uuid;
+use zip::ZipWriter;
+
+use crate::{
+    backup::write_manifest,
+    tempfiles::{temp_path_for, TempKind},
+};
+
+/// Bytes reserved for the headers of an entry and for data the compressor may still hold back from the previous entry
+const ENTRY_OVERHEAD: u64 = 0x4_0000;
+
+/// Bytes an entry takes in the central directory at the end of a volume, besides its name
+const CENTRAL_HEADER_SIZE: u64 = 46 + 28;
+
+/// Bytes reserved in each volume for the manifest and the end of the central directory
+const VOLUME_OVERHEAD: u64 = 0x1_0000;
+
+/// Writes an archive into temporary files, one per volume.
+pub struct Volumes {
+    /// Writer of the current volume
+    pub zip: ZipWriter<File>,
+    /// Handle of the current volume, used to determine how much was written to it
+    file: File,
+    /// Temporary paths of the volumes, the last one is the current volume
+    paths: Vec<PathBuf>,
+    /// Number of entries in the current volume
+    entries: usize,
+    /// Bytes needed for the central directory of the current volume
+    central_directory: u64,
+    /// Maximum size of a volume in bytes. [None] means that the archive isn't split.
+    max_size: Option<u64>,
+    dir: PathBuf,
+    uuid: Uuid,
+}
+
+impl Volumes {
+    /// Creates the first volume in `dir` for the profile with the given [Uuid].
+    ///
+    /// # Errors
+    /// Returns an [Err] describing the issue if the volume couldn't be created.
+    pub fn create(dir: &Path, uuid: &Uuid, max_size: Option<u64>) -> Result<Volumes, String> {
+        let (path, file) = create_volume(dir, uuid)?;
+        let handle = match file.try_clone() {
+            Ok(handle) => handle,
+            Err(err) => return Err(format!("Couldn't open {:?} because of {:?}", path, err)),
+        };
+        Ok(Volumes {
+            zip: ZipWriter::new(file),
+            file: handle,
+            paths: vec![path],
+            entries: 0,
+            central_directory: 0,
+            max_size,
+            dir: dir.to_path_buf(),
+            uuid: *uuid,
+        })
+    }
+
+    /// Temporary paths of the volumes written so far
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Makes sure that the entry for the file at `path` with `size` bytes fits into the current volume.
+    /// If it doesn't, the current volume is finished with the given `manifest` and a new one is started.
+    ///
+    /// # Errors
+    /// Returns an [Err] describing the issue if the entry is too large for any volume, or the volumes couldn't be written.
+    pub fn make_room(&mut self, path: &Path, size: u64, manifest: &ArchiveManifest) -> Result<(), String> {
+        let max_size = match self.max_size {
+            Some(max_size) => max_size,
+            None => return Ok(()),
+        };
+        // entry names are a bit longer than the paths at most, because of the root label
+        let name_len = path.as_os_str().len() as u64 + 16;
+        let needed = entry_bound(size, name_len) + CENTRAL_HEADER_SIZE + name_len;
+        if needed + VOLUME_OVERHEAD > max_size {
+            return Err(format!(
+                "{:?} with {} bytes doesn't fit into a volume of at most {} bytes (see max_archive_size)",
+                path, size, max_size
+            ));
+        }
+
+        let written = self.file.metadata().map_or(0, |metadata| metadata.len());
+        if self.entries > 0 && written + self.central_directory + needed + VOLUME_OVERHEAD > max_size {
+            self.next_volume(manifest)?;
+        }
+        self.entries += 1;
+        self.central_directory += CENTRAL_HEADER_SIZE + name_len;
+        Ok(())
+    }
+
+    /// Finishes the current volume with the given `manifest` and starts the next one.
+    fn next_volume(&mut self, manifest: &ArchiveManifest) -> Result<(), String> {
+        write_manifest(&mut self.zip, manifest)?;
+        self.finish()?;
+        let (path, file) = create_volume(&self.dir, &self.uuid)?;
+        self.file = match file.try_clone() {
+            Ok(handle) => handle,
+            Err(err) => return Err(format!("Couldn't open {:?} because of {:?}", path, err)),
+        };
+        debug!("Starting volume {} in {:?}", self.paths.len() + 1, path);
+        self.zip = ZipWriter::new(file);
+        self.paths.push(path);
+        self.entries = 0;
+        self.central_directory = 0;
+        Ok(())
+    }
+
+    /// Finishes the current volume.
+    ///
+    /// # Errors
+    /// Returns an [Err] describing the issue if the central directory couldn't be written.
+    pub fn finish(&mut self) -> Result<(), String> {
+        match self.zip.finish() {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!(
+                "Couldn't finish archive {:?} because of {:?}",
+                self.paths.last(),
+                err
+            )),
+        }
+    }
+
+    /// Removes all volumes. You call this after an unrecoverable error occured, to clean up.
+    pub fn remove(mut self) {
+        let _ = self.zip.finish();
+        drop(self.zip);
+        for path in &self.paths {
+            if let Err(err) = fs::remove_file(path) {
+                warn!("Couldn't remove {:?} because of {:?}", path, err);
+            }
+        }
+    }
+}
+
+/// Creates the temporary file of a new volume in `dir`.
+fn create_volume(dir: &Path, uuid: &Uuid) -> Result<(PathBuf, File), String> {
+    let path = temp_path_for(dir, TempKind::Partial, uuid);
+    match OpenOptions::new().read(true).write(true).create_new(true).open(&path) {
+        Ok(file) => Ok((path, file)),
+        Err(err) => Err(format!("Error creating file {:?}: {:?}", path, err)),
+    }
+}
+
+/// Bytes an entry of a file with `size` bytes takes in a volume at most, besides its share of the central directory.
+/// Data that doesn't compress well may grow a bit.
+fn entry_bound(size: u64, name_len: u64) -> u64 {
+    size + size / 100 + name_len + ENTRY_OVERHEAD
+}
+
+#[cfg(test)]
+mod volumes_tests {
+    use std::io::Write;
+
+    use zip::write::FileOptions;
+
+    use super::*;
+
+    fn write_entry(volumes: &mut Volumes, manifest: &mut ArchiveManifest, path: &Path, size: usize) -> Result<(), String> {
+        volumes.make_room(path, size as u64, manifest)?;
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        volumes.zip.start_file(manifest.entry_name(path), options).unwrap();
+        volumes.zip.write_all(&vec![7u8; size]).unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn splits_into_volumes() {
+        let dir = PathBuf::from("./test_dir/volumes_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let mut manifest = ArchiveManifest::default();
+        let mut volumes = Volumes::create(&dir, &Uuid::new_v4(), Some(1 << 20)).unwrap();
+        for i in 0..5 {
+            write_entry(&mut volumes, &mut manifest, &PathBuf::from(format!("C:/{}.bin", i)), 300_000).unwrap();
+        }
+        let too_large = write_entry(&mut volumes, &mut manifest, Path::new("C:/large.bin"), 1 << 20);
+        volumes.finish().unwrap();
+        let paths = volumes.paths().to_vec();
+        let sizes: Vec<u64> = paths.iter().map(|path| fs::metadata(path).unwrap().len()).collect();
+        let entries: Vec<usize> = paths
+            .iter()
+            .map(|path| zip::ZipArchive::new(File::open(path).unwrap()).unwrap().len())
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(too_large.unwrap_err().contains("large.bin"));
+        assert_eq!(paths.len(), 3);
+        assert!(sizes.iter().all(|size| *size <= 1 << 20));
+        // the finished volumes also contain the manifest
+        assert_eq!(entries, vec![3, 3, 1]);
+    }
+
+    #[test]
+    fn unlimited() {
+        let dir = PathBuf::from("./test_dir/volumes_unlimited_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let mut manifest = ArchiveManifest::default();
+        let mut volumes = Volumes::create(&dir, &Uuid::new_v4(), None).unwrap();
+        for i in 0..5 {
+            write_entry(&mut volumes, &mut manifest, &PathBuf::from(format!("C:/{}.bin", i)), 300_000).unwrap();
+        }
+        let paths = volumes.paths().to_vec();
+        volumes.remove();
+        let remaining = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(paths.len(), 1);
+        assert_eq!(remaining, 0);
+    }
+}
@@ -0,0 +1,32 @@
+//! Lists all profiles in a directory and prints their next three scheduled runs.
+//!
+//! Usage: `cargo run --example embed -- <profile-configs-dir>`
+
+use std::path::PathBuf;
+
+use chrono::offset;
+use config::ProfileConfig;
+
+fn main() {
+    let dir = PathBuf::from(std::env::args().nth(1).unwrap_or(String::from("test_tmp")));
+
+    let profile_configs = match ProfileConfig::load_dir(&dir) {
+        Ok(configs) => configs,
+        Err(e) => {
+            eprintln!("Couldn't read profile configs from {:?}: {}", dir, e);
+            std::process::exit(1);
+        }
+    };
+
+    let now = offset::Local::now().naive_local();
+    for config in profile_configs {
+        println!("{} ({})", config.name, config.get_uuid());
+        let upcoming = config.interval.upcoming(now, 3);
+        if upcoming.is_empty() {
+            println!("    not scheduled within the next year");
+        }
+        for datetime in upcoming {
+            println!("    {}", datetime);
+        }
+    }
+}
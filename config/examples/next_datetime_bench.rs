@@ -0,0 +1,55 @@
+//! Compares [Interval::next_datetime] with a naive search that checks every single day.
+//!
+//! Usage: `cargo run --release --example next_datetime_bench`
+
+use std::time::{Duration, Instant};
+
+use chrono::{Days, NaiveDate, NaiveDateTime};
+use config::interval::{Interval, IntervalBuilder, SpecifierKind};
+
+const ITERATIONS: u64 = 2000;
+
+/// The search [Interval::next_datetime] used to do
+fn day_by_day(interval: &Interval, datetime: NaiveDateTime) -> Option<NaiveDate> {
+    let mut curr_date = datetime.date();
+    for _ in 0..365 {
+        curr_date = curr_date.checked_add_days(Days::new(1))?;
+        if interval.matches_date(curr_date) {
+            return Some(curr_date);
+        }
+    }
+    None
+}
+
+fn measure<F: FnMut(NaiveDateTime)>(start: NaiveDateTime, mut f: F) -> Duration {
+    let now = Instant::now();
+    for day in 0..ITERATIONS {
+        f(start.checked_add_days(Days::new(day)).unwrap());
+    }
+    now.elapsed()
+}
+
+fn main() {
+    let feb_29 = IntervalBuilder::default()
+        .minutes(SpecifierKind::First)
+        .hours(SpecifierKind::First)
+        .monthdays(SpecifierKind::Nth(28))
+        .months(SpecifierKind::Nth(1))
+        .build()
+        .unwrap();
+    let start = NaiveDate::from_ymd_opt(2019, 1, 1)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap();
+
+    let old = measure(start, |datetime| {
+        std::hint::black_box(day_by_day(&feb_29, datetime));
+    });
+    let new = measure(start, |datetime| {
+        std::hint::black_box(feb_29.next_datetime(datetime));
+    });
+
+    println!("Feb 29 only, {} start dates", ITERATIONS);
+    println!("    day by day:    {:?}", old);
+    println!("    next_datetime: {:?}", new);
+}
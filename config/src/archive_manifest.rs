@@ -0,0 +1,342 @@
+//! Contains the manifest of a backup archive, which maps the relative entry names back to the original paths of the files.
+//!
+//! Entries are named `<root label>/<path below the root>` with `/` as separator, e.g., `C/Users/hutzi/notes.txt` for `C:\Users\hutzi\notes.txt`.
+//! This way, archives can be extracted with standard tools on any machine. The manifest records which root each label stands for.
+//!
+//! Entry names have to be unicode. Paths that aren't (e.g., non-UTF-8 names on Linux or names with unpaired surrogates on Windows) get a lossy entry name
+//! with a hash of their true name appended, and the manifest records their true names (see [RawName]).
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Name of the manifest entry in a backup archive
+pub const ARCHIVE_MANIFEST_NAME: &str = ".backup-rs/manifest.json";
+
+/// Label of the entries whose original paths are relative
+const RELATIVE_LABEL: &str = "relative";
+
+/// Separates the lossy entry name of a path that isn't unicode from the hash of its true name
+const RAW_HASH_SEPARATOR: char = '~';
+
+/// True name of a path below its root that isn't unicode, in the encoding of the platform it was archived on. Components are separated by `/`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RawName {
+    /// Bytes of the name on Unix-like systems
+    Unix(Vec<u8>),
+    /// UTF-16 code units of the name on Windows, possibly including unpaired surrogates
+    Windows(Vec<u16>),
+}
+
+impl RawName {
+    /// Encodes the components of the given `path` below its root. [None] if this platform has no lossless encoding.
+    #[allow(unreachable_code)]
+    fn of(path: &Path) -> Option<RawName> {
+        let components = path.components().filter_map(|component| match component {
+            Component::Normal(name) => Some(name),
+            Component::ParentDir => Some("..".as_ref()),
+            _ => None,
+        });
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let parts: Vec<&[u8]> = components.map(|name| name.as_bytes()).collect();
+            return Some(RawName::Unix(parts.join(&b'/')));
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::ffi::OsStrExt;
+            let parts: Vec<Vec<u16>> = components.map(|name| name.encode_wide().collect()).collect();
+            return Some(RawName::Windows(parts.join(&(b'/' as u16))));
+        }
+        None
+    }
+
+    /// Decodes the name. [None] if it was archived on another kind of platform.
+    #[allow(unreachable_code)]
+    fn to_os_string(&self) -> Option<OsString> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            return match self {
+                RawName::Unix(bytes) => Some(std::ffi::OsStr::from_bytes(bytes).to_os_string()),
+                RawName::Windows(_) => None,
+            };
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::ffi::OsStringExt;
+            return match self {
+                RawName::Windows(wide) => Some(OsString::from_wide(wide)),
+                RawName::Unix(_) => None,
+            };
+        }
+        None
+    }
+
+    /// Stable 32 bit FNV-1a hash of the name, so the same path always gets the same entry name.
+    fn hash(&self) -> u32 {
+        let bytes: Vec<u8> = match self {
+            RawName::Unix(bytes) => bytes.clone(),
+            RawName::Windows(wide) => wide.iter().flat_map(|unit| unit.to_le_bytes()).collect(),
+        };
+        bytes
+            .iter()
+            .fold(0x811c_9dc5, |hash: u32, byte| (hash ^ *byte as u32).wrapping_mul(0x0100_0193))
+    }
+}
+
+/// Maps the top-level dirs of a backup archive to the roots (e.g., `C:/` or `/`) the archived files originally were below.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// Original root prefix of the entries per label. Roots use `/` as separator.
+    pub roots: BTreeMap<String, String>,
+    /// True names of the entries whose paths aren't unicode, by entry name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub raw_names: BTreeMap<String, RawName>,
+    /// Whether the backup was stopped early, so the archive only contains some of the files
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+}
+
+impl ArchiveManifest {
+    /// Computes the name of the archive entry for the file at the given `path` and records its root if necessary.
+    /// Paths that aren't unicode get the hash of their true name appended (e.g., `root/home/a\u{FFFD}.txt~1c2d3e4f`), which is recorded as well.
+    ///
+    /// # Example
+    /// ```
+    /// use config::archive_manifest::ArchiveManifest;
+    /// use std::path::Path;
+    ///
+    /// let mut manifest = ArchiveManifest::default();
+    /// let name = manifest.entry_name(Path::new(r"C:\Users\hutzi\notes.txt"));
+    ///
+    /// assert_eq!(name, "C/Users/hutzi/notes.txt");
+    /// assert_eq!(manifest.original_path(&name), Some(String::from("C:/Users/hutzi/notes.txt")));
+    /// ```
+    pub fn entry_name(&mut self, path: &Path) -> String {
+        let normalized = path.to_string_lossy().replace('\\', "/");
+        let (root, rest) = split_root(&normalized);
+        let label = self.label_for(root);
+        let rest: Vec<&str> = rest
+            .split('/')
+            .filter(|component| !component.is_empty() && *component != ".")
+            .collect();
+        let name = format!("{}/{}", label, rest.join("/"));
+        if path.to_str().is_some() {
+            return name;
+        }
+        let raw = match RawName::of(path) {
+            Some(raw) => raw,
+            None => return name,
+        };
+        let name = format!("{}{}{:08x}", name, RAW_HASH_SEPARATOR, raw.hash());
+        self.raw_names.insert(name.clone(), raw);
+        name
+    }
+
+    /// Reconstructs the true original path of the entry with the given name, even if it isn't unicode.
+    /// Falls back to [ArchiveManifest::original_path] if the entry was archived on another kind of platform.
+    ///
+    /// # Returns
+    /// [None] if the entry doesn't start with a label of this manifest.
+    pub fn original_os_path(&self, entry_name: &str) -> Option<PathBuf> {
+        let raw = match self.raw_names.get(entry_name).and_then(RawName::to_os_string) {
+            Some(raw) => raw,
+            None => return self.original_path(entry_name).map(PathBuf::from),
+        };
+        let (label, _) = entry_name.split_once('/')?;
+        let root = self.roots.get(label)?;
+        Some(Path::new(root).join(raw))
+    }
+
+    /// Reconstructs the original path of the entry with the given name.
+    ///
+    /// # Returns
+    /// [None] if the entry doesn't start with a label of this manifest.
+    pub fn original_path(&self, entry_name: &str) -> Option<String> {
+        let (label, rest) = entry_name.split_once('/')?;
+        let root = self.roots.get(label)?;
+        Some(format!("{}{}", root, rest))
+    }
+
+    /// Returns the label of the given `root`, adding a new one if it isn't known yet.
+    /// Labels consist of the alphanumeric parts of the root, e.g., `C` for `C:/`, and get a number appended if they are taken.
+    fn label_for(&mut self, root: &str) -> String {
+        if let Some((label, _)) = self.roots.iter().find(|(_, known)| known.as_str() == root) {
+            return label.clone();
+        }
+
+        let base = if root.is_empty() {
+            String::from(RELATIVE_LABEL)
+        } else {
+            let parts: Vec<&str> = root
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .filter(|part| !part.is_empty())
+                .collect();
+            if parts.is_empty() {
+                String::from("root")
+            } else {
+                parts.join("_")
+            }
+        };
+        let mut label = base.clone();
+        let mut n = 2;
+        while self.roots.contains_key(&label) {
+            label = format!("{}_{}", base, n);
+            n += 1;
+        }
+        self.roots.insert(label.clone(), String::from(root));
+        label
+    }
+}
+
+/// Splits the given path with `/` as separator into its root and the rest.
+/// Recognizes drives (`C:/` or `C:`), UNC shares (`//server/share/`, including `//?/C:/`) and `/`. Relative paths have an empty root.
+fn split_root(path: &str) -> (&str, &str) {
+    let bytes = path.as_bytes();
+    let end = if let Some(unc) = path.strip_prefix("//") {
+        // server and share (or `?` and the drive) belong to the root
+        let mut end = 2;
+        for component in unc.splitn(3, '/').take(2) {
+            end = (end + component.len() + 1).min(path.len());
+        }
+        end
+    } else if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        if bytes.get(2) == Some(&b'/') {
+            3
+        } else {
+            2
+        }
+    } else if path.starts_with('/') {
+        1
+    } else {
+        0
+    };
+    path.split_at(end)
+}
+
+#[cfg(test)]
+mod archive_manifest_tests {
+    use super::*;
+
+    #[test]
+    fn roots() {
+        assert_eq!(split_root("C:/Users/a.txt"), ("C:/", "Users/a.txt"));
+        assert_eq!(split_root("C:a.txt"), ("C:", "a.txt"));
+        assert_eq!(split_root("/home/a.txt"), ("/", "home/a.txt"));
+        assert_eq!(split_root("//server/share/a.txt"), ("//server/share/", "a.txt"));
+        assert_eq!(split_root("//?/C:/a.txt"), ("//?/C:/", "a.txt"));
+        assert_eq!(split_root("./test_dir/a.txt"), ("", "./test_dir/a.txt"));
+    }
+
+    #[test]
+    fn entry_names() {
+        let mut manifest = ArchiveManifest::default();
+        let names: Vec<String> = [
+            "C:\\Users\\hutzi\\a.txt",
+            "D:/b.txt",
+            "/home/hutzi/c.txt",
+            "//nas/backup/d.txt",
+            "./test_dir/e.txt",
+            "c:/f.txt",
+        ]
+        .into_iter()
+        .map(|path| manifest.entry_name(Path::new(path)))
+        .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "C/Users/hutzi/a.txt",
+                "D/b.txt",
+                "root/home/hutzi/c.txt",
+                "nas_backup/d.txt",
+                "relative/test_dir/e.txt",
+                "c/f.txt",
+            ]
+        );
+        assert_eq!(manifest.roots.len(), 6);
+        assert_eq!(manifest.original_path("root/home/hutzi/c.txt"), Some(String::from("/home/hutzi/c.txt")));
+        assert_eq!(manifest.original_path("relative/test_dir/e.txt"), Some(String::from("test_dir/e.txt")));
+        assert_eq!(manifest.original_path("E/g.txt"), None);
+    }
+
+    #[test]
+    fn taken_labels() {
+        let mut manifest = ArchiveManifest::default();
+        let drive = manifest.entry_name(Path::new("C:/a.txt"));
+        let drive_relative = manifest.entry_name(Path::new("C:b.txt"));
+        let again = manifest.entry_name(Path::new("C:/c.txt"));
+
+        assert_eq!(drive, "C/a.txt");
+        assert_eq!(drive_relative, "C_2/b.txt");
+        assert_eq!(again, "C/c.txt");
+        assert_eq!(manifest.original_path(&drive_relative), Some(String::from("C:b.txt")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_names() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let mut manifest = ArchiveManifest::default();
+        let first = Path::new("/home").join(OsStr::from_bytes(b"a\xff.txt"));
+        let second = Path::new("/home").join(OsStr::from_bytes(b"a\xfe.txt"));
+        let first_name = manifest.entry_name(&first);
+        let second_name = manifest.entry_name(&second);
+
+        assert!(first_name.starts_with("root/home/a\u{FFFD}.txt~"));
+        assert_ne!(first_name, second_name);
+        assert_eq!(manifest.entry_name(&first), first_name);
+        assert_eq!(manifest.original_os_path(&first_name), Some(first));
+        assert_eq!(manifest.original_os_path(&second_name), Some(second));
+        assert_eq!(manifest.raw_names.len(), 2);
+        assert_eq!(manifest.original_os_path("root/home/b.txt"), Some(PathBuf::from("/home/b.txt")));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn unpaired_surrogates() {
+        use std::os::windows::ffi::OsStringExt;
+
+        let mut manifest = ArchiveManifest::default();
+        let path = Path::new("C:\\Users").join(OsString::from_wide(&[0x61, 0xD800, 0x62]));
+        let name = manifest.entry_name(&path);
+
+        assert!(name.starts_with("C/Users/a\u{FFFD}b~"));
+        assert_eq!(manifest.original_os_path(&name), Some(PathBuf::from("C:/").join(path.strip_prefix("C:\\").unwrap())));
+    }
+
+    #[test]
+    fn foreign_raw_names() {
+        let mut manifest = ArchiveManifest::default();
+        manifest.entry_name(Path::new("C:/a.txt"));
+        let foreign = if cfg!(windows) {
+            RawName::Unix(b"a\xff.txt".to_vec())
+        } else {
+            RawName::Windows(vec![0x61, 0xD800])
+        };
+        manifest.raw_names.insert(String::from("C/a\u{FFFD}~12345678"), foreign);
+
+        // names from another kind of platform fall back to the lossy name
+        assert_eq!(
+            manifest.original_os_path("C/a\u{FFFD}~12345678"),
+            Some(PathBuf::from("C:/a\u{FFFD}~12345678"))
+        );
+    }
+
+    #[test]
+    fn serde() {
+        let mut manifest = ArchiveManifest::default();
+        manifest.entry_name(Path::new("C:/a.txt"));
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert_eq!(json, r#"{"roots":{"C":"C:/"}}"#);
+        assert_eq!(serde_json::from_str::<ArchiveManifest>(&json).unwrap(), manifest);
+    }
+}
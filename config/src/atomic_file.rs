@@ -0,0 +1,74 @@
+//! Contains functions for replacing files atomically, so that a crash while writing never leaves a half-written file behind.
+
+use std::{
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::{BufWriter, Error, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+/// Suffix appended to the path of a file while its new content is written
+pub const TEMP_SUFFIX: &str = ".tmp";
+
+/// Returns the path of the temporary file used while writing to `file_path`, i.e., `file_path` with [TEMP_SUFFIX] appended.
+pub fn temp_path(file_path: &Path) -> PathBuf {
+    let mut path = OsString::from(file_path.as_os_str());
+    path.push(TEMP_SUFFIX);
+    PathBuf::from(path)
+}
+
+/// Writes `value` as pretty printed JSON to `file_path` (see [write]).
+pub fn write_json<T: Serialize>(file_path: &Path, value: &T) -> Result<(), Error> {
+    write(file_path, &serde_json::to_vec_pretty(value)?)
+}
+
+/// Replaces the content of the file at `file_path` with the given `content`.
+///
+/// The content is written to the [temp_path] in the same directory first, flushed to disk and then renamed to `file_path`.
+/// So `file_path` always contains either the old or the new content. The rename also replaces an existing file on Windows.
+/// A temporary file left behind by an earlier, interrupted write is overwritten.
+pub fn write(file_path: &Path, content: &[u8]) -> Result<(), Error> {
+    let tmp_path = temp_path(file_path);
+    let file = OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .open(&tmp_path)?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(content)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    drop(writer);
+
+    fs::rename(&tmp_path, file_path)
+}
+
+#[cfg(test)]
+mod atomic_file_tests {
+    use super::*;
+
+    #[test]
+    fn temp_path_appends_suffix() {
+        assert_eq!(
+            temp_path(&PathBuf::from("dir/abc.json")),
+            PathBuf::from("dir/abc.json.tmp")
+        );
+    }
+
+    #[test]
+    fn replaces_existing_file() {
+        let file_path = PathBuf::from("test_tmp/atomic_replace_test.json");
+        fs::write(&file_path, "[1, 2, 3, 4, 5, 6, 7, 8, 9]").unwrap();
+
+        let res = write_json(&file_path, &vec![1]);
+        let content = fs::read_to_string(&file_path).unwrap();
+        let tmp_exists = temp_path(&file_path).exists();
+        fs::remove_file(&file_path).unwrap();
+
+        assert!(res.is_ok());
+        assert_eq!(serde_json::from_str::<Vec<u32>>(&content).unwrap(), vec![1]);
+        assert!(!tmp_exists);
+    }
+}
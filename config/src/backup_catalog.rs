@@ -0,0 +1,273 @@
+//! Contains the catalog of archived files, which tells which archives contain a file without opening them.
+//!
+//! Every profile has its own catalog in the profile config dir (see [catalog_file]). It contains one JSON encoded [CatalogEntry] per line,
+//! so a backup only appends the files it archived, no matter how large the catalog already is.
+//! The catalog is only an index: backups don't read it, and a lost or stale catalog can be rebuilt from the archives.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDateTime;
+use globset::GlobBuilder;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::atomic_file;
+
+/// Suffix of the file name of a catalog, which is named after the [Uuid] of its profile
+pub const CATALOG_SUFFIX: &str = ".catalog.jsonl";
+
+/// Version of a file stored in an archive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    /// Original path of the file
+    pub path: String,
+    /// Size of the file in bytes
+    pub size: u64,
+    /// Modification time of the file when it was archived. [None] if it is unknown.
+    pub modified: Option<NaiveDateTime>,
+    /// SHA-256 of the content as lowercase hex digits
+    pub sha256: String,
+    /// Path of the archive containing the file. Split archives are named by their first volume.
+    pub archive: PathBuf,
+    /// Datetime the backup was started
+    pub archived: NaiveDateTime,
+    pub profile_uuid: Uuid,
+}
+
+/// Returns the path of the catalog of the profile with the given [Uuid] in the profile config dir at `dir_path`.
+pub fn catalog_file(dir_path: &Path, uuid: &Uuid) -> PathBuf {
+    dir_path.join(format!("{}{}", uuid.as_hyphenated(), CATALOG_SUFFIX))
+}
+
+/// Appends `entries` to the catalog of the profile with the given [Uuid], creating it if there is none.
+pub fn append_entries(dir_path: &Path, uuid: &Uuid, entries: &[CatalogEntry]) -> Result<(), io::Error> {
+    let file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(catalog_file(dir_path, uuid))?;
+    let mut writer = BufWriter::new(file);
+    write_lines(&mut writer, entries)?;
+    writer.flush()
+}
+
+/// Replaces the catalog of the profile with the given [Uuid] with `entries`, e.g., after rebuilding it from the archives.
+pub fn write_catalog(dir_path: &Path, uuid: &Uuid, entries: &[CatalogEntry]) -> Result<(), io::Error> {
+    let mut content = vec![];
+    write_lines(&mut content, entries)?;
+    atomic_file::write(&catalog_file(dir_path, uuid), &content)
+}
+
+fn write_lines(writer: &mut impl Write, entries: &[CatalogEntry]) -> Result<(), io::Error> {
+    for entry in entries {
+        serde_json::to_writer(&mut *writer, entry)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Reads the catalog at `path`. Lines that can't be parsed are skipped.
+///
+/// # Returns
+/// [Ok] containing the entries in the order they were added. Empty if there is no catalog.
+fn read_catalog_file(path: &Path) -> Result<Vec<CatalogEntry>, io::Error> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    let mut entries = vec![];
+    for line in BufReader::new(file).lines() {
+        if let Ok(entry) = serde_json::from_str(&line?) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Reads the catalog of the profile with the given [Uuid] (see [read_catalog_file]).
+pub fn read_catalog(dir_path: &Path, uuid: &Uuid) -> Result<Vec<CatalogEntry>, io::Error> {
+    read_catalog_file(&catalog_file(dir_path, uuid))
+}
+
+/// Builds the function matching the paths of [CatalogEntry]s against `pattern`, ignoring case.
+/// Patterns containing glob characters (`*`, `?`, `[` or `{`) are globs, in which `*` also matches `/`. Other patterns match any path containing them.
+/// Backslashes in paths are matched as `/`, so the same pattern works for Windows paths.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if `pattern` isn't a valid glob.
+pub fn path_matcher(pattern: &str) -> Result<impl Fn(&str) -> bool, String> {
+    let glob = if pattern.contains(['*', '?', '[', '{']) {
+        let glob = GlobBuilder::new(&pattern.replace('\\', "/"))
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("{:?} isn't a valid glob: {}", pattern, e))?;
+        Some(glob.compile_matcher())
+    } else {
+        None
+    };
+    let needle = pattern.replace('\\', "/").to_lowercase();
+    Ok(move |path: &str| {
+        let path = path.replace('\\', "/");
+        match &glob {
+            Some(glob) => glob.is_match(&path),
+            None => path.to_lowercase().contains(&needle),
+        }
+    })
+}
+
+/// Searches the catalogs in the profile config dir at `dir_path` for the files whose paths match `pattern` (see [path_matcher]).
+/// Only the catalogs of the profiles in `uuids` are searched, or all of them if it is [None].
+/// Entries whose archives don't exist anymore, e.g., because they were pruned, are left out.
+///
+/// # Returns
+/// The matching entries from the newest to the oldest backup, at most `limit` of them.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if `pattern` is invalid or the dir couldn't be read.
+pub fn search(dir_path: &Path, uuids: Option<&[Uuid]>, pattern: &str, limit: Option<usize>) -> Result<Vec<CatalogEntry>, String> {
+    let matches = path_matcher(pattern)?;
+    let mut found = vec![];
+    let dir = fs::read_dir(dir_path).map_err(|e| format!("Couldn't read {:?} because of {:?}", dir_path, e))?;
+    for entry in dir {
+        let path = entry.map_err(|e| format!("Couldn't read {:?} because of {:?}", dir_path, e))?.path();
+        let uuid = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(CATALOG_SUFFIX))
+            .and_then(|uuid| Uuid::parse_str(uuid).ok());
+        let uuid = match uuid {
+            Some(uuid) if uuids.is_none_or(|uuids| uuids.contains(&uuid)) => uuid,
+            _ => continue,
+        };
+        let entries = read_catalog_file(&path).map_err(|e| format!("Couldn't read the catalog of {} because of {:?}", uuid, e))?;
+        found.extend(entries.into_iter().filter(|entry| matches(&entry.path)));
+    }
+
+    // newest first, and among the files of one backup alphabetically
+    found.sort_by(|a, b| b.archived.cmp(&a.archived).then_with(|| a.path.cmp(&b.path)));
+    let mut exists: HashMap<PathBuf, bool> = HashMap::new();
+    let found = found
+        .into_iter()
+        .filter(|entry| *exists.entry(entry.archive.clone()).or_insert_with(|| entry.archive.exists()))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    Ok(found)
+}
+
+#[cfg(test)]
+mod backup_catalog_tests {
+    use std::time::Instant;
+
+    use chrono::{Duration, NaiveDate};
+
+    use super::*;
+
+    fn entry(path: &str, archive: &Path, archived: NaiveDateTime, uuid: Uuid) -> CatalogEntry {
+        CatalogEntry {
+            path: String::from(path),
+            size: 1234,
+            modified: Some(archived - Duration::days(1)),
+            sha256: format!("{:064x}", path.len()),
+            archive: archive.to_path_buf(),
+            archived,
+            profile_uuid: uuid,
+        }
+    }
+
+    fn date(day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 5, day).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn matching() {
+        let substring = path_matcher("Thesis").unwrap();
+        assert!(substring("/home/hutzi/thesis.docx"));
+        assert!(substring(r"C:\Users\hutzi\THESIS\draft.docx"));
+        assert!(!substring("/home/hutzi/notes.txt"));
+
+        let glob = path_matcher("*/hutzi/*.docx").unwrap();
+        assert!(glob("/home/hutzi/thesis.docx"));
+        assert!(glob(r"C:\Users\Hutzi\Uni\thesis.DOCX"));
+        assert!(!glob("/home/hutzi/thesis.pdf"));
+
+        let windows = path_matcher(r"C:\Users\*\thesis.docx").unwrap();
+        assert!(windows("C:/Users/hutzi/thesis.docx"));
+
+        assert!(path_matcher("[a-").is_err());
+    }
+
+    #[test]
+    fn search_newest_first() {
+        let dir = PathBuf::from("test_tmp/catalog_search");
+        fs::create_dir_all(&dir).unwrap();
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        let archives: Vec<PathBuf> = (1..=4).map(|i| dir.join(format!("archive{}.zip", i))).collect();
+        for archive in &archives[..3] {
+            fs::write(archive, "").unwrap();
+        }
+
+        append_entries(&dir, &first, &[
+            entry("/home/hutzi/thesis.docx", &archives[0], date(1), first),
+            entry("/home/hutzi/notes.txt", &archives[0], date(1), first),
+        ]).unwrap();
+        append_entries(&dir, &first, &[entry("/home/hutzi/thesis.docx", &archives[1], date(3), first)]).unwrap();
+        append_entries(&dir, &second, &[entry("/mnt/share/Thesis.docx", &archives[2], date(2), second)]).unwrap();
+        // the archive was pruned
+        append_entries(&dir, &second, &[entry("/mnt/share/thesis.docx", &archives[3], date(4), second)]).unwrap();
+        // a catalog survives broken lines
+        let mut file = OpenOptions::new().append(true).open(catalog_file(&dir, &first)).unwrap();
+        file.write_all(b"{not json\n").unwrap();
+
+        let all = search(&dir, None, "thesis", None);
+        let only_first = search(&dir, Some(&[first]), "thesis", None);
+        let limited = search(&dir, None, "*.docx", Some(1));
+        let rebuilt = write_catalog(&dir, &first, &[]).and_then(|_| read_catalog(&dir, &first));
+        let missing = read_catalog(&dir, &Uuid::new_v4());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let archived_in = |found: Vec<CatalogEntry>| -> Vec<PathBuf> { found.into_iter().map(|entry| entry.archive).collect() };
+        assert_eq!(archived_in(all.unwrap()), vec![archives[1].clone(), archives[2].clone(), archives[0].clone()]);
+        assert_eq!(archived_in(only_first.unwrap()), vec![archives[1].clone(), archives[0].clone()]);
+        assert_eq!(archived_in(limited.unwrap()), vec![archives[1].clone()]);
+        assert_eq!(rebuilt.unwrap(), vec![]);
+        assert_eq!(missing.unwrap(), vec![]);
+    }
+
+    /// Measures the size of the catalog and how long updating and searching it takes for a few thousand files.
+    #[test]
+    fn size_and_latency() {
+        const FILES: usize = 5000;
+        let dir = PathBuf::from("test_tmp/catalog_size");
+        fs::create_dir_all(&dir).unwrap();
+        let uuid = Uuid::new_v4();
+        let archive = dir.join("archive.zip");
+        fs::write(&archive, "").unwrap();
+        let entries: Vec<CatalogEntry> = (0..FILES)
+            .map(|i| entry(&format!("/home/hutzi/projects/project{}/src/file{}.rs", i / 100, i), &archive, date(1 + (i % 20) as u32), uuid))
+            .collect();
+
+        let start = Instant::now();
+        let appended = append_entries(&dir, &uuid, &entries);
+        let update = start.elapsed();
+        let size = fs::metadata(catalog_file(&dir, &uuid)).map(|metadata| metadata.len());
+        let start = Instant::now();
+        let found = search(&dir, Some(&[uuid]), "*/project7/*", None);
+        let query = start.elapsed();
+        fs::remove_dir_all(&dir).unwrap();
+
+        appended.unwrap();
+        let size = size.unwrap();
+        println!("{} entries: {} bytes, update took {:?}, search took {:?}", FILES, size, update, query);
+        assert_eq!(found.unwrap().len(), 100);
+        // roughly one line of JSON per file, dominated by the hash and the paths
+        assert!(size < FILES as u64 * 400, "catalog has {} bytes", size);
+        assert!(update.as_secs() < 5, "update took {:?}", update);
+        assert!(query.as_secs() < 5, "search took {:?}", query);
+    }
+}
@@ -0,0 +1,487 @@
+//! Contains the naming scheme of backup archives and functions for listing the archives of a profile.
+//!
+//! Archives are named `<uuid>_<YYYY-MM-DD_HH-MM>.zip` after the profile and the time the backup was started, see [archive_creation_date].
+//! Use [backup_inventory] to describe all archives of a profile, e.g., for picking the one to restore.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{Duration, NaiveDateTime};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    backup_report::{is_cancelled_archive, report_path},
+    profile_config::ProfileConfig,
+};
+
+/// Format of the creation date in the filename of a backup archive.
+pub const ARCHIVE_DATE_FORMAT: &str = "%Y-%m-%d_%H-%M";
+
+/// Suffix appended to the filename of incremental backup archives (before the extension).
+pub const INCREMENTAL_SUFFIX: &str = "_incr";
+
+/// Number of levels of subdirectories of a target dir that are searched for archives, e.g., `2023/` or `2023/04/`.
+/// The archives of a target dir at the root of a drive are found without walking the whole drive.
+pub const ARCHIVE_SEARCH_DEPTH: usize = 2;
+
+/// Suffix of the volumes of an archive that is split into several files, followed by the two-digit number of the volume, starting at `01`.
+/// E.g., `<uuid>_2023-04-01_13-37.part02.zip` is the second volume.
+pub const PART_SUFFIX: &str = ".part";
+
+/// Splits the file stem of an archive volume into the stem of the archive and the number of the volume.
+/// Archives that aren't split have no volume number.
+pub fn split_part_suffix(stem: &str) -> (&str, Option<u32>) {
+    let part = stem
+        .rsplit_once(PART_SUFFIX)
+        .filter(|(_, number)| number.len() >= 2 && number.bytes().all(|byte| byte.is_ascii_digit()))
+        .and_then(|(stem, number)| Some((stem, number.parse().ok()?)));
+    match part {
+        Some((stem, number)) => (stem, Some(number)),
+        None => (stem, None),
+    }
+}
+
+/// Returns the filename of the given volume of the archive with the given file stem (see [PART_SUFFIX]).
+pub fn part_file_name(stem: &str, number: u32) -> String {
+    format!("{}{}{:02}.zip", stem, PART_SUFFIX, number)
+}
+
+/// Extracts the creation date from the filename of a backup archive belonging to the given [ProfileConfig].
+/// The filename has the form `<uuid>_<YYYY-MM-DD_HH-MM>.zip`, where `uuid` is the current or one of the former [Uuid]s of the profile.
+/// Incremental archives additionally have the [INCREMENTAL_SUFFIX] before the extension, volumes of split archives the [PART_SUFFIX] after it.
+///
+/// # Returns
+/// [None] if the file isn't a backup archive of the profile.
+pub fn archive_creation_date(profile_config: &ProfileConfig, path: &Path) -> Option<NaiveDateTime> {
+    let (uuid, creation_date) = path
+        .file_name()?
+        .to_str()?
+        .strip_suffix(".zip")?
+        .split_once('_')?;
+    let (creation_date, _) = split_part_suffix(creation_date);
+    let creation_date = creation_date.strip_suffix(INCREMENTAL_SUFFIX).unwrap_or(creation_date);
+    if !profile_config.matches_uuid(&Uuid::parse_str(uuid).ok()?) {
+        return None;
+    }
+
+    match NaiveDateTime::parse_from_str(creation_date, ARCHIVE_DATE_FORMAT) {
+        Ok(date_time) => Some(date_time),
+        Err(e) => {
+            warn!("Couldn't parse date {:?} because {:?}", creation_date, e);
+            None
+        }
+    }
+}
+
+/// Lists the paths of all files in the given `dir` and in its subdirectories up to `depth` levels below it.
+/// Links to directories aren't followed. Entries and subdirectories that can't be read are skipped.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if `dir` itself couldn't be read.
+pub fn files_below(dir: &PathBuf, depth: usize) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(dir).map_err(|err| format!("Error reading dir: {:?}", err))?;
+    let mut files = vec![];
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.is_file() {
+            files.push(path);
+        } else if depth > 0 && entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            match files_below(&path, depth - 1) {
+                Ok(nested) => files.extend(nested),
+                Err(msg) => warn!("Skipping {:?} because {}", path, msg),
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Lists the backup archives of the given [ProfileConfig] in the given `dir` and its subdirectories (see [ARCHIVE_SEARCH_DEPTH]), sorted by creation date (oldest first).
+/// Archives that are split into volumes are only listed once, by their first volume (see [archive_parts]).
+/// Subdirectories that are archive dirs of the profile on their own (see [ProfileConfig::archive_dirs]) are left out.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the dir couldn't be read.
+pub fn archives_in_dir(profile_config: &ProfileConfig, dir: &PathBuf) -> Result<Vec<(NaiveDateTime, PathBuf)>, String> {
+    let nested_dirs: Vec<&PathBuf> = profile_config
+        .archive_dirs()
+        .into_iter()
+        .filter(|other| *other != dir && other.starts_with(dir))
+        .collect();
+    let mut archives: Vec<(NaiveDateTime, PathBuf)> = files_below(dir, ARCHIVE_SEARCH_DEPTH)?
+        .into_iter()
+        .filter(|path| !nested_dirs.iter().any(|nested| path.starts_with(nested)))
+        .filter(|path| archive_part_number(path).is_none_or(|number| number == 1))
+        .filter_map(|path| Some((archive_creation_date(profile_config, &path)?, path)))
+        .collect();
+    archives.sort();
+    Ok(archives)
+}
+
+/// Lists the backup archives of the given [ProfileConfig], sorted by creation date (oldest first).
+/// Searches the target dir as well as the `previous_target_dirs` (see [ProfileConfig::archive_dirs]), including their subdirectories up to the [ARCHIVE_SEARCH_DEPTH].
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the target dir couldn't be read. Previous target dirs that can't be read are skipped.
+pub fn list_archives(profile_config: &ProfileConfig) -> Result<Vec<(NaiveDateTime, PathBuf)>, String> {
+    let mut archives = vec![];
+
+    for dir in profile_config.archive_dirs() {
+        match archives_in_dir(profile_config, dir) {
+            Ok(dir_archives) => archives.extend(dir_archives),
+            Err(msg) if dir == &profile_config.target_dir => return Err(msg),
+            Err(msg) => warn!("Skipping previous target dir {:?} because {}", dir, msg),
+        }
+    }
+    archives.sort();
+
+    Ok(archives)
+}
+
+/// Checks whether the backup archive at `path` is an incremental one, i.e., only contains the files changed since the previous archive.
+pub fn is_incremental_archive(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| split_part_suffix(stem).0.ends_with(INCREMENTAL_SUFFIX))
+}
+
+/// Returns the number of the volume at `path` (see [PART_SUFFIX]), or [None] if it belongs to an archive that isn't split.
+pub fn archive_part_number(path: &Path) -> Option<u32> {
+    split_part_suffix(path.file_stem()?.to_str()?).1
+}
+
+/// Lists the volumes of the archive whose first volume is at `path`, in order. Archives that aren't split consist of `path` only.
+/// The volumes are numbered consecutively, so the list ends at the first missing number.
+pub fn archive_parts(path: &Path) -> Vec<PathBuf> {
+    let stem = match path.file_stem().and_then(|stem| stem.to_str()).map(split_part_suffix) {
+        Some((stem, Some(_))) => stem.to_string(),
+        _ => return vec![path.to_path_buf()],
+    };
+    (1..)
+        .map(|number| path.with_file_name(part_file_name(&stem, number)))
+        .take_while(|part| part.exists())
+        .collect()
+}
+
+/// Backup archive of a profile, as listed by [backup_inventory].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupInfo {
+    /// Path of the archive, or of its first volume if it is split
+    pub path: PathBuf,
+    /// Datetime the backup was started
+    pub created: NaiveDateTime,
+    /// Whether the archive only contains the files changed since the previous one (see [INCREMENTAL_SUFFIX])
+    pub incremental: bool,
+    /// Number of files the archive consists of (see [PART_SUFFIX])
+    pub volumes: usize,
+    /// Size of all volumes in bytes
+    pub bytes: u64,
+    /// Whether the report of the archive, which lists the checksums of its volumes, exists (see [report_path])
+    pub has_report: bool,
+    /// Whether the backup was stopped early, so the archive only contains some of the files (see [is_cancelled_archive])
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+/// Describes the backup archives of the given [ProfileConfig], sorted by creation date (oldest first).
+/// Like [list_archives], the target dir as well as the `previous_target_dirs` are searched.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the target dir couldn't be read.
+pub fn backup_inventory(profile_config: &ProfileConfig) -> Result<Vec<BackupInfo>, String> {
+    Ok(list_archives(profile_config)?
+        .into_iter()
+        .map(|(created, path)| {
+            let parts = archive_parts(&path);
+            BackupInfo {
+                created,
+                incremental: is_incremental_archive(&path),
+                volumes: parts.len(),
+                bytes: parts
+                    .iter()
+                    .map(|part| fs::metadata(part).map_or(0, |metadata| metadata.len()))
+                    .sum(),
+                has_report: report_path(&path).is_file(),
+                cancelled: is_cancelled_archive(&path),
+                path,
+            }
+        })
+        .collect())
+}
+
+/// Returns when the given [ProfileConfig] was last active: its `last_backup` or the creation of its newest archive, whichever is later.
+/// Archives that can't be listed are ignored.
+pub fn last_activity(profile_config: &ProfileConfig) -> Option<NaiveDateTime> {
+    let newest_archive = list_archives(profile_config)
+        .ok()
+        .and_then(|archives| archives.last().map(|(created, _)| *created));
+    profile_config.last_backup.max(newest_archive)
+}
+
+/// Checks if the system clock appears to be wrong, because `now` lies too far before the [last_activity] of the given [ProfileConfig] (see [ProfileConfig::clock_skew]).
+///
+/// # Returns
+/// [Some] containing the amount of time `now` lies before the last activity if the clock appears to be wrong. [None] else.
+pub fn clock_skew(profile_config: &ProfileConfig, now: NaiveDateTime) -> Option<Duration> {
+    ProfileConfig::clock_skew(now, last_activity(profile_config))
+}
+
+#[cfg(test)]
+mod backup_inventory_tests {
+    use std::fs::File;
+
+    use crate::interval::IntervalBuilder;
+
+    use super::*;
+
+    fn archive_test_config(target_dir: &str) -> ProfileConfig {
+        ProfileConfig::new(
+            String::from("archives"),
+            PathBuf::from(target_dir),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        )
+    }
+
+    #[test]
+    fn archive_creation_date_tests() {
+        let profile_config = archive_test_config("");
+        let uuid = profile_config.get_uuid().as_hyphenated().to_string();
+
+        assert_eq!(
+            archive_creation_date(&profile_config, &PathBuf::from(format!("{}_2023-04-01_13-37.zip", uuid))),
+            NaiveDateTime::parse_from_str("2023-04-01 13:37", "%Y-%m-%d %H:%M").ok()
+        );
+        assert_eq!(
+            archive_creation_date(&profile_config, &PathBuf::from(format!("{}_2023-04-01_13-37_incr.zip", uuid))),
+            NaiveDateTime::parse_from_str("2023-04-01 13:37", "%Y-%m-%d %H:%M").ok()
+        );
+        assert_eq!(
+            archive_creation_date(&profile_config, &PathBuf::from(format!("{}_2023-04-01_13-37.txt", uuid))),
+            None
+        );
+        assert_eq!(
+            archive_creation_date(&profile_config, &PathBuf::from("a1a2a3a4-0000-0000-0000-000000000000_2023-04-01_13-37.zip")),
+            None
+        );
+        assert_eq!(
+            archive_creation_date(&profile_config, &PathBuf::from(format!("{}_yesterday.zip", uuid))),
+            None
+        );
+        assert_eq!(
+            archive_creation_date(&profile_config, &PathBuf::from(format!("{}x_2023-04-01_13-37.zip", uuid))),
+            None
+        );
+    }
+
+    #[test]
+    fn is_incremental_archive_tests() {
+        assert!(is_incremental_archive(&PathBuf::from("./dir/a_2023-04-01_13-37_incr.zip")));
+        assert!(!is_incremental_archive(&PathBuf::from("./dir/a_2023-04-01_13-37.zip")));
+        assert!(!is_incremental_archive(&PathBuf::from("./dir_incr/a_2023-04-01_13-37.zip")));
+    }
+
+    #[test]
+    fn archive_volumes() {
+        let profile_config = archive_test_config("");
+        let uuid = profile_config.get_uuid().as_hyphenated().to_string();
+        let part = PathBuf::from(format!("./dir/{}_2023-04-01_13-37_incr.part02.zip", uuid));
+
+        assert_eq!(part_file_name(&format!("{}_2023-04-01_13-37_incr", uuid), 2), format!("{}_2023-04-01_13-37_incr.part02.zip", uuid));
+        assert_eq!(archive_part_number(&part), Some(2));
+        assert_eq!(archive_part_number(&PathBuf::from("./dir/a_2023-04-01_13-37.zip")), None);
+        assert_eq!(archive_part_number(&PathBuf::from("./dir/a_2023-04-01_13-37.part.zip")), None);
+        assert!(is_incremental_archive(&part));
+        assert_eq!(
+            archive_creation_date(&profile_config, &part),
+            NaiveDateTime::parse_from_str("2023-04-01 13:37", "%Y-%m-%d %H:%M").ok()
+        );
+    }
+
+    #[test]
+    fn list_split_archives() {
+        let dir = "./test_dir/split_archives_tmp";
+        fs::create_dir_all(dir).unwrap();
+        let profile_config = archive_test_config(dir);
+        let uuid = profile_config.get_uuid().as_hyphenated().to_string();
+        for name in [
+            format!("{}_2023-05-01_10-00.part01.zip", uuid),
+            format!("{}_2023-05-01_10-00.part02.zip", uuid),
+            format!("{}_2023-05-01_10-00.part03.zip", uuid),
+            format!("{}_2023-05-01_10-00.part05.zip", uuid),
+            format!("{}_2023-06-01_10-00.zip", uuid),
+        ] {
+            File::create(PathBuf::from(dir).join(name)).unwrap();
+        }
+
+        let archives = list_archives(&profile_config).unwrap();
+        let parts = archive_parts(&archives[0].1);
+        let single = archive_parts(&archives[1].1);
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(archives.len(), 2);
+        assert_eq!(archives[0].1, PathBuf::from(dir).join(format!("{}_2023-05-01_10-00.part01.zip", uuid)));
+        let names: Vec<String> = parts
+            .iter()
+            .map(|part| part.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                format!("{}_2023-05-01_10-00.part01.zip", uuid),
+                format!("{}_2023-05-01_10-00.part02.zip", uuid),
+                format!("{}_2023-05-01_10-00.part03.zip", uuid),
+            ]
+        );
+        assert_eq!(single, vec![archives[1].1.clone()]);
+    }
+
+    #[test]
+    fn last_activity_and_clock_skew() {
+        let dir = "./test_dir/last_activity_tmp";
+        fs::create_dir_all(dir).unwrap();
+        let mut profile_config = archive_test_config(dir);
+        let uuid = profile_config.get_uuid().as_hyphenated().to_string();
+        let datetime = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+
+        let nothing = last_activity(&profile_config);
+        profile_config.last_backup = Some(datetime("2023-05-01 10:00"));
+        let only_last_backup = last_activity(&profile_config);
+        File::create(format!("{}/{}_2023-05-03_10-00.zip", dir, uuid)).unwrap();
+        let newer_archive = last_activity(&profile_config);
+        profile_config.last_backup = Some(datetime("2023-05-05 10:00"));
+        let newer_last_backup = last_activity(&profile_config);
+        let skew = clock_skew(&profile_config, datetime("2010-01-01 00:00"));
+        let no_skew = clock_skew(&profile_config, datetime("2023-05-05 10:00"));
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(nothing, None);
+        assert_eq!(only_last_backup, Some(datetime("2023-05-01 10:00")));
+        assert_eq!(newer_archive, Some(datetime("2023-05-03 10:00")));
+        assert_eq!(newer_last_backup, Some(datetime("2023-05-05 10:00")));
+        assert_eq!(skew, Some(datetime("2023-05-05 10:00") - datetime("2010-01-01 00:00")));
+        assert_eq!(no_skew, None);
+    }
+
+    #[test]
+    fn list_archives_former_uuids() {
+        let dir = "./test_dir/former_uuids_tmp";
+        fs::create_dir_all(dir).unwrap();
+        let mut profile_config = archive_test_config(dir);
+        let former_uuid = profile_config.get_uuid().as_hyphenated().to_string();
+        File::create(PathBuf::from(dir).join(format!("{}_2023-05-01_10-00.zip", former_uuid))).unwrap();
+
+        let uuid = profile_config.regenerate_uuid().as_hyphenated().to_string();
+        File::create(PathBuf::from(dir).join(format!("{}_2023-06-01_10-00.zip", uuid))).unwrap();
+        let archives = list_archives(&profile_config);
+        profile_config.former_uuids.clear();
+        let without_former = list_archives(&profile_config);
+        fs::remove_dir_all(dir).unwrap();
+
+        let archives: Vec<PathBuf> = archives.unwrap().into_iter().map(|(_, path)| path).collect();
+        assert_eq!(
+            archives,
+            vec![
+                PathBuf::from(dir).join(format!("{}_2023-05-01_10-00.zip", former_uuid)),
+                PathBuf::from(dir).join(format!("{}_2023-06-01_10-00.zip", uuid)),
+            ]
+        );
+        assert_eq!(without_former.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn list_archives_in_subdirs() {
+        let dir = "./test_dir/archive_subdirs_tmp";
+        let profile_config = archive_test_config(dir);
+        let uuid = profile_config.get_uuid().as_hyphenated().to_string();
+        let nested = PathBuf::from(dir).join("2023").join("05");
+        let too_deep = nested.join("old");
+        fs::create_dir_all(&too_deep).unwrap();
+        let in_target_dir = PathBuf::from(dir).join(format!("{}_2024-01-01_10-00.zip", uuid));
+        let in_subdir = PathBuf::from(dir).join("2023").join(format!("{}_2023-06-01_10-00.zip", uuid));
+        let in_nested_subdir = nested.join(format!("{}_2023-05-01_10-00.zip", uuid));
+        for path in [&in_target_dir, &in_subdir, &in_nested_subdir, &too_deep.join(format!("{}_2022-01-01_10-00.zip", uuid))] {
+            File::create(path).unwrap();
+        }
+
+        let archives = list_archives(&profile_config);
+        let files = files_below(&PathBuf::from(dir), 0);
+        fs::remove_dir_all(dir).unwrap();
+
+        let archives: Vec<PathBuf> = archives.unwrap().into_iter().map(|(_, path)| path).collect();
+        assert_eq!(archives, vec![in_nested_subdir, in_subdir, in_target_dir.clone()]);
+        assert_eq!(files, Ok(vec![in_target_dir]));
+    }
+
+    #[test]
+    fn list_archives_previous_target_dirs() {
+        let dir = "./test_dir/previous_target_dirs_tmp";
+        let old_dir = "./test_dir/previous_target_dirs_tmp/old";
+        fs::create_dir_all(old_dir).unwrap();
+        let mut profile_config = archive_test_config(dir);
+        profile_config.previous_target_dirs = vec![
+            PathBuf::from(old_dir),
+            PathBuf::from("./test_dir/doesnt_exist"),
+        ];
+        let uuid = profile_config.get_uuid().as_hyphenated().to_string();
+        File::create(PathBuf::from(dir).join(format!("{}_2023-05-01_10-00.zip", uuid))).unwrap();
+        File::create(PathBuf::from(old_dir).join(format!("{}_2022-05-01_10-00.zip", uuid))).unwrap();
+
+        let archives = list_archives(&profile_config);
+        profile_config.target_dir = PathBuf::from("./test_dir/doesnt_exist_either");
+        let missing_target = list_archives(&profile_config);
+        fs::remove_dir_all(dir).unwrap();
+
+        let archives = archives.unwrap();
+        assert_eq!(archives.len(), 2);
+        assert_eq!(archives[0].1, PathBuf::from(old_dir).join(format!("{}_2022-05-01_10-00.zip", uuid)));
+        assert!(missing_target.is_err());
+    }
+
+    #[test]
+    fn inventory() {
+        let dir = "./test_dir/backup_inventory_tmp";
+        fs::create_dir_all(dir).unwrap();
+        let profile_config = archive_test_config(dir);
+        let uuid = profile_config.get_uuid().as_hyphenated().to_string();
+        fs::write(PathBuf::from(dir).join(format!("{}_2023-05-01_10-00.part01.zip", uuid)), [0u8; 10]).unwrap();
+        fs::write(PathBuf::from(dir).join(format!("{}_2023-05-01_10-00.part02.zip", uuid)), [0u8; 5]).unwrap();
+        fs::write(PathBuf::from(dir).join(format!("{}_2023-05-01_10-00.report.json", uuid)), "{}").unwrap();
+        fs::write(PathBuf::from(dir).join(format!("{}_2023-05-02_10-00_incr.zip", uuid)), [0u8; 3]).unwrap();
+        fs::write(PathBuf::from(dir).join("unrelated.zip"), [0u8; 3]).unwrap();
+
+        let inventory = backup_inventory(&profile_config);
+        fs::remove_dir_all(dir).unwrap();
+
+        let date = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        assert_eq!(
+            inventory.unwrap(),
+            vec![
+                BackupInfo {
+                    path: PathBuf::from(dir).join(format!("{}_2023-05-01_10-00.part01.zip", uuid)),
+                    created: date("2023-05-01 10:00"),
+                    incremental: false,
+                    volumes: 2,
+                    bytes: 15,
+                    has_report: true,
+                    cancelled: false,
+                },
+                BackupInfo {
+                    path: PathBuf::from(dir).join(format!("{}_2023-05-02_10-00_incr.zip", uuid)),
+                    created: date("2023-05-02 10:00"),
+                    incremental: true,
+                    volumes: 1,
+                    bytes: 3,
+                    has_report: false,
+                    cancelled: false,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Contains the report that is written next to each backup archive, so what an archive contains can be checked without opening it.
+//!
+//! The report of the archive `<name>.zip` is named `<name>.report.json`. Split archives have a single report for all their volumes.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::backup_inventory::split_part_suffix;
+
+/// Suffix of the file name of a report, replacing the `.zip` extension of its archive
+pub const REPORT_SUFFIX: &str = ".report.json";
+
+/// Exit code of the backupper if a backup was stopped early on request (e.g., by the server cancelling a job).
+/// The partial archive is kept and marked as `cancelled` in its report.
+pub const STOPPED_EXIT_CODE: i32 = 138;
+
+/// Returns the path of the report of the archive at `path` (see [REPORT_SUFFIX]). All volumes of a split archive share one report.
+pub fn report_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .map_or("", |stem| split_part_suffix(stem).0);
+    path.with_file_name(format!("{}{}", stem, REPORT_SUFFIX))
+}
+
+/// File that couldn't be archived.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedEntry {
+    pub path: PathBuf,
+    /// Why the file couldn't be archived
+    pub reason: String,
+}
+
+/// File of an archive, i.e., the archive itself or one of its volumes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveFile {
+    /// Name of the file within the target dir
+    pub file_name: String,
+    /// Size of the file in bytes
+    pub bytes: u64,
+    /// SHA-256 of the file as lowercase hex digits
+    pub sha256: String,
+}
+
+/// Describes what a backup archive contains.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupReport {
+    pub profile_name: String,
+    pub profile_uuid: Uuid,
+    /// Datetime the backup was started
+    pub started: NaiveDateTime,
+    /// Datetime the archive was complete
+    pub finished: NaiveDateTime,
+    /// Number of archived files, counting files that were archived more than once (e.g., because they were in use) only once
+    pub file_count: usize,
+    /// Size of all archived files in bytes
+    pub uncompressed_bytes: u64,
+    /// Size of all archived files within the archive in bytes
+    pub compressed_bytes: u64,
+    /// Files that couldn't be archived
+    pub skipped: Vec<SkippedEntry>,
+    /// Files the archive consists of, in order. Only split archives have more than one.
+    pub archive_files: Vec<ArchiveFile>,
+    /// Whether the backup was stopped early, so the archive only contains the files archived until then
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+/// Checks if the archive at `path` is the partial archive of a backup that was stopped early, according to its report.
+/// Such archives are skipped when looking for the archive an incremental backup builds on or for the archives to restore.
+/// Archives without a readable report count as complete.
+pub fn is_cancelled_archive(path: &Path) -> bool {
+    fs::read_to_string(report_path(path))
+        .ok()
+        .and_then(|content| serde_json::from_str::<BackupReport>(&content).ok())
+        .is_some_and(|report| report.cancelled)
+}
+
+#[cfg(test)]
+mod backup_report_tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn report_paths() {
+        assert_eq!(
+            report_path(Path::new("D:/backups/abc_2023-05-01_12-00.zip")),
+            PathBuf::from("D:/backups/abc_2023-05-01_12-00.report.json")
+        );
+        assert_eq!(
+            report_path(Path::new("D:/backups/abc_2023-05-01_12-00_incr.part02.zip")),
+            PathBuf::from("D:/backups/abc_2023-05-01_12-00_incr.report.json")
+        );
+    }
+
+    #[test]
+    fn serde() {
+        let started = NaiveDate::from_ymd_opt(2023, 5, 1).unwrap().and_hms_opt(12, 0, 0).unwrap();
+        let report = BackupReport {
+            profile_name: String::from("Hutzi"),
+            profile_uuid: Uuid::new_v4(),
+            started,
+            finished: started + chrono::Duration::minutes(3),
+            file_count: 2,
+            uncompressed_bytes: 300,
+            compressed_bytes: 120,
+            skipped: vec![SkippedEntry {
+                path: PathBuf::from("C:/a.pst"),
+                reason: String::from("in use by another process"),
+            }],
+            archive_files: vec![ArchiveFile {
+                file_name: String::from("archive.zip"),
+                bytes: 500,
+                sha256: String::from("ab"),
+            }],
+            cancelled: false,
+        };
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains(r#""started":"2023-05-01T12:00:00""#));
+        assert!(json.contains(r#""skipped":[{"path":"C:/a.pst","reason":"in use by another process"}]"#));
+        assert_eq!(serde_json::from_str::<BackupReport>(&json).unwrap(), report);
+
+        // reports written before backups could be stopped
+        let json = json.replace(r#","cancelled":false"#, "");
+        assert_eq!(serde_json::from_str::<BackupReport>(&json).unwrap(), report);
+    }
+
+    #[test]
+    fn cancelled_archives() {
+        let dir = PathBuf::from("./test_dir/cancelled_archives_tmp");
+        fs::create_dir_all(&dir).unwrap();
+        let started = NaiveDate::from_ymd_opt(2023, 5, 1).unwrap().and_hms_opt(12, 0, 0).unwrap();
+        let report = BackupReport {
+            profile_name: String::from("Hutzi"),
+            profile_uuid: Uuid::new_v4(),
+            started,
+            finished: started,
+            file_count: 1,
+            uncompressed_bytes: 3,
+            compressed_bytes: 3,
+            skipped: vec![],
+            archive_files: vec![],
+            cancelled: true,
+        };
+        fs::write(dir.join("stopped.report.json"), serde_json::to_string(&report).unwrap()).unwrap();
+        fs::write(dir.join("broken.report.json"), "{}").unwrap();
+
+        let stopped = is_cancelled_archive(&dir.join("stopped.part01.zip"));
+        let broken = is_cancelled_archive(&dir.join("broken.zip"));
+        let missing = is_cancelled_archive(&dir.join("missing.zip"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(stopped);
+        assert!(!broken);
+        assert!(!missing);
+    }
+}
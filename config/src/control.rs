@@ -0,0 +1,196 @@
+//! Contains the control channel of a running backup, through which it can be paused, resumed and stopped, e.g., from the tray or the server.
+//!
+//! The backupper listens on a unix socket in the temp dir, or a named pipe on Windows, which is named after the profile uuid (see [control_endpoint]).
+//! Clients send one [ControlCommand] per connection as a line of text. The backupper answers with a line containing its [ControlStatus] as JSON,
+//! or an error message starting with [ERROR_PREFIX].
+
+use std::{
+    fmt::Display,
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Prefix of the answers to commands that couldn't be handled
+pub const ERROR_PREFIX: &str = "error: ";
+
+/// Command for a running backup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlCommand {
+    /// Pause the backup after the current file
+    Pause,
+    /// Continue a paused backup
+    Resume,
+    /// Only return the [ControlStatus]
+    Status,
+    /// Finish the current file and keep the files archived until then as a partial archive
+    Stop,
+}
+
+impl Display for ControlCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ControlCommand::Pause => "pause",
+            ControlCommand::Resume => "resume",
+            ControlCommand::Status => "status",
+            ControlCommand::Stop => "stop",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for ControlCommand {
+    type Err = String;
+
+    /// Parses the name of a command case-insensitively.
+    ///
+    /// # Example
+    /// ```
+    /// use config::control::ControlCommand;
+    ///
+    /// assert_eq!("pause".parse(), Ok(ControlCommand::Pause));
+    /// assert_eq!("Resume".parse(), Ok(ControlCommand::Resume));
+    /// assert!("restart".parse::<ControlCommand>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "pause" => Ok(ControlCommand::Pause),
+            "resume" => Ok(ControlCommand::Resume),
+            "status" => Ok(ControlCommand::Status),
+            "stop" => Ok(ControlCommand::Stop),
+            _ => Err(format!(
+                "Unknown command {:?}. Expected one of \"pause\", \"resume\", \"status\" or \"stop\"",
+                s.trim()
+            )),
+        }
+    }
+}
+
+/// State of a running backup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupState {
+    Running,
+    /// Idling until it is resumed
+    Paused,
+    /// Finishing its archive after it was stopped or cancelled
+    Stopping,
+}
+
+/// Answer of a running backup to a [ControlCommand], describing its state after the command was applied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControlStatus {
+    pub profile_uuid: Uuid,
+    /// PID of the backupper
+    pub pid: u32,
+    pub state: BackupState,
+}
+
+/// Returns the path of the control channel of the backup of the profile with the given [Uuid]:
+/// a named pipe on Windows and a unix socket in the temp dir elsewhere.
+pub fn control_endpoint(uuid: &Uuid) -> PathBuf {
+    let name = format!("backup-rs-{}", uuid.as_hyphenated());
+    if cfg!(windows) {
+        PathBuf::from(format!(r"\\.\pipe\{}", name))
+    } else {
+        std::env::temp_dir().join(format!("{}.sock", name))
+    }
+}
+
+/// Sends `command` to the running backup of the profile with the given [Uuid] and returns its answer.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if no backup of the profile is running, it couldn't be reached or rejected the command.
+pub fn send_command(uuid: &Uuid, command: ControlCommand) -> Result<ControlStatus, String> {
+    let endpoint = control_endpoint(uuid);
+    let not_running = |e: std::io::Error| match e.kind() {
+        ErrorKind::NotFound | ErrorKind::ConnectionRefused => format!("No backup of {} is running", uuid),
+        _ => format!("Couldn't reach the backup of {} because of {:?}", uuid, e),
+    };
+    #[cfg(unix)]
+    let mut stream = {
+        let stream = std::os::unix::net::UnixStream::connect(&endpoint).map_err(not_running)?;
+        // a backupper that doesn't answer mustn't block the tray or the server
+        let timeout = Some(std::time::Duration::from_secs(5));
+        stream
+            .set_read_timeout(timeout)
+            .and_then(|_| stream.set_write_timeout(timeout))
+            .map_err(|e| format!("Couldn't reach the backup of {} because of {:?}", uuid, e))?;
+        stream
+    };
+    #[cfg(not(unix))]
+    let mut stream = {
+        /// Error of opening a named pipe whose instances are all connected to other clients
+        const ERROR_PIPE_BUSY: i32 = 231;
+        let mut attempts = 0;
+        loop {
+            match std::fs::OpenOptions::new().read(true).write(true).open(&endpoint) {
+                // the backupper creates the next instance right after answering the previous client
+                Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) && attempts < 20 => {
+                    attempts += 1;
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                }
+                opened => break opened.map_err(not_running)?,
+            }
+        }
+    };
+
+    writeln!(stream, "{}", command).map_err(|e| format!("Couldn't send {:?} to the backup of {} because of {:?}", command, uuid, e))?;
+    let mut answer = String::new();
+    BufReader::new(&mut stream)
+        .read_line(&mut answer)
+        .map_err(|e| format!("Couldn't read the answer of the backup of {} because of {:?}", uuid, e))?;
+    parse_answer(&answer)
+}
+
+/// Parses the `answer` of a backup to a command (see [ERROR_PREFIX]).
+fn parse_answer(answer: &str) -> Result<ControlStatus, String> {
+    if let Some(msg) = answer.strip_prefix(ERROR_PREFIX) {
+        return Err(msg.trim().to_string());
+    }
+    serde_json::from_str(answer.trim()).map_err(|e| format!("Couldn't parse the answer {:?} because of {}", answer.trim(), e))
+}
+
+#[cfg(test)]
+mod control_tests {
+    use super::*;
+
+    #[test]
+    fn commands_roundtrip() {
+        for command in [ControlCommand::Pause, ControlCommand::Resume, ControlCommand::Status, ControlCommand::Stop] {
+            assert_eq!(command.to_string().parse(), Ok(command));
+        }
+        assert_eq!(" STOP\n".parse(), Ok(ControlCommand::Stop));
+    }
+
+    #[test]
+    fn answers() {
+        let status = ControlStatus {
+            profile_uuid: Uuid::new_v4(),
+            pid: 42,
+            state: BackupState::Paused,
+        };
+        let answer = format!("{}\n", serde_json::to_string(&status).unwrap());
+        assert!(answer.contains("\"state\":\"paused\""));
+        assert_eq!(parse_answer(&answer), Ok(status));
+        assert_eq!(parse_answer("error: Unknown command\n"), Err(String::from("Unknown command")));
+        assert!(parse_answer("").is_err());
+    }
+
+    #[test]
+    fn endpoints_differ_by_profile() {
+        let uuid = Uuid::new_v4();
+        let endpoint = control_endpoint(&uuid);
+        assert!(endpoint.to_string_lossy().contains(&uuid.as_hyphenated().to_string()));
+        assert_ne!(endpoint, control_endpoint(&Uuid::new_v4()));
+    }
+
+    #[test]
+    fn nothing_running() {
+        let uuid = Uuid::new_v4();
+        assert_eq!(send_command(&uuid, ControlCommand::Status), Err(format!("No backup of {} is running", uuid)));
+    }
+}
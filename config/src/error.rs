@@ -0,0 +1,49 @@
+//! Contains the [Error] returned when loading, storing or validating configs fails.
+
+use std::io;
+
+use thiserror::Error;
+
+/// Describes why loading, storing or validating a config failed.
+/// The messages are meant to be shown to the user, the variants to decide how to react (e.g., which exit code or HTTP status to use).
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The config file couldn't be read or written
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// The content of a config couldn't be parsed or serialized
+    #[error("{0}")]
+    Parse(String),
+    /// The values of an [Interval](crate::Interval) are out of range or don't fit together
+    #[error("{0}")]
+    InvalidInterval(String),
+    /// A [Specifier](crate::Specifier) of an [Interval](crate::Interval) doesn't cover the range of its field
+    #[error("{0}")]
+    InvalidSpecifierRange(String),
+    /// The config was written by a newer version and mustn't be modified (see [ProfileConfig::is_read_only](crate::ProfileConfig::is_read_only))
+    #[error("{0}")]
+    ReadOnly(String),
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Parse(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod error_tests {
+    use std::io::ErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn display() {
+        let err = Error::from(io::Error::new(ErrorKind::NotFound, "no such file"));
+        assert_eq!(err.to_string(), "no such file");
+        assert_eq!(Error::InvalidInterval(String::from("hutzi")).to_string(), "hutzi");
+
+        let err = Error::from(serde_json::from_str::<u32>("hutzi").unwrap_err());
+        assert!(matches!(err, Error::Parse(_)));
+    }
+}
@@ -0,0 +1,193 @@
+//! Contains functions for estimating when a target volume fills up, based on the free space recorded during previous backups.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// Default number of most recent samples considered by [FreeSpaceTrend::analyze]
+pub const DEFAULT_TREND_SAMPLES: usize = 10;
+
+/// Free space of a target volume at a given time, e.g., recorded in a history entry.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct FreeSpaceSample {
+    /// When the free space was measured
+    pub datetime: NaiveDateTime,
+    /// Free space on the volume in bytes
+    pub free_bytes: u64,
+}
+
+/// Linear trend of the free space of a target volume.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct FreeSpaceTrend {
+    /// Change of the free space in bytes per day. Negative if the volume fills up.
+    pub bytes_per_day: f64,
+    /// Free space in bytes according to the most recent sample
+    pub free_bytes: u64,
+    /// Estimated number of days from the most recent sample until the volume is full.
+    /// [None] if the free space doesn't shrink.
+    pub days_until_full: Option<f64>,
+}
+
+impl FreeSpaceTrend {
+    /// Fits a linear trend (least squares) over the `max_samples` most recent `samples`.
+    ///
+    /// The samples may come from multiple profiles sharing the same volume and don't have to be sorted.
+    /// That way, the consumption of all these profiles is aggregated.
+    ///
+    /// # Returns
+    /// [Some] containing the [FreeSpaceTrend]. [None] if less than 2 samples are considered or all of them were taken at the same time.
+    ///
+    /// # Example
+    /// ```
+    /// use config::free_space::*;
+    /// use chrono::NaiveDate;
+    ///
+    /// let sample = |day, free_bytes| FreeSpaceSample {
+    ///     datetime: NaiveDate::from_ymd_opt(2023, 5, day).unwrap().and_hms_opt(12, 0, 0).unwrap(),
+    ///     free_bytes,
+    /// };
+    /// let samples = [sample(1, 1000), sample(2, 900), sample(3, 800)];
+    ///
+    /// let trend = FreeSpaceTrend::analyze(&samples, DEFAULT_TREND_SAMPLES).unwrap();
+    /// assert_eq!(trend.bytes_per_day, -100.0);
+    /// assert_eq!(trend.days_until_full, Some(8.0));
+    /// ```
+    pub fn analyze(samples: &[FreeSpaceSample], max_samples: usize) -> Option<FreeSpaceTrend> {
+        let mut samples = samples.to_vec();
+        samples.sort_by_key(|sample| sample.datetime);
+        let samples = &samples[samples.len().saturating_sub(max_samples)..];
+        if samples.len() < 2 {
+            return None;
+        }
+
+        let first = samples[0].datetime;
+        let points: Vec<(f64, f64)> = samples
+            .iter()
+            .map(|sample| {
+                let days = (sample.datetime - first).num_seconds() as f64 / 86400.0;
+                (days, sample.free_bytes as f64)
+            })
+            .collect();
+
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let covariance: f64 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        if variance == 0.0 {
+            return None;
+        }
+
+        let bytes_per_day = covariance / variance;
+        let latest = samples[samples.len() - 1];
+        let days_until_full = if bytes_per_day < 0.0 {
+            Some(latest.free_bytes as f64 / -bytes_per_day)
+        } else {
+            None
+        };
+
+        Some(FreeSpaceTrend {
+            bytes_per_day,
+            free_bytes: latest.free_bytes,
+            days_until_full,
+        })
+    }
+
+    /// Checks if the volume is estimated to be full within `threshold_days`.
+    pub fn fills_up_within(&self, threshold_days: u32) -> bool {
+        match self.days_until_full {
+            Some(days) => days <= threshold_days as f64,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod free_space_tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn sample(day: u32, hour: u32, free_bytes: u64) -> FreeSpaceSample {
+        FreeSpaceSample {
+            datetime: NaiveDate::from_ymd_opt(2023, 5, day)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap(),
+            free_bytes,
+        }
+    }
+
+    #[test]
+    fn not_enough_samples() {
+        assert_eq!(FreeSpaceTrend::analyze(&[], 10), None);
+        assert_eq!(FreeSpaceTrend::analyze(&[sample(1, 0, 100)], 10), None);
+        assert_eq!(
+            FreeSpaceTrend::analyze(&[sample(1, 0, 100), sample(2, 0, 50)], 1),
+            None
+        );
+        assert_eq!(
+            FreeSpaceTrend::analyze(&[sample(1, 0, 100), sample(1, 0, 50)], 10),
+            None
+        );
+    }
+
+    #[test]
+    fn shrinking() {
+        let samples: Vec<FreeSpaceSample> = (1..=5)
+            .map(|day| sample(day, 0, 10_000 - 500 * day as u64))
+            .collect();
+        let trend = FreeSpaceTrend::analyze(&samples, 10).unwrap();
+        assert!((trend.bytes_per_day + 500.0).abs() < 1e-6);
+        assert_eq!(trend.free_bytes, 7500);
+        assert!((trend.days_until_full.unwrap() - 15.0).abs() < 1e-6);
+        assert!(trend.fills_up_within(15));
+        assert!(!trend.fills_up_within(14));
+    }
+
+    #[test]
+    fn growing_or_constant() {
+        let trend =
+            FreeSpaceTrend::analyze(&[sample(1, 0, 100), sample(2, 0, 200)], 10).unwrap();
+        assert_eq!(trend.days_until_full, None);
+        assert!(!trend.fills_up_within(u32::MAX));
+
+        let trend =
+            FreeSpaceTrend::analyze(&[sample(1, 0, 100), sample(2, 0, 100)], 10).unwrap();
+        assert_eq!(trend.bytes_per_day, 0.0);
+        assert_eq!(trend.days_until_full, None);
+    }
+
+    #[test]
+    fn only_recent_samples() {
+        // the space was freed up on day 3; afterwards it shrinks slowly
+        let samples = [
+            sample(1, 0, 100),
+            sample(2, 0, 50),
+            sample(3, 0, 1000),
+            sample(4, 0, 990),
+            sample(5, 0, 980),
+        ];
+        let trend = FreeSpaceTrend::analyze(&samples, 3).unwrap();
+        assert!((trend.bytes_per_day + 10.0).abs() < 1e-6);
+        assert!((trend.days_until_full.unwrap() - 98.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn aggregates_unsorted_samples_of_multiple_profiles() {
+        // two profiles backing up to the same volume, each consuming 100 bytes per run
+        let profile_a = [sample(1, 0, 1000), sample(2, 0, 800), sample(3, 0, 600)];
+        let profile_b = [sample(1, 12, 900), sample(2, 12, 700), sample(3, 12, 500)];
+        let samples: Vec<FreeSpaceSample> = profile_b
+            .iter()
+            .chain(profile_a.iter())
+            .copied()
+            .collect();
+
+        let trend = FreeSpaceTrend::analyze(&samples, 10).unwrap();
+        assert!((trend.bytes_per_day + 200.0).abs() < 1e-6);
+        assert_eq!(trend.free_bytes, 500);
+    }
+}
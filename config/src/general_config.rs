@@ -1,50 +1,239 @@
 //! Contains structs and functions for the general program configuration
 
+use chrono::{Duration, NaiveDateTime};
+use crate::{atomic_file, migration::{self, Migration}, Error};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::{
-    fs::{File, OpenOptions},
-    io::{BufReader, BufWriter, Error},
-    path::PathBuf,
+    fs,
+    path::{Path, PathBuf},
 };
 
+/// Default for `max_history_entries`
+pub const DEFAULT_MAX_HISTORY_ENTRIES: usize = 500;
+/// Default for `max_sidecar_logs`
+pub const DEFAULT_MAX_SIDECAR_LOGS: usize = 100;
+/// Default for `max_sidecar_age_days`
+pub const DEFAULT_MAX_SIDECAR_AGE_DAYS: u32 = 90;
+/// Default for `free_space_warning_days`
+pub const DEFAULT_FREE_SPACE_WARNING_DAYS: u32 = 14;
+/// Name of the default `profile_configs` dir, which lies next to the general config file
+pub const DEFAULT_PROFILE_CONFIGS_DIR: &str = "profile_configs";
+
+/// Current schema version of the stored [GeneralConfig]
+pub const GENERAL_CONFIG_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Migrations of the stored [GeneralConfig]. The migration at index `n` migrates from version `n` to `n + 1`.
+const MIGRATIONS: [Migration; 1] = [add_default_limits];
+
+/// Version 1 writes the default limits into configs that didn't set them, so they can be seen and changed in the file.
+fn add_default_limits(fields: &mut Map<String, Value>) {
+    fields
+        .entry("max_history_entries")
+        .or_insert(Value::from(DEFAULT_MAX_HISTORY_ENTRIES));
+    fields
+        .entry("max_sidecar_logs")
+        .or_insert(Value::from(DEFAULT_MAX_SIDECAR_LOGS));
+    fields
+        .entry("max_sidecar_age_days")
+        .or_insert(Value::from(DEFAULT_MAX_SIDECAR_AGE_DAYS));
+    fields
+        .entry("free_space_warning_days")
+        .or_insert(Value::from(DEFAULT_FREE_SPACE_WARNING_DAYS));
+}
+
 /// Class containing general configuration.
 /// Can read general configuration from a given file and store it in a given file.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GeneralConfig {
     /// Specifies the path to the directory in which the `.json` files for the profiles can be found.
     pub profile_configs: PathBuf,
+    /// Maximum number of history entries kept per profile. [None] means no limit.
+    #[serde(default = "default_max_history_entries")]
+    pub max_history_entries: Option<usize>,
+    /// Maximum number of sidecar logs kept per profile. [None] means no limit.
+    #[serde(default = "default_max_sidecar_logs")]
+    pub max_sidecar_logs: Option<usize>,
+    /// Maximum age of sidecar logs in days. [None] means no limit.
+    #[serde(default = "default_max_sidecar_age_days")]
+    pub max_sidecar_age_days: Option<u32>,
+    /// Warn if a target volume is estimated to be full within that many days (see [FreeSpaceTrend](crate::free_space::FreeSpaceTrend)). [None] disables the warning.
+    #[serde(default = "default_free_space_warning_days")]
+    pub free_space_warning_days: Option<u32>,
+    /// Target dir of newly created profiles. [None] leaves it empty, so it has to be set before the first backup.
+    #[serde(default)]
+    pub default_target_dir: Option<PathBuf>,
+    /// Whether dialogs are shown, e.g., to ask for attaching the backup drive. Disable it on headless machines, where nobody could answer them.
+    #[serde(default = "default_show_dialogs")]
+    pub show_dialogs: bool,
+    /// Maximum level that is logged (`off`, `error`, `warn`, `info`, `debug` or `trace`). [None] keeps the levels of the logger config.
+    /// Levels the logger config doesn't log aren't logged either way.
+    #[serde(default)]
+    pub log_level: Option<String>,
+    /// Schema version of the stored config (see [GENERAL_CONFIG_VERSION]). Files of older versions are migrated by [GeneralConfig::read].
+    #[serde(default)]
+    pub config_version: u32,
+}
+
+fn default_max_history_entries() -> Option<usize> {
+    Some(DEFAULT_MAX_HISTORY_ENTRIES)
+}
+
+fn default_max_sidecar_logs() -> Option<usize> {
+    Some(DEFAULT_MAX_SIDECAR_LOGS)
+}
+
+fn default_max_sidecar_age_days() -> Option<u32> {
+    Some(DEFAULT_MAX_SIDECAR_AGE_DAYS)
+}
+
+fn default_free_space_warning_days() -> Option<u32> {
+    Some(DEFAULT_FREE_SPACE_WARNING_DAYS)
+}
+
+fn default_show_dialogs() -> bool {
+    true
+}
+
+impl Default for GeneralConfig {
+    /// Stores the profiles in [DEFAULT_PROFILE_CONFIGS_DIR] relative to the working directory. All other fields have their serde defaults.
+    fn default() -> Self {
+        GeneralConfig {
+            profile_configs: PathBuf::from(DEFAULT_PROFILE_CONFIGS_DIR),
+            max_history_entries: default_max_history_entries(),
+            max_sidecar_logs: default_max_sidecar_logs(),
+            max_sidecar_age_days: default_max_sidecar_age_days(),
+            free_space_warning_days: default_free_space_warning_days(),
+            default_target_dir: None,
+            show_dialogs: default_show_dialogs(),
+            log_level: None,
+            config_version: GENERAL_CONFIG_VERSION,
+        }
+    }
 }
 
 impl GeneralConfig {
     /// Reads general configuration from the file at the provided path.
+    ///
+    /// Files of an older `config_version` are migrated to [GENERAL_CONFIG_VERSION] and rewritten in place.
+    /// If rewriting fails, the migrated config is returned anyway.
     /// 
     /// # Returns
-    /// [Ok] containing a [GeneralConfig] instance or an [Error]
-    pub fn read(global_config_file: &PathBuf) -> Result<GeneralConfig, Error> {
-        let file = File::open(global_config_file)?;
-        let reader = BufReader::new(file);
+    /// [Ok] containing a [GeneralConfig] instance, [Error::Io] if the file can't be read or [Error::Parse] if its content isn't a valid [GeneralConfig]
+    pub fn read(global_config_file: &Path) -> Result<GeneralConfig, Error> {
+        let content = fs::read_to_string(global_config_file)?;
 
-        let config = serde_json::from_reader(reader)?;
+        let mut value: Value = serde_json::from_str(&content)?;
+        let migrated = migration::migrate(&mut value, &MIGRATIONS)?;
+        let config: GeneralConfig = serde_json::from_value(value)?;
+        if migrated {
+            let _ = config.store(global_config_file);
+        }
 
         Ok(config)
     }
 
+    /// Stores the [GeneralConfig::default] at `global_config_file` and creates its `profile_configs` dir, which is placed next to the file.
+    /// An existing `profile_configs` dir is kept as it is.
+    ///
+    /// # Returns
+    /// [Ok] containing the created [GeneralConfig] and the paths of the created files and directories or [Error::Io] if something couldn't be written
+    pub fn create_default(global_config_file: &Path) -> Result<(GeneralConfig, Vec<PathBuf>), Error> {
+        let mut config = GeneralConfig::default();
+        if let Some(parent) = global_config_file.parent() {
+            config.profile_configs = parent.join(DEFAULT_PROFILE_CONFIGS_DIR);
+        }
+
+        let mut created = vec![];
+        if !config.profile_configs.exists() {
+            fs::create_dir_all(&config.profile_configs)?;
+            created.push(config.profile_configs.clone());
+        }
+        config.store(global_config_file)?;
+        created.push(global_config_file.to_path_buf());
+        Ok((config, created))
+    }
+
     /// Stores general configuration from the file at the provided path.
-    /// Will create a file, if it doesn't exist yet.
+    /// Will create a file, if it doesn't exist yet, and replaces an existing one atomically, so no trailing bytes of a longer old config remain.
+    /// The stored `config_version` is set to [GENERAL_CONFIG_VERSION].
     /// 
     /// # Returns
-    /// [Ok] containing a [GeneralConfig] instance or an [Error]
-    pub fn store(&self, global_config_file: &PathBuf) -> Result<(), Error> {
-        let file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(global_config_file)?;
-        let writer = BufWriter::new(file);
-
-        match serde_json::to_writer_pretty(writer, self) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
-        }
+    /// [Ok] or [Error::Io] if the file can't be written
+    pub fn store(&self, global_config_file: &Path) -> Result<(), Error> {
+        let mut config = self.clone();
+        config.config_version = GENERAL_CONFIG_VERSION;
+        Ok(atomic_file::write_json(global_config_file, &config)?)
+    }
+
+    /// Returns the paths of the program's own files that are known from this configuration, i.e., the given `general_config_file` and the `profile_configs` dir.
+    /// The paths of the log files have to be added by the caller, since they are configured separately.
+    pub fn own_paths(&self, general_config_file: &Path) -> Vec<PathBuf> {
+        vec![general_config_file.to_path_buf(), self.profile_configs.clone()]
+    }
+
+    /// Drops the oldest entries from `history` until it contains at most `max_history_entries` entries that are not pinned.
+    /// Entries for which `is_pinned` returns `true` are never dropped and don't count towards the limit.
+    ///
+    /// `history` is expected to be sorted from oldest to newest. The order of the remaining entries is preserved.
+    pub fn trim_history<T>(&self, history: &mut Vec<T>, is_pinned: impl Fn(&T) -> bool) {
+        let max = match self.max_history_entries {
+            Some(max) => max,
+            None => return,
+        };
+
+        let unpinned = history.iter().filter(|entry| !is_pinned(entry)).count();
+        let mut to_drop = unpinned.saturating_sub(max);
+        history.retain(|entry| {
+            if to_drop > 0 && !is_pinned(entry) {
+                to_drop -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// Determines which of the given sidecar logs should be removed.
+    /// Logs older than `max_sidecar_age_days` are removed. Additionally the oldest logs are removed until at most `max_sidecar_logs` are left.
+    /// Logs for which `is_pinned` returns `true` are never removed and don't count towards the limit.
+    ///
+    /// # Returns
+    /// The logs to remove, sorted from oldest to newest.
+    pub fn sidecar_logs_to_remove<T>(
+        &self,
+        mut logs: Vec<T>,
+        now: NaiveDateTime,
+        created: impl Fn(&T) -> NaiveDateTime,
+        is_pinned: impl Fn(&T) -> bool,
+    ) -> Vec<T> {
+        logs.sort_by_key(|log| created(log));
+
+        let unpinned = logs.iter().filter(|log| !is_pinned(log)).count();
+        let mut to_drop = match self.max_sidecar_logs {
+            Some(max) => unpinned.saturating_sub(max),
+            None => 0,
+        };
+        let oldest_allowed = self
+            .max_sidecar_age_days
+            .map(|days| now - Duration::days(days as i64));
+
+        logs.into_iter()
+            .filter(|log| {
+                if is_pinned(log) {
+                    return false;
+                }
+                let expired = match oldest_allowed {
+                    Some(oldest_allowed) => created(log) < oldest_allowed,
+                    None => false,
+                };
+                if to_drop > 0 {
+                    to_drop -= 1;
+                    return true;
+                }
+                expired
+            })
+            .collect()
     }
 }
 
@@ -65,9 +254,53 @@ mod global_config_tests {
         let tmp_file = PathBuf::from("test_tmp/store_test.json");
         let config = GeneralConfig {
             profile_configs: PathBuf::from("test"),
+            max_history_entries: None,
+            max_sidecar_logs: None,
+            max_sidecar_age_days: None,
+            free_space_warning_days: None,
+            default_target_dir: Some(PathBuf::from("E:/backups")),
+            show_dialogs: false,
+            log_level: Some(String::from("warn")),
+            config_version: 0,
+        };
+        config.store(&tmp_file)?;
+        let stored: Value = serde_json::from_str(&fs::read_to_string(&tmp_file)?)?;
+        let read = GeneralConfig::read(&tmp_file)?;
+        delete_file(tmp_file);
+        assert_eq!(stored["config_version"], Value::from(GENERAL_CONFIG_VERSION));
+        assert_eq!(read.default_target_dir, Some(PathBuf::from("E:/backups")));
+        assert!(!read.show_dialogs);
+        assert_eq!(read.log_level, Some(String::from("warn")));
+        Ok(())
+    }
+
+    #[test]
+    fn store_shorter_config() -> Result<(), Error> {
+        let tmp_file = PathBuf::from("test_tmp/store_shorter_test.json");
+        let mut config = GeneralConfig {
+            profile_configs: PathBuf::from("a/very/long/path/to/the/profile/configs/of/this/machine"),
+            max_history_entries: Some(1000),
+            max_sidecar_logs: Some(1000),
+            max_sidecar_age_days: Some(1000),
+            free_space_warning_days: Some(1000),
+            default_target_dir: Some(PathBuf::from("a/very/long/path/to/the/default/target/dir")),
+            show_dialogs: true,
+            log_level: Some(String::from("debug")),
+            config_version: GENERAL_CONFIG_VERSION,
         };
         config.store(&tmp_file)?;
+
+        config.profile_configs = PathBuf::from("p");
+        config.default_target_dir = None;
+        config.log_level = None;
+        config.store(&tmp_file)?;
+        let read = GeneralConfig::read(&tmp_file);
         delete_file(tmp_file);
+
+        let read = read?;
+        assert_eq!(read.profile_configs, PathBuf::from("p"));
+        assert_eq!(read.default_target_dir, None);
+        assert_eq!(read.log_level, None);
         Ok(())
     }
 
@@ -76,6 +309,67 @@ mod global_config_tests {
         let tmp_file = PathBuf::from("test_tmp/read_test.json");
         let config = GeneralConfig::read(&tmp_file)?;
         assert_eq!(config.profile_configs, PathBuf::from("test"));
+        assert_eq!(config.max_history_entries, Some(DEFAULT_MAX_HISTORY_ENTRIES));
+        assert_eq!(config.max_sidecar_logs, Some(DEFAULT_MAX_SIDECAR_LOGS));
+        assert_eq!(config.max_sidecar_age_days, Some(DEFAULT_MAX_SIDECAR_AGE_DAYS));
+        assert_eq!(config.free_space_warning_days, Some(DEFAULT_FREE_SPACE_WARNING_DAYS));
+        assert_eq!(config.default_target_dir, None);
+        assert!(config.show_dialogs);
+        assert_eq!(config.log_level, None);
+        Ok(())
+    }
+
+    #[test]
+    fn own_paths() -> Result<(), Error> {
+        let tmp_file = PathBuf::from("test_tmp/read_test.json");
+        let config = GeneralConfig::read(&tmp_file)?;
+        assert_eq!(
+            config.own_paths(&tmp_file),
+            vec![tmp_file.clone(), PathBuf::from("test")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_migrates_version_0() -> Result<(), Error> {
+        // copy the fixture, so migrating it doesn't change the fixture itself
+        let tmp_file = PathBuf::from("test_tmp/migrated_general_config_tmp.json");
+        fs::copy("test_tmp/migration/general_config_v0.json", &tmp_file)?;
+        let config = GeneralConfig::read(&tmp_file)?;
+        let stored: Value = serde_json::from_str(&fs::read_to_string(&tmp_file)?)?;
+        delete_file(tmp_file);
+
+        assert_eq!(config.config_version, GENERAL_CONFIG_VERSION);
+        assert_eq!(config.max_history_entries, Some(20));
+        assert_eq!(config.max_sidecar_logs, Some(DEFAULT_MAX_SIDECAR_LOGS));
+        assert_eq!(stored["config_version"], Value::from(GENERAL_CONFIG_VERSION));
+        assert_eq!(stored["max_history_entries"], Value::from(20));
+        assert_eq!(stored["free_space_warning_days"], Value::from(DEFAULT_FREE_SPACE_WARNING_DAYS));
+        Ok(())
+    }
+
+    #[test]
+    fn create_default() -> Result<(), Error> {
+        let dir = PathBuf::from("test_tmp/create_default_tmp");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        let file = dir.join("general_config.json");
+
+        let (config, created) = GeneralConfig::create_default(&file)?;
+        let file_exists = file.is_file();
+        let dir_exists = dir.join(DEFAULT_PROFILE_CONFIGS_DIR).is_dir();
+        let stored = GeneralConfig::read(&file);
+        fs::remove_dir_all(&dir)?;
+
+        assert!(file_exists);
+        assert!(dir_exists);
+        assert_eq!(config.profile_configs, dir.join(DEFAULT_PROFILE_CONFIGS_DIR));
+        assert_eq!(created, vec![dir.join(DEFAULT_PROFILE_CONFIGS_DIR), file]);
+        let stored = stored?;
+        assert_eq!(stored.profile_configs, config.profile_configs);
+        assert_eq!(stored.max_history_entries, Some(DEFAULT_MAX_HISTORY_ENTRIES));
+        assert!(stored.show_dialogs);
+        assert_eq!(stored.config_version, GENERAL_CONFIG_VERSION);
         Ok(())
     }
 
@@ -83,6 +377,93 @@ mod global_config_tests {
     fn read_non_existing() {
         let file = PathBuf::from("Non-existing.abc");
         let config = GeneralConfig::read(&file);
-        assert!(config.is_err());
+        assert!(matches!(config, Err(Error::Io(_))));
+    }
+
+    mod trim_tests {
+        use super::*;
+        use chrono::NaiveDate;
+
+        fn limited_config(
+            max_history_entries: Option<usize>,
+            max_sidecar_logs: Option<usize>,
+            max_sidecar_age_days: Option<u32>,
+        ) -> GeneralConfig {
+            GeneralConfig {
+                profile_configs: PathBuf::from("test"),
+                max_history_entries,
+                max_sidecar_logs,
+                max_sidecar_age_days,
+                free_space_warning_days: None,
+                default_target_dir: None,
+                show_dialogs: true,
+                log_level: None,
+                config_version: GENERAL_CONFIG_VERSION,
+            }
+        }
+
+        fn day(day: u32) -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(2023, 1, day)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        }
+
+        #[test]
+        fn history_unlimited() {
+            let config = limited_config(None, None, None);
+            let mut history: Vec<u32> = (0..10).collect();
+            config.trim_history(&mut history, |_| false);
+            assert_eq!(history.len(), 10);
+        }
+
+        #[test]
+        fn history_drops_oldest() {
+            let config = limited_config(Some(3), None, None);
+            let mut history: Vec<u32> = (0..10).collect();
+            config.trim_history(&mut history, |_| false);
+            assert_eq!(history, vec![7, 8, 9]);
+
+            let mut history: Vec<u32> = (0..2).collect();
+            config.trim_history(&mut history, |_| false);
+            assert_eq!(history, vec![0, 1]);
+        }
+
+        #[test]
+        fn history_keeps_pinned() {
+            let config = limited_config(Some(3), None, None);
+            let mut history: Vec<u32> = (0..10).collect();
+            config.trim_history(&mut history, |entry| entry == &1 || entry == &4);
+            assert_eq!(history, vec![1, 4, 7, 8, 9]);
+
+            let mut history: Vec<u32> = (0..5).collect();
+            config.trim_history(&mut history, |_| true);
+            assert_eq!(history, vec![0, 1, 2, 3, 4]);
+        }
+
+        #[test]
+        fn sidecar_logs_by_count() {
+            let config = limited_config(None, Some(2), None);
+            let logs: Vec<u32> = vec![5, 1, 3, 4, 2];
+            let removed = config.sidecar_logs_to_remove(logs, day(10), |log| day(*log), |_| false);
+            assert_eq!(removed, vec![1, 2, 3]);
+        }
+
+        #[test]
+        fn sidecar_logs_by_age() {
+            let config = limited_config(None, None, Some(5));
+            let logs: Vec<u32> = (1..=10).collect();
+            let removed = config.sidecar_logs_to_remove(logs, day(10), |log| day(*log), |_| false);
+            assert_eq!(removed, vec![1, 2, 3, 4]);
+        }
+
+        #[test]
+        fn sidecar_logs_keep_pinned() {
+            let config = limited_config(None, Some(2), Some(5));
+            let logs: Vec<u32> = (1..=10).collect();
+            let removed =
+                config.sidecar_logs_to_remove(logs, day(10), |log| day(*log), |log| log == &2 || log == &9);
+            assert_eq!(removed, vec![1, 3, 4, 5, 6, 7]);
+        }
     }
 }
@@ -2,8 +2,11 @@
 
 use std::ops::RangeInclusive;
 
+mod cron;
 mod date_time_match;
+mod explanation;
 mod months;
+mod preset;
 mod specifier;
 mod weekdays;
 
@@ -11,10 +14,14 @@ use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
+use crate::Error;
+
 pub use self::{
     date_time_match::DateTimeMatch,
+    explanation::{DayRule, FieldExplanation, MatchExplanation},
     months::Month,
-    specifier::{Specifier, SpecifierKind},
+    preset::IntervalPreset,
+    specifier::{NamedValue, Specifier, SpecifierKind},
     weekdays::Weekday,
 };
 
@@ -24,7 +31,7 @@ pub use self::{
 /// If weekdays and monthdays are both not [SpecifierKind::All], then only one of them has to match.
 ///
 /// Weeknumbers are handles as [ISO-Weeks](https://en.wikipedia.org/wiki/ISO_week_date). I.e., the first week with 4 days or mor in a year is week 0.
-#[derive(Debug, PartialEq, Builder, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Builder, Serialize, Deserialize)]
 pub struct Interval {
     /// Range 0-59
     #[builder(
@@ -67,8 +74,25 @@ pub struct Interval {
         setter(custom)
     )]
     pub months: Specifier<months::Month>,
+
+    /// Range 0-4. Restricts the weekdays to their `n`-th occurrence in the month.
+    /// E.g., weekdays `Nth(0)` with `nth_weekday == Some(0)` matches the first Monday of every month.
+    #[builder(default, setter(custom))]
+    #[serde(default)]
+    pub nth_weekday: Option<u32>,
+
+    /// Range 0-59. Intervals without seconds only match at the start of a minute.
+    #[builder(default, setter(custom))]
+    #[serde(default)]
+    pub seconds: Option<Specifier<u32>>,
+
+    /// Dates that are never matched, regardless of the [Specifier]s (e.g., public holidays)
+    #[builder(default, setter(custom))]
+    #[serde(default)]
+    pub excluded_dates: Vec<NaiveDate>,
 }
 
+const SECONDS_RANGE: RangeInclusive<u32> = 0..=59;
 const MINUTES_RANGE: RangeInclusive<u32> = 0..=59;
 const HOURS_RANGE: RangeInclusive<u32> = 0..=23;
 const WEEKDAYS_RANGE: RangeInclusive<weekdays::Weekday> =
@@ -77,25 +101,20 @@ const MONTHDAYS_RANGE: RangeInclusive<u32> = 0..=31;
 const WEEKS_RANGE: RangeInclusive<u32> = 0..=52;
 const MONTHS_RANGE: RangeInclusive<months::Month> =
     months::Month::January()..=months::Month::December();
+const NTH_WEEKDAY_RANGE: RangeInclusive<u32> = 0..=4;
+/// Largest number of days of each month (in a leap year)
+const MONTH_LENGTHS: [u32; 12] = [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+/// Number of days after which the gregorian calendar repeats itself (including weekdays and weeks)
+const GREGORIAN_CYCLE_DAYS: u64 = 146097;
 
 impl Interval {
     /// Creates an [Interval] that specifies the given time on every day.
     ///
     /// # Returns
     /// [Ok] containig the corresponding [Interval] or [Err] descibing the issue, when `minute` or `hour` don't have meaningful values (e.g., `minute == 100`).
-    pub fn daily(minute: u32, hour: u32) -> Result<Interval, String> {
-        if !MINUTES_RANGE.contains(&minute) {
-            return Err(format!(
-                "Expect 'minute' to be in range {:?}. Got {}",
-                MINUTES_RANGE, minute
-            ));
-        }
-        if !HOURS_RANGE.contains(&hour) {
-            return Err(format!(
-                "Expect 'hour' to be in range {:?}. Got {}",
-                HOURS_RANGE, hour
-            ));
-        }
+    pub fn daily(minute: u32, hour: u32) -> Result<Interval, Error> {
+        Self::check_in_range("minute", &MINUTES_RANGE, minute)?;
+        Self::check_in_range("hour", &HOURS_RANGE, hour)?;
 
         let minutes_spec = SpecifierKind::Nth(minute);
         let hours_spec = SpecifierKind::Nth(hour);
@@ -106,16 +125,119 @@ impl Interval {
             .build()
         {
             Ok(interval) => Ok(interval),
-            Err(err) => Err(err.to_string()),
+            Err(err) => Err(Error::InvalidInterval(err.to_string())),
         }
     }
 
+    /// Creates an [Interval] that specifies the given time on the given `weekday` of every week.
+    ///
+    /// # Returns
+    /// [Ok] containig the corresponding [Interval] or [Err] descibing the issue, when `hour` or `minute` don't have meaningful values (e.g., `minute == 100`).
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    /// use chrono::NaiveDate;
+    ///
+    /// let weekly = Interval::weekly(Weekday::Friday(), 18, 30).unwrap();
+    /// let friday = NaiveDate::from_ymd_opt(2023, 5, 5).unwrap();
+    /// assert_eq!(weekly.matches_datetime(friday.and_hms_opt(18, 30, 0).unwrap()), DateTimeMatch::Ok);
+    /// assert!(!weekly.matches_date(friday.succ_opt().unwrap()));
+    ///
+    /// assert!(Interval::weekly(Weekday::Friday(), 24, 0).is_err());
+    /// ```
+    pub fn weekly(weekday: Weekday, hour: u32, minute: u32) -> Result<Interval, Error> {
+        Self::check_in_range("minute", &MINUTES_RANGE, minute)?;
+        Self::check_in_range("hour", &HOURS_RANGE, hour)?;
+
+        IntervalBuilder::default()
+            .minutes(SpecifierKind::Nth(minute))
+            .hours(SpecifierKind::Nth(hour))
+            .weekdays(SpecifierKind::Nth(weekday.into()))
+            .build()
+            .map_err(|err| Error::InvalidInterval(err.to_string()))
+    }
+
+    /// Creates an [Interval] that specifies the given time on the given `monthday` (starting at 0, like [Interval::monthdays]) of every month.
+    ///
+    /// # Returns
+    /// [Ok] containig the corresponding [Interval] or [Err] descibing the issue, when `monthday`, `hour` or `minute` don't have meaningful values (e.g., `monthday == 40`).
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    /// use chrono::NaiveDate;
+    ///
+    /// let monthly = Interval::monthly(14, 6, 0).unwrap();
+    /// let the_15th = NaiveDate::from_ymd_opt(2023, 5, 15).unwrap();
+    /// assert_eq!(monthly.matches_datetime(the_15th.and_hms_opt(6, 0, 0).unwrap()), DateTimeMatch::Ok);
+    /// assert!(!monthly.matches_date(NaiveDate::from_ymd_opt(2023, 5, 14).unwrap()));
+    ///
+    /// assert!(Interval::monthly(32, 6, 0).is_err());
+    /// ```
+    pub fn monthly(monthday: u32, hour: u32, minute: u32) -> Result<Interval, Error> {
+        Self::check_in_range("monthday", &MONTHDAYS_RANGE, monthday)?;
+        Self::check_in_range("minute", &MINUTES_RANGE, minute)?;
+        Self::check_in_range("hour", &HOURS_RANGE, hour)?;
+
+        IntervalBuilder::default()
+            .minutes(SpecifierKind::Nth(minute))
+            .hours(SpecifierKind::Nth(hour))
+            .monthdays(SpecifierKind::Nth(monthday))
+            .build()
+            .map_err(|err| Error::InvalidInterval(err.to_string()))
+    }
+
+    /// Creates an [Interval] that specifies the given time on the given `monthday` (starting at 0, like [Interval::monthdays]) of `month` every year.
+    ///
+    /// # Returns
+    /// [Ok] containig the corresponding [Interval] or [Err] descibing the issue, when `monthday`, `hour` or `minute` don't have meaningful values (e.g., `hour == 30`).
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    /// use chrono::NaiveDate;
+    ///
+    /// let new_year = Interval::yearly(Month::January(), 0, 0, 0).unwrap();
+    /// let start = NaiveDate::from_ymd_opt(2023, 5, 6).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    /// assert_eq!(
+    ///     new_year.next_datetime(start),
+    ///     NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(0, 0, 0)
+    /// );
+    ///
+    /// assert!(Interval::yearly(Month::January(), 0, 30, 0).is_err());
+    /// ```
+    pub fn yearly(month: Month, monthday: u32, hour: u32, minute: u32) -> Result<Interval, Error> {
+        Self::check_in_range("monthday", &MONTHDAYS_RANGE, monthday)?;
+        Self::check_in_range("minute", &MINUTES_RANGE, minute)?;
+        Self::check_in_range("hour", &HOURS_RANGE, hour)?;
+
+        IntervalBuilder::default()
+            .minutes(SpecifierKind::Nth(minute))
+            .hours(SpecifierKind::Nth(hour))
+            .monthdays(SpecifierKind::Nth(monthday))
+            .months(SpecifierKind::Nth(month.into()))
+            .build()
+            .map_err(|err| Error::InvalidInterval(err.to_string()))
+    }
+
+    fn check_in_range(name: &str, range: &RangeInclusive<u32>, value: u32) -> Result<(), Error> {
+        if !range.contains(&value) {
+            return Err(Error::InvalidInterval(format!(
+                "Expect '{}' to be in range {:?}. Got {}",
+                name, range, value
+            )));
+        }
+        Ok(())
+    }
+
     /// Checks that the [Specifier]s all have the correct ranges.
     /// This is already enforced when using the [IntervalBuilder]. However, when deserializing a JSON there might be some wrong values.
     ///
     /// # Returns
     /// [Ok] if everything is alright.
     /// [Err] describing a missconfigured specifier. It will only report one specifier hat a time.
+    /// That is [Error::InvalidSpecifierRange] for a specifier not covering the range of its field and [Error::InvalidInterval] for an out of range `nth_weekday`.
     ///
     /// # Example
     /// ```
@@ -127,7 +249,10 @@ impl Interval {
     ///     weekdays: Specifier::new(Weekday::Monday(), Weekday::Sunday(), SpecifierKind::All),
     ///     monthdays: Specifier::new(0, 31, SpecifierKind::All),
     ///     weeks: Specifier::new(0, 52, SpecifierKind::All),
-    ///     months: Specifier::new(Month::January(), Month::December(), SpecifierKind::All)
+    ///     months: Specifier::new(Month::January(), Month::December(), SpecifierKind::All),
+    ///     nth_weekday: None,
+    ///     seconds: None,
+    ///     excluded_dates: vec![],
     /// };
     /// assert!(interval.validate().is_ok());
     ///
@@ -137,47 +262,162 @@ impl Interval {
     ///     weekdays: Specifier::new(Weekday::Monday(), Weekday::Sunday(), SpecifierKind::All),
     ///     monthdays: Specifier::new(0, 31, SpecifierKind::All),
     ///     weeks: Specifier::new(0, 52, SpecifierKind::All),
-    ///     months: Specifier::new(Month::January(), Month::December(), SpecifierKind::All)
+    ///     months: Specifier::new(Month::January(), Month::December(), SpecifierKind::All),
+    ///     nth_weekday: None,
+    ///     seconds: None,
+    ///     excluded_dates: vec![],
     /// };
     /// assert!(bad_interval.validate().is_err());
     /// ```
-    pub fn validate(&self) -> Result<(), String> {
+    pub fn validate(&self) -> Result<(), Error> {
         let own_range = self.minutes.min()..=self.minutes.max();
         if own_range != MINUTES_RANGE {
-            return Err(format!("Minutes are not in range {:?}. Got {:?}", MINUTES_RANGE, own_range));
+            return Err(Error::InvalidSpecifierRange(format!(
+                "Minutes are not in range {:?}. Got {:?}",
+                MINUTES_RANGE, own_range
+            )));
         }
 
         let own_range = self.hours.min()..=self.hours.max();
         if own_range != HOURS_RANGE {
-            return Err(format!("Hours are not in range {:?}. Got {:?}", HOURS_RANGE, own_range));
+            return Err(Error::InvalidSpecifierRange(format!(
+                "Hours are not in range {:?}. Got {:?}",
+                HOURS_RANGE, own_range
+            )));
         }
 
         let own_range = self.weekdays.min()..=self.weekdays.max();
         if own_range != WEEKDAYS_RANGE {
-            return Err(format!("Weekdays are not in range {:?}. Got {:?}", WEEKDAYS_RANGE, own_range));
+            return Err(Error::InvalidSpecifierRange(format!(
+                "Weekdays are not in range {:?}. Got {:?}",
+                WEEKDAYS_RANGE, own_range
+            )));
         }
 
         let own_range = self.monthdays.min()..=self.monthdays.max();
         if own_range != MONTHDAYS_RANGE {
-            return Err(format!("Monthdays are not in range {:?}. Got {:?}", MONTHDAYS_RANGE, own_range));
+            return Err(Error::InvalidSpecifierRange(format!(
+                "Monthdays are not in range {:?}. Got {:?}",
+                MONTHDAYS_RANGE, own_range
+            )));
         }
 
         let own_range = self.weeks.min()..=self.weeks.max();
         if own_range != WEEKS_RANGE {
-            return Err(format!("Weeks are not in range {:?}. Got {:?}", WEEKS_RANGE, own_range));
+            return Err(Error::InvalidSpecifierRange(format!(
+                "Weeks are not in range {:?}. Got {:?}",
+                WEEKS_RANGE, own_range
+            )));
         }
 
         let own_range = self.months.min()..=self.months.max();
         if own_range != MONTHS_RANGE {
-            return Err(format!("Months are not in range {:?}. Got {:?}", MONTHS_RANGE, own_range));
+            return Err(Error::InvalidSpecifierRange(format!(
+                "Months are not in range {:?}. Got {:?}",
+                MONTHS_RANGE, own_range
+            )));
+        }
+
+        if let Some(seconds) = &self.seconds {
+            let own_range = seconds.min()..=seconds.max();
+            if own_range != SECONDS_RANGE {
+                return Err(Error::InvalidSpecifierRange(format!(
+                    "Seconds are not in range {:?}. Got {:?}",
+                    SECONDS_RANGE, own_range
+                )));
+            }
+        }
+
+        if let Some(n) = self.nth_weekday {
+            if !NTH_WEEKDAY_RANGE.contains(&n) {
+                return Err(Error::InvalidInterval(format!(
+                    "Nth weekday is not in range {:?}. Got {}",
+                    NTH_WEEKDAY_RANGE, n
+                )));
+            }
         }
-    
+
         Ok(())
     }
 
+    /// Checks that the interval can match at all.
+    /// Detects combinations of specifiers that never match, like the 31st of February or a week that doesn't lie in any of the matched months.
+    /// Intervals with a [SpecifierKind::None] specifier are deliberately never matched and pass the check.
+    ///
+    /// # Returns
+    /// [Ok] if the interval matches at least one date.
+    /// [Err] describing the conflict else.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// let feb_29th = IntervalBuilder::default()
+    ///     .monthdays(SpecifierKind::Nth(28))
+    ///     .months(SpecifierKind::Nth(1))
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(feb_29th.satisfiability_check().is_ok());
+    ///
+    /// let feb_31st = IntervalBuilder::default()
+    ///     .monthdays(SpecifierKind::Nth(30))
+    ///     .months(SpecifierKind::Nth(1))
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(feb_31st.satisfiability_check().is_err());
+    /// ```
+    pub fn satisfiability_check(&self) -> Result<(), String> {
+        if self.has_none_specifier() || self.matches_any_date() {
+            return Ok(());
+        }
+
+        let days_combined = self.weekdays.kind() != &SpecifierKind::All
+            && self.monthdays.kind() != &SpecifierKind::All;
+        if !days_combined {
+            if let Some(first_monthday) = self.monthdays.first_match() {
+                let longest_month = (0..12)
+                    .filter(|month| self.months.matches(Month::from(*month)))
+                    .map(|month| MONTH_LENGTHS[month as usize])
+                    .max()
+                    .unwrap_or(0);
+                if first_monthday >= longest_month {
+                    return Err(format!(
+                        "None of the matched months has a {}. day",
+                        first_monthday + 1
+                    ));
+                }
+            }
+        }
+
+        let weeks_and_months = IntervalBuilder::default()
+            .weeks(self.weeks.kind().clone())
+            .months(self.months.kind().clone())
+            .build()
+            .map_err(|err| err.to_string())?;
+        if !weeks_and_months.matches_any_date() {
+            return Err(String::from(
+                "None of the matched weeks lies in one of the matched months",
+            ));
+        }
+
+        Err(String::from("The interval doesn't match any date"))
+    }
+
+    /// Checks if the interval matches any date within a whole cycle of the gregorian calendar
+    fn matches_any_date(&self) -> bool {
+        let Some(first) = NaiveDate::from_ymd_opt(2000, 1, 1) else {
+            return false;
+        };
+        first
+            .checked_add_days(Days::new(GREGORIAN_CYCLE_DAYS - 1))
+            .and_then(|last| self.next_matching_date(first, last))
+            .is_some()
+    }
+
     /// Checks if the provided [NaiveDate] is matched by the interval.
     ///
     /// If both wekkdays specifier and monthday specifier are not [SpecifierKind::All], only one of them has to match.
+    /// The [Interval::excluded_dates] are never matched.
     ///
     /// # Example
     /// ```
@@ -206,11 +446,26 @@ impl Interval {
     /// assert!(week_and_monthdays.matches_date(friday_13th));
     /// let wednesday_1st = NaiveDate::from_ymd_opt(2023, 3, 1).unwrap();
     /// assert!(!week_and_monthdays.matches_date(wednesday_1st));
+    ///
+    /// let second_saturday = IntervalBuilder::default()
+    ///     .weekdays(SpecifierKind::Nth(5))
+    ///     .nth_weekday(1)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(second_saturday.matches_date(NaiveDate::from_ymd_opt(2023, 5, 13).unwrap()));
+    /// assert!(!second_saturday.matches_date(NaiveDate::from_ymd_opt(2023, 5, 6).unwrap()));
+    /// assert!(!second_saturday.matches_date(NaiveDate::from_ymd_opt(2023, 5, 20).unwrap()));
+    ///
+    /// let christmas = NaiveDate::from_ymd_opt(2023, 12, 25).unwrap();
+    /// let except_christmas = IntervalBuilder::default()
+    ///     .excluded_dates(vec![christmas])
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(!except_christmas.matches_date(christmas));
+    /// assert!(except_christmas.matches_date(christmas.succ_opt().unwrap()));
     /// ```
     pub fn matches_date(&self, date: NaiveDate) -> bool {
-        let weekday_match = self
-            .weekdays
-            .matches(Weekday::from(date.weekday().num_days_from_monday()));
+        let weekday_match = self.matches_weekday(date);
         let monthday_match = self.monthdays.matches(date.day0());
         let day_match = if self.weekdays.kind() != &SpecifierKind::All
             && self.monthdays.kind() != &SpecifierKind::All
@@ -223,7 +478,19 @@ impl Interval {
         let week_match = self.weeks.matches(date.iso_week().week0());
         let month_match = self.months.matches(Month::from(date.month0()));
 
-        day_match && week_match && month_match
+        day_match && week_match && month_match && !self.is_excluded(date)
+    }
+
+    /// Checks if `date` is one of the [Interval::excluded_dates]
+    fn is_excluded(&self, date: NaiveDate) -> bool {
+        self.excluded_dates.contains(&date)
+    }
+
+    /// Checks if the weekday of `date` is matched, taking [Interval::nth_weekday] into account
+    fn matches_weekday(&self, date: NaiveDate) -> bool {
+        self.weekdays
+            .matches(Weekday::from(date.weekday().num_days_from_monday()))
+            && self.nth_weekday.is_none_or(|n| date.day0() / 7 == n)
     }
 
     /// Checks if the provided [NaiveTime] is matched by the interval
@@ -244,7 +511,11 @@ impl Interval {
     /// assert!(!interval.matches_time(seven_am));
     /// ```
     pub fn matches_time(&self, time: NaiveTime) -> bool {
-        self.minutes.matches(time.minute()) && self.hours.matches(time.hour())
+        self.seconds
+            .as_ref()
+            .is_none_or(|seconds| seconds.matches(time.second()))
+            && self.minutes.matches(time.minute())
+            && self.hours.matches(time.hour())
     }
 
     /// Checks if the given [NaiveDateTime] is matched by the interval.
@@ -297,18 +568,54 @@ impl Interval {
         DateTimeMatch::Ok
     }
 
-    /// Checks if any of the specifiers are [SpecifierKind::None]
+    /// Checks if any of the specifiers are [SpecifierKind::None] or an [SpecifierKind::Except] that excludes everything
     pub fn has_none_specifier(&self) -> bool {
-        return self.minutes.kind() == &SpecifierKind::None
-            || self.hours.kind() == &SpecifierKind::None
-            || self.weekdays.kind() == &SpecifierKind::None
-            || self.monthdays.kind() == &SpecifierKind::None
-            || self.weeks.kind() == &SpecifierKind::None
-            || self.months.kind() == &SpecifierKind::None;
+        return self
+            .seconds
+            .as_ref()
+            .is_some_and(|seconds| seconds.is_none_specifier())
+            || self.minutes.is_none_specifier()
+            || self.hours.is_none_specifier()
+            || self.weekdays.is_none_specifier()
+            || self.monthdays.is_none_specifier()
+            || self.weeks.is_none_specifier()
+            || self.months.is_none_specifier();
+    }
+
+    /// Checks if both intervals match the same datetimes, even if their [Specifier]s are written differently.
+    /// Compares the canonical forms (see [Specifier::canonicalize]) of all specifiers.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// let first = IntervalBuilder::default().minutes(SpecifierKind::First).build().unwrap();
+    /// let nth = IntervalBuilder::default().minutes(SpecifierKind::Nth(0)).build().unwrap();
+    /// assert_ne!(first, nth);
+    /// assert!(first.semantically_eq(&nth));
+    /// ```
+    pub fn semantically_eq(&self, other: &Interval) -> bool {
+        self.seconds.as_ref().map(Specifier::canonicalize)
+            == other.seconds.as_ref().map(Specifier::canonicalize)
+            && self.minutes.canonicalize() == other.minutes.canonicalize()
+            && self.hours.canonicalize() == other.hours.canonicalize()
+            && self.weekdays.canonicalize() == other.weekdays.canonicalize()
+            && self.monthdays.canonicalize() == other.monthdays.canonicalize()
+            && self.weeks.canonicalize() == other.weeks.canonicalize()
+            && self.months.canonicalize() == other.months.canonicalize()
+            && self.nth_weekday == other.nth_weekday
+            && self.sorted_excluded_dates() == other.sorted_excluded_dates()
+    }
+
+    fn sorted_excluded_dates(&self) -> Vec<NaiveDate> {
+        let mut dates = self.excluded_dates.clone();
+        dates.sort_unstable();
+        dates.dedup();
+        dates
     }
 
     /// Returns the next matching time of day after the given time, if one exists.
-    /// All returned [NaiveTime]s have their seconds-value set to `0`.
+    /// All returned [NaiveTime]s have their seconds-value set to `0`, unless [Interval::seconds] is set.
     ///
     /// # Example
     /// ```
@@ -336,7 +643,7 @@ impl Interval {
     }
 
     /// Returns the next matching time of the day or cycles to the next day if needed.
-    /// All returned [NaiveTime]s have their seconds-value set to `0`.
+    /// All returned [NaiveTime]s have their seconds-value set to `0`, unless [Interval::seconds] is set.
     ///
     /// # Example
     /// ```
@@ -356,6 +663,23 @@ impl Interval {
     /// assert_eq!(noon.cyclic_next_daytime(afternoon).unwrap(), NaiveTime::from_hms_opt(12, 0, 0).unwrap());
     /// ```
     pub fn cyclic_next_daytime(&self, time: NaiveTime) -> Option<NaiveTime> {
+        let Some(seconds) = &self.seconds else {
+            return self.cyclic_next_minute(time);
+        };
+
+        if self.hours.matches(time.hour()) && self.minutes.matches(time.minute()) {
+            let next_second = seconds.cyclic_next(time.second())?;
+            if next_second > time.second() {
+                // the match is in the same minute
+                return NaiveTime::from_hms_opt(time.hour(), time.minute(), next_second);
+            }
+        }
+
+        self.cyclic_next_minute(time)?.with_second(seconds.first_match()?)
+    }
+
+    /// Returns the start of the next matching minute, ignoring [Interval::seconds].
+    fn cyclic_next_minute(&self, time: NaiveTime) -> Option<NaiveTime> {
         let hour_matches = self.hours.matches(time.hour());
 
         let next_minute = if hour_matches {
@@ -376,10 +700,110 @@ impl Interval {
         return NaiveTime::from_hms_opt(next_hour, next_minute, 0);
     }
 
+    /// Returns the previous matching time of day before the given time, if one exists.
+    /// All returned [NaiveTime]s have their seconds-value set to `0`, unless [Interval::seconds] is set.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    /// use chrono::NaiveTime;
+    ///
+    /// let noon = IntervalBuilder::default()
+    ///     .minutes(SpecifierKind::First)
+    ///     .hours(SpecifierKind::Nth(12))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let afternoon = NaiveTime::from_hms_opt(15, 0, 0).unwrap();
+    /// assert_eq!(noon.prev_daytime(afternoon).unwrap(), NaiveTime::from_hms_opt(12, 0, 0).unwrap());
+    ///
+    /// let morning = NaiveTime::from_hms_opt(6, 0, 0).unwrap();
+    /// assert!(noon.prev_daytime(morning).is_none());
+    /// ```
+    pub fn prev_daytime(&self, time: NaiveTime) -> Option<NaiveTime> {
+        let cyclic_prev_match = self.cyclic_prev_daytime(time)?;
+        if cyclic_prev_match >= time {
+            return None;
+        }
+        Some(cyclic_prev_match)
+    }
+
+    /// Returns the previous matching time of the day or cycles to the previous day if needed.
+    /// All returned [NaiveTime]s have their seconds-value set to `0`, unless [Interval::seconds] is set.
+    /// If the minute of `time` is matched, it only counts as previous match if `time` is past its first second.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    /// use chrono::NaiveTime;
+    ///
+    /// let noon = IntervalBuilder::default()
+    ///     .minutes(SpecifierKind::First)
+    ///     .hours(SpecifierKind::Nth(12))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let afternoon = NaiveTime::from_hms_opt(15, 0, 0).unwrap();
+    /// assert_eq!(noon.cyclic_prev_daytime(afternoon).unwrap(), NaiveTime::from_hms_opt(12, 0, 0).unwrap());
+    ///
+    /// let morning = NaiveTime::from_hms_opt(6, 0, 0).unwrap();
+    /// assert_eq!(noon.cyclic_prev_daytime(morning).unwrap(), NaiveTime::from_hms_opt(12, 0, 0).unwrap());
+    /// ```
+    pub fn cyclic_prev_daytime(&self, time: NaiveTime) -> Option<NaiveTime> {
+        let Some(seconds) = &self.seconds else {
+            return self.cyclic_prev_minute(time);
+        };
+
+        if self.hours.matches(time.hour()) && self.minutes.matches(time.minute()) {
+            if time.nanosecond() > 0 && seconds.matches(time.second()) {
+                // the match is in the same second
+                return NaiveTime::from_hms_opt(time.hour(), time.minute(), time.second());
+            }
+            let prev_second = seconds.cyclic_prev(time.second())?;
+            if prev_second < time.second() {
+                // the match is in the same minute
+                return NaiveTime::from_hms_opt(time.hour(), time.minute(), prev_second);
+            }
+        }
+
+        let minute_start = NaiveTime::from_hms_opt(time.hour(), time.minute(), 0)?;
+        self.cyclic_prev_minute(minute_start)?
+            .with_second(seconds.last_match()?)
+    }
+
+    /// Returns the start of the previous matching minute, ignoring [Interval::seconds].
+    /// If the minute of `time` is matched, it only counts as previous match if `time` is past its first second.
+    fn cyclic_prev_minute(&self, time: NaiveTime) -> Option<NaiveTime> {
+        let hour_matches = self.hours.matches(time.hour());
+
+        let past_full_minute = time.second() > 0 || time.nanosecond() > 0;
+        if hour_matches && past_full_minute && self.minutes.matches(time.minute()) {
+            // the match is in the same minute
+            return NaiveTime::from_hms_opt(time.hour(), time.minute(), 0);
+        }
+
+        let prev_minute = if hour_matches {
+            // we can try to find the previous match in the same hour
+            self.minutes.cyclic_prev(time.minute())?
+        } else {
+            // we have to cycle anyways
+            self.minutes.last_match()?
+        };
+
+        let prev_hour = if hour_matches && prev_minute < time.minute() {
+            // hour does match and we found a matching minute in this hour
+            time.hour()
+        } else {
+            self.hours.cyclic_prev(time.hour())?
+        };
+
+        NaiveTime::from_hms_opt(prev_hour, prev_minute, 0)
+    }
+
     /// Tries to find the next matching [NaiveDateTime] after the provided `datetime`.
     /// Only tries to find a match within 1 year (365 days) from the provided `datetime`.
     ///
-    /// The seconds-value of a returned value will always be `0`.
+    /// The seconds-value of a returned value will always be `0`, unless [Interval::seconds] is set.
     ///
     /// # Returns
     /// `Some` variant containing a [NaiveDateTime] representing the next matching `datetime` after the provided if one is found.
@@ -421,7 +845,93 @@ impl Interval {
         let next_date = if date_matches && next_time > datetime.time() {
             datetime.date()
         } else {
-            let mut curr_date = datetime.date().checked_add_days(Days::new(1))?;
+            let first = datetime.date().checked_add_days(Days::new(1))?;
+            let last = datetime.date().checked_add_days(Days::new(365))?;
+            self.next_matching_date(first, last)?
+        };
+
+        Some(next_date.and_time(next_time))
+    }
+
+    /// Returns the first matched date in `first..=last`.
+    /// Skips whole months and weeks that aren't matched instead of checking every single day.
+    fn next_matching_date(&self, first: NaiveDate, last: NaiveDate) -> Option<NaiveDate> {
+        let mut curr_date = first;
+        while curr_date <= last {
+            let month = Month::from(curr_date.month0());
+            if !self.months.matches(month) {
+                // jump to the first day of the next matching month
+                let next_month: u32 = self.months.cyclic_next(month)?.into();
+                let year = if next_month > curr_date.month0() {
+                    curr_date.year()
+                } else {
+                    curr_date.year() + 1
+                };
+                curr_date = NaiveDate::from_ymd_opt(year, next_month + 1, 1)?;
+                continue;
+            }
+
+            if !self.weeks.matches(curr_date.iso_week().week0()) {
+                // jump to the monday of the next week
+                let days_left = 7 - curr_date.weekday().num_days_from_monday();
+                curr_date = curr_date.checked_add_days(Days::new(days_left as u64))?;
+                continue;
+            }
+
+            if self.matches_date(curr_date) {
+                return Some(curr_date);
+            }
+            curr_date = curr_date.checked_add_days(Days::new(1))?;
+        }
+        None
+    }
+
+    /// Tries to find the previous matching [NaiveDateTime] before the provided `datetime`.
+    /// Only tries to find a match within 1 year (365 days) before the provided `datetime`.
+    ///
+    /// The seconds-value of a returned value will always be `0`, unless [Interval::seconds] is set.
+    ///
+    /// # Returns
+    /// `Some` variant containing a [NaiveDateTime] representing the previous matching `datetime` before the provided if one is found.
+    /// `None` if there is no matching datetime within the previous year.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    /// use chrono::{ NaiveDate, NaiveTime };
+    ///
+    /// let first_of_month = IntervalBuilder::default()
+    ///     .minutes(SpecifierKind::First)
+    ///     .hours(SpecifierKind::First)
+    ///     .monthdays(SpecifierKind::First)
+    ///     .build()
+    ///     .unwrap();
+    /// let feb_1st = NaiveDate::from_ymd_opt(2003, 2, 1).unwrap()
+    ///     .and_hms_opt(0, 0, 0).unwrap();
+    /// assert_eq!(first_of_month.prev_datetime(feb_1st).unwrap().date(), NaiveDate::from_ymd_opt(2003, 1, 1).unwrap());
+    ///
+    /// let daily = IntervalBuilder::default()
+    ///     .minutes(SpecifierKind::First)
+    ///     .hours(SpecifierKind::Nth(12))
+    ///     .build()
+    ///     .unwrap();
+    /// let noon = daily.prev_datetime(feb_1st).unwrap();
+    /// assert_eq!(noon.date(), NaiveDate::from_ymd_opt(2003, 1, 31).unwrap());
+    /// assert_eq!(noon.time(), NaiveTime::from_hms_opt(12, 0, 0).unwrap());
+    /// ```
+    pub fn prev_datetime(&self, datetime: NaiveDateTime) -> Option<NaiveDateTime> {
+        let date_matches = self.matches_date(datetime.date());
+
+        let prev_time = if date_matches {
+            self.cyclic_prev_daytime(datetime.time())?
+        } else {
+            self.cyclic_prev_daytime(NaiveTime::from_hms_opt(0, 0, 0)?)?
+        };
+
+        let prev_date = if date_matches && prev_time < datetime.time() {
+            datetime.date()
+        } else {
+            let mut curr_date = datetime.date().checked_sub_days(Days::new(1))?;
             let mut matched_date = false;
             for _ in 0..365 {
                 if self.matches_date(curr_date) {
@@ -429,7 +939,7 @@ impl Interval {
                     break;
                 }
 
-                curr_date = curr_date.checked_add_days(Days::new(1))?;
+                curr_date = curr_date.checked_sub_days(Days::new(1))?;
             }
             if !matched_date {
                 return None;
@@ -437,7 +947,38 @@ impl Interval {
             curr_date
         };
 
-        Some(next_date.and_time(next_time))
+        Some(prev_date.and_time(prev_time))
+    }
+
+    /// Returns up to `n` consecutive matching [NaiveDateTime]s after the provided `datetime`.
+    /// Stops early if no further match can be found (see [Interval::next_datetime]).
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    /// use chrono::NaiveDate;
+    ///
+    /// let daily = Interval::daily(30, 12).unwrap();
+    /// let start = NaiveDate::from_ymd_opt(2023, 5, 1).unwrap()
+    ///     .and_hms_opt(13, 0, 0).unwrap();
+    /// let upcoming = daily.upcoming(start, 3);
+    /// assert_eq!(upcoming.len(), 3);
+    /// assert_eq!(upcoming[0], NaiveDate::from_ymd_opt(2023, 5, 2).unwrap().and_hms_opt(12, 30, 0).unwrap());
+    /// assert_eq!(upcoming[2], NaiveDate::from_ymd_opt(2023, 5, 4).unwrap().and_hms_opt(12, 30, 0).unwrap());
+    /// ```
+    pub fn upcoming(&self, datetime: NaiveDateTime, n: usize) -> Vec<NaiveDateTime> {
+        let mut upcoming = Vec::with_capacity(n);
+        let mut curr = datetime;
+        while upcoming.len() < n {
+            match self.next_datetime(curr) {
+                Some(next) => {
+                    upcoming.push(next);
+                    curr = next;
+                }
+                None => break,
+            }
+        }
+        upcoming
     }
 }
 
@@ -495,6 +1036,27 @@ impl IntervalBuilder {
         ));
         self
     }
+
+    /// Restricts the weekdays to their `n`-th occurrence (starting at 0) in the month
+    pub fn nth_weekday(&mut self, n: u32) -> &mut Self {
+        self.nth_weekday = Some(Some(n));
+        self
+    }
+
+    /// Sets the dates that are never matched
+    pub fn excluded_dates(&mut self, dates: Vec<NaiveDate>) -> &mut Self {
+        self.excluded_dates = Some(dates);
+        self
+    }
+
+    pub fn seconds(&mut self, spec_kind: SpecifierKind) -> &mut Self {
+        self.seconds = Some(Some(Specifier::new(
+            *SECONDS_RANGE.start(),
+            *SECONDS_RANGE.end(),
+            spec_kind,
+        )));
+        self
+    }
 }
 
 #[cfg(test)]
@@ -513,6 +1075,9 @@ mod interval_tests {
                 monthdays: Specifier::new(0, 31, SpecifierKind::All),
                 weeks: Specifier::new(0, 52, SpecifierKind::All),
                 months: Specifier::new(Month::January(), Month::December(), SpecifierKind::All),
+                nth_weekday: None,
+                seconds: None,
+                excluded_dates: vec![],
             };
 
             assert!(interval.validate().is_ok());
@@ -527,41 +1092,509 @@ mod interval_tests {
                 monthdays: Specifier::new(17, 31, SpecifierKind::All),
                 weeks: Specifier::new(0, 52, SpecifierKind::All),
                 months: Specifier::new(Month::January(), Month::December(), SpecifierKind::All),
+                nth_weekday: None,
+                seconds: None,
+                excluded_dates: vec![],
             };
 
-            assert!(interval.validate().is_err());
+            let err = interval.validate().unwrap_err();
+            assert!(matches!(err, Error::InvalidSpecifierRange(_)));
+            assert_eq!(err.to_string(), "Monthdays are not in range 0..=31. Got 17..=31");
 
             interval.monthdays = Specifier::new(0, 31, SpecifierKind::All);
             interval.minutes = Specifier::new(0, 100, SpecifierKind::All);
 
-            assert!(interval.validate().is_err());
+            assert!(interval.validate().is_err());
+
+            interval.minutes = Specifier::new(200, 18, SpecifierKind::All);
+
+            assert!(interval.validate().is_err());
+        }
+
+        #[test]
+        fn out_of_range_daily() {
+            let err = Interval::daily(60, 12).unwrap_err();
+            assert!(matches!(err, Error::InvalidInterval(_)));
+            assert_eq!(err.to_string(), "Expect 'minute' to be in range 0..=59. Got 60");
+        }
+
+        #[test]
+        fn invalid_struct_interval() {
+            let mut interval = Interval {
+                minutes: Specifier::new(0, 59, SpecifierKind::All),
+                hours: Specifier::new(0, 23, SpecifierKind::All),
+                weekdays: Specifier::new(Weekday::Wednesday(), Weekday::Sunday(), SpecifierKind::All),
+                monthdays: Specifier::new(0, 31, SpecifierKind::All),
+                weeks: Specifier::new(0, 52, SpecifierKind::All),
+                months: Specifier::new(Month::January(), Month::December(), SpecifierKind::All),
+                nth_weekday: None,
+                seconds: None,
+                excluded_dates: vec![],
+            };
+
+            assert!(interval.validate().is_err());
+
+            interval.weekdays = Specifier::new(Weekday::Monday(), Weekday::Sunday(), SpecifierKind::All);
+            interval.months = Specifier::new(Month::January(), Month::October(), SpecifierKind::All);
+
+            assert!(interval.validate().is_err());
+
+            interval.months = Specifier::new(Month::November(), Month::April(), SpecifierKind::All);
+
+            assert!(interval.validate().is_err());
+        }
+
+        #[test]
+        fn invalid_nth_weekday() {
+            let mut interval = IntervalBuilder::default().nth_weekday(4).build().unwrap();
+            assert!(interval.validate().is_ok());
+
+            interval.nth_weekday = Some(5);
+            assert!(interval.validate().is_err());
+        }
+    }
+
+    mod nth_weekday_tests {
+        use super::*;
+
+        fn first_monday() -> Interval {
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::Nth(0))
+                .hours(SpecifierKind::Nth(3))
+                .weekdays(SpecifierKind::Nth(0))
+                .nth_weekday(0)
+                .build()
+                .unwrap()
+        }
+
+        #[test]
+        fn matches_date() {
+            let interval = first_monday();
+            let mondays_in_may: Vec<NaiveDate> = (1..=31)
+                .filter_map(|day| NaiveDate::from_ymd_opt(2023, 5, day))
+                .filter(|date| interval.matches_date(*date))
+                .collect();
+            assert_eq!(mondays_in_may, vec![NaiveDate::from_ymd_opt(2023, 5, 1).unwrap()]);
+
+            // there is no fifth Saturday in February 2023
+            let fifth_saturday = IntervalBuilder::default()
+                .weekdays(SpecifierKind::Nth(5))
+                .nth_weekday(4)
+                .build()
+                .unwrap();
+            assert!((1..=28)
+                .filter_map(|day| NaiveDate::from_ymd_opt(2023, 2, day))
+                .all(|date| !fifth_saturday.matches_date(date)));
+            assert!(fifth_saturday.matches_date(NaiveDate::from_ymd_opt(2023, 4, 29).unwrap()));
+        }
+
+        #[test]
+        fn next_datetime() {
+            let interval = first_monday();
+            let start = NaiveDate::from_ymd_opt(2023, 1, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap();
+            let dates: Vec<NaiveDate> = interval
+                .upcoming(start, 4)
+                .into_iter()
+                .map(|datetime| datetime.date())
+                .collect();
+            assert_eq!(
+                dates,
+                vec![
+                    NaiveDate::from_ymd_opt(2023, 1, 2).unwrap(),
+                    NaiveDate::from_ymd_opt(2023, 2, 6).unwrap(),
+                    NaiveDate::from_ymd_opt(2023, 3, 6).unwrap(),
+                    NaiveDate::from_ymd_opt(2023, 4, 3).unwrap(),
+                ]
+            );
+        }
+
+        #[test]
+        fn json_roundtrip() {
+            let interval = first_monday();
+            let json = serde_json::to_string(&interval).unwrap();
+            assert_eq!(serde_json::from_str::<Interval>(&json).unwrap(), interval);
+
+            // intervals stored without the field match every occurrence
+            let mut json: serde_json::Value = serde_json::from_str(&json).unwrap();
+            json.as_object_mut().unwrap().remove("nth_weekday");
+            let parsed: Interval = serde_json::from_value(json).unwrap();
+            assert_eq!(parsed.nth_weekday, None);
+        }
+    }
+
+    mod seconds_tests {
+        use super::*;
+
+        fn every_20_seconds() -> Interval {
+            IntervalBuilder::default()
+                .seconds(SpecifierKind::EveryNth(20, 0))
+                .minutes(SpecifierKind::Nth(30))
+                .hours(SpecifierKind::Nth(12))
+                .build()
+                .unwrap()
+        }
+
+        #[test]
+        fn matches_time() {
+            let interval = every_20_seconds();
+            assert!(interval.matches_time(NaiveTime::from_hms_opt(12, 30, 40).unwrap()));
+            assert!(!interval.matches_time(NaiveTime::from_hms_opt(12, 30, 41).unwrap()));
+
+            // without seconds every second of a matched minute is matched
+            let daily = Interval::daily(30, 12).unwrap();
+            assert!(daily.matches_time(NaiveTime::from_hms_opt(12, 30, 41).unwrap()));
+        }
+
+        #[test]
+        fn next_daytime() {
+            let interval = every_20_seconds();
+            let time = |h, m, s| NaiveTime::from_hms_opt(h, m, s).unwrap();
+            assert_eq!(interval.next_daytime(time(8, 0, 0)), Some(time(12, 30, 0)));
+            assert_eq!(interval.next_daytime(time(12, 30, 0)), Some(time(12, 30, 20)));
+            assert_eq!(interval.next_daytime(time(12, 30, 45)), None);
+            assert_eq!(interval.cyclic_next_daytime(time(12, 30, 45)), Some(time(12, 30, 0)));
+        }
+
+        #[test]
+        fn prev_daytime() {
+            let interval = every_20_seconds();
+            let time = |h, m, s| NaiveTime::from_hms_opt(h, m, s).unwrap();
+            assert_eq!(interval.prev_daytime(time(15, 0, 0)), Some(time(12, 30, 40)));
+            assert_eq!(interval.prev_daytime(time(12, 30, 40)), Some(time(12, 30, 20)));
+            assert_eq!(interval.prev_daytime(time(12, 30, 0)), None);
+            assert_eq!(
+                interval.prev_daytime(time(12, 30, 20).with_nanosecond(1).unwrap()),
+                Some(time(12, 30, 20))
+            );
+        }
+
+        #[test]
+        fn upcoming() {
+            let start = NaiveDate::from_ymd_opt(2023, 5, 1)
+                .unwrap()
+                .and_hms_opt(12, 30, 30)
+                .unwrap();
+            let upcoming = every_20_seconds().upcoming(start, 3);
+            assert_eq!(
+                upcoming,
+                vec![
+                    start.with_second(40).unwrap(),
+                    start.with_day(2).unwrap().with_second(0).unwrap(),
+                    start.with_day(2).unwrap().with_second(20).unwrap(),
+                ]
+            );
+        }
+
+        #[test]
+        fn validate() {
+            let mut interval = every_20_seconds();
+            assert!(interval.validate().is_ok());
+
+            interval.seconds = Some(Specifier::new(0, 60, SpecifierKind::All));
+            assert!(interval.validate().is_err());
+        }
+
+        #[test]
+        fn json_without_seconds() {
+            let interval = Interval::daily(30, 12).unwrap();
+            let mut json = serde_json::to_value(&interval).unwrap();
+            json.as_object_mut().unwrap().remove("seconds");
+            let parsed: Interval = serde_json::from_value(json).unwrap();
+            assert_eq!(parsed, interval);
+            assert_eq!(parsed.seconds, None);
+
+            let interval = every_20_seconds();
+            let json = serde_json::to_string(&interval).unwrap();
+            assert_eq!(serde_json::from_str::<Interval>(&json).unwrap(), interval);
+        }
+    }
+
+    mod named_values_tests {
+        use super::*;
+
+        fn interval() -> Interval {
+            IntervalBuilder::default()
+                .weekdays(SpecifierKind::ExplicitNths(vec![0, 4]))
+                .months(SpecifierKind::Range(1, 3))
+                .build()
+                .unwrap()
+        }
+
+        #[test]
+        fn numeric_json() {
+            let mut json = serde_json::to_value(interval()).unwrap();
+            json["weekdays"] = serde_json::json!({
+                "min": {"day": 0},
+                "max": {"day": 6},
+                "kind": {"ExplicitNths": [0, 4]}
+            });
+            json["months"] = serde_json::json!({
+                "min": {"month": 0},
+                "max": {"month": 11},
+                "kind": {"Range": [1, 3]}
+            });
+            let parsed: Interval = serde_json::from_value(json).unwrap();
+            assert_eq!(parsed, interval());
+
+            let json = serde_json::to_string(&parsed).unwrap();
+            assert_eq!(serde_json::from_str::<Interval>(&json).unwrap(), interval());
+        }
+
+        #[test]
+        fn named_json() {
+            let mut json = serde_json::to_value(interval()).unwrap();
+            json["weekdays"] = serde_json::json!({
+                "min": "Monday",
+                "max": "Sunday",
+                "kind": {"ExplicitNths": ["Monday", "Fri"]}
+            });
+            json["months"] = serde_json::json!({
+                "min": "Jan",
+                "max": "December",
+                "kind": {"Range": ["Feb", "april"]}
+            });
+            let parsed: Interval = serde_json::from_value(json).unwrap();
+            assert_eq!(parsed, interval());
+            assert!(parsed.validate().is_ok());
+
+            let json = serde_json::to_value(&parsed).unwrap();
+            assert_eq!(
+                json["weekdays"],
+                serde_json::json!({
+                    "min": "Monday",
+                    "max": "Sunday",
+                    "kind": {"ExplicitNths": ["Monday", "Friday"]}
+                })
+            );
+            assert_eq!(
+                json["months"],
+                serde_json::json!({
+                    "min": "January",
+                    "max": "December",
+                    "kind": {"Range": ["February", "April"]}
+                })
+            );
+            assert_eq!(serde_json::from_value::<Interval>(json).unwrap(), interval());
+        }
+
+        #[test]
+        fn explicit_list() {
+            let json = serde_json::json!({
+                "min": 0,
+                "max": 11,
+                "kind": {"ExplicitList": ["Mar", 5, "December"]}
+            });
+            let months: Specifier<Month> = serde_json::from_value(json).unwrap();
+            assert_eq!(months.kind(), &SpecifierKind::ExplicitList(vec![2, 5, 11]));
+        }
+
+        #[test]
+        fn unknown_names() {
+            let json = serde_json::json!({
+                "min": "Monday",
+                "max": "Sunday",
+                "kind": {"Nth": "Caturday"}
+            });
+            assert!(serde_json::from_value::<Specifier<Weekday>>(json).is_err());
+
+            // plain numbers have no names
+            let json = serde_json::json!({"min": 0, "max": 59, "kind": {"Nth": "Monday"}});
+            assert!(serde_json::from_value::<Specifier<u32>>(json).is_err());
+        }
+    }
+
+    mod satisfiability_tests {
+        use super::*;
+
+        #[test]
+        fn monthday_month_conflict() {
+            let interval = IntervalBuilder::default()
+                .monthdays(SpecifierKind::Nth(30))
+                .months(SpecifierKind::ExplicitNths(vec![1, 3, 5]))
+                .build()
+                .unwrap();
+            let msg = interval.satisfiability_check().unwrap_err();
+            assert!(msg.contains("31. day"), "{}", msg);
+
+            // a matched weekday is enough
+            let interval = IntervalBuilder::default()
+                .weekdays(SpecifierKind::First)
+                .monthdays(SpecifierKind::Nth(30))
+                .months(SpecifierKind::Nth(1))
+                .build()
+                .unwrap();
+            assert!(interval.satisfiability_check().is_ok());
+        }
+
+        #[test]
+        fn week_month_conflict() {
+            let interval = IntervalBuilder::default()
+                .weeks(SpecifierKind::Nth(20))
+                .months(SpecifierKind::First)
+                .build()
+                .unwrap();
+            let msg = interval.satisfiability_check().unwrap_err();
+            assert!(msg.contains("weeks"), "{}", msg);
+
+            // the first ISO week can start in December
+            let interval = IntervalBuilder::default()
+                .weeks(SpecifierKind::First)
+                .months(SpecifierKind::Last)
+                .build()
+                .unwrap();
+            assert!(interval.satisfiability_check().is_ok());
+        }
+
+        #[test]
+        fn other_conflicts() {
+            // a fifth monday never lies in the second to fourth week of the year
+            let interval = IntervalBuilder::default()
+                .weekdays(SpecifierKind::First)
+                .nth_weekday(4)
+                .monthdays(SpecifierKind::All)
+                .weeks(SpecifierKind::Range(1, 3))
+                .build()
+                .unwrap();
+            assert!(interval.satisfiability_check().is_err());
+        }
+
+        #[test]
+        fn satisfiable() {
+            assert!(IntervalBuilder::default().build().unwrap().satisfiability_check().is_ok());
+            assert!(Interval::yearly(Month::February(), 28, 0, 0)
+                .unwrap()
+                .satisfiability_check()
+                .is_ok());
+            // never matched on purpose
+            let never = IntervalBuilder::default()
+                .minutes(SpecifierKind::None)
+                .monthdays(SpecifierKind::Nth(30))
+                .months(SpecifierKind::Nth(1))
+                .build()
+                .unwrap();
+            assert!(never.satisfiability_check().is_ok());
+        }
+    }
+
+    mod excluded_dates_tests {
+        use super::*;
+
+        fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+            NaiveDate::from_ymd_opt(year, month, day).unwrap()
+        }
+
+        #[test]
+        fn next_datetime_skips_excluded() {
+            let interval = IntervalBuilder::default()
+                .minutes(SpecifierKind::First)
+                .hours(SpecifierKind::Nth(12))
+                .excluded_dates(vec![date(2023, 5, 1), date(2023, 5, 2)])
+                .build()
+                .unwrap();
+            let start = date(2023, 5, 1).and_hms_opt(8, 0, 0).unwrap();
+            assert_eq!(
+                interval.next_datetime(start),
+                date(2023, 5, 3).and_hms_opt(12, 0, 0)
+            );
+            assert_eq!(
+                interval.prev_datetime(date(2023, 5, 3).and_hms_opt(8, 0, 0).unwrap()),
+                date(2023, 4, 30).and_hms_opt(12, 0, 0)
+            );
+        }
+
+        #[test]
+        fn everything_excluded() {
+            let start = date(2023, 1, 1);
+            let interval = IntervalBuilder::default()
+                .excluded_dates(start.iter_days().take(400).collect())
+                .build()
+                .unwrap();
+            assert_eq!(interval.next_datetime(start.and_hms_opt(0, 0, 0).unwrap()), None);
+        }
+
+        #[test]
+        fn json_without_excluded_dates() {
+            let interval = IntervalBuilder::default()
+                .excluded_dates(vec![date(2023, 12, 24)])
+                .build()
+                .unwrap();
+            let json = serde_json::to_string(&interval).unwrap();
+            assert!(json.contains("\"2023-12-24\""));
+            assert_eq!(serde_json::from_str::<Interval>(&json).unwrap(), interval);
+
+            let mut json = serde_json::to_value(&interval).unwrap();
+            json.as_object_mut().unwrap().remove("excluded_dates");
+            let parsed: Interval = serde_json::from_value(json).unwrap();
+            assert!(parsed.excluded_dates.is_empty());
+        }
+    }
+
+    mod semantically_eq_tests {
+        use super::*;
+
+        #[test]
+        fn equivalent_kinds() {
+            let interval = IntervalBuilder::default()
+                .minutes(SpecifierKind::First)
+                .hours(SpecifierKind::Last)
+                .weekdays(SpecifierKind::ExplicitNths(vec![0, 1, 2, 3, 4, 5, 6]))
+                .build()
+                .unwrap();
+            let other = IntervalBuilder::default()
+                .minutes(SpecifierKind::Nth(0))
+                .hours(SpecifierKind::BackNth(0))
+                .months(SpecifierKind::EveryNth(1, 0))
+                .build()
+                .unwrap();
+            assert_ne!(interval, other);
+            assert!(interval.semantically_eq(&other));
+        }
+
+        #[test]
+        fn different_intervals() {
+            let interval = Interval::daily(0, 3).unwrap();
+            assert!(!interval.semantically_eq(&Interval::daily(0, 4).unwrap()));
 
-            interval.minutes = Specifier::new(200, 18, SpecifierKind::All);
+            let mut other = interval.clone();
+            other.nth_weekday = Some(1);
+            assert!(!interval.semantically_eq(&other));
 
-            assert!(interval.validate().is_err());
+            let mut other = interval.clone();
+            other.seconds = Some(Specifier::new(0, 59, SpecifierKind::Nth(0)));
+            assert!(!interval.semantically_eq(&other));
         }
+    }
 
-        #[test]
-        fn invalid_struct_interval() {
-            let mut interval = Interval {
-                minutes: Specifier::new(0, 59, SpecifierKind::All),
-                hours: Specifier::new(0, 23, SpecifierKind::All),
-                weekdays: Specifier::new(Weekday::Wednesday(), Weekday::Sunday(), SpecifierKind::All),
-                monthdays: Specifier::new(0, 31, SpecifierKind::All),
-                weeks: Specifier::new(0, 52, SpecifierKind::All),
-                months: Specifier::new(Month::January(), Month::December(), SpecifierKind::All),
-            };
-
-            assert!(interval.validate().is_err());
+    mod has_none_specifier_tests {
+        use super::*;
 
-            interval.weekdays = Specifier::new(Weekday::Monday(), Weekday::Sunday(), SpecifierKind::All);
-            interval.months = Specifier::new(Month::January(), Month::October(), SpecifierKind::All);
+        #[test]
+        fn none_and_except() {
+            assert!(!IntervalBuilder::default().build().unwrap().has_none_specifier());
 
-            assert!(interval.validate().is_err());
+            let interval = IntervalBuilder::default()
+                .hours(SpecifierKind::None)
+                .build()
+                .unwrap();
+            assert!(interval.has_none_specifier());
 
-            interval.months = Specifier::new(Month::November(), Month::April(), SpecifierKind::All);
+            let interval = IntervalBuilder::default()
+                .weekdays(SpecifierKind::Except(vec![6]))
+                .build()
+                .unwrap();
+            assert!(!interval.has_none_specifier());
 
-            assert!(interval.validate().is_err());
+            let interval = IntervalBuilder::default()
+                .weekdays(SpecifierKind::Except((0..7).collect()))
+                .build()
+                .unwrap();
+            assert!(interval.has_none_specifier());
+            assert!(interval
+                .next_datetime(NaiveDate::from_ymd_opt(2023, 5, 1).unwrap().and_hms_opt(0, 0, 0).unwrap())
+                .is_none());
         }
     }
 
@@ -1249,6 +2282,66 @@ mod interval_tests {
     mod next_datetime_tests {
         use super::*;
 
+        /// Reference implementation stepping through every single day
+        fn day_by_day(interval: &Interval, datetime: NaiveDateTime) -> Option<NaiveDate> {
+            let mut curr_date = datetime.date();
+            for _ in 0..365 {
+                curr_date = curr_date.checked_add_days(Days::new(1))?;
+                if interval.matches_date(curr_date) {
+                    return Some(curr_date);
+                }
+            }
+            None
+        }
+
+        #[test]
+        fn same_as_day_by_day() {
+            let intervals = [
+                IntervalBuilder::default()
+                    .monthdays(SpecifierKind::Nth(28))
+                    .months(SpecifierKind::Nth(1))
+                    .build()
+                    .unwrap(),
+                IntervalBuilder::default()
+                    .weeks(SpecifierKind::EveryNth(3, 1))
+                    .weekdays(SpecifierKind::Nth(2))
+                    .build()
+                    .unwrap(),
+                IntervalBuilder::default()
+                    .weeks(SpecifierKind::ExplicitList(vec![0, 52]))
+                    .months(SpecifierKind::ExplicitList(vec![0, 11]))
+                    .build()
+                    .unwrap(),
+                IntervalBuilder::default()
+                    .monthdays(SpecifierKind::Last)
+                    .months(SpecifierKind::Except(vec![3, 4, 5]))
+                    .build()
+                    .unwrap(),
+                IntervalBuilder::default()
+                    .weekdays(SpecifierKind::Nth(0))
+                    .nth_weekday(4)
+                    .months(SpecifierKind::EveryNth(4, 2))
+                    .build()
+                    .unwrap(),
+            ];
+            let start = NaiveDate::from_ymd_opt(2019, 12, 1)
+                .unwrap()
+                .and_hms_opt(23, 59, 59)
+                .unwrap();
+            for interval in &intervals {
+                for day in 0..800 {
+                    let datetime = start.checked_add_days(Days::new(day)).unwrap();
+                    assert_eq!(
+                        interval.next_datetime(datetime).map(|next| next.date()),
+                        day_by_day(interval, datetime),
+                        "{:?} after {}",
+                        interval,
+                        datetime
+                    );
+                }
+            }
+        }
+
         #[test]
         fn no_match() {
             let interval = IntervalBuilder::default()
@@ -1381,5 +2474,300 @@ mod interval_tests {
                     .and_hms_opt(0, 0, 0)
             );
         }
+
+        #[test]
+        fn back_nth_beyond_max() {
+            let interval = IntervalBuilder::default()
+                .minutes(SpecifierKind::BackNth(100))
+                .build()
+                .unwrap();
+
+            let datetime = NaiveDate::from_ymd_opt(2023, 8, 5)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap();
+            assert_eq!(interval.next_datetime(datetime), None);
+        }
+    }
+    mod prev_daytime_tests {
+        use super::*;
+
+        #[test]
+        fn no_match() {
+            let interval = IntervalBuilder::default()
+                .hours(SpecifierKind::None)
+                .build()
+                .unwrap();
+            let time = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+            assert!(interval.prev_daytime(time).is_none());
+            assert!(interval.cyclic_prev_daytime(time).is_none());
+        }
+
+        #[test]
+        fn prev_match_yesterday() {
+            let interval = IntervalBuilder::default()
+                .minutes(SpecifierKind::Nth(15))
+                .hours(SpecifierKind::ExplicitNths(vec![6, 12, 18]))
+                .build()
+                .unwrap();
+
+            let early = NaiveTime::from_hms_opt(5, 0, 0).unwrap();
+            assert!(interval.prev_daytime(early).is_none());
+            assert_eq!(
+                interval.cyclic_prev_daytime(early),
+                NaiveTime::from_hms_opt(18, 15, 0)
+            );
+
+            let first_match = NaiveTime::from_hms_opt(6, 15, 0).unwrap();
+            assert!(interval.prev_daytime(first_match).is_none());
+            assert_eq!(
+                interval.cyclic_prev_daytime(first_match),
+                NaiveTime::from_hms_opt(18, 15, 0)
+            );
+        }
+
+        #[test]
+        fn only_midnight() {
+            let interval = IntervalBuilder::default()
+                .minutes(SpecifierKind::First)
+                .hours(SpecifierKind::First)
+                .build()
+                .unwrap();
+
+            let midnight = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+            assert_eq!(
+                interval.cyclic_prev_daytime(midnight),
+                NaiveTime::from_hms_opt(0, 0, 0)
+            );
+            assert!(interval.prev_daytime(midnight).is_none());
+
+            let time = NaiveTime::from_hms_opt(14, 39, 55).unwrap();
+            assert_eq!(interval.prev_daytime(time), NaiveTime::from_hms_opt(0, 0, 0));
+        }
+
+        #[test]
+        fn in_same_hour() {
+            let interval = IntervalBuilder::default()
+                .minutes(SpecifierKind::EveryNth(10, 0))
+                .build()
+                .unwrap();
+            let time = NaiveTime::from_hms_opt(15, 35, 0).unwrap();
+            assert_eq!(interval.prev_daytime(time), NaiveTime::from_hms_opt(15, 30, 0));
+            let time = NaiveTime::from_hms_opt(15, 30, 0).unwrap();
+            assert_eq!(interval.prev_daytime(time), NaiveTime::from_hms_opt(15, 20, 0));
+        }
+
+        #[test]
+        fn in_same_minute() {
+            let interval = Interval::daily(30, 12).unwrap();
+            let time = NaiveTime::from_hms_opt(12, 30, 1).unwrap();
+            assert_eq!(interval.prev_daytime(time), NaiveTime::from_hms_opt(12, 30, 0));
+            let time = NaiveTime::from_hms_opt(12, 30, 0).unwrap();
+            assert!(interval.prev_daytime(time).is_none());
+        }
+
+        #[test]
+        fn no_hour_match() {
+            let interval = IntervalBuilder::default()
+                .minutes(SpecifierKind::ExplicitNths(vec![15, 45]))
+                .hours(SpecifierKind::ExplicitNths(vec![0, 6, 12, 18]))
+                .build()
+                .unwrap();
+
+            let time = NaiveTime::from_hms_opt(7, 37, 0).unwrap();
+            assert_eq!(interval.prev_daytime(time), NaiveTime::from_hms_opt(6, 45, 0));
+
+            let time = NaiveTime::from_hms_opt(12, 10, 0).unwrap();
+            assert_eq!(interval.prev_daytime(time), NaiveTime::from_hms_opt(6, 45, 0));
+        }
+    }
+
+    mod prev_datetime_tests {
+        use super::*;
+
+        #[test]
+        fn no_match() {
+            let interval = IntervalBuilder::default()
+                .weekdays(SpecifierKind::None)
+                .build()
+                .unwrap();
+
+            let datetime = NaiveDate::from_ymd_opt(2023, 9, 9)
+                .unwrap()
+                .and_hms_opt(12, 40, 29)
+                .unwrap();
+            assert!(interval.prev_datetime(datetime).is_none());
+        }
+
+        #[test]
+        fn not_within_a_year() {
+            let interval = IntervalBuilder::default()
+                .monthdays(SpecifierKind::Nth(28))
+                .months(SpecifierKind::Nth(1))
+                .build()
+                .unwrap();
+
+            let datetime = NaiveDate::from_ymd_opt(2023, 4, 8)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap();
+            assert!(interval.prev_datetime(datetime).is_none());
+        }
+
+        #[test]
+        fn valid_interval() {
+            let interval = IntervalBuilder::default()
+                .minutes(SpecifierKind::First)
+                .hours(SpecifierKind::First)
+                .monthdays(SpecifierKind::Nth(28))
+                .months(SpecifierKind::Nth(1))
+                .build()
+                .unwrap();
+
+            let datetime = NaiveDate::from_ymd_opt(2020, 3, 20)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap();
+            assert_eq!(
+                interval.prev_datetime(datetime),
+                NaiveDate::from_ymd_opt(2020, 2, 29)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+            );
+            let datetime = NaiveDate::from_ymd_opt(2021, 1, 5)
+                .unwrap()
+                .and_hms_opt(20, 55, 36)
+                .unwrap();
+            assert_eq!(
+                interval.prev_datetime(datetime),
+                NaiveDate::from_ymd_opt(2020, 2, 29)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+            );
+        }
+
+        #[test]
+        fn same_day() {
+            let interval = IntervalBuilder::default()
+                .minutes(SpecifierKind::First)
+                .build()
+                .unwrap();
+
+            let datetime = NaiveDate::from_ymd_opt(2023, 8, 5)
+                .unwrap()
+                .and_hms_opt(20, 35, 55)
+                .unwrap();
+            assert_eq!(
+                interval.prev_datetime(datetime),
+                NaiveDate::from_ymd_opt(2023, 8, 5)
+                    .unwrap()
+                    .and_hms_opt(20, 0, 0)
+            );
+            let datetime = NaiveDate::from_ymd_opt(2023, 8, 5)
+                .unwrap()
+                .and_hms_opt(20, 0, 0)
+                .unwrap();
+            assert_eq!(
+                interval.prev_datetime(datetime),
+                NaiveDate::from_ymd_opt(2023, 8, 5)
+                    .unwrap()
+                    .and_hms_opt(19, 0, 0)
+            );
+        }
+
+        #[test]
+        fn prev_day() {
+            let interval = IntervalBuilder::default()
+                .minutes(SpecifierKind::First)
+                .build()
+                .unwrap();
+
+            let datetime = NaiveDate::from_ymd_opt(2019, 4, 8)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap();
+            assert_eq!(
+                interval.prev_datetime(datetime),
+                NaiveDate::from_ymd_opt(2019, 4, 7)
+                    .unwrap()
+                    .and_hms_opt(23, 0, 0)
+            );
+        }
+
+        #[test]
+        fn inverse_of_next_datetime() {
+            let intervals = [
+                Interval::daily(30, 12).unwrap(),
+                IntervalBuilder::default()
+                    .minutes(SpecifierKind::EveryNth(15, 5))
+                    .hours(SpecifierKind::ExplicitNths(vec![0, 13, 23]))
+                    .weekdays(SpecifierKind::Nth(2))
+                    .build()
+                    .unwrap(),
+                IntervalBuilder::default()
+                    .minutes(SpecifierKind::Last)
+                    .hours(SpecifierKind::Last)
+                    .monthdays(SpecifierKind::ExplicitNths(vec![0, 15, 30]))
+                    .weekdays(SpecifierKind::Last)
+                    .build()
+                    .unwrap(),
+            ];
+            let start = NaiveDate::from_ymd_opt(2023, 1, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap();
+
+            for interval in intervals {
+                let upcoming = interval.upcoming(start, 30);
+                for window in upcoming.windows(2) {
+                    assert_eq!(interval.prev_datetime(window[1]), Some(window[0]));
+                    // everything between two matches has the first one as previous match
+                    let between = window[0] + chrono::Duration::seconds(30);
+                    assert_eq!(interval.prev_datetime(between), Some(window[0]));
+                }
+            }
+        }
+    }
+
+    mod upcoming_tests {
+        use super::*;
+
+        #[test]
+        fn no_match() {
+            let interval = IntervalBuilder::default()
+                .minutes(SpecifierKind::None)
+                .build()
+                .unwrap();
+            let datetime = NaiveDate::from_ymd_opt(2023, 9, 9)
+                .unwrap()
+                .and_hms_opt(12, 40, 29)
+                .unwrap();
+            assert!(interval.upcoming(datetime, 5).is_empty());
+        }
+
+        #[test]
+        fn consecutive_matches() {
+            let interval = IntervalBuilder::default()
+                .minutes(SpecifierKind::First)
+                .hours(SpecifierKind::First)
+                .monthdays(SpecifierKind::First)
+                .build()
+                .unwrap();
+            let datetime = NaiveDate::from_ymd_opt(2023, 11, 9)
+                .unwrap()
+                .and_hms_opt(12, 40, 29)
+                .unwrap();
+            let expected: Vec<NaiveDateTime> = [(2023, 12), (2024, 1), (2024, 2)]
+                .into_iter()
+                .map(|(year, month)| {
+                    NaiveDate::from_ymd_opt(year, month, 1)
+                        .unwrap()
+                        .and_hms_opt(0, 0, 0)
+                        .unwrap()
+                })
+                .collect();
+            assert_eq!(interval.upcoming(datetime, 3), expected);
+            assert!(interval.upcoming(datetime, 0).is_empty());
+        }
     }
 }
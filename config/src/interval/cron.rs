@@ -0,0 +1,753 @@
+//! Contains functions for converting [Interval]s from and to cron expressions (as used in crontabs on Linux).
+
+use std::ops::RangeInclusive;
+
+use super::{
+    months, weekdays, Interval, IntervalBuilder, SpecifierKind, HOURS_RANGE, MINUTES_RANGE,
+    MONTHDAYS_RANGE,
+};
+
+/// One of the five fields of a cron expression
+struct CronField {
+    name: &'static str,
+    /// Values allowed in the cron expression
+    range: RangeInclusive<u32>,
+    /// Largest value of the corresponding [super::Specifier]
+    spec_max: u32,
+}
+
+const CRON_FIELDS: [CronField; 5] = [
+    CronField {
+        name: "minute",
+        range: 0..=59,
+        spec_max: *MINUTES_RANGE.end(),
+    },
+    CronField {
+        name: "hour",
+        range: 0..=23,
+        spec_max: *HOURS_RANGE.end(),
+    },
+    CronField {
+        name: "day of month",
+        range: 1..=31,
+        spec_max: *MONTHDAYS_RANGE.end(),
+    },
+    CronField {
+        name: "month",
+        range: 1..=12,
+        spec_max: (months::N - 1) as u32,
+    },
+    // 0 and 7 are both Sunday
+    CronField {
+        name: "day of week",
+        range: 0..=7,
+        spec_max: (weekdays::N - 1) as u32,
+    },
+];
+
+const MONTHDAYS: usize = 2;
+const DAY_OF_WEEK: usize = 4;
+
+impl CronField {
+    /// Converts a value of the cron expression into the value used by the corresponding [super::Specifier]
+    fn to_spec_value(&self, value: u32, field: usize) -> u32 {
+        match field {
+            // cron starts with Sunday, we start with Monday
+            DAY_OF_WEEK => (value + 6) % 7,
+            _ => value - self.range.start(),
+        }
+    }
+
+    /// Converts a value used by the corresponding [super::Specifier] into the value of the cron expression
+    fn to_cron_value(&self, value: u32, field: usize) -> Result<u32, String> {
+        let cron_value = match field {
+            DAY_OF_WEEK if value < 7 => (value + 1) % 7,
+            DAY_OF_WEEK => value + 1,
+            _ => value + self.range.start(),
+        };
+        if !self.range.contains(&cron_value) {
+            return Err(format!(
+                "Value {} of field '{}' can't be represented in cron",
+                value, self.name
+            ));
+        }
+        Ok(cron_value)
+    }
+
+    fn parse_value(&self, token: &str) -> Result<u32, String> {
+        match token.parse::<u32>() {
+            Ok(value) if self.range.contains(&value) => Ok(value),
+            _ => Err(format!(
+                "Invalid value {:?} for field '{}'. Expected a number in range {:?}",
+                token, self.name, self.range
+            )),
+        }
+    }
+
+    fn parse_step(&self, token: &str) -> Result<u32, String> {
+        match token.parse::<u32>() {
+            Ok(step) if step > 0 => Ok(step),
+            _ => Err(format!(
+                "Invalid step {:?} for field '{}'. Expected a number larger than 0",
+                token, self.name
+            )),
+        }
+    }
+
+    /// Expands a single element of a comma separated list (`*`, `a`, `a-b`, optionally followed by `/step`) into the matched cron values
+    fn expand(&self, field: usize, item: &str) -> Result<Vec<u32>, String> {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, self.parse_step(step)?),
+            None => (item, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            // don't include Sunday twice
+            let end = if field == DAY_OF_WEEK {
+                6
+            } else {
+                *self.range.end()
+            };
+            (*self.range.start(), end)
+        } else if let Some((start, end)) = range.split_once('-') {
+            let start = self.parse_value(start)?;
+            let end = self.parse_value(end)?;
+            if start > end {
+                return Err(format!(
+                    "Invalid range {:?} for field '{}'. Start must not be larger than end",
+                    range, self.name
+                ));
+            }
+            (start, end)
+        } else {
+            let start = self.parse_value(range)?;
+            // `a/n` means from `a` to the end of the range
+            let end = if item.contains('/') {
+                *self.range.end()
+            } else {
+                start
+            };
+            (start, end)
+        };
+
+        Ok((start..=end).step_by(step as usize).collect())
+    }
+
+    fn parse(&self, field: usize, token: &str) -> Result<SpecifierKind, String> {
+        if token == "*" {
+            return Ok(SpecifierKind::All);
+        }
+
+        // steps over the whole range are kept as steps, as long as the conversion doesn't reorder the values
+        if field != DAY_OF_WEEK {
+            if let Some((start, step)) = token.split_once('/') {
+                let offset = match start {
+                    "*" => Some(*self.range.start()),
+                    _ if start.contains('-') || start.contains(',') => None,
+                    _ => Some(self.parse_value(start)?),
+                };
+                if let Some(offset) = offset {
+                    let step = self.parse_step(step)?;
+                    return Ok(SpecifierKind::EveryNth(
+                        step,
+                        self.to_spec_value(offset, field),
+                    ));
+                }
+            }
+        }
+
+        // single ranges are kept as ranges, as long as the conversion doesn't reorder the values
+        if let Some((start, end)) = token.split_once('-') {
+            if !token.contains(',') && !token.contains('/') {
+                let start = self.parse_value(start)?;
+                let end = self.parse_value(end)?;
+                if start > end {
+                    return Err(format!(
+                        "Invalid range {:?} for field '{}'. Start must not be larger than end",
+                        token, self.name
+                    ));
+                }
+                if field != DAY_OF_WEEK || start > 0 {
+                    return Ok(SpecifierKind::Range(
+                        self.to_spec_value(start, field),
+                        self.to_spec_value(end, field),
+                    ));
+                }
+            }
+        }
+
+        let mut values = vec![];
+        for item in token.split(',') {
+            values.extend(
+                self.expand(field, item)?
+                    .into_iter()
+                    .map(|value| self.to_spec_value(value, field)),
+            );
+        }
+        values.sort_unstable();
+        values.dedup();
+
+        if values.len() == 1 {
+            Ok(SpecifierKind::Nth(values[0]))
+        } else {
+            Ok(SpecifierKind::ExplicitList(values))
+        }
+    }
+
+    fn format(&self, field: usize, kind: &SpecifierKind) -> Result<String, String> {
+        let not_representable = || {
+            Err(format!(
+                "{:?} of field '{}' can't be represented in cron",
+                kind, self.name
+            ))
+        };
+        let max_value = self.spec_max;
+        let list = |values: &[u32]| -> Result<String, String> {
+            if values.is_empty() {
+                return not_representable();
+            }
+            let mut cron_values = values
+                .iter()
+                .map(|value| self.to_cron_value(*value, field))
+                .collect::<Result<Vec<u32>, String>>()?;
+            cron_values.sort_unstable();
+            Ok(cron_values
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<String>>()
+                .join(","))
+        };
+
+        match kind {
+            SpecifierKind::All => Ok(String::from("*")),
+            SpecifierKind::None => not_representable(),
+            SpecifierKind::First => list(&[0]),
+            SpecifierKind::Last => list(&[max_value]),
+            SpecifierKind::Nth(n) => list(&[*n]),
+            // the last day of the range isn't the last day of the month
+            SpecifierKind::BackNth(_) if field == MONTHDAYS => not_representable(),
+            SpecifierKind::BackNth(n) if *n <= max_value => list(&[max_value - n]),
+            SpecifierKind::BackNth(_) => not_representable(),
+            SpecifierKind::ExplicitNths(values) | SpecifierKind::ExplicitList(values) => {
+                list(values)
+            }
+            SpecifierKind::EveryNth(0, offset) => list(&[*offset]),
+            // the conversion would reorder the values
+            SpecifierKind::EveryNth(n, offset) if field == DAY_OF_WEEK => {
+                let values: Vec<u32> = (*offset..=max_value).step_by(*n as usize).collect();
+                list(&values)
+            }
+            SpecifierKind::EveryNth(n, 0) => Ok(format!("*/{}", n)),
+            SpecifierKind::EveryNth(n, offset) => {
+                Ok(format!("{}/{}", self.to_cron_value(*offset, field)?, n))
+            }
+            SpecifierKind::Except(excluded) => {
+                // days that don't exist in any month are dropped as well
+                let values: Vec<u32> = (0..=max_value)
+                    .filter(|value| {
+                        !excluded.contains(value) && self.to_cron_value(*value, field).is_ok()
+                    })
+                    .collect();
+                list(&values)
+            }
+            SpecifierKind::Range(a, _) if *a > max_value => not_representable(),
+            SpecifierKind::Range(a, b) if a >= b => list(&[*a]),
+            SpecifierKind::Range(a, b) => {
+                // Sunday is written as 7, so that the range isn't reordered
+                let (start, end) = if field == DAY_OF_WEEK {
+                    (a + 1, b.min(&max_value) + 1)
+                } else {
+                    (
+                        self.to_cron_value(*a, field)?,
+                        (b + self.range.start()).min(*self.range.end()),
+                    )
+                };
+                Ok(format!("{}-{}", start, end))
+            }
+        }
+    }
+}
+
+impl Interval {
+    /// Creates an [Interval] from a cron expression with the five fields minute, hour, day of month, month and day of week.
+    ///
+    /// Supports `*`, single values, ranges (`1-5`), steps (`*/10`, `5/15`, `1-10/2`) and lists (`1,15,28`).
+    /// As in cron, day of week `0` and `7` are Sunday and if both day of month and day of week are restricted, only one of them has to match.
+    ///
+    /// # Returns
+    /// [Ok] containing the [Interval]. [Err] describing the issue if the expression is invalid.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    /// use chrono::NaiveDate;
+    ///
+    /// let workdays = Interval::from_cron_str("30 12 * * 1-5").unwrap();
+    /// let monday = NaiveDate::from_ymd_opt(2023, 5, 1).unwrap();
+    /// assert!(workdays.matches_datetime(monday.and_hms_opt(12, 30, 0).unwrap()) == DateTimeMatch::Ok);
+    /// let sunday = NaiveDate::from_ymd_opt(2023, 5, 7).unwrap();
+    /// assert!(workdays.matches_datetime(sunday.and_hms_opt(12, 30, 0).unwrap()) == DateTimeMatch::DateNotMatched);
+    ///
+    /// assert!(Interval::from_cron_str("60 * * * *").is_err());
+    /// assert!(Interval::from_cron_str("0 0 * * * *").is_err());
+    /// ```
+    pub fn from_cron_str(cron: &str) -> Result<Interval, String> {
+        let tokens: Vec<&str> = cron.split_whitespace().collect();
+        if tokens.len() != CRON_FIELDS.len() {
+            return Err(format!(
+                "Expected {} fields in cron expression {:?}. Got {}",
+                CRON_FIELDS.len(),
+                cron,
+                tokens.len()
+            ));
+        }
+
+        let mut kinds = vec![];
+        for (i, (field, token)) in CRON_FIELDS.iter().zip(tokens).enumerate() {
+            kinds.push(field.parse(i, token)?);
+        }
+        let mut kinds = kinds.into_iter();
+        let mut next_kind = || kinds.next().expect("one kind per cron field");
+
+        IntervalBuilder::default()
+            .minutes(next_kind())
+            .hours(next_kind())
+            .monthdays(next_kind())
+            .months(next_kind())
+            .weekdays(next_kind())
+            .build()
+            .map_err(|e| e.to_string())
+    }
+
+    /// Converts the interval into a cron expression with the five fields minute, hour, day of month, month and day of week.
+    /// Lists are written as comma separated values and [SpecifierKind::EveryNth] as steps.
+    ///
+    /// # Returns
+    /// [Ok] containing the cron expression. [Err] if the interval can't be represented in cron without losing information,
+    /// e.g., if `weeks` isn't [SpecifierKind::All] or a specifier matches nothing.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// let interval = IntervalBuilder::default()
+    ///     .minutes(SpecifierKind::EveryNth(15, 0))
+    ///     .hours(SpecifierKind::ExplicitList(vec![8, 12]))
+    ///     .weekdays(SpecifierKind::First)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(interval.to_cron_string().unwrap(), "*/15 8,12 * * 1");
+    ///
+    /// let even_weeks = IntervalBuilder::default()
+    ///     .weeks(SpecifierKind::EveryNth(2, 0))
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(even_weeks.to_cron_string().is_err());
+    /// ```
+    pub fn to_cron_string(&self) -> Result<String, String> {
+        if self.weeks.kind() != &SpecifierKind::All {
+            return Err(String::from(
+                "Week numbers can't be represented in cron",
+            ));
+        }
+        if self
+            .seconds
+            .as_ref()
+            .is_some_and(|seconds| seconds.kind() != &SpecifierKind::First)
+        {
+            return Err(String::from("Seconds can't be represented in cron"));
+        }
+        if !self.excluded_dates.is_empty() {
+            return Err(String::from("Excluded dates can't be represented in cron"));
+        }
+        if self.nth_weekday.is_some() {
+            return Err(String::from(
+                "The n-th weekday of a month can't be represented in cron",
+            ));
+        }
+
+        let kinds = [
+            self.minutes.kind(),
+            self.hours.kind(),
+            self.monthdays.kind(),
+            self.months.kind(),
+            self.weekdays.kind(),
+        ];
+        let fields = CRON_FIELDS
+            .iter()
+            .zip(kinds)
+            .enumerate()
+            .map(|(i, (field, kind))| field.format(i, kind))
+            .collect::<Result<Vec<String>, String>>()?;
+        Ok(fields.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod cron_tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::interval::DateTimeMatch;
+
+    fn kinds(interval: &Interval) -> [&SpecifierKind; 5] {
+        [
+            interval.minutes.kind(),
+            interval.hours.kind(),
+            interval.monthdays.kind(),
+            interval.months.kind(),
+            interval.weekdays.kind(),
+        ]
+    }
+
+    #[test]
+    fn every_minute() {
+        let interval = Interval::from_cron_str("* * * * *").unwrap();
+        assert_eq!(interval, IntervalBuilder::default().build().unwrap());
+    }
+
+    #[test]
+    fn single_values() {
+        let interval = Interval::from_cron_str("30 12 1 6 0").unwrap();
+        assert_eq!(
+            kinds(&interval),
+            [
+                &SpecifierKind::Nth(30),
+                &SpecifierKind::Nth(12),
+                &SpecifierKind::Nth(0),
+                &SpecifierKind::Nth(5),
+                &SpecifierKind::Nth(6),
+            ]
+        );
+        assert_eq!(interval.weeks.kind(), &SpecifierKind::All);
+    }
+
+    #[test]
+    fn sunday_is_0_and_7() {
+        assert_eq!(
+            Interval::from_cron_str("0 0 * * 0").unwrap(),
+            Interval::from_cron_str("0 0 * * 7").unwrap()
+        );
+    }
+
+    #[test]
+    fn ranges_and_lists() {
+        let interval = Interval::from_cron_str("0,15,45 8-10 1,15,28 1-3,12 1-5").unwrap();
+        assert_eq!(
+            kinds(&interval),
+            [
+                &SpecifierKind::ExplicitList(vec![0, 15, 45]),
+                &SpecifierKind::Range(8, 10),
+                &SpecifierKind::ExplicitList(vec![0, 14, 27]),
+                &SpecifierKind::ExplicitList(vec![0, 1, 2, 11]),
+                &SpecifierKind::Range(0, 4),
+            ]
+        );
+
+        // Sunday to Tuesday isn't a range of weekdays starting on Monday
+        let interval = Interval::from_cron_str("* * * * 0-2").unwrap();
+        assert_eq!(
+            interval.weekdays.kind(),
+            &SpecifierKind::ExplicitList(vec![0, 1, 6])
+        );
+        let interval = Interval::from_cron_str("* * * * 5-7").unwrap();
+        assert_eq!(interval.weekdays.kind(), &SpecifierKind::Range(4, 6));
+
+        assert!(Interval::from_cron_str("* 10-8 * * *").is_err());
+    }
+
+    #[test]
+    fn steps() {
+        let interval = Interval::from_cron_str("*/10 5/6 */2 3/3 */2").unwrap();
+        assert_eq!(
+            kinds(&interval),
+            [
+                &SpecifierKind::EveryNth(10, 0),
+                &SpecifierKind::EveryNth(6, 5),
+                &SpecifierKind::EveryNth(2, 0),
+                &SpecifierKind::EveryNth(3, 2),
+                // Sunday, Tuesday, Thursday, Saturday
+                &SpecifierKind::ExplicitList(vec![1, 3, 5, 6]),
+            ]
+        );
+
+        let interval = Interval::from_cron_str("10-20/5 * * * 5-7/2").unwrap();
+        assert_eq!(
+            interval.minutes.kind(),
+            &SpecifierKind::ExplicitList(vec![10, 15, 20])
+        );
+        assert_eq!(
+            interval.weekdays.kind(),
+            &SpecifierKind::ExplicitList(vec![4, 6])
+        );
+    }
+
+    #[test]
+    fn matches_like_cron() {
+        // at 04:05 on every day-of-month 13 and on Friday
+        let interval = Interval::from_cron_str("5 4 13 * 5").unwrap();
+        let at = |y, m, d| {
+            NaiveDate::from_ymd_opt(y, m, d)
+                .unwrap()
+                .and_hms_opt(4, 5, 0)
+                .unwrap()
+        };
+        // Saturday 13th
+        assert_eq!(interval.matches_datetime(at(2023, 5, 13)), DateTimeMatch::Ok);
+        // Friday 5th
+        assert_eq!(interval.matches_datetime(at(2023, 5, 5)), DateTimeMatch::Ok);
+        // Thursday 4th
+        assert_eq!(
+            interval.matches_datetime(at(2023, 5, 4)),
+            DateTimeMatch::DateNotMatched
+        );
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        for cron in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * 32 * *",
+            "* * * 0 *",
+            "* * * 13 *",
+            "* * * * 8",
+            "a * * * *",
+            "-1 * * * *",
+            "*/0 * * * *",
+            "*/x * * * *",
+            "5-1 * * * *",
+            "1,,2 * * * *",
+            "1-2-3 * * * *",
+            "* * * JAN *",
+        ] {
+            assert!(Interval::from_cron_str(cron).is_err(), "{:?}", cron);
+        }
+    }
+
+    #[test]
+    fn to_cron_string() {
+        for (interval, cron) in [
+            (IntervalBuilder::default().build().unwrap(), "* * * * *"),
+            (Interval::daily(30, 12).unwrap(), "30 12 * * *"),
+            (
+                IntervalBuilder::default()
+                    .minutes(SpecifierKind::First)
+                    .hours(SpecifierKind::Last)
+                    .monthdays(SpecifierKind::ExplicitNths(vec![0, 14]))
+                    .months(SpecifierKind::Last)
+                    .build()
+                    .unwrap(),
+                "0 23 1,15 12 *",
+            ),
+            (
+                IntervalBuilder::default()
+                    .minutes(SpecifierKind::EveryNth(20, 5))
+                    .hours(SpecifierKind::BackNth(1))
+                    .months(SpecifierKind::EveryNth(0, 3))
+                    .build()
+                    .unwrap(),
+                "5/20 22 * 4 *",
+            ),
+            (
+                IntervalBuilder::default()
+                    .minutes(SpecifierKind::Nth(0))
+                    .hours(SpecifierKind::Nth(0))
+                    .weekdays(SpecifierKind::Last)
+                    .build()
+                    .unwrap(),
+                "0 0 * * 0",
+            ),
+            (
+                IntervalBuilder::default()
+                    .weekdays(SpecifierKind::EveryNth(2, 1))
+                    .build()
+                    .unwrap(),
+                "* * * * 2,4,6",
+            ),
+            (
+                IntervalBuilder::default()
+                    .weekdays(SpecifierKind::ExplicitList(vec![0, 6]))
+                    .build()
+                    .unwrap(),
+                "* * * * 0,1",
+            ),
+            (
+                IntervalBuilder::default()
+                    .hours(SpecifierKind::Range(8, 17))
+                    .monthdays(SpecifierKind::Range(0, 31))
+                    .weekdays(SpecifierKind::Range(4, 6))
+                    .build()
+                    .unwrap(),
+                "* 8-17 1-31 * 5-7",
+            ),
+            (
+                IntervalBuilder::default()
+                    .hours(SpecifierKind::Except(vec![0, 1, 2, 3, 4, 5, 6, 23]))
+                    .weekdays(SpecifierKind::Except(vec![6]))
+                    .build()
+                    .unwrap(),
+                "* 7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22 * * 1,2,3,4,5,6",
+            ),
+        ] {
+            assert_eq!(interval.to_cron_string().unwrap(), cron);
+        }
+    }
+
+    #[test]
+    fn not_representable() {
+        for interval in [
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::None)
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .weeks(SpecifierKind::Nth(3))
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .monthdays(SpecifierKind::BackNth(0))
+                .build()
+                .unwrap(),
+            // only matches day 32
+            IntervalBuilder::default()
+                .monthdays(SpecifierKind::Last)
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .hours(SpecifierKind::Nth(24))
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .hours(SpecifierKind::BackNth(24))
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::ExplicitList(vec![]))
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .months(SpecifierKind::EveryNth(2, 12))
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .monthdays(SpecifierKind::Range(31, 31))
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .weekdays(SpecifierKind::Nth(0))
+                .nth_weekday(0)
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::Except((0..60).collect()))
+                .build()
+                .unwrap(),
+        ] {
+            assert!(interval.to_cron_string().is_err(), "{:?}", interval);
+        }
+    }
+
+    #[test]
+    fn roundtrip_cron_strings() {
+        for cron in [
+            "* * * * *",
+            "0 0 1 1 *",
+            "30 12 * * 1-5",
+            "*/10 */2 */3 */4 *",
+            "5/15 3/6 2/10 2/5 *",
+            "0,15,45 8-10 1,15,28 1-3,12 0,6",
+            "0 8-17 1-15 4-9 1-5",
+            "59 23 31 12 0",
+            "0 4 13 * 5",
+        ] {
+            let interval = Interval::from_cron_str(cron).unwrap();
+            let roundtrip =
+                Interval::from_cron_str(&interval.to_cron_string().unwrap()).unwrap();
+            assert_eq!(roundtrip, interval, "{:?}", cron);
+        }
+    }
+
+    #[test]
+    fn roundtrip_representable_intervals() {
+        // kinds that are parsed back into the same kind
+        let kinds = |max: u32| {
+            vec![
+                SpecifierKind::All,
+                SpecifierKind::Nth(0),
+                SpecifierKind::Nth(max),
+                SpecifierKind::ExplicitList(vec![0, 1, max]),
+                SpecifierKind::EveryNth(2, 0),
+                SpecifierKind::EveryNth(3, 1),
+                SpecifierKind::Range(1, max),
+            ]
+        };
+        let weekday_kinds = [
+            SpecifierKind::All,
+            SpecifierKind::Nth(0),
+            SpecifierKind::Nth(6),
+            SpecifierKind::ExplicitList(vec![0, 2, 6]),
+            SpecifierKind::Range(0, 4),
+            SpecifierKind::Range(3, 6),
+        ];
+
+        for minutes in kinds(59) {
+            for hours in kinds(23) {
+                for monthdays in kinds(30) {
+                    for months in kinds(11) {
+                        for weekdays in weekday_kinds.iter() {
+                            let interval = IntervalBuilder::default()
+                                .minutes(minutes.clone())
+                                .hours(hours.clone())
+                                .monthdays(monthdays.clone())
+                                .months(months.clone())
+                                .weekdays(weekdays.clone())
+                                .build()
+                                .unwrap();
+                            let cron = interval.to_cron_string().unwrap();
+                            assert_eq!(
+                                Interval::from_cron_str(&cron).unwrap(),
+                                interval,
+                                "{:?}",
+                                cron
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip_matches_same_datetimes() {
+        // kinds that are parsed back into different, but equivalent kinds
+        let interval = IntervalBuilder::default()
+            .minutes(SpecifierKind::First)
+            .hours(SpecifierKind::ExplicitNths(vec![6, 18]))
+            .monthdays(SpecifierKind::ExplicitList(vec![9]))
+            .weekdays(SpecifierKind::EveryNth(3, 0))
+            .build()
+            .unwrap();
+        let roundtrip = Interval::from_cron_str(&interval.to_cron_string().unwrap()).unwrap();
+
+        let start = NaiveDate::from_ymd_opt(2023, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        assert_eq!(interval.upcoming(start, 50), roundtrip.upcoming(start, 50));
+    }
+
+    #[test]
+    fn descriptive_errors() {
+        let err = Interval::from_cron_str("* * * * * *").unwrap_err();
+        assert!(err.contains("Expected 5 fields"), "{}", err);
+        let err = Interval::from_cron_str("* 25 * * *").unwrap_err();
+        assert!(err.contains("'hour'") && err.contains("\"25\""), "{}", err);
+        let err = Interval::from_cron_str("*/0 * * * *").unwrap_err();
+        assert!(err.contains("step") && err.contains("'minute'"), "{}", err);
+    }
+}
@@ -0,0 +1,318 @@
+//! Contains [MatchExplanation] struct that describes why a datetime is or isn't matched by an [Interval].
+
+use std::fmt::Display;
+
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+use super::{Interval, Month, Specifier, SpecifierKind, Weekday};
+
+/// Describes whether a single [Specifier] of an [Interval] matched.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct FieldExplanation {
+    /// Value of the datetime that was checked. Weekdays, monthdays, weeks and months are 0-based.
+    pub value: u32,
+    /// Rule of the [Specifier]
+    pub rule: SpecifierKind,
+    /// Whether the [Specifier] matched `value`
+    pub matched: bool,
+}
+
+/// Describes how the weekday and the monthday are combined.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DayRule {
+    /// Both weekday and monthday have to match
+    Both,
+    /// Weekday or monthday has to match. Applies if neither of them is [SpecifierKind::All].
+    Either,
+}
+
+/// Describes for every [Specifier] of an [Interval] whether it matched a given datetime.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct MatchExplanation {
+    /// The explained datetime
+    pub datetime: NaiveDateTime,
+    /// Only set if the [Interval] has a seconds [Specifier]
+    #[serde(default)]
+    pub seconds: Option<FieldExplanation>,
+    pub minutes: FieldExplanation,
+    pub hours: FieldExplanation,
+    pub weekdays: FieldExplanation,
+    pub monthdays: FieldExplanation,
+    pub weeks: FieldExplanation,
+    pub months: FieldExplanation,
+    /// How `weekdays` and `monthdays` were combined
+    pub day_rule: DayRule,
+    /// Whether the date is one of the excluded dates
+    #[serde(default)]
+    pub excluded: bool,
+    /// Whether the date was matched
+    pub date_matched: bool,
+    /// Whether the time of day was matched
+    pub time_matched: bool,
+}
+
+impl MatchExplanation {
+    /// Whether the whole datetime was matched
+    pub fn matched(&self) -> bool {
+        self.date_matched && self.time_matched
+    }
+}
+
+fn explain_field<T>(specifier: &Specifier<T>, value: T) -> FieldExplanation
+where
+    T: Into<u32> + From<u32> + Copy,
+{
+    FieldExplanation {
+        value: value.into(),
+        rule: specifier.kind().clone(),
+        matched: specifier.matches(value),
+    }
+}
+
+impl Interval {
+    /// Explains for every [Specifier] whether it matches the given `datetime`.
+    /// The result always agrees with [Interval::matches_datetime].
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    /// use chrono::NaiveDate;
+    ///
+    /// let daily = Interval::daily(0, 3).unwrap();
+    /// let saturday = NaiveDate::from_ymd_opt(2023, 5, 6).unwrap()
+    ///     .and_hms_opt(3, 30, 0).unwrap();
+    ///
+    /// let explanation = daily.explain_match(saturday);
+    /// assert!(explanation.hours.matched);
+    /// assert!(!explanation.minutes.matched);
+    /// assert!(explanation.date_matched);
+    /// assert!(!explanation.matched());
+    /// ```
+    pub fn explain_match(&self, datetime: NaiveDateTime) -> MatchExplanation {
+        let date = datetime.date();
+        let time = datetime.time();
+
+        let seconds = self
+            .seconds
+            .as_ref()
+            .map(|seconds| explain_field(seconds, time.second()));
+        let minutes = explain_field(&self.minutes, time.minute());
+        let hours = explain_field(&self.hours, time.hour());
+        let mut weekdays = explain_field(
+            &self.weekdays,
+            Weekday::from(date.weekday().num_days_from_monday()),
+        );
+        weekdays.matched = self.matches_weekday(date);
+        let monthdays = explain_field(&self.monthdays, date.day0());
+        let weeks = explain_field(&self.weeks, date.iso_week().week0());
+        let months = explain_field(&self.months, Month::from(date.month0()));
+
+        let day_rule = if self.weekdays.kind() != &SpecifierKind::All
+            && self.monthdays.kind() != &SpecifierKind::All
+        {
+            DayRule::Either
+        } else {
+            DayRule::Both
+        };
+        let day_matched = match day_rule {
+            DayRule::Either => weekdays.matched || monthdays.matched,
+            DayRule::Both => weekdays.matched && monthdays.matched,
+        };
+
+        let excluded = self.is_excluded(date);
+
+        MatchExplanation {
+            datetime,
+            date_matched: day_matched && weeks.matched && months.matched && !excluded,
+            time_matched: seconds.as_ref().is_none_or(|seconds| seconds.matched)
+                && minutes.matched
+                && hours.matched,
+            seconds,
+            minutes,
+            hours,
+            weekdays,
+            monthdays,
+            weeks,
+            months,
+            day_rule,
+            excluded,
+        }
+    }
+}
+
+impl Display for MatchExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let matched = |matched: bool| if matched { "yes" } else { "no" };
+
+        writeln!(f, "{}", self.datetime)?;
+        writeln!(f, "{:<10} {:>5}  {:<7}  rule", "field", "value", "matched")?;
+        let fields = [
+            ("minutes", &self.minutes),
+            ("hours", &self.hours),
+            ("weekdays", &self.weekdays),
+            ("monthdays", &self.monthdays),
+            ("weeks", &self.weeks),
+            ("months", &self.months),
+        ];
+        let seconds = self.seconds.iter().map(|seconds| ("seconds", seconds));
+        for (name, field) in seconds.chain(fields) {
+            writeln!(
+                f,
+                "{:<10} {:>5}  {:<7}  {:?}",
+                name,
+                field.value,
+                matched(field.matched),
+                field.rule
+            )?;
+        }
+        match self.day_rule {
+            DayRule::Both => writeln!(f, "weekdays and monthdays have to match")?,
+            DayRule::Either => writeln!(f, "weekdays or monthdays have to match")?,
+        }
+        if self.excluded {
+            writeln!(f, "date is excluded")?;
+        }
+        writeln!(f, "date matched: {}", matched(self.date_matched))?;
+        write!(f, "time matched: {}", matched(self.time_matched))
+    }
+}
+
+#[cfg(test)]
+mod explanation_tests {
+    use chrono::{Days, NaiveDate};
+
+    use super::*;
+    use crate::interval::{DateTimeMatch, IntervalBuilder};
+
+    fn intervals() -> Vec<Interval> {
+        vec![
+            IntervalBuilder::default().build().unwrap(),
+            Interval::daily(30, 3).unwrap(),
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::First)
+                .hours(SpecifierKind::EveryNth(6, 1))
+                .weekdays(SpecifierKind::Last)
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::First)
+                .hours(SpecifierKind::First)
+                .weekdays(SpecifierKind::ExplicitNths(vec![0, 6]))
+                .monthdays(SpecifierKind::Nth(12))
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .monthdays(SpecifierKind::BackNth(0))
+                .weeks(SpecifierKind::EveryNth(2, 0))
+                .months(SpecifierKind::ExplicitList(vec![0, 5, 11]))
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::None)
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .excluded_dates(vec![NaiveDate::from_ymd_opt(2023, 1, 2).unwrap()])
+                .build()
+                .unwrap(),
+            IntervalBuilder::default()
+                .seconds(SpecifierKind::EveryNth(30, 0))
+                .hours(SpecifierKind::Nth(12))
+                .build()
+                .unwrap(),
+        ]
+    }
+
+    #[test]
+    fn agrees_with_matches_datetime() {
+        let start = NaiveDate::from_ymd_opt(2023, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        for interval in intervals() {
+            for day in 0..400 {
+                for (hour, minute) in [(0, 0), (1, 0), (3, 30), (7, 0), (12, 59), (23, 0)] {
+                    let datetime = start
+                        .checked_add_days(Days::new(day))
+                        .unwrap()
+                        .with_hour(hour)
+                        .unwrap()
+                        .with_minute(minute)
+                        .unwrap()
+                        .with_second(minute % 2 * 30)
+                        .unwrap();
+                    let explanation = interval.explain_match(datetime);
+                    let expected = interval.matches_datetime(datetime);
+
+                    assert_eq!(explanation.date_matched, interval.matches_date(datetime.date()));
+                    assert_eq!(explanation.time_matched, interval.matches_time(datetime.time()));
+                    assert_eq!(explanation.matched(), expected == DateTimeMatch::Ok);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn either_day_rule() {
+        let interval = &intervals()[3];
+        // Friday, 13th
+        let datetime = NaiveDate::from_ymd_opt(2023, 10, 13)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let explanation = interval.explain_match(datetime);
+        assert_eq!(explanation.day_rule, DayRule::Either);
+        assert!(!explanation.weekdays.matched);
+        assert!(explanation.monthdays.matched);
+        assert_eq!(explanation.weekdays.value, 4);
+        assert_eq!(explanation.monthdays.value, 12);
+        assert!(explanation.matched());
+    }
+
+    #[test]
+    fn both_day_rule() {
+        let interval = &intervals()[2];
+        // Saturday
+        let datetime = NaiveDate::from_ymd_opt(2023, 5, 6)
+            .unwrap()
+            .and_hms_opt(7, 0, 0)
+            .unwrap();
+        let explanation = interval.explain_match(datetime);
+        assert_eq!(explanation.day_rule, DayRule::Both);
+        assert!(!explanation.weekdays.matched);
+        assert!(explanation.monthdays.matched);
+        assert!(!explanation.date_matched);
+        assert!(explanation.time_matched);
+        assert_eq!(explanation.hours.rule, SpecifierKind::EveryNth(6, 1));
+    }
+
+    #[test]
+    fn table() {
+        let datetime = NaiveDate::from_ymd_opt(2023, 5, 6)
+            .unwrap()
+            .and_hms_opt(3, 0, 0)
+            .unwrap();
+        let table = Interval::daily(30, 3)
+            .unwrap()
+            .explain_match(datetime)
+            .to_string();
+        assert!(table.contains("minutes        0  no       Nth(30)"));
+        assert!(table.contains("hours          3  yes      Nth(3)"));
+        assert!(table.contains("time matched: no"));
+    }
+
+    #[test]
+    fn serialize() {
+        let datetime = NaiveDate::from_ymd_opt(2023, 5, 6)
+            .unwrap()
+            .and_hms_opt(3, 0, 0)
+            .unwrap();
+        let explanation = Interval::daily(30, 3).unwrap().explain_match(datetime);
+        let json = serde_json::to_string(&explanation).unwrap();
+        assert!(json.contains("\"day_rule\":\"both\""));
+        let parsed: MatchExplanation = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, explanation);
+    }
+}
@@ -1,14 +1,35 @@
 //! Contains struct for Months
 
-use serde::{Serialize, Deserialize};
+use serde::{Deserialize, Serialize, Serializer};
+
+use super::specifier::NamedValue;
 
 /// Number of months.
 /// `N-1` is the largest number, a `try_from` will work with.
 pub const N: u8 = 12;
 
+/// English names of the months starting with January
+const NAMES: [&str; N as usize] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
 /// Struct for representing a Month.
 /// Allows to associate numbers with days of the week starting with January as 0 and ending with December as 12
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+///
+/// A month is serialized with its english name and can be deserialized from its name, its abbreviated name, its number or the former `{"month": n}` form.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Deserialize)]
+#[serde(try_from = "MonthRepr")]
 pub struct Month {
     month: u8
 }
@@ -79,6 +100,72 @@ impl Month {
     pub const fn December() -> Month {
         Month::new(11)
     }
+
+    /// Returns the month with the given english name.
+    /// The name is case-insensitive and can be abbreviated to its first three letters.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// assert_eq!(Month::from_name("February"), Some(Month::February()));
+    /// assert_eq!(Month::from_name("feb"), Some(Month::February()));
+    /// assert_eq!(Month::from_name("Febr"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Month> {
+        NAMES
+            .iter()
+            .position(|full| full.eq_ignore_ascii_case(name) || full[..3].eq_ignore_ascii_case(name))
+            .map(|month| Month::new(month as u8))
+    }
+
+    /// Returns the english name of the month
+    pub fn name(&self) -> &'static str {
+        NAMES[self.month as usize]
+    }
+}
+
+impl Serialize for Month {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.name())
+    }
+}
+
+/// All forms a [Month] can be deserialized from
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MonthRepr {
+    Struct { month: u8 },
+    Number(u32),
+    Name(String),
+}
+
+impl TryFrom<MonthRepr> for Month {
+    type Error = String;
+
+    fn try_from(value: MonthRepr) -> Result<Self, Self::Error> {
+        match value {
+            MonthRepr::Struct { month } => Ok(Month::new(month)),
+            MonthRepr::Number(month) if month < N as u32 => Ok(Month::new(month as u8)),
+            MonthRepr::Number(month) => Err(format!("{} is not a month", month)),
+            MonthRepr::Name(name) => {
+                Month::from_name(&name).ok_or_else(|| format!("{:?} is not a month", name))
+            }
+        }
+    }
+}
+
+impl NamedValue for Month {
+    fn value_of_name(name: &str) -> Option<u32> {
+        Month::from_name(name).map(|month| month.into())
+    }
+
+    fn name_of_value(value: u32) -> Option<&'static str> {
+        NAMES.get(value as usize).copied()
+    }
 }
 
 impl Into<u32> for Month {
@@ -158,4 +245,18 @@ mod months_tests {
         assert_eq!(Month::July(), Month::from(18));
         assert_eq!(Month::September(), Month::from(20));
     }
+
+    #[test]
+    fn deserialize() {
+        let parse = |json: &str| serde_json::from_str::<Month>(json);
+        assert_eq!(parse(r#"{"month": 1}"#).unwrap(), Month::February());
+        assert_eq!(parse("1").unwrap(), Month::February());
+        assert_eq!(parse(r#""February""#).unwrap(), Month::February());
+        assert_eq!(parse(r#""Feb""#).unwrap(), Month::February());
+        assert!(parse("12").is_err());
+        assert!(parse(r#""Brumaire""#).is_err());
+
+        assert_eq!(serde_json::to_string(&Month::December()).unwrap(), r#""December""#);
+        assert_eq!(parse(&serde_json::to_string(&Month::December()).unwrap()).unwrap(), Month::December());
+    }
 }
\ No newline at end of file
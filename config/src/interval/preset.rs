@@ -0,0 +1,370 @@
+//! Contains [IntervalPreset] enum that describes commonly used [Interval]s in a simpler form.
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    Interval, IntervalBuilder, SpecifierKind, Weekday, HOURS_RANGE, MINUTES_RANGE,
+    MONTHDAYS_RANGE,
+};
+
+/// Simplified description of commonly used [Interval]s.
+/// Use [Interval::classify] to check if an [Interval] corresponds to a preset and [Interval::from_preset] to create one.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IntervalPreset {
+    /// Every day at `hour`:`minute`
+    Daily { hour: u32, minute: u32 },
+    /// Every week on `weekday` at `hour`:`minute`
+    Weekly {
+        weekday: Weekday,
+        hour: u32,
+        minute: u32,
+    },
+    /// Every month on the `day`-th (starting at 1) at `hour`:`minute`
+    Monthly { day: u32, hour: u32, minute: u32 },
+    /// Every `n` minutes, starting at the full hour
+    EveryNMinutes { n: u32 },
+    /// Doesn't correspond to any of the other presets
+    Custom,
+}
+
+impl Interval {
+    /// Checks which [IntervalPreset] the interval corresponds to.
+    /// The classification is conservative: If the interval doesn't exactly look like one created by [Interval::from_preset], [IntervalPreset::Custom] is returned.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// let daily = Interval::daily(30, 12).unwrap();
+    /// assert_eq!(daily.classify(), IntervalPreset::Daily { hour: 12, minute: 30 });
+    ///
+    /// let every_minute = IntervalBuilder::default().build().unwrap();
+    /// assert_eq!(every_minute.classify(), IntervalPreset::Custom);
+    /// ```
+    pub fn classify(&self) -> IntervalPreset {
+        let all = &SpecifierKind::All;
+        if self.weeks.kind() != all
+            || self.months.kind() != all
+            || self.nth_weekday.is_some()
+            || !self.excluded_dates.is_empty()
+        {
+            return IntervalPreset::Custom;
+        }
+        if self
+            .seconds
+            .as_ref()
+            .is_some_and(|seconds| seconds.kind() != &SpecifierKind::First)
+        {
+            return IntervalPreset::Custom;
+        }
+
+        match (
+            self.minutes.kind(),
+            self.hours.kind(),
+            self.weekdays.kind(),
+            self.monthdays.kind(),
+        ) {
+            (SpecifierKind::EveryNth(n, 0), hours, weekdays, monthdays)
+                if hours == all && weekdays == all && monthdays == all =>
+            {
+                IntervalPreset::EveryNMinutes { n: *n }
+            }
+            (SpecifierKind::Nth(minute), SpecifierKind::Nth(hour), weekdays, monthdays)
+                if weekdays == all && monthdays == all =>
+            {
+                IntervalPreset::Daily {
+                    hour: *hour,
+                    minute: *minute,
+                }
+            }
+            (
+                SpecifierKind::Nth(minute),
+                SpecifierKind::Nth(hour),
+                SpecifierKind::Nth(weekday),
+                monthdays,
+            ) if monthdays == all => IntervalPreset::Weekly {
+                weekday: Weekday::from(*weekday),
+                hour: *hour,
+                minute: *minute,
+            },
+            (
+                SpecifierKind::Nth(minute),
+                SpecifierKind::Nth(hour),
+                weekdays,
+                SpecifierKind::Nth(day0),
+            ) if weekdays == all => IntervalPreset::Monthly {
+                day: day0 + 1,
+                hour: *hour,
+                minute: *minute,
+            },
+            _ => IntervalPreset::Custom,
+        }
+    }
+
+    /// Creates the [Interval] described by the given [IntervalPreset].
+    ///
+    /// # Returns
+    /// [Ok] containing the [Interval]. [Err] describing the issue if a value is out of range or [IntervalPreset::Custom] was given.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// let preset = IntervalPreset::Weekly { weekday: Weekday::Friday(), hour: 18, minute: 0 };
+    /// let interval = Interval::from_preset(&preset).unwrap();
+    /// assert_eq!(interval.classify(), preset);
+    ///
+    /// assert!(Interval::from_preset(&IntervalPreset::Daily { hour: 24, minute: 0 }).is_err());
+    /// ```
+    pub fn from_preset(preset: &IntervalPreset) -> Result<Interval, String> {
+        let mut builder = IntervalBuilder::default();
+        match preset {
+            IntervalPreset::Daily { hour, minute } => {
+                Self::check_time(*hour, *minute)?;
+                builder
+                    .minutes(SpecifierKind::Nth(*minute))
+                    .hours(SpecifierKind::Nth(*hour));
+            }
+            IntervalPreset::Weekly {
+                weekday,
+                hour,
+                minute,
+            } => {
+                Self::check_time(*hour, *minute)?;
+                builder
+                    .minutes(SpecifierKind::Nth(*minute))
+                    .hours(SpecifierKind::Nth(*hour))
+                    .weekdays(SpecifierKind::Nth((*weekday).into()));
+            }
+            IntervalPreset::Monthly { day, hour, minute } => {
+                Self::check_time(*hour, *minute)?;
+                if *day < 1 || *day > *MONTHDAYS_RANGE.end() {
+                    return Err(format!(
+                        "Expect 'day' to be in range 1..={}. Got {}",
+                        MONTHDAYS_RANGE.end(),
+                        day
+                    ));
+                }
+                builder
+                    .minutes(SpecifierKind::Nth(*minute))
+                    .hours(SpecifierKind::Nth(*hour))
+                    .monthdays(SpecifierKind::Nth(day - 1));
+            }
+            IntervalPreset::EveryNMinutes { n } => {
+                if *n < 1 || *n > *MINUTES_RANGE.end() {
+                    return Err(format!(
+                        "Expect 'n' to be in range 1..={}. Got {}",
+                        MINUTES_RANGE.end(),
+                        n
+                    ));
+                }
+                builder.minutes(SpecifierKind::EveryNth(*n, 0));
+            }
+            IntervalPreset::Custom => {
+                return Err(String::from(
+                    "A custom interval can't be created from a preset",
+                ))
+            }
+        }
+
+        builder.build().map_err(|e| e.to_string())
+    }
+
+    fn check_time(hour: u32, minute: u32) -> Result<(), String> {
+        if !MINUTES_RANGE.contains(&minute) {
+            return Err(format!(
+                "Expect 'minute' to be in range {:?}. Got {}",
+                MINUTES_RANGE, minute
+            ));
+        }
+        if !HOURS_RANGE.contains(&hour) {
+            return Err(format!(
+                "Expect 'hour' to be in range {:?}. Got {}",
+                HOURS_RANGE, hour
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod preset_tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_daily() {
+        for hour in HOURS_RANGE {
+            for minute in MINUTES_RANGE {
+                let preset = IntervalPreset::Daily { hour, minute };
+                assert_eq!(Interval::from_preset(&preset).unwrap().classify(), preset);
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip_weekly() {
+        for weekday in 0..7 {
+            for (hour, minute) in [(0, 0), (12, 30), (23, 59)] {
+                let preset = IntervalPreset::Weekly {
+                    weekday: Weekday::from(weekday),
+                    hour,
+                    minute,
+                };
+                assert_eq!(Interval::from_preset(&preset).unwrap().classify(), preset);
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip_monthly() {
+        for day in 1..=31 {
+            for (hour, minute) in [(0, 0), (3, 15), (23, 59)] {
+                let preset = IntervalPreset::Monthly { day, hour, minute };
+                assert_eq!(Interval::from_preset(&preset).unwrap().classify(), preset);
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip_every_n_minutes() {
+        for n in 1..=59 {
+            let preset = IntervalPreset::EveryNMinutes { n };
+            assert_eq!(Interval::from_preset(&preset).unwrap().classify(), preset);
+        }
+    }
+
+    #[test]
+    fn daily_constructor() {
+        assert_eq!(
+            Interval::daily(15, 7).unwrap().classify(),
+            IntervalPreset::Daily { hour: 7, minute: 15 }
+        );
+    }
+
+    #[test]
+    fn invalid_presets() {
+        for preset in [
+            IntervalPreset::Daily { hour: 24, minute: 0 },
+            IntervalPreset::Daily { hour: 0, minute: 60 },
+            IntervalPreset::Weekly {
+                weekday: Weekday::Monday(),
+                hour: 25,
+                minute: 0,
+            },
+            IntervalPreset::Monthly {
+                day: 0,
+                hour: 0,
+                minute: 0,
+            },
+            IntervalPreset::Monthly {
+                day: 33,
+                hour: 0,
+                minute: 0,
+            },
+            IntervalPreset::EveryNMinutes { n: 0 },
+            IntervalPreset::EveryNMinutes { n: 60 },
+            IntervalPreset::Custom,
+        ] {
+            assert!(Interval::from_preset(&preset).is_err(), "{:?}", preset);
+        }
+    }
+
+    #[test]
+    fn custom_edge_cases() {
+        let custom = [
+            // every minute
+            IntervalBuilder::default().build().unwrap(),
+            // never
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::None)
+                .build()
+                .unwrap(),
+            // First instead of Nth(0)
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::First)
+                .hours(SpecifierKind::First)
+                .build()
+                .unwrap(),
+            // every hour
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::Nth(0))
+                .build()
+                .unwrap(),
+            // daily, but only in January
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::Nth(0))
+                .hours(SpecifierKind::Nth(3))
+                .months(SpecifierKind::Nth(0))
+                .build()
+                .unwrap(),
+            // daily, but only in even weeks
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::Nth(0))
+                .hours(SpecifierKind::Nth(3))
+                .weeks(SpecifierKind::EveryNth(2, 0))
+                .build()
+                .unwrap(),
+            // first Monday of the month
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::Nth(0))
+                .hours(SpecifierKind::Nth(3))
+                .weekdays(SpecifierKind::Nth(0))
+                .nth_weekday(0)
+                .build()
+                .unwrap(),
+            // weekly on two days
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::Nth(0))
+                .hours(SpecifierKind::Nth(3))
+                .weekdays(SpecifierKind::ExplicitNths(vec![0, 3]))
+                .build()
+                .unwrap(),
+            // weekday or monthday
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::Nth(0))
+                .hours(SpecifierKind::Nth(3))
+                .weekdays(SpecifierKind::Nth(0))
+                .monthdays(SpecifierKind::Nth(0))
+                .build()
+                .unwrap(),
+            // last day of the month
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::Nth(0))
+                .hours(SpecifierKind::Nth(3))
+                .monthdays(SpecifierKind::Last)
+                .build()
+                .unwrap(),
+            // every 10 minutes with an offset
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::EveryNth(10, 5))
+                .build()
+                .unwrap(),
+            // every 10 minutes during a single hour
+            IntervalBuilder::default()
+                .minutes(SpecifierKind::EveryNth(10, 0))
+                .hours(SpecifierKind::Nth(3))
+                .build()
+                .unwrap(),
+        ];
+
+        for interval in custom {
+            assert_eq!(interval.classify(), IntervalPreset::Custom, "{:?}", interval);
+        }
+    }
+
+    #[test]
+    fn serialize() {
+        let preset = IntervalPreset::Daily { hour: 3, minute: 0 };
+        let json = serde_json::to_string(&preset).unwrap();
+        assert_eq!(json, r#"{"kind":"daily","hour":3,"minute":0}"#);
+        assert_eq!(
+            serde_json::from_str::<IntervalPreset>(&json).unwrap(),
+            preset
+        );
+        assert_eq!(
+            serde_json::from_str::<IntervalPreset>(r#"{"kind":"custom"}"#).unwrap(),
+            IntervalPreset::Custom
+        );
+    }
+}
@@ -1,12 +1,15 @@
 //! Contains [Specifier] Struct that can be used to specify certain numbers from a range.
 //! For example, can be used to always take the first or the last or every `n`-th with an offset.
 
-use serde::{Serialize, Deserialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// Represents a specifier.
 /// Has a range of possible values and a specifier rule that filters out all none-specified values in that range.
 /// The represented range is inclusive. I.e., `min` and `max` can be matched by the specifier-rule as well.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// If `T` has names (see [NamedValue]), the values of the specifier kind are serialized by name, e.g., `{"ExplicitNths": ["Monday", "Friday"]}` for weekdays.
+/// When deserializing, they can be given by number or by (abbreviated) name, e.g., `{"ExplicitNths": [0, "Fri"]}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Specifier<T>
 where
     T: Into<u32> + From<u32> + Copy,
@@ -161,6 +164,49 @@ pub enum SpecifierKind {
     /// assert_eq!(spec.cyclic_next(1024), Some(128));
     /// ```
     ExplicitList(Vec<u32>),
+
+    /// All elements from `min + a` up to and including `min + b`
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// let spec = Specifier::new(0 as u32, 23 as u32, SpecifierKind::Range(8, 17));
+    /// assert!(!spec.matches(7));
+    /// assert!(spec.matches(8));
+    /// assert!(spec.matches(12));
+    /// assert!(spec.matches(17));
+    /// assert!(!spec.matches(18));
+    ///
+    /// assert_eq!(spec.cyclic_next(3), Some(8));
+    /// assert_eq!(spec.cyclic_next(8), Some(9));
+    /// assert_eq!(spec.cyclic_next(17), Some(8));
+    /// assert_eq!(spec.cyclic_next(20), Some(8));
+    /// ```
+    Range(u32, u32),
+
+    /// All elements except the ones with `min + i` for `i` in `vec`.
+    /// If every element is excluded, it behaves like [SpecifierKind::None].
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// // every day except Sunday
+    /// let spec = Specifier::new(0 as u32, 6 as u32, SpecifierKind::Except(vec![6]));
+    /// assert!(spec.matches(0));
+    /// assert!(spec.matches(5));
+    /// assert!(!spec.matches(6));
+    ///
+    /// assert_eq!(spec.cyclic_next(4), Some(5));
+    /// assert_eq!(spec.cyclic_next(5), Some(0));
+    /// assert_eq!(spec.cyclic_next(6), Some(0));
+    ///
+    /// let spec = Specifier::new(0 as u32, 2 as u32, SpecifierKind::Except(vec![0, 1, 2]));
+    /// assert!(spec.is_none_specifier());
+    /// assert_eq!(spec.cyclic_next(1), None);
+    /// ```
+    Except(Vec<u32>),
 }
 
 impl<T> Specifier<T>
@@ -177,6 +223,11 @@ where
     ///
     /// If the `offset` for [SpecifierKind::EveryNth] exceeds that range, no element from the range will be matched.
     ///
+    /// The bounds of [SpecifierKind::Range] are swapped if `a > b` and `b` is clamped to the range.
+    /// If `a` exceeds the range, no element will be matched and `b` is set to `a`.
+    ///
+    /// [SpecifierKind::Except] is filtered, sorted and dedupped like [SpecifierKind::ExplicitNths].
+    ///
     /// # Example
     /// ```
     /// use config::interval::*;
@@ -207,22 +258,37 @@ where
                 values.dedup();
                 values
             }),
-            SpecifierKind::ExplicitNths(indices) => SpecifierKind::ExplicitNths({
+            SpecifierKind::ExplicitNths(indices) => {
+                SpecifierKind::ExplicitNths(Self::filter_indices(min, max, indices))
+            }
+            SpecifierKind::Except(indices) => {
+                SpecifierKind::Except(Self::filter_indices(min, max, indices))
+            }
+            SpecifierKind::Range(mut a, mut b) => {
+                if a > b {
+                    std::mem::swap(&mut a, &mut b);
+                }
                 let max_index = max.into() - min.into();
-                let mut indices: Vec<u32> = indices
-                    .into_iter()
-                    .filter(|index| index <= &max_index)
-                    .collect();
-                indices.sort_unstable();
-                indices.dedup();
-                indices
-            }),
+                b = if a <= max_index { b.min(max_index) } else { a };
+                SpecifierKind::Range(a, b)
+            }
             other => other,
         };
 
         Specifier { min, max, kind }
     }
 
+    fn filter_indices(min: T, max: T, indices: Vec<u32>) -> Vec<u32> {
+        let max_index = max.into() - min.into();
+        let mut indices: Vec<u32> = indices
+            .into_iter()
+            .filter(|index| index <= &max_index)
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
     pub fn min(&self) -> T {
         self.min
     }
@@ -239,6 +305,107 @@ where
         &self.kind
     }
 
+    /// Returns if the specifier is [SpecifierKind::None] or an [SpecifierKind::Except] that excludes every element of the range
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// assert!(Specifier::new(0u32, 3u32, SpecifierKind::None).is_none_specifier());
+    /// assert!(Specifier::new(0u32, 3u32, SpecifierKind::Except(vec![0, 1, 2, 3])).is_none_specifier());
+    /// assert!(!Specifier::new(0u32, 3u32, SpecifierKind::Except(vec![0, 1, 3])).is_none_specifier());
+    /// ```
+    pub fn is_none_specifier(&self) -> bool {
+        match &self.kind {
+            SpecifierKind::None => true,
+            SpecifierKind::Except(indices) => indices.len() as u32 >= self.range_len(),
+            _ => false,
+        }
+    }
+
+    /// Returns an equivalent [Specifier] whose kind is rewritten into a canonical form.
+    /// Two specifiers with the same range match the same elements if their canonical forms are equal.
+    ///
+    /// The rewrites are:
+    /// - `Nth(0)` and `BackNth(len - 1)` become [SpecifierKind::First]
+    /// - `Nth(len - 1)` and `BackNth(0)` become [SpecifierKind::Last]
+    /// - other `BackNth(n)` become `Nth(len - 1 - n)`
+    /// - [SpecifierKind::ExplicitList] becomes [SpecifierKind::ExplicitNths]
+    /// - `ExplicitNths` with a single element become `Nth`, with all elements [SpecifierKind::All]
+    /// - `EveryNth(1, 0)` becomes [SpecifierKind::All], `EveryNth` with a single match becomes `Nth`
+    /// - `Range` over the whole range becomes [SpecifierKind::All], `Range(a, a)` becomes `Nth(a)`
+    /// - `Except` without elements becomes [SpecifierKind::All]
+    /// - everything that can't match any element becomes [SpecifierKind::None]
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// let nth = Specifier::new(0u32, 59u32, SpecifierKind::Nth(0));
+    /// let first = Specifier::new(0u32, 59u32, SpecifierKind::First);
+    /// assert_ne!(nth, first);
+    /// assert_eq!(nth.canonicalize(), first);
+    /// ```
+    pub fn canonicalize(&self) -> Specifier<T> {
+        let len = self.range_len();
+        let last = len - 1;
+        let kind = match &self.kind {
+            SpecifierKind::Nth(n) => Self::canonical_nth(*n, last),
+            SpecifierKind::BackNth(n) if *n <= last => Self::canonical_nth(last - n, last),
+            SpecifierKind::BackNth(_) => SpecifierKind::None,
+            SpecifierKind::ExplicitNths(indices) => Self::canonical_indices(indices.clone(), len),
+            SpecifierKind::ExplicitList(values) => Self::canonical_indices(
+                values
+                    .iter()
+                    .filter(|value| **value >= self.min.into())
+                    .map(|value| value - self.min.into())
+                    .collect(),
+                len,
+            ),
+            SpecifierKind::EveryNth(_, offset) if *offset > last => SpecifierKind::None,
+            SpecifierKind::EveryNth(1, 0) => SpecifierKind::All,
+            SpecifierKind::EveryNth(n, offset) if *n == 0 || offset + n > last => {
+                Self::canonical_nth(*offset, last)
+            }
+            SpecifierKind::Range(a, _) if *a > last => SpecifierKind::None,
+            SpecifierKind::Range(0, b) if *b >= last => SpecifierKind::All,
+            SpecifierKind::Range(a, b) if a == b => Self::canonical_nth(*a, last),
+            SpecifierKind::Except(indices) if indices.is_empty() => SpecifierKind::All,
+            SpecifierKind::Except(_) if self.is_none_specifier() => SpecifierKind::None,
+            other => other.clone(),
+        };
+
+        Specifier {
+            min: self.min,
+            max: self.max,
+            kind,
+        }
+    }
+
+    fn canonical_nth(n: u32, last: u32) -> SpecifierKind {
+        if n == 0 {
+            SpecifierKind::First
+        } else if n == last {
+            SpecifierKind::Last
+        } else if n > last {
+            SpecifierKind::None
+        } else {
+            SpecifierKind::Nth(n)
+        }
+    }
+
+    fn canonical_indices(mut indices: Vec<u32>, len: u32) -> SpecifierKind {
+        indices.retain(|index| index < &len);
+        indices.sort_unstable();
+        indices.dedup();
+        match indices.len() {
+            0 => SpecifierKind::None,
+            1 => Self::canonical_nth(indices[0], len - 1),
+            n if n as u32 == len => SpecifierKind::All,
+            _ => SpecifierKind::ExplicitNths(indices),
+        }
+    }
+
     /// Returns if `x` is contained in the range.
     /// Ignores the specifier-rule.
     ///
@@ -272,6 +439,21 @@ where
         self.cyclic_next(self.max)
     }
 
+    /// Returns the last (largest) element from the range that is matched, ot none if there is no match
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    /// let spec = Specifier::new(10u32, 20u32, SpecifierKind::EveryNth(3, 1));
+    /// assert_eq!(spec.last_match().unwrap(), 20);
+    ///
+    /// let spec = Specifier::new(10u32, 20u32, SpecifierKind::EveryNth(100, 400));
+    /// assert!(spec.last_match().is_none());
+    /// ```
+    pub fn last_match(&self) -> Option<T> {
+        self.cyclic_prev(self.min)
+    }
+
     /// Returns if `x` is matches by the given specifier-rule
     ///
     /// # Example
@@ -343,17 +525,15 @@ where
             SpecifierKind::All => true,
             SpecifierKind::First => x.into() == self.min.into(),
             SpecifierKind::Last => x.into() == self.max.into(),
-            SpecifierKind::Nth(n) => self.min.into() + n == x.into(),
-            SpecifierKind::BackNth(n) => self.max.into() - n == x.into(),
-            SpecifierKind::ExplicitNths(indices) => {
-                indices.iter().any(|n| self.min.into() + n == x.into())
-            }
+            SpecifierKind::Nth(n) => x.into() - self.min.into() == *n,
+            SpecifierKind::BackNth(n) => self.max.into().checked_sub(*n) == Some(x.into()),
+            SpecifierKind::ExplicitNths(indices) => indices.contains(&(x.into() - self.min.into())),
             SpecifierKind::EveryNth(n, offset) => {
                 // if offset is out of range or larger than x -> we can't match
-                let min_offset = self.min.into() + offset;
-                if min_offset > self.max.into() || min_offset > x.into() {
-                    return false;
-                }
+                let min_offset = match self.every_nth_start(*offset) {
+                    Some(min_offset) if min_offset <= x.into() => min_offset,
+                    _ => return false,
+                };
                 // if n == 0 we can only match, when x == min + offset
                 if n == &0 {
                     return x.into() == min_offset;
@@ -363,9 +543,46 @@ where
                 (x.into() - min_offset).rem_euclid(*n) == 0
             }
             SpecifierKind::ExplicitList(values) => values.contains(&x.into()),
+            SpecifierKind::Range(a, b) => {
+                // comparing indices, because `min + b` can overflow
+                let index = x.into() - self.min.into();
+                a <= &index && &index <= b
+            }
+            SpecifierKind::Except(indices) => !indices.contains(&(x.into() - self.min.into())),
         }
     }
 
+    /// Returns an iterator over all matched elements of the range in ascending order.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// let spec = Specifier::new(0u32, 10u32, SpecifierKind::EveryNth(3, 1));
+    /// assert_eq!(spec.iter_matches().collect::<Vec<u32>>(), vec![1, 4, 7, 10]);
+    ///
+    /// let spec = Specifier::new(0u32, 10u32, SpecifierKind::ExplicitList(vec![]));
+    /// assert_eq!(spec.iter_matches().next(), None);
+    /// ```
+    pub fn iter_matches(&self) -> impl Iterator<Item = T> + '_ {
+        (self.min.into()..=self.max.into())
+            .map(T::from)
+            .filter(move |x| self.matches(*x))
+    }
+
+    /// Returns the number of matched elements of the range.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// assert_eq!(Specifier::new(0u32, 59u32, SpecifierKind::EveryNth(15, 0)).count_matches(), 4);
+    /// assert_eq!(Specifier::new(0u32, 59u32, SpecifierKind::EveryNth(0, 7)).count_matches(), 1);
+    /// ```
+    pub fn count_matches(&self) -> usize {
+        self.iter_matches().count()
+    }
+
     /// Returns the next specified element from the range, if one exists.
     ///
     /// # Example
@@ -396,22 +613,11 @@ where
             )),
             SpecifierKind::First => Some(self.min),
             SpecifierKind::Last => Some(self.max),
-            SpecifierKind::Nth(n) => {
-                let val = self.min.into() + n;
-                if val <= self.max.into() {
-                    Some(T::from(val))
-                } else {
-                    None
-                }
-            }
-            SpecifierKind::BackNth(n) => {
-                let val = self.max.into() - n;
-                if val >= self.min.into() {
-                    Some(T::from(val))
-                } else {
-                    None
-                }
-            }
+            SpecifierKind::Nth(n) => self.nth_value(*n),
+            SpecifierKind::BackNth(n) => match self.max.into().checked_sub(*n) {
+                Some(val) if val >= self.min.into() => Some(T::from(val)),
+                _ => None,
+            },
             SpecifierKind::ExplicitNths(indices) => {
                 // if there are no indices -> return None
                 if indices.is_empty() {
@@ -434,28 +640,18 @@ where
             }
             SpecifierKind::EveryNth(n, offset) => {
                 // if n == 0 return self.min + offset
-                // else exists i from R such that self.min + offset + i * n = x
-                // ==> find i -> if i < 0 return self.min + offset
-                // ==> add .5 to i and round -> we alway end at the next larger int even if i is a perfect int
-                // ==> return self.min + offset + i_round_up * n if <= self.max; else return self.min + offset
-                let min_offset = self.min.into() + offset;
-                if min_offset > self.max.into() {
-                    return None;
-                }
-                if n == &0 {
-                    return Some(T::from(min_offset));
-                }
-                let i = (x.into() as f64 - min_offset as f64) / (*n as f64);
-                if i < 0.0 {
+                // else the next match is the multiple of n following x - (self.min + offset)
+                // ==> if it leaves the range, wrap around to self.min + offset
+                let min_offset = self.every_nth_start(*offset)?;
+                if n == &0 || x.into() < min_offset {
                     return Some(T::from(min_offset));
                 }
-                let i_round_up = (i + 0.5).round() as u32;
-                let candidate = min_offset + i_round_up * n;
-                let val = if candidate <= self.max.into() {
-                    candidate
-                } else {
-                    min_offset
-                };
+                let passed = (x.into() - min_offset) / n * n;
+                let val = passed
+                    .checked_add(*n)
+                    .and_then(|distance| min_offset.checked_add(distance))
+                    .filter(|candidate| candidate <= &self.max.into())
+                    .unwrap_or(min_offset);
                 Some(T::from(val))
             }
             SpecifierKind::ExplicitList(values) => {
@@ -475,8 +671,316 @@ where
                 };
                 Some(T::from(val))
             }
+            SpecifierKind::Range(a, b) => {
+                let (first, last) = self.range_bounds(*a, *b)?;
+                let val = if x.into() < first || x.into() >= last {
+                    first
+                } else {
+                    x.into() + 1
+                };
+                Some(T::from(val))
+            }
+            SpecifierKind::Except(indices) => {
+                // walk through the range starting after x until an element isn't excluded
+                let index = x.into() - self.min.into();
+                (1..=self.range_len())
+                    .map(|i| (index + i) % self.range_len())
+                    .find(|index| !indices.contains(index))
+                    .map(|index| T::from(self.min.into() + index))
+            }
         }
     }
+
+    /// Returns the previous specified element from the range, if one exists.
+    /// Counterpart to [Specifier::cyclic_next].
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// let a = 0 as u32;
+    /// let b = 10 as u32;
+    ///
+    /// let none_spec = Specifier::new(a, b, SpecifierKind::None);
+    /// assert_eq!(none_spec.cyclic_prev(5 as u32), None);
+    ///
+    /// let all_spec = Specifier::new(a, b, SpecifierKind::All);
+    /// assert_eq!(all_spec.cyclic_prev(0 as u32), Some(10));
+    ///
+    /// let even_spec = Specifier::new(a, b, SpecifierKind::EveryNth(2, 0));
+    /// assert_eq!(even_spec.cyclic_prev(4 as u32), Some(2));
+    /// ```
+    pub fn cyclic_prev(&self, x: T) -> Option<T> {
+        if !self.is_in_range(x) {
+            return None;
+        }
+
+        match &self.kind {
+            SpecifierKind::None => None,
+            SpecifierKind::All => Some(T::from(
+                self.min.into()
+                    + (x.into() - self.min.into() + self.range_len() - 1) % self.range_len(),
+            )),
+            SpecifierKind::First => Some(self.min),
+            SpecifierKind::Last => Some(self.max),
+            SpecifierKind::Nth(n) => self.nth_value(*n),
+            SpecifierKind::BackNth(n) => match self.max.into().checked_sub(*n) {
+                Some(val) if val >= self.min.into() => Some(T::from(val)),
+                _ => None,
+            },
+            SpecifierKind::ExplicitNths(indices) => {
+                // if there are no indices -> return None
+                if indices.is_empty() {
+                    return None;
+                }
+                // find the last n for which we are smaller than x
+                // if there is none -> use the last n
+                let n = indices
+                    .iter()
+                    .rev()
+                    .find(|n| self.min.into() + *n < x.into())
+                    .unwrap_or(&indices[indices.len() - 1]);
+                Some(T::from(self.min.into() + n))
+            }
+            SpecifierKind::EveryNth(n, offset) => {
+                // if n == 0 return self.min + offset
+                // else find the largest i with self.min + offset + i * n < x
+                // ==> if there is none, return the last match in the range
+                let min_offset = self.every_nth_start(*offset)?;
+                if n == &0 {
+                    return Some(T::from(min_offset));
+                }
+                let val = if x.into() > min_offset {
+                    min_offset + (x.into() - min_offset - 1) / n * n
+                } else {
+                    min_offset + (self.max.into() - min_offset) / n * n
+                };
+                Some(T::from(val))
+            }
+            SpecifierKind::ExplicitList(values) => {
+                // if there are no elements -> return None
+                if values.is_empty() {
+                    return None;
+                }
+                // find the index of the first value that is not smaller than x
+                let target_index = match values.binary_search(&x.into()) {
+                    Ok(index) => index,
+                    Err(index) => index,
+                };
+                let val = match target_index.checked_sub(1) {
+                    Some(index) => values[index],
+                    None => values[values.len() - 1],
+                };
+                Some(T::from(val))
+            }
+            SpecifierKind::Range(a, b) => {
+                let (first, last) = self.range_bounds(*a, *b)?;
+                let val = if x.into() <= first || x.into() > last {
+                    last
+                } else {
+                    x.into() - 1
+                };
+                Some(T::from(val))
+            }
+            SpecifierKind::Except(indices) => {
+                // walk backwards through the range starting before x until an element isn't excluded
+                let index = x.into() - self.min.into();
+                (1..=self.range_len())
+                    .map(|i| (index + self.range_len() - i) % self.range_len())
+                    .find(|index| !indices.contains(index))
+                    .map(|index| T::from(self.min.into() + index))
+            }
+        }
+    }
+
+    /// Returns the first element matched by [SpecifierKind::EveryNth] with the given `offset`, if it lies in the range
+    fn every_nth_start(&self, offset: u32) -> Option<u32> {
+        let min: u32 = self.min.into();
+        min.checked_add(offset)
+            .filter(|start| start <= &self.max.into())
+    }
+
+    /// Returns the value matched by [SpecifierKind::Nth], if it lies in the range
+    fn nth_value(&self, n: u32) -> Option<T> {
+        let min: u32 = self.min.into();
+        min.checked_add(n)
+            .filter(|val| val <= &self.max.into())
+            .map(T::from)
+    }
+
+    /// Returns the first and last value matched by [SpecifierKind::Range] or [None] if the range starts after `max`
+    fn range_bounds(&self, a: u32, b: u32) -> Option<(u32, u32)> {
+        let max_index = self.max.into() - self.min.into();
+        if a > max_index {
+            return None;
+        }
+        Some((self.min.into() + a, self.min.into() + b.min(max_index)))
+    }
+}
+
+/// Values of a [Specifier] that can be referred to by name
+pub trait NamedValue {
+    /// Returns the value with the given name, if there is one
+    fn value_of_name(_name: &str) -> Option<u32> {
+        None
+    }
+
+    /// Returns the name the given value is serialized with, if it has one
+    fn name_of_value(_value: u32) -> Option<&'static str> {
+        None
+    }
+}
+
+impl NamedValue for u32 {}
+
+/// A value of a (de)serialized [SpecifierKind] that is either a number or a name
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawValue {
+    Number(u32),
+    Name(String),
+}
+
+/// [SpecifierKind] as it is (de)serialized, i.e., with names instead of the values and indices that have one
+#[derive(Serialize, Deserialize)]
+enum RawSpecifierKind {
+    None,
+    All,
+    First,
+    Last,
+    Nth(RawValue),
+    BackNth(u32),
+    ExplicitNths(Vec<RawValue>),
+    EveryNth(u32, u32),
+    ExplicitList(Vec<RawValue>),
+    Range(RawValue, RawValue),
+    Except(Vec<RawValue>),
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawSpecifier<T> {
+    min: T,
+    max: T,
+    kind: RawSpecifierKind,
+}
+
+impl<T> RawSpecifier<T>
+where
+    T: Into<u32> + From<u32> + Copy + NamedValue,
+{
+    /// Resolves `value` to the value it names
+    fn value(&self, value: RawValue) -> Result<u32, String> {
+        match value {
+            RawValue::Number(value) => Ok(value),
+            RawValue::Name(name) => {
+                T::value_of_name(&name).ok_or_else(|| format!("Unknown value {:?}", name))
+            }
+        }
+    }
+
+    /// Resolves `value` to the offset of the value it names from `min`
+    fn index(&self, value: RawValue) -> Result<u32, String> {
+        match value {
+            RawValue::Number(index) => Ok(index),
+            name => self
+                .value(name)?
+                .checked_sub(self.min.into())
+                .ok_or_else(|| String::from("Named value is smaller than the minimum")),
+        }
+    }
+
+    fn indices(&self, values: Vec<RawValue>) -> Result<Vec<u32>, String> {
+        values.into_iter().map(|value| self.index(value)).collect()
+    }
+
+    /// Replaces the values and indices of the kind of `specifier` by their names, if they have one
+    fn named(specifier: &Specifier<T>) -> RawSpecifier<T> {
+        let min: u32 = specifier.min.into();
+        let value = |value: u32| match T::name_of_value(value) {
+            Some(name) => RawValue::Name(String::from(name)),
+            None => RawValue::Number(value),
+        };
+        let index = |index: u32| match min.checked_add(index).and_then(T::name_of_value) {
+            Some(name) => RawValue::Name(String::from(name)),
+            None => RawValue::Number(index),
+        };
+        let kind = match &specifier.kind {
+            SpecifierKind::None => RawSpecifierKind::None,
+            SpecifierKind::All => RawSpecifierKind::All,
+            SpecifierKind::First => RawSpecifierKind::First,
+            SpecifierKind::Last => RawSpecifierKind::Last,
+            SpecifierKind::Nth(n) => RawSpecifierKind::Nth(index(*n)),
+            SpecifierKind::BackNth(n) => RawSpecifierKind::BackNth(*n),
+            SpecifierKind::ExplicitNths(indices) => {
+                RawSpecifierKind::ExplicitNths(indices.iter().map(|n| index(*n)).collect())
+            }
+            SpecifierKind::EveryNth(n, offset) => RawSpecifierKind::EveryNth(*n, *offset),
+            SpecifierKind::ExplicitList(values) => {
+                RawSpecifierKind::ExplicitList(values.iter().map(|v| value(*v)).collect())
+            }
+            SpecifierKind::Range(a, b) => RawSpecifierKind::Range(index(*a), index(*b)),
+            SpecifierKind::Except(indices) => {
+                RawSpecifierKind::Except(indices.iter().map(|n| index(*n)).collect())
+            }
+        };
+
+        RawSpecifier {
+            min: specifier.min,
+            max: specifier.max,
+            kind,
+        }
+    }
+
+    fn resolve(mut self) -> Result<Specifier<T>, String> {
+        let kind = match std::mem::replace(&mut self.kind, RawSpecifierKind::None) {
+            RawSpecifierKind::None => SpecifierKind::None,
+            RawSpecifierKind::All => SpecifierKind::All,
+            RawSpecifierKind::First => SpecifierKind::First,
+            RawSpecifierKind::Last => SpecifierKind::Last,
+            RawSpecifierKind::Nth(value) => SpecifierKind::Nth(self.index(value)?),
+            RawSpecifierKind::BackNth(n) => SpecifierKind::BackNth(n),
+            RawSpecifierKind::ExplicitNths(values) => {
+                SpecifierKind::ExplicitNths(self.indices(values)?)
+            }
+            RawSpecifierKind::EveryNth(n, offset) => SpecifierKind::EveryNth(n, offset),
+            RawSpecifierKind::ExplicitList(values) => SpecifierKind::ExplicitList(
+                values
+                    .into_iter()
+                    .map(|value| self.value(value))
+                    .collect::<Result<Vec<u32>, String>>()?,
+            ),
+            RawSpecifierKind::Range(a, b) => SpecifierKind::Range(self.index(a)?, self.index(b)?),
+            RawSpecifierKind::Except(values) => SpecifierKind::Except(self.indices(values)?),
+        };
+
+        Ok(Specifier::new(self.min, self.max, kind))
+    }
+}
+
+impl<T> Serialize for Specifier<T>
+where
+    T: Into<u32> + From<u32> + Copy + NamedValue + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        RawSpecifier::named(self).serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Specifier<T>
+where
+    T: Into<u32> + From<u32> + Copy + NamedValue + Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        RawSpecifier::<T>::deserialize(deserializer)?
+            .resolve()
+            .map_err(serde::de::Error::custom)
+    }
 }
 
 #[cfg(test)]
@@ -548,6 +1052,29 @@ mod specifier_tests {
             let spec = Specifier::new(min, max, SpecifierKind::ExplicitList(values));
             assert_eq!(spec.kind(), &SpecifierKind::ExplicitList(dedupped));
         }
+
+        #[test]
+        fn clamp_range() {
+            let spec = Specifier::new(10_u32, 20_u32, SpecifierKind::Range(3, 7));
+            assert_eq!(spec.kind(), &SpecifierKind::Range(3, 7));
+
+            let spec = Specifier::new(10_u32, 20_u32, SpecifierKind::Range(7, 3));
+            assert_eq!(spec.kind(), &SpecifierKind::Range(3, 7));
+
+            let spec = Specifier::new(10_u32, 20_u32, SpecifierKind::Range(5, 40));
+            assert_eq!(spec.kind(), &SpecifierKind::Range(5, 10));
+
+            // starts after max -> end is set to the start and nothing is matched
+            let spec = Specifier::new(10_u32, 20_u32, SpecifierKind::Range(11, 40));
+            assert_eq!(spec.kind(), &SpecifierKind::Range(11, 11));
+            assert!((10..=20).all(|x| !spec.matches(x)));
+        }
+
+        #[test]
+        fn filter_except() {
+            let spec = Specifier::new(10_u32, 20_u32, SpecifierKind::Except(vec![7, 2, 30, 2]));
+            assert_eq!(spec.kind(), &SpecifierKind::Except(vec![2, 7]));
+        }
     }
 
     mod first_match_tests {
@@ -765,6 +1292,62 @@ mod specifier_tests {
             assert!(!spec.matches(19));
             assert!(!spec.matches(20));
         }
+
+        #[test]
+        fn range() {
+            let spec = Specifier::new(10u32, 20u32, SpecifierKind::Range(2, 5));
+            for x in 10..=20 {
+                assert_eq!(spec.matches(x), (12..=15).contains(&x));
+            }
+
+            let spec = Specifier::new(10u32, 20u32, SpecifierKind::Range(4, 4));
+            for x in 10..=20 {
+                assert_eq!(spec.matches(x), x == 14);
+            }
+        }
+
+        #[test]
+        fn except() {
+            let spec = Specifier::new(10u32, 20u32, SpecifierKind::Except(vec![0, 5, 10]));
+            for x in 10..=20 {
+                assert_eq!(spec.matches(x), ![10, 15, 20].contains(&x));
+            }
+
+            let spec = Specifier::new(10u32, 12u32, SpecifierKind::Except(vec![0, 1, 2]));
+            assert!((10..=12).all(|x| !spec.matches(x)));
+        }
+    }
+
+    #[test]
+    fn huge_range_bounds() {
+        let spec: Specifier<u32> =
+            serde_json::from_str(r#"{"min":1,"max":31,"kind":{"Range":[4294967295,4294967295]}}"#).unwrap();
+        assert!(!spec.matches(5));
+        assert_eq!(spec.cyclic_next(5), None);
+        assert_eq!(spec.cyclic_prev(5), None);
+
+        let spec = Specifier::new(1u32, 31u32, SpecifierKind::Range(40, u32::MAX));
+        assert_eq!(spec.kind(), &SpecifierKind::Range(40, 40));
+        assert!(!spec.matches(31));
+        let spec = Specifier::new(1u32, 31u32, SpecifierKind::Range(2, u32::MAX));
+        assert!(spec.matches(31));
+        assert_eq!(spec.cyclic_next(31), Some(3));
+
+        let spec = Specifier::new(1u32, 31u32, SpecifierKind::Nth(u32::MAX));
+        assert!(!spec.matches(31));
+        assert_eq!(spec.cyclic_next(31), None);
+        assert_eq!(spec.cyclic_prev(31), None);
+    }
+
+    #[test]
+    fn back_nth_beyond_max() {
+        let spec = Specifier::new(0u32, 59u32, SpecifierKind::BackNth(100));
+        assert_eq!(spec.first_match(), None);
+        assert_eq!(spec.cyclic_next(30), None);
+        assert_eq!(spec.last_match(), None);
+
+        let spec = Specifier::new(1u32, 31u32, SpecifierKind::BackNth(u32::MAX));
+        assert_eq!(spec.first_match(), None);
     }
 
     mod cyclic_next_tests {
@@ -1016,5 +1599,536 @@ mod specifier_tests {
             assert_eq!(spec.cyclic_next(960), Some(1000));
             assert_eq!(spec.cyclic_next(1000), Some(250));
         }
+
+        #[test]
+        fn range() {
+            let spec = Specifier::new(10_u32, 20_u32, SpecifierKind::Range(2, 5));
+            assert_eq!(spec.cyclic_next(10), Some(12));
+            assert_eq!(spec.cyclic_next(11), Some(12));
+            assert_eq!(spec.cyclic_next(12), Some(13));
+            assert_eq!(spec.cyclic_next(14), Some(15));
+            assert_eq!(spec.cyclic_next(15), Some(12));
+            assert_eq!(spec.cyclic_next(20), Some(12));
+
+            let spec = Specifier::new(10_u32, 20_u32, SpecifierKind::Range(0, 10));
+            assert_eq!(spec.cyclic_next(19), Some(20));
+            assert_eq!(spec.cyclic_next(20), Some(10));
+        }
+
+        #[test]
+        fn range_out_of_range() {
+            let spec = Specifier::new(10_u32, 20_u32, SpecifierKind::Range(11, 15));
+            for x in 10..=20 {
+                assert_eq!(spec.cyclic_next(x), None);
+            }
+        }
+
+        #[test]
+        fn except() {
+            let spec = Specifier::new(10_u32, 20_u32, SpecifierKind::Except(vec![0, 1, 5, 10]));
+            assert_eq!(spec.cyclic_next(10), Some(12));
+            assert_eq!(spec.cyclic_next(11), Some(12));
+            assert_eq!(spec.cyclic_next(14), Some(16));
+            assert_eq!(spec.cyclic_next(18), Some(19));
+            assert_eq!(spec.cyclic_next(19), Some(12));
+            assert_eq!(spec.cyclic_next(20), Some(12));
+            assert_eq!(spec.first_match(), Some(12));
+
+            // only one element left
+            let spec = Specifier::new(0_u32, 3_u32, SpecifierKind::Except(vec![0, 1, 3]));
+            for x in 0..=3 {
+                assert_eq!(spec.cyclic_next(x), Some(2));
+            }
+        }
+
+        #[test]
+        fn except_everything() {
+            let spec = Specifier::new(5_u32, 8_u32, SpecifierKind::Except(vec![0, 1, 2, 3]));
+            for x in 5..=8 {
+                assert_eq!(spec.cyclic_next(x), None);
+            }
+            assert_eq!(spec.first_match(), None);
+            assert!(spec.is_none_specifier());
+        }
+    }
+
+    mod every_nth_tests {
+        use super::*;
+
+        /// Smallest match larger than x or the smallest match at all
+        fn brute_force_next(spec: &Specifier<u32>, x: u32) -> Option<u32> {
+            (x + 1..=spec.max())
+                .find(|y| spec.matches(*y))
+                .or_else(|| (spec.min()..=spec.max()).find(|y| spec.matches(*y)))
+        }
+
+        #[test]
+        fn exhaustive_small_ranges() {
+            for min in 0..4 {
+                for max in min..min + 13 {
+                    for n in 0..8 {
+                        for offset in 0..16 {
+                            let spec = Specifier::new(min, max, SpecifierKind::EveryNth(n, offset));
+                            for x in min..=max {
+                                assert_eq!(
+                                    spec.cyclic_next(x),
+                                    brute_force_next(&spec, x),
+                                    "{:?} at {}",
+                                    spec,
+                                    x
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn large_values() {
+            let spec = Specifier::new(0, u32::MAX, SpecifierKind::EveryNth(3_000_000_000, 0));
+            assert_eq!(spec.cyclic_next(0), Some(3_000_000_000));
+            assert_eq!(spec.cyclic_next(3_000_000_000), Some(0));
+            assert_eq!(spec.cyclic_next(u32::MAX), Some(0));
+
+            let spec = Specifier::new(0, u32::MAX, SpecifierKind::EveryNth(1, 0));
+            assert_eq!(spec.cyclic_next(u32::MAX - 1), Some(u32::MAX));
+            assert_eq!(spec.cyclic_next(u32::MAX), Some(0));
+
+            let spec = Specifier::new(10, u32::MAX, SpecifierKind::EveryNth(2, u32::MAX));
+            assert_eq!(spec.cyclic_next(20), None);
+            assert!(!spec.matches(u32::MAX));
+            assert_eq!(spec.cyclic_prev(20), None);
+
+            let spec = Specifier::new(5, u32::MAX, SpecifierKind::EveryNth(2, u32::MAX - 5));
+            assert_eq!(spec.cyclic_next(5), Some(u32::MAX));
+            assert_eq!(spec.cyclic_next(u32::MAX), Some(u32::MAX));
+        }
+    }
+
+    mod cyclic_prev_tests {
+        use super::*;
+
+        #[test]
+        fn out_of_range_test() {
+            let spec = Specifier::new(25_u32, 50_u32, SpecifierKind::All);
+            assert_eq!(spec.cyclic_prev(0), None);
+            assert_eq!(spec.cyclic_prev(24), None);
+            assert_eq!(spec.cyclic_prev(51), None);
+        }
+
+        #[test]
+        fn none_test() {
+            let spec = Specifier::new(0_u32, 100_u32, SpecifierKind::None);
+            assert_eq!(spec.cyclic_prev(0), None);
+            assert_eq!(spec.cyclic_prev(19), None);
+            assert_eq!(spec.cyclic_prev(100), None);
+        }
+
+        #[test]
+        fn all_test() {
+            let spec = Specifier::new(0_u32, 10_u32, SpecifierKind::All);
+            for i in 1..=10 {
+                assert_eq!(spec.cyclic_prev(i), Some(i - 1));
+            }
+            assert_eq!(spec.cyclic_prev(0), Some(10));
+
+            let spec = Specifier::new(10_u32, 100_u32, SpecifierKind::All);
+            assert_eq!(spec.cyclic_prev(10), Some(100));
+            assert_eq!(spec.cyclic_prev(11), Some(10));
+            assert_eq!(spec.cyclic_prev(100), Some(99));
+        }
+
+        #[test]
+        fn single_element_kinds() {
+            let spec = Specifier::new(17_u32, 4325_u32, SpecifierKind::First);
+            assert_eq!(spec.cyclic_prev(17), Some(17));
+            assert_eq!(spec.cyclic_prev(453), Some(17));
+
+            let spec = Specifier::new(17_u32, 4325_u32, SpecifierKind::Last);
+            assert_eq!(spec.cyclic_prev(4325), Some(4325));
+            assert_eq!(spec.cyclic_prev(17), Some(4325));
+
+            let spec = Specifier::new(12_u32, 50_u32, SpecifierKind::Nth(10));
+            assert_eq!(spec.cyclic_prev(12), Some(22));
+            assert_eq!(spec.cyclic_prev(49), Some(22));
+
+            let spec = Specifier::new(12_u32, 50_u32, SpecifierKind::BackNth(10));
+            assert_eq!(spec.cyclic_prev(12), Some(40));
+            assert_eq!(spec.cyclic_prev(49), Some(40));
+        }
+
+        #[test]
+        fn nth_out_of_range() {
+            let spec = Specifier::new(0_u32, 10_u32, SpecifierKind::Nth(11));
+            assert_eq!(spec.cyclic_prev(5), None);
+
+            let spec = Specifier::new(5_u32, 10_u32, SpecifierKind::BackNth(6));
+            assert_eq!(spec.cyclic_prev(5), None);
+            let spec = Specifier::new(5_u32, 10_u32, SpecifierKind::BackNth(20));
+            assert_eq!(spec.cyclic_prev(5), None);
+        }
+
+        #[test]
+        fn explicit_nths() {
+            let spec = Specifier::new(
+                20_u32,
+                45_u32,
+                SpecifierKind::ExplicitNths(vec![0, 5, 10, 11, 15, 20]),
+            );
+            assert_eq!(spec.cyclic_prev(20), Some(40));
+            assert_eq!(spec.cyclic_prev(21), Some(20));
+            assert_eq!(spec.cyclic_prev(25), Some(20));
+            assert_eq!(spec.cyclic_prev(26), Some(25));
+            assert_eq!(spec.cyclic_prev(31), Some(30));
+            assert_eq!(spec.cyclic_prev(32), Some(31));
+            assert_eq!(spec.cyclic_prev(40), Some(35));
+            assert_eq!(spec.cyclic_prev(45), Some(40));
+        }
+
+        #[test]
+        fn explicit_lists_empty() {
+            let spec = Specifier::new(14_u32, 200_u32, SpecifierKind::ExplicitNths(vec![]));
+            assert_eq!(spec.cyclic_prev(14), None);
+            assert_eq!(spec.cyclic_prev(200), None);
+
+            let spec = Specifier::new(37_u32, 128_u32, SpecifierKind::ExplicitList(vec![]));
+            assert_eq!(spec.cyclic_prev(37), None);
+            assert_eq!(spec.cyclic_prev(128), None);
+        }
+
+        #[test]
+        fn explicit_list() {
+            let spec = Specifier::new(
+                1000_u32,
+                1500_u32,
+                SpecifierKind::ExplicitList(vec![1000, 1075, 1080, 1100, 1360, 1400, 1450]),
+            );
+            assert_eq!(spec.cyclic_prev(1000), Some(1450));
+            assert_eq!(spec.cyclic_prev(1001), Some(1000));
+            assert_eq!(spec.cyclic_prev(1075), Some(1000));
+            assert_eq!(spec.cyclic_prev(1077), Some(1075));
+            assert_eq!(spec.cyclic_prev(1360), Some(1100));
+            assert_eq!(spec.cyclic_prev(1450), Some(1400));
+            assert_eq!(spec.cyclic_prev(1500), Some(1450));
+        }
+
+        #[test]
+        fn every_0th() {
+            let spec = Specifier::new(
+                1000_u32,
+                1100_u32,
+                SpecifierKind::EveryNth(0_u32, 37_u32),
+            );
+            assert_eq!(spec.cyclic_prev(1000), Some(1037));
+            assert_eq!(spec.cyclic_prev(1037), Some(1037));
+            assert_eq!(spec.cyclic_prev(1100), Some(1037));
+        }
+
+        #[test]
+        fn every_nth_offset_too_big() {
+            let spec = Specifier::new(0_u32, 10_u32, SpecifierKind::EveryNth(1, 20));
+            for i in 0..=10 {
+                assert_eq!(spec.cyclic_prev(i), None);
+            }
+        }
+
+        #[test]
+        fn every_nth() {
+            let spec = Specifier::new(0_u32, 6_u32, SpecifierKind::EveryNth(2, 0));
+            assert_eq!(spec.cyclic_prev(0), Some(6));
+            assert_eq!(spec.cyclic_prev(1), Some(0));
+            assert_eq!(spec.cyclic_prev(2), Some(0));
+            assert_eq!(spec.cyclic_prev(3), Some(2));
+            assert_eq!(spec.cyclic_prev(6), Some(4));
+
+            let spec = Specifier::new(10_u32, 100_u32, SpecifierKind::EveryNth(10, 5));
+            assert_eq!(spec.cyclic_prev(10), Some(95));
+            assert_eq!(spec.cyclic_prev(15), Some(95));
+            assert_eq!(spec.cyclic_prev(16), Some(15));
+            assert_eq!(spec.cyclic_prev(25), Some(15));
+            assert_eq!(spec.cyclic_prev(100), Some(95));
+        }
+
+        #[test]
+        fn matches_brute_force() {
+            let kinds = vec![
+                SpecifierKind::None,
+                SpecifierKind::All,
+                SpecifierKind::First,
+                SpecifierKind::Last,
+                SpecifierKind::Nth(3),
+                SpecifierKind::BackNth(3),
+                SpecifierKind::ExplicitNths(vec![1, 4, 9]),
+                SpecifierKind::ExplicitList(vec![12, 14, 19]),
+                SpecifierKind::EveryNth(0, 2),
+                SpecifierKind::EveryNth(3, 0),
+                SpecifierKind::EveryNth(4, 3),
+                SpecifierKind::EveryNth(2, 30),
+                SpecifierKind::Range(0, 10),
+                SpecifierKind::Range(3, 6),
+                SpecifierKind::Range(8, 8),
+                SpecifierKind::Range(12, 15),
+                SpecifierKind::Except(vec![0, 4, 10]),
+                SpecifierKind::Except(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10]),
+                SpecifierKind::Except((0..=10).collect()),
+            ];
+            for kind in kinds {
+                let spec = Specifier::new(10_u32, 20_u32, kind);
+                for x in 10..=20 {
+                    // largest match smaller than x or the largest match at all
+                    let expected = (10..x)
+                        .rev()
+                        .find(|y| spec.matches(*y))
+                        .or_else(|| (10..=20).rev().find(|y| spec.matches(*y)));
+                    assert_eq!(spec.cyclic_prev(x), expected, "{:?} at {}", spec, x);
+                }
+                assert_eq!(
+                    spec.last_match(),
+                    (10..=20).rev().find(|y| spec.matches(*y))
+                );
+            }
+        }
+    }
+
+    mod canonicalize_tests {
+        use super::*;
+
+        fn canonical(kind: SpecifierKind) -> SpecifierKind {
+            Specifier::new(10_u32, 20_u32, kind).canonicalize().kind().clone()
+        }
+
+        #[test]
+        fn nth() {
+            assert_eq!(canonical(SpecifierKind::Nth(0)), SpecifierKind::First);
+            assert_eq!(canonical(SpecifierKind::Nth(10)), SpecifierKind::Last);
+            assert_eq!(canonical(SpecifierKind::Nth(11)), SpecifierKind::None);
+            assert_eq!(canonical(SpecifierKind::Nth(4)), SpecifierKind::Nth(4));
+        }
+
+        #[test]
+        fn back_nth() {
+            assert_eq!(canonical(SpecifierKind::BackNth(0)), SpecifierKind::Last);
+            assert_eq!(canonical(SpecifierKind::BackNth(10)), SpecifierKind::First);
+            assert_eq!(canonical(SpecifierKind::BackNth(11)), SpecifierKind::None);
+            assert_eq!(canonical(SpecifierKind::BackNth(3)), SpecifierKind::Nth(7));
+        }
+
+        #[test]
+        fn explicit_nths() {
+            assert_eq!(canonical(SpecifierKind::ExplicitNths(vec![])), SpecifierKind::None);
+            assert_eq!(canonical(SpecifierKind::ExplicitNths(vec![0])), SpecifierKind::First);
+            assert_eq!(canonical(SpecifierKind::ExplicitNths(vec![5])), SpecifierKind::Nth(5));
+            assert_eq!(
+                canonical(SpecifierKind::ExplicitNths((0..=10).collect())),
+                SpecifierKind::All
+            );
+            assert_eq!(
+                canonical(SpecifierKind::ExplicitNths(vec![1, 3])),
+                SpecifierKind::ExplicitNths(vec![1, 3])
+            );
+        }
+
+        #[test]
+        fn explicit_list() {
+            assert_eq!(canonical(SpecifierKind::ExplicitList(vec![])), SpecifierKind::None);
+            assert_eq!(canonical(SpecifierKind::ExplicitList(vec![20])), SpecifierKind::Last);
+            assert_eq!(
+                canonical(SpecifierKind::ExplicitList(vec![12, 14])),
+                SpecifierKind::ExplicitNths(vec![2, 4])
+            );
+            assert_eq!(
+                canonical(SpecifierKind::ExplicitList((10..=20).collect())),
+                SpecifierKind::All
+            );
+        }
+
+        #[test]
+        fn every_nth() {
+            assert_eq!(canonical(SpecifierKind::EveryNth(1, 0)), SpecifierKind::All);
+            assert_eq!(canonical(SpecifierKind::EveryNth(0, 3)), SpecifierKind::Nth(3));
+            assert_eq!(canonical(SpecifierKind::EveryNth(20, 0)), SpecifierKind::First);
+            assert_eq!(canonical(SpecifierKind::EveryNth(2, 11)), SpecifierKind::None);
+            assert_eq!(canonical(SpecifierKind::EveryNth(2, 1)), SpecifierKind::EveryNth(2, 1));
+        }
+
+        #[test]
+        fn range() {
+            assert_eq!(canonical(SpecifierKind::Range(0, 10)), SpecifierKind::All);
+            assert_eq!(canonical(SpecifierKind::Range(0, 30)), SpecifierKind::All);
+            assert_eq!(canonical(SpecifierKind::Range(4, 4)), SpecifierKind::Nth(4));
+            assert_eq!(canonical(SpecifierKind::Range(11, 12)), SpecifierKind::None);
+            assert_eq!(canonical(SpecifierKind::Range(2, 5)), SpecifierKind::Range(2, 5));
+        }
+
+        #[test]
+        fn except() {
+            assert_eq!(canonical(SpecifierKind::Except(vec![])), SpecifierKind::All);
+            assert_eq!(canonical(SpecifierKind::Except((0..=10).collect())), SpecifierKind::None);
+            assert_eq!(canonical(SpecifierKind::Except(vec![3])), SpecifierKind::Except(vec![3]));
+        }
+
+        #[test]
+        fn unchanged() {
+            for kind in [
+                SpecifierKind::None,
+                SpecifierKind::All,
+                SpecifierKind::First,
+                SpecifierKind::Last,
+            ] {
+                assert_eq!(canonical(kind.clone()), kind);
+            }
+        }
+
+        #[test]
+        fn same_matches() {
+            let kinds = vec![
+                SpecifierKind::Nth(0),
+                SpecifierKind::BackNth(3),
+                SpecifierKind::ExplicitNths(vec![4]),
+                SpecifierKind::ExplicitList(vec![12, 14, 19]),
+                SpecifierKind::EveryNth(0, 2),
+                SpecifierKind::EveryNth(1, 0),
+                SpecifierKind::EveryNth(8, 3),
+                SpecifierKind::Range(0, 10),
+                SpecifierKind::Range(8, 8),
+                SpecifierKind::Except(vec![]),
+            ];
+            for kind in kinds {
+                let spec = Specifier::new(10_u32, 20_u32, kind);
+                let canonical = spec.canonicalize();
+                for x in 10..=20 {
+                    assert_eq!(spec.matches(x), canonical.matches(x), "{:?} at {}", spec, x);
+                }
+            }
+        }
+    }
+
+    mod iter_matches_tests {
+        use super::*;
+        use crate::interval::Weekday;
+
+        /// Simple linear congruential generator, so the tests are reproducible
+        struct Lcg(u64);
+
+        impl Lcg {
+            fn next(&mut self, bound: u32) -> u32 {
+                self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                ((self.0 >> 33) % bound as u64) as u32
+            }
+
+            fn values(&mut self, bound: u32) -> Vec<u32> {
+                let len = self.next(6);
+                (0..len).map(|_| self.next(bound)).collect()
+            }
+
+            /// Returns an index that lies beyond the range of length `len` now and then, sometimes far beyond
+            fn index(&mut self, len: u32) -> u32 {
+                match self.next(5) {
+                    0 => u32::MAX - self.next(3),
+                    _ => self.next(len * 2),
+                }
+            }
+
+            fn kind(&mut self, len: u32) -> SpecifierKind {
+                match self.next(11) {
+                    0 => SpecifierKind::None,
+                    1 => SpecifierKind::All,
+                    2 => SpecifierKind::First,
+                    3 => SpecifierKind::Last,
+                    4 => SpecifierKind::Nth(self.index(len)),
+                    5 => SpecifierKind::BackNth(self.index(len)),
+                    6 => SpecifierKind::ExplicitNths(self.values(len * 2)),
+                    7 => SpecifierKind::EveryNth(self.next(len), self.index(len)),
+                    8 => SpecifierKind::ExplicitList(self.values(len * 3)),
+                    9 => SpecifierKind::Range(self.index(len), self.index(len)),
+                    _ => SpecifierKind::Except(self.values(len * 2)),
+                }
+            }
+        }
+
+        /// Computes the matches of `kind` in `min..=max` from the definition of each kind, without [Specifier::matches]
+        fn expected(min: u32, max: u32, kind: &SpecifierKind) -> Vec<u32> {
+            let (min, max) = (min as u64, max as u64);
+            let mut values: Vec<u64> = match kind {
+                SpecifierKind::None => vec![],
+                SpecifierKind::All => (min..=max).collect(),
+                SpecifierKind::First => vec![min],
+                SpecifierKind::Last => vec![max],
+                SpecifierKind::Nth(n) => vec![min + *n as u64],
+                SpecifierKind::BackNth(n) => max.checked_sub(*n as u64).into_iter().collect(),
+                SpecifierKind::ExplicitNths(indices) => indices.iter().map(|n| min + *n as u64).collect(),
+                SpecifierKind::EveryNth(0, offset) => vec![min + *offset as u64],
+                SpecifierKind::EveryNth(n, offset) => (min + *offset as u64..=max).step_by(*n as usize).collect(),
+                SpecifierKind::ExplicitList(values) => values.iter().map(|val| *val as u64).collect(),
+                SpecifierKind::Range(a, b) => (min + *a.min(b) as u64..=min + *a.max(b) as u64).take_while(|val| *val <= max).collect(),
+                SpecifierKind::Except(indices) => (min..=max).filter(|val| !indices.contains(&((val - min) as u32))).collect(),
+            };
+            values.retain(|val| (min..=max).contains(val));
+            values.sort_unstable();
+            values.dedup();
+            values.into_iter().map(|val| val as u32).collect()
+        }
+
+        #[test]
+        fn hand_computed() {
+            let cases = [
+                (0, 59, SpecifierKind::BackNth(100), vec![]),
+                (0, 59, SpecifierKind::BackNth(59), vec![0]),
+                (10, 20, SpecifierKind::BackNth(3), vec![17]),
+                (10, 20, SpecifierKind::Nth(11), vec![]),
+                (10, 20, SpecifierKind::Nth(u32::MAX), vec![]),
+                (10, 20, SpecifierKind::Nth(10), vec![20]),
+                (10, 20, SpecifierKind::Range(8, 100), vec![18, 19, 20]),
+                (10, 20, SpecifierKind::Range(3, 1), vec![11, 12, 13]),
+                (10, 20, SpecifierKind::Range(u32::MAX, u32::MAX), vec![]),
+                (10, 20, SpecifierKind::EveryNth(4, 1), vec![11, 15, 19]),
+                (10, 20, SpecifierKind::EveryNth(4, u32::MAX), vec![]),
+                (10, 20, SpecifierKind::ExplicitNths(vec![9, 0, 40]), vec![10, 19]),
+                (10, 20, SpecifierKind::ExplicitList(vec![9, 20, 12, 12]), vec![12, 20]),
+                (1, 5, SpecifierKind::Except(vec![0, 2, 9]), vec![2, 4, 5]),
+                (3, 3, SpecifierKind::Last, vec![3]),
+            ];
+            for (min, max, kind, matches) in cases {
+                let spec = Specifier::new(min, max, kind);
+                assert_eq!(spec.iter_matches().collect::<Vec<u32>>(), matches, "{:?}", spec);
+                assert_eq!(spec.count_matches(), matches.len(), "{:?}", spec);
+                assert_eq!(expected(min, max, spec.kind()), matches, "{:?}", spec);
+            }
+        }
+
+        #[test]
+        fn random_specifiers() {
+            let mut rng = Lcg(1756);
+            for _ in 0..2000 {
+                let min = rng.next(20);
+                let max = min + rng.next(40);
+                let kind = rng.kind(max - min + 1);
+                let expected = expected(min, max, &kind);
+                let spec = Specifier::new(min, max, kind);
+
+                assert_eq!(spec.iter_matches().collect::<Vec<u32>>(), expected, "{:?}", spec);
+                assert_eq!(spec.count_matches(), expected.len(), "{:?}", spec);
+            }
+        }
+
+        #[test]
+        fn edge_cases() {
+            let spec = Specifier::new(10_u32, 20_u32, SpecifierKind::EveryNth(0, 4));
+            assert_eq!(spec.iter_matches().collect::<Vec<u32>>(), vec![14]);
+
+            let spec = Specifier::new(10_u32, 20_u32, SpecifierKind::ExplicitNths(vec![]));
+            assert_eq!(spec.count_matches(), 0);
+
+            let spec = Specifier::new(10_u32, 20_u32, SpecifierKind::ExplicitList(vec![]));
+            assert_eq!(spec.count_matches(), 0);
+
+            let spec = Specifier::new(
+                Weekday::Monday(),
+                Weekday::Sunday(),
+                SpecifierKind::Except(vec![5, 6]),
+            );
+            assert_eq!(spec.iter_matches().last(), Some(Weekday::Friday()));
+        }
     }
 }
@@ -1,14 +1,30 @@
 //! Contains struct for Weekdays
 
-use serde::{Serialize, Deserialize};
+use serde::{Deserialize, Serialize, Serializer};
+
+use super::specifier::NamedValue;
 
 /// Number of weekdays.
 /// `N-1` is the largest number, a `try_from` will work with.
 pub const N: u8 = 7;
 
+/// English names of the weekdays starting with Monday
+const NAMES: [&str; N as usize] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
 /// Struct for representing a Weekday.
 /// Allows to associate numbers with days of the week starting with Monday as 0 and ending with Sunday as 6
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+///
+/// A weekday is serialized with its english name and can be deserialized from its name, its abbreviated name, its number or the former `{"day": n}` form.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Deserialize)]
+#[serde(try_from = "WeekdayRepr")]
 pub struct Weekday {
     day: u8
 }
@@ -84,6 +100,72 @@ impl Weekday {
     pub fn yesterday(self) -> Weekday {
         Weekday::new((7 + self.day - 1) % 7)
     }
+
+    /// Returns the weekday with the given english name.
+    /// The name is case-insensitive and can be abbreviated to its first three letters.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    ///
+    /// assert_eq!(Weekday::from_name("Monday"), Some(Weekday::Monday()));
+    /// assert_eq!(Weekday::from_name("fri"), Some(Weekday::Friday()));
+    /// assert_eq!(Weekday::from_name("Fr"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Weekday> {
+        NAMES
+            .iter()
+            .position(|full| full.eq_ignore_ascii_case(name) || full[..3].eq_ignore_ascii_case(name))
+            .map(|day| Weekday::new(day as u8))
+    }
+
+    /// Returns the english name of the weekday
+    pub fn name(&self) -> &'static str {
+        NAMES[self.day as usize]
+    }
+}
+
+impl Serialize for Weekday {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.name())
+    }
+}
+
+/// All forms a [Weekday] can be deserialized from
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WeekdayRepr {
+    Struct { day: u8 },
+    Number(u32),
+    Name(String),
+}
+
+impl TryFrom<WeekdayRepr> for Weekday {
+    type Error = String;
+
+    fn try_from(value: WeekdayRepr) -> Result<Self, Self::Error> {
+        match value {
+            WeekdayRepr::Struct { day } => Ok(Weekday::new(day)),
+            WeekdayRepr::Number(day) if day < N as u32 => Ok(Weekday::new(day as u8)),
+            WeekdayRepr::Number(day) => Err(format!("{} is not a weekday", day)),
+            WeekdayRepr::Name(name) => {
+                Weekday::from_name(&name).ok_or_else(|| format!("{:?} is not a weekday", name))
+            }
+        }
+    }
+}
+
+impl NamedValue for Weekday {
+    fn value_of_name(name: &str) -> Option<u32> {
+        Weekday::from_name(name).map(|day| day.into())
+    }
+
+    fn name_of_value(value: u32) -> Option<&'static str> {
+        NAMES.get(value as usize).copied()
+    }
 }
 
 impl Into<u32> for Weekday {
@@ -155,4 +237,18 @@ mod weekday_tests {
         assert_eq!(Weekday::Sunday(), Weekday::from(13));
         assert_eq!(Weekday::Monday(), Weekday::from(70));
     }
+
+    #[test]
+    fn deserialize() {
+        let parse = |json: &str| serde_json::from_str::<Weekday>(json);
+        assert_eq!(parse(r#"{"day": 2}"#).unwrap(), Weekday::Wednesday());
+        assert_eq!(parse("2").unwrap(), Weekday::Wednesday());
+        assert_eq!(parse(r#""Wednesday""#).unwrap(), Weekday::Wednesday());
+        assert_eq!(parse(r#""wed""#).unwrap(), Weekday::Wednesday());
+        assert!(parse("7").is_err());
+        assert!(parse(r#""Wodan""#).is_err());
+
+        assert_eq!(serde_json::to_string(&Weekday::Sunday()).unwrap(), r#""Sunday""#);
+        assert_eq!(parse(&serde_json::to_string(&Weekday::Sunday()).unwrap()).unwrap(), Weekday::Sunday());
+    }
 }
\ No newline at end of file
@@ -1,5 +1,28 @@
 //! Contains structs and utilities for reading and writing configurations
+//!
+//! The most commonly used types are re-exported at the crate root:
+//! - [ProfileConfig] describes what to back up, where to and when (see [ProfileConfig::load_dir] for loading all profiles of a directory),
+//! - [Interval] (built with [IntervalBuilder]) describes when backups are scheduled,
+//! - [GeneralConfig] holds the program wide settings,
+//! - [Error] describes why loading, storing or validating a config failed.
+//!
+//! See `examples/embed.rs` for a small program listing profiles and their next scheduled runs.
 
+pub mod archive_manifest;
+mod atomic_file;
+pub mod backup_catalog;
+pub mod backup_inventory;
+pub mod backup_report;
+pub mod control;
+pub mod error;
+pub mod free_space;
 pub mod general_config;
 pub mod profile_config;
-pub mod interval;
\ No newline at end of file
+pub mod support_bundle;
+pub mod interval;
+mod migration;
+
+pub use error::Error;
+pub use general_config::GeneralConfig;
+pub use interval::{Interval, IntervalBuilder, Month, Specifier, SpecifierKind, Weekday};
+pub use profile_config::ProfileConfig;
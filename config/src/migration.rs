@@ -0,0 +1,87 @@
+//! Contains the migration of stored configs from older schema versions to the current one.
+
+use serde_json::{Map, Value};
+
+use crate::Error;
+
+/// Name of the field storing the schema version of a config. Configs without it have version 0.
+pub const VERSION_FIELD: &str = "config_version";
+
+/// Transforms the fields of a stored config from one schema version to the next one
+pub type Migration = fn(&mut Map<String, Value>);
+
+/// Migrates the config stored in `value` to the version `migrations.len()`, where `migrations[n]` migrates from version `n` to `n + 1`.
+/// Configs of newer versions are left as they are.
+///
+/// # Returns
+/// Whether `value` was migrated
+///
+/// # Errors
+/// Returns an [Error::Parse] if `value` isn't an object or its version isn't a number.
+pub fn migrate(value: &mut Value, migrations: &[Migration]) -> Result<bool, Error> {
+    let fields = value
+        .as_object_mut()
+        .ok_or_else(|| Error::Parse(String::from("Config isn't an object")))?;
+    let version = match fields.get(VERSION_FIELD) {
+        None => 0,
+        Some(version) => version.as_u64().ok_or_else(|| {
+            Error::Parse(format!("{} {} isn't a number", VERSION_FIELD, version))
+        })? as usize,
+    };
+    if version >= migrations.len() {
+        return Ok(false);
+    }
+
+    for migration in &migrations[version..] {
+        migration(fields);
+    }
+    fields.insert(String::from(VERSION_FIELD), Value::from(migrations.len()));
+    Ok(true)
+}
+
+#[cfg(test)]
+mod migration_tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn add_a(fields: &mut Map<String, Value>) {
+        fields.insert(String::from("a"), json!(1));
+    }
+
+    fn double_a(fields: &mut Map<String, Value>) {
+        let a = fields["a"].as_u64().unwrap();
+        fields.insert(String::from("a"), json!(a * 2));
+    }
+
+    const MIGRATIONS: [Migration; 2] = [add_a, double_a];
+
+    #[test]
+    fn from_version_0() {
+        let mut value = json!({"b": true});
+        assert!(migrate(&mut value, &MIGRATIONS).unwrap());
+        assert_eq!(value, json!({"a": 2, "b": true, "config_version": 2}));
+    }
+
+    #[test]
+    fn from_version_1() {
+        let mut value = json!({"a": 5, "config_version": 1});
+        assert!(migrate(&mut value, &MIGRATIONS).unwrap());
+        assert_eq!(value, json!({"a": 10, "config_version": 2}));
+    }
+
+    #[test]
+    fn current_and_newer_versions() {
+        for version in [2, 3] {
+            let mut value = json!({"a": 5, "config_version": version});
+            assert!(!migrate(&mut value, &MIGRATIONS).unwrap());
+            assert_eq!(value, json!({"a": 5, "config_version": version}));
+        }
+    }
+
+    #[test]
+    fn invalid_configs() {
+        assert!(migrate(&mut json!([1]), &MIGRATIONS).is_err());
+        assert!(migrate(&mut json!({"config_version": "1"}), &MIGRATIONS).is_err());
+    }
+}
@@ -1,18 +1,62 @@
 //! Contains structs and functions for profile configurations.
 
 use std::{
-    fs::{File, OpenOptions},
-    io::{BufReader, BufWriter, Error},
-    path::PathBuf,
+    ffi::OsStr,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
 };
 
-use crate::interval::*;
-use chrono::{offset, Days, NaiveDateTime};
+use crate::{atomic_file, interval::*, migration, Error};
+use chrono::{offset, Days, Duration, NaiveDateTime};
+use log::warn;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+mod adoption;
+mod audit;
+mod compression;
+mod encryption;
+mod expansion;
+mod format;
+mod overlap;
+mod patterns;
+mod policy;
+mod preset;
+mod version;
+
+pub use self::{
+    adoption::AdoptionReport,
+    audit::{
+        append_audit_entry, diff_profile_configs, read_audit_log, AuditAction, AuditActor,
+        AuditEntry, FieldChange, MAX_AUDIT_LOG_BYTES,
+    },
+    compression::{CompressionMethod, CompressionSettings},
+    encryption::{EncryptionSettings, PassphraseSource},
+    format::ConfigFormat,
+    overlap::{find_overlaps, ProfileOverlap},
+    policy::{BackupMode, LockedFilePolicy, SymlinkPolicy, UnstableFilePolicy},
+    preset::ProfilePreset,
+    version::{CRATE_VERSION, PROFILE_CONFIG_VERSION},
+};
+
+/// Number of hours the current time may lie before the last recorded activity of a profile before the system clock is considered to be wrong.
+pub const CLOCK_SKEW_THRESHOLD_HOURS: i64 = 24;
+
+/// Maximum number of entries in `previous_target_dirs`
+pub const MAX_PREVIOUS_TARGET_DIRS: usize = 10;
+
+/// Number of scheduled runs stored in `upcoming`
+pub const UPCOMING_RUNS: usize = 5;
+
+/// Number of seconds a hook command may run, if the profile doesn't specify `hook_timeout_secs`
+pub const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 300;
+
+/// Smallest allowed `max_archive_size` in bytes (1 MiB)
+pub const MIN_ARCHIVE_SIZE: u64 = 1 << 20;
+
 /// Struct representing a profile configuration.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ProfileConfig {
     /// Descriptive name of the profile; doesn't need to be unique
     pub name: String,
@@ -33,6 +77,127 @@ pub struct ProfileConfig {
     /// Datetime specifying when the next backup should be made
     /// `next_backup` isn't guaranteed to be matched by `interval. It must always be checked first.
     pub next_backup: NaiveDateTime,
+    /// File extensions (without leading `.`) to exclude from the backup. Matched case-insensitively.
+    #[serde(default)]
+    pub exclude_extensions: Vec<String>,
+    /// File extensions (without leading `.`) to exclusively include in the backup. If empty, files with any extension are included.
+    #[serde(default)]
+    pub include_extensions: Vec<String>,
+    /// Maximum size of a file in bytes to be included in the backup. [None] means that there is no limit.
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+    /// Directories that were used as `target_dir` before. They are only searched for existing backups, but never written to.
+    #[serde(default)]
+    pub previous_target_dirs: Vec<PathBuf>,
+    /// [Uuid]s of profiles this profile is allowed to overlap with (see [find_overlaps]).
+    #[serde(default)]
+    pub allow_overlap_with: Vec<Uuid>,
+    /// Whether NTFS alternate data streams and reparse-point targets should be captured. Only has an effect on Windows.
+    #[serde(default)]
+    pub capture_ads: bool,
+    /// The next [UPCOMING_RUNS] scheduled backups, starting with `next_backup`.
+    /// This is derived data for displaying purposes only. It is recomputed by [ProfileConfig::refresh_upcoming] and may be outdated.
+    #[serde(default)]
+    pub upcoming: Vec<NaiveDateTime>,
+    /// Whether files that couldn't be read because they were in use should be retried once at the end of the backup.
+    #[serde(default)]
+    pub retry_in_use_files: bool,
+    /// What happens to files that can't be opened because another process is using them.
+    #[serde(default)]
+    pub locked_file_policy: LockedFilePolicy,
+    /// Maximum number of bytes the backups of this profile may use in the `target_dir`. [None] means that there is no limit.
+    #[serde(default)]
+    pub max_target_usage_bytes: Option<u64>,
+    /// Maximum size of a single archive file in bytes, e.g., because the `target_dir` is on a FAT32 drive. Larger backups are split into several volumes.
+    /// [None] means that archives aren't split.
+    #[serde(default)]
+    pub max_archive_size: Option<u64>,
+    /// Version of the config crate that stored this profile the last time. Gets set by [ProfileConfig::store].
+    /// Empty if the profile was stored before versions were recorded.
+    #[serde(default)]
+    pub written_by_version: String,
+    /// Whether the program's own files (logs, general config, profile configs) may be backed up. If `false`, they are skipped.
+    #[serde(default)]
+    pub include_self: bool,
+    /// Whether files should be archived in ascending order of their size, so that an interrupted backup still contains as many files as possible.
+    #[serde(default)]
+    pub small_files_first: bool,
+    /// [Uuid]s this profile had before (see [ProfileConfig::regenerate_uuid]). Backups made under them still belong to this profile.
+    #[serde(default)]
+    pub former_uuids: Vec<Uuid>,
+    /// What happens to files that changed while they were archived.
+    #[serde(default)]
+    pub unstable_files: UnstableFilePolicy,
+    /// Whether files that changed while they were archived should be archived once more at the end of the backup.
+    #[serde(default)]
+    pub retry_unstable_files: bool,
+    /// Whether all files or only the ones modified since the newest archive are archived.
+    #[serde(default)]
+    pub backup_mode: BackupMode,
+    /// How the files in the archives are compressed. [None] means that every file is deflated with the default level.
+    #[serde(default)]
+    pub compression: Option<CompressionSettings>,
+    /// How the archives are encrypted. [None] means that they aren't encrypted.
+    #[serde(default)]
+    pub encryption: Option<EncryptionSettings>,
+    /// What happens to symbolic links in the included dirs and files.
+    #[serde(default)]
+    pub symlink_policy: SymlinkPolicy,
+    /// Whether the written archive is read back completely after the backup, so corrupted archives are noticed right away instead of on restore.
+    #[serde(default = "default_verify_after_backup")]
+    pub verify_after_backup: bool,
+    /// Whether a backup is skipped if no file was added, removed or modified since the newest archive. Forced backups are never skipped.
+    #[serde(default)]
+    pub skip_if_unchanged: bool,
+    /// Glob patterns (e.g. `**/node_modules` or `*.tmp`) of paths to exclude from the backup.
+    /// `/` in a pattern also matches `\` in a path. Matched case-insensitively.
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    /// Datetime the last successful backup was started. [None] if there wasn't any yet.
+    #[serde(default)]
+    pub last_backup: Option<NaiveDateTime>,
+    /// Path of the archive written by the last successful backup. [None] if there wasn't any yet.
+    #[serde(default)]
+    pub last_backup_archive: Option<PathBuf>,
+    /// Number of newest archives in the `target_dir` that are always kept (see [ProfileConfig::archives_to_prune]). [None] means that there is no such rule.
+    #[serde(default)]
+    pub keep_last: Option<u32>,
+    /// Number of days archives in the `target_dir` are kept at least (see [ProfileConfig::archives_to_prune]). [None] means that there is no such rule.
+    #[serde(default)]
+    pub keep_days: Option<u32>,
+    /// Tags for selecting several profiles at once, e.g., `work`. Matched case-insensitively (see [ProfileConfig::has_tag]).
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Shell commands run one after another before the archive is created. If one of them fails, the backup is aborted.
+    #[serde(default)]
+    pub pre_backup_commands: Vec<String>,
+    /// Shell commands run one after another after the archive was finished. Failures are only logged.
+    #[serde(default)]
+    pub post_backup_commands: Vec<String>,
+    /// Number of seconds each of the `pre_backup_commands` and `post_backup_commands` may run before it is killed.
+    /// [None] means that [DEFAULT_HOOK_TIMEOUT_SECS] is used.
+    #[serde(default)]
+    pub hook_timeout_secs: Option<u64>,
+    /// Whether the profile is backed up. Disabled profiles keep their schedule, but their backups are skipped.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Schema version of the stored profile (see [PROFILE_CONFIG_VERSION]). Files of older versions are migrated by [ProfileConfig::load].
+    #[serde(default)]
+    pub config_version: u32,
+    /// Compiled `exclude_patterns`
+    #[serde(skip)]
+    exclude_matcher: patterns::PatternCache,
+    /// Format of the file the profile was loaded from. [ProfileConfig::store] keeps it.
+    #[serde(skip)]
+    format: ConfigFormat,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_verify_after_backup() -> bool {
+    true
 }
 
 impl ProfileConfig {
@@ -59,7 +224,7 @@ impl ProfileConfig {
         let uuid = Uuid::new_v4();
         let now = offset::Local::now().naive_local();
 
-        ProfileConfig {
+        let mut config = ProfileConfig {
             name,
             uuid,
             target_dir,
@@ -69,35 +234,143 @@ impl ProfileConfig {
             dirs_to_exclude,
             interval,
             next_backup: now,
-        }
+            exclude_extensions: vec![],
+            include_extensions: vec![],
+            max_file_size: None,
+            previous_target_dirs: vec![],
+            allow_overlap_with: vec![],
+            capture_ads: false,
+            upcoming: vec![],
+            retry_in_use_files: false,
+            locked_file_policy: LockedFilePolicy::Skip,
+            max_target_usage_bytes: None,
+            max_archive_size: None,
+            written_by_version: String::new(),
+            include_self: false,
+            small_files_first: false,
+            former_uuids: vec![],
+            unstable_files: UnstableFilePolicy::Keep,
+            retry_unstable_files: false,
+            backup_mode: BackupMode::Full,
+            compression: None,
+            encryption: None,
+            symlink_policy: SymlinkPolicy::Follow,
+            verify_after_backup: true,
+            skip_if_unchanged: false,
+            exclude_patterns: vec![],
+            last_backup: None,
+            last_backup_archive: None,
+            keep_last: None,
+            keep_days: None,
+            tags: vec![],
+            pre_backup_commands: vec![],
+            post_backup_commands: vec![],
+            hook_timeout_secs: None,
+            enabled: true,
+            config_version: PROFILE_CONFIG_VERSION,
+            exclude_matcher: Default::default(),
+            format: ConfigFormat::Json,
+        };
+        config.refresh_upcoming();
+        config
     }
 
     /// Attempts to load [ProfileConfig] from the given file.
+    /// The [ConfigFormat] is determined by the extension of the file. Files with other extensions are read as JSON.
+    ///
+    /// Files of an older `config_version` are migrated to [PROFILE_CONFIG_VERSION] and rewritten in place.
+    /// If rewriting fails, the migrated profile is returned anyway and the next [ProfileConfig::store] writes the current version.
     ///
     /// # Returns
-    /// [Ok] containing [ProfileConfig] if the file exists and is the correct format. [Error] else.
+    /// [Ok] containing [ProfileConfig] if the file exists and is the correct format.
+    /// [Error::Io] if the file can't be read and [Error::Parse] if its content isn't a valid [ProfileConfig].
     pub fn load(file_path: &PathBuf) -> Result<ProfileConfig, Error> {
-        let file = File::open(file_path)?;
-        let reader = BufReader::new(file);
-
-        let config = serde_json::from_reader(reader)?;
+        let format = ConfigFormat::from_path(file_path).unwrap_or_default();
+        let content = fs::read_to_string(file_path)?;
+
+        let mut value = format.deserialize_value(&content)?;
+        let migrated = migration::migrate(&mut value, &version::MIGRATIONS)?;
+        let mut config: ProfileConfig = serde_json::from_value(value)?;
+        config.format = format;
+        if migrated {
+            if let Err(err) = format
+                .serialize(&config)
+                .and_then(|content| atomic_file::write(file_path, content.as_bytes()).map_err(Error::from))
+            {
+                warn!("Couldn't rewrite the migrated ProfileConfig {:?}: {}", file_path, err);
+            }
+        }
         Ok(config)
     }
 
     /// Stores configuration to afile named after the own [Uuid] and places it into the directory pointed to by the given [PathBuf].
+    /// The file is written in the [ConfigFormat] the profile was loaded from, or JSON for new profiles (see [ProfileConfig::store_as]).
     pub fn store(&self, dir_path: &PathBuf) -> Result<(), Error> {
-        let file_path = Self::dir_uuid_to_file(dir_path, self.uuid);
-        let file = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .create(true)
-            .open(&file_path)?;
-        let writer = BufWriter::new(file);
+        self.store_as(dir_path, self.format)
+    }
+
+    /// Stores configuration to afile named after the own [Uuid] in the given [ConfigFormat] and places it into the directory pointed to by the given [PathBuf].
+    /// Files of this profile in other formats are removed, so the profile isn't loaded twice.
+    /// The stored `written_by_version` is set to [CRATE_VERSION] and the `config_version` to [PROFILE_CONFIG_VERSION].
+    ///
+    /// # Returns
+    /// [Error::ReadOnly] if the profile is read-only (see [ProfileConfig::is_read_only]) and [Error::Io] if a file can't be written or removed.
+    pub fn store_as(&self, dir_path: &PathBuf, format: ConfigFormat) -> Result<(), Error> {
+        if self.is_read_only() {
+            return Err(Error::ReadOnly(format!(
+                "Version {} is too old to modify ProfileConfig {:?} written by version {}",
+                CRATE_VERSION, self.uuid, self.written_by_version
+            )));
+        }
+        let mut config = self.clone();
+        config.written_by_version = CRATE_VERSION.to_string();
+        config.config_version = PROFILE_CONFIG_VERSION;
+        config.normalize_extensions();
+
+        let file_path = Self::dir_uuid_to_file(dir_path, self.uuid, format);
+        atomic_file::write(&file_path, format.serialize(&config)?.as_bytes())?;
+
+        for other in ConfigFormat::ALL.into_iter().filter(|other| *other != format) {
+            match fs::remove_file(Self::dir_uuid_to_file(dir_path, self.uuid, other)) {
+                Err(err) if err.kind() != ErrorKind::NotFound => return Err(Error::Io(err)),
+                _ => (),
+            }
+        }
+        Ok(())
+    }
 
-        match serde_json::to_writer_pretty(writer, self) {
-            Err(e) => Err(e.into()),
-            _ => Ok(()),
+    /// Loads all [ProfileConfig]s from the files in the given directory, that have the extension of a [ConfigFormat].
+    /// Files that can't be parsed are skipped.
+    ///
+    /// # Returns
+    /// [Ok] containing the loaded [ProfileConfig]s. [io::Error] if the directory can't be read.
+    pub fn load_dir(dir_path: &PathBuf) -> Result<Vec<ProfileConfig>, io::Error> {
+        let mut configs = vec![];
+        for entry in fs::read_dir(dir_path)? {
+            let path = entry?.path();
+            if ConfigFormat::from_path(&path).is_none() || !path.is_file() {
+                continue;
+            }
+            if let Ok(config) = Self::load(&path) {
+                configs.push(config);
+            }
         }
+        Ok(configs)
+    }
+
+    /// Returns the [ConfigFormat] the profile is stored in by [ProfileConfig::store].
+    pub fn format(&self) -> ConfigFormat {
+        self.format
+    }
+
+    /// Sets the [ConfigFormat] the profile is stored in by [ProfileConfig::store].
+    pub fn set_format(&mut self, format: ConfigFormat) {
+        self.format = format
+    }
+
+    /// Returns the path of the file [ProfileConfig::store] writes this profile to in the given directory.
+    pub fn file_path(&self, dir_path: &PathBuf) -> PathBuf {
+        Self::dir_uuid_to_file(dir_path, self.uuid, self.format)
     }
 
     /// Returns immutable reference to `uuid`
@@ -110,6 +383,92 @@ impl ProfileConfig {
         self.uuid = uuid
     }
 
+    /// Replaces the `uuid` with a new random one and remembers the old one in `former_uuids`.
+    /// The config is stored under its `uuid`, so the caller has to delete the file of the old `uuid` after storing it.
+    ///
+    /// # Returns
+    /// The new [Uuid]
+    pub fn regenerate_uuid(&mut self) -> Uuid {
+        let former_uuid = self.uuid;
+        if !self.former_uuids.contains(&former_uuid) {
+            self.former_uuids.push(former_uuid);
+        }
+        self.uuid = Uuid::new_v4();
+        self.uuid
+    }
+
+    /// Returns a copy of this profile named `new_name` with a new random `uuid` and `next_backup` set to now.
+    /// The history of this profile (`former_uuids`, `previous_target_dirs`, `last_backup` and `last_backup_archive`) isn't copied,
+    /// so the copy doesn't claim the archives of this profile.
+    ///
+    /// The copy isn't scheduled. That happens with its first backup or reschedule.
+    pub fn duplicate(&self, new_name: String) -> ProfileConfig {
+        let mut copy = self.clone();
+        copy.name = new_name;
+        copy.uuid = Uuid::new_v4();
+        copy.next_backup = offset::Local::now().naive_local();
+        copy.former_uuids = vec![];
+        copy.previous_target_dirs = vec![];
+        copy.last_backup = None;
+        copy.last_backup_archive = None;
+        copy.written_by_version = String::new();
+        copy.refresh_upcoming();
+        copy
+    }
+
+    /// Returns a copy of this profile, in which `~`, `$HOME`, `${VAR}` and `%VAR%` in the `target_dir`, `files_to_include`, `dirs_to_include`,
+    /// `files_to_exclude` and `dirs_to_exclude` are replaced by the home directory and the values of the environment variables.
+    /// This allows sharing profiles between machines and users.
+    ///
+    /// The copy is meant for working with the paths. Store the profile itself, so the stored paths stay portable.
+    ///
+    /// # Errors
+    /// Returns an [Err] naming the profile, field and variable if a variable isn't set.
+    pub fn resolve_paths(&self) -> Result<ProfileConfig, String> {
+        let resolve = |field: &str, path: &PathBuf| {
+            expansion::expand(path, |name| std::env::var(name).ok()).map_err(|msg| {
+                format!("Couldn't resolve {} {:?} of {:?}: {}", field, path, self.name, msg)
+            })
+        };
+        let resolve_all = |field: &str, paths: &Vec<PathBuf>| {
+            paths
+                .iter()
+                .map(|path| resolve(field, path))
+                .collect::<Result<Vec<PathBuf>, String>>()
+        };
+
+        let mut resolved = self.clone();
+        resolved.target_dir = resolve("target_dir", &self.target_dir)?;
+        resolved.files_to_include = resolve_all("files_to_include", &self.files_to_include)?;
+        resolved.dirs_to_include = resolve_all("dirs_to_include", &self.dirs_to_include)?;
+        resolved.files_to_exclude = resolve_all("files_to_exclude", &self.files_to_exclude)?;
+        resolved.dirs_to_exclude = resolve_all("dirs_to_exclude", &self.dirs_to_exclude)?;
+        Ok(resolved)
+    }
+
+    /// Checks if the given [Uuid] is the current `uuid` or one of the `former_uuids`.
+    pub fn matches_uuid(&self, uuid: &Uuid) -> bool {
+        &self.uuid == uuid || self.former_uuids.contains(uuid)
+    }
+
+    /// Checks if the profile has the given `tag`. Tags are compared case-insensitively.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own.to_lowercase() == tag.to_lowercase())
+    }
+
+    /// Returns the time each hook command may run (see `hook_timeout_secs`).
+    pub fn hook_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.hook_timeout_secs.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS))
+    }
+
+    /// Checks if a backup archive with the given file name belongs to this profile, i.e., if it starts with the current `uuid` or one of the `former_uuids`.
+    /// New archives should always be named after the current `uuid`.
+    pub fn is_own_archive(&self, file_name: &str) -> bool {
+        std::iter::once(&self.uuid)
+            .chain(self.former_uuids.iter())
+            .any(|uuid| file_name.starts_with(&uuid.as_hyphenated().to_string()))
+    }
+
     /// Gets the [NaiveDateTime] for the next backup, either based on its own `next_backup` value or based on `from_datetime` if [Some] was given.
     ///
     /// # Returns
@@ -131,15 +490,163 @@ impl ProfileConfig {
         }
     }
 
-    /// Converts a [PathBuf] describing a directory and a [Uuid] into a filename.
-    fn dir_uuid_to_file(dir: &PathBuf, uuid: Uuid) -> PathBuf {
+    /// Checks if the system clock appears to be wrong.
+    /// That is the case, when `now` lies more than [CLOCK_SKEW_THRESHOLD_HOURS] before `last_activity` (see [crate::backup_inventory::last_activity]).
+    ///
+    /// # Returns
+    /// [Some] containing the amount of time `now` lies before `last_activity` if the clock appears to be wrong. [None] else.
+    pub fn clock_skew(
+        now: NaiveDateTime,
+        last_activity: Option<NaiveDateTime>,
+    ) -> Option<Duration> {
+        let skew = last_activity? - now;
+        if skew > Duration::hours(CLOCK_SKEW_THRESHOLD_HOURS) {
+            Some(skew)
+        } else {
+            None
+        }
+    }
+
+    /// Sets `next_backup` to `datetime`, unless that would move it backwards while the system clock appears to be wrong (see [ProfileConfig::clock_skew]).
+    ///
+    /// # Returns
+    /// `true` if `next_backup` was updated. `false` if the update was refused.
+    pub fn set_next_backup_guarded(
+        &mut self,
+        datetime: NaiveDateTime,
+        now: NaiveDateTime,
+        last_activity: Option<NaiveDateTime>,
+    ) -> bool {
+        if datetime < self.next_backup && Self::clock_skew(now, last_activity).is_some() {
+            return false;
+        }
+        self.next_backup = datetime;
+        self.refresh_upcoming();
+        true
+    }
+
+    /// Computes the next [UPCOMING_RUNS] scheduled backups, starting with `next_backup`.
+    pub fn compute_upcoming(&self) -> Vec<NaiveDateTime> {
+        let mut upcoming = vec![self.next_backup];
+        upcoming.append(
+            &mut self
+                .interval
+                .upcoming(self.next_backup, UPCOMING_RUNS - 1),
+        );
+        upcoming
+    }
+
+    /// Recomputes `upcoming`. Should be called whenever `interval` or `next_backup` changes.
+    ///
+    /// # Returns
+    /// `true` if the stored `upcoming` was outdated.
+    pub fn refresh_upcoming(&mut self) -> bool {
+        let upcoming = self.compute_upcoming();
+        let outdated = upcoming != self.upcoming;
+        self.upcoming = upcoming;
+        outdated
+    }
+
+    /// Remembers `old_target_dir` in `previous_target_dirs`, if it differs from the current `target_dir`.
+    /// Empty paths are ignored. If the list exceeds [MAX_PREVIOUS_TARGET_DIRS], the oldest entries are dropped.
+    pub fn record_previous_target_dir(&mut self, old_target_dir: PathBuf) {
+        if old_target_dir.as_os_str().is_empty() || old_target_dir == self.target_dir {
+            return;
+        }
+
+        self.previous_target_dirs.retain(|dir| dir != &old_target_dir);
+        self.previous_target_dirs.push(old_target_dir);
+
+        let overflow = self
+            .previous_target_dirs
+            .len()
+            .saturating_sub(MAX_PREVIOUS_TARGET_DIRS);
+        self.previous_target_dirs.drain(..overflow);
+    }
+
+    /// Returns all directories that should be searched for existing backups.
+    /// The current `target_dir` comes first, followed by the `previous_target_dirs` from newest to oldest.
+    pub fn archive_dirs(&self) -> Vec<&PathBuf> {
+        std::iter::once(&self.target_dir)
+            .chain(
+                self.previous_target_dirs
+                    .iter()
+                    .rev()
+                    .filter(|dir| *dir != &self.target_dir),
+            )
+            .collect()
+    }
+
+    /// Checks if adding `additional_bytes` to the current `usage` (both in bytes) would exceed `max_target_usage_bytes`.
+    pub fn exceeds_target_usage(&self, usage: u64, additional_bytes: u64) -> bool {
+        match self.max_target_usage_bytes {
+            Some(max) => usage.saturating_add(additional_bytes) > max,
+            None => false,
+        }
+    }
+
+    /// Returns how much of `max_target_usage_bytes` is used by `usage` (in bytes) in percent.
+    ///
+    /// # Returns
+    /// [Some] containing the percentage (may be more than `100.0`). [None] if there is no limit.
+    pub fn target_usage_percentage(&self, usage: u64) -> Option<f64> {
+        match self.max_target_usage_bytes {
+            Some(0) => Some(if usage == 0 { 0.0 } else { f64::INFINITY }),
+            Some(max) => Some(usage as f64 / max as f64 * 100.0),
+            None => None,
+        }
+    }
+
+    /// Selects the archives that aren't covered by the retention rules `keep_last` and `keep_days` anymore.
+    /// `archives` contains the creation date and path of each archive, sorted by creation date (oldest first).
+    ///
+    /// An archive is kept if it is one of the `keep_last` newest archives or if it was created less than `keep_days` days before `now`.
+    /// The newest archive is always kept. If neither rule is set, all archives are kept.
+    ///
+    /// # Returns
+    /// The paths of the archives to delete, oldest first.
+    pub fn archives_to_prune(
+        &self,
+        archives: &[(NaiveDateTime, PathBuf)],
+        now: NaiveDateTime,
+    ) -> Vec<PathBuf> {
+        if self.keep_last.is_none() && self.keep_days.is_none() {
+            return vec![];
+        }
+        let keep_last = self.keep_last.unwrap_or(0).max(1) as usize;
+        let prunable = archives.len().saturating_sub(keep_last);
+        archives[..prunable]
+            .iter()
+            .filter(|(created, _)| {
+                self.keep_days.is_none_or(|days| {
+                    now.checked_sub_days(Days::new(days as u64))
+                        .is_some_and(|limit| *created < limit)
+                })
+            })
+            .map(|(_, path)| path.clone())
+            .collect()
+    }
+
+    /// Converts a [PathBuf] describing a directory, a [Uuid] and a [ConfigFormat] into a filename.
+    fn dir_uuid_to_file(dir: &PathBuf, uuid: Uuid, format: ConfigFormat) -> PathBuf {
         PathBuf::from(format!(
-            "{}/{}.json",
+            "{}/{}.{}",
             dir.to_str().unwrap_or(""),
-            uuid.as_hyphenated()
+            uuid.as_hyphenated(),
+            format.extension()
         ))
     }
 
+    /// Returns the path of the stored file of the [ProfileConfig] with the given [Uuid] in the given directory, whatever its [ConfigFormat] is.
+    /// If there is no such file, the path of the JSON file is returned.
+    fn stored_file(dir: &PathBuf, uuid: Uuid) -> PathBuf {
+        ConfigFormat::ALL
+            .into_iter()
+            .map(|format| Self::dir_uuid_to_file(dir, uuid, format))
+            .find(|path| path.is_file())
+            .unwrap_or_else(|| Self::dir_uuid_to_file(dir, uuid, ConfigFormat::Json))
+    }
+
     /// Checks if the provided `path` is in the given `dir`.
     ///
     /// Expects that both paths are either relative with respect to the same root or absolute.
@@ -150,7 +657,7 @@ impl ProfileConfig {
         path.starts_with(dir)
     }
 
-    /// Checks if the provided [PathBuf] is matched by the `files_to_exclude` or `dirs_to_exclude`.
+    /// Checks if the provided [PathBuf] is matched by the `files_to_exclude`, `dirs_to_exclude` or `exclude_patterns`.
     ///
     /// Since the exlusion paths can be expected to be absolute, the provided `path` should also be absolute. Otherwise the result can't be trusted.
     pub fn is_excluded(&self, path: &PathBuf) -> bool {
@@ -161,6 +668,154 @@ impl ProfileConfig {
                 .dirs_to_exclude
                 .iter()
                 .any(|excluded_dir| Self::is_in_dir(path, excluded_dir))
+            || self.exclude_matcher.is_match(&self.exclude_patterns, path)
+    }
+
+    /// Checks if the file at the provided [PathBuf] has to be skipped because of its extension.
+    /// That is the case if the extension is in `exclude_extensions`, or if `include_extensions` isn't empty and doesn't contain it.
+    /// Extensions are compared case-insensitively.
+    pub fn is_extension_excluded(&self, path: &Path) -> bool {
+        let extension = path.extension().and_then(OsStr::to_str).unwrap_or("");
+        let listed = |extensions: &Vec<String>| {
+            extensions
+                .iter()
+                .any(|listed| listed.trim_start_matches('.').eq_ignore_ascii_case(extension))
+        };
+        listed(&self.exclude_extensions)
+            || !self.include_extensions.is_empty() && !listed(&self.include_extensions)
+    }
+
+    /// Checks if a file of the given `size` in bytes is larger than `max_file_size`.
+    pub fn exceeds_max_file_size(&self, size: u64) -> bool {
+        self.max_file_size.is_some_and(|max_file_size| size > max_file_size)
+    }
+
+    /// Checks that all `exclude_patterns` are valid glob patterns.
+    ///
+    /// # Returns
+    /// [Err] describing the first invalid pattern. Invalid patterns are ignored by [ProfileConfig::is_excluded].
+    pub fn validate_patterns(&self) -> Result<(), String> {
+        self.exclude_patterns
+            .iter()
+            .try_for_each(|pattern| patterns::compile(pattern).map(|_| ()))
+    }
+
+    /// Checks the whole profile for mistakes that would make its backups useless or harmful.
+    /// The paths are checked after resolving their variables (see [ProfileConfig::resolve_paths]).
+    ///
+    /// These are:
+    /// - a `target_dir` inside one of the `dirs_to_include`, unless it is excluded (the backup would archive its own archives),
+    /// - relative paths in the include and exclude lists,
+    /// - duplicate entries in one of these lists,
+    /// - neither files nor dirs to include,
+    /// - an invalid or unsatisfiable `interval`,
+    /// - invalid `exclude_patterns`.
+    ///
+    /// # Returns
+    /// [Err] containing a message for each mistake found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut msgs = vec![];
+        let resolved = match self.resolve_paths() {
+            Ok(resolved) => resolved,
+            Err(msg) => {
+                msgs.push(msg);
+                self.clone()
+            }
+        };
+
+        for dir in &resolved.dirs_to_include {
+            if Self::is_in_dir(&resolved.target_dir, dir) && !resolved.is_excluded(&resolved.target_dir) {
+                msgs.push(format!(
+                    "target_dir {:?} lies inside the included dir {:?}, so the backups would contain each other. Exclude it or choose another target_dir.",
+                    resolved.target_dir, dir
+                ));
+            }
+        }
+
+        let lists = [
+            ("files_to_include", &resolved.files_to_include),
+            ("dirs_to_include", &resolved.dirs_to_include),
+            ("files_to_exclude", &resolved.files_to_exclude),
+            ("dirs_to_exclude", &resolved.dirs_to_exclude),
+        ];
+        for (field, paths) in lists {
+            for (i, path) in paths.iter().enumerate() {
+                if path.is_relative() {
+                    msgs.push(format!("{} contains the relative path {:?}. Use an absolute path instead.", field, path));
+                }
+                if paths[..i].contains(path) {
+                    msgs.push(format!("{} contains {:?} more than once.", field, path));
+                }
+            }
+        }
+
+        if resolved.files_to_include.is_empty() && resolved.dirs_to_include.is_empty() {
+            msgs.push(String::from("Neither files_to_include nor dirs_to_include contain anything, so the backups would be empty."));
+        }
+
+        let interval_check = self.interval.validate().map_err(|err| err.to_string());
+        if let Err(msg) = interval_check.and_then(|_| self.interval.satisfiability_check()) {
+            msgs.push(format!("Invalid interval: {}", msg));
+        }
+        if let Err(msg) = self.validate_patterns() {
+            msgs.push(msg);
+        }
+        if let Some(Err(msg)) = self.compression.as_ref().map(CompressionSettings::validate) {
+            msgs.push(msg);
+        }
+        if let Some(Err(msg)) = self.encryption.as_ref().map(EncryptionSettings::validate) {
+            msgs.push(msg);
+        }
+        if let Some(max_archive_size) = self.max_archive_size.filter(|size| *size < MIN_ARCHIVE_SIZE) {
+            msgs.push(format!(
+                "max_archive_size of {} bytes is too small. Use at least {} bytes.",
+                max_archive_size, MIN_ARCHIVE_SIZE
+            ));
+        }
+
+        if msgs.is_empty() {
+            Ok(())
+        } else {
+            Err(msgs)
+        }
+    }
+
+    /// Checks if the provided [PathBuf] is one of the program's own files or lies in one of its own dirs and therefore has to be skipped.
+    /// `own_paths` should be resolved from the actual configuration (see [GeneralConfig::own_paths](crate::general_config::GeneralConfig::own_paths)).
+    /// Always `false` if `include_self` is set.
+    pub fn is_self_excluded(&self, path: &PathBuf, own_paths: &[PathBuf]) -> bool {
+        !self.include_self
+            && own_paths
+                .iter()
+                .any(|own_path| Self::is_in_dir(path, own_path))
+    }
+
+    /// Orders the files found by the pre-scan of a backup as they should be archived.
+    /// `files` contains the path and size in bytes of each file in the order they were found.
+    ///
+    /// If `small_files_first` is set, the files of each include root (`files_to_include` and `dirs_to_include`, in this order) are sorted ascending by size.
+    /// Files of the same size stay grouped by their directory. Otherwise, the order isn't changed.
+    pub fn archive_order(&self, mut files: Vec<(PathBuf, u64)>) -> Vec<(PathBuf, u64)> {
+        if !self.small_files_first {
+            return files;
+        }
+
+        let root_index = |path: &PathBuf| {
+            self.files_to_include
+                .iter()
+                .position(|included_file| included_file == path)
+                .or_else(|| {
+                    self.dirs_to_include
+                        .iter()
+                        .position(|included_dir| Self::is_in_dir(path, included_dir))
+                        .map(|index| index + self.files_to_include.len())
+                })
+                .unwrap_or(usize::MAX)
+        };
+        files.sort_by_cached_key(|(path, size)| {
+            (root_index(path), *size, path.parent().map(PathBuf::from))
+        });
+        files
     }
 
     /// Checks if the provided [PathBuf] is in any of the `dirs_to_include`.
@@ -186,9 +841,13 @@ use {
     },
 };
 
+/// Format of the `next_backup` in protobuf messages
+#[cfg(feature = "protobuf")]
+const PROTO_DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
 #[cfg(feature = "protobuf")]
 impl ProfileConfig {
-    fn to_specifier_kind(specifier: ProtoSpecifier) -> Result<SpecifierKind, String> {
+    fn to_specifier_kind(specifier: ProtoSpecifier) -> Result<SpecifierKind, Error> {
         match specifier.kind {
             x if x == ProtoSpecifierKind::None as i32 => Ok(SpecifierKind::None),
             x if x == ProtoSpecifierKind::All as i32 => Ok(SpecifierKind::All),
@@ -196,16 +855,16 @@ impl ProfileConfig {
             x if x == ProtoSpecifierKind::Last as i32 => Ok(SpecifierKind::Last),
             x if x == ProtoSpecifierKind::Nth as i32 => {
                 if specifier.values.len() < 1 {
-                    Err(String::from("No value for SpecifierKind::Nth provided!"))
+                    Err(Error::InvalidInterval(String::from("No value for SpecifierKind::Nth provided!")))
                 } else {
                     Ok(SpecifierKind::Nth(specifier.values[0]))
                 }
             }
             x if x == ProtoSpecifierKind::BackNth as i32 => {
                 if specifier.values.len() < 1 {
-                    Err(String::from(
+                    Err(Error::InvalidInterval(String::from(
                         "No value for SpecifierKind::BackNth provided!",
-                    ))
+                    )))
                 } else {
                     Ok(SpecifierKind::BackNth(specifier.values[0]))
                 }
@@ -215,9 +874,9 @@ impl ProfileConfig {
             }
             x if x == ProtoSpecifierKind::EveryNth as i32 => {
                 if specifier.values.len() < 2 {
-                    Err(String::from(
+                    Err(Error::InvalidInterval(String::from(
                         "Not enough values provided for SpecifierKind::EveryNth!",
-                    ))
+                    )))
                 } else {
                     Ok(SpecifierKind::EveryNth(
                         specifier.values[0],
@@ -228,14 +887,30 @@ impl ProfileConfig {
             x if x == ProtoSpecifierKind::ExplicitList as i32 => {
                 Ok(SpecifierKind::ExplicitList(specifier.values))
             }
-            _ => Err(String::from("Unknown SpecifierKind")),
+            x if x == ProtoSpecifierKind::Range as i32 => {
+                if specifier.values.len() < 2 {
+                    Err(Error::InvalidInterval(String::from(
+                        "Not enough values provided for SpecifierKind::Range!",
+                    )))
+                } else {
+                    Ok(SpecifierKind::Range(specifier.values[0], specifier.values[1]))
+                }
+            }
+            x if x == ProtoSpecifierKind::Except as i32 => {
+                Ok(SpecifierKind::Except(specifier.values))
+            }
+            _ => Err(Error::InvalidInterval(String::from("Unknown SpecifierKind"))),
         }
     }
 
     /// Tries to construct a [ProfileConfig] from a provided protobuf.
-    pub fn from_buf(buf: Bytes) -> Result<ProfileConfig, String> {
-        let proto_config =
-            proto::ProfileConfig::decode(buf).or(Err(String::from("Couldn't decode buffer!")))?;
+    /// Messages of older versions may lack the `next_backup`, which is set to now then, and `enabled`, which is set to `true` then.
+    ///
+    /// # Errors
+    /// [Error::Parse] if the message or one of its dates can't be decoded and [Error::InvalidInterval] if its interval is invalid.
+    pub fn from_buf(buf: Bytes) -> Result<ProfileConfig, Error> {
+        let proto_config = proto::ProfileConfig::decode(buf)
+            .or(Err(Error::Parse(String::from("Couldn't decode buffer!"))))?;
 
         let mut interval = IntervalBuilder::default();
         if let Some(proto_interval) = proto_config.interval {
@@ -257,15 +932,36 @@ impl ProfileConfig {
             if let Some(months) = proto_interval.months {
                 interval.months(Self::to_specifier_kind(months)?);
             }
+            if let Some(nth_weekday) = proto_interval.nth_weekday {
+                interval.nth_weekday(nth_weekday);
+            }
+            if let Some(seconds) = proto_interval.seconds {
+                interval.seconds(Self::to_specifier_kind(seconds)?);
+            }
+            interval.excluded_dates(
+                proto_interval
+                    .excluded_dates
+                    .iter()
+                    .map(|date| {
+                        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                            .or(Err(Error::Parse(format!("Couldn't parse excluded date {:?}", date))))
+                    })
+                    .collect::<Result<Vec<chrono::NaiveDate>, Error>>()?,
+            );
         }
         let interval = interval
             .build()
-            .or(Err(String::from("Error building interval")))?;
+            .or(Err(Error::InvalidInterval(String::from("Error building interval"))))?;
+        let next_backup = match proto_config.next_backup {
+            Some(next_backup) => NaiveDateTime::parse_from_str(&next_backup, PROTO_DATE_TIME_FORMAT)
+                .or(Err(Error::Parse(format!("Couldn't parse next_backup {:?}", next_backup))))?,
+            None => chrono::Local::now().naive_local(),
+        };
 
         Ok(ProfileConfig {
             name: proto_config.name,
             uuid: Uuid::parse_str(&proto_config.uuid)
-                .or(Err(String::from("Couldn't parse uuid!")))?,
+                .or(Err(Error::Parse(String::from("Couldn't parse uuid!"))))?,
             target_dir: PathBuf::from(proto_config.target_dir),
             files_to_include: proto_config
                 .files_to_include
@@ -288,7 +984,43 @@ impl ProfileConfig {
                 .map(|path| PathBuf::from(path))
                 .collect(),
             interval,
-            next_backup: chrono::Local::now().naive_local(),
+            next_backup,
+            exclude_extensions: proto_config.exclude_extensions,
+            include_extensions: proto_config.include_extensions,
+            max_file_size: proto_config.max_file_size,
+            previous_target_dirs: vec![],
+            allow_overlap_with: vec![],
+            capture_ads: false,
+            upcoming: vec![],
+            retry_in_use_files: false,
+            locked_file_policy: LockedFilePolicy::Skip,
+            max_target_usage_bytes: None,
+            max_archive_size: None,
+            written_by_version: String::new(),
+            include_self: false,
+            small_files_first: false,
+            former_uuids: vec![],
+            unstable_files: UnstableFilePolicy::Keep,
+            retry_unstable_files: false,
+            backup_mode: BackupMode::Full,
+            compression: None,
+            encryption: None,
+            symlink_policy: SymlinkPolicy::Follow,
+            verify_after_backup: true,
+            skip_if_unchanged: false,
+            exclude_patterns: proto_config.exclude_patterns,
+            last_backup: None,
+            last_backup_archive: None,
+            keep_last: None,
+            keep_days: None,
+            tags: proto_config.tags,
+            pre_backup_commands: vec![],
+            post_backup_commands: vec![],
+            hook_timeout_secs: None,
+            enabled: proto_config.enabled.unwrap_or(true),
+            config_version: PROFILE_CONFIG_VERSION,
+            exclude_matcher: Default::default(),
+            format: ConfigFormat::Json,
         })
     }
 
@@ -303,6 +1035,8 @@ impl ProfileConfig {
             SpecifierKind::ExplicitNths(_) => ProtoSpecifierKind::ExplicitNths,
             SpecifierKind::EveryNth(_, _) => ProtoSpecifierKind::EveryNth,
             SpecifierKind::ExplicitList(_) => ProtoSpecifierKind::ExplicitList,
+            SpecifierKind::Range(_, _) => ProtoSpecifierKind::Range,
+            SpecifierKind::Except(_) => ProtoSpecifierKind::Except,
         }
     }
 
@@ -321,6 +1055,8 @@ impl ProfileConfig {
             SpecifierKind::ExplicitNths(vals) => vals.clone(),
             SpecifierKind::EveryNth(n, offset) => vec![*n, *offset],
             SpecifierKind::ExplicitList(vals) => vals.clone(),
+            SpecifierKind::Range(a, b) => vec![*a, *b],
+            SpecifierKind::Except(vals) => vals.clone(),
         };
 
         ProtoSpecifier { kind, values }
@@ -334,12 +1070,23 @@ impl ProfileConfig {
             monthdays: Some(Self::to_proto_specifier(&self.interval.monthdays)),
             weeks: Some(Self::to_proto_specifier(&self.interval.weeks)),
             months: Some(Self::to_proto_specifier(&self.interval.months)),
+            nth_weekday: self.interval.nth_weekday,
+            seconds: self.interval.seconds.as_ref().map(Self::to_proto_specifier),
+            excluded_dates: self
+                .interval
+                .excluded_dates
+                .iter()
+                .map(|date| date.format("%Y-%m-%d").to_string())
+                .collect(),
         };
 
         let proto_config = proto::ProfileConfig {
             name: self.name.clone(),
             uuid: self.uuid.as_hyphenated().to_string(),
             target_dir: self.target_dir.to_str().unwrap().to_string(),
+            next_backup: Some(self.next_backup.format(PROTO_DATE_TIME_FORMAT).to_string()),
+            max_file_size: self.max_file_size,
+            enabled: Some(self.enabled),
             files_to_include: self
                 .files_to_include
                 .iter()
@@ -360,6 +1107,10 @@ impl ProfileConfig {
                 .iter()
                 .map(|path| path.to_str().unwrap().to_string())
                 .collect(),
+            exclude_patterns: self.exclude_patterns.clone(),
+            exclude_extensions: self.exclude_extensions.clone(),
+            include_extensions: self.include_extensions.clone(),
+            tags: self.tags.clone(),
             interval: Some(interval),
         };
 
@@ -369,6 +1120,50 @@ impl ProfileConfig {
 
         bytes.into()
     }
+
+    /// Overwrites the `name`, `uuid`, `target_dir`, the included and excluded paths and the `interval` with the ones decoded from `buf`.
+    /// All other fields (e.g., `next_backup` or the filters) are kept, even if they are part of the protobuf message.
+    pub fn update_from_buf(&mut self, buf: &[u8]) -> Result<(), Error> {
+        let decoded = Self::from_buf(Bytes::copy_from_slice(buf))?;
+
+        self.name = decoded.name;
+        self.uuid = decoded.uuid;
+        self.target_dir = decoded.target_dir;
+        self.files_to_include = decoded.files_to_include;
+        self.dirs_to_include = decoded.dirs_to_include;
+        self.files_to_exclude = decoded.files_to_exclude;
+        self.dirs_to_exclude = decoded.dirs_to_exclude;
+        self.interval = decoded.interval;
+        Ok(())
+    }
+
+    /// Serializes multiple [ProfileConfig]s into one buffer. Every message is prefixed with its length as varint.
+    pub fn to_buf_list(configs: &[ProfileConfig]) -> Bytes {
+        let mut bytes = BytesMut::new();
+        for config in configs {
+            let buf = config.to_buf();
+            prost::encoding::encode_varint(buf.len() as u64, &mut bytes);
+            bytes.extend_from_slice(&buf);
+        }
+        bytes.into()
+    }
+
+    /// Tries to construct [ProfileConfig]s from a buffer created by [ProfileConfig::to_buf_list].
+    pub fn from_buf_list(mut buf: &[u8]) -> Result<Vec<ProfileConfig>, Error> {
+        let mut configs = vec![];
+        while !buf.is_empty() {
+            let len = prost::encoding::decode_varint(&mut buf)
+                .or(Err(Error::Parse(String::from("Couldn't decode message length!"))))?;
+            let len = usize::try_from(len)
+                .ok()
+                .filter(|len| *len <= buf.len())
+                .ok_or(Error::Parse(String::from("Message length exceeds buffer!")))?;
+            let (message, rest) = buf.split_at(len);
+            configs.push(Self::from_buf(Bytes::copy_from_slice(message))?);
+            buf = rest;
+        }
+        Ok(configs)
+    }
 }
 
 #[cfg(test)]
@@ -416,28 +1211,79 @@ mod profile_config_tests {
             interval,
         );
         assert!(config.store(&config_file_dir).is_ok());
-        let file = ProfileConfig::dir_uuid_to_file(&config_file_dir, config.uuid);
+        let file = ProfileConfig::dir_uuid_to_file(&config_file_dir, config.uuid, ConfigFormat::Json);
+        delete_file(file);
+    }
+
+    #[test]
+    fn store_after_interrupted_store() {
+        let config_file_dir = PathBuf::from("test_tmp");
+        let mut config = ProfileConfig::new(
+            "Hutzi".to_string(),
+            PathBuf::from("ho"),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        );
+        let file = ProfileConfig::dir_uuid_to_file(&config_file_dir, config.uuid, ConfigFormat::Json);
+        let tmp_file = atomic_file::temp_path(&file);
+        assert!(config.store(&config_file_dir).is_ok());
+
+        // simulate a crash while storing the renamed profile
+        fs::write(&tmp_file, "{\"name\": \"Fuschi\", \"uu").unwrap();
+        let interrupted = ProfileConfig::load(&file);
+        let loaded_dir = ProfileConfig::load_dir(&config_file_dir).unwrap();
+
+        config.name = "Fuschi".to_string();
+        let stored = config.store(&config_file_dir);
+        let tmp_exists = tmp_file.exists();
+        let loaded = ProfileConfig::load(&file);
         delete_file(file);
+
+        assert_eq!(interrupted.unwrap().name, "Hutzi");
+        assert!(loaded_dir.iter().any(|loaded| loaded.uuid == config.uuid));
+        assert!(stored.is_ok());
+        assert!(!tmp_exists);
+        assert_eq!(loaded.unwrap().name, "Fuschi");
     }
 
     #[test]
     fn load_test() {
         let uuid = Uuid::parse_str("001a828a-30ca-4b12-9756-6ce9696ac868").unwrap();
         let config_file_dir = PathBuf::from("test_tmp");
-        let file_path = ProfileConfig::dir_uuid_to_file(&config_file_dir, uuid);
+        let file_path = ProfileConfig::dir_uuid_to_file(&config_file_dir, uuid, ConfigFormat::Json);
         let config = ProfileConfig::load(&file_path);
         assert!(config.is_ok());
         let config = config.unwrap();
         assert_eq!(config.name, "Hutzi");
     }
 
+    #[test]
+    fn load_dir_test() {
+        let config_file_dir = PathBuf::from("test_tmp");
+        let configs = ProfileConfig::load_dir(&config_file_dir).unwrap();
+        let uuid = Uuid::parse_str("001a828a-30ca-4b12-9756-6ce9696ac868").unwrap();
+        assert!(configs.iter().any(|config| config.get_uuid() == &uuid));
+
+        assert!(ProfileConfig::load_dir(&PathBuf::from("hutzi")).is_err());
+    }
+
     #[test]
     fn load_non_existing() {
         let uuid = Uuid::new_v4();
         let dir_path = PathBuf::from("hutzi");
-        let file_path = ProfileConfig::dir_uuid_to_file(&dir_path, uuid);
+        let file_path = ProfileConfig::dir_uuid_to_file(&dir_path, uuid, ConfigFormat::Json);
         let config = ProfileConfig::load(&file_path);
-        assert!(config.is_err());
+        assert!(matches!(config, Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn load_invalid() {
+        // the general config isn't a valid profile config
+        let config = ProfileConfig::load(&PathBuf::from("test_tmp/read_test.json"));
+        assert!(matches!(config, Err(Error::Parse(_))));
     }
 
     mod set_next_backup_tests {
@@ -505,60 +1351,1123 @@ mod profile_config_tests {
         }
     }
 
-    mod exclusion_tests {
+    mod clock_skew_tests {
+        use chrono::NaiveDate;
+
         use super::*;
 
-        fn exclusion_config(
-            files_to_exclude: Vec<PathBuf>,
-            dirs_to_exclude: Vec<PathBuf>,
-        ) -> ProfileConfig {
-            ProfileConfig::new(
-                "".to_string(),
-                PathBuf::from(""),
-                vec![],
-                vec![],
-                files_to_exclude,
-                dirs_to_exclude,
-                IntervalBuilder::default().build().unwrap(),
-            )
+        fn datetime(year: i32, month: u32, day: u32) -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(year, month, day)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap()
         }
 
         #[test]
-        fn is_in_dir() {
-            let dir = PathBuf::from("/home/hutzi/Documents");
-            let path = PathBuf::from("/home/fuschi/Documents");
-            assert!(!ProfileConfig::is_in_dir(&path, &dir));
-
-            let path = PathBuf::from("/etc/passwd");
-            assert!(!ProfileConfig::is_in_dir(&path, &dir));
+        fn no_activity() {
+            assert!(ProfileConfig::clock_skew(datetime(2010, 1, 1), None).is_none());
+        }
 
-            let path = PathBuf::from("/home/hutzi/Pictures/dog.jpg");
-            assert!(!ProfileConfig::is_in_dir(&path, &dir));
+        #[test]
+        fn clock_ok() {
+            let last_activity = Some(datetime(2023, 5, 1));
+            assert!(ProfileConfig::clock_skew(datetime(2023, 5, 2), last_activity).is_none());
+            assert!(ProfileConfig::clock_skew(datetime(2023, 5, 1), last_activity).is_none());
+
+            // small deviations are tolerated
+            let now = datetime(2023, 5, 1) - Duration::hours(CLOCK_SKEW_THRESHOLD_HOURS);
+            assert!(ProfileConfig::clock_skew(now, last_activity).is_none());
+        }
 
-            let path = PathBuf::from("/home/hutzi");
-            assert!(!ProfileConfig::is_in_dir(&path, &dir));
+        #[test]
+        fn clock_wrong() {
+            let last_activity = Some(datetime(2023, 5, 1));
+            let skew = ProfileConfig::clock_skew(datetime(2010, 1, 1), last_activity);
+            assert_eq!(skew, Some(datetime(2023, 5, 1) - datetime(2010, 1, 1)));
+        }
 
-            let path = PathBuf::from("/home/hutzi/Documents");
-            assert!(ProfileConfig::is_in_dir(&path, &dir));
+        #[test]
+        fn guarded_next_backup() {
+            let mut p = ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("ho"),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            );
+            p.next_backup = datetime(2023, 5, 2);
+            let last_activity = Some(datetime(2023, 5, 1));
 
-            let path = PathBuf::from("/home/hutzi/Documents/homework");
-            assert!(ProfileConfig::is_in_dir(&path, &dir));
+            // clock is wrong -> don't go backwards
+            assert!(!p.set_next_backup_guarded(datetime(2010, 1, 2), datetime(2010, 1, 1), last_activity));
+            assert_eq!(p.next_backup, datetime(2023, 5, 2));
 
-            let dir = PathBuf::from("C:\\users\\fuschi");
-            let path = PathBuf::from("C:\\users");
-            assert!(!ProfileConfig::is_in_dir(&path, &dir));
+            // clock is wrong -> going forward is still fine
+            assert!(p.set_next_backup_guarded(datetime(2023, 5, 3), datetime(2010, 1, 1), last_activity));
+            assert_eq!(p.next_backup, datetime(2023, 5, 3));
 
-            let path = PathBuf::from("C:\\users\\fuschi\\Downloads");
-            assert!(ProfileConfig::is_in_dir(&path, &dir));
+            // clock is ok -> going backwards is fine
+            assert!(p.set_next_backup_guarded(datetime(2023, 5, 2), datetime(2023, 5, 2), last_activity));
+            assert_eq!(p.next_backup, datetime(2023, 5, 2));
         }
+    }
 
-        #[test]
-        fn is_excluded() {
-            let excluded_files = vec![
-                PathBuf::from("/etc/passwd"),
-                PathBuf::from("/home/hutzi/test.txt"),
-                PathBuf::from("C:\\users\\tester\\test.md"),
-            ];
+    mod target_dir_tests {
+        use super::*;
+
+        fn target_config(target_dir: &str) -> ProfileConfig {
+            ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from(target_dir),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            )
+        }
+
+        #[test]
+        fn record_changed_target() {
+            let mut config = target_config("E:/backups/2023");
+            config.record_previous_target_dir(PathBuf::from("E:/backup"));
+            assert_eq!(config.previous_target_dirs, vec![PathBuf::from("E:/backup")]);
+        }
+
+        #[test]
+        fn ignore_unchanged_and_empty_target() {
+            let mut config = target_config("E:/backup");
+            config.record_previous_target_dir(PathBuf::from("E:/backup"));
+            config.record_previous_target_dir(PathBuf::from(""));
+            assert!(config.previous_target_dirs.is_empty());
+        }
+
+        #[test]
+        fn no_duplicates() {
+            let mut config = target_config("C");
+            config.record_previous_target_dir(PathBuf::from("A"));
+            config.record_previous_target_dir(PathBuf::from("B"));
+            config.record_previous_target_dir(PathBuf::from("A"));
+            assert_eq!(
+                config.previous_target_dirs,
+                vec![PathBuf::from("B"), PathBuf::from("A")]
+            );
+        }
+
+        #[test]
+        fn capped() {
+            let mut config = target_config("target");
+            for i in 0..(MAX_PREVIOUS_TARGET_DIRS + 5) {
+                config.record_previous_target_dir(PathBuf::from(format!("old_{}", i)));
+            }
+            assert_eq!(config.previous_target_dirs.len(), MAX_PREVIOUS_TARGET_DIRS);
+            assert_eq!(config.previous_target_dirs[0], PathBuf::from("old_5"));
+            assert_eq!(
+                config.previous_target_dirs.last(),
+                Some(&PathBuf::from(format!("old_{}", MAX_PREVIOUS_TARGET_DIRS + 4)))
+            );
+        }
+
+        #[test]
+        fn archive_dirs() {
+            let mut config = target_config("C");
+            config.previous_target_dirs = vec![
+                PathBuf::from("A"),
+                PathBuf::from("C"),
+                PathBuf::from("B"),
+            ];
+            assert_eq!(
+                config.archive_dirs(),
+                vec![&PathBuf::from("C"), &PathBuf::from("B"), &PathBuf::from("A")]
+            );
+        }
+
+        #[test]
+        fn load_without_previous_target_dirs() {
+            let file_path = PathBuf::from("test_tmp/001a828a-30ca-4b12-9756-6ce9696ac868.json");
+            let config = ProfileConfig::load(&file_path).unwrap();
+            assert!(config.previous_target_dirs.is_empty());
+        }
+    }
+
+    mod upcoming_tests {
+        use chrono::NaiveDate;
+
+        use super::*;
+
+        fn daily_config() -> ProfileConfig {
+            ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("ho"),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                Interval::daily(0, 12).unwrap(),
+            )
+        }
+
+        fn noon(day: u32) -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(2023, 5, day)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap()
+        }
+
+        #[test]
+        fn computed_on_creation() {
+            let config = daily_config();
+            assert_eq!(config.upcoming.len(), UPCOMING_RUNS);
+            assert_eq!(config.upcoming[0], config.next_backup);
+        }
+
+        #[test]
+        fn starts_with_next_backup() {
+            let mut config = daily_config();
+            config.next_backup = noon(1);
+            assert!(config.refresh_upcoming());
+            assert_eq!(
+                config.upcoming,
+                vec![noon(1), noon(2), noon(3), noon(4), noon(5)]
+            );
+            assert!(!config.refresh_upcoming());
+        }
+
+        #[test]
+        fn regenerated_after_interval_change() {
+            let mut config = daily_config();
+            config.next_backup = noon(1);
+            config.refresh_upcoming();
+
+            config.interval = Interval::daily(30, 12).unwrap();
+            assert_ne!(config.upcoming, config.compute_upcoming());
+            assert!(config.refresh_upcoming());
+            assert_eq!(
+                config.upcoming[1],
+                NaiveDate::from_ymd_opt(2023, 5, 1)
+                    .unwrap()
+                    .and_hms_opt(12, 30, 0)
+                    .unwrap()
+            );
+        }
+
+        #[test]
+        fn regenerated_after_next_backup_change() {
+            let mut config = daily_config();
+            config.next_backup = noon(1);
+            config.refresh_upcoming();
+
+            assert!(config.set_next_backup_guarded(noon(10), noon(9), None));
+            assert_eq!(config.upcoming[0], noon(10));
+            assert_eq!(config.upcoming[4], noon(14));
+        }
+
+        #[test]
+        fn load_without_upcoming() {
+            let file_path = PathBuf::from("test_tmp/001a828a-30ca-4b12-9756-6ce9696ac868.json");
+            let mut config = ProfileConfig::load(&file_path).unwrap();
+            assert!(config.upcoming.is_empty());
+            assert!(config.refresh_upcoming());
+            assert_eq!(config.upcoming[0], config.next_backup);
+        }
+    }
+
+    mod self_exclusion_tests {
+        use super::*;
+
+        fn own_paths() -> Vec<PathBuf> {
+            vec![
+                PathBuf::from("/home/hutzi/.backup-rs/config.json"),
+                PathBuf::from("/home/hutzi/.backup-rs/profiles"),
+                PathBuf::from("/var/log/backup-rs/backupper.log"),
+            ]
+        }
+
+        #[test]
+        fn excluded_by_default() {
+            let config = ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("ho"),
+                vec![],
+                vec![PathBuf::from("/home/hutzi")],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            );
+            let own_paths = own_paths();
+
+            assert!(config.is_self_excluded(&own_paths[0], &own_paths));
+            assert!(config.is_self_excluded(
+                &PathBuf::from("/home/hutzi/.backup-rs/profiles/abc.json"),
+                &own_paths
+            ));
+            assert!(config.is_self_excluded(
+                &PathBuf::from("/var/log/backup-rs/backupper.log"),
+                &own_paths
+            ));
+            assert!(!config.is_self_excluded(
+                &PathBuf::from("/var/log/backup-rs/other.log"),
+                &own_paths
+            ));
+            assert!(!config.is_self_excluded(
+                &PathBuf::from("/home/hutzi/.backup-rs/notes.txt"),
+                &own_paths
+            ));
+            assert!(!config.is_self_excluded(&own_paths[0], &[]));
+        }
+
+        #[test]
+        fn include_self() {
+            let mut config = ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("ho"),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            );
+            config.include_self = true;
+            let own_paths = own_paths();
+
+            assert!(own_paths
+                .iter()
+                .all(|path| !config.is_self_excluded(path, &own_paths)));
+        }
+    }
+
+    mod target_usage_tests {
+        use super::*;
+
+        fn quota_config(max_target_usage_bytes: Option<u64>) -> ProfileConfig {
+            let mut config = ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("ho"),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            );
+            config.max_target_usage_bytes = max_target_usage_bytes;
+            config
+        }
+
+        #[test]
+        fn no_limit() {
+            let config = quota_config(None);
+            assert!(!config.exceeds_target_usage(u64::MAX, u64::MAX));
+            assert_eq!(config.target_usage_percentage(1000), None);
+        }
+
+        #[test]
+        fn exceeds() {
+            let config = quota_config(Some(1000));
+            assert!(!config.exceeds_target_usage(600, 400));
+            assert!(config.exceeds_target_usage(600, 401));
+            assert!(config.exceeds_target_usage(1001, 0));
+            assert!(config.exceeds_target_usage(u64::MAX, 1));
+        }
+
+        #[test]
+        fn percentage() {
+            let config = quota_config(Some(1000));
+            assert_eq!(config.target_usage_percentage(0), Some(0.0));
+            assert_eq!(config.target_usage_percentage(250), Some(25.0));
+            assert_eq!(config.target_usage_percentage(1500), Some(150.0));
+
+            let config = quota_config(Some(0));
+            assert_eq!(config.target_usage_percentage(0), Some(0.0));
+            assert_eq!(config.target_usage_percentage(1), Some(f64::INFINITY));
+        }
+    }
+
+    mod migration_tests {
+        use super::*;
+
+        /// Copies the fixture of an old version, so migrating it doesn't change the fixture itself
+        fn copy_fixture(name: &str) -> PathBuf {
+            let file_path = PathBuf::from(format!("test_tmp/migrated_{}_tmp.json", name));
+            fs::copy(format!("test_tmp/migration/{}.json", name), &file_path).unwrap();
+            file_path
+        }
+
+        fn stored_version(file_path: &PathBuf) -> Option<u64> {
+            let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(file_path).unwrap()).unwrap();
+            value["config_version"].as_u64()
+        }
+
+        #[test]
+        fn from_version_0() {
+            let file_path = copy_fixture("profile_v0");
+            let config = ProfileConfig::load(&file_path).unwrap();
+            let version = stored_version(&file_path);
+            let reloaded = ProfileConfig::load(&file_path).unwrap();
+            delete_file(file_path);
+
+            assert_eq!(config.name, "Hutzi v0");
+            assert!(config.enabled);
+            assert_eq!(config.exclude_extensions, vec![String::from("tmp"), String::from("log")]);
+            assert_eq!(config.config_version, PROFILE_CONFIG_VERSION);
+            assert_eq!(version, Some(PROFILE_CONFIG_VERSION as u64));
+            assert!(reloaded.enabled);
+            assert_eq!(reloaded.exclude_extensions, config.exclude_extensions);
+        }
+
+        #[test]
+        fn from_version_1() {
+            let file_path = copy_fixture("profile_v1");
+            let config = ProfileConfig::load(&file_path).unwrap();
+            let version = stored_version(&file_path);
+            delete_file(file_path);
+
+            assert_eq!(config.name, "Hutzi v1");
+            assert!(!config.enabled);
+            assert_eq!(config.include_extensions, vec![String::from("jpg"), String::from("png")]);
+            assert_eq!(config.config_version, PROFILE_CONFIG_VERSION);
+            assert_eq!(version, Some(PROFILE_CONFIG_VERSION as u64));
+        }
+
+        #[test]
+        fn current_version_isnt_rewritten() {
+            let file_path = PathBuf::from("test_tmp/001a828a-30ca-4b12-9756-6ce9696ac868.json");
+            let content = fs::read_to_string(&file_path).unwrap();
+            let config = ProfileConfig::load(&file_path).unwrap();
+
+            assert_eq!(config.config_version, PROFILE_CONFIG_VERSION);
+            assert_eq!(fs::read_to_string(&file_path).unwrap(), content);
+        }
+
+        #[test]
+        fn newer_version_is_read_only() {
+            let mut config = ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("ho"),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            );
+            assert!(!config.is_read_only());
+            config.config_version = PROFILE_CONFIG_VERSION + 1;
+            assert!(config.is_read_only());
+        }
+
+        #[test]
+        fn store_normalizes_extensions() {
+            let dir = PathBuf::from("test_tmp");
+            let mut config = ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("ho"),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            );
+            config.exclude_extensions = vec![String::from(".BAK")];
+            config.store(&dir).unwrap();
+            let file_path = config.file_path(&dir);
+            let stored = ProfileConfig::load(&file_path).unwrap();
+            delete_file(file_path);
+
+            assert_eq!(stored.exclude_extensions, vec![String::from("bak")]);
+        }
+    }
+
+    mod tags_tests {
+        use super::*;
+
+        #[test]
+        fn has_tag() {
+            let mut config = ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("ho"),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            );
+            assert!(!config.has_tag("work"));
+
+            config.tags = vec![String::from("Work"), String::from("photos")];
+            assert!(config.has_tag("work"));
+            assert!(config.has_tag("PHOTOS"));
+            assert!(!config.has_tag("wor"));
+        }
+
+        #[test]
+        fn none_in_old_config() {
+            let file_path = PathBuf::from("test_tmp/001a828a-30ca-4b12-9756-6ce9696ac868.json");
+            let config = ProfileConfig::load(&file_path).unwrap();
+            assert!(config.tags.is_empty());
+        }
+    }
+
+    mod last_backup_tests {
+        use super::*;
+        use chrono::NaiveDate;
+
+        #[test]
+        fn none_in_old_config() {
+            let file_path = PathBuf::from("test_tmp/001a828a-30ca-4b12-9756-6ce9696ac868.json");
+            let config = ProfileConfig::load(&file_path).unwrap();
+            assert!(config.last_backup.is_none());
+            assert!(config.last_backup_archive.is_none());
+        }
+
+        #[test]
+        fn roundtrip() {
+            let mut config = ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("ho"),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            );
+            let last_backup = NaiveDate::from_ymd_opt(2023, 5, 1)
+                .unwrap()
+                .and_hms_opt(12, 30, 0)
+                .unwrap();
+            config.last_backup = Some(last_backup);
+            config.last_backup_archive = Some(PathBuf::from("ho/archive.zip"));
+
+            let json = serde_json::to_string(&config).unwrap();
+            let parsed: ProfileConfig = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed.last_backup, Some(last_backup));
+            assert_eq!(parsed.last_backup_archive, Some(PathBuf::from("ho/archive.zip")));
+        }
+    }
+
+    mod format_tests {
+        use super::*;
+        use chrono::NaiveDate;
+
+        fn nested_config() -> ProfileConfig {
+            let interval = IntervalBuilder::default()
+                .seconds(SpecifierKind::EveryNth(15, 0))
+                .minutes(SpecifierKind::ExplicitList(vec![0, 30]))
+                .hours(SpecifierKind::Range(8, 18))
+                .weekdays(SpecifierKind::Except(vec![5, 6]))
+                .monthdays(SpecifierKind::BackNth(1))
+                .months(SpecifierKind::ExplicitNths(vec![0, 6]))
+                .nth_weekday(2)
+                .excluded_dates(vec![NaiveDate::from_ymd_opt(2023, 12, 24).unwrap()])
+                .build()
+                .unwrap();
+            let mut config = ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("E:/backups"),
+                vec![PathBuf::from("C:/notes.txt")],
+                vec![PathBuf::from("C:/Documents")],
+                vec![],
+                vec![PathBuf::from("C:/Documents/tmp")],
+                interval,
+            );
+            config.max_file_size = Some(1 << 30);
+            config.exclude_patterns = vec![String::from("**/node_modules")];
+            config.written_by_version = CRATE_VERSION.to_string();
+            config
+        }
+
+        #[test]
+        fn roundtrip() {
+            let dir = PathBuf::from("test_tmp");
+            let config = nested_config();
+            for format in ConfigFormat::ALL {
+                config.store_as(&dir, format).unwrap();
+                let file_path = ProfileConfig::dir_uuid_to_file(&dir, config.uuid, format);
+                let loaded = ProfileConfig::load(&file_path);
+                fs::remove_file(&file_path).unwrap();
+
+                let loaded = loaded.unwrap();
+                assert_eq!(loaded.format(), format);
+                assert_eq!(loaded.interval, config.interval, "{:?}", format);
+                assert_eq!(
+                    serde_json::to_value(&loaded).unwrap(),
+                    serde_json::to_value(&config).unwrap(),
+                    "{:?}",
+                    format
+                );
+            }
+        }
+
+        #[test]
+        fn store_keeps_format() {
+            let dir = PathBuf::from("test_tmp");
+            let config = nested_config();
+            config.store_as(&dir, ConfigFormat::Toml).unwrap();
+            let toml_file = ProfileConfig::dir_uuid_to_file(&dir, config.uuid, ConfigFormat::Toml);
+
+            let mut loaded = ProfileConfig::load(&toml_file).unwrap();
+            loaded.name = String::from("Fuschi");
+            let stored = loaded.store(&dir);
+            let reloaded = ProfileConfig::load(&toml_file);
+            let json_exists = ProfileConfig::dir_uuid_to_file(&dir, config.uuid, ConfigFormat::Json).exists();
+
+            // converting removes the old file
+            let converted = loaded.store_as(&dir, ConfigFormat::Yaml);
+            let toml_exists = toml_file.exists();
+            let yaml_file = ProfileConfig::dir_uuid_to_file(&dir, config.uuid, ConfigFormat::Yaml);
+            let found = ProfileConfig::stored_file(&dir, config.uuid);
+            let _ = fs::remove_file(&yaml_file);
+            let _ = fs::remove_file(&toml_file);
+
+            assert!(stored.is_ok());
+            assert_eq!(reloaded.unwrap().name, "Fuschi");
+            assert!(!json_exists);
+            assert!(converted.is_ok());
+            assert!(!toml_exists);
+            assert_eq!(found, ProfileConfig::dir_uuid_to_file(&dir, config.uuid, ConfigFormat::Yaml));
+        }
+
+        #[test]
+        fn load_dir_all_formats() {
+            let dir = PathBuf::from("test_tmp/load_dir_formats_tmp");
+            fs::create_dir_all(&dir).unwrap();
+            let uuids: Vec<Uuid> = ConfigFormat::ALL
+                .into_iter()
+                .map(|format| {
+                    let config = nested_config();
+                    config.store_as(&dir, format).unwrap();
+                    config.uuid
+                })
+                .collect();
+            fs::write(dir.join("notes.txt"), "not a profile").unwrap();
+
+            let configs = ProfileConfig::load_dir(&dir);
+            fs::remove_dir_all(&dir).unwrap();
+
+            let mut loaded: Vec<Uuid> = configs.unwrap().iter().map(|config| config.uuid).collect();
+            let mut uuids = uuids;
+            loaded.sort();
+            uuids.sort();
+            assert_eq!(loaded, uuids);
+        }
+    }
+
+    mod validate_tests {
+        use super::*;
+
+        /// Absolute path on the current platform
+        fn abs(path: &str) -> PathBuf {
+            if cfg!(target_family = "windows") {
+                PathBuf::from(format!("C:{}", path))
+            } else {
+                PathBuf::from(path)
+            }
+        }
+
+        fn valid_config() -> ProfileConfig {
+            ProfileConfig::new(
+                String::from("Hutzi"),
+                abs("/backups"),
+                vec![abs("/home/hutzi/notes.txt")],
+                vec![abs("/home/hutzi/Documents")],
+                vec![],
+                vec![abs("/home/hutzi/Documents/tmp")],
+                IntervalBuilder::default().build().unwrap(),
+            )
+        }
+
+        fn single_msg(config: &ProfileConfig) -> String {
+            let msgs = config.validate().unwrap_err();
+            assert_eq!(msgs.len(), 1, "{:?}", msgs);
+            msgs.into_iter().next().unwrap()
+        }
+
+        #[test]
+        fn valid() {
+            assert_eq!(valid_config().validate(), Ok(()));
+        }
+
+        #[test]
+        fn target_dir_inside_included_dir() {
+            let mut config = valid_config();
+            config.target_dir = abs("/home/hutzi/Documents/backups");
+            assert!(single_msg(&config).contains("target_dir"));
+
+            config.dirs_to_exclude.push(abs("/home/hutzi/Documents/backups"));
+            assert_eq!(config.validate(), Ok(()));
+        }
+
+        #[test]
+        fn relative_paths() {
+            let mut config = valid_config();
+            config.files_to_exclude = vec![PathBuf::from("notes.txt")];
+            assert!(single_msg(&config).contains("files_to_exclude"));
+        }
+
+        #[test]
+        fn duplicates() {
+            let mut config = valid_config();
+            config.dirs_to_include.push(abs("/home/hutzi/Music"));
+            config.dirs_to_include.push(abs("/home/hutzi/Documents"));
+            assert!(single_msg(&config).contains("more than once"));
+        }
+
+        #[test]
+        fn nothing_included() {
+            let mut config = valid_config();
+            config.files_to_include.clear();
+            config.dirs_to_include.clear();
+            assert!(single_msg(&config).contains("empty"));
+        }
+
+        #[test]
+        fn invalid_interval() {
+            let mut config = valid_config();
+            config.interval = IntervalBuilder::default()
+                .monthdays(SpecifierKind::Nth(30))
+                .months(SpecifierKind::Nth(1))
+                .build()
+                .unwrap();
+            assert!(single_msg(&config).contains("interval"));
+        }
+
+        #[test]
+        fn invalid_compression_level() {
+            let mut config = valid_config();
+            config.compression = Some(CompressionSettings {
+                method: CompressionMethod::Bzip2,
+                level: Some(0),
+                ..Default::default()
+            });
+            assert!(single_msg(&config).contains("Bzip2"));
+        }
+
+        #[test]
+        fn invalid_passphrase_source() {
+            let mut config = valid_config();
+            config.encryption = Some(EncryptionSettings {
+                passphrase: PassphraseSource::Env(String::new()),
+            });
+            assert!(single_msg(&config).contains("environment variable"));
+        }
+
+        #[test]
+        fn too_small_archive_size() {
+            let mut config = valid_config();
+            config.max_archive_size = Some(MIN_ARCHIVE_SIZE);
+            assert!(config.validate().is_ok());
+            config.max_archive_size = Some(MIN_ARCHIVE_SIZE - 1);
+            assert!(single_msg(&config).contains("max_archive_size"));
+        }
+
+        #[test]
+        fn all_mistakes() {
+            let mut config = valid_config();
+            config.files_to_include.clear();
+            config.dirs_to_include.clear();
+            config.dirs_to_exclude = vec![PathBuf::from("tmp"), PathBuf::from("tmp")];
+            config.exclude_patterns = vec![String::from("[abc")];
+            // relative, relative and duplicate, empty, pattern
+            assert_eq!(config.validate().unwrap_err().len(), 5);
+        }
+    }
+
+    mod resolve_paths_tests {
+        use super::*;
+
+        fn portable_config() -> ProfileConfig {
+            ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("${BACKUP_RS_TEST_TARGET}/backups"),
+                vec![PathBuf::from("%BACKUP_RS_TEST_HOME%/notes.txt")],
+                vec![PathBuf::from("%BACKUP_RS_TEST_HOME%/Documents")],
+                vec![],
+                vec![PathBuf::from("C:/$Recycle.Bin")],
+                IntervalBuilder::default().build().unwrap(),
+            )
+        }
+
+        #[test]
+        fn resolved() {
+            std::env::set_var("BACKUP_RS_TEST_TARGET", "E:");
+            std::env::set_var("BACKUP_RS_TEST_HOME", "C:/Users/hutzi");
+            let config = portable_config();
+            let resolved = config.resolve_paths().unwrap();
+
+            assert_eq!(resolved.target_dir, PathBuf::from("E:/backups"));
+            assert_eq!(resolved.files_to_include, vec![PathBuf::from("C:/Users/hutzi/notes.txt")]);
+            assert_eq!(resolved.dirs_to_include, vec![PathBuf::from("C:/Users/hutzi/Documents")]);
+            assert_eq!(resolved.dirs_to_exclude, vec![PathBuf::from("C:/$Recycle.Bin")]);
+            assert_eq!(resolved.uuid, config.uuid);
+            // the profile itself keeps the portable paths
+            assert_eq!(config.target_dir, PathBuf::from("${BACKUP_RS_TEST_TARGET}/backups"));
+        }
+
+        #[test]
+        fn unset_variable() {
+            let mut config = portable_config();
+            config.target_dir = PathBuf::from("E:/backups");
+            config.files_to_exclude = vec![PathBuf::from("%BACKUP_RS_TEST_UNSET%/notes.txt")];
+            std::env::set_var("BACKUP_RS_TEST_HOME", "C:/Users/hutzi");
+
+            let msg = config.resolve_paths().unwrap_err();
+            assert!(msg.contains("Hutzi"));
+            assert!(msg.contains("files_to_exclude"));
+            assert!(msg.contains("BACKUP_RS_TEST_UNSET"));
+        }
+    }
+
+    mod retention_tests {
+        use super::*;
+        use chrono::NaiveDate;
+
+        fn date(day: u32) -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(2023, 5, day)
+                .unwrap()
+                .and_hms_opt(10, 0, 0)
+                .unwrap()
+        }
+
+        fn retention_config(keep_last: Option<u32>, keep_days: Option<u32>) -> ProfileConfig {
+            let mut config = ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("ho"),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            );
+            config.keep_last = keep_last;
+            config.keep_days = keep_days;
+            config
+        }
+
+        fn archives() -> Vec<(NaiveDateTime, PathBuf)> {
+            (1..=5)
+                .map(|day| (date(day), PathBuf::from(format!("{}.zip", day))))
+                .collect()
+        }
+
+        fn pruned(config: &ProfileConfig) -> Vec<String> {
+            config
+                .archives_to_prune(&archives(), date(10))
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect()
+        }
+
+        #[test]
+        fn no_rules() {
+            assert!(pruned(&retention_config(None, None)).is_empty());
+        }
+
+        #[test]
+        fn keep_last() {
+            assert_eq!(pruned(&retention_config(Some(2), None)), vec!["1.zip", "2.zip", "3.zip"]);
+            assert!(pruned(&retention_config(Some(5), None)).is_empty());
+            assert!(pruned(&retention_config(Some(10), None)).is_empty());
+            // the newest archive is always kept
+            assert_eq!(pruned(&retention_config(Some(0), None)).len(), 4);
+        }
+
+        #[test]
+        fn keep_days() {
+            assert_eq!(pruned(&retention_config(None, Some(7))), vec!["1.zip", "2.zip"]);
+            assert_eq!(pruned(&retention_config(None, Some(0))).len(), 4);
+            assert!(pruned(&retention_config(None, Some(100))).is_empty());
+        }
+
+        #[test]
+        fn both_rules_keep_union() {
+            assert_eq!(pruned(&retention_config(Some(4), Some(7))), vec!["1.zip"]);
+            assert_eq!(pruned(&retention_config(Some(1), Some(7))), vec!["1.zip", "2.zip"]);
+        }
+
+        #[test]
+        fn no_archives() {
+            let config = retention_config(Some(1), Some(1));
+            assert!(config.archives_to_prune(&[], date(10)).is_empty());
+        }
+    }
+
+    mod opt_in_flags_tests {
+        use super::*;
+
+        #[test]
+        fn disabled_by_default() {
+            let file_path = PathBuf::from("test_tmp/001a828a-30ca-4b12-9756-6ce9696ac868.json");
+            let config = ProfileConfig::load(&file_path).unwrap();
+            assert!(!config.capture_ads);
+            assert!(!config.retry_in_use_files);
+            assert!(!config.small_files_first);
+            assert!(!config.retry_unstable_files);
+            assert_eq!(config.unstable_files, UnstableFilePolicy::Keep);
+            assert_eq!(config.backup_mode, BackupMode::Full);
+            assert_eq!(config.compression, None);
+            assert_eq!(config.symlink_policy, SymlinkPolicy::Follow);
+            assert!(!config.skip_if_unchanged);
+            assert_eq!(config.locked_file_policy, LockedFilePolicy::Skip);
+            assert_eq!(config.max_archive_size, None);
+            assert_eq!(config.encryption, None);
+            // verification is opt-out
+            assert!(config.verify_after_backup);
+        }
+
+        #[test]
+        fn roundtrip() {
+            let mut config = ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("ho"),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            );
+            config.capture_ads = true;
+            config.retry_in_use_files = true;
+            config.small_files_first = true;
+            config.retry_unstable_files = true;
+            config.unstable_files = UnstableFilePolicy::Drop;
+            config.backup_mode = BackupMode::Incremental;
+            config.symlink_policy = SymlinkPolicy::Preserve;
+            config.verify_after_backup = false;
+            config.skip_if_unchanged = true;
+            config.locked_file_policy = LockedFilePolicy::Retry(3);
+            config.max_archive_size = Some(4 << 30);
+            config.encryption = Some(EncryptionSettings {
+                passphrase: PassphraseSource::Env(String::from("BACKUP_KEY")),
+            });
+            config.compression = Some(CompressionSettings {
+                method: CompressionMethod::Stored,
+                ..Default::default()
+            });
+
+            let json = serde_json::to_string(&config).unwrap();
+            let parsed: ProfileConfig = serde_json::from_str(&json).unwrap();
+            assert!(parsed.capture_ads);
+            assert!(parsed.retry_in_use_files);
+            assert!(parsed.small_files_first);
+            assert!(parsed.retry_unstable_files);
+            assert_eq!(parsed.unstable_files, UnstableFilePolicy::Drop);
+            assert_eq!(parsed.backup_mode, BackupMode::Incremental);
+            assert_eq!(parsed.symlink_policy, SymlinkPolicy::Preserve);
+            assert!(!parsed.verify_after_backup);
+            assert!(parsed.skip_if_unchanged);
+            assert_eq!(parsed.locked_file_policy, LockedFilePolicy::Retry(3));
+            assert_eq!(parsed.max_archive_size, Some(4 << 30));
+            assert_eq!(parsed.encryption, config.encryption);
+            assert_eq!(parsed.compression.unwrap().method, CompressionMethod::Stored);
+        }
+    }
+
+    mod former_uuids_tests {
+        use super::*;
+
+        fn uuid_config() -> ProfileConfig {
+            ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("ho"),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            )
+        }
+
+        #[test]
+        fn empty_by_default() {
+            let file_path = PathBuf::from("test_tmp/001a828a-30ca-4b12-9756-6ce9696ac868.json");
+            let config = ProfileConfig::load(&file_path).unwrap();
+            assert!(config.former_uuids.is_empty());
+        }
+
+        #[test]
+        fn regenerate_uuid() {
+            let mut config = uuid_config();
+            let first = *config.get_uuid();
+            let second = config.regenerate_uuid();
+            let third = config.regenerate_uuid();
+
+            assert_eq!(config.get_uuid(), &third);
+            assert_ne!(first, second);
+            assert_ne!(second, third);
+            assert_eq!(config.former_uuids, vec![first, second]);
+            assert!(config.matches_uuid(&first));
+            assert!(config.matches_uuid(&second));
+            assert!(config.matches_uuid(&third));
+            assert!(!config.matches_uuid(&Uuid::new_v4()));
+        }
+
+        #[test]
+        fn duplicate() {
+            let mut config = uuid_config();
+            config.regenerate_uuid();
+            config.tags = vec![String::from("work")];
+            config.last_backup = Some(config.next_backup);
+            config.last_backup_archive = Some(PathBuf::from("ho/archive.zip"));
+
+            let copy = config.duplicate(String::from("Hutzi 2"));
+
+            assert_eq!(copy.name, "Hutzi 2");
+            assert_ne!(copy.get_uuid(), config.get_uuid());
+            assert!(!copy.matches_uuid(config.get_uuid()));
+            assert!(copy.former_uuids.is_empty());
+            assert!(copy.last_backup.is_none());
+            assert!(copy.last_backup_archive.is_none());
+            assert_eq!(copy.target_dir, config.target_dir);
+            assert_eq!(copy.tags, config.tags);
+            assert_eq!(copy.interval, config.interval);
+        }
+
+        #[test]
+        fn set_uuid_back() {
+            let mut config = uuid_config();
+            let first = *config.get_uuid();
+            config.regenerate_uuid();
+            config.set_uuid(first);
+            config.regenerate_uuid();
+            assert_eq!(config.former_uuids, vec![first]);
+        }
+
+        #[test]
+        fn own_archives() {
+            let mut config = uuid_config();
+            let first = *config.get_uuid();
+            config.regenerate_uuid();
+            let current = *config.get_uuid();
+
+            let archive = |uuid: &Uuid| format!("{}_2023-05-01_12-00.zip", uuid.as_hyphenated());
+            assert!(config.is_own_archive(&archive(&first)));
+            assert!(config.is_own_archive(&archive(&current)));
+            assert!(!config.is_own_archive(&archive(&Uuid::new_v4())));
+            assert!(!config.is_own_archive("backup.zip"));
+        }
+
+        #[test]
+        fn roundtrip() {
+            let mut config = uuid_config();
+            config.regenerate_uuid();
+            let json = serde_json::to_string(&config).unwrap();
+            let parsed: ProfileConfig = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed.former_uuids, config.former_uuids);
+        }
+    }
+
+    mod archive_order_tests {
+        use super::*;
+
+        fn files() -> Vec<(PathBuf, u64)> {
+            vec![
+                (PathBuf::from("/home/hutzi/vm.img"), 10_000),
+                (PathBuf::from("/home/hutzi/docs/b.txt"), 20),
+                (PathBuf::from("/home/hutzi/docs/a.txt"), 300),
+                (PathBuf::from("/home/hutzi/pics/dog.jpg"), 20),
+                (PathBuf::from("/home/hutzi/docs/c.txt"), 20),
+                (PathBuf::from("/etc/hosts"), 5000),
+                (PathBuf::from("/srv/big.iso"), 100),
+                (PathBuf::from("/srv/small.txt"), 1),
+            ]
+        }
+
+        fn order_config(small_files_first: bool) -> ProfileConfig {
+            let mut config = ProfileConfig::new(
+                String::from("Hutzi"),
+                PathBuf::from("ho"),
+                vec![PathBuf::from("/etc/hosts")],
+                vec![PathBuf::from("/home/hutzi"), PathBuf::from("/srv")],
+                vec![],
+                vec![],
+                IntervalBuilder::default().build().unwrap(),
+            );
+            config.small_files_first = small_files_first;
+            config
+        }
+
+        #[test]
+        fn unchanged_by_default() {
+            assert_eq!(order_config(false).archive_order(files()), files());
+        }
+
+        #[test]
+        fn small_files_first() {
+            let ordered: Vec<PathBuf> = order_config(true)
+                .archive_order(files())
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect();
+            assert_eq!(
+                ordered,
+                vec![
+                    PathBuf::from("/etc/hosts"),
+                    PathBuf::from("/home/hutzi/docs/b.txt"),
+                    PathBuf::from("/home/hutzi/docs/c.txt"),
+                    PathBuf::from("/home/hutzi/pics/dog.jpg"),
+                    PathBuf::from("/home/hutzi/docs/a.txt"),
+                    PathBuf::from("/home/hutzi/vm.img"),
+                    PathBuf::from("/srv/small.txt"),
+                    PathBuf::from("/srv/big.iso"),
+                ]
+            );
+        }
+    }
+
+    mod exclusion_tests {
+        use super::*;
+
+        fn exclusion_config(
+            files_to_exclude: Vec<PathBuf>,
+            dirs_to_exclude: Vec<PathBuf>,
+        ) -> ProfileConfig {
+            ProfileConfig::new(
+                "".to_string(),
+                PathBuf::from(""),
+                vec![],
+                vec![],
+                files_to_exclude,
+                dirs_to_exclude,
+                IntervalBuilder::default().build().unwrap(),
+            )
+        }
+
+        #[test]
+        fn is_in_dir() {
+            let dir = PathBuf::from("/home/hutzi/Documents");
+            let path = PathBuf::from("/home/fuschi/Documents");
+            assert!(!ProfileConfig::is_in_dir(&path, &dir));
+
+            let path = PathBuf::from("/etc/passwd");
+            assert!(!ProfileConfig::is_in_dir(&path, &dir));
+
+            let path = PathBuf::from("/home/hutzi/Pictures/dog.jpg");
+            assert!(!ProfileConfig::is_in_dir(&path, &dir));
+
+            let path = PathBuf::from("/home/hutzi");
+            assert!(!ProfileConfig::is_in_dir(&path, &dir));
+
+            let path = PathBuf::from("/home/hutzi/Documents");
+            assert!(ProfileConfig::is_in_dir(&path, &dir));
+
+            let path = PathBuf::from("/home/hutzi/Documents/homework");
+            assert!(ProfileConfig::is_in_dir(&path, &dir));
+
+            let dir = PathBuf::from("C:\\users\\fuschi");
+            let path = PathBuf::from("C:\\users");
+            assert!(!ProfileConfig::is_in_dir(&path, &dir));
+
+            let path = PathBuf::from("C:\\users\\fuschi\\Downloads");
+            assert!(ProfileConfig::is_in_dir(&path, &dir));
+        }
+
+        #[test]
+        fn is_excluded() {
+            let excluded_files = vec![
+                PathBuf::from("/etc/passwd"),
+                PathBuf::from("/home/hutzi/test.txt"),
+                PathBuf::from("C:\\users\\tester\\test.md"),
+            ];
             let excluded_dirs = vec![
                 PathBuf::from("/var"),
                 PathBuf::from("/home/fuschi"),
@@ -597,6 +2506,89 @@ mod profile_config_tests {
             let path = PathBuf::from("C:\\Windows");
             assert!(!config.is_excluded(&path));
         }
+
+        #[test]
+        fn exclude_patterns() {
+            let mut config = exclusion_config(vec![], vec![]);
+            config.exclude_patterns = vec![String::from("**/node_modules"), String::from("*.tmp")];
+
+            assert!(config.is_excluded(&PathBuf::from("/home/hutzi/project/node_modules")));
+            assert!(config.is_excluded(&PathBuf::from("/home/hutzi/notes.tmp")));
+            assert!(config.is_excluded(&PathBuf::from("/home/hutzi/NOTES.TMP")));
+            assert!(!config.is_excluded(&PathBuf::from("/home/hutzi/notes.txt")));
+            assert!(!config.is_excluded(&PathBuf::from("/home/hutzi/node_modules_backup")));
+        }
+
+        #[test]
+        fn exclude_patterns_with_backslashes() {
+            let mut config = exclusion_config(vec![], vec![]);
+            config.exclude_patterns = vec![String::from("C:/Users/*/AppData/**")];
+
+            assert!(config.is_excluded(&PathBuf::from("C:\\Users\\hutzi\\AppData\\Local\\cache.db")));
+            assert!(!config.is_excluded(&PathBuf::from("C:\\Users\\hutzi\\Documents\\cv.pdf")));
+        }
+
+        #[test]
+        fn exclude_patterns_changed_after_use() {
+            let mut config = exclusion_config(vec![], vec![]);
+            let path = PathBuf::from("/home/hutzi/notes.tmp");
+            assert!(!config.is_excluded(&path));
+
+            config.exclude_patterns = vec![String::from("*.tmp")];
+            assert!(config.is_excluded(&path));
+
+            config.exclude_patterns = vec![String::from("*.bak")];
+            assert!(!config.is_excluded(&path));
+        }
+
+        #[test]
+        fn invalid_exclude_patterns() {
+            let mut config = exclusion_config(vec![], vec![]);
+            config.exclude_patterns = vec![String::from("*.tmp")];
+            assert!(config.validate_patterns().is_ok());
+
+            config.exclude_patterns.push(String::from("[abc"));
+            assert!(config.validate_patterns().is_err());
+            // the valid pattern is still applied
+            assert!(config.is_excluded(&PathBuf::from("/home/hutzi/notes.tmp")));
+            assert!(!config.is_excluded(&PathBuf::from("/home/hutzi/[abc")));
+        }
+
+        #[test]
+        fn extensions() {
+            let mut config = exclusion_config(vec![], vec![]);
+            assert!(!config.is_extension_excluded(&PathBuf::from("C:\\isos\\debian.iso")));
+
+            config.exclude_extensions = vec![String::from("iso"), String::from(".tmp")];
+            assert!(config.is_extension_excluded(&PathBuf::from("C:\\isos\\debian.iso")));
+            assert!(config.is_extension_excluded(&PathBuf::from("/home/hutzi/debian.ISO")));
+            assert!(config.is_extension_excluded(&PathBuf::from("/home/hutzi/notes.tmp")));
+            assert!(!config.is_extension_excluded(&PathBuf::from("/home/hutzi/iso")));
+            assert!(!config.is_extension_excluded(&PathBuf::from("/home/hutzi/debian.iso.txt")));
+
+            config.include_extensions = vec![String::from("txt")];
+            assert!(!config.is_extension_excluded(&PathBuf::from("/home/hutzi/debian.iso.txt")));
+            assert!(config.is_extension_excluded(&PathBuf::from("/home/hutzi/cv.pdf")));
+            assert!(config.is_extension_excluded(&PathBuf::from("/home/hutzi/README")));
+        }
+
+        #[test]
+        fn max_file_size() {
+            let mut config = exclusion_config(vec![], vec![]);
+            assert!(!config.exceeds_max_file_size(u64::MAX));
+
+            config.max_file_size = Some(1 << 30);
+            assert!(!config.exceeds_max_file_size(0));
+            assert!(!config.exceeds_max_file_size(1 << 30));
+            assert!(config.exceeds_max_file_size((1 << 30) + 1));
+        }
+
+        #[test]
+        fn no_exclude_patterns_in_old_config() {
+            let file_path = PathBuf::from("test_tmp/001a828a-30ca-4b12-9756-6ce9696ac868.json");
+            let config = ProfileConfig::load(&file_path).unwrap();
+            assert!(config.exclude_patterns.is_empty());
+        }
     }
 
     #[cfg(feature = "protobuf")]
@@ -608,7 +2600,7 @@ mod profile_config_tests {
         #[test]
         fn invalid_buf() {
             let buf = Bytes::from("Hutzi");
-            assert!(ProfileConfig::from_buf(buf).is_err());
+            assert!(matches!(ProfileConfig::from_buf(buf), Err(Error::Parse(_))));
         }
 
         #[test]
@@ -664,6 +2656,184 @@ mod profile_config_tests {
             assert_eq!(deserialized.dirs_to_include, profile_config.dirs_to_include);
             assert_eq!(deserialized.dirs_to_exclude, profile_config.dirs_to_exclude);
             assert_eq!(deserialized.interval, profile_config.interval);
+            assert_eq!(deserialized.next_backup, profile_config.next_backup);
+        }
+
+        #[test]
+        fn filters_roundtrip() {
+            let mut config = proto_config("filters");
+            config.exclude_patterns = vec![String::from("**/node_modules")];
+            config.exclude_extensions = vec![String::from("tmp")];
+            config.include_extensions = vec![String::from("jpg"), String::from("png")];
+            config.max_file_size = Some(1024);
+            config.tags = vec![String::from("work")];
+            config.enabled = false;
+
+            let deserialized = ProfileConfig::from_buf(config.to_buf()).unwrap();
+            assert_eq!(deserialized.exclude_patterns, config.exclude_patterns);
+            assert_eq!(deserialized.exclude_extensions, config.exclude_extensions);
+            assert_eq!(deserialized.include_extensions, config.include_extensions);
+            assert_eq!(deserialized.max_file_size, config.max_file_size);
+            assert_eq!(deserialized.tags, config.tags);
+            assert!(!deserialized.enabled);
+        }
+
+        #[test]
+        fn old_buf_without_new_fields() {
+            // messages of older versions only contain the name, uuid, paths and interval
+            let mut proto_config =
+                proto::ProfileConfig::decode(proto_config("old").to_buf()).unwrap();
+            proto_config.next_backup = None;
+            proto_config.max_file_size = None;
+            proto_config.enabled = None;
+            proto_config.exclude_patterns = vec![];
+            proto_config.exclude_extensions = vec![];
+            proto_config.include_extensions = vec![];
+            proto_config.tags = vec![];
+            let buf = Bytes::from(proto_config.encode_to_vec());
+
+            let before = chrono::Local::now().naive_local();
+            let deserialized = ProfileConfig::from_buf(buf).unwrap();
+            assert_eq!(deserialized.name, "old");
+            assert!(deserialized.next_backup >= before);
+            assert!(deserialized.enabled);
+            assert!(deserialized.max_file_size.is_none());
+        }
+
+        #[test]
+        fn invalid_next_backup() {
+            let mut proto_config =
+                proto::ProfileConfig::decode(proto_config("invalid").to_buf()).unwrap();
+            proto_config.next_backup = Some(String::from("tomorrow"));
+            let buf = Bytes::from(proto_config.encode_to_vec());
+
+            assert!(matches!(ProfileConfig::from_buf(buf), Err(Error::Parse(_))));
+        }
+
+        fn proto_config(name: &str) -> ProfileConfig {
+            ProfileConfig::new(
+                name.to_string(),
+                PathBuf::from("target_dir"),
+                vec![PathBuf::from("file")],
+                vec![PathBuf::from("dir")],
+                vec![],
+                vec![],
+                Interval::daily(30, 3).unwrap(),
+            )
+        }
+
+        #[test]
+        fn buf_list_roundtrip() {
+            let configs = vec![proto_config("a"), proto_config("b"), proto_config("c")];
+            let buf = ProfileConfig::to_buf_list(&configs);
+
+            let deserialized = ProfileConfig::from_buf_list(&buf).unwrap();
+            assert_eq!(deserialized.len(), 3);
+            for (deserialized, config) in deserialized.iter().zip(configs.iter()) {
+                assert_eq!(deserialized.name, config.name);
+                assert_eq!(deserialized.uuid, config.uuid);
+                assert_eq!(deserialized.interval, config.interval);
+            }
+
+            assert!(ProfileConfig::from_buf_list(&ProfileConfig::to_buf_list(&[]))
+                .unwrap()
+                .is_empty());
+        }
+
+        #[test]
+        fn range_and_except_roundtrip() {
+            let mut config = proto_config("range");
+            config.interval = IntervalBuilder::default()
+                .minutes(SpecifierKind::Nth(0))
+                .hours(SpecifierKind::Range(8, 17))
+                .weekdays(SpecifierKind::Except(vec![6]))
+                .build()
+                .unwrap();
+
+            let deserialized = ProfileConfig::from_buf(config.to_buf()).unwrap();
+            assert_eq!(deserialized.interval, config.interval);
+        }
+
+        #[test]
+        fn nth_weekday_roundtrip() {
+            let mut config = proto_config("nth_weekday");
+            config.interval = IntervalBuilder::default()
+                .weekdays(SpecifierKind::Nth(0))
+                .nth_weekday(0)
+                .build()
+                .unwrap();
+
+            let deserialized = ProfileConfig::from_buf(config.to_buf()).unwrap();
+            assert_eq!(deserialized.interval, config.interval);
+        }
+
+        #[test]
+        fn seconds_roundtrip() {
+            let mut config = proto_config("seconds");
+            config.interval = IntervalBuilder::default()
+                .seconds(SpecifierKind::EveryNth(15, 0))
+                .build()
+                .unwrap();
+
+            let deserialized = ProfileConfig::from_buf(config.to_buf()).unwrap();
+            assert_eq!(deserialized.interval, config.interval);
+        }
+
+        #[test]
+        fn excluded_dates_roundtrip() {
+            let mut config = proto_config("excluded_dates");
+            config.interval = IntervalBuilder::default()
+                .excluded_dates(vec![
+                    chrono::NaiveDate::from_ymd_opt(2023, 12, 24).unwrap(),
+                    chrono::NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+                ])
+                .build()
+                .unwrap();
+
+            let deserialized = ProfileConfig::from_buf(config.to_buf()).unwrap();
+            assert_eq!(deserialized.interval, config.interval);
+        }
+
+        #[test]
+        fn truncated_buf_list() {
+            let buf = ProfileConfig::to_buf_list(&[proto_config("a"), proto_config("b")]);
+            assert!(ProfileConfig::from_buf_list(&buf[..buf.len() - 1]).is_err());
+        }
+
+        #[test]
+        fn update_from_buf_keeps_other_fields() {
+            let mut stored = proto_config("old");
+            stored.exclude_extensions = vec![String::from("tmp")];
+            stored.max_file_size = Some(1024);
+            let next_backup = stored.next_backup;
+
+            let mut changed = proto_config("new");
+            changed.set_uuid(stored.uuid);
+            changed.interval = Interval::daily(0, 12).unwrap();
+
+            stored.update_from_buf(&changed.to_buf()).unwrap();
+            assert_eq!(stored.name, "new");
+            assert_eq!(stored.interval, changed.interval);
+            assert_eq!(stored.exclude_extensions, vec![String::from("tmp")]);
+            assert_eq!(stored.max_file_size, Some(1024));
+            assert_eq!(stored.next_backup, next_backup);
+
+            assert!(stored.update_from_buf(b"Hutzi").is_err());
+            assert_eq!(stored.name, "new");
+        }
+
+        #[test]
+        fn json_and_proto_agree() {
+            let config = proto_config("Hutzi");
+            let json = serde_json::to_string(&config).unwrap();
+            let from_json: ProfileConfig = serde_json::from_str(&json).unwrap();
+            let from_proto = ProfileConfig::from_buf(from_json.to_buf()).unwrap();
+
+            assert_eq!(from_proto.name, config.name);
+            assert_eq!(from_proto.uuid, config.uuid);
+            assert_eq!(from_proto.files_to_include, config.files_to_include);
+            assert_eq!(from_proto.dirs_to_include, config.dirs_to_include);
+            assert_eq!(from_proto.interval, config.interval);
         }
 
         #[test]
@@ -678,6 +2848,9 @@ mod profile_config_tests {
                     kind: 743892,
                     values: vec![],
                 }),
+                nth_weekday: None,
+                seconds: None,
+                excluded_dates: vec![],
             };
             let proto_config = proto::ProfileConfig {
                 name: "Hutzi".to_string(),
@@ -688,6 +2861,7 @@ mod profile_config_tests {
                 files_to_exclude: vec![],
                 dirs_to_exclude: vec![],
                 interval: Some(interval),
+                ..Default::default()
             };
 
             let buf = Bytes::from(proto_config.encode_to_vec());
@@ -708,6 +2882,9 @@ mod profile_config_tests {
                     kind: ProtoSpecifierKind::Nth as i32,
                     values: vec![],
                 }),
+                nth_weekday: None,
+                seconds: None,
+                excluded_dates: vec![],
             };
             let proto_config = proto::ProfileConfig {
                 name: "Hutzi".to_string(),
@@ -718,6 +2895,7 @@ mod profile_config_tests {
                 files_to_exclude: vec![],
                 dirs_to_exclude: vec![],
                 interval: Some(interval),
+                ..Default::default()
             };
 
             let buf = Bytes::from(proto_config.encode_to_vec());
@@ -737,6 +2915,7 @@ mod profile_config_tests {
                 files_to_exclude: vec![],
                 dirs_to_exclude: vec![],
                 interval: None,
+                ..Default::default()
             };
 
             let buf = Bytes::from(proto_config.encode_to_vec());
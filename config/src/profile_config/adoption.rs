@@ -0,0 +1,143 @@
+//! Contains functions for adopting [ProfileConfig]s on a (new) machine, e.g., after restoring them from a backup.
+
+use std::path::PathBuf;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::ProfileConfig;
+
+/// Describes the result of adopting a single [ProfileConfig].
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct AdoptionReport {
+    /// [Uuid] of the adopted profile
+    pub uuid: Uuid,
+    /// Name of the adopted profile
+    pub name: String,
+    /// Whether `next_backup` was stale and had to be recomputed
+    pub rescheduled: bool,
+    /// `next_backup` after the adoption
+    pub next_backup: NaiveDateTime,
+    /// The `target_dir` of the profile
+    pub target_dir: PathBuf,
+    /// Whether the `target_dir` exists and is a directory, after resolving its variables (see [ProfileConfig::resolve_paths])
+    pub target_available: bool,
+    /// Whether the schedule was registered with the operating system. Has to be set by the caller after registering.
+    pub schedule_registered: bool,
+}
+
+impl ProfileConfig {
+    /// Adopts the profile: If `next_backup` lies before `now`, it is recomputed from the `interval` starting at `now`.
+    /// Also refreshes `upcoming` and checks whether the `target_dir` is available.
+    ///
+    /// Registering the schedule with the operating system is left to the caller.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    /// use config::profile_config::*;
+    /// use chrono::NaiveDate;
+    /// use std::path::PathBuf;
+    ///
+    /// let mut config = ProfileConfig::new(
+    ///     String::from("Hutzi"),
+    ///     PathBuf::from("target_dir"),
+    ///     vec![],
+    ///     vec![],
+    ///     vec![],
+    ///     vec![],
+    ///     Interval::daily(0, 12).unwrap(),
+    /// );
+    /// config.next_backup = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    /// let now = NaiveDate::from_ymd_opt(2023, 5, 1).unwrap().and_hms_opt(18, 0, 0).unwrap();
+    ///
+    /// let report = config.adopt(now);
+    /// assert!(report.rescheduled);
+    /// assert_eq!(report.next_backup, NaiveDate::from_ymd_opt(2023, 5, 2).unwrap().and_hms_opt(12, 0, 0).unwrap());
+    /// ```
+    pub fn adopt(&mut self, now: NaiveDateTime) -> AdoptionReport {
+        let rescheduled = self.next_backup < now;
+        if rescheduled {
+            self.next_backup = self.get_next_scheduled(Some(now));
+        }
+        self.refresh_upcoming();
+
+        AdoptionReport {
+            uuid: self.uuid,
+            name: self.name.clone(),
+            rescheduled,
+            next_backup: self.next_backup,
+            target_available: self
+                .resolve_paths()
+                .is_ok_and(|resolved| resolved.target_dir.is_dir()),
+            target_dir: self.target_dir.clone(),
+            schedule_registered: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod adoption_tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::interval::Interval;
+
+    fn daily_config(target_dir: &str) -> ProfileConfig {
+        ProfileConfig::new(
+            String::from("Hutzi"),
+            PathBuf::from(target_dir),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            Interval::daily(30, 3).unwrap(),
+        )
+    }
+
+    fn datetime(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 5, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn stale_next_backup() {
+        let mut config = daily_config("test_tmp");
+        config.next_backup = datetime(1, 3);
+
+        let report = config.adopt(datetime(10, 12));
+        assert!(report.rescheduled);
+        assert_eq!(
+            config.next_backup,
+            NaiveDate::from_ymd_opt(2023, 5, 11)
+                .unwrap()
+                .and_hms_opt(3, 30, 0)
+                .unwrap()
+        );
+        assert_eq!(report.next_backup, config.next_backup);
+        assert_eq!(report.uuid, *config.get_uuid());
+        assert!(!report.schedule_registered);
+    }
+
+    #[test]
+    fn up_to_date_next_backup() {
+        let mut config = daily_config("test_tmp");
+        config.next_backup = datetime(11, 3);
+
+        let report = config.adopt(datetime(10, 12));
+        assert!(!report.rescheduled);
+        assert_eq!(config.next_backup, datetime(11, 3));
+    }
+
+    #[test]
+    fn target_availability() {
+        let mut config = daily_config("test_tmp");
+        assert!(config.adopt(datetime(10, 12)).target_available);
+
+        let mut config = daily_config("non_existing_target_dir");
+        assert!(!config.adopt(datetime(10, 12)).target_available);
+    }
+}
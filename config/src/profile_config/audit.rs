@@ -0,0 +1,340 @@
+//! Contains functions for keeping an audit log of the changes made to a [ProfileConfig].
+//!
+//! Every [ProfileConfig] has its own log file in the profile config dir (see [ProfileConfig::audit_log_file]).
+//! It contains one JSON encoded [AuditEntry] per line.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{offset, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::ProfileConfig;
+use crate::Error;
+
+/// Size in bytes after which the audit log gets rotated. Only one rotated log is kept.
+pub const MAX_AUDIT_LOG_BYTES: u64 = 1024 * 1024;
+
+/// Fields that are derived from other fields and therefore not recorded in the audit log
+const IGNORED_FIELDS: [&str; 2] = ["upcoming", "written_by_version"];
+
+/// Describes who changed a [ProfileConfig].
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AuditActor {
+    /// The command line interface of the backupper
+    Cli,
+    /// The REST API
+    Server {
+        /// IP address of the client that sent the request
+        client_ip: Option<String>,
+    },
+    /// The backupper while performing a backup, e.g., when advancing `next_backup`
+    Backupper,
+}
+
+/// Describes what happened to a [ProfileConfig].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// Describes the change of a single field.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    /// Name of the changed field
+    pub field: String,
+    /// Value before the change. [Value::Null] if the field didn't exist.
+    pub old: Value,
+    /// Value after the change. [Value::Null] if the field doesn't exist anymore.
+    pub new: Value,
+}
+
+/// A single entry of the audit log.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: NaiveDateTime,
+    pub actor: AuditActor,
+    pub action: AuditAction,
+    /// Changed fields, sorted by name
+    pub changes: Vec<FieldChange>,
+}
+
+/// Computes the field-level differences between two versions of a [ProfileConfig].
+/// [None] stands for a [ProfileConfig] that doesn't exist (yet).
+pub fn diff_profile_configs(
+    old: Option<&ProfileConfig>,
+    new: Option<&ProfileConfig>,
+) -> Vec<FieldChange> {
+    let to_fields = |config: Option<&ProfileConfig>| match config.map(serde_json::to_value) {
+        Some(Ok(Value::Object(fields))) => fields,
+        _ => serde_json::Map::new(),
+    };
+    let old = to_fields(old);
+    let new = to_fields(new);
+
+    let mut fields: Vec<&String> = old.keys().chain(new.keys()).collect();
+    fields.sort_unstable();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter(|field| !IGNORED_FIELDS.contains(&field.as_str()))
+        .filter_map(|field| {
+            let old = old.get(field).cloned().unwrap_or(Value::Null);
+            let new = new.get(field).cloned().unwrap_or(Value::Null);
+            if old == new {
+                return None;
+            }
+            Some(FieldChange {
+                field: field.clone(),
+                old,
+                new,
+            })
+        })
+        .collect()
+}
+
+impl ProfileConfig {
+    /// Returns the path of the audit log of the [ProfileConfig] with the given [Uuid] in the given dir.
+    pub fn audit_log_file(dir_path: &Path, uuid: &Uuid) -> PathBuf {
+        dir_path.join(format!("{}.audit.log", uuid.as_hyphenated()))
+    }
+
+    /// Stores the [ProfileConfig] like [ProfileConfig::store] and appends the changes compared to the previously stored version to the audit log.
+    /// Nothing is logged if no field changed.
+    pub fn store_audited(&self, dir_path: &PathBuf, actor: AuditActor) -> Result<(), Error> {
+        let previous = Self::load(&Self::stored_file(dir_path, self.uuid)).ok();
+        self.store(dir_path)?;
+
+        let action = match previous {
+            Some(_) => AuditAction::Update,
+            None => AuditAction::Create,
+        };
+        let changes = diff_profile_configs(previous.as_ref(), Some(self));
+        if action == AuditAction::Update && changes.is_empty() {
+            return Ok(());
+        }
+
+        append_audit_entry(
+            dir_path,
+            &self.uuid,
+            &AuditEntry {
+                timestamp: offset::Local::now().naive_local(),
+                actor,
+                action,
+                changes,
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Appends the deletion of this [ProfileConfig] to the audit log. Doesn't delete anything.
+    pub fn audit_deletion(&self, dir_path: &Path, actor: AuditActor) -> Result<(), Error> {
+        append_audit_entry(
+            dir_path,
+            &self.uuid,
+            &AuditEntry {
+                timestamp: offset::Local::now().naive_local(),
+                actor,
+                action: AuditAction::Delete,
+                changes: diff_profile_configs(Some(self), None),
+            },
+        )?;
+        Ok(())
+    }
+}
+
+/// Appends `entry` to the audit log of the [ProfileConfig] with the given [Uuid].
+/// If the log exceeds [MAX_AUDIT_LOG_BYTES], it is rotated first.
+pub fn append_audit_entry(
+    dir_path: &Path,
+    uuid: &Uuid,
+    entry: &AuditEntry,
+) -> Result<(), io::Error> {
+    let file_path = ProfileConfig::audit_log_file(dir_path, uuid);
+    if let Ok(metadata) = fs::metadata(&file_path) {
+        if metadata.len() >= MAX_AUDIT_LOG_BYTES {
+            fs::rename(&file_path, rotated_file(&file_path))?;
+        }
+    }
+
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&file_path)?;
+    file.write_all(line.as_bytes())
+}
+
+/// Reads the audit log of the [ProfileConfig] with the given [Uuid], including the rotated log.
+/// Lines that can't be parsed are skipped.
+///
+/// # Returns
+/// [Ok] containing the entries from oldest to newest. Empty if there is no audit log.
+pub fn read_audit_log(dir_path: &Path, uuid: &Uuid) -> Result<Vec<AuditEntry>, io::Error> {
+    let file_path = ProfileConfig::audit_log_file(dir_path, uuid);
+
+    let mut entries = vec![];
+    for path in [rotated_file(&file_path), file_path] {
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for line in BufReader::new(file).lines() {
+            if let Ok(entry) = serde_json::from_str(&line?) {
+                entries.push(entry);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn rotated_file(file_path: &Path) -> PathBuf {
+    let mut rotated = file_path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+#[cfg(test)]
+mod audit_tests {
+    use super::*;
+    use crate::interval::Interval;
+
+    fn audit_config() -> ProfileConfig {
+        ProfileConfig::new(
+            String::from("Hutzi"),
+            PathBuf::from("ho"),
+            vec![],
+            vec![PathBuf::from("/home/hutzi")],
+            vec![],
+            vec![],
+            Interval::daily(0, 12).unwrap(),
+        )
+    }
+
+    fn cleanup(dir: &PathBuf, uuid: &Uuid) {
+        let log = ProfileConfig::audit_log_file(dir, uuid);
+        let _ = fs::remove_file(rotated_file(&log));
+        let _ = fs::remove_file(log);
+        let _ = fs::remove_file(ProfileConfig::stored_file(dir, *uuid));
+    }
+
+    #[test]
+    fn diff_unchanged() {
+        let config = audit_config();
+        assert!(diff_profile_configs(Some(&config), Some(&config)).is_empty());
+    }
+
+    #[test]
+    fn diff_changed_fields() {
+        let old = audit_config();
+        let mut new = old.clone();
+        new.dirs_to_include.clear();
+        new.name = String::from("Hutzi 2");
+        new.upcoming.clear();
+
+        let changes = diff_profile_configs(Some(&old), Some(&new));
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].field, "dirs_to_include");
+        assert_eq!(changes[0].old, serde_json::json!(["/home/hutzi"]));
+        assert_eq!(changes[0].new, serde_json::json!([]));
+        assert_eq!(changes[1].field, "name");
+        assert_eq!(changes[1].new, serde_json::json!("Hutzi 2"));
+    }
+
+    #[test]
+    fn diff_created_and_deleted() {
+        let config = audit_config();
+
+        let created = diff_profile_configs(None, Some(&config));
+        assert!(created.iter().all(|change| change.old == Value::Null));
+        assert!(created.iter().any(|change| change.field == "name"));
+        assert!(!created.iter().any(|change| change.field == "upcoming"));
+
+        let deleted = diff_profile_configs(Some(&config), None);
+        assert_eq!(deleted.len(), created.len());
+        assert!(deleted.iter().all(|change| change.new == Value::Null));
+    }
+
+    #[test]
+    fn store_audited() {
+        let dir = PathBuf::from("test_tmp");
+        let mut config = audit_config();
+        let uuid = *config.get_uuid();
+
+        config.store_audited(&dir, AuditActor::Cli).unwrap();
+        // unchanged; shouldn't be logged
+        config.store_audited(&dir, AuditActor::Cli).unwrap();
+        config.max_file_size = Some(1);
+        config
+            .store_audited(
+                &dir,
+                AuditActor::Server {
+                    client_ip: Some(String::from("127.0.0.1")),
+                },
+            )
+            .unwrap();
+        config.audit_deletion(&dir, AuditActor::Backupper).unwrap();
+
+        let entries = read_audit_log(&dir, &uuid).unwrap();
+        cleanup(&dir, &uuid);
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].action, AuditAction::Create);
+        assert_eq!(entries[0].actor, AuditActor::Cli);
+        assert_eq!(entries[1].action, AuditAction::Update);
+        assert_eq!(
+            entries[1].changes,
+            vec![FieldChange {
+                field: String::from("max_file_size"),
+                old: Value::Null,
+                new: serde_json::json!(1),
+            }]
+        );
+        assert_eq!(entries[2].action, AuditAction::Delete);
+        assert_eq!(entries[2].actor, AuditActor::Backupper);
+    }
+
+    #[test]
+    fn rotation() {
+        let dir = PathBuf::from("test_tmp");
+        let config = audit_config();
+        let uuid = *config.get_uuid();
+        let log = ProfileConfig::audit_log_file(&dir, &uuid);
+
+        config.audit_deletion(&dir, AuditActor::Cli).unwrap();
+        // pad the log so the next entry triggers the rotation
+        let mut file = OpenOptions::new().append(true).open(&log).unwrap();
+        file.write_all(&vec![b' '; MAX_AUDIT_LOG_BYTES as usize]).unwrap();
+        file.write_all(b"\n").unwrap();
+        config.audit_deletion(&dir, AuditActor::Backupper).unwrap();
+
+        let rotated_exists = rotated_file(&log).exists();
+        let current_len = fs::metadata(&log).unwrap().len();
+        let entries = read_audit_log(&dir, &uuid).unwrap();
+        cleanup(&dir, &uuid);
+
+        assert!(rotated_exists);
+        assert!(current_len < MAX_AUDIT_LOG_BYTES);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].actor, AuditActor::Cli);
+        assert_eq!(entries[1].actor, AuditActor::Backupper);
+    }
+
+    #[test]
+    fn no_audit_log() {
+        let entries = read_audit_log(&PathBuf::from("test_tmp"), &Uuid::new_v4()).unwrap();
+        assert!(entries.is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+//! Contains the settings for compressing the files in the archives of a [ProfileConfig](super::ProfileConfig).
+
+use std::{collections::BTreeMap, ffi::OsStr, ops::RangeInclusive, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// Method used to compress the files in an archive.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionMethod {
+    /// Stores the files without compressing them. Fastest choice for already compressed files like images or videos.
+    Stored,
+    /// Compresses the files with Deflate, which is supported by every zip tool.
+    #[default]
+    Deflated,
+    /// Compresses the files with Bzip2.
+    Bzip2,
+    /// Compresses the files with Zstandard, which is fast, but not supported by every zip tool.
+    Zstd,
+}
+
+impl CompressionMethod {
+    /// Range of the compression levels the method supports. [None] if it doesn't support any.
+    pub fn level_range(&self) -> Option<RangeInclusive<i32>> {
+        match self {
+            CompressionMethod::Stored => None,
+            CompressionMethod::Deflated => Some(0..=9),
+            CompressionMethod::Bzip2 => Some(1..=9),
+            CompressionMethod::Zstd => Some(-7..=22),
+        }
+    }
+}
+
+/// Describes how the files in the archives of a profile are compressed.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct CompressionSettings {
+    /// Method used for all files without an entry in `extension_overrides`.
+    #[serde(default)]
+    pub method: CompressionMethod,
+    /// Compression level of the `method`. [None] means that the default level of the method is used.
+    #[serde(default)]
+    pub level: Option<i32>,
+    /// Methods used for the files with the given extensions instead of `method`, e.g., `jpg` -> [CompressionMethod::Stored].
+    /// Extensions are compared case-insensitively and may start with a `.`. The default level of the method is used for them.
+    #[serde(default)]
+    pub extension_overrides: BTreeMap<String, CompressionMethod>,
+}
+
+impl CompressionSettings {
+    /// Determines how the file at the given `path` is compressed.
+    ///
+    /// # Returns
+    /// The [CompressionMethod] and the level to use for the file, where [None] means the default level of the method.
+    pub fn for_path(&self, path: &Path) -> (CompressionMethod, Option<i32>) {
+        let extension = path.extension().and_then(OsStr::to_str).unwrap_or("");
+        let overridden = self
+            .extension_overrides
+            .iter()
+            .find(|(listed, _)| !extension.is_empty() && listed.trim_start_matches('.').eq_ignore_ascii_case(extension));
+        match overridden {
+            Some((_, method)) => (*method, None),
+            None => (self.method, self.level),
+        }
+    }
+
+    /// Checks that the `level` is supported by the `method`.
+    ///
+    /// # Errors
+    /// Returns an [Err] describing the issue if the `level` isn't supported.
+    pub fn validate(&self) -> Result<(), String> {
+        let level = match self.level {
+            Some(level) => level,
+            None => return Ok(()),
+        };
+        match self.method.level_range() {
+            Some(range) if range.contains(&level) => Ok(()),
+            Some(range) => Err(format!(
+                "Compression level {} isn't supported by {:?}, which supports levels {} to {}",
+                level,
+                self.method,
+                range.start(),
+                range.end()
+            )),
+            None => Err(format!("{:?} doesn't support compression levels", self.method)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod compression_tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn media_stored() -> CompressionSettings {
+        CompressionSettings {
+            method: CompressionMethod::Zstd,
+            level: Some(10),
+            extension_overrides: BTreeMap::from([
+                (String::from("jpg"), CompressionMethod::Stored),
+                (String::from(".MP4"), CompressionMethod::Stored),
+            ]),
+        }
+    }
+
+    #[test]
+    fn for_path() {
+        let settings = media_stored();
+        assert_eq!(settings.for_path(&PathBuf::from("C:/a/b.JPG")), (CompressionMethod::Stored, None));
+        assert_eq!(settings.for_path(&PathBuf::from("C:/a/b.mp4")), (CompressionMethod::Stored, None));
+        assert_eq!(settings.for_path(&PathBuf::from("C:/a/b.txt")), (CompressionMethod::Zstd, Some(10)));
+        assert_eq!(settings.for_path(&PathBuf::from("C:/a/jpg")), (CompressionMethod::Zstd, Some(10)));
+    }
+
+    #[test]
+    fn validate() {
+        assert!(CompressionSettings::default().validate().is_ok());
+        assert!(media_stored().validate().is_ok());
+
+        let too_high = CompressionSettings {
+            method: CompressionMethod::Deflated,
+            level: Some(10),
+            ..Default::default()
+        };
+        assert!(too_high.validate().unwrap_err().contains("0 to 9"));
+        let stored = CompressionSettings {
+            method: CompressionMethod::Stored,
+            level: Some(1),
+            ..Default::default()
+        };
+        assert!(stored.validate().is_err());
+    }
+
+    #[test]
+    fn serde() {
+        let settings: CompressionSettings =
+            serde_json::from_str(r#"{"method": "stored", "extension_overrides": {"txt": "bzip2"}}"#).unwrap();
+        assert_eq!(settings.method, CompressionMethod::Stored);
+        assert_eq!(settings.level, None);
+        assert_eq!(settings.extension_overrides["txt"], CompressionMethod::Bzip2);
+        assert_eq!(serde_json::to_string(&CompressionMethod::Zstd).unwrap(), r#""zstd""#);
+    }
+}
@@ -0,0 +1,121 @@
+//! Contains the settings for encrypting the archives of a [ProfileConfig](super::ProfileConfig).
+//!
+//! The passphrase itself is never stored in a config. The settings only tell where it can be read from.
+
+use std::{env, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Where the passphrase of the encrypted archives of a profile comes from.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PassphraseSource {
+    /// Name of the environment variable holding the passphrase.
+    Env(String),
+    /// Path of a file containing the passphrase. A trailing line break isn't part of the passphrase.
+    KeyFile(PathBuf),
+}
+
+/// Describes how the archives of a profile are encrypted.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct EncryptionSettings {
+    /// Where the passphrase is read from
+    pub passphrase: PassphraseSource,
+}
+
+impl EncryptionSettings {
+    /// Checks that the `passphrase` refers to something that could hold a passphrase.
+    /// Doesn't check whether the passphrase is actually available, because that may differ between the machine editing the config and the one running the backups.
+    ///
+    /// # Errors
+    /// Returns an [Err] describing the issue if the source of the passphrase is invalid.
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.passphrase {
+            PassphraseSource::Env(name) if name.is_empty() || name.contains('=') => {
+                Err(format!("{:?} isn't a valid name of an environment variable for the passphrase", name))
+            }
+            PassphraseSource::KeyFile(path) if path.as_os_str().is_empty() => {
+                Err(String::from("The path of the key file for the passphrase is empty"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads the passphrase from its source.
+    ///
+    /// # Errors
+    /// Returns an [Err] describing the issue if the passphrase couldn't be read or is empty.
+    pub fn passphrase(&self) -> Result<String, String> {
+        let passphrase = match &self.passphrase {
+            PassphraseSource::Env(name) => match env::var(name) {
+                Ok(passphrase) => passphrase,
+                Err(err) => return Err(format!("Couldn't read the passphrase from environment variable {:?}: {}", name, err)),
+            },
+            PassphraseSource::KeyFile(path) => match fs::read_to_string(path) {
+                Ok(content) => String::from(content.trim_end_matches(['\r', '\n'])),
+                Err(err) => return Err(format!("Couldn't read the passphrase from key file {:?}: {:?}", path, err)),
+            },
+        };
+        if passphrase.is_empty() {
+            return Err(format!("The passphrase from {:?} is empty", self.passphrase));
+        }
+        Ok(passphrase)
+    }
+}
+
+#[cfg(test)]
+mod encryption_tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let env = |name: &str| EncryptionSettings {
+            passphrase: PassphraseSource::Env(String::from(name)),
+        };
+        assert!(env("BACKUP_KEY").validate().is_ok());
+        assert!(env("").validate().is_err());
+        assert!(env("A=B").validate().is_err());
+        let key_file = EncryptionSettings {
+            passphrase: PassphraseSource::KeyFile(PathBuf::new()),
+        };
+        assert!(key_file.validate().is_err());
+    }
+
+    #[test]
+    fn passphrase_from_env() {
+        let settings = EncryptionSettings {
+            passphrase: PassphraseSource::Env(String::from("BACKUP_RS_ENCRYPTION_TEST_KEY")),
+        };
+        assert!(settings.passphrase().unwrap_err().contains("BACKUP_RS_ENCRYPTION_TEST_KEY"));
+        env::set_var("BACKUP_RS_ENCRYPTION_TEST_KEY", "correct horse");
+        assert_eq!(settings.passphrase().unwrap(), "correct horse");
+        env::set_var("BACKUP_RS_ENCRYPTION_TEST_KEY", "");
+        assert!(settings.passphrase().unwrap_err().contains("empty"));
+    }
+
+    #[test]
+    fn passphrase_from_key_file() {
+        let path = PathBuf::from("test_tmp/encryption_key_file_test.txt");
+        fs::write(&path, "battery staple\r\n").unwrap();
+        let settings = EncryptionSettings {
+            passphrase: PassphraseSource::KeyFile(path.clone()),
+        };
+        let passphrase = settings.passphrase();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(passphrase.unwrap(), "battery staple");
+        assert!(settings.passphrase().is_err());
+    }
+
+    #[test]
+    fn serde() {
+        let settings: EncryptionSettings = serde_json::from_str(r#"{"passphrase": {"env": "BACKUP_KEY"}}"#).unwrap();
+        assert_eq!(settings.passphrase, PassphraseSource::Env(String::from("BACKUP_KEY")));
+        let key_file = EncryptionSettings {
+            passphrase: PassphraseSource::KeyFile(PathBuf::from("E:/backup.key")),
+        };
+        assert_eq!(
+            serde_json::to_string(&key_file).unwrap(),
+            r#"{"passphrase":{"key_file":"E:/backup.key"}}"#
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! Contains the expansion of the home directory and environment variables in paths of a profile.
+
+use std::path::{Path, PathBuf};
+
+/// Expands `~` at the start of the `path` as well as `$HOME`, `${VAR}` and `%VAR%` anywhere in it.
+/// `var` looks up the value of an environment variable. If `HOME` isn't set, `USERPROFILE` is used for `~` and `$HOME` instead.
+///
+/// Other `$` and `%` characters (e.g., in `C:\$Recycle.Bin`) are kept as they are. Paths that aren't valid unicode are returned unchanged.
+///
+/// # Errors
+/// Returns an [Err] naming the variable if one of them isn't set.
+pub(super) fn expand(path: &Path, var: impl Fn(&str) -> Option<String>) -> Result<PathBuf, String> {
+    let lookup = |name: &str| {
+        var(name)
+            .or_else(|| if name == "HOME" { var("USERPROFILE") } else { None })
+            .ok_or_else(|| format!("Environment variable {:?} isn't set", name))
+    };
+    let mut rest = match path.to_str() {
+        Some(path) => path,
+        None => return Ok(path.to_path_buf()),
+    };
+
+    let mut expanded = String::new();
+    if let Some(after) = rest.strip_prefix('~') {
+        if after.is_empty() || after.starts_with(['/', '\\']) {
+            expanded.push_str(&lookup("HOME")?);
+            rest = after;
+        }
+    }
+    while let Some(start) = rest.find(['$', '%']) {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match variable_at(rest) {
+            Some((name, len)) => {
+                expanded.push_str(&lookup(name)?);
+                rest = &rest[len..];
+            }
+            None => {
+                expanded.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    Ok(PathBuf::from(expanded))
+}
+
+/// Checks if `token` starts with a variable, i.e., `$HOME`, `${VAR}` or `%VAR%`.
+///
+/// # Returns
+/// [Some] containing the name of the variable and the length of the whole token. [None] if there is no variable.
+fn variable_at(token: &str) -> Option<(&str, usize)> {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    if let Some(braced) = token.strip_prefix("${") {
+        let name = &braced[..braced.find('}')?];
+        return (!name.is_empty() && name.chars().all(is_name_char)).then_some((name, name.len() + 3));
+    }
+    if let Some(after) = token.strip_prefix("$HOME") {
+        return (!after.starts_with(is_name_char)).then_some(("HOME", 5));
+    }
+    let enclosed = token.strip_prefix('%')?;
+    let name = &enclosed[..enclosed.find('%')?];
+    (!name.is_empty() && !name.contains(['/', '\\'])).then_some((name, name.len() + 2))
+}
+
+#[cfg(test)]
+mod expansion_tests {
+    use super::*;
+
+    fn env(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some(String::from("/home/hutzi")),
+            "USERPROFILE" => Some(String::from("C:\\Users\\hutzi")),
+            "ProgramFiles(x86)" => Some(String::from("C:\\Program Files (x86)")),
+            "DATA" => Some(String::from("D:")),
+            _ => None,
+        }
+    }
+
+    fn windows_env(name: &str) -> Option<String> {
+        if name == "HOME" {
+            None
+        } else {
+            env(name)
+        }
+    }
+
+    fn expanded(path: &str) -> Result<String, String> {
+        expand(&PathBuf::from(path), env).map(|path| path.to_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn home() {
+        assert_eq!(expanded("~"), Ok(String::from("/home/hutzi")));
+        assert_eq!(expanded("~/Documents"), Ok(String::from("/home/hutzi/Documents")));
+        assert_eq!(expanded("$HOME/Documents"), Ok(String::from("/home/hutzi/Documents")));
+        assert_eq!(expanded("${HOME}/Documents"), Ok(String::from("/home/hutzi/Documents")));
+        assert_eq!(
+            expand(&PathBuf::from("~\\Documents"), windows_env),
+            Ok(PathBuf::from("C:\\Users\\hutzi\\Documents"))
+        );
+        // only a leading `~` is expanded
+        assert_eq!(expanded("/tmp/~/a~b"), Ok(String::from("/tmp/~/a~b")));
+        assert_eq!(expanded("~hutzi"), Ok(String::from("~hutzi")));
+    }
+
+    #[test]
+    fn variables() {
+        assert_eq!(expanded("%USERPROFILE%\\Documents"), Ok(String::from("C:\\Users\\hutzi\\Documents")));
+        assert_eq!(expanded("%ProgramFiles(x86)%\\Gimp"), Ok(String::from("C:\\Program Files (x86)\\Gimp")));
+        assert_eq!(expanded("${DATA}/backups/${DATA}"), Ok(String::from("D:/backups/D:")));
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(expanded("C:\\$Recycle.Bin"), Ok(String::from("C:\\$Recycle.Bin")));
+        assert_eq!(expanded("$HOMEPAGE/100%"), Ok(String::from("$HOMEPAGE/100%")));
+        assert_eq!(expanded("50%/50%"), Ok(String::from("50%/50%")));
+        assert_eq!(expanded("${unclosed"), Ok(String::from("${unclosed")));
+        assert_eq!(expanded("%%"), Ok(String::from("%%")));
+    }
+
+    #[test]
+    fn unset_variables() {
+        let err = expanded("%MISSING%\\Documents").unwrap_err();
+        assert!(err.contains("MISSING"));
+        assert!(expanded("${MISSING}").is_err());
+        assert!(expand(&PathBuf::from("~"), |_| None).is_err());
+    }
+}
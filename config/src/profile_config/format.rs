@@ -0,0 +1,134 @@
+//! Contains the file formats [ProfileConfig](super::ProfileConfig)s can be stored in.
+
+use std::{ffi::OsStr, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_yaml::value::TaggedValue;
+
+use crate::Error;
+
+/// File format of a stored [ProfileConfig](super::ProfileConfig). The format of a file is determined by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfigFormat {
+    /// `.json`
+    #[default]
+    Json,
+    /// `.yaml` or `.yml`
+    Yaml,
+    /// `.toml`
+    Toml,
+}
+
+impl ConfigFormat {
+    /// All supported formats
+    pub const ALL: [ConfigFormat; 3] = [ConfigFormat::Json, ConfigFormat::Yaml, ConfigFormat::Toml];
+
+    /// Determines the format of the file at `path` by its extension (case-insensitive).
+    ///
+    /// # Returns
+    /// [None] if the extension doesn't belong to any supported format.
+    pub fn from_path(path: &Path) -> Option<ConfigFormat> {
+        let extension = path.extension().and_then(OsStr::to_str)?.to_lowercase();
+        match extension.as_str() {
+            "json" => Some(ConfigFormat::Json),
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            "toml" => Some(ConfigFormat::Toml),
+            _ => None,
+        }
+    }
+
+    /// Returns the extension (without leading `.`) of files written in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Yaml => "yaml",
+            ConfigFormat::Toml => "toml",
+        }
+    }
+
+    /// Converts the `value` into a human readable string in this format.
+    pub(super) fn serialize<T: Serialize>(&self, value: &T) -> Result<String, Error> {
+        match self {
+            ConfigFormat::Json => serde_json::to_string_pretty(value).map_err(Error::from),
+            ConfigFormat::Yaml => serde_yaml::to_string(value).map_err(|err| Error::Parse(err.to_string())),
+            ConfigFormat::Toml => toml::to_string_pretty(value).map_err(|err| Error::Parse(err.to_string())),
+        }
+    }
+
+    /// Parses the `content` in this format into a [serde_json::Value], e.g., for migrating it before deserializing it.
+    /// YAML tags of enum variants (e.g., `!Nth 3`) become maps (`{"Nth": 3}`) like in the other formats.
+    pub(super) fn deserialize_value(&self, content: &str) -> Result<serde_json::Value, Error> {
+        match self {
+            ConfigFormat::Yaml => {
+                let mut value: serde_yaml::Value = self.deserialize(content)?;
+                untag(&mut value);
+                serde_json::to_value(value).map_err(Error::from)
+            }
+            _ => self.deserialize(content),
+        }
+    }
+
+    /// Parses the `content` in this format.
+    pub(super) fn deserialize<T: DeserializeOwned>(&self, content: &str) -> Result<T, Error> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(content).map_err(Error::from),
+            ConfigFormat::Yaml => serde_yaml::from_str(content).map_err(|err| Error::Parse(err.to_string())),
+            ConfigFormat::Toml => toml::from_str(content).map_err(|err| Error::Parse(err.to_string())),
+        }
+    }
+}
+
+/// Replaces all tagged values in the YAML `value` by maps from the tag (without `!`) to the value.
+fn untag(value: &mut serde_yaml::Value) {
+    match value {
+        serde_yaml::Value::Tagged(_) => {
+            if let serde_yaml::Value::Tagged(tagged) = std::mem::take(value) {
+                let TaggedValue { tag, value: mut inner } = *tagged;
+                untag(&mut inner);
+                let mut map = serde_yaml::Mapping::new();
+                map.insert(serde_yaml::Value::String(tag.to_string().trim_start_matches('!').to_string()), inner);
+                *value = serde_yaml::Value::Mapping(map);
+            }
+        }
+        serde_yaml::Value::Sequence(values) => values.iter_mut().for_each(untag),
+        serde_yaml::Value::Mapping(map) => map.values_mut().for_each(untag),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod format_tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn from_path() {
+        assert_eq!(ConfigFormat::from_path(&PathBuf::from("a/b.json")), Some(ConfigFormat::Json));
+        assert_eq!(ConfigFormat::from_path(&PathBuf::from("b.YAML")), Some(ConfigFormat::Yaml));
+        assert_eq!(ConfigFormat::from_path(&PathBuf::from("b.yml")), Some(ConfigFormat::Yaml));
+        assert_eq!(ConfigFormat::from_path(&PathBuf::from("b.toml")), Some(ConfigFormat::Toml));
+        assert_eq!(ConfigFormat::from_path(&PathBuf::from("b.audit.log")), None);
+        assert_eq!(ConfigFormat::from_path(&PathBuf::from("b.json.tmp")), None);
+        assert_eq!(ConfigFormat::from_path(&PathBuf::from("json")), None);
+    }
+
+    #[test]
+    fn yaml_tags_as_maps() {
+        let value = ConfigFormat::Yaml
+            .deserialize_value("kind: !Nth 3\nkinds:\n- All\n- !Range\n  - 1\n  - 5\n")
+            .unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"kind": {"Nth": 3}, "kinds": ["All", {"Range": [1, 5]}]})
+        );
+    }
+
+    #[test]
+    fn extension_roundtrip() {
+        for format in ConfigFormat::ALL {
+            let path = PathBuf::from(format!("b.{}", format.extension()));
+            assert_eq!(ConfigFormat::from_path(&path), Some(format));
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Contains functions for detecting [ProfileConfig]s whose included paths overlap.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::ProfileConfig;
+
+/// Describes that an include root of one profile is also backed up by another profile.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ProfileOverlap {
+    /// [Uuid] of the profile including `root`
+    pub profile: Uuid,
+    /// Name of the profile including `root`
+    pub profile_name: String,
+    /// [Uuid] of the profile that also backs up `root`
+    pub other: Uuid,
+    /// Name of the profile that also backs up `root`
+    pub other_name: String,
+    /// The overlapping include root
+    pub root: PathBuf,
+}
+
+impl ProfileConfig {
+    /// Checks if `path` is backed up by this profile, i.e., it is either an included file or lies in an included dir without being excluded.
+    pub fn backs_up(&self, path: &PathBuf) -> bool {
+        self.files_to_include.contains(path)
+            || (self.in_included_dirs(path) && !self.is_excluded(path))
+    }
+
+    /// Checks if overlaps with the profile with the given [Uuid] were explicitly allowed.
+    pub fn allows_overlap_with(&self, uuid: &Uuid) -> bool {
+        self.allow_overlap_with.contains(uuid)
+    }
+}
+
+/// Finds all include roots of the given [ProfileConfig]s that are also backed up by another profile.
+/// A pair of profiles is skipped if one of them lists the other in `allow_overlap_with`.
+///
+/// # Example
+/// ```
+/// use config::interval::*;
+/// use config::profile_config::*;
+/// use std::path::PathBuf;
+///
+/// let documents = ProfileConfig::new(
+///     String::from("Documents"),
+///     PathBuf::from("E:/backup"),
+///     vec![],
+///     vec![PathBuf::from("/home/me/Documents")],
+///     vec![],
+///     vec![],
+///     IntervalBuilder::default().build().unwrap(),
+/// );
+/// let everything = ProfileConfig::new(
+///     String::from("Everything"),
+///     PathBuf::from("E:/backup"),
+///     vec![],
+///     vec![PathBuf::from("/home/me")],
+///     vec![],
+///     vec![],
+///     IntervalBuilder::default().build().unwrap(),
+/// );
+///
+/// let overlaps = find_overlaps(&[documents, everything]);
+/// assert_eq!(overlaps.len(), 1);
+/// assert_eq!(overlaps[0].profile_name, "Documents");
+/// assert_eq!(overlaps[0].other_name, "Everything");
+/// assert_eq!(overlaps[0].root, PathBuf::from("/home/me/Documents"));
+/// ```
+pub fn find_overlaps(configs: &[ProfileConfig]) -> Vec<ProfileOverlap> {
+    let mut overlaps = vec![];
+
+    for (i, a) in configs.iter().enumerate() {
+        for b in configs.iter().skip(i + 1) {
+            if a.allows_overlap_with(b.get_uuid()) || b.allows_overlap_with(a.get_uuid()) {
+                continue;
+            }
+
+            let mut pair_overlaps: Vec<ProfileOverlap> = vec![];
+            for (config, other) in [(a, b), (b, a)] {
+                for root in config.files_to_include.iter().chain(config.dirs_to_include.iter()) {
+                    let already_found = pair_overlaps.iter().any(|overlap| &overlap.root == root);
+                    if already_found || !other.backs_up(root) {
+                        continue;
+                    }
+                    pair_overlaps.push(ProfileOverlap {
+                        profile: *config.get_uuid(),
+                        profile_name: config.name.clone(),
+                        other: *other.get_uuid(),
+                        other_name: other.name.clone(),
+                        root: root.clone(),
+                    });
+                }
+            }
+            overlaps.append(&mut pair_overlaps);
+        }
+    }
+
+    overlaps
+}
+
+#[cfg(test)]
+mod overlap_tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::interval::IntervalBuilder;
+
+    fn include_config(
+        name: &str,
+        files_to_include: Vec<&str>,
+        dirs_to_include: Vec<&str>,
+        dirs_to_exclude: Vec<&str>,
+    ) -> ProfileConfig {
+        let to_paths = |paths: Vec<&str>| paths.into_iter().map(PathBuf::from).collect();
+        ProfileConfig::new(
+            name.to_string(),
+            PathBuf::from("target"),
+            to_paths(files_to_include),
+            to_paths(dirs_to_include),
+            vec![],
+            to_paths(dirs_to_exclude),
+            IntervalBuilder::default().build().unwrap(),
+        )
+    }
+
+    #[test]
+    fn no_overlap() {
+        let configs = [
+            include_config("a", vec![], vec!["/home/a"], vec![]),
+            include_config("b", vec!["/home/b.txt"], vec!["/home/b"], vec![]),
+        ];
+        assert!(find_overlaps(&configs).is_empty());
+    }
+
+    #[test]
+    fn same_dir() {
+        let configs = [
+            include_config("a", vec![], vec!["/home/docs"], vec![]),
+            include_config("b", vec![], vec!["/home/docs"], vec![]),
+        ];
+        let overlaps = find_overlaps(&configs);
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(overlaps[0].profile, *configs[0].get_uuid());
+        assert_eq!(overlaps[0].other, *configs[1].get_uuid());
+        assert_eq!(overlaps[0].root, PathBuf::from("/home/docs"));
+    }
+
+    #[test]
+    fn nested_dir() {
+        let configs = [
+            include_config("everything", vec![], vec!["/home"], vec![]),
+            include_config("docs", vec![], vec!["/home/docs"], vec![]),
+        ];
+        let overlaps = find_overlaps(&configs);
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(overlaps[0].profile_name, "docs");
+        assert_eq!(overlaps[0].other_name, "everything");
+    }
+
+    #[test]
+    fn included_file() {
+        let configs = [
+            include_config("a", vec!["/etc/hosts"], vec![], vec![]),
+            include_config("b", vec!["/etc/hosts"], vec![], vec![]),
+            include_config("c", vec![], vec!["/etc"], vec![]),
+        ];
+        let overlaps = find_overlaps(&configs);
+        assert_eq!(overlaps.len(), 3);
+        assert!(overlaps.iter().all(|overlap| overlap.root == Path::new("/etc/hosts")));
+    }
+
+    #[test]
+    fn excluded_root() {
+        let configs = [
+            include_config("everything", vec![], vec!["/home"], vec!["/home/docs"]),
+            include_config("docs", vec![], vec!["/home/docs/tax"], vec![]),
+        ];
+        assert!(find_overlaps(&configs).is_empty());
+    }
+
+    #[test]
+    fn allowed_overlap() {
+        let mut configs = [
+            include_config("a", vec![], vec!["/home/docs"], vec![]),
+            include_config("b", vec![], vec!["/home"], vec![]),
+        ];
+        let uuid_b = *configs[1].get_uuid();
+        configs[0].allow_overlap_with.push(uuid_b);
+        assert!(find_overlaps(&configs).is_empty());
+
+        configs[0].allow_overlap_with.clear();
+        let uuid_a = *configs[0].get_uuid();
+        configs[1].allow_overlap_with.push(uuid_a);
+        assert!(find_overlaps(&configs).is_empty());
+    }
+}
@@ -0,0 +1,55 @@
+//! Contains the matching of paths against the glob patterns of a profile.
+
+use std::{path::Path, sync::OnceLock};
+
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+
+/// Lazily compiled [GlobSet] for a list of glob patterns.
+///
+/// The set is compiled once on first use and reused for every following path.
+/// It remembers the patterns it was compiled from, so changing the patterns afterwards doesn't lead to stale results.
+#[derive(Debug, Clone, Default)]
+pub(super) struct PatternCache(OnceLock<(Vec<String>, GlobSet)>);
+
+impl PatternCache {
+    /// Checks if the given `path` is matched by any of the `patterns`. Invalid patterns never match.
+    pub fn is_match(&self, patterns: &[String], path: &Path) -> bool {
+        if patterns.is_empty() {
+            return false;
+        }
+        let path = normalize(path);
+        let (compiled_from, set) = self
+            .0
+            .get_or_init(|| (patterns.to_vec(), compile_all(patterns)));
+        if compiled_from == patterns {
+            set.is_match(&path)
+        } else {
+            compile_all(patterns).is_match(&path)
+        }
+    }
+}
+
+/// Compiles a single glob pattern.
+///
+/// `*` and `**` may match across path separators and matching is case-insensitive, like paths on Windows are.
+pub(super) fn compile(pattern: &str) -> Result<Glob, String> {
+    GlobBuilder::new(pattern)
+        .case_insensitive(true)
+        .literal_separator(false)
+        .build()
+        .map_err(|err| format!("Invalid pattern {:?}: {}", pattern, err.kind()))
+}
+
+/// Compiles all valid `patterns` into a single [GlobSet].
+fn compile_all(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for glob in patterns.iter().filter_map(|pattern| compile(pattern).ok()) {
+        builder.add(glob);
+    }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
+}
+
+/// Converts the `path` into a string with `/` as the only separator, so that patterns written with `/` also match Windows paths.
+fn normalize(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
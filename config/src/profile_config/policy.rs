@@ -0,0 +1,122 @@
+//! Contains enums that describe how the backupper should handle special files of a [ProfileConfig](super::ProfileConfig).
+
+use serde::{Deserialize, Serialize};
+
+/// Describes what happens to a file that changed while it was archived (e.g., a log that was appended to).
+/// Such a file is detected by comparing its size and modification time before and after reading it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnstableFilePolicy {
+    /// Keeps the possibly inconsistent copy in the backup, but marks it as unstable.
+    #[default]
+    Keep,
+    /// Removes the file from the backup.
+    Drop,
+}
+
+/// Describes which files a backup archives.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupMode {
+    /// Archives all files.
+    #[default]
+    Full,
+    /// Only archives the files modified since the newest archive of the profile. Without such an archive, all files are archived.
+    Incremental,
+}
+
+/// Describes what happens to symbolic links (and junctions on Windows) in the included dirs and files.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkPolicy {
+    /// Ignores links.
+    Skip,
+    /// Archives the files and dirs links point to like regular ones. Dirs reachable through several links or a link cycle are only archived once.
+    #[default]
+    Follow,
+    /// Archives the links themselves with their target, so they are restored as links.
+    Preserve,
+}
+
+/// Describes what happens to a file that can't be opened because another process is using it (e.g., an open Outlook PST or Excel file on Windows).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockedFilePolicy {
+    /// Skips the file. It is listed in the summary of the backup.
+    #[default]
+    Skip,
+    /// Tries to open the file again the given number of times, waiting longer before each attempt. Skips the file if it's still in use.
+    Retry(u32),
+    /// Fails the backup if the file is still in use at the end of the backup.
+    Fail,
+}
+
+impl LockedFilePolicy {
+    /// Number of times opening a file in use is tried again right away.
+    pub fn retries(&self) -> u32 {
+        match self {
+            LockedFilePolicy::Retry(retries) => *retries,
+            LockedFilePolicy::Skip | LockedFilePolicy::Fail => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod policy_tests {
+    use super::*;
+
+    #[test]
+    fn unstable_file_policy() {
+        assert_eq!(UnstableFilePolicy::default(), UnstableFilePolicy::Keep);
+        assert_eq!(
+            serde_json::to_string(&UnstableFilePolicy::Drop).unwrap(),
+            r#""drop""#
+        );
+        assert_eq!(
+            serde_json::from_str::<UnstableFilePolicy>(r#""keep""#).unwrap(),
+            UnstableFilePolicy::Keep
+        );
+        assert!(serde_json::from_str::<UnstableFilePolicy>(r#""ignore""#).is_err());
+    }
+
+    #[test]
+    fn symlink_policy() {
+        assert_eq!(SymlinkPolicy::default(), SymlinkPolicy::Follow);
+        assert_eq!(
+            serde_json::to_string(&SymlinkPolicy::Preserve).unwrap(),
+            r#""preserve""#
+        );
+        assert_eq!(
+            serde_json::from_str::<SymlinkPolicy>(r#""skip""#).unwrap(),
+            SymlinkPolicy::Skip
+        );
+    }
+
+    #[test]
+    fn backup_mode() {
+        assert_eq!(BackupMode::default(), BackupMode::Full);
+        assert_eq!(
+            serde_json::to_string(&BackupMode::Incremental).unwrap(),
+            r#""incremental""#
+        );
+        assert_eq!(
+            serde_json::from_str::<BackupMode>(r#""full""#).unwrap(),
+            BackupMode::Full
+        );
+    }
+
+    #[test]
+    fn locked_file_policy() {
+        assert_eq!(LockedFilePolicy::default(), LockedFilePolicy::Skip);
+        assert_eq!(
+            serde_json::to_string(&LockedFilePolicy::Retry(3)).unwrap(),
+            r#"{"retry":3}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<LockedFilePolicy>(r#""fail""#).unwrap(),
+            LockedFilePolicy::Fail
+        );
+        assert_eq!(LockedFilePolicy::Retry(3).retries(), 3);
+        assert_eq!(LockedFilePolicy::Fail.retries(), 0);
+    }
+}
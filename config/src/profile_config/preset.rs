@@ -0,0 +1,220 @@
+//! Contains [ProfilePreset] enum that can be used to quickly set up the filters of a [ProfileConfig].
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::ProfileConfig;
+
+/// Extensions of common image files
+pub const IMAGE_EXTENSIONS: [&str; 10] = [
+    "jpg", "jpeg", "png", "gif", "bmp", "tif", "tiff", "heic", "webp", "raw",
+];
+
+/// Extensions of common video files
+pub const VIDEO_EXTENSIONS: [&str; 8] = ["mp4", "mov", "avi", "mkv", "wmv", "m4v", "mpg", "mpeg"];
+
+/// Extensions of common audio files
+pub const AUDIO_EXTENSIONS: [&str; 6] = ["mp3", "wav", "flac", "aac", "ogg", "m4a"];
+
+/// Maximum file size for the [ProfilePreset::Documents] preset in bytes (100 MB)
+pub const DOCUMENTS_MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
+
+/// Named presets for the filters of a [ProfileConfig].
+/// Applying a preset only changes `exclude_extensions`, `include_extensions` and `max_file_size`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfilePreset {
+    /// Skips files larger than [DOCUMENTS_MAX_FILE_SIZE] and image, video and audio files.
+    Documents,
+    /// Doesn't filter anything.
+    FullFolder,
+    /// Only includes image and video files, regardless of their size.
+    Photos,
+}
+
+impl ProfilePreset {
+    /// Applies the preset to the given [ProfileConfig] by overwriting its filters.
+    ///
+    /// # Example
+    /// ```
+    /// use config::interval::*;
+    /// use config::profile_config::*;
+    /// use std::path::PathBuf;
+    ///
+    /// let mut config = ProfileConfig::new(
+    ///     String::from("Docs"),
+    ///     PathBuf::from("target"),
+    ///     vec![],
+    ///     vec![],
+    ///     vec![],
+    ///     vec![],
+    ///     IntervalBuilder::default().build().unwrap(),
+    /// );
+    /// ProfilePreset::Documents.apply(&mut config);
+    /// assert!(config.exclude_extensions.contains(&String::from("mp4")));
+    /// assert_eq!(config.max_file_size, Some(100 * 1024 * 1024));
+    /// ```
+    pub fn apply(&self, config: &mut ProfileConfig) {
+        match self {
+            ProfilePreset::Documents => {
+                config.exclude_extensions = Self::to_strings(
+                    IMAGE_EXTENSIONS
+                        .iter()
+                        .chain(VIDEO_EXTENSIONS.iter())
+                        .chain(AUDIO_EXTENSIONS.iter()),
+                );
+                config.include_extensions = vec![];
+                config.max_file_size = Some(DOCUMENTS_MAX_FILE_SIZE);
+            }
+            ProfilePreset::FullFolder => {
+                config.exclude_extensions = vec![];
+                config.include_extensions = vec![];
+                config.max_file_size = None;
+            }
+            ProfilePreset::Photos => {
+                config.exclude_extensions = vec![];
+                config.include_extensions =
+                    Self::to_strings(IMAGE_EXTENSIONS.iter().chain(VIDEO_EXTENSIONS.iter()));
+                config.max_file_size = None;
+            }
+        }
+    }
+
+    fn to_strings<'a>(extensions: impl Iterator<Item = &'a &'a str>) -> Vec<String> {
+        extensions.map(|ext| ext.to_string()).collect()
+    }
+}
+
+impl FromStr for ProfilePreset {
+    type Err = String;
+
+    /// Parses the name of a preset case-insensitively. `-` and `_` are treated the same.
+    ///
+    /// # Example
+    /// ```
+    /// use config::profile_config::ProfilePreset;
+    ///
+    /// assert_eq!("documents".parse(), Ok(ProfilePreset::Documents));
+    /// assert_eq!("Full-Folder".parse(), Ok(ProfilePreset::FullFolder));
+    /// assert!("videos".parse::<ProfilePreset>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "documents" => Ok(ProfilePreset::Documents),
+            "full_folder" => Ok(ProfilePreset::FullFolder),
+            "photos" => Ok(ProfilePreset::Photos),
+            _ => Err(format!(
+                "Unknown preset {:?}. Expected one of \"documents\", \"full_folder\" or \"photos\"",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod preset_tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::interval::IntervalBuilder;
+
+    fn blank_config() -> ProfileConfig {
+        ProfileConfig::new(
+            String::from("Hutzi"),
+            PathBuf::from("ho"),
+            vec![],
+            vec![PathBuf::from("/home/hutzi")],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        )
+    }
+
+    #[test]
+    fn documents() {
+        let mut config = blank_config();
+        ProfilePreset::Documents.apply(&mut config);
+
+        assert_eq!(config.max_file_size, Some(DOCUMENTS_MAX_FILE_SIZE));
+        assert!(config.include_extensions.is_empty());
+        for ext in IMAGE_EXTENSIONS
+            .iter()
+            .chain(VIDEO_EXTENSIONS.iter())
+            .chain(AUDIO_EXTENSIONS.iter())
+        {
+            assert!(config.exclude_extensions.contains(&ext.to_string()));
+        }
+    }
+
+    #[test]
+    fn full_folder() {
+        let mut config = blank_config();
+        ProfilePreset::Documents.apply(&mut config);
+        ProfilePreset::FullFolder.apply(&mut config);
+
+        assert_eq!(config.max_file_size, None);
+        assert!(config.include_extensions.is_empty());
+        assert!(config.exclude_extensions.is_empty());
+    }
+
+    #[test]
+    fn photos() {
+        let mut config = blank_config();
+        ProfilePreset::Documents.apply(&mut config);
+        ProfilePreset::Photos.apply(&mut config);
+
+        assert_eq!(config.max_file_size, None);
+        assert!(config.exclude_extensions.is_empty());
+        assert!(config.include_extensions.contains(&String::from("jpg")));
+        assert!(config.include_extensions.contains(&String::from("mp4")));
+        assert!(!config.include_extensions.contains(&String::from("mp3")));
+    }
+
+    #[test]
+    fn only_touches_filters() {
+        let mut config = blank_config();
+        ProfilePreset::Photos.apply(&mut config);
+
+        assert_eq!(config.name, "Hutzi");
+        assert_eq!(config.target_dir, PathBuf::from("ho"));
+        assert_eq!(config.dirs_to_include, vec![PathBuf::from("/home/hutzi")]);
+    }
+
+    #[test]
+    fn applying_is_deterministic() {
+        let mut a = blank_config();
+        let mut b = blank_config();
+        b.exclude_extensions = vec![String::from("tmp")];
+        b.max_file_size = Some(1);
+
+        for preset in [
+            ProfilePreset::Documents,
+            ProfilePreset::FullFolder,
+            ProfilePreset::Photos,
+        ] {
+            preset.apply(&mut a);
+            preset.apply(&mut b);
+            assert_eq!(a.exclude_extensions, b.exclude_extensions);
+            assert_eq!(a.include_extensions, b.include_extensions);
+            assert_eq!(a.max_file_size, b.max_file_size);
+        }
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("documents".parse(), Ok(ProfilePreset::Documents));
+        assert_eq!("DOCUMENTS".parse(), Ok(ProfilePreset::Documents));
+        assert_eq!("full_folder".parse(), Ok(ProfilePreset::FullFolder));
+        assert_eq!("full-folder".parse(), Ok(ProfilePreset::FullFolder));
+        assert_eq!("photos".parse(), Ok(ProfilePreset::Photos));
+        assert!("".parse::<ProfilePreset>().is_err());
+        assert!("music".parse::<ProfilePreset>().is_err());
+    }
+
+    #[test]
+    fn deserialize() {
+        let preset: ProfilePreset = serde_json::from_str("\"full_folder\"").unwrap();
+        assert_eq!(preset, ProfilePreset::FullFolder);
+    }
+}
@@ -0,0 +1,166 @@
+//! Contains functions for comparing the version that wrote a [ProfileConfig] with the running version
+//! and the migrations of stored profiles to the current schema version.
+
+use serde_json::{Map, Value};
+
+use super::ProfileConfig;
+use crate::migration::Migration;
+
+/// Version of this crate. It gets written into every stored [ProfileConfig].
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Current schema version of stored [ProfileConfig]s
+pub const PROFILE_CONFIG_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Migrations of stored [ProfileConfig]s. The migration at index `n` migrates from version `n` to `n + 1`.
+pub(super) const MIGRATIONS: [Migration; 2] = [add_enabled, normalize_stored_extensions];
+
+/// Version 1 added `enabled`. Existing profiles stay enabled.
+fn add_enabled(fields: &mut Map<String, Value>) {
+    fields.entry("enabled").or_insert(Value::Bool(true));
+}
+
+/// Version 2 stores the `exclude_extensions` and `include_extensions` in lowercase and without leading `.` (see [normalize_extension]).
+fn normalize_stored_extensions(fields: &mut Map<String, Value>) {
+    for field in ["exclude_extensions", "include_extensions"] {
+        if let Some(Value::Array(extensions)) = fields.get_mut(field) {
+            for extension in extensions.iter_mut() {
+                if let Value::String(name) = extension {
+                    *name = normalize_extension(name);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the `extension` in lowercase and without leading `.`, e.g., `tmp` for `.TMP`.
+fn normalize_extension(extension: &str) -> String {
+    extension.trim_start_matches('.').to_lowercase()
+}
+
+/// Parses a version of the form `major.minor.patch`. Pre-release and build suffixes (e.g., `-rc.1`) are ignored.
+///
+/// # Returns
+/// [Some] containing `(major, minor, patch)` if `version` could be parsed. [None] else.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.trim().split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next()?.ok()?;
+    let patch = parts.next()?.ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((major, minor, patch))
+}
+
+impl ProfileConfig {
+    /// Checks if the profile was written by a newer version than `version`.
+    /// Returns `false` if either version can't be parsed, e.g., because the profile was written before versions were recorded.
+    pub fn written_by_newer_version(&self, version: &str) -> bool {
+        match (
+            parse_version(&self.written_by_version),
+            parse_version(version),
+        ) {
+            (Some(written_by), Some(version)) => written_by > version,
+            _ => false,
+        }
+    }
+
+    /// Checks if the profile must not be modified, because it was written by a newer version than [CRATE_VERSION]
+    /// or has a newer `config_version` than [PROFILE_CONFIG_VERSION].
+    /// Storing such a profile would drop the fields this version doesn't know.
+    pub fn is_read_only(&self) -> bool {
+        self.written_by_newer_version(CRATE_VERSION) || self.config_version > PROFILE_CONFIG_VERSION
+    }
+
+    /// Brings the `exclude_extensions` and `include_extensions` into the form they are stored in (see [normalize_extension]).
+    pub(super) fn normalize_extensions(&mut self) {
+        for extension in self.exclude_extensions.iter_mut().chain(self.include_extensions.iter_mut()) {
+            *extension = normalize_extension(extension);
+        }
+    }
+}
+
+#[cfg(test)]
+mod version_tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::interval::IntervalBuilder;
+
+    fn versioned_config(written_by_version: &str) -> ProfileConfig {
+        let mut config = ProfileConfig::new(
+            String::from("Hutzi"),
+            PathBuf::from("ho"),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            IntervalBuilder::default().build().unwrap(),
+        );
+        config.written_by_version = written_by_version.to_string();
+        config
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_version("0.4.2"), Some((0, 4, 2)));
+        assert_eq!(parse_version("1.10.0-rc.1"), Some((1, 10, 0)));
+        assert_eq!(parse_version("2.0.1+build.5"), Some((2, 0, 1)));
+        assert_eq!(parse_version(""), None);
+        assert_eq!(parse_version("1.2"), None);
+        assert_eq!(parse_version("1.2.3.4"), None);
+        assert_eq!(parse_version("a.b.c"), None);
+    }
+
+    #[test]
+    fn newer_version() {
+        let config = versioned_config("0.5.0");
+        assert!(config.written_by_newer_version("0.4.2"));
+        assert!(config.written_by_newer_version("0.4.10"));
+        assert!(!config.written_by_newer_version("0.5.0"));
+        assert!(!config.written_by_newer_version("0.10.0"));
+        assert!(!config.written_by_newer_version("1.0.0"));
+    }
+
+    #[test]
+    fn unknown_version() {
+        let config = versioned_config("");
+        assert!(!config.written_by_newer_version("0.4.2"));
+        assert!(!config.is_read_only());
+
+        let config = versioned_config("0.5.0");
+        assert!(!config.written_by_newer_version("garbage"));
+    }
+
+    #[test]
+    fn store_records_version() {
+        let dir = PathBuf::from("test_tmp");
+        let config = versioned_config("");
+        config.store(&dir).unwrap();
+
+        let file_path = dir.join(format!("{}.json", config.get_uuid().as_hyphenated()));
+        let stored = ProfileConfig::load(&file_path).unwrap();
+        std::fs::remove_file(file_path).unwrap();
+        assert_eq!(stored.written_by_version, CRATE_VERSION);
+    }
+
+    #[test]
+    fn store_refuses_read_only() {
+        let dir = PathBuf::from("test_tmp");
+        let config = versioned_config("999.0.0");
+        let err = config.store(&dir).unwrap_err();
+        assert!(matches!(err, crate::Error::ReadOnly(_)));
+
+        let file_path = dir.join(format!("{}.json", config.get_uuid().as_hyphenated()));
+        assert!(!file_path.exists());
+    }
+
+    #[test]
+    fn read_only() {
+        assert!(!versioned_config(CRATE_VERSION).is_read_only());
+        assert!(versioned_config("999.0.0").is_read_only());
+        assert!(!versioned_config("0.0.1").is_read_only());
+    }
+}
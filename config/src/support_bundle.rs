@@ -0,0 +1,304 @@
+//! Contains structs for assembling a support bundle, i.e., the configuration and version info needed to investigate bug reports.
+//!
+//! The bundle itself is only a list of named entries. It is up to the caller to pack them into an archive.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::{Component, Path, PathBuf},
+};
+
+use chrono::{offset, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    general_config::GeneralConfig,
+    profile_config::{ProfileConfig, CRATE_VERSION},
+};
+
+/// Name of the manifest entry in a support bundle
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Replaces the components of paths with salted hashes, so that no file names leak into a support bundle.
+///
+/// The root of a path (e.g., `/` or `C:\`) is kept. Every other component is replaced by the first 12 hex digits of its hash.
+/// The same [PathRedactor] always produces the same hashes, so paths can still be correlated within a bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathRedactor {
+    salt: u128,
+}
+
+impl PathRedactor {
+    /// Creates a [PathRedactor] with a random salt
+    pub fn new() -> PathRedactor {
+        PathRedactor {
+            salt: uuid::Uuid::new_v4().as_u128(),
+        }
+    }
+
+    /// Creates a [PathRedactor] with the given salt
+    pub fn with_salt(salt: u128) -> PathRedactor {
+        PathRedactor { salt }
+    }
+
+    /// Redacts the given path.
+    ///
+    /// # Example
+    /// ```
+    /// use config::support_bundle::PathRedactor;
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// let redactor = PathRedactor::with_salt(42);
+    /// let docs = redactor.redact_path(Path::new("/home/hutzi/Documents"));
+    /// let pics = redactor.redact_path(Path::new("/home/hutzi/Pictures"));
+    ///
+    /// assert!(docs.has_root());
+    /// assert_eq!(docs.components().count(), 4);
+    /// assert!(!docs.to_string_lossy().contains("hutzi"));
+    /// // same parent dirs result in the same hashes
+    /// assert_eq!(docs.parent(), pics.parent());
+    /// ```
+    pub fn redact_path(&self, path: &Path) -> PathBuf {
+        path.components()
+            .map(|component| match component {
+                Component::Normal(name) => {
+                    PathBuf::from(self.hash(name.to_string_lossy().as_ref()))
+                }
+                other => PathBuf::from(other.as_os_str()),
+            })
+            .collect()
+    }
+
+    fn redact_paths(&self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        paths.iter().map(|path| self.redact_path(path)).collect()
+    }
+
+    /// Returns a copy of the given [ProfileConfig] with all paths redacted
+    pub fn redact_profile_config(&self, config: &ProfileConfig) -> ProfileConfig {
+        let mut config = config.clone();
+        config.target_dir = self.redact_path(&config.target_dir);
+        config.files_to_include = self.redact_paths(&config.files_to_include);
+        config.dirs_to_include = self.redact_paths(&config.dirs_to_include);
+        config.files_to_exclude = self.redact_paths(&config.files_to_exclude);
+        config.dirs_to_exclude = self.redact_paths(&config.dirs_to_exclude);
+        config.previous_target_dirs = self.redact_paths(&config.previous_target_dirs);
+        config
+    }
+
+    /// Returns a copy of the given [GeneralConfig] with all paths redacted
+    pub fn redact_general_config(&self, config: &GeneralConfig) -> GeneralConfig {
+        let mut config = config.clone();
+        config.profile_configs = self.redact_path(&config.profile_configs);
+        config.default_target_dir = config.default_target_dir.map(|dir| self.redact_path(&dir));
+        config
+    }
+
+    fn hash(&self, component: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.salt.hash(&mut hasher);
+        component.hash(&mut hasher);
+        let hash = format!("{:016x}", hasher.finish());
+        hash[..12].to_string()
+    }
+}
+
+impl Default for PathRedactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Describes the contents of a support bundle. Stored as [MANIFEST_NAME] in the bundle.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct SupportBundleManifest {
+    /// When the bundle was created
+    pub created: NaiveDateTime,
+    /// Version of the config crate that created the bundle
+    pub version: String,
+    /// Whether the paths in the bundle were redacted
+    pub redacted: bool,
+    /// Names of the other entries in the bundle in the order they were added
+    pub entries: Vec<String>,
+}
+
+/// Collects the entries of a support bundle.
+#[derive(Debug)]
+pub struct SupportBundle {
+    redactor: Option<PathRedactor>,
+    created: NaiveDateTime,
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl SupportBundle {
+    /// Creates an empty bundle. If `redactor` is [Some], all paths in added configs get redacted with it.
+    pub fn new(redactor: Option<PathRedactor>) -> SupportBundle {
+        SupportBundle {
+            redactor,
+            created: offset::Local::now().naive_local(),
+            entries: vec![],
+        }
+    }
+
+    /// Adds the [GeneralConfig] as `general_config.json`
+    pub fn add_general_config(&mut self, config: &GeneralConfig) -> Result<(), serde_json::Error> {
+        let json = match &self.redactor {
+            Some(redactor) => serde_json::to_vec_pretty(&redactor.redact_general_config(config))?,
+            None => serde_json::to_vec_pretty(config)?,
+        };
+        self.add_entry(String::from("general_config.json"), json);
+        Ok(())
+    }
+
+    /// Adds the [ProfileConfig] as `profiles/<uuid>.json`
+    pub fn add_profile_config(&mut self, config: &ProfileConfig) -> Result<(), serde_json::Error> {
+        let json = match &self.redactor {
+            Some(redactor) => serde_json::to_vec_pretty(&redactor.redact_profile_config(config))?,
+            None => serde_json::to_vec_pretty(config)?,
+        };
+        self.add_entry(
+            format!("profiles/{}.json", config.get_uuid().as_hyphenated()),
+            json,
+        );
+        Ok(())
+    }
+
+    /// Adds an arbitrary entry, e.g., a log file. The content isn't redacted.
+    pub fn add_entry(&mut self, name: String, content: Vec<u8>) {
+        self.entries.push((name, content));
+    }
+
+    /// Returns the manifest describing the current entries
+    pub fn manifest(&self) -> SupportBundleManifest {
+        SupportBundleManifest {
+            created: self.created,
+            version: String::from(CRATE_VERSION),
+            redacted: self.redactor.is_some(),
+            entries: self.entries.iter().map(|(name, _)| name.clone()).collect(),
+        }
+    }
+
+    /// Returns all entries, starting with the manifest
+    pub fn into_entries(self) -> Result<Vec<(String, Vec<u8>)>, serde_json::Error> {
+        let manifest = serde_json::to_vec_pretty(&self.manifest())?;
+        let mut entries = vec![(String::from(MANIFEST_NAME), manifest)];
+        entries.extend(self.entries);
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod support_bundle_tests {
+    use super::*;
+    use crate::interval::Interval;
+
+    fn bundle_config() -> ProfileConfig {
+        ProfileConfig::new(
+            String::from("Hutzi"),
+            PathBuf::from("/mnt/backup/hutzi"),
+            vec![PathBuf::from("/home/hutzi/.bashrc")],
+            vec![PathBuf::from("/home/hutzi/Documents")],
+            vec![],
+            vec![PathBuf::from("/home/hutzi/Documents/tmp")],
+            Interval::daily(0, 12).unwrap(),
+        )
+    }
+
+    #[test]
+    fn redaction_is_deterministic() {
+        let redactor = PathRedactor::with_salt(7);
+        let path = Path::new("/home/hutzi/Documents");
+        assert_eq!(redactor.redact_path(path), redactor.redact_path(path));
+        assert_eq!(
+            redactor.redact_path(path),
+            PathRedactor::with_salt(7).redact_path(path)
+        );
+        assert_ne!(
+            redactor.redact_path(path),
+            PathRedactor::with_salt(8).redact_path(path)
+        );
+    }
+
+    #[test]
+    fn redaction_keeps_structure() {
+        let redactor = PathRedactor::with_salt(7);
+        let dir = redactor.redact_path(Path::new("/home/hutzi/Documents"));
+        let sub_dir = redactor.redact_path(Path::new("/home/hutzi/Documents/tmp"));
+        let other = redactor.redact_path(Path::new("/home/fuschi/Documents"));
+
+        assert!(dir.has_root());
+        assert!(sub_dir.starts_with(&dir));
+        assert_ne!(dir, other);
+        assert_eq!(
+            dir.components().next_back(),
+            other.components().next_back(),
+            "same names result in the same hashes"
+        );
+        for component in dir.components().skip(1) {
+            let name = component.as_os_str().to_string_lossy();
+            assert_eq!(name.len(), 12);
+            assert!(name.chars().all(|c| c.is_ascii_hexdigit()));
+        }
+    }
+
+    #[test]
+    fn relative_and_empty_paths() {
+        let redactor = PathRedactor::with_salt(7);
+        assert_eq!(redactor.redact_path(Path::new("")), PathBuf::new());
+        let relative = redactor.redact_path(Path::new("test_tmp/profiles"));
+        assert!(relative.is_relative());
+        assert_eq!(relative.components().count(), 2);
+    }
+
+    #[test]
+    fn redact_profile_config() {
+        let redactor = PathRedactor::with_salt(7);
+        let config = bundle_config();
+        let redacted = redactor.redact_profile_config(&config);
+
+        assert_eq!(redacted.name, config.name);
+        assert_eq!(redacted.get_uuid(), config.get_uuid());
+        let json = serde_json::to_string(&redacted).unwrap();
+        assert!(!json.contains("hutzi"), "{}", json);
+        assert!(!json.contains("Documents"), "{}", json);
+        assert!(redacted.dirs_to_exclude[0].starts_with(&redacted.dirs_to_include[0]));
+    }
+
+    #[test]
+    fn manifest() {
+        let config = bundle_config();
+        let mut bundle = SupportBundle::new(Some(PathRedactor::with_salt(7)));
+        bundle.add_profile_config(&config).unwrap();
+        bundle.add_entry(String::from("logs/server.log"), b"hello".to_vec());
+
+        let manifest = bundle.manifest();
+        assert!(manifest.redacted);
+        assert_eq!(manifest.version, CRATE_VERSION);
+        assert_eq!(
+            manifest.entries,
+            vec![
+                format!("profiles/{}.json", config.get_uuid().as_hyphenated()),
+                String::from("logs/server.log"),
+            ]
+        );
+
+        let entries = bundle.into_entries().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].0, MANIFEST_NAME);
+        let parsed: SupportBundleManifest = serde_json::from_slice(&entries[0].1).unwrap();
+        assert_eq!(parsed, manifest);
+        let profile = String::from_utf8(entries[1].1.clone()).unwrap();
+        assert!(!profile.contains("hutzi"));
+    }
+
+    #[test]
+    fn unredacted_bundle() {
+        let config = bundle_config();
+        let mut bundle = SupportBundle::new(None);
+        bundle.add_profile_config(&config).unwrap();
+
+        assert!(!bundle.manifest().redacted);
+        let entries = bundle.into_entries().unwrap();
+        let profile: ProfileConfig = serde_json::from_slice(&entries[1].1).unwrap();
+        assert_eq!(profile.dirs_to_include, config.dirs_to_include);
+    }
+}
@@ -1,7 +1,7 @@
 import { Injectable } from '@angular/core';
 import { HttpClient, HttpHeaders } from '@angular/common/http';
-import { Observable, catchError, of, tap } from 'rxjs';
-import { ProfileConfig } from './profile-config';
+import { Observable, catchError, map, of, tap } from 'rxjs';
+import { ProfileConfig, withNumberedNames } from './profile-config';
 import { MessageService } from './message.service';
 import { Message, MessageType } from 'Message';
 
@@ -18,6 +18,7 @@ export class ApiServiceService {
    */
   getProfileConfigs(): Observable<ProfileConfig[]> {
     return this.http.get<ProfileConfig[]>('/api/profiles').pipe(
+      map(profileConfigs => profileConfigs.map(withNumberedNames)),
       catchError(err => {
         console.error('Got error:');
         console.error(err);
@@ -43,6 +44,7 @@ export class ApiServiceService {
     };
 
     return this.http.post<ProfileConfig | undefined>(`/api/profiles/create/${name}`, null, httpOptions).pipe(
+      map(profileConfig => profileConfig && withNumberedNames(profileConfig)),
       tap(() => this.messageService.sendMsg(new Message(MessageType.Info, 'Neues Profil erfolgreich erzeugt'))),
       catchError(err => {
         console.error('Couldn\' create new ProfileConfig. Got:');
@@ -63,6 +65,7 @@ export class ApiServiceService {
       headers: new HttpHeaders({ 'Content-Type': 'application/json' })
     };
     return this.http.put<ProfileConfig>(`/api/profiles/uuid/${profileConfig.uuid}`, profileConfig, httpOptions).pipe(
+      map(withNumberedNames),
       tap(() => this.messageService.sendMsg(new Message(MessageType.Info, 'Profil erfolgreich aktualisiert'))),
       catchError(err => {
         console.error('Couldn\'t update profileConfig, got:');
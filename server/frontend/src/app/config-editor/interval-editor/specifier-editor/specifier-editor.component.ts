@@ -1,5 +1,5 @@
 import { Component, Input, OnInit } from '@angular/core';
-import { SPECIFIER_KINDS, Specifier, SpecifierKind, isDay, isMonth } from 'src/app/profile-config';
+import { SPECIFIER_KINDS, Specifier, SpecifierKind } from 'src/app/profile-config';
 
 @Component({
   selector: 'app-specifier-editor',
@@ -48,10 +48,10 @@ export class SpecifierEditorComponent<MIN, MAX> implements OnInit {
       // eslint-disable-next-line @typescript-eslint/no-explicit-any
       (this.specifier.kind as any)[targetValue] = newValue;
     }
-    if (this.selectedKind === 'EveryNth' && targetValue.startsWith('EveryNth')) {
+    if ((this.selectedKind === 'EveryNth' || this.selectedKind === 'Range') && targetValue.startsWith(this.selectedKind)) {
       const index = Number(targetValue.split('.')[1]);
       // eslint-disable-next-line @typescript-eslint/no-explicit-any
-      (this.specifier.kind as any)['EveryNth'][index] = newValue;
+      (this.specifier.kind as any)[this.selectedKind][index] = newValue;
     }
    
   }
@@ -94,6 +94,10 @@ export class SpecifierEditorComponent<MIN, MAX> implements OnInit {
         return { EveryNth: [0, 0] };
       case 'ExplicitList':
         return { ExplicitList: [] };
+      case 'Range':
+        return { Range: [0, 0] };
+      case 'Except':
+        return { Except: [] };
       case 'None':
       default:
         return 'None';
@@ -103,23 +107,12 @@ export class SpecifierEditorComponent<MIN, MAX> implements OnInit {
   specifierRange(end: 'min' | 'max', applyOffset = true): number {
     const value = (end === 'min' ?
       this.specifier.min :
-      this.specifier.max) as object;
-    
-    let ret: number | undefined;
-    if (typeof value === 'number') {
-      ret = value;
-    }
-    if (isDay(value)) {
-      ret = value.day;
-    }
-    if (isMonth(value)) {
-      ret = value.month;
-    }
+      this.specifier.max) as unknown;
 
-    if (ret === undefined) {
+    if (typeof value !== 'number') {
       return 0;
     }
-    return ret + (applyOffset ? this.offset : 0);
+    return value + (applyOffset ? this.offset : 0);
   }
 
   specifierValue(targetValue: string, applyOffset = true): number {
@@ -127,10 +120,10 @@ export class SpecifierEditorComponent<MIN, MAX> implements OnInit {
       // eslint-disable-next-line @typescript-eslint/no-explicit-any
       return (this.specifier.kind as any)[targetValue] as number + (applyOffset ? this.offset : 0);
     }
-    if (this.selectedKind === 'EveryNth' && targetValue.startsWith('EveryNth')) {
+    if ((this.selectedKind === 'EveryNth' || this.selectedKind === 'Range') && targetValue.startsWith(this.selectedKind)) {
       const index = Number(targetValue.split('.')[1]);
       // eslint-disable-next-line @typescript-eslint/no-explicit-any
-      return (this.specifier.kind as any)['EveryNth'][index] as number + (applyOffset ? this.offset : 0);
+      return (this.specifier.kind as any)[this.selectedKind][index] as number + (applyOffset ? this.offset : 0);
     }
     return 0;
   }
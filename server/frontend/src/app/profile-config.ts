@@ -4,7 +4,9 @@ export type SpecifierKind = 'None' | 'All' | 'First' | 'Last'
     | { BackNth: number }
     | { ExplicitNths: number[] }
     | { EveryNth: [number, number] }
-    | { ExplicitList: number[] };
+    | { ExplicitList: number[] }
+    | { Range: [number, number] }
+    | { Except: number[] };
 
 export const SPECIFIER_KINDS = [
     'None',
@@ -15,7 +17,9 @@ export const SPECIFIER_KINDS = [
     'BackNth',
     'ExplicitNths',
     'EveryNth',
-    'ExplicitList'
+    'ExplicitList',
+    'Range',
+    'Except'
 ];
 
 export type Specifier<MIN, MAX> = {
@@ -24,19 +28,67 @@ export type Specifier<MIN, MAX> = {
     kind: SpecifierKind
 };
 
-export type Day = { day: number };
-export type Month = { month: number };
-export type Monday = { day: 0 };
-export type Sunday = { day: 6 };
-export type January = { month: 0 };
-export type December = { month: 11 };
+export type Monday = 0;
+export type Sunday = 6;
+export type January = 0;
+export type December = 11;
 
-export function isDay(unknownType: object): unknownType is Day {
-    return Object.hasOwn(unknownType, 'day');
+/** Names weekdays are serialized with, starting with Monday */
+export const WEEKDAYS = ['Monday', 'Tuesday', 'Wednesday', 'Thursday', 'Friday', 'Saturday', 'Sunday'];
+/** Names months are serialized with, starting with January */
+export const MONTHS = [
+    'January', 'February', 'March', 'April', 'May', 'June',
+    'July', 'August', 'September', 'October', 'November', 'December'
+];
+
+/**
+ * Converts a weekday or month value into its number
+ * @param value Value as sent by the server. Either a name, a number or the former `{ day: n }` / `{ month: n }` form
+ * @param names Names of the values, starting with 0
+ */
+function toNumber(value: unknown, names: string[]): number {
+    if (typeof value === 'number') {
+        return value;
+    }
+    if (typeof value === 'string') {
+        return Math.max(0, names.findIndex(name => name.toLowerCase() === value.toLowerCase()
+            || name.substring(0, 3).toLowerCase() === value.toLowerCase()));
+    }
+    if (typeof value === 'object' && value !== null) {
+        const number = Object.values(value)[0];
+        return typeof number === 'number' ? number : 0;
+    }
+    return 0;
+}
+
+/**
+ * Converts the named values of a weekday or month specifier into numbers.
+ * Since the specifier starts at 0, the indices of the kind are equal to the values.
+ */
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+function withNumbers<MIN, MAX>(specifier: any, names: string[]): Specifier<MIN, MAX> {
+    const convert = (value: unknown) => toNumber(value, names);
+    let kind = specifier.kind;
+    if (typeof kind === 'object' && kind !== null) {
+        const [name, values] = Object.entries(kind)[0];
+        kind = { [name]: (name === 'BackNth' || name === 'EveryNth') ? values : Array.isArray(values) ? values.map(convert) : convert(values) };
+    }
+    return { min: convert(specifier.min), max: convert(specifier.max), kind } as Specifier<MIN, MAX>;
 }
 
-export function isMonth(unknownType: object): unknownType is Month {
-    return Object.hasOwn(unknownType, 'month');
+/**
+ * Converts the weekdays and months of the `ProfileConfig` into numbers, since the server sends them by name
+ */
+export function withNumberedNames(profileConfig: ProfileConfig): ProfileConfig {
+    const interval = profileConfig.interval;
+    return {
+        ...profileConfig,
+        interval: {
+            ...interval,
+            weekdays: withNumbers(interval.weekdays, WEEKDAYS),
+            months: withNumbers(interval.months, MONTHS),
+        }
+    };
 }
 
 export type Interval = {
@@ -45,7 +97,10 @@ export type Interval = {
     weekdays: Specifier<Monday, Sunday>,
     monthdays: Specifier<0, 31>,
     weeks: Specifier<0, 52>,
-    months: Specifier<January, December>
+    months: Specifier<January, December>,
+    nth_weekday?: number | null,
+    seconds?: Specifier<0, 59> | null,
+    excluded_dates?: string[]
 };
 
 export type ProfileConfig = {
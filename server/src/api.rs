@@ -1,19 +1,49 @@
-use std::ffi::OsStr;
+use std::io::{Cursor, Write};
+use std::net::IpAddr;
 use std::path::PathBuf;
 
-use config::interval::IntervalBuilder;
-use config::{general_config::GeneralConfig, profile_config::ProfileConfig};
-use rocket::http::Status;
-use rocket::serde::{json::Json, Serialize};
+use chrono::NaiveDateTime;
+use config::interval::{Interval, IntervalBuilder, IntervalPreset, MatchExplanation, Month, Weekday};
+use config::{
+    backup_catalog::{path_matcher, search, CatalogEntry},
+    backup_inventory::{backup_inventory, clock_skew, last_activity, BackupInfo},
+    general_config::GeneralConfig,
+    Error as ConfigError,
+    profile_config::{
+        find_overlaps, read_audit_log, AdoptionReport, AuditActor, AuditEntry, ConfigFormat,
+        ProfileConfig, ProfileOverlap, ProfilePreset,
+    },
+};
+use config::support_bundle::{PathRedactor, SupportBundle};
+use rocket::http::{Header, Status};
+use rocket::serde::{json::Json, Deserialize, Serialize};
 use rocket::tokio::fs;
 use rocket::State;
 use uuid::Uuid;
 
 use crate::errors::{Error, ErrorKind};
+use crate::jobs::{Job, JobError, JobQueue};
+use crate::negotiation::{Format, Negotiated, ProfileConfigData};
 
 #[allow(dead_code)]
 type APIError = (Status, String);
 
+/// Converts a [ConfigError] into an [APIError] with a status matching its kind.
+/// Invalid input results in a `400` and changing a read-only config in a `409`.
+/// IO errors result in a `500` without exposing the details, which are logged by the caller.
+fn config_api_error(err: ConfigError) -> APIError {
+    match err {
+        ConfigError::Io(_) => (
+            Status::InternalServerError,
+            String::from("Unexpected Error"),
+        ),
+        ConfigError::Parse(msg)
+        | ConfigError::InvalidInterval(msg)
+        | ConfigError::InvalidSpecifierRange(msg) => (Status::BadRequest, msg),
+        ConfigError::ReadOnly(msg) => (Status::Conflict, msg),
+    }
+}
+
 /// Returns the path to the dir that stores the [ProfileConfig]s.
 ///
 /// If the [GeneralConfig] file can't be opened, a `404` Error is returned.
@@ -36,7 +66,7 @@ async fn read_profile_configs(path: &PathBuf) -> Result<Vec<ProfileConfig>, Erro
 
     let mut profile_configs = vec![];
 
-    // go through each entry, check that it's a JSON and try to desrialize it
+    // go through each entry, check that it's a config file and try to desrialize it
     while let Some(entry) = dir.next_entry().await.or_else(|e| {
         log::error!("Couldn't get netx dir entry because {:#?}", e);
         Err(Error {
@@ -47,9 +77,9 @@ async fn read_profile_configs(path: &PathBuf) -> Result<Vec<ProfileConfig>, Erro
     })? {
         let path = entry.path();
 
-        // check that it's a JSON
-        if &path.extension().unwrap_or(OsStr::new("")) != &"json" {
-            log::debug!("Skip entry {:?} because it's not a JSON file", &path);
+        // check that it's in one of the config formats
+        if ConfigFormat::from_path(&path).is_none() {
+            log::debug!("Skip entry {:?} because it's not a config file", &path);
             continue;
         }
 
@@ -72,49 +102,187 @@ async fn read_profile_configs(path: &PathBuf) -> Result<Vec<ProfileConfig>, Erro
     Ok(profile_configs)
 }
 
-/// Contains protobuf serializes [ProfileConfig]s
-#[derive(Debug, Serialize)]
-#[serde(crate = "rocket::serde")]
-pub struct ProfileConfigs {
-    pub configs: Vec<Vec<u8>>,
+/// Returns the [AuditActor] for changes made through the API
+fn server_actor(client_ip: Option<IpAddr>) -> AuditActor {
+    AuditActor::Server {
+        client_ip: client_ip.map(|ip| ip.to_string()),
+    }
+}
+
+/// Appends the deletion of the given [ProfileConfig]s to their audit logs
+fn audit_deletions(dir: &PathBuf, configs: &[ProfileConfig], actor: &AuditActor) {
+    for config in configs {
+        if let Err(e) = config.audit_deletion(dir, actor.clone()) {
+            log::warn!(
+                "Couldn't write audit log of ProfileConfig {:?} because {:#?}",
+                config.get_uuid(),
+                e
+            );
+        }
+    }
 }
 
 /// Returns a vector of the [ProfileConfig]s that are found inside te directory specified in the [GeneralConfig]
 #[get("/profiles")]
 pub async fn get_profile_configs(
     general_config: &State<GeneralConfig>,
-) -> Result<(Status, Json<Vec<ProfileConfig>>), APIError> {
+    format: Format,
+) -> Result<(Status, Negotiated), APIError> {
+    let dir = &general_config.profile_configs;
+
+    let profile_configs = read_profile_configs(dir)
+        .await
+        .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
+
+    Ok((Status::Ok, format.profile_configs(&profile_configs)))
+}
+
+/// Returns all include roots that are backed up by more than one [ProfileConfig] (see [find_overlaps])
+#[get("/profiles/overlaps")]
+pub async fn get_profile_overlaps(
+    general_config: &State<GeneralConfig>,
+) -> Result<(Status, Json<Vec<ProfileOverlap>>), APIError> {
+    let dir = &general_config.profile_configs;
+
+    let profile_configs = read_profile_configs(dir)
+        .await
+        .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
+
+    Ok((Status::Ok, Json(find_overlaps(&profile_configs))))
+}
+
+/// [ProfileConfig] whose last backup lies too far in the future, because the system clock appears to be wrong (see [clock_skew])
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ClockSkew {
+    pub profile_uuid: Uuid,
+    pub profile_name: String,
+    /// `last_backup` or creation of the newest archive of the profile, whichever is later (see [last_activity])
+    pub last_activity: NaiveDateTime,
+    /// Number of hours the system clock lies before the `last_activity`
+    pub hours_behind: i64,
+}
+
+/// Response of [get_health]
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Health {
+    /// Whether the system clock appears to be right for all profiles
+    pub clock_ok: bool,
+    pub clock_skews: Vec<ClockSkew>,
+}
+
+/// Checks if the system clock appears to be wrong (e.g., because it was reset to 2010), since scheduled backups don't run in that case.
+/// The clock is compared to the last backup of every [ProfileConfig], which is searched in its resolved target dirs.
+#[get("/health")]
+pub async fn get_health(
+    general_config: &State<GeneralConfig>,
+) -> Result<(Status, Json<Health>), APIError> {
     let dir = &general_config.profile_configs;
 
     let profile_configs = read_profile_configs(dir)
         .await
         .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
 
-    Ok((Status::Ok, Json(profile_configs)))
+    let now = chrono::Local::now().naive_local();
+    let clock_skews: Vec<ClockSkew> = profile_configs
+        .iter()
+        .filter_map(|profile_config| {
+            let resolved = profile_config.resolve_paths();
+            let resolved = resolved.as_ref().unwrap_or(profile_config);
+            let skew = clock_skew(resolved, now)?;
+            log::warn!(
+                "The system clock appears to be wrong! It is {} hours behind the last backup of {:?}.",
+                skew.num_hours(),
+                profile_config.name
+            );
+            Some(ClockSkew {
+                profile_uuid: *profile_config.get_uuid(),
+                profile_name: profile_config.name.clone(),
+                last_activity: last_activity(resolved)?,
+                hours_behind: skew.num_hours(),
+            })
+        })
+        .collect();
+
+    Ok((
+        Status::Ok,
+        Json(Health {
+            clock_ok: clock_skews.is_empty(),
+            clock_skews,
+        }),
+    ))
+}
+
+/// Response of [get_profile_config_by_name]
+#[derive(Debug, Responder)]
+pub enum ProfileConfigByName {
+    /// Exactly one [ProfileConfig] has the requested name
+    #[response(status = 200)]
+    Single(Negotiated),
+    /// More than one [ProfileConfig] has the requested name
+    #[response(status = 300)]
+    Multiple(Negotiated),
 }
 
-/// Returns the [ProfileConfig] with the specified `name` or a `404` if it doesn't exist
+/// Returns the [ProfileConfig] with the specified `name` or a `404` if it doesn't exist.
+///
+/// If multiple [ProfileConfig]s share that name, a `300` with all of them is returned,
+/// so the client can pick one and use its uuid.
 #[get("/profiles/name/<name>")]
 pub async fn get_profile_config_by_name(
     general_config: &State<GeneralConfig>,
     name: String,
-) -> Result<(Status, Json<ProfileConfig>), APIError> {
+    format: Format,
+) -> Result<ProfileConfigByName, APIError> {
     let dir = &general_config.profile_configs;
 
     let profile_configs = read_profile_configs(dir)
         .await
         .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
 
-    let target_config = profile_configs
+    let target_configs: Vec<ProfileConfig> = profile_configs
         .into_iter()
-        .find(|config| config.name == name)
-        .ok_or_else(|| {
+        .filter(|config| config.name == name)
+        .collect();
+
+    match target_configs.len() {
+        0 => {
             let msg = format!("No ProfileConfig with the name {:?} was found", name);
             log::warn!("{}", msg);
-            (Status::NotFound, msg)
-        })?;
+            Err((Status::NotFound, msg))
+        }
+        1 => Ok(ProfileConfigByName::Single(
+            format.profile_config(&target_configs[0]),
+        )),
+        n => {
+            log::warn!("{} ProfileConfigs with the name {:?} were found", n, name);
+            Ok(ProfileConfigByName::Multiple(
+                format.profile_configs(&target_configs),
+            ))
+        }
+    }
+}
+
+/// Returns all [ProfileConfig]s that have the specified `tag` (case-insensitive). The list is empty if no [ProfileConfig] has it.
+#[get("/profiles/tag/<tag>")]
+pub async fn get_profile_configs_by_tag(
+    general_config: &State<GeneralConfig>,
+    tag: String,
+    format: Format,
+) -> Result<(Status, Negotiated), APIError> {
+    let dir = &general_config.profile_configs;
+
+    let profile_configs = read_profile_configs(dir)
+        .await
+        .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
+
+    let target_configs: Vec<ProfileConfig> = profile_configs
+        .into_iter()
+        .filter(|config| config.has_tag(&tag))
+        .collect();
 
-    Ok((Status::Ok, Json(target_config)))
+    Ok((Status::Ok, format.profile_configs(&target_configs)))
 }
 
 #[derive(Debug)]
@@ -160,18 +328,52 @@ async fn delete_profile_config(
     }
 }
 
+/// Lets the backupper (re-)register the schedule of the profile config with the OS
+async fn reschedule_profile_config(backupper_path: &PathBuf, uuid: &Uuid) -> Result<String, String> {
+    let output = rocket::tokio::process::Command::new(backupper_path.as_os_str())
+        .arg("-u")
+        .arg(uuid.as_hyphenated().to_string())
+        .arg("reschedule")
+        .output()
+        .await;
+    log::debug!("{:#?}", output);
+    match output {
+        Ok(output) => match output.status.code() {
+            Some(x) if x == 0 => Ok(format!("Rescheduling of ProfileConfig {:?} successful", uuid)),
+            _ => Err(format!(
+                "Rescheduling of ProfileConfig {:?} failed. Error: {:#?}",
+                uuid, output
+            )),
+        },
+        Err(e) => Err(format!(
+            "Rescheduling of ProfileConfig {:?} failed. Error: {:#?}",
+            uuid, e
+        )),
+    }
+}
+
 /// Deletes the [ProfileConfig] with the given name.
 #[delete("/profiles/name/<name>")]
 pub async fn delete_profile_config_by_name(
+    general_config: &State<GeneralConfig>,
     backupper_path: &State<PathBuf>,
+    client_ip: Option<IpAddr>,
     name: String,
 ) -> Result<Status, APIError> {
     log::debug!("Delete ProfileConfig {:?}", name);
+    let dir = &general_config.profile_configs;
+    let deleted_configs: Vec<ProfileConfig> = read_profile_configs(dir)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|config| config.name == name)
+        .collect();
     let res = delete_profile_config(backupper_path, Identifyier::Name(name)).await;
 
     match res {
         Ok(msg) => {
             log::debug!("{}", msg);
+            audit_deletions(dir, &deleted_configs, &server_actor(client_ip));
             Ok(Status::NoContent)
         },
         Err(msg) => {
@@ -185,7 +387,8 @@ pub async fn delete_profile_config_by_name(
 pub async fn get_profile_config_by_uuid(
     general_config: &State<GeneralConfig>,
     uuid: String,
-) -> Result<(Status, Json<ProfileConfig>), APIError> {
+    format: Format,
+) -> Result<(Status, Negotiated), APIError> {
     let uuid = Uuid::parse_str(&uuid).or_else(|e| {
         log::warn!("Couldn't parse uuid {:?} because {:#?}", uuid, e);
         Err((
@@ -209,13 +412,188 @@ pub async fn get_profile_config_by_uuid(
             (Status::NotFound, msg)
         })?;
 
-    Ok((Status::Ok, Json(target_config)))
+    Ok((Status::Ok, format.profile_config(&target_config)))
+}
+
+/// Explains why the [ProfileConfig] with the given uuid is or isn't scheduled at `at`.
+/// `at` has the format `YYYY-MM-DD HH:MM` or `YYYY-MM-DDTHH:MM`.
+#[get("/profiles/uuid/<uuid>/explain?<at>")]
+pub async fn explain_profile_schedule(
+    general_config: &State<GeneralConfig>,
+    uuid: String,
+    at: String,
+) -> Result<(Status, Json<MatchExplanation>), APIError> {
+    let uuid = Uuid::parse_str(&uuid).or_else(|e| {
+        log::warn!("Couldn't parse uuid {:?} because {:#?}", uuid, e);
+        Err((
+            Status::BadRequest,
+            format!("{:?} is not a valid uuid", uuid),
+        ))
+    })?;
+
+    let datetime = NaiveDateTime::parse_from_str(&at, "%Y-%m-%d %H:%M")
+        .or_else(|_| NaiveDateTime::parse_from_str(&at, "%Y-%m-%dT%H:%M"))
+        .or_else(|e| {
+            log::warn!("Couldn't parse datetime {:?} because {:#?}", at, e);
+            Err((
+                Status::BadRequest,
+                format!("{:?} is not a valid datetime. Expected YYYY-MM-DD HH:MM", at),
+            ))
+        })?;
+
+    let dir = &general_config.profile_configs;
+
+    let profile_configs = read_profile_configs(dir)
+        .await
+        .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
+
+    let target_config = profile_configs
+        .into_iter()
+        .find(|config| config.get_uuid() == &uuid)
+        .ok_or_else(|| {
+            let msg = format!("No ProfileConfig with the uuid {:?} was found", uuid);
+            log::warn!("{}", msg);
+            (Status::NotFound, msg)
+        })?;
+
+    Ok((
+        Status::Ok,
+        Json(target_config.interval.explain_match(datetime)),
+    ))
+}
+
+/// Lists the backup archives of the [ProfileConfig] with the given uuid from oldest to newest, e.g., for picking the one to restore.
+/// If the target dir can't be read (e.g., because the external drive isn't attached), a `503` Error is returned.
+#[get("/profiles/uuid/<uuid>/backups")]
+pub async fn get_profile_backups(
+    general_config: &State<GeneralConfig>,
+    uuid: String,
+) -> Result<(Status, Json<Vec<BackupInfo>>), APIError> {
+    let uuid = Uuid::parse_str(&uuid).or_else(|e| {
+        log::warn!("Couldn't parse uuid {:?} because {:#?}", uuid, e);
+        Err((
+            Status::BadRequest,
+            format!("{:?} is not a valid uuid", uuid),
+        ))
+    })?;
+
+    let dir = &general_config.profile_configs;
+
+    let profile_configs = read_profile_configs(dir)
+        .await
+        .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
+
+    let target_config = profile_configs
+        .into_iter()
+        .find(|config| config.get_uuid() == &uuid)
+        .ok_or_else(|| {
+            let msg = format!("No ProfileConfig with the uuid {:?} was found", uuid);
+            log::warn!("{}", msg);
+            (Status::NotFound, msg)
+        })?;
+
+    let backups = backup_inventory(&target_config).or_else(|msg| {
+        log::warn!("Couldn't list the backups of {:?} because {}", target_config.name, msg);
+        Err((Status::ServiceUnavailable, msg))
+    })?;
+
+    Ok((Status::Ok, Json(backups)))
+}
+
+/// Returns the [IntervalPreset] the interval of the [ProfileConfig] with the given uuid corresponds to.
+/// If it doesn't fit any preset, `{"kind": "custom"}` is returned.
+#[get("/profiles/uuid/<uuid>/interval/preset")]
+pub async fn get_profile_interval_preset(
+    general_config: &State<GeneralConfig>,
+    uuid: String,
+) -> Result<(Status, Json<IntervalPreset>), APIError> {
+    let uuid = Uuid::parse_str(&uuid).or_else(|e| {
+        log::warn!("Couldn't parse uuid {:?} because {:#?}", uuid, e);
+        Err((
+            Status::BadRequest,
+            format!("{:?} is not a valid uuid", uuid),
+        ))
+    })?;
+
+    let dir = &general_config.profile_configs;
+
+    let profile_configs = read_profile_configs(dir)
+        .await
+        .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
+
+    let target_config = profile_configs
+        .into_iter()
+        .find(|config| config.get_uuid() == &uuid)
+        .ok_or_else(|| {
+            let msg = format!("No ProfileConfig with the uuid {:?} was found", uuid);
+            log::warn!("{}", msg);
+            (Status::NotFound, msg)
+        })?;
+
+    Ok((Status::Ok, Json(target_config.interval.classify())))
+}
+
+/// Returns the audit log of the [ProfileConfig] with the given uuid from oldest to newest entry.
+/// Also works for deleted profiles.
+#[get("/profiles/uuid/<uuid>/audit")]
+pub async fn get_profile_audit_log(
+    general_config: &State<GeneralConfig>,
+    uuid: String,
+) -> Result<(Status, Json<Vec<AuditEntry>>), APIError> {
+    let uuid = Uuid::parse_str(&uuid).or_else(|e| {
+        log::warn!("Couldn't parse uuid {:?} because {:#?}", uuid, e);
+        Err((
+            Status::BadRequest,
+            format!("{:?} is not a valid uuid", uuid),
+        ))
+    })?;
+
+    let entries = read_audit_log(&general_config.profile_configs, &uuid).or_else(|e| {
+        log::error!(
+            "Couldn't read audit log of ProfileConfig {:?} because {:#?}",
+            uuid,
+            e
+        );
+        Err((
+            Status::InternalServerError,
+            String::from("Unexpected Error"),
+        ))
+    })?;
+
+    Ok((Status::Ok, Json(entries)))
+}
+
+/// Searches the catalogs of all profiles for the archived versions of the files whose paths match `q`, newest first.
+/// `q` is either a part of the path or a glob like `*/Documents/*.docx`, and case is ignored. At most `limit` versions are returned.
+/// If `q` isn't a valid glob, a `400` Error is returned.
+#[get("/search?<q>&<limit>")]
+pub async fn search_catalogs(
+    general_config: &State<GeneralConfig>,
+    q: String,
+    limit: Option<usize>,
+) -> Result<(Status, Json<Vec<CatalogEntry>>), APIError> {
+    if let Err(msg) = path_matcher(&q) {
+        log::warn!("Couldn't search for {:?} because {}", q, msg);
+        return Err((Status::BadRequest, msg));
+    }
+
+    let found = search(&general_config.profile_configs, None, &q, limit).or_else(|msg| {
+        log::error!("Couldn't search the catalogs because {}", msg);
+        Err((
+            Status::InternalServerError,
+            String::from("Unexpected Error"),
+        ))
+    })?;
+
+    Ok((Status::Ok, Json(found)))
 }
 
 // Deletes [ProfileConfig] with the given uuid
 #[delete("/profiles/uuid/<uuid>")]
 pub async fn delete_profile_config_by_uuid(
+    general_config: &State<GeneralConfig>,
     backupper_path: &State<PathBuf>,
+    client_ip: Option<IpAddr>,
     uuid: String,
 ) -> Result<Status, APIError> {
     let uuid = Uuid::parse_str(&uuid).or_else(|e| {
@@ -227,11 +605,19 @@ pub async fn delete_profile_config_by_uuid(
     })?;
 
     log::info!("Delete ProfileConfig {:?}", uuid);
+    let dir = &general_config.profile_configs;
+    let deleted_configs: Vec<ProfileConfig> = read_profile_configs(dir)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|config| config.get_uuid() == &uuid)
+        .collect();
     let msg = delete_profile_config(backupper_path, Identifyier::Uuid(uuid)).await;
 
     match msg {
         Ok(msg) => {
             log::debug!("{}", msg);
+            audit_deletions(dir, &deleted_configs, &server_actor(client_ip));
             Ok(Status::NoContent)
         },
         Err(msg) => {
@@ -241,22 +627,99 @@ pub async fn delete_profile_config_by_uuid(
     }
 }
 
-/// Tries to create a new profile config with the given name.
-///
-/// # Returns
-/// On success: The created profile config
-/// On error: An error describing the issue
-#[post("/profiles/create/<name>")]
-pub async fn create_blank_profile_config(
+/// Converts a [JobError] into an [APIError] with a matching status.
+fn job_api_error(err: JobError) -> APIError {
+    match err {
+        JobError::NotFound(msg) => (Status::NotFound, msg),
+        JobError::Conflict(msg) => (Status::Conflict, msg),
+        JobError::Io(msg) => {
+            log::error!("{}", msg);
+            (Status::InternalServerError, msg)
+        }
+    }
+}
+
+/// Starts a backup of the [ProfileConfig] with the given uuid, even if it isn't due yet.
+/// The backup runs as a [Job], which can be queried and cancelled through `/jobs/<id>`.
+/// If the profile already has a running job, a `409` Error is returned.
+#[post("/profiles/uuid/<uuid>/backup")]
+pub async fn start_profile_backup(
     general_config: &State<GeneralConfig>,
-    name: String,
-) -> Result<(Status, Json<ProfileConfig>), APIError> {
-    let dir = &general_config.profile_configs;
+    backupper_path: &State<PathBuf>,
+    jobs: &State<JobQueue>,
+    uuid: String,
+) -> Result<(Status, Json<Job>), APIError> {
+    let uuid = Uuid::parse_str(&uuid).or_else(|e| {
+        log::warn!("Couldn't parse uuid {:?} because {:#?}", uuid, e);
+        Err((
+            Status::BadRequest,
+            format!("{:?} is not a valid uuid", uuid),
+        ))
+    })?;
 
-    let profile_configs = read_profile_configs(dir)
+    let profile_configs = read_profile_configs(&general_config.profile_configs)
         .await
         .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
+    if !profile_configs.iter().any(|config| config.get_uuid() == &uuid) {
+        let msg = format!("No ProfileConfig with the uuid {:?} was found", uuid);
+        log::warn!("{}", msg);
+        return Err((Status::NotFound, msg));
+    }
+
+    let job = jobs.start_backup(backupper_path, &uuid).map_err(job_api_error)?;
+    Ok((Status::Accepted, Json(job)))
+}
+
+/// Returns all [Job]s started since the server was started, oldest first.
+#[get("/jobs")]
+pub async fn get_jobs(jobs: &State<JobQueue>) -> (Status, Json<Vec<Job>>) {
+    (Status::Ok, Json(jobs.jobs()))
+}
+
+/// Returns the [Job] with the given id. Stopped backups have the status `cancelled`.
+#[get("/jobs/<id>")]
+pub async fn get_job(jobs: &State<JobQueue>, id: u64) -> Result<(Status, Json<Job>), APIError> {
+    jobs.job(id)
+        .map(|job| (Status::Ok, Json(job)))
+        .ok_or_else(|| (Status::NotFound, format!("There is no job {}", id)))
+}
+
+/// Cancels the [Job] with the given id: The backupper finishes the current file and keeps the files archived until then as a partial archive.
+/// The job is `cancelling` until the backupper exits. If the job isn't running anymore, a `409` Error is returned.
+#[delete("/jobs/<id>")]
+pub async fn cancel_job(jobs: &State<JobQueue>, id: u64) -> Result<(Status, Json<Job>), APIError> {
+    let job = jobs.cancel(id).map_err(job_api_error)?;
+    Ok((Status::Accepted, Json(job)))
+}
+
+/// Pauses the [Job] with the given id after the file it is archiving. It stays `paused` until it is resumed.
+/// If the job isn't running anymore, a `409` Error is returned. If its backupper can't be reached, a `500` Error is returned.
+#[post("/jobs/<id>/pause")]
+pub async fn pause_job(jobs: &State<JobQueue>, id: u64) -> Result<(Status, Json<Job>), APIError> {
+    let job = jobs.pause(id).map_err(job_api_error)?;
+    Ok((Status::Ok, Json(job)))
+}
 
+/// Resumes the paused [Job] with the given id. Errors are returned like for pausing it.
+#[post("/jobs/<id>/resume")]
+pub async fn resume_job(jobs: &State<JobQueue>, id: u64) -> Result<(Status, Json<Job>), APIError> {
+    let job = jobs.resume(id).map_err(job_api_error)?;
+    Ok((Status::Ok, Json(job)))
+}
+
+/// Optional body for creating a new [ProfileConfig]
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateOptions {
+    /// Preset to apply to the newly created [ProfileConfig]
+    pub preset: Option<ProfilePreset>,
+}
+
+/// Checks that none of the `profile_configs` has the given `name`. Names are compared case-insensitively.
+///
+/// # Errors
+/// Returns a `409` if the name is already taken.
+fn check_name_available(profile_configs: &[ProfileConfig], name: &str) -> Result<(), APIError> {
     let name_already_taken = profile_configs
         .iter()
         .any(|config| config.name.to_lowercase() == name.to_lowercase());
@@ -266,46 +729,161 @@ pub async fn create_blank_profile_config(
             format!("Name {:?} is already taken", name),
         ));
     }
+    Ok(())
+}
 
-    let none_interval = IntervalBuilder::default()
-        .minutes(config::interval::SpecifierKind::None)
-        .build()
-        .or_else(|e| {
-            log::error!("Couldn't build none_interval because {:#?}", e);
-            Err((
-                Status::InternalServerError,
-                String::from("Unexpected Error"),
+/// Hour at which the intervals created by [preset_interval] are scheduled
+const PRESET_HOUR: u32 = 12;
+
+/// Creates the interval for the `preset` query parameter of [create_blank_profile_config]
+fn preset_interval(preset: &str) -> Result<Interval, APIError> {
+    let interval = match preset {
+        "daily" => Interval::daily(0, PRESET_HOUR),
+        "weekly" => Interval::weekly(Weekday::Monday(), PRESET_HOUR, 0),
+        "monthly" => Interval::monthly(0, PRESET_HOUR, 0),
+        "yearly" => Interval::yearly(Month::January(), 0, PRESET_HOUR, 0),
+        _ => {
+            return Err((
+                Status::BadRequest,
+                format!(
+                    "{:?} is not a valid interval preset. Expected daily, weekly, monthly or yearly",
+                    preset
+                ),
             ))
+        }
+    };
+    interval.map_err(config_api_error)
+}
+
+/// Tries to create a new profile config with the given name and the `default_target_dir` of the [GeneralConfig].
+/// If the body contains a `preset`, it will be applied to the new profile config.
+/// The `preset` query parameter (`daily`, `weekly`, `monthly` or `yearly`) sets the interval. Without it, the profile is never scheduled.
+///
+/// # Returns
+/// On success: The created profile config
+/// On error: An error describing the issue
+#[post("/profiles/create/<name>?<preset>", data = "<options>")]
+pub async fn create_blank_profile_config(
+    general_config: &State<GeneralConfig>,
+    client_ip: Option<IpAddr>,
+    name: String,
+    preset: Option<String>,
+    options: Option<Json<CreateOptions>>,
+    format: Format,
+) -> Result<(Status, Negotiated), APIError> {
+    let interval = preset
+        .map(|preset| preset_interval(&preset))
+        .transpose()
+        .or_else(|(status, msg)| {
+            log::warn!("{}", msg);
+            Err((status, msg))
         })?;
-    let profile_config = ProfileConfig::new(
+
+    let dir = &general_config.profile_configs;
+
+    let profile_configs = read_profile_configs(dir)
+        .await
+        .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
+
+    check_name_available(&profile_configs, &name)?;
+
+    let interval = match interval {
+        Some(interval) => interval,
+        None => IntervalBuilder::default()
+            .minutes(config::interval::SpecifierKind::None)
+            .build()
+            .or_else(|e| {
+                log::error!("Couldn't build none_interval because {:#?}", e);
+                Err((
+                    Status::InternalServerError,
+                    String::from("Unexpected Error"),
+                ))
+            })?,
+    };
+    let mut profile_config = ProfileConfig::new(
         name,
-        PathBuf::from(""),
+        general_config.default_target_dir.clone().unwrap_or_default(),
         vec![],
         vec![],
         vec![],
         vec![],
-        none_interval,
+        interval,
     );
 
-    profile_config.store(dir).or_else(|e| {
+    if let Some(preset) = options.and_then(|options| options.preset) {
+        log::debug!("Apply preset {:?} to new ProfileConfig", preset);
+        preset.apply(&mut profile_config);
+    }
+
+    profile_config.store_audited(dir, server_actor(client_ip)).or_else(|e| {
         log::error!("Couldn't store new ProfileConfig because {:#?}", e);
+        Err(config_api_error(e))
+    })?;
+
+    Ok((Status::Created, format.profile_config(&profile_config)))
+}
+
+/// Creates a copy of the [ProfileConfig] with the given `uuid` named `name` (see [ProfileConfig::duplicate]).
+/// The copy is stored in the same format as the original, but isn't scheduled.
+///
+/// # Returns
+/// On success: The created profile config
+/// On error: `400` if the uuid is invalid, `404` if there is no such profile or `409` if the name is already taken
+#[post("/profiles/uuid/<uuid>/duplicate?<name>")]
+pub async fn duplicate_profile_config(
+    general_config: &State<GeneralConfig>,
+    client_ip: Option<IpAddr>,
+    uuid: String,
+    name: String,
+    format: Format,
+) -> Result<(Status, Negotiated), APIError> {
+    let uuid = Uuid::parse_str(&uuid).or_else(|e| {
+        log::warn!("Couldn't parse uuid {:?} because {:#?}", uuid, e);
         Err((
-            Status::InternalServerError,
-            String::from("Unexpected Error"),
+            Status::BadRequest,
+            format!("{:?} is not a valid uuid", uuid),
         ))
     })?;
 
-    Ok((Status::Created, Json(profile_config)))
+    let dir = &general_config.profile_configs;
+
+    let profile_configs = read_profile_configs(dir)
+        .await
+        .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
+
+    check_name_available(&profile_configs, &name)?;
+
+    let source_config = profile_configs
+        .iter()
+        .find(|config| config.get_uuid() == &uuid)
+        .ok_or_else(|| {
+            let msg = format!("No ProfileConfig with the uuid {:?} was found", uuid);
+            log::warn!("{}", msg);
+            (Status::NotFound, msg)
+        })?;
+
+    let profile_config = source_config.duplicate(name);
+    profile_config.store_audited(dir, server_actor(client_ip)).or_else(|e| {
+        log::error!("Couldn't store duplicated ProfileConfig because {:#?}", e);
+        Err(config_api_error(e))
+    })?;
+
+    Ok((Status::Created, format.profile_config(&profile_config)))
 }
 
-/// Updates the [ProfileConfig] with the given `uuid`. The `uuid` and the `next-backup` field won't be considered for updating
+/// Updates the [ProfileConfig] with the given `uuid`. The `uuid`, `next-backup` and `former_uuids` fields won't be considered for updating.
+/// If the `target_dir` changed, the old one is added to `previous_target_dirs`.
+///
+/// The new config can also be sent as protobuf. Only the fields applied by [ProfileConfig::update_from_buf] are taken from it, all others are kept.
 #[put("/profiles/uuid/<uuid>", data = "<new_config>")]
 pub async fn update_profile_config(
     general_config: &State<GeneralConfig>,
     backupper_path: &State<PathBuf>,
+    client_ip: Option<IpAddr>,
     uuid: String,
-    new_config: Json<ProfileConfig>,
-) -> Result<(Status, Json<ProfileConfig>), APIError> {
+    new_config: ProfileConfigData,
+    format: Format,
+) -> Result<(Status, Negotiated), APIError> {
     let uuid = Uuid::parse_str(&uuid).or_else(|e| {
         log::warn!("Couldn't parse uuid {:?} because {:#?}", uuid, e);
         Err((
@@ -314,24 +892,43 @@ pub async fn update_profile_config(
         ))
     })?;
 
-    if let Err(msg) = new_config.interval.validate() {
-        log::warn!("Got invalid interval: {:?}", msg);
-        return Err((Status::BadRequest, msg));
-    }
-
     let dir = &general_config.profile_configs;
 
     let profile_configs = read_profile_configs(dir)
         .await
         .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
 
-    let mut new_config = new_config.0;
+    let (target_config, other_configs): (Vec<ProfileConfig>, Vec<ProfileConfig>) = profile_configs
+        .into_iter()
+        .partition(|config| config.get_uuid() == &uuid);
+    let target_config = target_config.into_iter().next().ok_or_else(|| {
+        let msg = format!("No ProfileConfig with the uuid {:?} was found", uuid);
+        log::warn!("{}", msg);
+        (Status::NotFound, msg)
+    })?;
+
+    let mut new_config = match new_config {
+        ProfileConfigData::Json(config) => config,
+        #[cfg(feature = "protobuf")]
+        ProfileConfigData::Protobuf(buf) => {
+            let mut config = target_config.clone();
+            config.update_from_buf(&buf).or_else(|e| {
+                log::warn!("Couldn't decode ProfileConfig because {:?}", e);
+                Err(config_api_error(e))
+            })?;
+            config
+        }
+    };
+
+    if let Err(msgs) = new_config.validate() {
+        log::warn!("Got invalid ProfileConfig: {:?}", msgs);
+        return Err((Status::BadRequest, msgs.join("\n")));
+    }
 
     // check that name isn't already taken
-    let name_already_taken = profile_configs.iter().any(|config| {
-        config.get_uuid() != new_config.get_uuid()
-            && config.name.to_lowercase() == new_config.name.to_lowercase()
-    });
+    let name_already_taken = other_configs
+        .iter()
+        .any(|config| config.name.to_lowercase() == new_config.name.to_lowercase());
     if name_already_taken {
         return Err((
             Status::Conflict,
@@ -339,59 +936,295 @@ pub async fn update_profile_config(
         ));
     }
 
-    let target_config = profile_configs
-        .into_iter()
-        .find(|config| config.get_uuid() == &uuid)
-        .ok_or_else(|| {
-            let msg = format!("No ProfileConfig with the uuid {:?} was found", uuid);
-            log::warn!("{}", msg);
-            (Status::NotFound, msg)
-        })?;
+    if target_config.is_read_only() {
+        let msg = format!(
+            "ProfileConfig {:?} was written by version {} and can't be modified by this version",
+            uuid, target_config.written_by_version
+        );
+        log::warn!("{}", msg);
+        return Err((Status::Conflict, msg));
+    }
 
     new_config.set_uuid(uuid);
+    new_config.set_format(target_config.format());
     new_config.next_backup = target_config.next_backup;
+    new_config.former_uuids = target_config.former_uuids.clone();
+    // the interval might have changed
+    new_config.refresh_upcoming();
+    if new_config.target_dir != target_config.target_dir {
+        log::info!(
+            "target_dir of ProfileConfig {:?} changed from {:?} to {:?}",
+            uuid,
+            target_config.target_dir,
+            new_config.target_dir
+        );
+        new_config.record_previous_target_dir(target_config.target_dir.clone());
+    }
+
+    let mut all_configs = other_configs;
+    all_configs.push(new_config);
+    for overlap in find_overlaps(&all_configs)
+        .into_iter()
+        .filter(|overlap| overlap.profile == uuid || overlap.other == uuid)
+    {
+        log::warn!(
+            "{:?} of ProfileConfig {:?} is also backed up by ProfileConfig {:?}",
+            overlap.root,
+            overlap.profile_name,
+            overlap.other_name
+        );
+    }
+    let new_config = all_configs.pop().expect("new_config was just pushed");
 
     // we have to store first; otherwise the reschedule would just take the old interval
-    new_config.store(dir).or_else(|e| {
+    new_config.store_audited(dir, server_actor(client_ip)).or_else(|e| {
         log::error!(
             "Couldn't store ProfileConfig {:?} because {:#?}",
             new_config.get_uuid(),
             e
         );
+        Err(config_api_error(e))
+    })?;
+
+    if !new_config.interval.semantically_eq(&target_config.interval) {
+        log::info!("Rescheduling ProfileConfig {:?}", new_config.get_uuid());
+        match reschedule_profile_config(backupper_path, new_config.get_uuid()).await {
+            Ok(msg) => log::debug!("{}", msg),
+            Err(msg) => log::warn!("{}", msg),
+        }
+    }
+
+    Ok((Status::Ok, format.profile_config(&new_config)))
+}
+
+/// Body for updating only the interval of a [ProfileConfig].
+/// Exactly one of the fields has to be set.
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct IntervalPatch {
+    /// The new interval described as [IntervalPreset]
+    pub preset: Option<IntervalPreset>,
+    /// The new interval as raw structure
+    pub interval: Option<Interval>,
+    /// The new interval as cron expression (see [Interval::from_cron_str])
+    pub cron: Option<String>,
+}
+
+/// Updates the interval of the [ProfileConfig] with the given `uuid` and reschedules it.
+/// The new interval is either given as `preset`, as raw `interval` or as `cron` expression (see [IntervalPatch]).
+#[patch("/profiles/uuid/<uuid>/interval", data = "<patch>")]
+pub async fn patch_profile_interval(
+    general_config: &State<GeneralConfig>,
+    backupper_path: &State<PathBuf>,
+    client_ip: Option<IpAddr>,
+    uuid: String,
+    patch: Json<IntervalPatch>,
+    format: Format,
+) -> Result<(Status, Negotiated), APIError> {
+    let uuid = Uuid::parse_str(&uuid).or_else(|e| {
+        log::warn!("Couldn't parse uuid {:?} because {:#?}", uuid, e);
         Err((
-            Status::InternalServerError,
-            String::from("Unexpected Error"),
+            Status::BadRequest,
+            format!("{:?} is not a valid uuid", uuid),
         ))
     })?;
 
-    if new_config.interval != target_config.interval {
-        log::info!("Rescheduling ProfileConfig {:?}", new_config.get_uuid());
-        let output = rocket::tokio::process::Command::new(backupper_path.as_os_str())
-            .arg("-u")
-            .arg(new_config.get_uuid().to_string())
-            .arg("reschedule")
-            .output()
-            .await;
-        log::debug!("{:#?}", output);
-        match output {
-            Ok(output) => match output.status.code() {
-                Some(x) if x == 0 => log::debug!(
-                    "Rescheduling of ProfileConfig {:?} successful",
-                    new_config.get_uuid()
-                ),
-                _ => log::warn!(
-                    "Rescheduling of ProfileConfig {:?} failed. Error: {:#?}",
-                    new_config.get_uuid(),
-                    output
-                ),
-            },
-            Err(e) => log::warn!(
-                "Rescheduling of ProfileConfig {:?} failed. Error: {:#?}",
-                new_config.get_uuid(),
+    let interval = match patch.into_inner() {
+        IntervalPatch {
+            preset: Some(preset),
+            interval: None,
+            cron: None,
+        } => Interval::from_preset(&preset).or_else(|msg| {
+            log::warn!("Got invalid preset {:?}: {:?}", preset, msg);
+            Err((Status::BadRequest, msg))
+        })?,
+        IntervalPatch {
+            preset: None,
+            interval: None,
+            cron: Some(cron),
+        } => Interval::from_cron_str(&cron).or_else(|msg| {
+            log::warn!("Got invalid cron expression {:?}: {:?}", cron, msg);
+            Err((Status::BadRequest, msg))
+        })?,
+        IntervalPatch {
+            preset: None,
+            interval: Some(interval),
+            cron: None,
+        } => {
+            if let Err(e) = interval.validate() {
+                log::warn!("Got invalid interval: {:?}", e);
+                return Err(config_api_error(e));
+            }
+            interval
+        }
+        _ => {
+            return Err((
+                Status::BadRequest,
+                String::from("Expected exactly one of 'preset', 'interval' and 'cron'"),
+            ))
+        }
+    };
+    if let Err(msg) = interval.satisfiability_check() {
+        log::warn!("Got unsatisfiable interval: {:?}", msg);
+        return Err((Status::BadRequest, msg));
+    }
+
+    let dir = &general_config.profile_configs;
+
+    let profile_configs = read_profile_configs(dir)
+        .await
+        .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
+
+    let mut target_config = profile_configs
+        .into_iter()
+        .find(|config| config.get_uuid() == &uuid)
+        .ok_or_else(|| {
+            let msg = format!("No ProfileConfig with the uuid {:?} was found", uuid);
+            log::warn!("{}", msg);
+            (Status::NotFound, msg)
+        })?;
+
+    if target_config.is_read_only() {
+        let msg = format!(
+            "ProfileConfig {:?} was written by version {} and can't be modified by this version",
+            uuid, target_config.written_by_version
+        );
+        log::warn!("{}", msg);
+        return Err((Status::Conflict, msg));
+    }
+
+    if target_config.interval == interval {
+        return Ok((Status::Ok, format.profile_config(&target_config)));
+    }
+
+    target_config.interval = interval;
+    target_config.refresh_upcoming();
+    target_config
+        .store_audited(dir, server_actor(client_ip))
+        .or_else(|e| {
+            log::error!("Couldn't store ProfileConfig {:?} because {:#?}", uuid, e);
+            Err(config_api_error(e))
+        })?;
+
+    log::info!("Rescheduling ProfileConfig {:?}", uuid);
+    match reschedule_profile_config(backupper_path, &uuid).await {
+        Ok(msg) => log::debug!("{}", msg),
+        Err(msg) => log::warn!("{}", msg),
+    }
+
+    Ok((Status::Ok, format.profile_config(&target_config)))
+}
+
+/// Adopts all [ProfileConfig]s in the config dir, e.g., after restoring them on a new machine.
+/// Stale `next_backup` values are recomputed and the schedules are registered with the OS again.
+#[post("/profiles/adopt")]
+pub async fn adopt_profile_configs(
+    general_config: &State<GeneralConfig>,
+    backupper_path: &State<PathBuf>,
+    client_ip: Option<IpAddr>,
+) -> Result<(Status, Json<Vec<AdoptionReport>>), APIError> {
+    let dir = &general_config.profile_configs;
+
+    let profile_configs = read_profile_configs(dir)
+        .await
+        .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
+
+    let now = chrono::Local::now().naive_local();
+    let mut reports = vec![];
+    for mut config in profile_configs {
+        let mut report = config.adopt(now);
+        if !report.target_available {
+            log::warn!(
+                "target_dir {:?} of ProfileConfig {:?} is not available",
+                report.target_dir,
+                report.uuid
+            );
+        }
+
+        if let Err(e) = config.store_audited(dir, server_actor(client_ip)) {
+            log::error!(
+                "Couldn't store ProfileConfig {:?} because {:#?}",
+                config.get_uuid(),
                 e
-            ),
+            );
+            reports.push(report);
+            continue;
+        }
+
+        match reschedule_profile_config(backupper_path, config.get_uuid()).await {
+            Ok(msg) => {
+                log::debug!("{}", msg);
+                report.schedule_registered = true;
+            }
+            Err(msg) => log::warn!("{}", msg),
         }
+        reports.push(report);
     }
 
-    Ok((Status::Ok, Json(new_config)))
+    Ok((Status::Ok, Json(reports)))
+}
+
+/// A zip archive that is downloaded as `support-bundle.zip`
+#[derive(Debug, Responder)]
+#[response(content_type = "application/zip")]
+pub struct SupportBundleZip {
+    archive: Vec<u8>,
+    disposition: Header<'static>,
+}
+
+/// Assembles the [SupportBundle] and packs it into a zip archive
+fn zip_support_bundle(
+    general_config: &GeneralConfig,
+    profile_configs: &[ProfileConfig],
+    redactor: Option<PathRedactor>,
+) -> Result<Vec<u8>, String> {
+    let mut bundle = SupportBundle::new(redactor);
+    bundle
+        .add_general_config(general_config)
+        .map_err(|e| e.to_string())?;
+    for config in profile_configs {
+        bundle.add_profile_config(config).map_err(|e| e.to_string())?;
+    }
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+    let options = zip::write::FileOptions::default();
+    for (name, content) in bundle.into_entries().map_err(|e| e.to_string())? {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(&content).map_err(|e| e.to_string())?;
+    }
+    Ok(zip.finish().map_err(|e| e.to_string())?.into_inner())
+}
+
+/// Assembles the general config, all [ProfileConfig]s and the version info into a zip archive for bug reports.
+/// With `redact=true`, all path components beyond the root are replaced by hashes.
+#[get("/support-bundle?<redact>")]
+pub async fn get_support_bundle(
+    general_config: &State<GeneralConfig>,
+    redact: Option<bool>,
+) -> Result<SupportBundleZip, APIError> {
+    let dir = &general_config.profile_configs;
+
+    let profile_configs = read_profile_configs(dir)
+        .await
+        .or_else(|e| Err((Status::InternalServerError, e.msg)))?;
+
+    let redactor = match redact {
+        Some(true) => Some(PathRedactor::new()),
+        _ => None,
+    };
+    let archive = zip_support_bundle(general_config, &profile_configs, redactor).or_else(|msg| {
+        log::error!("Couldn't assemble support bundle because {:?}", msg);
+        Err((
+            Status::InternalServerError,
+            String::from("Unexpected Error"),
+        ))
+    })?;
+
+    Ok(SupportBundleZip {
+        archive,
+        disposition: Header::new(
+            "Content-Disposition",
+            "attachment; filename=\"support-bundle.zip\"",
+        ),
+    })
 }
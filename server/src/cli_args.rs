@@ -7,6 +7,10 @@ pub struct Args {
     #[arg(short, long, default_value_t = String::from("./general_config.json"))]
     pub general_config: String,
 
+    /// Fail if the general config file doesn't exist instead of creating a default one.
+    #[arg(long)]
+    pub no_create: bool,
+
     /// Path to the logger config YAML-file.
     #[arg(long, default_value_t = String::from("./logging_conf.yaml"))]
     pub logger_config: String,
@@ -0,0 +1,231 @@
+//! Contains the queue of the backups started through the API, so their status can be queried and they can be paused, resumed and cancelled.
+//!
+//! Each job runs the backupper for one profile, which is controlled through the control channel of its backup (see [config::control]).
+//! Cancelling a job sends the backupper the `stop` command, or the stop signal (`SIGUSR1` on Unix, `CTRL_BREAK_EVENT` on Windows)
+//! if the channel can't be reached. The backupper then finishes the current file, keeps the files archived until then as a partial archive
+//! marked as `cancelled` and exits with [STOPPED_EXIT_CODE].
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    process::{ExitStatus, Stdio},
+    sync::{Arc, Mutex},
+};
+
+use chrono::NaiveDateTime;
+use config::{
+    backup_report::STOPPED_EXIT_CODE,
+    control::{send_command, ControlCommand},
+};
+use rocket::serde::Serialize;
+use uuid::Uuid;
+
+#[cfg(windows)]
+mod windows;
+
+/// State of a [Job]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "snake_case")]
+pub enum JobStatus {
+    /// The backupper is running
+    Running,
+    /// The backupper idles between two files until the job is resumed
+    Paused,
+    /// The backupper was asked to stop, but hasn't exited yet
+    Cancelling,
+    /// The backup is complete
+    Succeeded,
+    /// The backup was stopped and kept a partial archive
+    Cancelled,
+    /// The backupper failed or its exit status couldn't be determined
+    Failed,
+}
+
+/// Backup of a profile that was started through the API.
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Job {
+    pub id: u64,
+    pub profile_uuid: Uuid,
+    /// Datetime the backupper was started
+    pub started: NaiveDateTime,
+    /// Datetime the backupper exited. [None] while it is running.
+    pub finished: Option<NaiveDateTime>,
+    pub status: JobStatus,
+    /// Exit code of the backupper. [None] while it is running or if it was killed.
+    pub exit_code: Option<i32>,
+    /// PID of the backupper while it is running
+    #[serde(skip)]
+    pid: Option<u32>,
+}
+
+/// Why a [Job] couldn't be started or cancelled.
+#[derive(Debug, PartialEq)]
+pub enum JobError {
+    /// There is no job with the given id
+    NotFound(String),
+    /// The profile already has a running job, or the job isn't running anymore
+    Conflict(String),
+    /// The backupper couldn't be started or signalled
+    Io(String),
+}
+
+#[derive(Debug, Default)]
+struct Jobs {
+    next_id: u64,
+    jobs: BTreeMap<u64, Job>,
+}
+
+/// Jobs started since the server was started. It's shared with the tasks waiting for the backuppers to exit.
+#[derive(Debug, Clone, Default)]
+pub struct JobQueue(Arc<Mutex<Jobs>>);
+
+impl JobQueue {
+    /// Starts a forced backup of the profile with the given [Uuid] by the backupper at `backupper_path`.
+    ///
+    /// # Errors
+    /// Returns [JobError::Conflict] if the profile already has a running job and [JobError::Io] if the backupper couldn't be started.
+    pub fn start_backup(&self, backupper_path: &PathBuf, uuid: &Uuid) -> Result<Job, JobError> {
+        let mut jobs = self.0.lock().unwrap();
+        if let Some(job) = jobs.jobs.values().find(|job| &job.profile_uuid == uuid && job.pid.is_some()) {
+            return Err(JobError::Conflict(format!("The backup of {:?} is already running as job {}", uuid, job.id)));
+        }
+
+        let mut command = rocket::tokio::process::Command::new(backupper_path.as_os_str());
+        command
+            .arg("-u")
+            .arg(uuid.as_hyphenated().to_string())
+            .arg("-f")
+            .arg("backup")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        // the stop signal is sent to a whole process group on Windows, which mustn't include the server
+        #[cfg(windows)]
+        command.creation_flags(windows::CREATE_NEW_PROCESS_GROUP);
+        let mut child = command
+            .spawn()
+            .map_err(|err| JobError::Io(format!("Couldn't start the backup of {:?} because of {:?}", uuid, err)))?;
+
+        jobs.next_id += 1;
+        let job = Job {
+            id: jobs.next_id,
+            profile_uuid: *uuid,
+            started: chrono::offset::Local::now().naive_local(),
+            finished: None,
+            status: JobStatus::Running,
+            exit_code: None,
+            pid: child.id(),
+        };
+        jobs.jobs.insert(job.id, job.clone());
+        log::info!("Started job {} backing up {:?}", job.id, uuid);
+
+        let queue = self.clone();
+        let id = job.id;
+        rocket::tokio::spawn(async move {
+            let status = child.wait().await;
+            queue.finish(id, status.ok());
+        });
+        Ok(job)
+    }
+
+    /// Records that the backupper of the job with the given id exited with the given `status`.
+    fn finish(&self, id: u64, status: Option<ExitStatus>) {
+        let mut jobs = self.0.lock().unwrap();
+        if let Some(job) = jobs.jobs.get_mut(&id) {
+            job.pid = None;
+            job.finished = Some(chrono::offset::Local::now().naive_local());
+            job.exit_code = status.and_then(|status| status.code());
+            job.status = match job.exit_code {
+                Some(0) => JobStatus::Succeeded,
+                Some(STOPPED_EXIT_CODE) => JobStatus::Cancelled,
+                _ => JobStatus::Failed,
+            };
+            log::info!("Job {} finished with {:?}", id, job.status);
+        }
+    }
+
+    /// Returns all jobs, oldest first.
+    pub fn jobs(&self) -> Vec<Job> {
+        self.0.lock().unwrap().jobs.values().cloned().collect()
+    }
+
+    /// Returns the job with the given id.
+    pub fn job(&self, id: u64) -> Option<Job> {
+        self.0.lock().unwrap().jobs.get(&id).cloned()
+    }
+
+    /// Asks the backupper of the job with the given id to stop after its current file.
+    /// The job stays [JobStatus::Cancelling] until the backupper exits.
+    ///
+    /// # Errors
+    /// Returns [JobError::NotFound] if there is no such job, [JobError::Conflict] if it isn't running anymore
+    /// and [JobError::Io] if the stop signal couldn't be sent.
+    pub fn cancel(&self, id: u64) -> Result<Job, JobError> {
+        let mut jobs = self.0.lock().unwrap();
+        let job = jobs
+            .jobs
+            .get_mut(&id)
+            .ok_or_else(|| JobError::NotFound(format!("There is no job {}", id)))?;
+        let pid = job
+            .pid
+            .ok_or_else(|| JobError::Conflict(format!("Job {} already finished", id)))?;
+        if let Err(msg) = send_command(&job.profile_uuid, ControlCommand::Stop) {
+            log::warn!("Sending the stop signal to job {}, because {}", id, msg);
+            send_stop_signal(pid).map_err(|msg| JobError::Io(format!("Couldn't cancel job {}: {}", id, msg)))?;
+        }
+        job.status = JobStatus::Cancelling;
+        log::info!("Cancelling job {}", id);
+        Ok(job.clone())
+    }
+
+    /// Pauses the backup of the job with the given id after its current file. It idles until the job is resumed (see [JobQueue::resume]).
+    ///
+    /// # Errors
+    /// Returns [JobError::NotFound] if there is no such job, [JobError::Conflict] if it isn't running
+    /// and [JobError::Io] if the backupper couldn't be reached through its control channel.
+    pub fn pause(&self, id: u64) -> Result<Job, JobError> {
+        self.control(id, ControlCommand::Pause, JobStatus::Paused)
+    }
+
+    /// Resumes the paused backup of the job with the given id.
+    ///
+    /// # Errors
+    /// See [JobQueue::pause].
+    pub fn resume(&self, id: u64) -> Result<Job, JobError> {
+        self.control(id, ControlCommand::Resume, JobStatus::Running)
+    }
+
+    /// Sends `command` to the backupper of the job with the given id and sets the job to `status`.
+    fn control(&self, id: u64, command: ControlCommand, status: JobStatus) -> Result<Job, JobError> {
+        let mut jobs = self.0.lock().unwrap();
+        let job = jobs
+            .jobs
+            .get_mut(&id)
+            .ok_or_else(|| JobError::NotFound(format!("There is no job {}", id)))?;
+        if job.pid.is_none() || job.status == JobStatus::Cancelling {
+            return Err(JobError::Conflict(format!("Job {} isn't running anymore", id)));
+        }
+        send_command(&job.profile_uuid, command).map_err(|msg| JobError::Io(format!("Couldn't {} job {}: {}", command, id, msg)))?;
+        job.status = status;
+        log::info!("Sent {} to job {}", command, id);
+        Ok(job.clone())
+    }
+}
+
+/// Sends the stop signal to the backupper with the given PID.
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the signal couldn't be sent.
+fn send_stop_signal(pid: u32) -> Result<(), String> {
+    #[cfg(windows)]
+    return windows::send_ctrl_break(pid);
+    #[cfg(unix)]
+    {
+        let sent = unsafe { libc::kill(pid as libc::pid_t, libc::SIGUSR1) };
+        if sent != 0 {
+            return Err(format!("{:?}", std::io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,17 @@
+//! Contains the windows implementation of sending the stop signal, which is `CTRL_BREAK_EVENT`
+
+use windows::Win32::System::Console::{GenerateConsoleCtrlEvent, CTRL_BREAK_EVENT};
+
+/// Creation flag that starts a process in its own process group, so `CTRL_BREAK_EVENT` can be sent to it alone
+pub const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+
+/// Sends `CTRL_BREAK_EVENT` to the process group of the process with the given PID, which was started with [CREATE_NEW_PROCESS_GROUP].
+///
+/// # Errors
+/// Returns an [Err] describing the issue if the event couldn't be sent.
+pub fn send_ctrl_break(pid: u32) -> Result<(), String> {
+    match unsafe { GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid) }.as_bool() {
+        true => Ok(()),
+        false => Err(format!("{:?}", windows::core::Error::from_win32())),
+    }
+}
@@ -1,4 +1,4 @@
-use std::{path::PathBuf, process::{Command, self}};
+use std::{io, path::{Path, PathBuf}, process::{Command, self}};
 
 use cli_args::{parse_args, Args};
 use config::general_config::GeneralConfig;
@@ -12,6 +12,8 @@ extern crate rocket;
 mod api;
 mod cli_args;
 mod errors;
+mod jobs;
+mod negotiation;
 
 fn init_logger(path: &PathBuf) {
     match log4rs::init_file(path, Default::default()) {
@@ -23,6 +25,19 @@ fn init_logger(path: &PathBuf) {
     }
 }
 
+/// Limits the logged levels to the `log_level` of the [GeneralConfig], if it is set.
+fn apply_log_level(log_level: &Option<String>) {
+    if let Some(log_level) = log_level {
+        match log_level.parse::<log::LevelFilter>() {
+            Ok(level) => {
+                info!("Setting log level to {}", level);
+                log::set_max_level(level);
+            }
+            Err(_) => log::warn!("Ignoring invalid log level {:?}", log_level),
+        }
+    }
+}
+
 /// Checks if the path to the backupper executable is valid by trying to call the `-V` command.
 fn check_backupper(args: &Args) -> bool {
     let backupper_path = PathBuf::from(&args.backupper);
@@ -53,33 +68,75 @@ fn rocket() -> _ {
     }
     std::env::set_var("ROCKET_CLI_COLORS", format!("{}", args.rocket_colors));
 
-    let general_config = match GeneralConfig::read(&PathBuf::from(&args.general_config)) {
+    let general_config_file = Path::new(&args.general_config);
+    let general_config = match GeneralConfig::read(general_config_file) {
         Ok(config) => config,
+        Err(config::Error::Io(e)) if e.kind() == io::ErrorKind::NotFound && !args.no_create => {
+            match GeneralConfig::create_default(general_config_file) {
+                Ok((config, created)) => {
+                    info!("General config {} didn't exist. Created {:?}", args.general_config, created);
+                    config
+                }
+                Err(e) => {
+                    log::error!(
+                        "Couldn't create general config {}. Got: {:#?}",
+                        args.general_config,
+                        e
+                    );
+                    std::process::exit(exitcode::CANTCREAT);
+                }
+            }
+        }
         Err(e) => {
             log::error!(
                 "Couldn't read general config from {}. Got: {:#?}",
                 args.general_config,
                 e
             );
-            std::process::exit(exitcode::UNAVAILABLE);
+            let code = match e {
+                config::Error::Io(e) if e.kind() == io::ErrorKind::NotFound => exitcode::NOINPUT,
+                config::Error::Io(_) => exitcode::IOERR,
+                _ => exitcode::CONFIG,
+            };
+            std::process::exit(code);
         }
     };
+    apply_log_level(&general_config.log_level);
 
     rocket::build()
         .manage(general_config)
         .manage(PathBuf::from(&args.backupper))
+        .manage(jobs::JobQueue::default())
         .mount("/", FileServer::from(&args.frontend))
         .mount(
             "/api",
             routes![
                 api::get_profile_config_dir,
                 api::get_profile_configs,
+                api::get_profile_overlaps,
+                api::get_health,
                 api::get_profile_config_by_name,
+                api::get_profile_configs_by_tag,
                 api::get_profile_config_by_uuid,
+                api::explain_profile_schedule,
+                api::get_profile_interval_preset,
+                api::get_profile_audit_log,
+                api::get_profile_backups,
+                api::search_catalogs,
+                api::start_profile_backup,
+                api::get_jobs,
+                api::get_job,
+                api::cancel_job,
+                api::pause_job,
+                api::resume_job,
                 api::create_blank_profile_config,
+                api::duplicate_profile_config,
                 api::delete_profile_config_by_name,
                 api::delete_profile_config_by_uuid,
-                api::update_profile_config
+                api::update_profile_config,
+                api::patch_profile_interval,
+                api::adopt_profile_configs,
+                api::get_support_bundle
             ],
         )
 }
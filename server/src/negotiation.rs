@@ -0,0 +1,138 @@
+//! Content negotiation between JSON and protobuf for the profile endpoints.
+//!
+//! Protobuf is only available if the server is built with the `protobuf` feature.
+
+use config::profile_config::ProfileConfig;
+use rocket::data::{self, Data, FromData};
+use rocket::http::{ContentType, MediaType, Status};
+use rocket::request::{self, FromRequest, Request};
+use rocket::serde::json::{self, Json};
+
+/// Body of a negotiated response
+pub type Negotiated = (ContentType, Vec<u8>);
+
+/// Checks if the given [MediaType] is `application/x-protobuf`
+fn is_protobuf(media_type: &MediaType) -> bool {
+    media_type.top() == "application" && media_type.sub() == "x-protobuf"
+}
+
+/// Response format requested by the client.
+/// Protobuf is requested with the header `Accept: application/x-protobuf` or the query `?format=proto`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    #[cfg(feature = "protobuf")]
+    Protobuf,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Format {
+    type Error = String;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let query_protobuf = matches!(request.query_value::<&str>("format"), Some(Ok("proto")));
+        let accept_protobuf = request
+            .accept()
+            .map(|accept| accept.media_types().any(is_protobuf))
+            .unwrap_or(false);
+
+        if !(query_protobuf || accept_protobuf) {
+            return request::Outcome::Success(Format::Json);
+        }
+
+        #[cfg(feature = "protobuf")]
+        return request::Outcome::Success(Format::Protobuf);
+
+        #[cfg(not(feature = "protobuf"))]
+        request::Outcome::Failure((
+            Status::NotAcceptable,
+            String::from("This server was built without protobuf support"),
+        ))
+    }
+}
+
+impl Format {
+    /// Serializes a single [ProfileConfig] in the requested format
+    pub fn profile_config(&self, config: &ProfileConfig) -> Negotiated {
+        match self {
+            Format::Json => (
+                ContentType::JSON,
+                json::to_string(config)
+                    .expect("ProfileConfig is serializable")
+                    .into_bytes(),
+            ),
+            #[cfg(feature = "protobuf")]
+            Format::Protobuf => (
+                ContentType::new("application", "x-protobuf"),
+                config.to_buf().to_vec(),
+            ),
+        }
+    }
+
+    /// Serializes multiple [ProfileConfig]s in the requested format.
+    /// Protobuf messages are length-delimited (see [ProfileConfig::to_buf_list]).
+    pub fn profile_configs(&self, configs: &[ProfileConfig]) -> Negotiated {
+        match self {
+            Format::Json => (
+                ContentType::JSON,
+                json::to_string(&configs)
+                    .expect("ProfileConfig is serializable")
+                    .into_bytes(),
+            ),
+            #[cfg(feature = "protobuf")]
+            Format::Protobuf => (
+                ContentType::new("application", "x-protobuf"),
+                ProfileConfig::to_buf_list(configs).to_vec(),
+            ),
+        }
+    }
+}
+
+/// A [ProfileConfig] sent in the request body, either as JSON or, with `Content-Type: application/x-protobuf`, as protobuf.
+#[derive(Debug)]
+pub enum ProfileConfigData {
+    Json(ProfileConfig),
+    /// Encoded protobuf message. Since it doesn't contain all fields, it should be applied to the stored [ProfileConfig] with [ProfileConfig::update_from_buf].
+    #[cfg(feature = "protobuf")]
+    Protobuf(Vec<u8>),
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for ProfileConfigData {
+    type Error = String;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let protobuf_body = request
+            .content_type()
+            .map(|content_type| is_protobuf(content_type.media_type()))
+            .unwrap_or(false);
+
+        if !protobuf_body {
+            return Json::<ProfileConfig>::from_data(request, data)
+                .await
+                .map(|config| ProfileConfigData::Json(config.into_inner()))
+                .map_failure(|(status, e)| (status, format!("{:?}", e)));
+        }
+
+        #[cfg(feature = "protobuf")]
+        {
+            let limit = request.limits().get("bytes").unwrap_or(data::Limits::BYTES);
+            return match data.open(limit).into_bytes().await {
+                Ok(bytes) if bytes.is_complete() => {
+                    data::Outcome::Success(ProfileConfigData::Protobuf(bytes.into_inner()))
+                }
+                Ok(_) => data::Outcome::Failure((
+                    Status::PayloadTooLarge,
+                    String::from("Protobuf body is too large"),
+                )),
+                Err(e) => data::Outcome::Failure((Status::BadRequest, e.to_string())),
+            };
+        }
+
+        #[cfg(not(feature = "protobuf"))]
+        data::Outcome::Failure((
+            Status::UnsupportedMediaType,
+            String::from("This server was built without protobuf support"),
+        ))
+    }
+}